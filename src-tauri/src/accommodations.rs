@@ -0,0 +1,388 @@
+//! Per-student accommodations
+//!
+//! Handles:
+//! - Flags on a student, stored with the roster (see `roster::Student`):
+//!   never cold-called, needs a front seat, kept apart from a classmate;
+//!   each with optional details for the teacher
+//! - Enforcing them wherever the backend picks or places students: the
+//!   random picker, the group generator and seating charts (see
+//!   `class_records::validate_seating`)
+//!
+//! The rules are applied here rather than in the frontend so a UI change
+//! can't drop them by accident. "Keep apart" works both ways, even when
+//! only one of the two students carries it. Row 0 of a seating chart is
+//! the front row.
+
+use crate::class_records::SeatingChart;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::roster::{ClassData, RosterStore, Student};
+use serde::{Deserialize, Serialize};
+
+const MAX_DETAILS_CHARS: usize = 200;
+/// Shuffles tried before giving up on separating every pair
+const GROUP_ATTEMPTS: usize = 200;
+
+/// What an accommodation asks for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AccommodationKind {
+    /// Never chosen by the random picker
+    NoColdCall,
+    /// Seated in the front row
+    FrontSeat,
+    /// Never in the same group as, or seated next to, another student
+    KeepApart {
+        #[serde(rename = "studentId")]
+        student_id: String,
+    },
+}
+
+/// An accommodation of a student
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Accommodation {
+    #[serde(flatten)]
+    pub kind: AccommodationKind,
+    /// Why, for the teacher; may be sensitive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+impl Accommodation {
+    /// Same accommodation with the referenced student renamed by `new_id`
+    pub fn with_student_ids(&self, new_id: impl Fn(&str) -> String) -> Self {
+        let kind = match &self.kind {
+            AccommodationKind::KeepApart { student_id } => AccommodationKind::KeepApart {
+                student_id: new_id(student_id),
+            },
+            other => other.clone(),
+        };
+        Self {
+            kind,
+            details: self.details.clone(),
+        }
+    }
+}
+
+fn has(student: &Student, kind: &AccommodationKind) -> bool {
+    student.accommodations.iter().any(|a| &a.kind == kind)
+}
+
+/// Whether two students must be kept apart (either may carry the flag)
+pub fn kept_apart(a: &Student, b: &Student) -> bool {
+    let apart_from = |s: &Student, other: &Student| {
+        has(
+            s,
+            &AccommodationKind::KeepApart {
+                student_id: other.id.clone(),
+            },
+        )
+    };
+    apart_from(a, b) || apart_from(b, a)
+}
+
+fn invalid_input(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(details.to_string())
+}
+
+fn violated(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::accommodation::VIOLATED, message).with_details(details.to_string())
+}
+
+/// Check a student's accommodations against the class; details are trimmed
+fn validate(
+    class: &ClassData,
+    student_id: &str,
+    accommodations: Vec<Accommodation>,
+) -> Result<Vec<Accommodation>, BackendError> {
+    let mut valid: Vec<Accommodation> = Vec::new();
+    for mut accommodation in accommodations {
+        if let AccommodationKind::KeepApart { student_id: other } = &accommodation.kind {
+            if other == student_id {
+                return Err(invalid_input(
+                    "A student can't be kept apart from themselves",
+                    other,
+                ));
+            }
+            if !class.students.iter().any(|s| &s.id == other) {
+                return Err(invalid_input("Student is not in this class", other));
+            }
+        }
+        if valid.iter().any(|a| a.kind == accommodation.kind) {
+            return Err(invalid_input(
+                "Accommodation listed twice",
+                format!("{:?}", accommodation.kind),
+            ));
+        }
+        accommodation.details = accommodation
+            .details
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        if accommodation
+            .details
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > MAX_DETAILS_CHARS)
+        {
+            return Err(invalid_input(
+                "Details are too long",
+                format!("max {} characters", MAX_DETAILS_CHARS),
+            ));
+        }
+        valid.push(accommodation);
+    }
+    Ok(valid)
+}
+
+fn class_not_found(class_id: &str) -> BackendError {
+    BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+        .with_details(class_id.to_string())
+}
+
+fn load_class(class_id: &str) -> Result<ClassData, BackendError> {
+    RosterStore::load()?
+        .find(class_id)
+        .cloned()
+        .ok_or_else(|| class_not_found(class_id))
+}
+
+/// Replace a student's accommodations
+pub fn set_student_accommodations(
+    class_id: &str,
+    student_id: &str,
+    accommodations: Vec<Accommodation>,
+) -> Result<Student, BackendError> {
+    let mut store = RosterStore::load()?;
+    let class = store
+        .classes
+        .iter_mut()
+        .find(|c| c.id == class_id)
+        .ok_or_else(|| class_not_found(class_id))?;
+    let accommodations = validate(class, student_id, accommodations)?;
+    let student = class
+        .students
+        .iter_mut()
+        .find(|s| s.id == student_id)
+        .ok_or_else(|| invalid_input("Student is not in this class", student_id))?;
+    student.accommodations = accommodations;
+    let student = student.clone();
+    class.updated_at = clock::now_millis();
+    store.save()?;
+    Ok(student)
+}
+
+fn random_u64() -> Result<u64, BackendError> {
+    let mut buf = [0u8; 8];
+    getrandom::fill(&mut buf).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn shuffle<T>(items: &mut [T]) -> Result<(), BackendError> {
+    for i in (1..items.len()).rev() {
+        let j = (random_u64()? % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+    Ok(())
+}
+
+/// Students the picker may choose: present, not excluded, not flagged
+/// "never cold-call"
+fn pick_candidates<'a>(class: &'a ClassData, exclude: &[String]) -> Vec<&'a Student> {
+    class
+        .students
+        .iter()
+        .filter(|s| !s.absent && !exclude.contains(&s.id))
+        .filter(|s| !has(s, &AccommodationKind::NoColdCall))
+        .collect()
+}
+
+/// Pick a random present student
+///
+/// `exclude` lists students already called (e.g. earlier in the lesson).
+pub fn pick_student(class_id: &str, exclude: &[String]) -> Result<Student, BackendError> {
+    let class = load_class(class_id)?;
+    let candidates = pick_candidates(&class, exclude);
+    if candidates.is_empty() {
+        return Err(BackendError::new(
+            errors::accommodation::NO_ELIGIBLE_STUDENT,
+            "No student can be picked",
+        ));
+    }
+    let index = (random_u64()? % candidates.len() as u64) as usize;
+    Ok(candidates[index].clone())
+}
+
+/// Fill groups of at most `size` in the given order, each student joining
+/// the smallest group without a classmate they must be kept apart from
+fn assign_groups(students: &[&Student], size: usize) -> Option<Vec<Vec<Student>>> {
+    let count = students.len().div_ceil(size);
+    let mut groups: Vec<Vec<Student>> = vec![Vec::new(); count];
+    for &student in students {
+        let group = groups
+            .iter_mut()
+            .filter(|g| g.len() < size && !g.iter().any(|m| kept_apart(m, student)))
+            .min_by_key(|g| g.len())?;
+        group.push(student.clone());
+    }
+    Some(groups)
+}
+
+/// Split the present students into random groups of at most `size`
+pub fn generate_groups(class_id: &str, size: usize) -> Result<Vec<Vec<Student>>, BackendError> {
+    let class = load_class(class_id)?;
+    let mut present: Vec<&Student> = class.students.iter().filter(|s| !s.absent).collect();
+    if present.is_empty() {
+        return Err(BackendError::new(
+            errors::accommodation::NO_ELIGIBLE_STUDENT,
+            "No student is present",
+        ));
+    }
+    if size == 0 || size > present.len() {
+        return Err(invalid_input(
+            "Group size must be between 1 and the number of students present",
+            size,
+        ));
+    }
+    for _ in 0..GROUP_ATTEMPTS {
+        shuffle(&mut present)?;
+        if let Some(groups) = assign_groups(&present, size) {
+            return Ok(groups);
+        }
+    }
+    Err(violated(
+        "Groups can't keep every \"keep apart\" pair separated",
+        format!("group size {}", size),
+    ))
+}
+
+/// Check a seating chart against the accommodations of its class
+pub fn check_seating(chart: &SeatingChart, class: &ClassData) -> Result<(), BackendError> {
+    let seated: Vec<(&Student, u32, u32)> = chart
+        .seats
+        .iter()
+        .filter_map(|seat| {
+            let student = class.students.iter().find(|s| s.id == seat.student_id)?;
+            Some((student, seat.row, seat.column))
+        })
+        .collect();
+    for (i, &(student, row, column)) in seated.iter().enumerate() {
+        if row != 0 && has(student, &AccommodationKind::FrontSeat) {
+            return Err(violated("Student needs a front seat", &student.name));
+        }
+        for &(other, other_row, other_column) in &seated[i + 1..] {
+            let adjacent = row.abs_diff(other_row) <= 1 && column.abs_diff(other_column) <= 1;
+            if adjacent && kept_apart(student, other) {
+                return Err(violated(
+                    "Students who must be kept apart are seated together",
+                    format!("{}, {}", student.name, other.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_records::Seat;
+    use crate::roster::ClassBuilder;
+
+    fn class(names: &[&str]) -> ClassData {
+        ClassBuilder::new("class_1", "3A")
+            .created_at(1)
+            .students(names)
+            .build()
+    }
+
+    fn flag(kind: AccommodationKind) -> Accommodation {
+        Accommodation {
+            kind,
+            details: None,
+        }
+    }
+
+    fn apart(student_id: &str) -> Accommodation {
+        flag(AccommodationKind::KeepApart {
+            student_id: student_id.into(),
+        })
+    }
+
+    #[test]
+    fn test_picker_and_groups_respect_accommodations() {
+        let mut class = class(&["Mario", "Anna", "Luca", "Sara"]);
+        class.students[0].accommodations = vec![flag(AccommodationKind::NoColdCall)];
+        class.students[1].absent = true;
+        let ids: Vec<&str> = pick_candidates(&class, &["s2".into()])
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(ids, ["s3"]);
+
+        // Only Sara carries the flag; it still keeps Mario away from her
+        class.students[1].absent = false;
+        class.students[3].accommodations = vec![apart("s0")];
+        let ordered: Vec<&Student> = class.students.iter().collect();
+        let groups = assign_groups(&ordered, 2).unwrap();
+        assert_eq!(groups.len(), 2);
+        for group in &groups {
+            assert!(!(group.iter().any(|s| s.id == "s0") && group.iter().any(|s| s.id == "s3")));
+        }
+        // Three students who must all be apart can't share two groups
+        class.students[2].accommodations = vec![apart("s0"), apart("s3")];
+        let ordered: Vec<&Student> = class.students.iter().collect();
+        assert!(assign_groups(&ordered, 2).is_none());
+    }
+
+    #[test]
+    fn test_seating_and_validation() {
+        let mut class = class(&["Mario", "Anna", "Luca"]);
+        class.students[0].accommodations = vec![flag(AccommodationKind::FrontSeat)];
+        class.students[1].accommodations = vec![apart("s2")];
+        let seat = |id: &str, row, column| Seat {
+            student_id: id.into(),
+            row,
+            column,
+        };
+        let mut chart = SeatingChart {
+            class_id: class.id.clone(),
+            rows: 3,
+            columns: 3,
+            seats: vec![seat("s0", 0, 0), seat("s1", 2, 0), seat("s2", 0, 2)],
+            updated_at: 0,
+        };
+        assert!(check_seating(&chart, &class).is_ok());
+        chart.seats[2] = seat("s2", 1, 1);
+        assert_eq!(
+            check_seating(&chart, &class).unwrap_err().code,
+            errors::accommodation::VIOLATED
+        );
+        chart.seats = vec![seat("s0", 1, 0)];
+        assert!(check_seating(&chart, &class).is_err());
+
+        let valid = validate(
+            &class,
+            "s0",
+            vec![Accommodation {
+                kind: AccommodationKind::NoColdCall,
+                details: Some("  ansia  ".into()),
+            }],
+        )
+        .unwrap();
+        assert_eq!(valid[0].details.as_deref(), Some("ansia"));
+        assert!(validate(&class, "s0", vec![apart("s0")]).is_err());
+        assert!(validate(&class, "s0", vec![apart("s9")]).is_err());
+        assert!(validate(&class, "s0", vec![apart("s1"), apart("s1")]).is_err());
+
+        let json = serde_json::to_value(apart("s1")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "keepApart", "studentId": "s1" })
+        );
+        let back: Accommodation = serde_json::from_value(json).unwrap();
+        assert_eq!(back, apart("s1"));
+    }
+}
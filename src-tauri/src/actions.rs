@@ -0,0 +1,605 @@
+//! Action registry for the command palette and hardware controllers
+//!
+//! Handles:
+//! - Describing every user-triggerable action (id, title, argument schema)
+//! - Validating arguments against the schema
+//! - Dispatching: backend actions run here, frontend actions (timer, mode,
+//!   student picker) are forwarded to the webview as `action-invoked`
+//!
+//! The palette, Stream Deck and presenter remotes all go through
+//! `execute_action`, so a new feature only needs an entry in `ACTIONS`.
+//! Each action is checked like the command it stands for, so a controller
+//! can't do what the app lock, the active role or read-only mode would
+//! refuse.
+
+use crate::backup;
+use crate::classroom_state;
+use crate::errors::{self, BackendError};
+use crate::exit_tickets;
+use crate::jobs;
+use crate::locale::{self, Language};
+use crate::presentation_safe;
+use crate::projector_dim;
+use crate::read_only_mode;
+use crate::recovery;
+use crate::roles;
+use crate::roster_sync;
+use crate::screenshot;
+use crate::timers;
+use crate::weekly_summary;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying frontend actions to the webview
+pub const ACTION_EVENT: &str = "action-invoked";
+
+/// Source label of actions from hardware (Stream Deck, presenter remotes)
+pub const CONTROLLER_SOURCE: &str = "controller";
+
+/// Where an action is carried out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionHandler {
+    Backend,
+    Frontend,
+}
+
+struct ActionSpec {
+    id: &'static str,
+    title_it: &'static str,
+    title_en: &'static str,
+    category: &'static str,
+    handler: ActionHandler,
+    /// Command the action stands for; its lock, observer, role and
+    /// read-only checks apply (`execute_action` when it only drives the UI)
+    command: Option<&'static str>,
+    args_schema: fn() -> Value,
+}
+
+fn no_args() -> Value {
+    json!({ "type": "object", "additionalProperties": false })
+}
+
+const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        id: "timer.start",
+        title_it: "Avvia timer",
+        title_en: "Start timer",
+        category: "timer",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": {
+                    "minutes": { "type": "number", "exclusiveMinimum": 0, "maximum": 240 },
+                    "label": { "type": "string", "maxLength": 80 }
+                },
+                "required": ["minutes"],
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "timer.pause",
+        title_it: "Metti in pausa il timer",
+        title_en: "Pause timer",
+        category: "timer",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "timer.stop",
+        title_it: "Ferma timer",
+        title_en: "Stop timer",
+        category: "timer",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "timer.silence",
+        title_it: "Silenzia allarme timer",
+        title_en: "Silence timer alarm",
+        category: "timer",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "timer.next_phase",
+        title_it: "Fase successiva del timer",
+        title_en: "Next timer phase",
+        category: "timer",
+        handler: ActionHandler::Backend,
+        command: Some("skip_phase"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "alert.acknowledge",
+        title_it: "Conferma avviso",
+        title_en: "Acknowledge alert",
+        category: "noise",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "break.start",
+        title_it: "Avvia pausa",
+        title_en: "Start break",
+        category: "timer",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": {
+                    "minutes": { "type": "number", "exclusiveMinimum": 0, "maximum": 60 }
+                },
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "mode.switch",
+        title_it: "Cambia modalità",
+        title_en: "Switch mode",
+        category: "view",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": { "mode": { "type": "string", "minLength": 1 } },
+                "required": ["mode"],
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "student.pick",
+        title_it: "Estrai uno studente",
+        title_en: "Pick a student",
+        category: "class",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": { "classId": { "type": "string" } },
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "classroom.set_state",
+        title_it: "Imposta semaforo",
+        title_en: "Set traffic light",
+        category: "noise",
+        handler: ActionHandler::Backend,
+        command: Some("set_classroom_state"),
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": { "color": { "enum": ["green", "yellow", "red"] } },
+                "required": ["color"],
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "noise.toggle",
+        title_it: "Attiva/disattiva monitor rumore",
+        title_en: "Toggle noise monitor",
+        category: "noise",
+        handler: ActionHandler::Frontend,
+        command: None,
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "exit_ticket.start",
+        title_it: "Apri exit ticket",
+        title_en: "Open exit ticket",
+        category: "class",
+        handler: ActionHandler::Backend,
+        command: Some("start_exit_ticket"),
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string", "minLength": 1 },
+                    "lessonId": { "type": "string" }
+                },
+                "required": ["prompt"],
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "exit_ticket.close",
+        title_it: "Chiudi exit ticket",
+        title_en: "Close exit ticket",
+        category: "class",
+        handler: ActionHandler::Backend,
+        command: Some("close_exit_ticket"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "backup.create",
+        title_it: "Crea backup",
+        title_en: "Create backup",
+        category: "data",
+        handler: ActionHandler::Backend,
+        command: Some("create_backup"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "roster.scan_folder",
+        title_it: "Controlla cartella elenchi",
+        title_en: "Scan roster folder",
+        category: "data",
+        handler: ActionHandler::Backend,
+        command: Some("scan_roster_folder"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "weekly_summary.generate",
+        title_it: "Genera riepilogo settimanale",
+        title_en: "Generate weekly summary",
+        category: "data",
+        handler: ActionHandler::Backend,
+        command: Some("generate_weekly_summary_now"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "lesson.end",
+        title_it: "Termina lezione",
+        title_en: "End lesson",
+        category: "class",
+        handler: ActionHandler::Backend,
+        command: Some("clear_lesson_state"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "projector.toggle_dim",
+        title_it: "Oscura/ripristina proiettore",
+        title_en: "Dim/undim projector",
+        category: "view",
+        handler: ActionHandler::Backend,
+        command: Some("dim_projector"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "display.presentation_safe",
+        title_it: "Modalità condivisione schermo sicura",
+        title_en: "Toggle presentation-safe mode",
+        category: "view",
+        handler: ActionHandler::Backend,
+        command: Some("set_presentation_safe"),
+        args_schema: no_args,
+    },
+    ActionSpec {
+        id: "window.screenshot",
+        title_it: "Salva screenshot della finestra",
+        title_en: "Save window screenshot",
+        category: "view",
+        handler: ActionHandler::Backend,
+        command: Some("capture_window_screenshot"),
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": {
+                    "windowLabel": { "type": "string", "minLength": 1 },
+                    "path": { "type": "string", "pattern": "(?i)\\.png$" }
+                },
+                "additionalProperties": false
+            })
+        },
+    },
+    ActionSpec {
+        id: "app.set_language",
+        title_it: "Cambia lingua",
+        title_en: "Change language",
+        category: "settings",
+        handler: ActionHandler::Backend,
+        command: Some("set_app_language"),
+        args_schema: || {
+            json!({
+                "type": "object",
+                "properties": { "lang": { "enum": ["it", "en"] } },
+                "required": ["lang"],
+                "additionalProperties": false
+            })
+        },
+    },
+];
+
+/// Machine-readable description of an action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionDescriptor {
+    pub id: String,
+    /// In the app language
+    pub title: String,
+    pub category: String,
+    pub handler: ActionHandler,
+    /// JSON schema of the `args` object
+    pub args_schema: Value,
+}
+
+/// Outcome of `execute_action`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionResult {
+    pub id: String,
+    pub handler: ActionHandler,
+    /// Backend result; `null` for frontend actions
+    pub result: Value,
+}
+
+/// Payload of `action-invoked`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionInvocation {
+    pub id: String,
+    pub args: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExitTicketArgs {
+    prompt: String,
+    lesson_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ClassroomStateArgs {
+    color: classroom_state::StateColor,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScreenshotArgs {
+    #[serde(default = "main_window")]
+    window_label: String,
+    path: Option<String>,
+}
+
+fn main_window() -> String {
+    "main".to_string()
+}
+
+#[derive(Deserialize)]
+struct LanguageArgs {
+    lang: String,
+}
+
+fn find(id: &str) -> Result<&'static ActionSpec, BackendError> {
+    ACTIONS.iter().find(|a| a.id == id).ok_or_else(|| {
+        BackendError::new(errors::action::NOT_FOUND, "Unknown action").with_details(id.to_string())
+    })
+}
+
+/// Whether an action id exists
+pub fn is_known(id: &str) -> bool {
+    find(id).is_ok()
+}
+
+/// All actions, titles in `lang`
+pub fn list_actions(lang: Language) -> Vec<ActionDescriptor> {
+    ACTIONS
+        .iter()
+        .map(|a| ActionDescriptor {
+            id: a.id.to_string(),
+            title: lang.pick(a.title_it, a.title_en).to_string(),
+            category: a.category.to_string(),
+            handler: a.handler,
+            args_schema: (a.args_schema)(),
+        })
+        .collect()
+}
+
+/// Check `args` against the action's schema (`null` counts as `{}`)
+fn validate_args(spec: &ActionSpec, args: Value) -> Result<Value, BackendError> {
+    let args = if args.is_null() { json!({}) } else { args };
+    let schema = (spec.args_schema)();
+    let validator = jsonschema::validator_for(&schema).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Invalid action schema")
+            .with_details(e.to_string())
+    })?;
+    validator.validate(&args).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid action arguments")
+            .with_details(format!("{}: {}", e.instance_path, e))
+    })?;
+    Ok(args)
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(args: Value) -> Result<T, BackendError> {
+    serde_json::from_value(args).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid action arguments")
+            .with_details(e.to_string())
+    })
+}
+
+fn to_value<T: Serialize>(result: T) -> Result<Value, BackendError> {
+    serde_json::to_value(result).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize result")
+            .with_details(e.to_string())
+    })
+}
+
+/// Command whose checks an action goes through
+fn gating_command(spec: &ActionSpec) -> &'static str {
+    spec.command.unwrap_or("execute_action")
+}
+
+async fn run_backend(app: &AppHandle, id: &str, args: Value) -> Result<Value, BackendError> {
+    match id {
+        "exit_ticket.start" => {
+            let args: ExitTicketArgs = parse(args)?;
+            to_value(exit_tickets::start_exit_ticket(
+                &args.prompt,
+                args.lesson_id,
+            )?)
+        }
+        "exit_ticket.close" => to_value(exit_tickets::close_exit_ticket()?),
+        "backup.create" => to_value(
+            jobs::run("backup", "Local backup", |_| {
+                backup::create_local_backup("backup")
+            })
+            .await?,
+        ),
+        "roster.scan_folder" => {
+            let app = app.clone();
+            to_value(
+                jobs::run("sync", "Roster folder scan", move |_| {
+                    roster_sync::scan_now(&app)
+                })
+                .await?,
+            )
+        }
+        "weekly_summary.generate" => to_value(
+            jobs::run("report", "Weekly summary", |_| {
+                weekly_summary::generate_now()
+            })
+            .await?,
+        ),
+        "timer.next_phase" => to_value(timers::skip_phase(app)?),
+        "classroom.set_state" => {
+            let args: ClassroomStateArgs = parse(args)?;
+            to_value(classroom_state::set_classroom_state(
+                app,
+                Some(args.color),
+                None,
+            )?)
+        }
+        "lesson.end" => to_value(recovery::clear_lesson_state()?),
+        "projector.toggle_dim" => to_value(projector_dim::toggle_projector_dim(app)?),
+        "display.presentation_safe" => to_value(presentation_safe::toggle_presentation_safe(app)?),
+        "window.screenshot" => {
+            let args: ScreenshotArgs = parse(args)?;
+            to_value(screenshot::capture_window_screenshot(
+                app,
+                &args.window_label,
+                args.path.as_deref(),
+            )?)
+        }
+        "app.set_language" => {
+            let args: LanguageArgs = parse(args)?;
+            to_value(locale::set_app_language(&args.lang)?)
+        }
+        _ => Err(
+            BackendError::new(errors::action::NOT_FOUND, "Action has no backend handler")
+                .with_details(id.to_string()),
+        ),
+    }
+}
+
+/// Validate and run an action on behalf of the window labelled `label`
+/// (`CONTROLLER_SOURCE` for hardware)
+pub async fn execute_action(
+    app: &AppHandle,
+    label: &str,
+    id: &str,
+    args: Value,
+) -> Result<ActionResult, BackendError> {
+    let spec = find(id)?;
+    let command = gating_command(spec);
+    roles::check_access(label, command)?;
+    read_only_mode::check(app, command)?;
+    let args = validate_args(spec, args)?;
+    let result = match spec.handler {
+        ActionHandler::Backend => run_backend(app, id, args).await?,
+        ActionHandler::Frontend => {
+            app.emit(
+                ACTION_EVENT,
+                ActionInvocation {
+                    id: id.to_string(),
+                    args,
+                },
+            )
+            .map_err(|e| {
+                BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to forward action")
+                    .with_details(e.to_string())
+            })?;
+            Value::Null
+        }
+    };
+    Ok(ActionResult {
+        id: id.to_string(),
+        handler: spec.handler,
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_ids_are_unique_and_schemas_compile() {
+        let mut ids: Vec<&str> = ACTIONS.iter().map(|a| a.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), ACTIONS.len());
+        for action in ACTIONS {
+            assert!(jsonschema::validator_for(&(action.args_schema)()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_backend_actions_stand_for_registered_commands() {
+        let registered: Vec<&str> = include_str!("lib.rs")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("commands::")?.strip_suffix(','))
+            .collect();
+        for action in ACTIONS {
+            assert_eq!(
+                action.command.is_some(),
+                action.handler == ActionHandler::Backend,
+                "{}",
+                action.id
+            );
+            assert!(
+                registered.contains(&gating_command(action)),
+                "{}",
+                action.id
+            );
+        }
+        let screenshot = find("window.screenshot").unwrap();
+        assert_eq!(gating_command(screenshot), "capture_window_screenshot");
+        assert_eq!(
+            gating_command(find("timer.start").unwrap()),
+            "execute_action"
+        );
+    }
+
+    #[test]
+    fn test_validate_args() {
+        let timer = find("timer.start").unwrap();
+        assert!(validate_args(timer, json!({ "minutes": 5 })).is_ok());
+        let err = validate_args(timer, json!({ "minutes": 0 })).unwrap_err();
+        assert_eq!(err.code, errors::system::INVALID_INPUT);
+        assert!(validate_args(timer, Value::Null).is_err());
+
+        let close = find("exit_ticket.close").unwrap();
+        assert_eq!(validate_args(close, Value::Null).unwrap(), json!({}));
+        assert!(validate_args(close, json!({ "extra": 1 })).is_err());
+
+        assert_eq!(
+            find("missing").err().unwrap().code,
+            errors::action::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_list_actions_uses_language() {
+        let it = list_actions(Language::It);
+        let en = list_actions(Language::En);
+        assert_eq!(it[0].title, "Avvia timer");
+        assert_eq!(en[0].title, "Start timer");
+        assert!(en.iter().all(|a| a.args_schema["type"] == "object"));
+    }
+}
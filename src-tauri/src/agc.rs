@@ -0,0 +1,193 @@
+//! Automatic gain compensation for reported noise levels
+//!
+//! Handles:
+//! - An optional stage at the start of the level pipeline
+//!   (`classroom_state::report_noise_level`): levels are shifted so their
+//!   long-run average sits at a target, before the traffic light, noise
+//!   history and `noise-level` events see them
+//! - The setting (`agc` config key): on/off and the target level
+//!
+//! A quiet laptop mic and a sensitive USB mic then trip the same
+//! thresholds in the same room. The average adapts over minutes, so a class
+//! getting louder still shows as louder; the gain is capped at
+//! `MAX_GAIN` either way, so a silent room or an unplugged mic is never
+//! boosted into a red light.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+const CONFIG_KEY: &str = "agc";
+
+/// Hard ceiling on the gain, in level points either way
+pub const MAX_GAIN: f64 = 20.0;
+/// Level scale of the meter (and of the light thresholds)
+pub const MAX_LEVEL: f64 = 100.0;
+const DEFAULT_TARGET: f64 = 50.0;
+/// Time constant of the running average
+const AVERAGE_WINDOW_MS: f64 = 3.0 * 60_000.0;
+
+static STATE: Mutex<Option<Tracker>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgcConfig {
+    pub enabled: bool,
+    /// Level the average is brought to, 0-100
+    pub target_level: f64,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_level: DEFAULT_TARGET,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgcStatus {
+    pub enabled: bool,
+    pub target_level: f64,
+    /// Gain applied to the latest level (0 when off)
+    pub gain: f64,
+    pub max_gain: f64,
+}
+
+/// Running average of raw levels
+#[derive(Debug, Clone, PartialEq)]
+struct Tracker {
+    average: f64,
+    at: u64,
+}
+
+impl Tracker {
+    /// Fold a level in, weighted by the time since the previous one
+    fn observe(&mut self, level: f64, now: u64) {
+        let elapsed = now.saturating_sub(self.at) as f64;
+        let weight = 1.0 - (-elapsed / AVERAGE_WINDOW_MS).exp();
+        self.average += (level - self.average) * weight;
+        self.at = now;
+    }
+
+    fn gain(&self, target: f64) -> f64 {
+        (target - self.average).clamp(-MAX_GAIN, MAX_GAIN)
+    }
+}
+
+pub fn get_config() -> AgcConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn current_gain(config: &AgcConfig) -> f64 {
+    if !config.enabled {
+        return 0.0;
+    }
+    STATE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or(0.0, |t| t.gain(config.target_level))
+}
+
+pub fn get_agc() -> AgcStatus {
+    let config = get_config();
+    AgcStatus {
+        gain: current_gain(&config),
+        enabled: config.enabled,
+        target_level: config.target_level,
+        max_gain: MAX_GAIN,
+    }
+}
+
+/// Turn compensation on or off; `target_level` keeps the saved one if `None`
+///
+/// The running average starts over, so a new target applies from the next
+/// level on.
+pub fn set_agc(enabled: bool, target_level: Option<f64>) -> Result<AgcStatus, BackendError> {
+    let mut config = get_config();
+    if let Some(target) = target_level {
+        if !(0.0..=MAX_LEVEL).contains(&target) {
+            return Err(BackendError::new(
+                errors::system::INVALID_INPUT,
+                format!("Target level must be between 0 and {}", MAX_LEVEL),
+            ));
+        }
+        config.target_level = target;
+    }
+    config.enabled = enabled;
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    Ok(get_agc())
+}
+
+/// Compensate a raw level; returns the compensated level and the gain
+pub fn apply(level: f64, now: u64) -> (f64, f64) {
+    let config = get_config();
+    if !config.enabled {
+        return (level, 0.0);
+    }
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let tracker = state.get_or_insert(Tracker {
+        average: level,
+        at: now,
+    });
+    tracker.observe(level, now);
+    let gain = tracker.gain(config.target_level);
+    ((level + gain).clamp(0.0, MAX_LEVEL), gain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_adapts_over_minutes() {
+        let mut tracker = Tracker {
+            average: 30.0,
+            at: 0,
+        };
+        // A minute of a louder class moves the average only part of the way
+        for second in 1..=60 {
+            tracker.observe(60.0, second * 1000);
+        }
+        assert!(tracker.average > 35.0 && tracker.average < 45.0);
+        // Ten quiet minutes later it has settled
+        tracker.observe(30.0, 11 * 60_000);
+        assert!((tracker.average - 30.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_gain_is_capped() {
+        let quiet_mic = Tracker {
+            average: 10.0,
+            at: 0,
+        };
+        assert_eq!(quiet_mic.gain(50.0), MAX_GAIN);
+        let hot_mic = Tracker {
+            average: 65.0,
+            at: 0,
+        };
+        assert_eq!(hot_mic.gain(50.0), -15.0);
+        assert_eq!(hot_mic.gain(0.0), -MAX_GAIN);
+
+        let config = AgcConfig {
+            enabled: true,
+            target_level: 80.0,
+        };
+        let value = serde_json::to_value(&config).unwrap();
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &value).is_ok());
+        let off_scale = serde_json::json!({ "enabled": true, "targetLevel": 120 });
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &off_scale).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Ambient light estimate from the camera
+//!
+//! Handles:
+//! - Grabbing one frame from the default camera with `ffmpeg`, scaled down
+//!   to a thumbnail and kept in memory only (nothing is saved)
+//! - A rough room brightness (mean luma, 0 to 1) and the projector theme
+//!   that suits it: light high-contrast in sunlit rooms, where a projector
+//!   can't show dark backgrounds, dark in dim ones
+//!
+//! Optional: it only runs when the frontend asks and the camera permission
+//! is granted (see `permissions::camera_permission`). The camera's
+//! auto-exposure flattens the result, so treat it as a hint.
+
+use crate::errors::{self, BackendError};
+use crate::permissions;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Thumbnail width; enough for an average, too small to recognize anyone
+const SAMPLE_WIDTH: u32 = 32;
+/// Frames before this are dropped while the camera adjusts its exposure
+const WARMUP_SECONDS: &str = "0.8";
+
+/// At or above: suggest the light theme
+pub const BRIGHT_ABOVE: f64 = 0.55;
+/// At or below: suggest the dark theme
+pub const DARK_BELOW: f64 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectorTheme {
+    LightHighContrast,
+    DarkHighContrast,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmbientLight {
+    /// Mean luma of the frame, 0 (black) to 1 (white)
+    pub brightness: f64,
+    /// `None` between the thresholds: keep the current theme
+    pub suggested_theme: Option<ProjectorTheme>,
+}
+
+fn sample_error(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::ambient::SAMPLE_FAILED, message).with_details(details.to_string())
+}
+
+/// Mean Rec. 601 luma of an image, 0 to 1
+fn mean_luma(image: &image::RgbImage) -> f64 {
+    let pixels = image.width() as usize * image.height() as usize;
+    if pixels == 0 {
+        return 0.0;
+    }
+    let total: f64 = image
+        .pixels()
+        .map(|p| 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64)
+        .sum();
+    total / pixels as f64 / 255.0
+}
+
+/// Theme for a brightness; the gap between thresholds avoids flipping back
+/// and forth when a cloud passes
+pub fn suggest_theme(brightness: f64) -> Option<ProjectorTheme> {
+    if brightness >= BRIGHT_ABOVE {
+        Some(ProjectorTheme::LightHighContrast)
+    } else if brightness <= DARK_BELOW {
+        Some(ProjectorTheme::DarkHighContrast)
+    } else {
+        None
+    }
+}
+
+/// Video devices in `ffmpeg -list_devices true -f dshow` output
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_dshow_video_devices(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter(|l| l.contains("(video)"))
+        .filter_map(|l| {
+            let start = l.find('"')? + 1;
+            let end = start + l[start..].find('"')?;
+            Some(l[start..end].to_string())
+        })
+        .collect()
+}
+
+/// Camera `ffmpeg` should open, if any
+#[cfg(target_os = "windows")]
+pub fn default_camera() -> Option<String> {
+    let output = Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-list_devices",
+            "true",
+            "-f",
+            "dshow",
+            "-i",
+            "dummy",
+        ])
+        .output()
+        .ok()?;
+    parse_dshow_video_devices(&String::from_utf8_lossy(&output.stderr))
+        .into_iter()
+        .next()
+}
+
+/// Camera `ffmpeg` should open, if any
+#[cfg(target_os = "macos")]
+pub fn default_camera() -> Option<String> {
+    Some("0".to_string())
+}
+
+/// Camera `ffmpeg` should open, if any
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn default_camera() -> Option<String> {
+    let mut devices: Vec<_> = std::fs::read_dir("/dev")
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("video"))
+        })
+        .filter(|p| std::fs::File::open(p).is_ok())
+        .collect();
+    devices.sort();
+    devices.first().map(|p| p.to_string_lossy().to_string())
+}
+
+/// `ffmpeg` input arguments for a camera
+pub(crate) fn input_args(camera: &str) -> Vec<String> {
+    let input =
+        |format: &str, device: String| vec!["-f".to_string(), format.into(), "-i".into(), device];
+    if cfg!(target_os = "windows") {
+        input("dshow", format!("video={}", camera))
+    } else if cfg!(target_os = "macos") {
+        let mut args = vec!["-framerate".to_string(), "30".into()];
+        args.extend(input("avfoundation", camera.to_string()));
+        args
+    } else {
+        input("v4l2", camera.to_string())
+    }
+}
+
+/// One thumbnail frame from the camera, as PNG bytes
+fn grab_frame(camera: &str) -> Result<Vec<u8>, BackendError> {
+    let scale = format!("scale={}:-2", SAMPLE_WIDTH);
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error"])
+        .args(input_args(camera))
+        .args(["-ss", WARMUP_SECONDS, "-frames:v", "1", "-vf", &scale])
+        .args(["-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()
+        .map_err(|e| sample_error("ffmpeg failed to start", e))?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(sample_error(
+            "Camera capture failed",
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Camera to open, once the camera permission is granted
+pub(crate) fn require_camera() -> Result<String, BackendError> {
+    let permission = permissions::camera_permission();
+    if !permission.available {
+        return Err(BackendError::new(
+            errors::permission::CAMERA_UNAVAILABLE,
+            permission.message,
+        ));
+    }
+    if !permission.granted {
+        let error = BackendError::new(errors::permission::CAMERA_DENIED, permission.message);
+        return Err(match permission.details {
+            Some(details) => error.with_details(details),
+            None => error,
+        });
+    }
+    default_camera().ok_or_else(|| {
+        BackendError::new(errors::permission::CAMERA_UNAVAILABLE, "No camera detected")
+    })
+}
+
+/// Estimate the room brightness from one camera frame
+pub fn sample_ambient_light() -> Result<AmbientLight, BackendError> {
+    let camera = require_camera()?;
+    let frame = grab_frame(&camera)?;
+    let image = image::load_from_memory(&frame)
+        .map_err(|e| sample_error("Camera frame could not be read", e))?
+        .to_rgb8();
+    let brightness = (mean_luma(&image) * 1000.0).round() / 1000.0;
+    Ok(AmbientLight {
+        brightness,
+        suggested_theme: suggest_theme(brightness),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_luma() {
+        let white = image::RgbImage::from_pixel(4, 4, image::Rgb([255, 255, 255]));
+        assert!((mean_luma(&white) - 1.0).abs() < 1e-9);
+        let mut half = image::RgbImage::new(2, 1);
+        half.put_pixel(0, 0, image::Rgb([255, 255, 255]));
+        assert!((mean_luma(&half) - 0.5).abs() < 1e-9);
+        // Green weighs more than blue
+        let green = image::RgbImage::from_pixel(1, 1, image::Rgb([0, 255, 0]));
+        let blue = image::RgbImage::from_pixel(1, 1, image::Rgb([0, 0, 255]));
+        assert!(mean_luma(&green) > mean_luma(&blue));
+    }
+
+    #[test]
+    fn test_suggest_theme() {
+        assert_eq!(suggest_theme(0.8), Some(ProjectorTheme::LightHighContrast));
+        assert_eq!(suggest_theme(0.2), Some(ProjectorTheme::DarkHighContrast));
+        assert_eq!(suggest_theme(0.45), None);
+    }
+
+    #[test]
+    fn test_parse_dshow_devices() {
+        let stderr = r#"[dshow @ 0000] "Integrated Camera" (video)
+[dshow @ 0000]   Alternative name "@device_pnp_\\?\usb#vid"
+[dshow @ 0000] "Microphone Array (Realtek)" (audio)
+dummy: Immediate exit requested"#;
+        assert_eq!(parse_dshow_video_devices(stderr), ["Integrated Camera"]);
+    }
+}
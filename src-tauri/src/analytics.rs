@@ -0,0 +1,304 @@
+//! Opt-in anonymous usage analytics
+//!
+//! Handles:
+//! - Explicit consent (`analytics_consent` config key, off by default)
+//! - Counting feature usage locally (`data/analytics.json`)
+//! - Uploading the counters once a day to the `analytics_endpoint` URL, when
+//!   one is configured
+//! - Showing exactly what the next upload would contain
+//!
+//! Only counters of fixed feature names are collected: no student or class
+//! names and no install or user identifier. Periods are sent as dates, not
+//! timestamps. Withdrawing consent deletes the local counters.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CONSENT_KEY: &str = "analytics_consent";
+const ENDPOINT_KEY: &str = "analytics_endpoint";
+const COLLECTION: &str = "analytics";
+
+pub const PAYLOAD_VERSION: u32 = 1;
+
+/// Distinct feature names kept; further names are ignored
+const MAX_FEATURES: usize = 200;
+const MAX_FEATURE_LEN: usize = 64;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const UPLOAD_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Counts not yet merged into the store
+static PENDING: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// Locally stored counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsStore {
+    /// Start of the current counting period (epoch millis)
+    pub period_start: u64,
+    pub counters: BTreeMap<String, u64>,
+    pub last_upload: Option<u64>,
+}
+
+/// What is sent to the endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsPayload {
+    pub version: u32,
+    pub app_version: String,
+    /// "windows", "macos" or "linux"
+    pub os: String,
+    /// Local dates (YYYY-MM-DD)
+    pub period_start: String,
+    pub period_end: String,
+    pub counters: BTreeMap<String, u64>,
+}
+
+/// Consent state and what the next upload would contain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsPreview {
+    pub consent: bool,
+    /// Where the payload goes; `None` keeps counters local only
+    pub endpoint: Option<String>,
+    pub next_upload_at: Option<u64>,
+    pub payload: AnalyticsPayload,
+}
+
+/// Feature names are fixed identifiers like `timer.start`, never free text
+fn is_valid_feature(feature: &str) -> bool {
+    !feature.is_empty()
+        && feature.len() <= MAX_FEATURE_LEN
+        && feature
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | '-'))
+}
+
+fn local_date(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+impl AnalyticsStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Add counts, respecting the distinct-name limit
+    fn merge(&mut self, counts: BTreeMap<String, u64>, now: u64) {
+        if self.period_start == 0 {
+            self.period_start = now;
+        }
+        for (feature, count) in counts {
+            if self.counters.len() >= MAX_FEATURES && !self.counters.contains_key(&feature) {
+                continue;
+            }
+            *self.counters.entry(feature).or_default() += count;
+        }
+    }
+
+    fn payload(&self, now: u64) -> AnalyticsPayload {
+        AnalyticsPayload {
+            version: PAYLOAD_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            period_start: local_date(if self.period_start == 0 {
+                now
+            } else {
+                self.period_start
+            }),
+            period_end: local_date(now),
+            counters: self.counters.clone(),
+        }
+    }
+
+    fn next_upload_at(&self) -> Option<u64> {
+        if self.counters.is_empty() {
+            return None;
+        }
+        Some(self.last_upload.unwrap_or(self.period_start) + UPLOAD_INTERVAL_MS)
+    }
+}
+
+/// Whether the user opted in
+pub fn has_consent() -> bool {
+    file_ops::load_config(CONSENT_KEY)
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn endpoint() -> Option<String> {
+    file_ops::load_config(ENDPOINT_KEY)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .filter(|url| !url.is_empty())
+}
+
+/// Opt in or out; opting out deletes all collected counters
+pub fn set_consent(enabled: bool) -> Result<(), BackendError> {
+    file_ops::save_config(CONSENT_KEY, enabled.into())?;
+    if !enabled {
+        PENDING.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        AnalyticsStore::default().save()?;
+    }
+    Ok(())
+}
+
+/// Count one use of a feature (ignored without consent)
+pub fn record_event(feature: &str) -> Result<(), BackendError> {
+    if !is_valid_feature(feature) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Feature names may only contain a-z, 0-9, '.', '_' and '-'",
+        )
+        .with_details(feature.to_string()));
+    }
+    if has_consent() {
+        *PENDING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(feature.to_string())
+            .or_default() += 1;
+    }
+    Ok(())
+}
+
+/// Merge pending counts into the stored counters
+fn flush() -> Result<AnalyticsStore, BackendError> {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut store = AnalyticsStore::load()?;
+    if !pending.is_empty() {
+        store.merge(pending, clock::now_millis());
+        store.save()?;
+    }
+    Ok(store)
+}
+
+/// Exactly what the next upload would send
+pub fn preview() -> Result<AnalyticsPreview, BackendError> {
+    let store = flush()?;
+    Ok(AnalyticsPreview {
+        consent: has_consent(),
+        endpoint: endpoint(),
+        next_upload_at: store.next_upload_at(),
+        payload: store.payload(clock::now_millis()),
+    })
+}
+
+fn upload(url: &str, payload: &AnalyticsPayload) -> Result<(), BackendError> {
+    let body = serde_json::to_string(payload).map_err(|e| {
+        BackendError::new(
+            errors::system::UNKNOWN_ERROR,
+            "Failed to serialize analytics",
+        )
+        .with_details(e.to_string())
+    })?;
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .map_err(|e| {
+            BackendError::new(errors::lan::NETWORK_ERROR, "Analytics upload failed")
+                .with_details(e.to_string())
+        })?;
+    Ok(())
+}
+
+/// Upload the counters if consent is given and the period is over
+fn upload_if_due() -> Result<(), BackendError> {
+    if !has_consent() {
+        return Ok(());
+    }
+    let mut store = flush()?;
+    let now = clock::now_millis();
+    let (Some(url), Some(due)) = (endpoint(), store.next_upload_at()) else {
+        return Ok(());
+    };
+    if now < due {
+        return Ok(());
+    }
+    upload(&url, &store.payload(now))?;
+    store.counters.clear();
+    store.period_start = now;
+    store.last_upload = Some(now);
+    store.save()
+}
+
+/// Start the periodic flush and upload
+pub fn start() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        // Failed uploads keep the counters for the next attempt
+        let _ = upload_if_due();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_names_exclude_free_text() {
+        assert!(is_valid_feature("timer.start"));
+        assert!(is_valid_feature("noise_monitor-open"));
+        assert!(!is_valid_feature("Mario Rossi"));
+        assert!(!is_valid_feature("export:3A"));
+        assert!(!is_valid_feature(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn test_merge_limits_distinct_features() {
+        let mut store = AnalyticsStore::default();
+        let counts = (0..MAX_FEATURES + 10)
+            .map(|i| (format!("feature{:03}", i), 1))
+            .collect();
+        store.merge(counts, 1_000);
+        store.merge(BTreeMap::from([("feature000".to_string(), 4)]), 2_000);
+
+        assert_eq!(store.counters.len(), MAX_FEATURES);
+        assert_eq!(store.counters["feature000"], 5);
+        assert_eq!(store.period_start, 1_000);
+        assert_eq!(store.next_upload_at(), Some(1_000 + UPLOAD_INTERVAL_MS));
+    }
+
+    #[test]
+    fn test_payload_contains_only_counters_and_dates() {
+        let mut store = AnalyticsStore::default();
+        store.merge(BTreeMap::from([("timer.start".to_string(), 3)]), 1_000);
+        let json = serde_json::to_value(store.payload(2_000)).unwrap();
+        let keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "appVersion",
+                "counters",
+                "os",
+                "periodEnd",
+                "periodStart",
+                "version"
+            ]
+        );
+        assert_eq!(json["counters"]["timer.start"], 3);
+    }
+}
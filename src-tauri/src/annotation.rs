@@ -0,0 +1,257 @@
+//! Annotation overlay for the projector
+//!
+//! Handles:
+//! - The `annotation` window: transparent, borderless, always on top and
+//!   fullscreen on the projector (see `window::open_projector_layer`)
+//! - Input routing: click-through (the default) lets the mouse reach the
+//!   app underneath; turning it off makes the overlay catch pointer input
+//! - Relaying strokes drawn in the teacher window to the overlay as
+//!   `annotation-event` (stroke, undo, clear)
+//!
+//! Stroke coordinates are fractions of the drawing area (0 to 1), so the
+//! teacher window and the projector don't need the same resolution.
+
+use crate::errors::{self, BackendError};
+use crate::pointer_highlight;
+use crate::window;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+/// Label of the annotation window
+pub const OVERLAY_LABEL: &str = "annotation";
+const OVERLAY_ROUTE: &str = "index.html#/annotation";
+/// Sent to the annotation window only
+pub const ANNOTATION_EVENT: &str = "annotation-event";
+
+const MAX_POINTS: usize = 5000;
+const MIN_WIDTH: f64 = 1.0;
+const MAX_WIDTH: f64 = 64.0;
+
+static CLICK_THROUGH: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrokeTool {
+    Pen,
+    Highlighter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrokePoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// One stroke; resending an `id` replaces the stroke (for live drawing)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stroke {
+    pub id: String,
+    pub tool: StrokeTool,
+    /// `#rrggbb`
+    pub color: String,
+    /// In pixels on the projector
+    pub width: f64,
+    pub points: Vec<StrokePoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AnnotationEvent {
+    Stroke { stroke: Stroke },
+    Undo,
+    Clear,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationOverlayStatus {
+    pub open: bool,
+    pub click_through: bool,
+}
+
+fn invalid(message: &str) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message)
+}
+
+fn window_error(message: &str, e: tauri::Error) -> BackendError {
+    BackendError::new(errors::window::CREATE_FAILED, message).with_details(e.to_string())
+}
+
+fn validate_stroke(stroke: &Stroke) -> Result<(), BackendError> {
+    if stroke.id.is_empty() {
+        return Err(invalid("Stroke needs an id"));
+    }
+    let color = stroke.color.strip_prefix('#').unwrap_or_default();
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid("Stroke color must be #rrggbb").with_details(stroke.color.clone()));
+    }
+    if !(MIN_WIDTH..=MAX_WIDTH).contains(&stroke.width) {
+        return Err(invalid("Stroke width out of range"));
+    }
+    if stroke.points.is_empty() || stroke.points.len() > MAX_POINTS {
+        return Err(invalid("Stroke must have 1 to 5000 points"));
+    }
+    let in_range = |v: f64| (0.0..=1.0).contains(&v);
+    if !stroke.points.iter().all(|p| in_range(p.x) && in_range(p.y)) {
+        return Err(invalid("Stroke points must be between 0 and 1"));
+    }
+    Ok(())
+}
+
+fn overlay(app: &AppHandle) -> Result<WebviewWindow, BackendError> {
+    app.get_webview_window(OVERLAY_LABEL).ok_or_else(|| {
+        BackendError::new(errors::window::NOT_FOUND, "Annotation overlay is not open")
+            .with_details(OVERLAY_LABEL.to_string())
+    })
+}
+
+fn apply_click_through(window: &WebviewWindow, enabled: bool) -> Result<(), BackendError> {
+    window
+        .set_ignore_cursor_events(enabled)
+        .map_err(|e| window_error("Failed to change input routing", e))?;
+    if !enabled {
+        // Catching input means the overlay must be the focused window
+        window
+            .set_focus()
+            .map_err(|e| window_error("Failed to focus annotation overlay", e))?;
+    }
+    Ok(())
+}
+
+/// Open the overlay fullscreen on a monitor (default: the projector)
+pub fn open_annotation_overlay(
+    app: &AppHandle,
+    monitor_index: Option<usize>,
+) -> Result<AnnotationOverlayStatus, BackendError> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        window
+            .show()
+            .map_err(|e| window_error("Failed to show annotation overlay", e))?;
+        return Ok(get_annotation_overlay_status(app));
+    }
+
+    let window = window::open_projector_layer(
+        app,
+        OVERLAY_LABEL,
+        OVERLAY_ROUTE,
+        "Classroom – Annotations",
+        monitor_index,
+    )?;
+    apply_click_through(&window, CLICK_THROUGH.load(Ordering::Relaxed))?;
+    window
+        .show()
+        .map_err(|e| window_error("Failed to show annotation overlay", e))?;
+    pointer_highlight::raise(app);
+    Ok(get_annotation_overlay_status(app))
+}
+
+/// Close the overlay (strokes are not kept)
+pub fn close_annotation_overlay(app: &AppHandle) -> Result<(), BackendError> {
+    match app.get_webview_window(OVERLAY_LABEL) {
+        Some(window) => window
+            .close()
+            .map_err(|e| window_error("Failed to close annotation overlay", e)),
+        None => Ok(()),
+    }
+}
+
+pub fn get_annotation_overlay_status(app: &AppHandle) -> AnnotationOverlayStatus {
+    AnnotationOverlayStatus {
+        open: app.get_webview_window(OVERLAY_LABEL).is_some(),
+        click_through: CLICK_THROUGH.load(Ordering::Relaxed),
+    }
+}
+
+/// Let mouse input pass through the overlay (`true`) or catch it
+pub fn set_annotation_click_through(
+    app: &AppHandle,
+    enabled: bool,
+) -> Result<AnnotationOverlayStatus, BackendError> {
+    CLICK_THROUGH.store(enabled, Ordering::Relaxed);
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        apply_click_through(&window, enabled)?;
+        pointer_highlight::raise(app);
+    }
+    Ok(get_annotation_overlay_status(app))
+}
+
+fn send(app: &AppHandle, event: AnnotationEvent) -> Result<(), BackendError> {
+    overlay(app)?;
+    app.emit_to(OVERLAY_LABEL, ANNOTATION_EVENT, event)
+        .map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to send annotation")
+                .with_details(e.to_string())
+        })
+}
+
+/// Draw (or update) a stroke on the overlay
+pub fn relay_annotation_stroke(app: &AppHandle, stroke: Stroke) -> Result<(), BackendError> {
+    validate_stroke(&stroke)?;
+    send(app, AnnotationEvent::Stroke { stroke })
+}
+
+/// Remove the last stroke
+pub fn undo_annotation(app: &AppHandle) -> Result<(), BackendError> {
+    send(app, AnnotationEvent::Undo)
+}
+
+/// Remove all strokes
+pub fn clear_annotations(app: &AppHandle) -> Result<(), BackendError> {
+    send(app, AnnotationEvent::Clear)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke() -> Stroke {
+        Stroke {
+            id: "s1".into(),
+            tool: StrokeTool::Pen,
+            color: "#ff0000".into(),
+            width: 4.0,
+            points: vec![
+                StrokePoint { x: 0.1, y: 0.2 },
+                StrokePoint { x: 0.5, y: 0.5 },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_validate_stroke() {
+        assert!(validate_stroke(&stroke()).is_ok());
+        assert!(validate_stroke(&Stroke {
+            color: "red".into(),
+            ..stroke()
+        })
+        .is_err());
+        assert!(validate_stroke(&Stroke {
+            width: 100.0,
+            ..stroke()
+        })
+        .is_err());
+        assert!(validate_stroke(&Stroke {
+            points: vec![StrokePoint { x: 1.5, y: 0.0 }],
+            ..stroke()
+        })
+        .is_err());
+        assert!(validate_stroke(&Stroke {
+            points: vec![],
+            ..stroke()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_event_shape() {
+        let value = serde_json::to_value(AnnotationEvent::Stroke { stroke: stroke() }).unwrap();
+        assert_eq!(value["kind"], "stroke");
+        assert_eq!(value["stroke"]["tool"], "pen");
+        assert_eq!(
+            serde_json::to_value(AnnotationEvent::Clear).unwrap()["kind"],
+            "clear"
+        );
+    }
+}
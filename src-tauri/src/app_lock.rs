@@ -0,0 +1,472 @@
+//! App lock after inactivity
+//!
+//! Handles:
+//! - The lock settings (`app_lock` config key: enabled, idle timeout) and
+//!   the PIN (Argon2 hash in the keychain)
+//! - Locking on demand or after the idle timeout, and unlocking with the
+//!   PIN (with a short lockout after repeated wrong PINs)
+//! - The command guard: while locked, only commands that return no student
+//!   data (unlock, status, formatting, the noise feed) are served; anything
+//!   else fails with `APP_LOCKED`
+//!
+//! Every served command counts as activity. `app-lock-changed` tells the
+//! windows to show or hide the lock screen.
+//!
+//! The settings travel with the config file but the PIN stays in this PC's
+//! keychain. Without a PIN here (a portable copy on another PC) the lock
+//! stays on; `recover_app_lock` sets a new PIN with the recovery code
+//! shown when the lock was set up, whose hash travels with the settings.
+
+use crate::command_trace;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// Emitted when the app locks or unlocks
+pub const LOCK_EVENT: &str = "app-lock-changed";
+const CONFIG_KEY: &str = "app_lock";
+pub(crate) const PIN_SECRET: &str = "app_lock_pin";
+
+pub const MIN_TIMEOUT_MINUTES: u32 = 1;
+pub const MAX_TIMEOUT_MINUTES: u32 = 240;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Wrong PINs allowed before unlocking is blocked for `LOCKOUT`
+const MAX_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(30);
+
+/// Commands served while locked; none of them return student data
+pub const UNLOCKED_COMMANDS: &[&str] = &[
+    "unlock_app",
+    "recover_app_lock",
+    "lock_app",
+    "get_app_lock_status",
+    "report_noise_level",
+    "get_classroom_state",
+    "get_time_remaining_in_period",
+    "get_projector_dim",
+    "get_freeze_status",
+    "get_device_freeze",
+    "get_app_language",
+    "get_system_locale",
+    "format_date",
+    "format_number",
+    "localize_error",
+    "greet",
+];
+
+static STATE: Mutex<Option<LockState>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppLockConfig {
+    pub enabled: bool,
+    pub timeout_minutes: u32,
+    /// Argon2 hash of the recovery code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_hash: Option<String>,
+}
+
+impl Default for AppLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_minutes: 5,
+            recovery_hash: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub timeout_minutes: u32,
+    /// Only when the lock was just set up; shown once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug)]
+struct LockState {
+    config: AppLockConfig,
+    locked: bool,
+    last_activity: Instant,
+    failures: u32,
+    blocked_until: Option<Instant>,
+}
+
+impl LockState {
+    fn new(config: AppLockConfig, now: Instant) -> Self {
+        Self {
+            config,
+            locked: false,
+            last_activity: now,
+            failures: 0,
+            blocked_until: None,
+        }
+    }
+
+    fn status(&self) -> AppLockStatus {
+        AppLockStatus {
+            enabled: self.config.enabled,
+            locked: self.locked,
+            timeout_minutes: self.config.timeout_minutes,
+            recovery_code: None,
+        }
+    }
+
+    /// Lock if idle for longer than the timeout; returns whether it locked
+    fn lock_if_idle(&mut self, now: Instant) -> bool {
+        let timeout = Duration::from_secs(u64::from(self.config.timeout_minutes) * 60);
+        if self.config.enabled && !self.locked && now.duration_since(self.last_activity) >= timeout
+        {
+            self.locked = true;
+            return true;
+        }
+        false
+    }
+
+    /// Whether `command` may run now; records activity when it may
+    fn admit(&mut self, command: &str, now: Instant) -> bool {
+        self.lock_if_idle(now);
+        if self.locked && !UNLOCKED_COMMANDS.contains(&command) {
+            return false;
+        }
+        // The noise meter reports continuously; it is not the teacher
+        if !self.locked && command != "report_noise_level" {
+            self.last_activity = now;
+        }
+        true
+    }
+
+    /// Count an unlock attempt as failed before it is checked, so parallel
+    /// attempts can't get past `MAX_ATTEMPTS`; `unlock` clears it
+    fn begin_attempt(&mut self, now: Instant) -> Result<(), BackendError> {
+        if self.is_blocked(now) {
+            return Err(BackendError::new(
+                errors::lock::LOCKED_OUT,
+                "Too many wrong PINs, try again shortly",
+            ));
+        }
+        self.failures += 1;
+        if self.failures >= MAX_ATTEMPTS {
+            self.failures = 0;
+            self.blocked_until = Some(now + LOCKOUT);
+        }
+        Ok(())
+    }
+
+    fn is_blocked(&self, now: Instant) -> bool {
+        self.blocked_until.is_some_and(|until| now < until)
+    }
+
+    fn unlock(&mut self, now: Instant) -> AppLockStatus {
+        self.failures = 0;
+        self.blocked_until = None;
+        self.locked = false;
+        self.last_activity = now;
+        self.status()
+    }
+}
+
+fn load_config() -> AppLockConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// A fresh recovery code, e.g. `4F2A-91C0-7D3E-B815`
+fn new_recovery_code() -> Result<String, BackendError> {
+    let mut bytes = [0u8; 8];
+    getrandom::fill(&mut bytes).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    let hex = hex::encode_upper(bytes);
+    Ok(hex
+        .as_bytes()
+        .chunks(4)
+        .map(|c| String::from_utf8_lossy(c).into_owned())
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+/// Recovery codes are compared without dashes, spaces or case
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn with_state<T>(f: impl FnOnce(&mut LockState) -> T) -> T {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(|| LockState::new(load_config(), Instant::now())))
+}
+
+fn emit<R: Runtime>(app: &AppHandle<R>, status: &AppLockStatus) {
+    let _ = app.emit(LOCK_EVENT, status);
+}
+
+/// Drop the lock state so it is rebuilt from the config (factory reset)
+pub(crate) fn reset_state() {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Current lock settings and state
+pub fn get_app_lock_status() -> AppLockStatus {
+    with_state(|s| s.status())
+}
+
+/// Whether the app is locked right now
+pub fn is_locked() -> bool {
+    with_state(|s| s.locked)
+}
+
+/// Enable the lock with a PIN, or disable it with `None`
+///
+/// Enabling returns a new recovery code in the status.
+pub fn set_app_lock(
+    pin: Option<&str>,
+    timeout_minutes: u32,
+) -> Result<AppLockStatus, BackendError> {
+    if !(MIN_TIMEOUT_MINUTES..=MAX_TIMEOUT_MINUTES).contains(&timeout_minutes) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "Timeout must be {} to {} minutes",
+                MIN_TIMEOUT_MINUTES, MAX_TIMEOUT_MINUTES
+            ),
+        ));
+    }
+    let recovery_code = match pin {
+        Some(pin) => {
+            secrets::validate_pin(pin)?;
+            secrets::set_secret(PIN_SECRET, &secrets::hash_pin(pin)?)?;
+            Some(new_recovery_code()?)
+        }
+        None => {
+            secrets::delete_secret(PIN_SECRET)?;
+            None
+        }
+    };
+    let recovery_hash = match &recovery_code {
+        Some(code) => Some(secrets::hash_pin(&normalize_recovery_code(code))?),
+        None => None,
+    };
+    let config = AppLockConfig {
+        enabled: pin.is_some(),
+        timeout_minutes,
+        recovery_hash,
+    };
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(
+            errors::system::UNKNOWN_ERROR,
+            "Failed to serialize settings",
+        )
+        .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(with_state(|s| {
+        s.config = config;
+        s.last_activity = Instant::now();
+        AppLockStatus {
+            recovery_code,
+            ..s.status()
+        }
+    }))
+}
+
+/// Lock now (fails when no PIN is set)
+pub fn lock_app(app: &AppHandle) -> Result<AppLockStatus, BackendError> {
+    let (changed, status) = with_state(|s| {
+        if !s.config.enabled {
+            return Err(BackendError::new(
+                errors::lock::NOT_CONFIGURED,
+                "Set a PIN before locking the app",
+            ));
+        }
+        let changed = !s.locked;
+        s.locked = true;
+        Ok((changed, s.status()))
+    })?;
+    if changed {
+        emit(app, &status);
+    }
+    Ok(status)
+}
+
+/// Unlock with the PIN
+///
+/// Without a PIN in this PC's keychain the app stays locked and fails with
+/// `APP_LOCK_PIN_MISSING`; see `recover_app_lock`.
+pub fn unlock_app(app: &AppHandle, pin: &str) -> Result<AppLockStatus, BackendError> {
+    let stored = secrets::get_secret(PIN_SECRET)?.ok_or_else(|| {
+        BackendError::new(
+            errors::lock::PIN_MISSING,
+            "The lock PIN is missing on this PC, unlock with the recovery code",
+        )
+    })?;
+    with_state(|s| s.begin_attempt(Instant::now()))?;
+    // Argon2 is deliberately slow; verify outside the state lock
+    if !secrets::verify_pin(&stored, pin) {
+        return Err(BackendError::new(errors::lock::INVALID_PIN, "Wrong PIN"));
+    }
+    let status = with_state(|s| s.unlock(Instant::now()));
+    emit(app, &status);
+    Ok(status)
+}
+
+/// Set a new PIN with the recovery code and unlock
+///
+/// Wrong codes count towards the same lockout as wrong PINs.
+pub fn recover_app_lock(
+    app: &AppHandle,
+    recovery_code: &str,
+    new_pin: &str,
+) -> Result<AppLockStatus, BackendError> {
+    secrets::validate_pin(new_pin)?;
+    let stored = load_config().recovery_hash.ok_or_else(|| {
+        BackendError::new(
+            errors::lock::NOT_CONFIGURED,
+            "No recovery code was set up for the app lock",
+        )
+    })?;
+    with_state(|s| s.begin_attempt(Instant::now()))?;
+    if !secrets::verify_pin(&stored, &normalize_recovery_code(recovery_code)) {
+        return Err(BackendError::new(
+            errors::lock::INVALID_PIN,
+            "Wrong recovery code",
+        ));
+    }
+    secrets::set_secret(PIN_SECRET, &secrets::hash_pin(new_pin)?)?;
+    let status = with_state(|s| s.unlock(Instant::now()));
+    emit(app, &status);
+    Ok(status)
+}
+
+/// Wrap the app's command handler with the lock check
+pub fn lock_guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let command = invoke.message.command().to_string();
+        let (admitted, just_locked, status) = with_state(|s| {
+            let now = Instant::now();
+            let was_locked = s.locked;
+            let admitted = s.admit(&command, now);
+            (admitted, !was_locked && s.locked, s.status())
+        });
+        if just_locked {
+            emit(invoke.message.webview_ref().app_handle(), &status);
+        }
+        if admitted {
+            return handler(invoke);
+        }
+        command_trace::reject(
+            invoke.resolver,
+            BackendError::new(errors::lock::LOCKED, "The app is locked").with_details(command),
+        );
+        true
+    }
+}
+
+/// Lock after the idle timeout even when no command arrives
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(IDLE_CHECK_INTERVAL);
+        let locked = with_state(|s| s.lock_if_idle(Instant::now()).then(|| s.status()));
+        if let Some(status) = locked {
+            emit(&app, &status);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled(now: Instant) -> LockState {
+        LockState::new(
+            AppLockConfig {
+                enabled: true,
+                ..AppLockConfig::default()
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_locks_after_idle_timeout() {
+        let start = Instant::now();
+        let mut state = enabled(start);
+        assert!(state.admit("get_classes", start + Duration::from_secs(200)));
+        // Activity at 200 s restarts the timeout
+        assert!(!state.lock_if_idle(start + Duration::from_secs(400)));
+        assert!(state.lock_if_idle(start + Duration::from_secs(500)));
+
+        let mut disabled = LockState::new(AppLockConfig::default(), start);
+        assert!(!disabled.lock_if_idle(start + Duration::from_secs(86_400)));
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let code = new_recovery_code().unwrap();
+        assert_eq!(code.len(), 19);
+        assert_eq!(code.matches('-').count(), 3);
+        assert_eq!(
+            normalize_recovery_code(&code.to_lowercase().replace('-', " ")),
+            code.replace('-', "")
+        );
+    }
+
+    #[test]
+    fn test_locked_state_gates_data_commands() {
+        let start = Instant::now();
+        let mut state = enabled(start);
+        state.locked = true;
+        assert!(!state.admit("get_classes", start));
+        assert!(!state.admit("export_class_archive", start));
+        assert!(state.admit("unlock_app", start));
+        assert!(state.admit("report_noise_level", start));
+    }
+
+    #[test]
+    fn test_noise_feed_is_not_activity() {
+        let start = Instant::now();
+        let mut state = enabled(start);
+        for secs in (0..=300).step_by(10) {
+            state.admit("report_noise_level", start + Duration::from_secs(secs));
+        }
+        assert!(state.locked);
+    }
+
+    #[test]
+    fn test_wrong_pins_block_unlocking() {
+        let start = Instant::now();
+        let mut state = enabled(start);
+        // Attempts count before they are checked, so parallel ones stop too
+        for _ in 0..MAX_ATTEMPTS {
+            assert!(state.begin_attempt(start).is_ok());
+        }
+        let err = state.begin_attempt(start).unwrap_err();
+        assert_eq!(err.code, errors::lock::LOCKED_OUT);
+        assert!(state.is_blocked(start + Duration::from_secs(10)));
+        assert!(!state.is_blocked(start + LOCKOUT));
+
+        // A right PIN clears the count
+        let mut state = enabled(start);
+        state.locked = true;
+        state.begin_attempt(start).unwrap();
+        assert!(!state.unlock(start).locked);
+        assert_eq!(state.failures, 0);
+    }
+}
@@ -0,0 +1,383 @@
+//! Appointment queue for parent-teacher conferences
+//!
+//! Handles:
+//! - A waiting list of appointments, each with its own slot length and an
+//!   optional booked time ("17:30"); walk-ins without one join the end
+//! - Calling the next appointment, which closes the one in progress
+//! - Expected start times recalculated from the actual start of the
+//!   current appointment, so an overrun pushes everyone back and the
+//!   queue reports how late it is running
+//! - `appointment-queue` on every change, and every 30 seconds while an
+//!   appointment is in progress, for the projector display; the kiosk
+//!   page (`kiosk`) renders the same state for the corridor
+//!
+//! The queue lives in memory for the evening and is cleared on restart.
+//! Names are shown on the projector and on the kiosk page, so teachers
+//! enter what they are happy to display (e.g. "Fam. Rossi").
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use chrono::{Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with the `QueueState` whenever the queue changes
+pub const QUEUE_EVENT: &str = "appointment-queue";
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub const DEFAULT_SLOT_MINUTES: u32 = 10;
+pub const MAX_SLOT_MINUTES: u32 = 120;
+pub const MAX_APPOINTMENTS: usize = 200;
+const MAX_NAME_CHARS: usize = 80;
+const MINUTE_MS: u64 = 60_000;
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue::new());
+static TICKER: OnceLock<()> = OnceLock::new();
+
+/// One booked or walk-in appointment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Appointment {
+    /// Ticket number, counting up from 1 for the evening
+    pub number: u32,
+    pub name: String,
+    #[serde(default)]
+    pub student_id: Option<String>,
+    pub slot_minutes: u32,
+    /// Booked start (epoch millis), `None` for walk-ins
+    #[serde(default)]
+    pub scheduled_at: Option<u64>,
+}
+
+/// An appointment with its recalculated times
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueEntry {
+    #[serde(flatten)]
+    pub appointment: Appointment,
+    /// Actual start for the current appointment, expected start otherwise
+    pub expected_at: u64,
+    pub expected_end_at: u64,
+    /// Minutes past the booked time (0 for walk-ins)
+    pub late_minutes: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueState {
+    pub current: Option<QueueEntry>,
+    pub waiting: Vec<QueueEntry>,
+    /// Appointments held so far
+    pub done: u32,
+    /// How late the next booked appointment will start
+    pub running_late_minutes: u32,
+    pub updated_at: u64,
+}
+
+#[derive(Debug)]
+struct Queue {
+    next_number: u32,
+    /// The appointment in progress and when it started
+    current: Option<(Appointment, u64)>,
+    waiting: Vec<Appointment>,
+    done: u32,
+}
+
+impl Queue {
+    const fn new() -> Self {
+        Queue {
+            next_number: 1,
+            current: None,
+            waiting: Vec::new(),
+            done: 0,
+        }
+    }
+
+    /// Booked appointments go before later bookings; walk-ins at the end
+    fn add(&mut self, mut appointment: Appointment) -> Appointment {
+        appointment.number = self.next_number;
+        self.next_number += 1;
+        let position = appointment
+            .scheduled_at
+            .and_then(|at| {
+                self.waiting
+                    .iter()
+                    .position(|a| a.scheduled_at.is_some_and(|other| other > at))
+            })
+            .unwrap_or(self.waiting.len());
+        self.waiting.insert(position, appointment.clone());
+        appointment
+    }
+
+    fn call_next(&mut self, now: u64) {
+        if self.current.take().is_some() {
+            self.done += 1;
+        }
+        if !self.waiting.is_empty() {
+            self.current = Some((self.waiting.remove(0), now));
+        }
+    }
+
+    fn remove(&mut self, number: u32) -> Option<Appointment> {
+        let index = self.waiting.iter().position(|a| a.number == number)?;
+        Some(self.waiting.remove(index))
+    }
+
+    /// Times as of `now`: each appointment starts when the previous one is
+    /// expected to end, never before its booked time
+    fn state(&self, now: u64) -> QueueState {
+        let late = |start: u64, appointment: &Appointment| {
+            appointment
+                .scheduled_at
+                .map_or(0, |at| (start.saturating_sub(at) / MINUTE_MS) as u32)
+        };
+        let slot_ms = |appointment: &Appointment| u64::from(appointment.slot_minutes) * MINUTE_MS;
+
+        let current = self.current.as_ref().map(|(appointment, started)| {
+            // An overrun ends no earlier than now
+            let end = (started + slot_ms(appointment)).max(now);
+            QueueEntry {
+                appointment: appointment.clone(),
+                expected_at: *started,
+                expected_end_at: end,
+                late_minutes: late(*started, appointment),
+            }
+        });
+        let mut cursor = current.as_ref().map_or(now, |c| c.expected_end_at);
+        let waiting: Vec<QueueEntry> = self
+            .waiting
+            .iter()
+            .map(|appointment| {
+                let start = appointment.scheduled_at.map_or(cursor, |at| at.max(cursor));
+                cursor = start + slot_ms(appointment);
+                QueueEntry {
+                    appointment: appointment.clone(),
+                    expected_at: start,
+                    expected_end_at: cursor,
+                    late_minutes: late(start, appointment),
+                }
+            })
+            .collect();
+        let running_late_minutes = waiting
+            .iter()
+            .find(|e| e.appointment.scheduled_at.is_some())
+            .map_or(0, |e| e.late_minutes);
+        QueueState {
+            current,
+            waiting,
+            done: self.done,
+            running_late_minutes,
+            updated_at: now,
+        }
+    }
+}
+
+fn lock() -> MutexGuard<'static, Queue> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn invalid(message: impl Into<String>) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message)
+}
+
+/// Epoch millis of `HH:MM` today
+fn today_at(time: &str) -> Result<u64, BackendError> {
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| invalid("Time must be HH:MM").with_details(time.to_string()))?;
+    Local
+        .from_local_datetime(&Local::now().date_naive().and_time(time))
+        .earliest()
+        .map(|at| at.timestamp_millis().max(0) as u64)
+        .ok_or_else(|| invalid("Time does not exist today"))
+}
+
+/// `HH:MM` of epoch millis in local time
+pub fn local_time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|at| at.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn emit(app: &AppHandle, state: &QueueState) {
+    let _ = app.emit(QUEUE_EVENT, state);
+}
+
+/// Re-send the times while an appointment is in progress, as an overrun
+/// moves everyone after it
+fn ensure_ticker(app: &AppHandle) {
+    TICKER.get_or_init(|| {
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(TICK_INTERVAL);
+            let queue = lock();
+            if queue.current.is_some() {
+                let state = queue.state(clock::now_millis());
+                drop(queue);
+                emit(&app, &state);
+            }
+        });
+    });
+}
+
+/// Add an appointment to the queue
+///
+/// `time` is a booked start today ("17:30"); without one the appointment
+/// is a walk-in and joins the end.
+pub fn add_appointment(
+    app: &AppHandle,
+    name: &str,
+    student_id: Option<String>,
+    slot_minutes: Option<u32>,
+    time: Option<&str>,
+) -> Result<QueueState, BackendError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(invalid(format!(
+            "Name must be 1-{} characters",
+            MAX_NAME_CHARS
+        )));
+    }
+    let slot_minutes = slot_minutes.unwrap_or(DEFAULT_SLOT_MINUTES);
+    if !(1..=MAX_SLOT_MINUTES).contains(&slot_minutes) {
+        return Err(invalid(format!(
+            "Slot length must be 1-{} minutes",
+            MAX_SLOT_MINUTES
+        )));
+    }
+    let scheduled_at = time.map(today_at).transpose()?;
+
+    let mut queue = lock();
+    if queue.waiting.len() >= MAX_APPOINTMENTS {
+        return Err(invalid(format!(
+            "The queue holds at most {} appointments",
+            MAX_APPOINTMENTS
+        )));
+    }
+    queue.add(Appointment {
+        number: 0,
+        name: name.to_string(),
+        student_id: student_id.filter(|id| !id.trim().is_empty()),
+        slot_minutes,
+        scheduled_at,
+    });
+    let state = queue.state(clock::now_millis());
+    drop(queue);
+    ensure_ticker(app);
+    emit(app, &state);
+    Ok(state)
+}
+
+/// Close the appointment in progress and start the next one
+pub fn call_next(app: &AppHandle) -> QueueState {
+    let now = clock::now_millis();
+    let mut queue = lock();
+    queue.call_next(now);
+    let state = queue.state(now);
+    drop(queue);
+    emit(app, &state);
+    state
+}
+
+/// Take a waiting appointment off the queue (e.g. a no-show)
+pub fn remove_appointment(app: &AppHandle, number: u32) -> Result<QueueState, BackendError> {
+    let mut queue = lock();
+    queue.remove(number).ok_or_else(|| {
+        BackendError::new(errors::appointment::NOT_FOUND, "Appointment not found")
+            .with_details(number.to_string())
+    })?;
+    let state = queue.state(clock::now_millis());
+    drop(queue);
+    emit(app, &state);
+    Ok(state)
+}
+
+/// Empty the queue and restart ticket numbers
+pub fn clear_queue(app: &AppHandle) -> QueueState {
+    let mut queue = lock();
+    *queue = Queue::new();
+    let state = queue.state(clock::now_millis());
+    drop(queue);
+    emit(app, &state);
+    state
+}
+
+pub fn get_queue_state() -> QueueState {
+    lock().state(clock::now_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: u64 = 1_700_000_000_000;
+
+    fn appointment(name: &str, slot_minutes: u32, scheduled_minute: Option<u64>) -> Appointment {
+        Appointment {
+            number: 0,
+            name: name.to_string(),
+            student_id: None,
+            slot_minutes,
+            scheduled_at: scheduled_minute.map(|m| T0 + m * MINUTE_MS),
+        }
+    }
+
+    fn names(state: &QueueState) -> Vec<&str> {
+        state
+            .waiting
+            .iter()
+            .map(|e| e.appointment.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_bookings_are_ordered_and_walk_ins_join_the_end() {
+        let mut queue = Queue::new();
+        queue.add(appointment("Rossi", 10, Some(20)));
+        queue.add(appointment("Walk-in", 5, None));
+        let bianchi = queue.add(appointment("Bianchi", 10, Some(10)));
+        assert_eq!(bianchi.number, 3);
+
+        let state = queue.state(T0);
+        assert_eq!(names(&state), ["Bianchi", "Rossi", "Walk-in"]);
+        // Nobody is seen before their booked time
+        assert_eq!(state.waiting[0].expected_at, T0 + 10 * MINUTE_MS);
+        assert_eq!(state.waiting[1].expected_at, T0 + 20 * MINUTE_MS);
+        assert_eq!(state.waiting[2].expected_at, T0 + 30 * MINUTE_MS);
+        assert_eq!(state.running_late_minutes, 0);
+
+        assert!(queue.remove(bianchi.number).is_some());
+        assert!(queue.remove(bianchi.number).is_none());
+        assert_eq!(names(&queue.state(T0)), ["Rossi", "Walk-in"]);
+    }
+
+    #[test]
+    fn test_overrun_pushes_the_queue_back() {
+        let mut queue = Queue::new();
+        queue.add(appointment("Rossi", 10, Some(0)));
+        queue.add(appointment("Bianchi", 15, Some(10)));
+        queue.add(appointment("Verdi", 10, Some(25)));
+
+        // Rossi starts 5 minutes late and is still talking 20 minutes in
+        queue.call_next(T0 + 5 * MINUTE_MS);
+        let state = queue.state(T0 + 25 * MINUTE_MS);
+        let current = state.current.as_ref().unwrap();
+        assert_eq!(current.appointment.name, "Rossi");
+        assert_eq!(current.late_minutes, 5);
+        assert_eq!(current.expected_end_at, T0 + 25 * MINUTE_MS);
+        assert_eq!(state.waiting[0].late_minutes, 15);
+        assert_eq!(state.waiting[1].expected_at, T0 + 40 * MINUTE_MS);
+        assert_eq!(state.waiting[1].late_minutes, 15);
+        assert_eq!(state.running_late_minutes, 15);
+
+        queue.call_next(T0 + 25 * MINUTE_MS);
+        queue.call_next(T0 + 38 * MINUTE_MS);
+        queue.call_next(T0 + 50 * MINUTE_MS);
+        let state = queue.state(T0 + 50 * MINUTE_MS);
+        assert!(state.current.is_none() && state.waiting.is_empty());
+        assert_eq!(state.done, 3);
+    }
+}
@@ -0,0 +1,383 @@
+//! `classroom-asset://` protocol for stored photos and attachments
+//!
+//! Handles:
+//! - Serving files from managed storage to the app's own webviews, so an
+//!   `<img>` or `<iframe>` can point at them instead of pulling megabytes
+//!   of base64 through an invoke response:
+//!   - `classroom-asset://localhost/photo/<class_id>/<student_id>`
+//!   - `classroom-asset://localhost/attachment/<attachment_id>`
+//!
+//!   (On Windows the webview sees the scheme as
+//!   `http://classroom-asset.localhost/...`.)
+//! - Access control: a request is refused (403) while the app is locked,
+//!   and otherwise served only when the requesting window and active role
+//!   may run the command that exposes the same data (`get_classes` for
+//!   photos, `list_attachments` for attachments)
+//! - Caching: attachments are content-addressed and served as immutable;
+//!   photos can be replaced under the same URL, so they are revalidated
+//!   with an ETag (304 when unchanged)
+//! - Byte ranges (`Range: bytes=a-b`), so video and audio attachments can
+//!   seek without the whole file being sent
+//!
+//! Files are served with `nosniff`, and anything that isn't an image,
+//! audio, video, PDF or plain text as a download, so a stored HTML or SVG
+//! file never runs inside the app.
+
+use crate::attachments::{self, AttachmentStore};
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::photos;
+use crate::roles;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{Runtime, UriSchemeContext, UriSchemeResponder};
+
+pub const SCHEME: &str = "classroom-asset";
+
+/// A stored file addressed by a URL
+#[derive(Debug, Clone, PartialEq)]
+enum Asset {
+    Photo {
+        class_id: String,
+        student_id: String,
+    },
+    Attachment {
+        id: String,
+    },
+}
+
+impl Asset {
+    /// Command whose guards decide who may load the asset
+    fn guard_command(&self) -> &'static str {
+        match self {
+            Asset::Photo { .. } => "get_classes",
+            Asset::Attachment { .. } => "list_attachments",
+        }
+    }
+}
+
+/// A resolved asset on disk
+struct StoredFile {
+    path: PathBuf,
+    content_type: &'static str,
+    etag: String,
+    /// Same URL, same bytes, forever
+    immutable: bool,
+}
+
+/// Decode `%XX` escapes; `None` on a malformed escape or invalid UTF-8
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// One path segment naming a stored item; never a path of its own
+fn id_segment(segment: &str) -> Option<String> {
+    let id = percent_decode(segment)?;
+    let safe = !id.is_empty()
+        && id != "."
+        && id != ".."
+        && !id.contains(['/', '\\', '\0'])
+        && !id.contains(':');
+    safe.then_some(id)
+}
+
+fn parse_path(path: &str) -> Option<Asset> {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    match segments[..] {
+        ["photo", class_id, student_id] => Some(Asset::Photo {
+            class_id: id_segment(class_id)?,
+            student_id: id_segment(student_id)?,
+        }),
+        ["attachment", id] => Some(Asset::Attachment {
+            id: id_segment(id)?,
+        }),
+        _ => None,
+    }
+}
+
+const DOWNLOAD_TYPE: &str = "application/octet-stream";
+
+/// MIME type of the types shown inline, `DOWNLOAD_TYPE` for the rest
+fn content_type(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain; charset=utf-8",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => DOWNLOAD_TYPE,
+    }
+}
+
+fn missing(path: &Path) -> BackendError {
+    BackendError::new(errors::file::NOT_FOUND, "File is missing from storage")
+        .with_details(path.to_string_lossy().to_string())
+}
+
+fn resolve(asset: &Asset) -> Result<StoredFile, BackendError> {
+    match asset {
+        Asset::Photo {
+            class_id,
+            student_id,
+        } => {
+            let path = photos::class_photo_dir(class_id)?.join(format!("{}.jpg", student_id));
+            let meta = std::fs::metadata(&path).map_err(|_| missing(&path))?;
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis());
+            Ok(StoredFile {
+                path,
+                content_type: "image/jpeg",
+                etag: format!("\"{:x}-{:x}\"", modified, meta.len()),
+                immutable: false,
+            })
+        }
+        Asset::Attachment { id } => {
+            let store = AttachmentStore::load()?;
+            let attachment = store
+                .attachments
+                .iter()
+                .find(|a| &a.id == id)
+                .ok_or_else(|| {
+                    BackendError::new(errors::attachment::NOT_FOUND, "Attachment not found")
+                        .with_details(id.clone())
+                })?;
+            let stored_name = attachment.stored_name();
+            let path = file_ops::get_config_dir()?
+                .join(attachments::STORAGE_DIR)
+                .join(&stored_name);
+            if !path.is_file() {
+                return Err(missing(&path));
+            }
+            let extension = stored_name.rsplit_once('.').map_or("", |(_, ext)| ext);
+            Ok(StoredFile {
+                path,
+                content_type: content_type(extension),
+                etag: format!("\"{}\"", attachment.hash),
+                immutable: true,
+            })
+        }
+    }
+}
+
+/// First byte range of a `Range` header, clamped to `len`
+///
+/// `None` without a usable range (the whole file is sent), `Some(Err)`
+/// when the range can't be satisfied.
+fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    let (start, end) = spec.split(',').next()?.trim().split_once('-')?;
+    let range = match (start.trim(), end.trim()) {
+        ("", "") => return None,
+        // The last `n` bytes
+        ("", suffix) => {
+            let n = suffix.parse::<u64>().ok()?;
+            (len.saturating_sub(n), len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.checked_sub(1)?),
+        (start, end) => {
+            let end: u64 = end.parse().ok()?;
+            (start.parse().ok()?, end.min(len.saturating_sub(1)))
+        }
+    };
+    Some(if range.0 <= range.1 && range.1 < len {
+        Ok(range)
+    } else {
+        Err(())
+    })
+}
+
+fn status_for(error: &BackendError) -> StatusCode {
+    match error.code.as_str() {
+        errors::file::NOT_FOUND | errors::attachment::NOT_FOUND => StatusCode::NOT_FOUND,
+        errors::lock::LOCKED | errors::role::FORBIDDEN | errors::observer::READ_ONLY => {
+            StatusCode::FORBIDDEN
+        }
+        errors::system::INVALID_INPUT => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(error: &BackendError) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status_for(error))
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(error.to_string().into_bytes())
+        .unwrap_or_default()
+}
+
+/// Refuse the request unless `label` and the active role may see `asset`
+fn check_access(label: &str, asset: &Asset) -> Result<(), BackendError> {
+    roles::check_access(label, asset.guard_command())
+}
+
+fn serve(label: &str, request: &Request<Vec<u8>>) -> Result<Response<Vec<u8>>, BackendError> {
+    let method = request.method();
+    if method != Method::GET && method != Method::HEAD {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Only GET and HEAD are supported",
+        )
+        .with_details(method.to_string()));
+    }
+    let asset = parse_path(request.uri().path()).ok_or_else(|| {
+        BackendError::new(errors::file::NOT_FOUND, "Unknown asset")
+            .with_details(request.uri().path().to_string())
+    })?;
+    check_access(label, &asset)?;
+    let stored = resolve(&asset)?;
+
+    let cache_control = if stored.immutable {
+        "private, max-age=31536000, immutable"
+    } else {
+        "private, no-cache"
+    };
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, stored.content_type)
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ETAG, &stored.etag)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(
+            header::CONTENT_DISPOSITION,
+            if stored.content_type == DOWNLOAD_TYPE {
+                "attachment"
+            } else {
+                "inline"
+            },
+        );
+    let unchanged = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| tags.split(',').any(|t| t.trim() == stored.etag));
+    let build_error = |e: tauri::http::Error| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to build response")
+            .with_details(e.to_string())
+    };
+    if unchanged {
+        return response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .map_err(build_error);
+    }
+
+    let mut file = File::open(&stored.path).map_err(|_| missing(&stored.path))?;
+    let len = file.metadata()?.len();
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, len));
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, len),
+        Some(Ok((start, last))) => (StatusCode::PARTIAL_CONTENT, start, last + 1),
+        Some(Err(())) => {
+            return response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .map_err(build_error);
+        }
+    };
+    let mut response = response
+        .status(status)
+        .header(header::CONTENT_LENGTH, end - start);
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end - 1, len),
+        );
+    }
+    let mut body = Vec::new();
+    if method == Method::GET {
+        file.seek(SeekFrom::Start(start))?;
+        file.take(end - start).read_to_end(&mut body)?;
+    }
+    response.body(body).map_err(build_error)
+}
+
+/// Protocol handler; file reads run off the webview's thread
+pub fn handle<R: Runtime>(
+    context: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let label = context.webview_label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = serve(&label, &request).unwrap_or_else(|e| error_response(&e));
+        responder.respond(response);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("/photo/class_1/Mario%20Rossi"),
+            Some(Asset::Photo {
+                class_id: "class_1".into(),
+                student_id: "Mario Rossi".into(),
+            })
+        );
+        assert_eq!(
+            parse_path("/attachment/attachment_1_abc"),
+            Some(Asset::Attachment {
+                id: "attachment_1_abc".into()
+            })
+        );
+        for path in [
+            "/photo/..%2F..%2Fsecrets/x",
+            "/photo/../x",
+            "/photo/a%5Cb/x",
+            "/photo/a/C:x",
+            "/photo/a/%zz",
+            "/attachment/",
+            "/attachment/a/b",
+            "/config.json",
+        ] {
+            assert_eq!(parse_path(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some(Ok((0, 99))));
+        assert_eq!(parse_range("bytes=900-", 1000), Some(Ok((900, 999))));
+        assert_eq!(parse_range("bytes=-100", 1000), Some(Ok((900, 999))));
+        assert_eq!(parse_range("bytes=990-2000", 1000), Some(Ok((990, 999))));
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(Err(())));
+        assert_eq!(parse_range("bytes=0-0", 0), Some(Err(())));
+        assert_eq!(parse_range("bytes=-10", 0), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+        assert_eq!(parse_range("bytes=a-b", 1000), None);
+    }
+}
@@ -0,0 +1,346 @@
+//! Files attached to lessons and classes
+//!
+//! Handles:
+//! - Copying worksheets, slides, etc. into managed storage
+//!   (`attachments/` in the config directory), so they survive the
+//!   original being moved or deleted
+//! - Content-addressed storage: files are stored once per SHA-256, however
+//!   many lessons or classes they are attached to
+//! - Shrinking JPEG and PNG images over `OPTIMIZE_ABOVE_BYTES` before they
+//!   are stored (see `image_optimize`), keeping the original when that
+//!   doesn't make it smaller; an opaque PNG becomes a JPEG and its file
+//!   name follows
+//! - Opening an attachment with the system's default application
+//!
+//! Attachment records live in the `attachments` data collection. Entities
+//! are referenced as `class:<id>` or `lesson:<id>`.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::image_optimize::{self, ImageFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+const COLLECTION: &str = "attachments";
+pub(crate) const STORAGE_DIR: &str = "attachments";
+
+/// Largest file that can be attached
+pub const MAX_ATTACHMENT_BYTES: u64 = 200 * 1024 * 1024;
+
+const ENTITY_KINDS: &[&str] = &["class", "lesson"];
+
+/// Images up to this size are stored as they are
+const OPTIMIZE_ABOVE_BYTES: u64 = 300 * 1024;
+
+/// An attached file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    /// `class:<id>` or `lesson:<id>`
+    pub entity: String,
+    /// Original file name, shown in the UI
+    pub file_name: String,
+    /// SHA-256 of the content, also the stored file's name
+    pub hash: String,
+    pub size: u64,
+    pub added_at: u64,
+}
+
+impl Attachment {
+    /// Name in managed storage (hash plus original extension, so the OS
+    /// picks the right application)
+    pub(crate) fn stored_name(&self) -> String {
+        stored_name(&self.hash, &self.file_name)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttachmentStore {
+    pub attachments: Vec<Attachment>,
+}
+
+impl AttachmentStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    fn find(&self, id: &str) -> Result<&Attachment, BackendError> {
+        self.attachments.iter().find(|a| a.id == id).ok_or_else(|| {
+            BackendError::new(errors::attachment::NOT_FOUND, "Attachment not found")
+                .with_details(id.to_string())
+        })
+    }
+}
+
+fn stored_name(hash: &str, file_name: &str) -> String {
+    match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            format!("{}.{}", hash, ext.to_ascii_lowercase())
+        }
+        _ => hash.to_string(),
+    }
+}
+
+fn validate_entity(entity: &str) -> Result<(), BackendError> {
+    match entity.split_once(':') {
+        Some((kind, id)) if ENTITY_KINDS.contains(&kind) && !id.trim().is_empty() => Ok(()),
+        _ => Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Entity must be class:<id> or lesson:<id>",
+        )
+        .with_details(entity.to_string())),
+    }
+}
+
+fn storage_dir() -> Result<PathBuf, BackendError> {
+    Ok(file_ops::get_config_dir()?.join(STORAGE_DIR))
+}
+
+pub(crate) fn hash_file(path: &Path) -> Result<String, BackendError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Copy `source` into `dir` unless identical content is already stored;
+/// returns the hash
+fn store_blob(dir: &Path, source: &Path, file_name: &str) -> Result<String, BackendError> {
+    let hash = hash_file(source)?;
+    let target = dir.join(stored_name(&hash, file_name));
+    if !target.exists() {
+        fs::create_dir_all(dir)?;
+        let tmp = target.with_extension("tmp");
+        fs::copy(source, &tmp)?;
+        fs::rename(&tmp, &target)?;
+    }
+    Ok(hash)
+}
+
+/// Store `bytes` in `dir` unless identical content is already stored;
+/// returns the hash
+fn store_bytes(dir: &Path, bytes: &[u8], file_name: &str) -> Result<String, BackendError> {
+    let hash = hex::encode(Sha256::digest(bytes));
+    let target = dir.join(stored_name(&hash, file_name));
+    if !target.exists() {
+        fs::create_dir_all(dir)?;
+        let tmp = target.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &target)?;
+    }
+    Ok(hash)
+}
+
+/// A smaller re-encoding of an image file and the name to store it under;
+/// `None` for other files, images that don't shrink or can't be decoded
+fn shrink_image(source: &Path, file_name: &str, size: u64) -> Option<(Vec<u8>, String)> {
+    let format = ImageFormat::from_path(Path::new(file_name))?;
+    if size <= OPTIMIZE_ABOVE_BYTES || size > image_optimize::MAX_INPUT_BYTES {
+        return None;
+    }
+    let optimized = image_optimize::optimize(
+        &fs::read(source).ok()?,
+        image_optimize::DEFAULT_MAX_DIM,
+        image_optimize::DEFAULT_QUALITY,
+    )
+    .ok()?;
+    if optimized.bytes.len() as u64 >= size {
+        return None;
+    }
+    let name = if optimized.format == format {
+        file_name.to_string()
+    } else {
+        Path::new(file_name)
+            .with_extension(optimized.format.extension())
+            .to_string_lossy()
+            .to_string()
+    };
+    Some((optimized.bytes, name))
+}
+
+/// Attach a file to a class or lesson
+///
+/// Attaching the same content to the same entity again returns the
+/// existing attachment.
+pub fn attach_file(entity: &str, path: &str) -> Result<Attachment, BackendError> {
+    validate_entity(entity)?;
+    let source = Path::new(path);
+    let meta = fs::metadata(source)?;
+    if !meta.is_file() {
+        return Err(
+            BackendError::new(errors::system::INVALID_INPUT, "Not a file")
+                .with_details(path.to_string()),
+        );
+    }
+    if meta.len() > MAX_ATTACHMENT_BYTES {
+        return Err(BackendError::new(
+            errors::attachment::TOO_LARGE,
+            format!(
+                "Attachments are limited to {} MB",
+                MAX_ATTACHMENT_BYTES / 1024 / 1024
+            ),
+        ));
+    }
+    let file_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+
+    let dir = storage_dir()?;
+    let (hash, file_name, size) = match shrink_image(source, &file_name, meta.len()) {
+        Some((bytes, name)) => (store_bytes(&dir, &bytes, &name)?, name, bytes.len() as u64),
+        None => (store_blob(&dir, source, &file_name)?, file_name, meta.len()),
+    };
+    let mut store = AttachmentStore::load()?;
+    if let Some(existing) = store
+        .attachments
+        .iter()
+        .find(|a| a.entity == entity && a.hash == hash)
+    {
+        return Ok(existing.clone());
+    }
+    let now = clock::now_millis();
+    let attachment = Attachment {
+        id: format!("attachment_{}_{}", now, &hash[..12]),
+        entity: entity.to_string(),
+        file_name,
+        hash,
+        size,
+        added_at: now,
+    };
+    store.attachments.push(attachment.clone());
+    store.save()?;
+    Ok(attachment)
+}
+
+/// Attachments of a class or lesson, oldest first
+pub fn list_attachments(entity: &str) -> Result<Vec<Attachment>, BackendError> {
+    validate_entity(entity)?;
+    Ok(AttachmentStore::load()?
+        .attachments
+        .into_iter()
+        .filter(|a| a.entity == entity)
+        .collect())
+}
+
+/// Open an attachment with the default application
+pub fn open_attachment(app: &AppHandle, id: &str) -> Result<(), BackendError> {
+    let store = AttachmentStore::load()?;
+    let path = storage_dir()?.join(store.find(id)?.stored_name());
+    if !path.exists() {
+        return Err(BackendError::new(
+            errors::file::NOT_FOUND,
+            "Attachment file is missing from storage",
+        )
+        .with_details(path.to_string_lossy().to_string()));
+    }
+    app.opener()
+        .open_path(path.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to open attachment")
+                .with_details(e.to_string())
+        })
+}
+
+/// Remove an attachment; the stored file is deleted once nothing uses it
+pub fn remove_attachment(id: &str) -> Result<(), BackendError> {
+    let mut store = AttachmentStore::load()?;
+    let removed = store.find(id)?.clone();
+    store.attachments.retain(|a| a.id != id);
+    store.save()?;
+    remove_unused_files(&store, &[removed])
+}
+
+/// Delete the stored files of `removed` that no attachment in `store`
+/// still uses
+pub(crate) fn remove_unused_files(
+    store: &AttachmentStore,
+    removed: &[Attachment],
+) -> Result<(), BackendError> {
+    for attachment in removed {
+        let name = attachment.stored_name();
+        if store.attachments.iter().any(|a| a.stored_name() == name) {
+            continue;
+        }
+        match fs::remove_file(storage_dir()?.join(name)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_blob_deduplicates_by_content() {
+        let src = tempfile::tempdir().unwrap();
+        let storage = tempfile::tempdir().unwrap();
+        let a = src.path().join("Scheda 1.PDF");
+        let b = src.path().join("copia.pdf");
+        fs::write(&a, b"%PDF-1.4 worksheet").unwrap();
+        fs::write(&b, b"%PDF-1.4 worksheet").unwrap();
+
+        let hash_a = store_blob(storage.path(), &a, "Scheda 1.PDF").unwrap();
+        let hash_b = store_blob(storage.path(), &b, "copia.pdf").unwrap();
+        assert_eq!(hash_a, hash_b);
+
+        let files: Vec<_> = fs::read_dir(storage.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let stored = storage.path().join(format!("{}.pdf", hash_a));
+        assert_eq!(fs::read(stored).unwrap(), b"%PDF-1.4 worksheet");
+    }
+
+    #[test]
+    fn test_large_images_are_shrunk() {
+        let src = tempfile::tempdir().unwrap();
+        // Noise doesn't compress, so the PNG is well over the threshold
+        let mut seed = 1u32;
+        let photo = image::RgbImage::from_fn(3000, 200, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let [a, b, c, _] = seed.to_le_bytes();
+            image::Rgb([a, b, c])
+        });
+        let path = src.path().join("Lavagna.PNG");
+        photo
+            .save_with_format(&path, image::ImageFormat::Png)
+            .unwrap();
+        let size = fs::metadata(&path).unwrap().len();
+        assert!(size > OPTIMIZE_ABOVE_BYTES);
+
+        let (bytes, name) = shrink_image(&path, "Lavagna.PNG", size).unwrap();
+        assert_eq!(name, "Lavagna.jpg");
+        assert!((bytes.len() as u64) < size);
+        let stored = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(stored.width(), image_optimize::DEFAULT_MAX_DIM);
+
+        // Small images and other files are kept as they are
+        assert!(shrink_image(&path, "Lavagna.PNG", OPTIMIZE_ABOVE_BYTES).is_none());
+        assert!(shrink_image(&path, "Lavagna.pdf", size).is_none());
+    }
+
+    #[test]
+    fn test_validate_entity() {
+        assert!(validate_entity("class:3A").is_ok());
+        assert!(validate_entity("lesson:lesson_123").is_ok());
+        assert!(validate_entity("student:1").is_err());
+        assert!(validate_entity("class:").is_err());
+        assert!(validate_entity("3A").is_err());
+        assert_eq!(stored_name("abc", "slides.pptx"), "abc.pptx");
+        assert_eq!(stored_name("abc", "README"), "abc");
+    }
+}
@@ -0,0 +1,328 @@
+//! Audio output devices and routing
+//!
+//! Handles:
+//! - Listing output devices (projector HDMI audio, local speakers, USB
+//!   headsets)
+//! - Choosing a device per purpose (`audio_outputs` config key): alert
+//!   chimes and background music can go to different outputs
+//! - Opening the output for a purpose, falling back to the system default
+//!   when the chosen device is gone (e.g. the projector is off)
+//! - Playing alert chimes on the alerts output, ducking background music
+//!   meanwhile
+//! - The volume safety limit (`volume_safety` config key): a maximum gain
+//!   and a ramp-in applied to every sound the backend plays, so a bell
+//!   through a PA system never starts at full volume
+//!
+//! Devices are identified by their name, which is what the OS shows and
+//! stays the same across restarts.
+
+use crate::audio_supervisor;
+use crate::background_audio;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::state::AppState;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::source::SineWave;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CONFIG_KEY: &str = "audio_outputs";
+const SAFETY_KEY: &str = "volume_safety";
+
+pub const MIN_MAX_DB: f32 = -40.0;
+pub const MAX_RAMP_MS: u64 = 5_000;
+
+/// Notes of the built-in chime (Hz, duration)
+const CHIME: &[(f32, u64)] = &[(880.0, 180), (660.0, 320)];
+const CHIME_VOLUME: f32 = 0.35;
+/// Name of the chime thread in `AppState`'s audio streams
+const CHIME_STREAM: &str = "alert-chime";
+/// How often a playing chime checks whether it should stop
+const CHIME_POLL: Duration = Duration::from_millis(50);
+/// Longest alert file played; longer files are cut
+const MAX_ALERT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioPurpose {
+    Alerts,
+    Music,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevice {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// Chosen device per purpose; `None` follows the system default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutputRouting {
+    pub alerts: Option<String>,
+    pub music: Option<String>,
+}
+
+impl OutputRouting {
+    fn device_for(&self, purpose: AudioPurpose) -> Option<&str> {
+        match purpose {
+            AudioPurpose::Alerts => self.alerts.as_deref(),
+            AudioPurpose::Music => self.music.as_deref(),
+        }
+    }
+}
+
+/// Limit applied to every played sound
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VolumeSafety {
+    /// Maximum output gain in dB relative to full scale (0 = no limit)
+    pub max_db: f32,
+    /// Every sound fades in over at least this long
+    pub ramp_ms: u64,
+}
+
+impl Default for VolumeSafety {
+    fn default() -> Self {
+        Self {
+            max_db: -6.0,
+            ramp_ms: 300,
+        }
+    }
+}
+
+impl VolumeSafety {
+    /// `max_db` as a linear gain
+    pub fn max_gain(&self) -> f32 {
+        10f32.powf(self.max_db / 20.0)
+    }
+
+    /// Cap a linear volume at the maximum gain
+    pub fn limit(&self, volume: f32) -> f32 {
+        volume.clamp(0.0, self.max_gain())
+    }
+
+    pub fn ramp(&self) -> Duration {
+        Duration::from_millis(self.ramp_ms)
+    }
+}
+
+fn output_error(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::audio::OUTPUT_UNAVAILABLE, message).with_details(details.to_string())
+}
+
+/// Output devices currently available
+pub fn list_audio_output_devices() -> Result<Vec<OutputDevice>, BackendError> {
+    let host = rodio::cpal::default_host();
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+    let devices = host
+        .output_devices()
+        .map_err(|e| output_error("Failed to list audio outputs", e))?;
+    let mut list: Vec<OutputDevice> = devices
+        .filter_map(|d| d.name().ok())
+        .map(|name| OutputDevice {
+            id: name.clone(),
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+        })
+        .collect();
+    list.dedup_by(|a, b| a.id == b.id);
+    Ok(list)
+}
+
+/// Current device choice per purpose
+pub fn get_output_routing() -> OutputRouting {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Send a purpose to a device (`None`: system default)
+///
+/// Music already playing keeps its device until it is started again.
+pub fn set_output_device(
+    purpose: AudioPurpose,
+    device_id: Option<String>,
+) -> Result<OutputRouting, BackendError> {
+    if let Some(id) = device_id.as_deref() {
+        if !list_audio_output_devices()?.iter().any(|d| d.id == id) {
+            return Err(output_error("Audio output not found", id));
+        }
+    }
+    let mut routing = get_output_routing();
+    match purpose {
+        AudioPurpose::Alerts => routing.alerts = device_id,
+        AudioPurpose::Music => routing.music = device_id,
+    }
+    let value = serde_json::to_value(&routing)
+        .map_err(|e| output_error("Failed to serialize routing", e))?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(routing)
+}
+
+/// Current volume safety limit
+///
+/// Read from the config cache on every call, so a change (or a reset or
+/// preset import) applies to music already playing.
+pub fn get_volume_safety() -> VolumeSafety {
+    file_ops::load_config(SAFETY_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Set the maximum gain (dB, -40 to 0) and ramp-in (ms, up to 5000)
+///
+/// Applies immediately, including to music already playing.
+pub fn set_volume_safety(max_db: f32, ramp_ms: u64) -> Result<VolumeSafety, BackendError> {
+    if !(MIN_MAX_DB..=0.0).contains(&max_db) || ramp_ms > MAX_RAMP_MS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "Maximum must be {} to 0 dB and ramp at most {} ms",
+                MIN_MAX_DB, MAX_RAMP_MS
+            ),
+        ));
+    }
+    let safety = VolumeSafety { max_db, ramp_ms };
+    let value = serde_json::to_value(safety)
+        .map_err(|e| output_error("Failed to serialize volume safety", e))?;
+    file_ops::save_config(SAFETY_KEY, value)?;
+    Ok(safety)
+}
+
+/// Open the output for a purpose
+pub fn open_output(
+    purpose: AudioPurpose,
+) -> Result<(OutputStream, OutputStreamHandle), BackendError> {
+    let routing = get_output_routing();
+    if let Some(id) = routing.device_for(purpose) {
+        let chosen = rodio::cpal::default_host()
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == id)));
+        if let Some(device) = chosen {
+            if let Ok(output) = OutputStream::try_from_device(&device) {
+                return Ok(output);
+            }
+        }
+        // Unplugged or busy: better the default than silence
+    }
+    OutputStream::try_default().map_err(|e| output_error("No audio output device", e))
+}
+
+/// Total length of the built-in chime
+fn chime_duration() -> Duration {
+    Duration::from_millis(CHIME.iter().map(|(_, ms)| ms).sum())
+}
+
+fn decode_alert(path: &str) -> Result<Decoder<BufReader<File>>, BackendError> {
+    Decoder::new(BufReader::new(File::open(path)?)).map_err(|e| {
+        BackendError::new(errors::audio::DECODE_FAILED, "Unsupported audio file")
+            .with_details(e.to_string())
+    })
+}
+
+/// Play one alert to the end, or until `stop` is set
+fn play_alert(path: Option<&str>, stop: &AtomicBool) -> Result<(), String> {
+    let (_stream, handle) = open_output(AudioPurpose::Alerts).map_err(|e| e.message)?;
+    let sink = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+    let safety = get_volume_safety();
+    sink.set_volume(safety.limit(1.0));
+    match path {
+        Some(path) => {
+            let file = decode_alert(path).map_err(|e| e.message)?;
+            sink.append(file.take_duration(MAX_ALERT).fade_in(safety.ramp()));
+        }
+        None => {
+            for (i, &(frequency, ms)) in CHIME.iter().enumerate() {
+                let note = SineWave::new(frequency)
+                    .take_duration(Duration::from_millis(ms))
+                    .amplify(CHIME_VOLUME);
+                // Only the first note ramps in; the chime is one sound
+                let ramp = if i == 0 {
+                    safety.ramp()
+                } else {
+                    Duration::ZERO
+                };
+                sink.append(note.fade_in(ramp));
+            }
+        }
+    }
+    while !sink.empty() && !stop.load(Ordering::SeqCst) {
+        std::thread::sleep(CHIME_POLL);
+    }
+    Ok(())
+}
+
+/// Play an alert on the alerts output: `path` (cut at 10 s) or the
+/// built-in chime
+///
+/// Plays on a thread supervised by `audio_supervisor`, which retries when
+/// the output fails; a new alert replaces one still sounding.
+pub fn play_alert_chime(app: &AppHandle, path: Option<&str>) -> Result<(), BackendError> {
+    let length = match path {
+        Some(path) => decode_alert(path)?.total_duration(),
+        None => None,
+    }
+    .unwrap_or_else(chime_duration)
+    .min(MAX_ALERT);
+    let _ = background_audio::duck_background_audio(app, length.as_millis() as u64);
+
+    // The stream must live on the thread that waits for the sound to end
+    let path = path.map(str::to_string);
+    let handle = audio_supervisor::start_stream(app, CHIME_STREAM, move |stop| {
+        play_alert(path.as_deref(), stop)
+    });
+    app.state::<AppState>()
+        .register_audio_stream(CHIME_STREAM, handle);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routing_per_purpose() {
+        let routing: OutputRouting =
+            serde_json::from_value(serde_json::json!({ "alerts": "Speakers (Realtek)" })).unwrap();
+        assert_eq!(
+            routing.device_for(AudioPurpose::Alerts),
+            Some("Speakers (Realtek)")
+        );
+        assert_eq!(routing.device_for(AudioPurpose::Music), None);
+    }
+
+    #[test]
+    fn test_volume_safety_limit() {
+        let safety = VolumeSafety {
+            max_db: -6.0,
+            ramp_ms: 300,
+        };
+        assert!((safety.max_gain() - 0.501).abs() < 0.001);
+        assert!((safety.limit(1.0) - 0.501).abs() < 0.001);
+        assert_eq!(safety.limit(0.2), 0.2);
+        assert_eq!(safety.limit(-1.0), 0.0);
+        let unlimited = VolumeSafety {
+            max_db: 0.0,
+            ..safety
+        };
+        assert_eq!(unlimited.limit(1.0), 1.0);
+        assert!(set_volume_safety(3.0, 0).is_err());
+        assert!(set_volume_safety(-6.0, 60_000).is_err());
+    }
+
+    #[test]
+    fn test_chime_fits_alert_duck() {
+        assert!(chime_duration() <= background_audio::ALERT_DUCK);
+    }
+}
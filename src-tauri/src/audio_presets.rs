@@ -0,0 +1,192 @@
+//! Audio presets: thresholds and calibration shared between classrooms
+//!
+//! Handles:
+//! - Exporting the audio-related config keys (light thresholds, noise meter
+//!   calibration and gain compensation, volume safety, output routing) to
+//!   a JSON file
+//! - Importing such a file: every value is validated against its config
+//!   schema before anything is written, so a bad preset changes nothing
+//!
+//! A department calibrates one classroom and hands the file to colleagues
+//! with the same hardware. Keys missing from a preset are left untouched.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops::{self, config_schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+const FORMAT: &str = "classroom-audio-preset";
+const VERSION: u32 = 1;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Config keys a preset carries
+pub const PRESET_KEYS: &[&str] = &[
+    "classroom_state_rules",
+    "noise_calibration",
+    "agc",
+    "volume_safety",
+    "audio_outputs",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioPreset {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub exported_at: u64,
+    /// Config key → value
+    pub settings: Map<String, Value>,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetImportResult {
+    pub name: Option<String>,
+    pub applied: Vec<String>,
+    /// Keys in the file this version doesn't know, left out
+    pub ignored: Vec<String>,
+}
+
+fn invalid_preset(message: &str, details: impl Into<String>) -> BackendError {
+    BackendError::new(errors::file::INVALID_FORMAT, message).with_details(details)
+}
+
+/// Collect the current values of the preset keys (unset keys are left out)
+fn current_preset(name: Option<String>) -> Result<AudioPreset, BackendError> {
+    let mut settings = Map::new();
+    for key in PRESET_KEYS {
+        let value = file_ops::load_config(key)?;
+        if !value.is_null() {
+            settings.insert(key.to_string(), value);
+        }
+    }
+    Ok(AudioPreset {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        name,
+        exported_at: clock::now_millis(),
+        settings,
+    })
+}
+
+/// Check a preset; returns the keys to apply and the ones to ignore
+fn check_preset(preset: &AudioPreset) -> Result<(Vec<String>, Vec<String>), BackendError> {
+    if preset.format != FORMAT {
+        return Err(invalid_preset(
+            "Not an audio preset file",
+            preset.format.clone(),
+        ));
+    }
+    if preset.version > VERSION {
+        return Err(invalid_preset(
+            "Preset was made by a newer version of the app",
+            preset.version.to_string(),
+        ));
+    }
+    let (known, ignored): (Vec<String>, Vec<String>) = preset
+        .settings
+        .keys()
+        .cloned()
+        .partition(|key| PRESET_KEYS.contains(&key.as_str()));
+    for key in &known {
+        config_schema::validate(key, &preset.settings[key])?;
+    }
+    if known.is_empty() {
+        return Err(invalid_preset("Preset contains no settings", ""));
+    }
+    Ok((known, ignored))
+}
+
+/// Write the current audio settings to `path`
+pub fn export_audio_presets(
+    path: &Path,
+    name: Option<String>,
+) -> Result<AudioPreset, BackendError> {
+    let preset = current_preset(name)?;
+    let json = serde_json::to_string_pretty(&preset).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize preset")
+            .with_details(e.to_string())
+    })?;
+    std::fs::write(path, json)?;
+    Ok(preset)
+}
+
+/// Apply the settings in the preset file at `path`
+pub fn import_audio_presets(path: &Path) -> Result<PresetImportResult, BackendError> {
+    if std::fs::metadata(path)?.len() > MAX_FILE_BYTES {
+        return Err(invalid_preset(
+            "Preset file is too large",
+            path.to_string_lossy(),
+        ));
+    }
+    let content = std::fs::read_to_string(path)?;
+    let preset: AudioPreset = serde_json::from_str(&content)
+        .map_err(|e| invalid_preset("Not an audio preset file", e.to_string()))?;
+    let (applied, ignored) = check_preset(&preset)?;
+    for key in &applied {
+        file_ops::save_config(key, preset.settings[key].clone())?;
+    }
+    Ok(PresetImportResult {
+        name: preset.name,
+        applied,
+        ignored,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn preset(settings: Value) -> AudioPreset {
+        AudioPreset {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            name: Some("Lab 2".into()),
+            exported_at: 0,
+            settings: settings.as_object().unwrap().clone(),
+        }
+    }
+
+    #[test]
+    fn test_check_preset() {
+        let good = preset(json!({
+            "classroom_state_rules": { "yellowAbove": 55, "redAbove": 72 },
+            "volume_safety": { "maxDb": -10, "rampMs": 500 },
+            "theme": "dark"
+        }));
+        let (applied, ignored) = check_preset(&good).unwrap();
+        assert_eq!(applied, ["classroom_state_rules", "volume_safety"]);
+        assert_eq!(ignored, ["theme"]);
+
+        let bad_value = preset(json!({ "volume_safety": { "maxDb": 12 } }));
+        assert_eq!(
+            check_preset(&bad_value).unwrap_err().code,
+            errors::config::VALIDATION_FAILED
+        );
+
+        let newer = AudioPreset {
+            version: VERSION + 1,
+            ..good.clone()
+        };
+        assert!(check_preset(&newer).is_err());
+        assert!(check_preset(&preset(json!({ "theme": "dark" }))).is_err());
+    }
+
+    #[test]
+    fn test_preset_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lab.json");
+        let original = preset(json!({ "noise_calibration": { "offsetDb": -3.5, "gain": 1.2 } }));
+        std::fs::write(&path, serde_json::to_string(&original).unwrap()).unwrap();
+        let parsed: AudioPreset =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed, original);
+        assert!(check_preset(&parsed).is_ok());
+    }
+}
@@ -0,0 +1,293 @@
+//! Supervision of native audio stream threads
+//!
+//! Handles:
+//! - Running a capture/playback stream task on its own thread
+//! - Catching panics and errors from the task (driver hiccups, unplugged
+//!   devices) instead of letting monitoring die silently
+//! - Emitting `audio-stream-error` and restarting with exponential backoff,
+//!   up to the limit in the `audio_restart_policy` config key
+//! - Counting buffer underruns reported by stream tasks
+//!
+//! The background music player and alert chimes run through
+//! `start_stream`; their handles live in `AppState`, which stops them at
+//! shutdown.
+//!
+//! A task returning `Ok(())` means it stopped on request (or finished) and
+//! is not restarted. A stream that ran for a while before failing starts over with
+//! a fresh retry budget, so one glitch a day never exhausts it.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted when a stream fails
+pub const STREAM_ERROR_EVENT: &str = "audio-stream-error";
+
+const POLICY_CONFIG_KEY: &str = "audio_restart_policy";
+
+/// A run this long counts as healthy and resets the retry count
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// Granularity of stop checks while backing off
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Buffer underruns reported by stream tasks, per stream name
+static UNDERRUNS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// When and how often a failed stream is restarted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartPolicy {
+    /// Restarts after consecutive failures; 0 disables restarting
+    pub max_restarts: u32,
+    /// Delay before the first restart, doubled on each further failure
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before restart number `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(20);
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// Payload of `audio-stream-error`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamErrorEvent {
+    /// Stream name, e.g. "microphone"
+    pub stream: String,
+    pub message: String,
+    /// Whether the failure was a panic rather than a returned error
+    pub panicked: bool,
+    /// Consecutive failures so far
+    pub attempt: u32,
+    /// Delay before the restart; `None` when giving up
+    pub retry_in_ms: Option<u64>,
+}
+
+/// Handle to a supervised stream
+pub struct StreamHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl StreamHandle {
+    /// Ask the task to stop and wait for the supervisor to exit
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.thread.join();
+    }
+
+    /// Whether the supervisor has exited (stopped or gave up)
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+/// Record a buffer underrun (dropped or late samples) of a stream
+pub fn record_underrun(stream: &str) {
+    *UNDERRUNS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(stream.to_string())
+        .or_default() += 1;
+}
+
+/// Buffer underruns per stream since startup
+pub fn underrun_counts() -> BTreeMap<String, u64> {
+    UNDERRUNS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Load the restart policy, falling back to defaults
+pub fn load_policy() -> RestartPolicy {
+    file_ops::load_config(POLICY_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Save the restart policy (applies to streams started afterwards)
+pub fn save_policy(policy: &RestartPolicy) -> Result<(), BackendError> {
+    let value = serde_json::to_value(policy).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid restart policy")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(POLICY_CONFIG_KEY, value)
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "audio thread panicked".to_string())
+}
+
+/// Run `task` on a supervised thread
+///
+/// The task gets a stop flag to poll and should return `Ok(())` once it is
+/// set. `on_error` is called for every failure, before backing off.
+pub fn supervise<F, E>(name: &str, policy: RestartPolicy, mut task: F, on_error: E) -> StreamHandle
+where
+    F: FnMut(&AtomicBool) -> Result<(), String> + Send + 'static,
+    E: Fn(&StreamErrorEvent) + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    let name = name.to_string();
+
+    let thread = thread::spawn(move || {
+        let mut attempt = 0u32;
+        while !flag.load(Ordering::SeqCst) {
+            let started = Instant::now();
+            let (message, panicked) = match panic::catch_unwind(AssertUnwindSafe(|| task(&flag))) {
+                Ok(Ok(())) => break,
+                Ok(Err(message)) => (message, false),
+                Err(payload) => (panic_message(payload.as_ref()), true),
+            };
+            if started.elapsed() >= STABLE_RUN {
+                attempt = 0;
+            }
+            attempt += 1;
+
+            let retry = (attempt <= policy.max_restarts).then(|| policy.backoff(attempt));
+            on_error(&StreamErrorEvent {
+                stream: name.clone(),
+                message,
+                panicked,
+                attempt,
+                retry_in_ms: retry.map(|d| d.as_millis() as u64),
+            });
+            let Some(delay) = retry else {
+                break;
+            };
+
+            let resume_at = Instant::now() + delay;
+            while Instant::now() < resume_at && !flag.load(Ordering::SeqCst) {
+                thread::sleep(STOP_POLL.min(resume_at - Instant::now()));
+            }
+        }
+    });
+
+    StreamHandle { stop, thread }
+}
+
+/// Supervise `task` with the configured policy, emitting errors to the UI
+pub fn start_stream<F>(app: &AppHandle, name: &str, task: F) -> StreamHandle
+where
+    F: FnMut(&AtomicBool) -> Result<(), String> + Send + 'static,
+{
+    let app = app.clone();
+    supervise(name, load_policy(), task, move |event| {
+        let _ = app.emit(STREAM_ERROR_EVENT, event);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Mutex;
+
+    fn fast_policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 4,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_millis(2_000));
+        assert_eq!(policy.backoff(10), Duration::from_millis(10_000));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn test_panicking_task_restarts_until_limit() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (r, e) = (runs.clone(), events.clone());
+
+        let handle = supervise(
+            "microphone",
+            fast_policy(2),
+            move |_| {
+                r.fetch_add(1, Ordering::SeqCst);
+                panic!("driver hiccup");
+            },
+            move |event| e.lock().unwrap().push(event.clone()),
+        );
+        handle.thread.join().unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(events[0].panicked);
+        assert_eq!(events[0].message, "driver hiccup");
+        assert_eq!(events[1].retry_in_ms, Some(2));
+        assert_eq!(events[2].retry_in_ms, None);
+    }
+
+    #[test]
+    fn test_task_recovers_after_error() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let r = runs.clone();
+
+        let handle = supervise(
+            "microphone",
+            fast_policy(3),
+            move |_| match r.fetch_add(1, Ordering::SeqCst) {
+                0 => Err("device unplugged".to_string()),
+                _ => Ok(()),
+            },
+            |_| {},
+        );
+        handle.thread.join().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_stop_ends_running_task() {
+        let handle = supervise(
+            "microphone",
+            fast_policy(0),
+            |stop| {
+                while !stop.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Ok(())
+            },
+            |_| panic!("no errors expected"),
+        );
+        assert!(!handle.is_finished());
+        handle.stop();
+    }
+}
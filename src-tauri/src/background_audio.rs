@@ -0,0 +1,479 @@
+//! Background music and white noise for focus time
+//!
+//! Handles:
+//! - Playing a local audio file (looped) or generated white/pink noise on
+//!   the music output (see `audio_output`), with fade in/out, within the
+//!   volume safety limit
+//! - Ducking: the volume drops while the noise alert sounds (automatic on
+//!   an escalation of the classroom light, or on request) and comes back
+//!   afterwards
+//! - Optionally stopping with a fade when the current period ends (see
+//!   `schedule`)
+//! - `background-audio-changed` to every window on start, stop and duck
+//!
+//! Playback runs on its own thread, which owns the output stream (not
+//! `Send`) and is driven over a channel. The thread is supervised by
+//! `audio_supervisor` (the `music` stream), so a panic in the driver
+//! restarts it instead of leaving the channel dead. The device is released
+//! when playback stops.
+
+use crate::audio_output::{self, AudioPurpose};
+use crate::audio_supervisor;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::schedule;
+use crate::state::AppState;
+use rodio::cpal::SampleRate;
+use rodio::{Decoder, OutputStream, Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SendError, Sender, SyncSender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted when playback starts, stops or is ducked
+pub const AUDIO_EVENT: &str = "background-audio-changed";
+/// Name of the player thread in `AppState`'s audio streams
+const STREAM_NAME: &str = "music";
+
+/// Volume factor while ducked
+const DUCK_FACTOR: f32 = 0.2;
+/// How long an automatic duck (noise alert) lasts
+pub const ALERT_DUCK: Duration = Duration::from_secs(4);
+const MAX_DUCK: Duration = Duration::from_secs(60);
+const MAX_FADE: Duration = Duration::from_secs(30);
+/// Fade used when the period ends
+const PERIOD_END_FADE: Duration = Duration::from_secs(5);
+const NOISE_SAMPLE_RATE: u32 = 48_000;
+const TICK: Duration = Duration::from_millis(50);
+
+static PLAYER: Mutex<Option<Sender<PlayerCommand>>> = Mutex::new(None);
+static STATUS: Mutex<Option<BackgroundAudioStatus>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseColor {
+    White,
+    Pink,
+}
+
+/// What to play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AudioSource {
+    /// A local mp3, ogg, flac or wav file, looped
+    File { path: String },
+    /// Generated noise (no file needed)
+    Noise { color: NoiseColor },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundAudioStatus {
+    pub playing: bool,
+    pub source: Option<AudioSource>,
+    /// Volume set by the teacher, 0 to 1 (before ducking and fades)
+    pub volume: f32,
+    pub ducked: bool,
+    /// Epoch millis of the automatic stop at the end of the period
+    pub stops_at: Option<u64>,
+}
+
+type BoxedSource = Box<dyn Source<Item = f32> + Send>;
+
+enum PlayerCommand {
+    Play {
+        source: BoxedSource,
+        status: BackgroundAudioStatus,
+        fade: Duration,
+        reply: SyncSender<Result<(), BackendError>>,
+    },
+    Stop {
+        fade: Duration,
+    },
+    Duck {
+        duration: Duration,
+    },
+}
+
+/// Linear volume ramp
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Fade {
+    fn level(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return self.to;
+        }
+        let progress =
+            (now.duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        self.from + (self.to - self.from) * progress
+    }
+
+    fn done(&self, now: Instant) -> bool {
+        now.duration_since(self.start) >= self.duration
+    }
+}
+
+/// Volume the sink plays at
+fn effective_volume(fade: &Fade, ducked: bool, now: Instant) -> f32 {
+    let level = fade.level(now);
+    if ducked {
+        level * DUCK_FACTOR
+    } else {
+        level
+    }
+}
+
+fn audio_error(code: &str, message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(code, message).with_details(details.to_string())
+}
+
+fn open_source(source: &AudioSource) -> Result<BoxedSource, BackendError> {
+    match source {
+        AudioSource::File { path } => {
+            let file = File::open(path)?;
+            let decoder = Decoder::new_looped(BufReader::new(file)).map_err(|e| {
+                audio_error(errors::audio::DECODE_FAILED, "Unsupported audio file", e)
+            })?;
+            Ok(Box::new(decoder.convert_samples::<f32>()))
+        }
+        AudioSource::Noise {
+            color: NoiseColor::White,
+        } => Ok(Box::new(rodio::source::white(SampleRate(
+            NOISE_SAMPLE_RATE,
+        )))),
+        AudioSource::Noise {
+            color: NoiseColor::Pink,
+        } => Ok(Box::new(rodio::source::pink(SampleRate(NOISE_SAMPLE_RATE)))),
+    }
+}
+
+fn validate_fade(fade_ms: Option<u64>) -> Result<Duration, BackendError> {
+    let fade = Duration::from_millis(fade_ms.unwrap_or(0));
+    if fade > MAX_FADE {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Fade must be at most {} s", MAX_FADE.as_secs()),
+        ));
+    }
+    Ok(fade)
+}
+
+/// Playback running on the player thread
+struct Playback {
+    // Dropping the stream releases the device
+    _stream: OutputStream,
+    sink: Sink,
+    fade: Fade,
+    stopping: bool,
+    duck_until: Option<Instant>,
+}
+
+struct Player {
+    app: AppHandle,
+    playback: Option<Playback>,
+}
+
+impl Player {
+    fn publish(&self, update: impl FnOnce(&mut BackgroundAudioStatus)) {
+        let status = {
+            let mut guard = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+            let status = guard.get_or_insert_with(BackgroundAudioStatus::default);
+            update(status);
+            status.clone()
+        };
+        let _ = self.app.emit(AUDIO_EVENT, status);
+    }
+
+    fn play(
+        &mut self,
+        source: BoxedSource,
+        status: BackgroundAudioStatus,
+        fade: Duration,
+    ) -> Result<(), BackendError> {
+        // Replacing a track starts from silence
+        self.playback = None;
+        let (stream, handle) = audio_output::open_output(AudioPurpose::Music)?;
+        let sink = Sink::try_new(&handle).map_err(|e| {
+            audio_error(errors::audio::OUTPUT_UNAVAILABLE, "Audio output failed", e)
+        })?;
+        let now = Instant::now();
+        let fade = Fade {
+            from: 0.0,
+            to: status.volume,
+            start: now,
+            duration: fade.max(audio_output::get_volume_safety().ramp()),
+        };
+        sink.set_volume(0.0);
+        sink.append(source);
+        self.playback = Some(Playback {
+            _stream: stream,
+            sink,
+            fade,
+            stopping: false,
+            duck_until: None,
+        });
+        self.publish(|s| *s = status);
+        Ok(())
+    }
+
+    fn stop(&mut self, fade: Duration) {
+        let now = Instant::now();
+        if let Some(playback) = self.playback.as_mut() {
+            playback.fade = Fade {
+                from: playback.fade.level(now),
+                to: 0.0,
+                start: now,
+                duration: fade,
+            };
+            playback.stopping = true;
+        }
+        self.tick(now);
+    }
+
+    fn duck(&mut self, duration: Duration) {
+        if let Some(playback) = self.playback.as_mut() {
+            playback.duck_until = Some(Instant::now() + duration);
+            self.publish(|s| s.ducked = true);
+        }
+    }
+
+    fn tick(&mut self, now: Instant) {
+        let Some(playback) = self.playback.as_mut() else {
+            return;
+        };
+        if playback.stopping && playback.fade.done(now) {
+            playback.sink.stop();
+            self.playback = None;
+            self.publish(|s| *s = BackgroundAudioStatus::default());
+            return;
+        }
+        let ducked = playback.duck_until.is_some_and(|until| now < until);
+        let volume = effective_volume(&playback.fade, ducked, now);
+        playback
+            .sink
+            .set_volume(audio_output::get_volume_safety().limit(volume));
+        if !ducked && playback.duck_until.take().is_some() {
+            self.publish(|s| s.ducked = false);
+        }
+
+        let period_over = {
+            let status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+            status
+                .as_ref()
+                .and_then(|s| s.stops_at)
+                .is_some_and(|at| clock::now_millis() >= at)
+        };
+        if period_over && !self.playback.as_ref().is_some_and(|p| p.stopping) {
+            self.publish(|s| s.stops_at = None);
+            self.stop(PERIOD_END_FADE);
+        }
+    }
+
+    fn run(mut self, commands: &Receiver<PlayerCommand>, stop: &AtomicBool) -> Result<(), String> {
+        // After a restart, whatever was playing is gone
+        if get_background_audio_status().playing {
+            self.publish(|s| *s = BackgroundAudioStatus::default());
+        }
+        while !stop.load(Ordering::SeqCst) {
+            match commands.recv_timeout(TICK) {
+                Ok(PlayerCommand::Play {
+                    source,
+                    status,
+                    fade,
+                    reply,
+                }) => {
+                    let _ = reply.send(self.play(source, status, fade));
+                }
+                Ok(PlayerCommand::Stop { fade }) => self.stop(fade),
+                Ok(PlayerCommand::Duck { duration }) => self.duck(duration),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            self.tick(Instant::now());
+        }
+        Ok(())
+    }
+}
+
+/// Start the supervised player thread
+fn start_player(app: &AppHandle) -> Sender<PlayerCommand> {
+    let (sender, receiver) = mpsc::channel();
+    let player_app = app.clone();
+    let handle = audio_supervisor::start_stream(app, STREAM_NAME, move |stop| {
+        Player {
+            app: player_app.clone(),
+            playback: None,
+        }
+        .run(&receiver, stop)
+    });
+    app.state::<AppState>()
+        .register_audio_stream(STREAM_NAME, handle);
+    sender
+}
+
+/// Send to the player thread, starting it when needed
+fn send(app: &AppHandle, command: PlayerCommand) -> Result<(), BackendError> {
+    let mut player = PLAYER.lock().unwrap_or_else(|e| e.into_inner());
+    let sender = player.get_or_insert_with(|| start_player(app));
+    let Err(SendError(command)) = sender.send(command) else {
+        return Ok(());
+    };
+    // Stopped (shutdown, end of day) or out of restarts: start over once
+    let sender = player.insert(start_player(app));
+    sender.send(command).map_err(|_| {
+        *player = None;
+        BackendError::new(errors::audio::OUTPUT_UNAVAILABLE, "Audio player stopped")
+    })
+}
+
+/// Current playback
+pub fn get_background_audio_status() -> BackgroundAudioStatus {
+    STATUS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Start playing, replacing whatever is playing
+///
+/// With `stop_at_period_end`, playback fades out when the current period
+/// ends; outside of a period it just keeps playing.
+pub fn play_background_audio(
+    app: &AppHandle,
+    source: AudioSource,
+    volume: f32,
+    fade_ms: Option<u64>,
+    stop_at_period_end: bool,
+) -> Result<BackgroundAudioStatus, BackendError> {
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Volume must be between 0 and 1",
+        ));
+    }
+    let fade = validate_fade(fade_ms)?;
+    let stream = open_source(&source)?;
+    let stops_at = stop_at_period_end
+        .then(|| schedule::get_time_remaining_in_period().current)
+        .flatten()
+        .map(|period| period.ends_at);
+    let status = BackgroundAudioStatus {
+        playing: true,
+        source: Some(source),
+        volume,
+        ducked: false,
+        stops_at,
+    };
+    let (reply, result) = mpsc::sync_channel(1);
+    send(
+        app,
+        PlayerCommand::Play {
+            source: stream,
+            status: status.clone(),
+            fade,
+            reply,
+        },
+    )?;
+    result.recv().map_err(|_| {
+        BackendError::new(errors::audio::OUTPUT_UNAVAILABLE, "Audio player stopped")
+    })??;
+    Ok(status)
+}
+
+/// Stop playing, fading out over `fade_ms`
+pub fn stop_background_audio(app: &AppHandle, fade_ms: Option<u64>) -> Result<(), BackendError> {
+    let fade = validate_fade(fade_ms)?;
+    if !get_background_audio_status().playing {
+        return Ok(());
+    }
+    send(app, PlayerCommand::Stop { fade })
+}
+
+/// Lower the volume for `duration_ms` (e.g. while an alert sounds)
+pub fn duck_background_audio(app: &AppHandle, duration_ms: u64) -> Result<(), BackendError> {
+    let duration = Duration::from_millis(duration_ms);
+    if duration > MAX_DUCK {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Duck duration must be at most {} s", MAX_DUCK.as_secs()),
+        ));
+    }
+    if !get_background_audio_status().playing {
+        return Ok(());
+    }
+    send(app, PlayerCommand::Duck { duration })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_ramps_linearly() {
+        let start = Instant::now();
+        let fade = Fade {
+            from: 0.0,
+            to: 0.8,
+            start,
+            duration: Duration::from_secs(4),
+        };
+        assert_eq!(fade.level(start), 0.0);
+        assert!((fade.level(start + Duration::from_secs(1)) - 0.2).abs() < 1e-6);
+        assert_eq!(fade.level(start + Duration::from_secs(10)), 0.8);
+        assert!(!fade.done(start + Duration::from_secs(3)));
+        assert!(fade.done(start + Duration::from_secs(4)));
+
+        let instant = Fade {
+            duration: Duration::ZERO,
+            ..fade
+        };
+        assert_eq!(instant.level(start), 0.8);
+    }
+
+    #[test]
+    fn test_ducking_scales_volume() {
+        let start = Instant::now();
+        let fade = Fade {
+            from: 0.5,
+            to: 0.5,
+            start,
+            duration: Duration::ZERO,
+        };
+        assert_eq!(effective_volume(&fade, false, start), 0.5);
+        assert!((effective_volume(&fade, true, start) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_source_shape_and_validation() {
+        let source: AudioSource =
+            serde_json::from_value(serde_json::json!({ "kind": "noise", "color": "pink" }))
+                .unwrap();
+        assert_eq!(
+            source,
+            AudioSource::Noise {
+                color: NoiseColor::Pink
+            }
+        );
+        assert!(open_source(&source).is_ok());
+        let missing = AudioSource::File {
+            path: "/nonexistent/focus.mp3".into(),
+        };
+        assert_eq!(
+            open_source(&missing).err().unwrap().code,
+            errors::file::NOT_FOUND
+        );
+        assert!(validate_fade(Some(60_000)).is_err());
+        assert_eq!(validate_fade(None).unwrap(), Duration::ZERO);
+    }
+}
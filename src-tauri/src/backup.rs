@@ -0,0 +1,505 @@
+//! Backup archives of configuration and data
+//!
+//! Handles:
+//! - Creating zip archives of `app_config.json`, all data collections, the
+//!   stored attachment files and the student photos
+//! - AES-256 encryption of archive entries when a backup passphrase is set
+//! - Validating and restoring archives (with a safety backup first)
+//!
+//! Cloud targets can only be configured and used once a passphrase is set
+//! (see `require_passphrase`), so only encrypted archives are uploaded.
+//!
+//! Archives are written to `<config dir>/backups/` first, so a backup always
+//! exists locally even when cloud upload fails (offline-first).
+
+use crate::attachments;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::photos;
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Version of the archive layout; bump when entries change incompatibly
+/// (2: attachment files, 3: student photos)
+pub const BACKUP_FORMAT_VERSION: u32 = 3;
+
+const BACKUPS_DIR: &str = "backups";
+const MANIFEST_NAME: &str = "manifest.json";
+pub(crate) const PASSPHRASE_SECRET: &str = "backup-passphrase";
+
+/// Directories archived with their files as they are, and how many levels
+/// below the directory the files sit
+/// (`photos/<class id>/<photo>`)
+const FILE_DIRS: &[(&str, usize)] = &[(attachments::STORAGE_DIR, 1), (photos::PHOTOS_DIR, 2)];
+
+/// Metadata stored inside every archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: u64,
+    pub encrypted: bool,
+    pub files: Vec<String>,
+}
+
+/// A backup archive stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size: u64,
+    pub created_at: u64,
+}
+
+/// Decoded archive entries (relative path -> content)
+#[derive(Debug, Clone)]
+pub struct BackupContents {
+    pub manifest: BackupManifest,
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+fn archive_error(message: &str, e: impl ToString) -> BackendError {
+    BackendError::new(errors::backup::ARCHIVE_ERROR, message).with_details(e.to_string())
+}
+
+/// A plain file or folder name: no separators, no `..`
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('/') && !name.contains('\\') && !name.contains("..")
+}
+
+/// Only these entries may appear in an archive (guards against zip-slip)
+fn is_allowed_entry(name: &str) -> bool {
+    if name == file_ops::CONFIG_FILENAME {
+        return true;
+    }
+    if let Some(file) = name.strip_prefix(&format!("{}/", file_ops::DATA_DIR)) {
+        return file.ends_with(".json") && is_plain_name(file);
+    }
+    FILE_DIRS.iter().any(|(dir, depth)| {
+        name.strip_prefix(&format!("{}/", dir)).is_some_and(|rest| {
+            let parts: Vec<&str> = rest.split('/').collect();
+            parts.len() == *depth && parts.iter().all(|part| is_plain_name(part))
+        })
+    })
+}
+
+/// Files `depth` levels below `dir`, as `prefix/...` entries
+fn collect_dir(
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), BackendError> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let entry = format!("{}/{}", prefix, name);
+        if depth > 1 && path.is_dir() {
+            collect_dir(&path, &entry, depth - 1, files)?;
+        } else if depth == 1 && path.is_file() && is_allowed_entry(&entry) {
+            files.push((entry, fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+/// Collect the files that make up a backup (relative path -> bytes)
+fn collect_files() -> Result<Vec<(String, Vec<u8>)>, BackendError> {
+    collect_files_in(&file_ops::get_config_dir()?)
+}
+
+fn collect_files_in(config_dir: &Path) -> Result<Vec<(String, Vec<u8>)>, BackendError> {
+    let mut files = Vec::new();
+
+    let config_path = config_dir.join(file_ops::CONFIG_FILENAME);
+    if config_path.exists() {
+        files.push((
+            file_ops::CONFIG_FILENAME.to_string(),
+            fs::read(&config_path)?,
+        ));
+    }
+
+    let data_dir = config_dir.join(file_ops::DATA_DIR);
+    if data_dir.exists() {
+        let mut entries: Vec<_> = fs::read_dir(&data_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+            .collect();
+        entries.sort();
+        for path in entries {
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                files.push((format!("{}/{}", file_ops::DATA_DIR, name), fs::read(&path)?));
+            }
+        }
+    }
+
+    for (dir, depth) in FILE_DIRS {
+        collect_dir(&config_dir.join(dir), dir, *depth, &mut files)?;
+    }
+
+    Ok(files)
+}
+
+/// Write decoded entries under `config_dir`
+fn write_files(config_dir: &Path, files: &[(String, Vec<u8>)]) -> Result<(), BackendError> {
+    for (name, content) in files {
+        let path = config_dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// Build an archive from in-memory files
+pub fn build_archive(
+    files: &[(String, Vec<u8>)],
+    passphrase: Option<&str>,
+    now: u64,
+) -> Result<Vec<u8>, BackendError> {
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now,
+        encrypted: passphrase.is_some(),
+        files: files.iter().map(|(name, _)| name.clone()).collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| archive_error("Failed to serialize backup manifest", e))?;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let plain = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The manifest stays readable so archives can be listed without the passphrase
+    writer
+        .start_file(MANIFEST_NAME, plain)
+        .and_then(|_| writer.write_all(&manifest_json).map_err(Into::into))
+        .map_err(|e| archive_error("Failed to write backup manifest", e))?;
+
+    for (name, content) in files {
+        let options = match passphrase {
+            Some(pass) => plain.with_aes_encryption(AesMode::Aes256, pass),
+            None => plain,
+        };
+        writer
+            .start_file(name.as_str(), options)
+            .and_then(|_| writer.write_all(content).map_err(Into::into))
+            .map_err(|e| archive_error("Failed to write backup entry", e))?;
+    }
+
+    let cursor = writer
+        .finish()
+        .map_err(|e| archive_error("Failed to finalize backup archive", e))?;
+    Ok(cursor.into_inner())
+}
+
+fn manifest_of(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<BackupManifest, BackendError> {
+    let mut entry = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|e| archive_error("Backup manifest missing", e))?;
+    let mut buf = Vec::new();
+    entry
+        .read_to_end(&mut buf)
+        .map_err(|e| archive_error("Failed to read backup manifest", e))?;
+    serde_json::from_slice(&buf).map_err(|e| archive_error("Invalid backup manifest", e))
+}
+
+/// Read only the manifest of an archive (no passphrase needed)
+pub fn read_manifest(bytes: &[u8]) -> Result<BackupManifest, BackendError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| archive_error("Not a valid backup archive", e))?;
+    manifest_of(&mut archive)
+}
+
+/// Read and validate an archive
+pub fn read_archive(
+    bytes: &[u8],
+    passphrase: Option<&str>,
+) -> Result<BackupContents, BackendError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| archive_error("Not a valid backup archive", e))?;
+
+    let manifest = manifest_of(&mut archive)?;
+
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(BackendError::new(
+            errors::backup::UNSUPPORTED_VERSION,
+            "Backup was created by a newer version of the app",
+        )
+        .with_details(format!("format version {}", manifest.format_version)));
+    }
+
+    let mut files = Vec::new();
+    for name in &manifest.files {
+        if !is_allowed_entry(name) {
+            return Err(archive_error("Backup contains an unexpected file", name));
+        }
+
+        let mut entry = match (manifest.encrypted, passphrase) {
+            (true, Some(pass)) => archive.by_name_decrypt(name, pass.as_bytes()),
+            (true, None) => {
+                return Err(BackendError::new(
+                    errors::backup::WRONG_PASSPHRASE,
+                    "This backup is encrypted; set the backup passphrase first",
+                ))
+            }
+            (false, _) => archive.by_name(name),
+        }
+        .map_err(|e| match e {
+            zip::result::ZipError::InvalidPassword => {
+                BackendError::new(errors::backup::WRONG_PASSPHRASE, "Wrong backup passphrase")
+            }
+            other => archive_error("Failed to read backup entry", other),
+        })?;
+
+        let mut buf = Vec::new();
+        entry.read_to_end(&mut buf).map_err(|_| {
+            // AES authentication failures surface as read errors
+            BackendError::new(errors::backup::WRONG_PASSPHRASE, "Wrong backup passphrase")
+        })?;
+        files.push((name.clone(), buf));
+    }
+
+    Ok(BackupContents { manifest, files })
+}
+
+fn backups_dir() -> Result<PathBuf, BackendError> {
+    Ok(file_ops::get_config_dir()?.join(BACKUPS_DIR))
+}
+
+/// Configured backup passphrase, if any
+pub fn passphrase() -> Result<Option<String>, BackendError> {
+    secrets::get_secret(PASSPHRASE_SECRET)
+}
+
+/// `Err(BACKUP_PASSPHRASE_REQUIRED)` unless a backup passphrase is set;
+/// archives never leave the PC unencrypted
+pub fn require_passphrase() -> Result<(), BackendError> {
+    match passphrase()? {
+        Some(pass) if !pass.is_empty() => Ok(()),
+        _ => Err(BackendError::new(
+            errors::backup::PASSPHRASE_REQUIRED,
+            "Set a backup passphrase before using cloud backup",
+        )),
+    }
+}
+
+/// Set (or clear, with an empty string) the backup passphrase
+pub fn set_passphrase(passphrase: &str) -> Result<(), BackendError> {
+    if passphrase.is_empty() {
+        secrets::delete_secret(PASSPHRASE_SECRET)
+    } else {
+        secrets::set_secret(PASSPHRASE_SECRET, passphrase)
+    }
+}
+
+/// Create a backup archive of the current state in the backups folder
+pub fn create_local_backup(label: &str) -> Result<BackupInfo, BackendError> {
+    let now = clock::now_millis();
+    let bytes = build_archive(&collect_files()?, passphrase()?.as_deref(), now)?;
+
+    let dir = backups_dir()?;
+    fs::create_dir_all(&dir)?;
+    let file_name = format!("classroom-{}-{}.zip", label, now);
+    let path = dir.join(&file_name);
+    fs::write(&path, &bytes)?;
+
+    Ok(BackupInfo {
+        file_name,
+        path: path.display().to_string(),
+        size: bytes.len() as u64,
+        created_at: now,
+    })
+}
+
+/// List archives in the backups folder, newest first
+pub fn list_local_backups() -> Result<Vec<BackupInfo>, BackendError> {
+    let dir = backups_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<BackupInfo> = fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            let file_name = path.file_name()?.to_str()?.to_string();
+            if !file_name.ends_with(".zip") {
+                return None;
+            }
+            // File names end with "-<millis>.zip"
+            let created_at = file_name
+                .trim_end_matches(".zip")
+                .rsplit('-')
+                .next()?
+                .parse()
+                .ok()?;
+            Some(BackupInfo {
+                size: e.metadata().ok()?.len(),
+                path: path.display().to_string(),
+                file_name,
+                created_at,
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Read a local backup archive by file name
+pub fn read_local_backup(file_name: &str) -> Result<Vec<u8>, BackendError> {
+    if file_name.contains('/') || file_name.contains('\\') || file_name.contains("..") {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Invalid backup file name",
+        ));
+    }
+    Ok(fs::read(backups_dir()?.join(file_name))?)
+}
+
+/// Restore an archive over the current state
+///
+/// The archive is fully decoded before anything is touched, and the current
+/// state is saved as a `pre-restore` backup first.
+pub fn restore_archive(bytes: &[u8]) -> Result<BackupManifest, BackendError> {
+    let contents = read_archive(bytes, passphrase()?.as_deref())?;
+    create_local_backup("pre-restore")?;
+
+    write_files(&file_ops::get_config_dir()?, &contents.files)?;
+    Ok(contents.manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_files() -> Vec<(String, Vec<u8>)> {
+        vec![
+            (
+                "app_config.json".to_string(),
+                br#"{"theme":"Energy"}"#.to_vec(),
+            ),
+            (
+                "data/exit_tickets.json".to_string(),
+                br#"{"sessions":[]}"#.to_vec(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_archive_roundtrip_plain() {
+        let bytes = build_archive(&sample_files(), None, 7).unwrap();
+        let contents = read_archive(&bytes, None).unwrap();
+        assert_eq!(contents.manifest.created_at, 7);
+        assert!(!contents.manifest.encrypted);
+        assert_eq!(contents.files, sample_files());
+    }
+
+    #[test]
+    fn test_archive_roundtrip_encrypted() {
+        let bytes = build_archive(&sample_files(), Some("segreto"), 7).unwrap();
+        let contents = read_archive(&bytes, Some("segreto")).unwrap();
+        assert_eq!(contents.files, sample_files());
+
+        let err = read_archive(&bytes, Some("sbagliato")).unwrap_err();
+        assert_eq!(err.code, errors::backup::WRONG_PASSPHRASE);
+        let err = read_archive(&bytes, None).unwrap_err();
+        assert_eq!(err.code, errors::backup::WRONG_PASSPHRASE);
+
+        // The manifest stays readable, so cloud sync can skip plain archives
+        assert!(read_manifest(&bytes).unwrap().encrypted);
+        let plain = build_archive(&sample_files(), None, 7).unwrap();
+        assert!(!read_manifest(&plain).unwrap().encrypted);
+    }
+
+    #[test]
+    fn test_rejects_unexpected_entries() {
+        assert!(is_allowed_entry("app_config.json"));
+        assert!(is_allowed_entry("data/rosters.json"));
+        assert!(!is_allowed_entry("data/../../evil.json"));
+        assert!(!is_allowed_entry("../app_config.json"));
+        assert!(!is_allowed_entry("data/sub/x.json"));
+        assert!(is_allowed_entry("attachments/ab12.pdf"));
+        assert!(!is_allowed_entry("attachments/../app_config.json"));
+        assert!(!is_allowed_entry("attachments/sub/ab12.pdf"));
+        assert!(is_allowed_entry("photos/3a/rossi-mario.jpg"));
+        assert!(!is_allowed_entry("photos/rossi-mario.jpg"));
+        assert!(!is_allowed_entry("photos/../3a/x.jpg"));
+
+        let files = vec![("../evil.json".to_string(), b"{}".to_vec())];
+        let bytes = build_archive(&files, None, 1).unwrap();
+        assert!(read_archive(&bytes, None).is_err());
+    }
+
+    #[test]
+    fn test_photos_roundtrip() {
+        let source = tempfile::tempdir().unwrap();
+        let class_dir = source.path().join(photos::PHOTOS_DIR).join("3a");
+        fs::create_dir_all(&class_dir).unwrap();
+        fs::write(class_dir.join("rossi-mario.jpg"), b"jpeg").unwrap();
+        // Loose files outside a class folder are not photos
+        fs::write(
+            source.path().join(photos::PHOTOS_DIR).join("stray.jpg"),
+            b"x",
+        )
+        .unwrap();
+
+        let files = collect_files_in(source.path()).unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["photos/3a/rossi-mario.jpg"]);
+
+        let bytes = build_archive(&files, None, 7).unwrap();
+        let restored = tempfile::tempdir().unwrap();
+        write_files(restored.path(), &read_archive(&bytes, None).unwrap().files).unwrap();
+        let photo = restored.path().join("photos/3a/rossi-mario.jpg");
+        assert_eq!(fs::read(photo).unwrap(), b"jpeg");
+    }
+
+    #[test]
+    fn test_attachments_roundtrip() {
+        let source = tempfile::tempdir().unwrap();
+        let storage = source.path().join(attachments::STORAGE_DIR);
+        fs::create_dir_all(source.path().join(file_ops::DATA_DIR)).unwrap();
+        fs::create_dir_all(&storage).unwrap();
+        fs::write(source.path().join("data/attachments.json"), b"{}").unwrap();
+        fs::write(storage.join("ab12.pdf"), b"%PDF").unwrap();
+        fs::write(storage.join("cd34"), b"notes").unwrap();
+
+        let files = collect_files_in(source.path()).unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "data/attachments.json",
+                "attachments/ab12.pdf",
+                "attachments/cd34"
+            ]
+        );
+
+        let bytes = build_archive(&files, Some("segreto"), 7).unwrap();
+        let contents = read_archive(&bytes, Some("segreto")).unwrap();
+        let restored = tempfile::tempdir().unwrap();
+        write_files(restored.path(), &contents.files).unwrap();
+        let stored = restored
+            .path()
+            .join(attachments::STORAGE_DIR)
+            .join("ab12.pdf");
+        assert_eq!(fs::read(stored).unwrap(), b"%PDF");
+        assert_eq!(collect_files_in(restored.path()).unwrap(), files);
+    }
+}
@@ -0,0 +1,814 @@
+//! Keyboard-wedge badge readers (USB RFID and barcode readers)
+//!
+//! Handles:
+//! - Settings (`badge_reader` config key): on/off, the prefix and suffix
+//!   the reader wraps codes in, the shortest code, the longest pause
+//!   between a reader's keystrokes, and the class cards are matched against
+//! - Watching key presses system-wide, so cards are read while another
+//!   application has focus
+//! - Telling a reader from someone typing: a reader sends the whole code a
+//!   few milliseconds per key, then its suffix (Enter by default)
+//! - Matching the code to a student (their id or school email, see
+//!   `badge_scan`), recording them present today and emitting
+//!   `badge-scanned` with `recorded: true`; `badge-unknown` otherwise
+//!
+//! Keystrokes are read system-wide, so what looks like a code could be
+//! something typed fast elsewhere: by default the reader must be set up
+//! with a prefix, events only go to the main window, and `badge-unknown`
+//! carries the code's length, never the code.
+//!
+//! Key presses are watched, never taken: the code also reaches the
+//! focused application, as it would without the listener. Per platform:
+//! - **Windows**: a low-level keyboard hook
+//! - **macOS**: a listen-only event tap, which needs the Input Monitoring
+//!   permission
+//! - **Linux**: the keyboards under `/dev/input/by-id`, readable by members
+//!   of the `input` group; a stopped listener lets go of a keyboard at its
+//!   next key press
+
+use crate::badge_scan::{self, BadgeScan};
+use crate::class_records;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::roster;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+const CONFIG_KEY: &str = "badge_reader";
+/// The only window reader events go to
+const MAIN_WINDOW: &str = "main";
+
+pub const MAX_AFFIX_CHARS: usize = 4;
+pub const MAX_CODE_LENGTH: usize = 64;
+pub const MIN_KEY_INTERVAL_MS: u64 = 10;
+pub const MAX_KEY_INTERVAL_MS: u64 = 200;
+/// Longest input kept while waiting for the suffix
+const MAX_BUFFER_CHARS: usize = 128;
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+/// A key press turned into a character ('\n' for Enter, '\t' for Tab)
+type Key = (char, Instant);
+
+/// Running listener, if any
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+
+struct Listener {
+    stop: Arc<AtomicBool>,
+    capture: capture::Capture,
+    decoder: JoinHandle<()>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BadgeReaderConfig {
+    pub enabled: bool,
+    /// Sent by the reader before the code; empty for none
+    pub prefix: String,
+    /// Sent by the reader after the code; "\n" is Enter, "\t" is Tab
+    pub suffix: String,
+    pub min_length: usize,
+    /// Longest pause between two keys of the same code
+    pub max_key_interval_ms: u64,
+    /// Class cards are matched against; every class when `None`
+    pub class_id: Option<String>,
+    /// Accept an empty prefix, for readers that can't send one
+    pub allow_without_prefix: bool,
+}
+
+impl Default for BadgeReaderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            prefix: String::new(),
+            suffix: "\n".to_string(),
+            min_length: 4,
+            max_key_interval_ms: 50,
+            class_id: None,
+            allow_without_prefix: false,
+        }
+    }
+}
+
+/// Payload of the reader's `badge-unknown`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownReaderCode {
+    pub code_length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeReaderStatus {
+    pub config: BadgeReaderConfig,
+    pub listening: bool,
+}
+
+/// Picks reader codes out of a stream of key presses
+#[derive(Debug, Clone)]
+pub struct WedgeDecoder {
+    prefix: String,
+    suffix: String,
+    min_length: usize,
+    max_interval: Duration,
+    buffer: String,
+    last: Option<Instant>,
+}
+
+impl WedgeDecoder {
+    pub fn new(config: &BadgeReaderConfig) -> Self {
+        Self {
+            prefix: config.prefix.clone(),
+            suffix: config.suffix.clone(),
+            min_length: config.min_length,
+            max_interval: Duration::from_millis(config.max_key_interval_ms),
+            buffer: String::new(),
+            last: None,
+        }
+    }
+
+    /// Take one key press; returns the code when it completes one
+    ///
+    /// A pause longer than the interval starts over, so typed text never
+    /// builds up to a code.
+    pub fn push(&mut self, c: char, at: Instant) -> Option<String> {
+        if self
+            .last
+            .is_some_and(|last| at.duration_since(last) > self.max_interval)
+        {
+            self.buffer.clear();
+        }
+        self.last = Some(at);
+        self.buffer.push(c);
+        if self.buffer.chars().count() > MAX_BUFFER_CHARS {
+            self.buffer.clear();
+            return None;
+        }
+        let body = self.buffer.strip_suffix(self.suffix.as_str())?;
+        let code = if self.prefix.is_empty() {
+            Some(body)
+        } else {
+            body.rfind(self.prefix.as_str())
+                .map(|i| &body[i + self.prefix.len()..])
+        }
+        .map(|code| code.trim().to_string())
+        .filter(|code| code.chars().count() >= self.min_length);
+        self.buffer.clear();
+        code
+    }
+}
+
+fn validate(config: &BadgeReaderConfig) -> Result<(), BackendError> {
+    let invalid = |msg: String| Err(BackendError::new(errors::system::INVALID_INPUT, msg));
+    if config.suffix.is_empty() {
+        return invalid("The reader's suffix can't be empty".to_string());
+    }
+    if config.enabled && config.prefix.is_empty() && !config.allow_without_prefix {
+        return invalid("Set the prefix the reader sends before each code".to_string());
+    }
+    if config.prefix.chars().count() > MAX_AFFIX_CHARS
+        || config.suffix.chars().count() > MAX_AFFIX_CHARS
+    {
+        return invalid(format!(
+            "Prefix and suffix are at most {} characters",
+            MAX_AFFIX_CHARS
+        ));
+    }
+    if !(1..=MAX_CODE_LENGTH).contains(&config.min_length) {
+        return invalid(format!("Minimum code length must be 1-{}", MAX_CODE_LENGTH));
+    }
+    if !(MIN_KEY_INTERVAL_MS..=MAX_KEY_INTERVAL_MS).contains(&config.max_key_interval_ms) {
+        return invalid(format!(
+            "Key interval must be {}-{} ms",
+            MIN_KEY_INTERVAL_MS, MAX_KEY_INTERVAL_MS
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_config() -> BadgeReaderConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn get_badge_reader() -> BadgeReaderStatus {
+    BadgeReaderStatus {
+        config: get_config(),
+        listening: LISTENER.lock().unwrap_or_else(|e| e.into_inner()).is_some(),
+    }
+}
+
+/// Save the settings and start or stop listening to match
+pub fn set_badge_reader(
+    app: &AppHandle,
+    config: BadgeReaderConfig,
+) -> Result<BadgeReaderStatus, BackendError> {
+    validate(&config)?;
+    if let Some(id) = &config.class_id {
+        class_records::load_class(id)?;
+    }
+    stop();
+    if config.enabled {
+        listen(app.clone(), &config)?;
+    }
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(get_badge_reader())
+}
+
+/// Start listening at launch if the reader is enabled
+pub fn start(app: AppHandle) {
+    let config = get_config();
+    if config.enabled {
+        if let Err(e) = validate(&config).and_then(|_| listen(app, &config)) {
+            eprintln!("Badge reader not started: {}", e);
+        }
+    }
+}
+
+/// Stop listening if the listener runs
+pub fn stop() {
+    let Some(listener) = LISTENER.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    listener.stop.store(true, Ordering::SeqCst);
+    listener.capture.stop();
+    let _ = listener.decoder.join();
+}
+
+fn listen(app: AppHandle, config: &BadgeReaderConfig) -> Result<(), BackendError> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (keys, received) = mpsc::channel();
+    let capture = capture::start(keys, stop.clone())?;
+    let decoder = {
+        let (decoder, class_id) = (WedgeDecoder::new(config), config.class_id.clone());
+        let stop = stop.clone();
+        std::thread::spawn(move || decode_loop(app, decoder, class_id, received, &stop))
+    };
+    *LISTENER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Listener {
+        stop,
+        capture,
+        decoder,
+    });
+    Ok(())
+}
+
+fn decode_loop(
+    app: AppHandle,
+    mut decoder: WedgeDecoder,
+    class_id: Option<String>,
+    keys: Receiver<Key>,
+    stop: &AtomicBool,
+) {
+    // Linux reading threads hold their senders until a key is pressed, so
+    // the flag is checked between keys too
+    while !stop.load(Ordering::SeqCst) {
+        match keys.recv_timeout(STOP_POLL) {
+            Ok((c, at)) => {
+                if let Some(code) = decoder.push(c, at) {
+                    report(&app, class_id.as_deref(), code);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn report(app: &AppHandle, class_id: Option<&str>, code: String) {
+    let classes = roster::list_classes().unwrap_or_default();
+    let _ = match badge_scan::find_student(&classes, class_id, &code) {
+        Some((class, student)) => {
+            let recorded = class_records::record_arrival(&class.id, &student.id)
+                .inspect_err(|e| eprintln!("Badge reader: attendance not recorded: {}", e))
+                .is_ok();
+            app.emit_to(
+                MAIN_WINDOW,
+                "badge-scanned",
+                BadgeScan {
+                    student_id: student.id.clone(),
+                    student_name: student.name.clone(),
+                    class_id: class.id.clone(),
+                    code,
+                    recorded,
+                },
+            )
+        }
+        None => app.emit_to(
+            MAIN_WINDOW,
+            "badge-unknown",
+            UnknownReaderCode {
+                code_length: code.chars().count(),
+            },
+        ),
+    };
+}
+
+/// Key capture on Linux: evdev keyboards
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod capture {
+    use super::Key;
+    use crate::errors::{self, BackendError};
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    /// `struct input_event`: a `timeval`, then type, code and value
+    const EVENT_SIZE: usize = 2 * std::mem::size_of::<std::ffi::c_long>() + 8;
+    const EV_KEY: u16 = 1;
+    const KEY_LEFTSHIFT: u16 = 42;
+    const KEY_RIGHTSHIFT: u16 = 54;
+
+    /// Reading threads end at their keyboard's next key press
+    pub struct Capture;
+
+    impl Capture {
+        pub fn stop(self) {}
+    }
+
+    /// Character of a US-layout key code; `shift` for upper case
+    pub(super) fn key_char(code: u16, shift: bool) -> Option<char> {
+        const DIGIT_ROW: &str = "1234567890";
+        const LETTER_ROWS: [(u16, &str); 3] =
+            [(16, "qwertyuiop"), (30, "asdfghjkl"), (44, "zxcvbnm")];
+        // KEY_KP7 .. KEY_KP0, with the non-digit keys in between skipped
+        const KEYPAD: [(u16, char); 10] = [
+            (71, '7'),
+            (72, '8'),
+            (73, '9'),
+            (75, '4'),
+            (76, '5'),
+            (77, '6'),
+            (79, '1'),
+            (80, '2'),
+            (81, '3'),
+            (82, '0'),
+        ];
+        match code {
+            2..=11 => DIGIT_ROW.chars().nth(usize::from(code - 2)),
+            12 => Some('-'),
+            15 => Some('\t'),
+            // KEY_ENTER, KEY_KPENTER
+            28 | 96 => Some('\n'),
+            _ => KEYPAD
+                .iter()
+                .find(|(key, _)| *key == code)
+                .map(|&(_, c)| c)
+                .or_else(|| {
+                    LETTER_ROWS.iter().find_map(|&(first, row)| {
+                        let c = row.chars().nth(usize::from(code.checked_sub(first)?))?;
+                        Some(if shift { c.to_ascii_uppercase() } else { c })
+                    })
+                }),
+        }
+    }
+
+    /// Keyboard devices (USB readers show up as keyboards)
+    fn keyboards() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir("/dev/input/by-id")
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with("-event-kbd"))
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn read_keys(mut device: File, keys: Sender<Key>, stop: Arc<AtomicBool>) {
+        let mut event = [0u8; EVENT_SIZE];
+        let mut shift = false;
+        while device.read_exact(&mut event).is_ok() && !stop.load(Ordering::SeqCst) {
+            let at = Instant::now();
+            let offset = EVENT_SIZE - 8;
+            let kind = u16::from_ne_bytes([event[offset], event[offset + 1]]);
+            let code = u16::from_ne_bytes([event[offset + 2], event[offset + 3]]);
+            let value = i32::from_ne_bytes([
+                event[offset + 4],
+                event[offset + 5],
+                event[offset + 6],
+                event[offset + 7],
+            ]);
+            if kind != EV_KEY {
+                continue;
+            }
+            if code == KEY_LEFTSHIFT || code == KEY_RIGHTSHIFT {
+                // 1 pressed, 2 repeated, 0 released
+                shift = value != 0;
+            } else if value == 1 {
+                if let Some(c) = key_char(code, shift) {
+                    if keys.send((c, at)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn start(keys: Sender<Key>, stop: Arc<AtomicBool>) -> Result<Capture, BackendError> {
+        let paths = keyboards();
+        let devices: Vec<File> = paths.iter().filter_map(|p| File::open(p).ok()).collect();
+        if devices.is_empty() {
+            let message = if paths.is_empty() {
+                "No keyboard or badge reader detected"
+            } else {
+                "Keyboards can't be read: add the user to the `input` group"
+            };
+            return Err(BackendError::new(
+                errors::permission::KEYBOARD_ACCESS_DENIED,
+                message,
+            ));
+        }
+        for device in devices {
+            let (keys, stop) = (keys.clone(), stop.clone());
+            std::thread::spawn(move || read_keys(device, keys, stop));
+        }
+        Ok(Capture)
+    }
+}
+
+/// Key capture on Windows: low-level keyboard hook
+#[cfg(target_os = "windows")]
+mod capture {
+    use super::Key;
+    use crate::errors::{self, BackendError};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+    use std::time::Instant;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+        HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_QUIT, WM_SYSKEYDOWN,
+    };
+
+    const VK_TAB: u32 = 0x09;
+    const VK_RETURN: u32 = 0x0D;
+    const VK_LSHIFT: u32 = 0xA0;
+    const VK_RSHIFT: u32 = 0xA1;
+    const VK_NUMPAD0: u32 = 0x60;
+    const VK_OEM_MINUS: u32 = 0xBD;
+
+    /// Where the hook sends key presses
+    static KEYS: Mutex<Option<Sender<Key>>> = Mutex::new(None);
+    static SHIFT: AtomicBool = AtomicBool::new(false);
+
+    pub struct Capture {
+        thread_id: u32,
+        thread: JoinHandle<()>,
+    }
+
+    impl Capture {
+        pub fn stop(self) {
+            // SAFETY: posts WM_QUIT to the hook thread's message loop
+            let _ = unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+            let _ = self.thread.join();
+            *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+
+    fn key_char(vk: u32, shift: bool) -> Option<char> {
+        match vk {
+            VK_TAB => Some('\t'),
+            VK_RETURN => Some('\n'),
+            VK_OEM_MINUS => Some('-'),
+            0x30..=0x39 => char::from_u32(vk),
+            VK_NUMPAD0..=0x69 => char::from_u32(vk - VK_NUMPAD0 + 0x30),
+            0x41..=0x5A => {
+                let c = char::from_u32(vk)?;
+                Some(if shift { c } else { c.to_ascii_lowercase() })
+            }
+            _ => None,
+        }
+    }
+
+    unsafe extern "system" fn hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 {
+            // SAFETY: for WH_KEYBOARD_LL, `lparam` points to a KBDLLHOOKSTRUCT
+            let vk = unsafe { (*(lparam.0 as *const KBDLLHOOKSTRUCT)).vkCode };
+            let message = wparam.0 as u32;
+            let down = message == WM_KEYDOWN || message == WM_SYSKEYDOWN;
+            if vk == VK_LSHIFT || vk == VK_RSHIFT {
+                SHIFT.store(down, Ordering::SeqCst);
+            } else if down {
+                if let Some(c) = key_char(vk, SHIFT.load(Ordering::SeqCst)) {
+                    if let Some(keys) = KEYS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                        let _ = keys.send((c, Instant::now()));
+                    }
+                }
+            }
+        }
+        // SAFETY: passes the event on unchanged
+        unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+    }
+
+    pub fn start(keys: Sender<Key>, _stop: Arc<AtomicBool>) -> Result<Capture, BackendError> {
+        *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = Some(keys);
+        let (started, result) = std::sync::mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            // SAFETY: the hook is removed on this thread once its message
+            // loop ends
+            unsafe {
+                let hook =
+                    match SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook), HINSTANCE::default(), 0) {
+                        Ok(hook) => hook,
+                        Err(e) => {
+                            let _ = started.send(Err(e.to_string()));
+                            return;
+                        }
+                    };
+                let _ = started.send(Ok(GetCurrentThreadId()));
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {}
+                let _ = UnhookWindowsHookEx(hook);
+            }
+        });
+        match result.recv() {
+            Ok(Ok(thread_id)) => Ok(Capture { thread_id, thread }),
+            failed => {
+                let _ = thread.join();
+                *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                Err(BackendError::new(
+                    errors::permission::KEYBOARD_ACCESS_DENIED,
+                    "Failed to watch the keyboard",
+                )
+                .with_details(format!("{:?}", failed)))
+            }
+        }
+    }
+}
+
+/// Key capture on macOS: listen-only event tap
+#[cfg(target_os = "macos")]
+mod capture {
+    use super::Key;
+    use crate::errors::{self, BackendError};
+    use std::ffi::c_void;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc::Sender;
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+    use std::time::Instant;
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT: u32 = 0;
+    const LISTEN_ONLY: u32 = 1;
+    const KEY_DOWN: u32 = 10;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+    const KEYCODE_FIELD: u32 = 9;
+    /// Return and keypad Enter
+    const RETURN_KEYS: [i64; 2] = [36, 76];
+    const TAB_KEY: i64 = 48;
+
+    type Callback = extern "C" fn(*mut c_void, u32, *mut c_void, *mut c_void) -> *mut c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightListenEventAccess() -> bool;
+        fn CGRequestListenEventAccess() -> bool;
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            events: u64,
+            callback: Callback,
+            user_info: *mut c_void,
+        ) -> *mut c_void;
+        fn CGEventTapEnable(tap: *mut c_void, enable: bool);
+        fn CGEventGetIntegerValueField(event: *mut c_void, field: u32) -> i64;
+        fn CGEventKeyboardGetUnicodeString(
+            event: *mut c_void,
+            max_len: usize,
+            actual_len: *mut usize,
+            chars: *mut u16,
+        );
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: *const c_void;
+        fn CFMachPortCreateRunLoopSource(
+            allocator: *const c_void,
+            port: *mut c_void,
+            order: isize,
+        ) -> *mut c_void;
+        fn CFRunLoopGetCurrent() -> *mut c_void;
+        fn CFRunLoopAddSource(run_loop: *mut c_void, source: *mut c_void, mode: *const c_void);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: *mut c_void);
+        fn CFRelease(object: *const c_void);
+    }
+
+    /// Where the tap sends key presses, and the tap to re-enable
+    static KEYS: Mutex<Option<Sender<Key>>> = Mutex::new(None);
+    static TAP: Mutex<usize> = Mutex::new(0);
+
+    pub struct Capture {
+        /// The tap thread's `CFRunLoopRef`
+        run_loop: usize,
+        thread: JoinHandle<()>,
+    }
+
+    impl Capture {
+        pub fn stop(self) {
+            // SAFETY: CFRunLoopStop may be called from any thread
+            unsafe { CFRunLoopStop(self.run_loop as *mut c_void) };
+            let _ = self.thread.join();
+            *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+
+    extern "C" fn on_event(
+        _proxy: *mut c_void,
+        kind: u32,
+        event: *mut c_void,
+        _user_info: *mut c_void,
+    ) -> *mut c_void {
+        if kind == TAP_DISABLED_BY_TIMEOUT {
+            let tap = *TAP.lock().unwrap_or_else(|e| e.into_inner());
+            // SAFETY: `tap` is the live tap created in `start`
+            unsafe { CGEventTapEnable(tap as *mut c_void, true) };
+            return event;
+        }
+        if kind != KEY_DOWN {
+            return event;
+        }
+        // SAFETY: `event` is the key-down event the tap was called with
+        let c = unsafe {
+            let keycode = CGEventGetIntegerValueField(event, KEYCODE_FIELD);
+            if RETURN_KEYS.contains(&keycode) {
+                Some('\n')
+            } else if keycode == TAB_KEY {
+                Some('\t')
+            } else {
+                let mut chars = [0u16; 4];
+                let mut len = 0;
+                CGEventKeyboardGetUnicodeString(event, chars.len(), &mut len, chars.as_mut_ptr());
+                char::decode_utf16(chars[..len.min(chars.len())].iter().copied())
+                    .next()
+                    .and_then(|c| c.ok())
+            }
+        };
+        if let Some(c) = c.filter(|c| !c.is_control() || *c == '\n' || *c == '\t') {
+            if let Some(keys) = KEYS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                let _ = keys.send((c, Instant::now()));
+            }
+        }
+        event
+    }
+
+    pub fn start(keys: Sender<Key>, _stop: Arc<AtomicBool>) -> Result<Capture, BackendError> {
+        // SAFETY: takes no arguments; asks for Input Monitoring the first time
+        let allowed = unsafe { CGPreflightListenEventAccess() || CGRequestListenEventAccess() };
+        if !allowed {
+            return Err(BackendError::new(
+                errors::permission::KEYBOARD_ACCESS_DENIED,
+                "Input Monitoring permission not granted",
+            )
+            .with_details("Allow it in System Settings > Privacy & Security > Input Monitoring"));
+        }
+        *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = Some(keys);
+        let (started, result) = std::sync::mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            // SAFETY: the tap and its source are released on this thread
+            // once the run loop is stopped
+            unsafe {
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    LISTEN_ONLY,
+                    1 << KEY_DOWN,
+                    on_event,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = started.send(None);
+                    return;
+                }
+                *TAP.lock().unwrap_or_else(|e| e.into_inner()) = tap as usize;
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                let _ = started.send(Some(run_loop as usize));
+                CFRunLoopRun();
+                CGEventTapEnable(tap, false);
+                *TAP.lock().unwrap_or_else(|e| e.into_inner()) = 0;
+                CFRelease(source);
+                CFRelease(tap);
+            }
+        });
+        match result.recv() {
+            Ok(Some(run_loop)) => Ok(Capture { run_loop, thread }),
+            _ => {
+                let _ = thread.join();
+                *KEYS.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                Err(BackendError::new(
+                    errors::permission::KEYBOARD_ACCESS_DENIED,
+                    "Failed to watch the keyboard",
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(decoder: &mut WedgeDecoder, text: &str, start: Instant, gap_ms: u64) -> Vec<String> {
+        text.chars()
+            .enumerate()
+            .filter_map(|(i, c)| decoder.push(c, start + Duration::from_millis(i as u64 * gap_ms)))
+            .collect()
+    }
+
+    #[test]
+    fn test_decoder_tells_reader_from_typing() {
+        let mut decoder = WedgeDecoder::new(&BadgeReaderConfig::default());
+        let start = Instant::now();
+        assert_eq!(feed(&mut decoder, "0012345678\n", start, 8), ["0012345678"]);
+        // Typed by hand
+        assert!(feed(
+            &mut decoder,
+            "0012345678\n",
+            start + Duration::from_secs(1),
+            150
+        )
+        .is_empty());
+        // Too short
+        assert!(feed(&mut decoder, "12\n", start + Duration::from_secs(5), 8).is_empty());
+
+        let config = BadgeReaderConfig {
+            prefix: "%".into(),
+            suffix: "?\n".into(),
+            ..Default::default()
+        };
+        let mut decoder = WedgeDecoder::new(&config);
+        assert_eq!(feed(&mut decoder, "%S-042?\n", start, 5), ["S-042"]);
+        assert!(feed(&mut decoder, "S-042?\n", start + Duration::from_secs(1), 5).is_empty());
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(validate(&BadgeReaderConfig::default()).is_ok());
+        let with = |f: fn(&mut BadgeReaderConfig)| {
+            let mut config = BadgeReaderConfig::default();
+            f(&mut config);
+            validate(&config)
+        };
+        assert!(with(|c| c.suffix.clear()).is_err());
+        assert!(with(|c| c.prefix = "#####".into()).is_err());
+        assert!(with(|c| c.min_length = 0).is_err());
+        assert!(with(|c| c.max_key_interval_ms = 1000).is_err());
+        // Enabling needs a prefix unless explicitly waived
+        assert!(with(|c| c.enabled = true).is_err());
+        assert!(with(|c| {
+            c.enabled = true;
+            c.prefix = "%".into();
+        })
+        .is_ok());
+        assert!(with(|c| {
+            c.enabled = true;
+            c.allow_without_prefix = true;
+        })
+        .is_ok());
+
+        let value = serde_json::to_value(BadgeReaderConfig::default()).unwrap();
+        assert_eq!(value["maxKeyIntervalMs"], 50);
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &value).is_ok());
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[test]
+    fn test_linux_key_chars() {
+        assert_eq!(capture::key_char(2, false), Some('1'));
+        assert_eq!(capture::key_char(11, false), Some('0'));
+        assert_eq!(capture::key_char(16, false), Some('q'));
+        assert_eq!(capture::key_char(30, true), Some('A'));
+        assert_eq!(capture::key_char(50, false), Some('m'));
+        assert_eq!(capture::key_char(82, false), Some('0'));
+        assert_eq!(capture::key_char(28, false), Some('\n'));
+        assert_eq!(capture::key_char(1, false), None);
+    }
+}
@@ -0,0 +1,657 @@
+//! Student ID card scanning at the classroom door
+//!
+//! Handles:
+//! - Streaming frames from the camera with `ffmpeg` (grayscale, a few per
+//!   second, kept in memory only)
+//! - Decoding Code 128 barcodes in each frame, along rows and columns and
+//!   either way round, so cards can be held sideways or upside down
+//! - QR codes (with `rqrr`) in frames without a barcode
+//! - Matching the code to a student: their id, or their school email
+//! - `badge-scanned` events `{ studentId, studentName, classId, code,
+//!   recorded: false }`, and `badge-unknown` `{ code }` for cards matching
+//!   nobody
+//!
+//! A card held in front of the camera counts once, and again only after
+//! it has been out of sight for a few seconds. Marking the student present
+//! is up to the frontend, like a tap on their name.
+//!
+//! Frames never touch the disk. Uses the camera permission and `ffmpeg`
+//! like `ambient_light`.
+
+use crate::ambient_light;
+use crate::errors::{self, BackendError};
+use crate::roster::{self, ClassData, Student};
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+const FRAME_WIDTH: u32 = 640;
+const FRAME_HEIGHT: u32 = 480;
+const FRAMES_PER_SECOND: u32 = 4;
+/// Rows (and columns) of a frame searched for a barcode
+const SCANLINES: u32 = 15;
+/// A card counts again after being out of sight this long
+pub const REPEAT_AFTER: Duration = Duration::from_secs(5);
+
+/// Darkest-to-lightest difference below which a line is blank
+const MIN_CONTRAST: u8 = 40;
+/// Light margin required before the start symbol, in modules
+const QUIET_MODULES: f64 = 5.0;
+/// Summed width error (in modules) still accepted for a symbol
+const MAX_SYMBOL_ERROR: f64 = 2.0;
+const MAX_SYMBOLS: usize = 80;
+
+/// Code 128 bar and space widths of symbols 0-105, then the first six
+/// elements of the stop symbol (its seventh is a 2-module bar)
+const PATTERNS: [[u8; 6]; 107] = [
+    [2, 1, 2, 2, 2, 2],
+    [2, 2, 2, 1, 2, 2],
+    [2, 2, 2, 2, 2, 1],
+    [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2],
+    [1, 3, 1, 2, 2, 2],
+    [1, 2, 2, 2, 1, 3],
+    [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2],
+    [2, 2, 1, 2, 1, 3],
+    [2, 2, 1, 3, 1, 2],
+    [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2],
+    [1, 2, 2, 1, 3, 2],
+    [1, 2, 2, 2, 3, 1],
+    [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2],
+    [1, 2, 3, 2, 2, 1],
+    [2, 2, 3, 2, 1, 1],
+    [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1],
+    [2, 1, 3, 2, 1, 2],
+    [2, 2, 3, 1, 1, 2],
+    [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2],
+    [3, 2, 1, 1, 2, 2],
+    [3, 2, 1, 2, 2, 1],
+    [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2],
+    [3, 2, 2, 2, 1, 1],
+    [2, 1, 2, 1, 2, 3],
+    [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1],
+    [1, 1, 1, 3, 2, 3],
+    [1, 3, 1, 1, 2, 3],
+    [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3],
+    [1, 3, 2, 1, 1, 3],
+    [1, 3, 2, 3, 1, 1],
+    [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3],
+    [2, 3, 1, 3, 1, 1],
+    [1, 1, 2, 1, 3, 3],
+    [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1],
+    [1, 1, 3, 1, 2, 3],
+    [1, 1, 3, 3, 2, 1],
+    [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1],
+    [2, 1, 1, 3, 3, 1],
+    [2, 3, 1, 1, 3, 1],
+    [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1],
+    [2, 1, 3, 1, 3, 1],
+    [3, 1, 1, 1, 2, 3],
+    [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1],
+    [3, 1, 2, 1, 1, 3],
+    [3, 1, 2, 3, 1, 1],
+    [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1],
+    [2, 2, 1, 4, 1, 1],
+    [4, 3, 1, 1, 1, 1],
+    [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2],
+    [1, 2, 1, 1, 2, 4],
+    [1, 2, 1, 4, 2, 1],
+    [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1],
+    [1, 1, 2, 2, 1, 4],
+    [1, 1, 2, 4, 1, 2],
+    [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1],
+    [1, 4, 2, 1, 1, 2],
+    [1, 4, 2, 2, 1, 1],
+    [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4],
+    [4, 1, 3, 1, 1, 1],
+    [2, 4, 1, 1, 1, 2],
+    [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2],
+    [1, 2, 1, 1, 4, 2],
+    [1, 2, 1, 2, 4, 1],
+    [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2],
+    [1, 2, 4, 2, 1, 1],
+    [4, 1, 1, 2, 1, 2],
+    [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1],
+    [2, 1, 2, 1, 4, 1],
+    [2, 1, 4, 1, 2, 1],
+    [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3],
+    [1, 1, 1, 3, 4, 1],
+    [1, 3, 1, 1, 4, 1],
+    [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1],
+    [4, 1, 1, 1, 1, 3],
+    [4, 1, 1, 3, 1, 1],
+    [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1],
+    [3, 1, 1, 1, 4, 1],
+    [4, 1, 1, 1, 3, 1],
+    [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4],
+    [2, 1, 1, 2, 3, 2],
+    [2, 3, 3, 1, 1, 1],
+];
+const START_A: usize = 103;
+const START_C: usize = 105;
+const STOP: usize = 106;
+
+/// Running scanner, if any
+static SCANNER: Mutex<Option<Running>> = Mutex::new(None);
+
+struct Running {
+    class_id: Option<String>,
+    stop: Arc<AtomicBool>,
+    ffmpeg: Child,
+    thread: JoinHandle<()>,
+}
+
+/// Payload of `badge-scanned`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeScan {
+    pub student_id: String,
+    pub student_name: String,
+    pub class_id: String,
+    /// Text read from the card
+    pub code: String,
+    /// Whether the student was recorded present (by `badge_reader`);
+    /// camera scans leave that to the frontend
+    pub recorded: bool,
+}
+
+/// Payload of `badge-unknown`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownBadge {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeScanStatus {
+    pub running: bool,
+    /// Class cards are matched against; every class when `None`
+    pub class_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+/// Run lengths of a line of pixels, light first (possibly 0 long), then
+/// alternating dark and light; empty for a blank line
+fn runs(line: &[u8]) -> Vec<u32> {
+    let (min, max) = line
+        .iter()
+        .fold((u8::MAX, u8::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if max.saturating_sub(min) < MIN_CONTRAST {
+        return Vec::new();
+    }
+    let threshold = (u16::from(min) + u16::from(max)) / 2;
+    let mut runs = vec![0];
+    let mut dark = false;
+    for &v in line {
+        let is_dark = u16::from(v) < threshold;
+        if is_dark != dark {
+            runs.push(0);
+            dark = is_dark;
+        }
+        if let Some(run) = runs.last_mut() {
+            *run += 1;
+        }
+    }
+    runs
+}
+
+/// Width of one module for six runs making up a symbol
+fn module_width(runs: &[u32]) -> f64 {
+    runs.iter().sum::<u32>() as f64 / 11.0
+}
+
+/// Symbol closest to six runs, if close enough
+fn match_symbol(runs: &[u32]) -> Option<usize> {
+    let module = module_width(runs);
+    PATTERNS
+        .iter()
+        .enumerate()
+        .map(|(symbol, pattern)| {
+            let error: f64 = runs
+                .iter()
+                .zip(pattern)
+                .map(|(&run, &width)| (run as f64 / module - f64::from(width)).abs())
+                .sum();
+            (symbol, error)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|&(_, error)| error < MAX_SYMBOL_ERROR)
+        .map(|(symbol, _)| symbol)
+}
+
+/// Symbols from a start symbol up to (not including) the stop symbol
+fn read_symbols(mut runs: &[u32]) -> Option<Vec<usize>> {
+    let mut symbols = Vec::new();
+    while symbols.len() < MAX_SYMBOLS {
+        let symbol = match_symbol(runs.get(..6)?)?;
+        if symbol == STOP {
+            let bar = *runs.get(6)? as f64 / module_width(&runs[..6]);
+            return (1.4..=2.6).contains(&bar).then_some(symbols);
+        }
+        symbols.push(symbol);
+        runs = &runs[6..];
+    }
+    None
+}
+
+/// Text of a symbol sequence (start, data, checksum), if the checksum
+/// matches
+fn decode_symbols(symbols: &[usize]) -> Option<String> {
+    let (&start, rest) = symbols.split_first()?;
+    let (&check, data) = rest.split_last()?;
+    if data.is_empty() {
+        return None;
+    }
+    let sum = start
+        + data
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (i + 1) * v)
+            .sum::<usize>();
+    if sum % 103 != check {
+        return None;
+    }
+    let mut set = match start {
+        START_A => CodeSet::A,
+        104 => CodeSet::B,
+        START_C => CodeSet::C,
+        _ => return None,
+    };
+    let mut shift = false;
+    let mut text = String::new();
+    for &v in data {
+        let current = match (set, shift) {
+            (CodeSet::A, true) => CodeSet::B,
+            (CodeSet::B, true) => CodeSet::A,
+            _ => set,
+        };
+        shift = false;
+        match (current, v) {
+            (CodeSet::C, 0..=99) => text.push_str(&format!("{:02}", v)),
+            (CodeSet::A, 0..=63) | (CodeSet::B, 0..=95) => text.push((v as u8 + 32) as char),
+            (CodeSet::A, 64..=95) => text.push((v as u8 - 64) as char),
+            (CodeSet::A | CodeSet::B, 98) => shift = true,
+            (CodeSet::A | CodeSet::B, 99) => set = CodeSet::C,
+            (CodeSet::A | CodeSet::C, 100) => set = CodeSet::B,
+            (CodeSet::B | CodeSet::C, 101) => set = CodeSet::A,
+            // FNC1-4
+            (_, 96..=102) => {}
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// Code 128 text along a line of pixels, read left to right
+fn decode_line(line: &[u8]) -> Option<String> {
+    let runs = runs(line);
+    // Dark runs are at odd indices
+    (1..runs.len().saturating_sub(6))
+        .step_by(2)
+        .filter(|&i| {
+            f64::from(runs[i - 1]) >= QUIET_MODULES * module_width(&runs[i..i + 6])
+                && matches!(match_symbol(&runs[i..i + 6]), Some(START_A..=START_C))
+        })
+        .find_map(|i| decode_symbols(&read_symbols(&runs[i..])?))
+}
+
+/// Code 128 text in a frame, looking along rows then columns, either way
+pub fn decode_code128(frame: &GrayImage) -> Option<String> {
+    let (width, height) = frame.dimensions();
+    let rows = (1..=SCANLINES).map(|k| {
+        let y = height * k / (SCANLINES + 1);
+        (0..width)
+            .map(|x| frame.get_pixel(x, y).0[0])
+            .collect::<Vec<_>>()
+    });
+    let columns = (1..=SCANLINES).map(|k| {
+        let x = width * k / (SCANLINES + 1);
+        (0..height)
+            .map(|y| frame.get_pixel(x, y).0[0])
+            .collect::<Vec<_>>()
+    });
+    rows.chain(columns).find_map(|mut line| {
+        decode_line(&line).or_else(|| {
+            line.reverse();
+            decode_line(&line)
+        })
+    })
+}
+
+/// QR code text in a frame
+pub fn decode_qr(frame: &GrayImage) -> Option<String> {
+    let (width, height) = frame.dimensions();
+    let mut prepared =
+        rqrr::PreparedImage::prepare_from_greyscale(width as usize, height as usize, |x, y| {
+            frame.get_pixel(x as u32, y as u32).0[0]
+        });
+    prepared
+        .detect_grids()
+        .into_iter()
+        .find_map(|grid| grid.decode().ok())
+        .map(|(_, text)| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Whether a sighting of `code` counts, i.e. it wasn't in sight within
+/// `REPEAT_AFTER`; records the sighting either way
+fn first_sighting(seen: &mut HashMap<String, Instant>, code: &str, now: Instant) -> bool {
+    seen.retain(|_, at| now.duration_since(*at) < REPEAT_AFTER);
+    seen.insert(code.to_string(), now).is_none()
+}
+
+/// Student whose id or school email is `code`
+pub(crate) fn find_student<'a>(
+    classes: &'a [ClassData],
+    class_id: Option<&str>,
+    code: &str,
+) -> Option<(&'a ClassData, &'a Student)> {
+    let code = code.trim();
+    classes
+        .iter()
+        .filter(|c| class_id.is_none_or(|id| c.id == id))
+        .find_map(|class| {
+            class
+                .students
+                .iter()
+                .find(|s| {
+                    s.id == code
+                        || s.email
+                            .as_deref()
+                            .is_some_and(|e| e.eq_ignore_ascii_case(code))
+                })
+                .map(|s| (class, s))
+        })
+}
+
+fn report(app: &AppHandle, class_id: Option<&str>, code: String) {
+    // Read at every card, so students added since the start are found
+    let classes = roster::list_classes().unwrap_or_default();
+    let _ = match find_student(&classes, class_id, &code) {
+        Some((class, student)) => app.emit(
+            "badge-scanned",
+            BadgeScan {
+                student_id: student.id.clone(),
+                student_name: student.name.clone(),
+                class_id: class.id.clone(),
+                code,
+                recorded: false,
+            },
+        ),
+        None => app.emit("badge-unknown", UnknownBadge { code }),
+    };
+}
+
+fn scan_loop(
+    app: AppHandle,
+    class_id: Option<String>,
+    mut frames: ChildStdout,
+    stop: Arc<AtomicBool>,
+) {
+    let mut buffer = vec![0; (FRAME_WIDTH * FRAME_HEIGHT) as usize];
+    let mut seen = HashMap::new();
+    while !stop.load(Ordering::SeqCst) && frames.read_exact(&mut buffer).is_ok() {
+        let Some(frame) = GrayImage::from_raw(FRAME_WIDTH, FRAME_HEIGHT, buffer) else {
+            break;
+        };
+        let code = decode_code128(&frame).or_else(|| decode_qr(&frame));
+        buffer = frame.into_raw();
+        if let Some(code) = code {
+            if first_sighting(&mut seen, &code, Instant::now()) {
+                report(&app, class_id.as_deref(), code);
+            }
+        }
+    }
+}
+
+pub fn get_badge_scan_status() -> BadgeScanStatus {
+    let scanner = SCANNER.lock().unwrap_or_else(|e| e.into_inner());
+    match scanner.as_ref() {
+        // ffmpeg exits at once when the camera is busy
+        Some(running) => BadgeScanStatus {
+            running: !running.thread.is_finished(),
+            class_id: running.class_id.clone(),
+        },
+        None => BadgeScanStatus {
+            running: false,
+            class_id: None,
+        },
+    }
+}
+
+/// Start reading cards from the camera, replacing a running scan
+///
+/// `class_id` limits matching to one class.
+pub fn start_badge_scan(
+    app: &AppHandle,
+    class_id: Option<String>,
+) -> Result<BadgeScanStatus, BackendError> {
+    if let Some(id) = &class_id {
+        if !roster::list_classes()?.iter().any(|c| &c.id == id) {
+            return Err(
+                BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                    .with_details(id.clone()),
+            );
+        }
+    }
+    let camera = ambient_light::require_camera()?;
+    stop_badge_scan();
+
+    let filter = format!(
+        "fps={},scale={}:{},format=gray",
+        FRAMES_PER_SECOND, FRAME_WIDTH, FRAME_HEIGHT
+    );
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error"])
+        .args(ambient_light::input_args(&camera))
+        .args(["-vf", &filter, "-f", "rawvideo", "-pix_fmt", "gray", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            BackendError::new(
+                errors::permission::CAMERA_UNAVAILABLE,
+                "ffmpeg failed to start",
+            )
+            .with_details(e.to_string())
+        })?;
+    let Some(frames) = ffmpeg.stdout.take() else {
+        let _ = ffmpeg.kill();
+        return Err(BackendError::new(
+            errors::permission::CAMERA_UNAVAILABLE,
+            "Camera stream unavailable",
+        ));
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let (app, class_id, stop) = (app.clone(), class_id.clone(), stop.clone());
+        std::thread::spawn(move || scan_loop(app, class_id, frames, stop))
+    };
+    *SCANNER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Running {
+        class_id,
+        stop,
+        ffmpeg,
+        thread,
+    });
+    Ok(get_badge_scan_status())
+}
+
+/// Stop reading cards and release the camera
+pub fn stop_badge_scan() {
+    let Some(mut running) = SCANNER.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    running.stop.store(true, Ordering::SeqCst);
+    // Ends the frame stream, which ends the thread
+    let _ = running.ffmpeg.kill();
+    let _ = running.ffmpeg.wait();
+    let _ = running.thread.join();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels of a Code 128 barcode (set B, or C for digit pairs), 3 px
+    /// per module, with 15-module quiet zones
+    fn barcode(text: &str, set_c: bool) -> Vec<u8> {
+        let mut symbols = if set_c {
+            let digits = text.as_bytes();
+            let mut symbols = vec![START_C];
+            symbols.extend(
+                digits
+                    .chunks(2)
+                    .map(|pair| ((pair[0] - b'0') * 10 + (pair[1] - b'0')) as usize),
+            );
+            symbols
+        } else {
+            let mut symbols = vec![104];
+            symbols.extend(text.bytes().map(|b| (b - 32) as usize));
+            symbols
+        };
+        let sum: usize = symbols[0]
+            + symbols[1..]
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (i + 1) * v)
+                .sum::<usize>();
+        symbols.push(sum % 103);
+
+        let mut widths: Vec<u8> = symbols.iter().flat_map(|&s| PATTERNS[s]).collect();
+        widths.extend(PATTERNS[STOP]);
+        widths.push(2);
+        let mut line = vec![230; 45];
+        for (i, width) in widths.into_iter().enumerate() {
+            let shade = if i % 2 == 0 { 20 } else { 230 };
+            line.extend(std::iter::repeat_n(shade, usize::from(width) * 3));
+        }
+        line.extend([230; 45]);
+        line
+    }
+
+    #[test]
+    fn test_decode_code128() {
+        let line = barcode("S-042", false);
+        assert_eq!(decode_line(&line).as_deref(), Some("S-042"));
+        assert_eq!(
+            decode_line(&barcode("20240917", true)).as_deref(),
+            Some("20240917")
+        );
+        // One way only: `decode_code128` tries both
+        let mut reversed = line.clone();
+        reversed.reverse();
+        assert_eq!(decode_line(&reversed), None);
+
+        // A card held sideways, on a frame with blank areas around it
+        let mut frame = GrayImage::from_pixel(60, line.len() as u32, image::Luma([230]));
+        for (y, &v) in reversed.iter().enumerate() {
+            for x in 10..50 {
+                frame.put_pixel(x, y as u32, image::Luma([v]));
+            }
+        }
+        assert_eq!(decode_code128(&frame).as_deref(), Some("S-042"));
+
+        // A misread bar fails the checksum
+        let mut damaged = line.clone();
+        let bar = damaged.iter().position(|&v| v == 20).unwrap() + 11 * 3 * 2;
+        damaged[bar..bar + 3].fill(230);
+        assert_eq!(decode_line(&damaged), None);
+        assert_eq!(decode_line(&[200; 100]), None);
+        assert_eq!(decode_qr(&frame), None);
+    }
+
+    #[test]
+    fn test_matching_and_repeats() {
+        let student = |id: &str, email: Option<&str>| Student {
+            id: id.to_string(),
+            name: format!("Student {}", id),
+            absent: false,
+            notes: None,
+            email: email.map(str::to_string),
+            accommodations: Vec::new(),
+        };
+        let class = |id: &str, students| ClassData {
+            id: id.to_string(),
+            name: id.to_string(),
+            students,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let classes = [
+            class("3A", vec![student("s1", Some("anna.rossi@scuola.it"))]),
+            class("3B", vec![student("s2", None)]),
+        ];
+        let found = |class_id, code| {
+            find_student(&classes, class_id, code).map(|(c, s)| (c.id.as_str(), s.id.as_str()))
+        };
+        assert_eq!(found(None, "s2"), Some(("3B", "s2")));
+        assert_eq!(found(None, "Anna.Rossi@scuola.it "), Some(("3A", "s1")));
+        assert_eq!(found(Some("3A"), "s2"), None);
+        assert_eq!(found(None, "s3"), None);
+
+        let mut seen = HashMap::new();
+        let start = Instant::now();
+        assert!(first_sighting(&mut seen, "s1", start));
+        assert!(!first_sighting(
+            &mut seen,
+            "s1",
+            start + Duration::from_secs(3)
+        ));
+        // Still in sight: each sighting pushes the repeat back
+        assert!(!first_sighting(
+            &mut seen,
+            "s1",
+            start + Duration::from_secs(7)
+        ));
+        assert!(first_sighting(
+            &mut seen,
+            "s2",
+            start + Duration::from_secs(7)
+        ));
+        assert!(first_sighting(
+            &mut seen,
+            "s1",
+            start + Duration::from_secs(13)
+        ));
+    }
+}
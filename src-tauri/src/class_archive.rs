@@ -0,0 +1,1047 @@
+//! Class archives: a whole class in a single zip
+//!
+//! Handles:
+//! - Exporting a class at end of term or to hand it to another teacher:
+//!   roster, attendance and behavior as CSV, the seating chart as JSON and a
+//!   printable PDF report
+//! - Previewing and importing an archive, with collision handling when the
+//!   class already exists
+//! - Replacing a class of another id (a name collision) removes everything
+//!   kept for it: grades, attachments, photos and quick notes. An archive
+//!   whose id and name match two different classes is refused
+//!
+//! `class.json` carries the full class record (ids, notes, timestamps) so an
+//! archive can be imported back without loss; the CSV files are for people.
+//! CSV files use `;` and a UTF-8 BOM so Excel opens them correctly with
+//! Italian regional settings.
+//!
+//! Imports are all-or-nothing: the archive is fully decoded and validated
+//! first, and if saving one of the stores fails the ones already written are
+//! restored.
+
+use crate::attachments::{self, AttachmentStore};
+use crate::class_records::{
+    self, AttendanceRecord, AttendanceStore, BehaviorEntry, BehaviorKind, BehaviorStore,
+    SeatingChart, SeatingStore,
+};
+use crate::clock;
+use crate::documents::pdf::TextPdf;
+use crate::errors::{self, BackendError};
+use crate::gradebook::GradeStore;
+use crate::locale::{self, DateStyle, Language};
+use crate::photos;
+use crate::quick_notes;
+use crate::roster::{self, ClassData, RosterStore, Student};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Version of the archive layout; bump when entries change incompatibly
+pub const CLASS_ARCHIVE_VERSION: u32 = 1;
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+pub const CLASS_NAME: &str = "class.json";
+pub const ROSTER_NAME: &str = "roster.csv";
+pub const ATTENDANCE_NAME: &str = "attendance.csv";
+pub const BEHAVIOR_NAME: &str = "behavior.csv";
+pub const SEATING_NAME: &str = "seating.json";
+pub const REPORT_NAME: &str = "report.pdf";
+
+/// Largest archive (and uncompressed entry) accepted for import
+const MAX_IMPORT_BYTES: u64 = 50 * 1024 * 1024;
+
+const CSV_DELIMITER: u8 = b';';
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Metadata stored inside every class archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassArchiveManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: u64,
+    pub class_id: String,
+    pub class_name: String,
+    pub student_count: usize,
+    pub files: Vec<String>,
+}
+
+/// Everything exported for one class
+#[derive(Debug, Clone)]
+pub struct ClassBundle {
+    pub class: ClassData,
+    pub attendance: Vec<AttendanceRecord>,
+    pub behavior: Vec<BehaviorEntry>,
+    pub seating: Option<SeatingChart>,
+}
+
+/// Result of an export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassArchiveInfo {
+    pub path: String,
+    pub size: u64,
+    pub manifest: ClassArchiveManifest,
+}
+
+/// Summary shown before importing an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassArchivePreview {
+    pub manifest: ClassArchiveManifest,
+    pub students: Vec<String>,
+    pub attendance_days: usize,
+    pub behavior_entries: usize,
+    pub has_seating: bool,
+    /// Existing class with the same id or name
+    pub collision: Option<ClassCollision>,
+}
+
+/// An existing class that an import would clash with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassCollision {
+    pub class_id: String,
+    pub class_name: String,
+}
+
+/// What to do when the archived class already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionStrategy {
+    /// Overwrite the existing class and its records
+    Replace,
+    /// Import as a new class with fresh ids and a distinct name
+    Copy,
+}
+
+fn archive_error(message: &str, e: impl ToString) -> BackendError {
+    BackendError::new(errors::backup::ARCHIVE_ERROR, message).with_details(e.to_string())
+}
+
+fn json<T: Serialize>(value: &T) -> Result<Vec<u8>, BackendError> {
+    serde_json::to_vec_pretty(value).map_err(|e| archive_error("Failed to serialize class data", e))
+}
+
+fn csv_document(
+    header: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result<Vec<u8>, BackendError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(CSV_DELIMITER)
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(UTF8_BOM.as_bytes().to_vec());
+    let write_error = |e: csv::Error| archive_error("Failed to write archive CSV", e);
+    writer.write_record(header).map_err(write_error)?;
+    for row in rows {
+        writer.write_record(&row).map_err(write_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| archive_error("Failed to write archive CSV", e))
+}
+
+impl ClassBundle {
+    fn student_name(&self, student_id: &str) -> &str {
+        self.class
+            .students
+            .iter()
+            .find(|s| s.id == student_id)
+            .map_or("", |s| s.name.as_str())
+    }
+
+    fn roster_csv(&self) -> Result<Vec<u8>, BackendError> {
+        csv_document(
+            &["id", "name", "absent", "notes"],
+            self.class.students.iter().map(|s| {
+                vec![
+                    s.id.clone(),
+                    s.name.clone(),
+                    s.absent.to_string(),
+                    s.notes.clone().unwrap_or_default(),
+                ]
+            }),
+        )
+    }
+
+    fn attendance_csv(&self) -> Result<Vec<u8>, BackendError> {
+        csv_document(
+            &["date", "student_id", "student_name", "status"],
+            self.attendance.iter().map(|r| {
+                vec![
+                    r.date.clone(),
+                    r.student_id.clone(),
+                    self.student_name(&r.student_id).to_string(),
+                    if r.absent { "absent" } else { "present" }.to_string(),
+                ]
+            }),
+        )
+    }
+
+    fn behavior_csv(&self) -> Result<Vec<u8>, BackendError> {
+        csv_document(
+            &[
+                "id",
+                "timestamp",
+                "date",
+                "student_id",
+                "student_name",
+                "kind",
+                "note",
+            ],
+            self.behavior.iter().map(|e| {
+                vec![
+                    e.id.clone(),
+                    e.timestamp.to_string(),
+                    class_records::date_string(e.timestamp),
+                    e.student_id.clone(),
+                    self.student_name(&e.student_id).to_string(),
+                    e.kind.as_str().to_string(),
+                    e.note.clone(),
+                ]
+            }),
+        )
+    }
+
+    /// Printable summary in the app language
+    fn report_pdf(&self, now: u64, lang: Language) -> Vec<u8> {
+        let date = |millis| locale::format_date(millis, DateStyle::Short, lang);
+        let mut pdf = TextPdf::new();
+        pdf.heading(&format!(
+            "{} {}",
+            lang.pick("Classe", "Class"),
+            self.class.name
+        ));
+        pdf.paragraph(&match lang {
+            Language::It => format!(
+                "Esportato il {} - {} studenti",
+                date(now),
+                self.class.students.len()
+            ),
+            Language::En => format!(
+                "Exported on {} - {} students",
+                date(now),
+                self.class.students.len()
+            ),
+        });
+
+        let days: std::collections::BTreeSet<&str> =
+            self.attendance.iter().map(|r| r.date.as_str()).collect();
+        pdf.subheading(lang.pick("Studenti", "Students"));
+        pdf.paragraph(&format!(
+            "{}: {}",
+            lang.pick("Giorni di presenza registrati", "Attendance days recorded"),
+            days.len()
+        ));
+        for (i, student) in self.class.students.iter().enumerate() {
+            let absences = self
+                .attendance
+                .iter()
+                .filter(|r| r.student_id == student.id && r.absent)
+                .count();
+            let count = |kind: BehaviorKind| {
+                self.behavior
+                    .iter()
+                    .filter(|e| e.student_id == student.id && e.kind == kind)
+                    .count()
+            };
+            pdf.paragraph(&format!(
+                "{}. {} - {}: {}, {}: {}, {}: {}",
+                i + 1,
+                student.name,
+                lang.pick("assenze", "absences"),
+                absences,
+                lang.pick("note positive", "positive notes"),
+                count(BehaviorKind::Positive),
+                lang.pick("note negative", "negative notes"),
+                count(BehaviorKind::Negative)
+            ));
+        }
+
+        if !self.behavior.is_empty() {
+            pdf.subheading(lang.pick("Registro comportamento", "Behavior log"));
+            for entry in &self.behavior {
+                let kind = match entry.kind {
+                    BehaviorKind::Positive => "+",
+                    BehaviorKind::Negative => "-",
+                    BehaviorKind::Note => lang.pick("nota", "note"),
+                };
+                pdf.paragraph(&format!(
+                    "{} {} ({}) {}",
+                    date(entry.timestamp),
+                    self.student_name(&entry.student_id),
+                    kind,
+                    entry.note
+                ));
+            }
+        }
+
+        if let Some(chart) = &self.seating {
+            pdf.subheading(lang.pick("Disposizione dei banchi", "Seating chart"));
+            for row in 0..chart.rows {
+                let mut seats: Vec<&class_records::Seat> =
+                    chart.seats.iter().filter(|s| s.row == row).collect();
+                seats.sort_by_key(|s| s.column);
+                let names: Vec<&str> = seats
+                    .iter()
+                    .map(|s| self.student_name(&s.student_id))
+                    .collect();
+                pdf.paragraph(&format!(
+                    "{} {}: {}",
+                    lang.pick("Fila", "Row"),
+                    row + 1,
+                    names.join(", ")
+                ));
+            }
+        }
+
+        pdf.finish()
+    }
+
+    /// Build the zip archive in memory (report in `lang`)
+    pub fn to_archive(
+        &self,
+        now: u64,
+        lang: Language,
+    ) -> Result<(Vec<u8>, ClassArchiveManifest), BackendError> {
+        let mut files: Vec<(&str, Vec<u8>)> = vec![
+            (CLASS_NAME, json(&self.class)?),
+            (ROSTER_NAME, self.roster_csv()?),
+            (ATTENDANCE_NAME, self.attendance_csv()?),
+            (BEHAVIOR_NAME, self.behavior_csv()?),
+        ];
+        if let Some(chart) = &self.seating {
+            files.push((SEATING_NAME, json(chart)?));
+        }
+        files.push((REPORT_NAME, self.report_pdf(now, lang)));
+
+        let manifest = ClassArchiveManifest {
+            format_version: CLASS_ARCHIVE_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: now,
+            class_id: self.class.id.clone(),
+            class_name: self.class.name.clone(),
+            student_count: self.class.students.len(),
+            files: files.iter().map(|(name, _)| name.to_string()).collect(),
+        };
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let manifest_json = json(&manifest)?;
+        for (name, content) in std::iter::once((MANIFEST_NAME, &manifest_json))
+            .chain(files.iter().map(|(name, content)| (*name, content)))
+        {
+            writer
+                .start_file(name, options)
+                .and_then(|_| writer.write_all(content).map_err(Into::into))
+                .map_err(|e| archive_error("Failed to write class archive entry", e))?;
+        }
+        let cursor = writer
+            .finish()
+            .map_err(|e| archive_error("Failed to finalize class archive", e))?;
+        Ok((cursor.into_inner(), manifest))
+    }
+}
+
+/// Gather a class and its records from the stores
+pub fn load_bundle(class_id: &str) -> Result<ClassBundle, BackendError> {
+    Ok(ClassBundle {
+        class: class_records::load_class(class_id)?,
+        attendance: AttendanceStore::load()?.for_class(class_id),
+        behavior: BehaviorStore::load()?.for_class(class_id),
+        seating: SeatingStore::load()?.find(class_id).cloned(),
+    })
+}
+
+/// Export a class to a zip archive at `path`
+pub fn export_class_archive(class_id: &str, path: &str) -> Result<ClassArchiveInfo, BackendError> {
+    let path = Path::new(path);
+    if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "Class archive must be a .zip file",
+        )
+        .with_details(path.display().to_string()));
+    }
+
+    let bundle = load_bundle(class_id)?;
+    let (bytes, manifest) = bundle.to_archive(clock::now_millis(), locale::app_language())?;
+    fs::write(path, &bytes).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write class archive")
+            .with_details(format!("{}: {}", path.display(), e))
+    })?;
+
+    Ok(ClassArchiveInfo {
+        path: path.display().to_string(),
+        size: bytes.len() as u64,
+        manifest,
+    })
+}
+
+/// Data rows of an archive CSV as header-name lookups
+fn csv_records(
+    name: &str,
+    content: &str,
+    columns: &[&str],
+) -> Result<Vec<Vec<String>>, BackendError> {
+    let read_error =
+        |e: csv::Error| archive_error("Invalid archive CSV", format!("{}: {}", name, e));
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(CSV_DELIMITER)
+        .flexible(true)
+        .from_reader(content.trim_start_matches(UTF8_BOM).as_bytes());
+    let header = reader.headers().map_err(read_error)?.clone();
+    let indices: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            header.iter().position(|h| h == *column).ok_or_else(|| {
+                archive_error(
+                    "Archive CSV is missing a column",
+                    format!("{}: {}", name, column),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(read_error)?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows.push(
+            indices
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default().to_string())
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+/// Decoded archive: manifest plus class data
+#[derive(Debug, Clone)]
+pub struct ArchiveContents {
+    pub manifest: ClassArchiveManifest,
+    pub bundle: ClassBundle,
+}
+
+/// Read and validate a class archive without touching any store
+pub fn read_archive(bytes: &[u8]) -> Result<ArchiveContents, BackendError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| archive_error("Not a valid class archive", e))?;
+    let mut read = |name: &str| -> Result<Option<Vec<u8>>, BackendError> {
+        let entry = match archive.by_name(name) {
+            Ok(entry) => entry,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(archive_error("Failed to read class archive entry", e)),
+        };
+        let mut buf = Vec::new();
+        entry
+            .take(MAX_IMPORT_BYTES)
+            .read_to_end(&mut buf)
+            .map_err(|e| archive_error("Failed to read class archive entry", e))?;
+        Ok(Some(buf))
+    };
+    let required = |content: Option<Vec<u8>>, name: &str| {
+        content.ok_or_else(|| archive_error("Class archive is incomplete", name))
+    };
+
+    let manifest: ClassArchiveManifest =
+        serde_json::from_slice(&required(read(MANIFEST_NAME)?, MANIFEST_NAME)?)
+            .map_err(|e| archive_error("Invalid class archive manifest", e))?;
+    if manifest.format_version > CLASS_ARCHIVE_VERSION {
+        return Err(BackendError::new(
+            errors::backup::UNSUPPORTED_VERSION,
+            "Class archive was created by a newer version of the app",
+        )
+        .with_details(format!("format version {}", manifest.format_version)));
+    }
+
+    let class: ClassData = serde_json::from_slice(&required(read(CLASS_NAME)?, CLASS_NAME)?)
+        .map_err(|e| archive_error("Invalid class data", e))?;
+    if class.name.trim().is_empty() || class.students.len() > roster::MAX_STUDENTS {
+        return Err(archive_error(
+            "Invalid class data",
+            format!("{} ({} students)", class.name, class.students.len()),
+        ));
+    }
+    let is_student = |id: &str| class.students.iter().any(|s| s.id == id);
+    let text = |content: Vec<u8>| String::from_utf8_lossy(&content).into_owned();
+
+    let mut attendance = Vec::new();
+    if let Some(content) = read(ATTENDANCE_NAME)? {
+        let rows = csv_records(
+            ATTENDANCE_NAME,
+            &text(content),
+            &["date", "student_id", "status"],
+        )?;
+        for row in rows {
+            if !is_student(&row[1]) {
+                return Err(archive_error(
+                    "Attendance refers to an unknown student",
+                    &row[1],
+                ));
+            }
+            attendance.push(AttendanceRecord {
+                class_id: class.id.clone(),
+                student_id: row[1].clone(),
+                date: row[0].clone(),
+                absent: row[2] == "absent",
+            });
+        }
+    }
+
+    let mut behavior = Vec::new();
+    if let Some(content) = read(BEHAVIOR_NAME)? {
+        let rows = csv_records(
+            BEHAVIOR_NAME,
+            &text(content),
+            &["id", "timestamp", "student_id", "kind", "note"],
+        )?;
+        for row in rows {
+            if !is_student(&row[2]) {
+                return Err(archive_error(
+                    "Behavior log refers to an unknown student",
+                    &row[2],
+                ));
+            }
+            let timestamp = row[1]
+                .parse()
+                .map_err(|_| archive_error("Invalid behavior timestamp", &row[1]))?;
+            let kind = serde_json::from_value(serde_json::Value::String(row[3].clone()))
+                .map_err(|_| archive_error("Invalid behavior kind", &row[3]))?;
+            behavior.push(BehaviorEntry {
+                id: row[0].clone(),
+                class_id: class.id.clone(),
+                student_id: row[2].clone(),
+                timestamp,
+                kind,
+                note: row[4].clone(),
+            });
+        }
+    }
+
+    let seating = match read(SEATING_NAME)? {
+        Some(content) => {
+            let chart: SeatingChart = serde_json::from_slice(&content)
+                .map_err(|e| archive_error("Invalid seating chart", e))?;
+            class_records::validate_seating(&chart, &class)?;
+            Some(SeatingChart {
+                class_id: class.id.clone(),
+                ..chart
+            })
+        }
+        None => None,
+    };
+
+    Ok(ArchiveContents {
+        manifest,
+        bundle: ClassBundle {
+            class,
+            attendance,
+            behavior,
+            seating,
+        },
+    })
+}
+
+/// All stores touched by an import
+#[derive(Debug, Clone, Default)]
+pub struct ClassStores {
+    pub roster: RosterStore,
+    pub attendance: AttendanceStore,
+    pub behavior: BehaviorStore,
+    pub seating: SeatingStore,
+    pub grades: GradeStore,
+    pub attachments: AttachmentStore,
+}
+
+impl ClassStores {
+    pub fn load() -> Result<Self, BackendError> {
+        Ok(Self {
+            roster: RosterStore::load()?,
+            attendance: AttendanceStore::load()?,
+            behavior: BehaviorStore::load()?,
+            seating: SeatingStore::load()?,
+            grades: GradeStore::load()?,
+            attachments: AttachmentStore::load()?,
+        })
+    }
+
+    /// Save every store; on failure, put back the ones already written
+    pub fn save_all(&self, previous: &ClassStores) -> Result<(), BackendError> {
+        type Step = fn(&ClassStores) -> Result<(), BackendError>;
+        let steps: [Step; 6] = [
+            |s| s.roster.save(),
+            |s| s.attendance.save(),
+            |s| s.behavior.save(),
+            |s| s.seating.save(),
+            |s| s.grades.save(),
+            |s| s.attachments.save(),
+        ];
+        for (i, step) in steps.iter().enumerate() {
+            if let Err(e) = step(self) {
+                for undo in &steps[..i] {
+                    if let Err(undo_err) = undo(previous) {
+                        eprintln!("Failed to roll back class import: {}", undo_err.message);
+                    }
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Existing class with the same id or (case-insensitive) name
+    pub fn collision(&self, class: &ClassData) -> Option<ClassCollision> {
+        self.roster
+            .find(&class.id)
+            .or_else(|| self.roster.find_by_name(&class.name))
+            .map(|c| ClassCollision {
+                class_id: c.id.clone(),
+                class_name: c.name.clone(),
+            })
+    }
+
+    /// Remove what the archive replaces; grades and attachments stay when
+    /// the archived class takes over the same id
+    fn remove_class(&mut self, class_id: &str, same_id: bool) {
+        self.roster.classes.retain(|c| c.id != class_id);
+        self.attendance.records.retain(|r| r.class_id != class_id);
+        self.behavior.entries.retain(|e| e.class_id != class_id);
+        self.seating.charts.retain(|c| c.class_id != class_id);
+        if !same_id {
+            let entity = format!("class:{}", class_id);
+            self.grades.scores.retain(|s| s.class_id != class_id);
+            self.attachments.attachments.retain(|a| a.entity != entity);
+        }
+    }
+
+    /// Add a bundle to the stores; returns the id of the imported class
+    pub fn apply(
+        &mut self,
+        bundle: ClassBundle,
+        strategy: Option<CollisionStrategy>,
+        now: u64,
+    ) -> Result<String, BackendError> {
+        let bundle = match (self.collision(&bundle.class), strategy) {
+            (None, _) => bundle,
+            (Some(existing), None) => {
+                return Err(BackendError::new(
+                    errors::roster::CLASS_EXISTS,
+                    "A class with this name already exists",
+                )
+                .with_details(existing.class_name));
+            }
+            (Some(existing), Some(CollisionStrategy::Replace)) => {
+                if let Some(other) = self
+                    .roster
+                    .find_by_name(&bundle.class.name)
+                    .filter(|c| c.id != existing.class_id)
+                {
+                    return Err(BackendError::new(
+                        errors::roster::CLASS_EXISTS,
+                        "The archived class matches two existing classes",
+                    )
+                    .with_details(format!("{}, {}", existing.class_name, other.name)));
+                }
+                let same_id = existing.class_id == bundle.class.id;
+                self.remove_class(&existing.class_id, same_id);
+                bundle
+            }
+            (Some(_), Some(CollisionStrategy::Copy)) => {
+                let name = self.unique_name(&bundle.class.name);
+                bundle.renumbered(&name, now)
+            }
+        };
+
+        let class_id = bundle.class.id.clone();
+        self.roster.classes.push(bundle.class);
+        self.attendance.records.extend(bundle.attendance);
+        self.behavior.entries.extend(bundle.behavior);
+        if let Some(chart) = bundle.seating {
+            self.seating.set(chart);
+        }
+        Ok(class_id)
+    }
+
+    /// `3A`, then `3A (2)`, `3A (3)`, ...
+    fn unique_name(&self, name: &str) -> String {
+        (2..)
+            .map(|n| format!("{} ({})", name.trim(), n))
+            .find(|candidate| self.roster.find_by_name(candidate).is_none())
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+impl ClassBundle {
+    /// Same data under a new class name with fresh ids
+    fn renumbered(self, name: &str, now: u64) -> Self {
+        let class_id = format!("class_{}", now);
+        let students: Vec<(String, Student)> = self
+            .class
+            .students
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let old = s.id.clone();
+                (
+                    old,
+                    Student {
+                        id: format!("student_{}_{}", now, i),
+                        ..s
+                    },
+                )
+            })
+            .collect();
+        let new_id = |old: &str| {
+            students
+                .iter()
+                .find(|(o, _)| o == old)
+                .map_or_else(String::new, |(_, s)| s.id.clone())
+        };
+
+        Self {
+            attendance: self
+                .attendance
+                .into_iter()
+                .map(|r| AttendanceRecord {
+                    class_id: class_id.clone(),
+                    student_id: new_id(&r.student_id),
+                    ..r
+                })
+                .collect(),
+            behavior: self
+                .behavior
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| BehaviorEntry {
+                    id: format!("behavior_{}_{}", now, i),
+                    class_id: class_id.clone(),
+                    student_id: new_id(&e.student_id),
+                    ..e
+                })
+                .collect(),
+            seating: self.seating.map(|chart| SeatingChart {
+                class_id: class_id.clone(),
+                seats: chart
+                    .seats
+                    .into_iter()
+                    .map(|seat| class_records::Seat {
+                        student_id: new_id(&seat.student_id),
+                        ..seat
+                    })
+                    .collect(),
+                ..chart
+            }),
+            class: ClassData {
+                id: class_id.clone(),
+                name: name.to_string(),
+                students: students
+                    .iter()
+                    .map(|(_, s)| Student {
+                        accommodations: s
+                            .accommodations
+                            .iter()
+                            .map(|a| a.with_student_ids(new_id))
+                            .collect(),
+                        ..s.clone()
+                    })
+                    .collect(),
+                created_at: now,
+                updated_at: now,
+            },
+        }
+    }
+}
+
+fn read_archive_file(path: &str) -> Result<Vec<u8>, BackendError> {
+    let size = fs::metadata(path)
+        .map_err(|e| {
+            BackendError::new(errors::file::NOT_FOUND, "Class archive not found")
+                .with_details(format!("{}: {}", path, e))
+        })?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(archive_error(
+            "Class archive is too large",
+            format!("{} bytes", size),
+        ));
+    }
+    fs::read(path).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to read class archive")
+            .with_details(e.to_string())
+    })
+}
+
+/// Describe an archive and whether it clashes with an existing class
+pub fn preview_class_archive(path: &str) -> Result<ClassArchivePreview, BackendError> {
+    let contents = read_archive(&read_archive_file(path)?)?;
+    let stores = ClassStores::load()?;
+    let bundle = &contents.bundle;
+    let days: std::collections::BTreeSet<&str> =
+        bundle.attendance.iter().map(|r| r.date.as_str()).collect();
+
+    Ok(ClassArchivePreview {
+        collision: stores.collision(&bundle.class),
+        students: bundle
+            .class
+            .students
+            .iter()
+            .map(|s| s.name.clone())
+            .collect(),
+        attendance_days: days.len(),
+        behavior_entries: bundle.behavior.len(),
+        has_seating: bundle.seating.is_some(),
+        manifest: contents.manifest,
+    })
+}
+
+/// Import a class archive
+///
+/// Fails with `CLASS_ALREADY_EXISTS` when the class exists and no
+/// `strategy` is given. Returns the id of the imported class.
+pub fn import_class_archive(
+    path: &str,
+    strategy: Option<CollisionStrategy>,
+) -> Result<String, BackendError> {
+    let contents = read_archive(&read_archive_file(path)?)?;
+    let previous = ClassStores::load()?;
+    let mut stores = previous.clone();
+    let class_id = stores.apply(contents.bundle, strategy, clock::now_millis())?;
+    stores.save_all(&previous)?;
+    remove_files_of_replaced(&previous, &stores);
+    Ok(class_id)
+}
+
+/// Photos, attachment files and quick notes of classes an import removed
+///
+/// Runs after the stores are saved; the import already happened, so a
+/// failure here is only logged.
+fn remove_files_of_replaced(previous: &ClassStores, stores: &ClassStores) {
+    for class in &previous.roster.classes {
+        if stores.roster.find(&class.id).is_some() {
+            continue;
+        }
+        quick_notes::forget_class(&class.id);
+        if let Err(e) = photos::remove_class_photos(&class.id) {
+            eprintln!("Failed to remove photos of {}: {}", class.id, e.message);
+        }
+    }
+    let removed: Vec<_> = previous
+        .attachments
+        .attachments
+        .iter()
+        .filter(|a| !stores.attachments.attachments.iter().any(|b| b.id == a.id))
+        .cloned()
+        .collect();
+    if let Err(e) = attachments::remove_unused_files(&stores.attachments, &removed) {
+        eprintln!("Failed to remove attachment files: {}", e.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_records::Seat;
+    use crate::roster::RosterStore;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn bundle() -> ClassBundle {
+        let mut store = RosterStore::default();
+        let id = store
+            .apply_names(
+                None,
+                "3A",
+                &["Rossi; Mario".into(), "Anna \"Nina\" Bianchi".into()],
+                1,
+            )
+            .unwrap();
+        let class = store.find(&id).unwrap().clone();
+        let mut attendance = AttendanceStore::default();
+        attendance.record_day(&class, "2026-03-02");
+        let student = class.students[0].id.clone();
+        ClassBundle {
+            attendance: attendance.records,
+            behavior: vec![BehaviorEntry {
+                id: "b1".into(),
+                class_id: id.clone(),
+                student_id: student.clone(),
+                timestamp: 1_772_000_000_000,
+                kind: BehaviorKind::Positive,
+                note: "Ottimo lavoro\nin gruppo".into(),
+            }],
+            seating: Some(SeatingChart {
+                class_id: id,
+                rows: 1,
+                columns: 2,
+                seats: vec![Seat {
+                    student_id: student,
+                    row: 0,
+                    column: 1,
+                }],
+                updated_at: 1,
+            }),
+            class,
+        }
+    }
+
+    #[test]
+    fn test_archive_contents() {
+        let (bytes, manifest) = bundle()
+            .to_archive(1_772_000_000_000, Language::It)
+            .unwrap();
+        assert_eq!(manifest.format_version, CLASS_ARCHIVE_VERSION);
+        assert_eq!(manifest.student_count, 2);
+        assert_eq!(manifest.files.len(), 6);
+
+        let mut zip = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            zip.by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+
+        let roster = read(ROSTER_NAME);
+        assert!(roster.starts_with("\u{feff}id;name;absent;notes\r\n"));
+        assert!(roster.contains("\"Rossi; Mario\""));
+        assert!(roster.contains("\"Anna \"\"Nina\"\" Bianchi\""));
+
+        assert_eq!(read(ATTENDANCE_NAME).lines().count(), 3);
+        assert!(read(BEHAVIOR_NAME).contains("positive;\"Ottimo lavoro\nin gruppo\""));
+        assert!(read(SEATING_NAME).contains("\"column\": 1"));
+        assert!(read(CLASS_NAME).contains("\"createdAt\": 1"));
+        assert!(read(MANIFEST_NAME).contains("\"class_name\": \"3A\""));
+        assert!(zip.by_name(REPORT_NAME).unwrap().size() > 0);
+    }
+
+    #[test]
+    fn test_read_archive_roundtrip() {
+        let original = bundle();
+        let (bytes, _) = original.to_archive(1, Language::It).unwrap();
+        let contents = read_archive(&bytes).unwrap();
+        assert_eq!(contents.manifest.class_name, "3A");
+        assert_eq!(contents.bundle.class, original.class);
+        assert_eq!(contents.bundle.attendance, original.attendance);
+        assert_eq!(contents.bundle.behavior, original.behavior);
+        assert_eq!(contents.bundle.seating, original.seating);
+    }
+
+    #[test]
+    fn test_read_archive_rejects_newer_version() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(MANIFEST_NAME, SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .write_all(
+                br#"{"format_version":99,"app_version":"9.0.0","exported_at":0,
+                    "class_id":"c","class_name":"3A","student_count":0,"files":[]}"#,
+            )
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let err = read_archive(&bytes).unwrap_err();
+        assert_eq!(err.code, errors::backup::UNSUPPORTED_VERSION);
+        assert!(read_archive(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_apply_collision_strategies() {
+        let archived = bundle();
+        let mut stores = ClassStores::default();
+        stores.apply(archived.clone(), None, 1).unwrap();
+
+        let err = stores.apply(archived.clone(), None, 2).unwrap_err();
+        assert_eq!(err.code, errors::roster::CLASS_EXISTS);
+
+        let id = stores
+            .apply(archived.clone(), Some(CollisionStrategy::Replace), 2)
+            .unwrap();
+        assert_eq!(id, archived.class.id);
+        assert_eq!(stores.roster.classes.len(), 1);
+        assert_eq!(stores.behavior.entries.len(), 1);
+
+        let copy_id = stores
+            .apply(archived.clone(), Some(CollisionStrategy::Copy), 3)
+            .unwrap();
+        assert_eq!(stores.roster.classes.len(), 2);
+        let copy = stores.roster.find(&copy_id).unwrap();
+        assert_eq!(copy.name, "3A (2)");
+        assert_ne!(copy.students[0].id, archived.class.students[0].id);
+        let entry = stores
+            .behavior
+            .entries
+            .iter()
+            .find(|e| e.class_id == copy_id)
+            .unwrap();
+        assert_eq!(entry.student_id, copy.students[0].id);
+        assert_eq!(
+            stores.seating.find(&copy_id).unwrap().seats[0].student_id,
+            copy.students[0].id
+        );
+        assert_eq!(stores.attendance.for_class(&copy_id).len(), 2);
+    }
+
+    #[test]
+    fn test_replace_removes_only_the_collided_class() {
+        let archived = bundle();
+        let class = |id: &str, name: &str| ClassData {
+            id: id.into(),
+            name: name.into(),
+            ..archived.class.clone()
+        };
+        let score = |class_id: &str| crate::gradebook::Score {
+            id: format!("score_{}", class_id),
+            class_id: class_id.into(),
+            student_id: "s".into(),
+            assessment: "Verifica".into(),
+            value: 7.0,
+            label: "7".into(),
+            weight: 1.0,
+            recorded_at: 1,
+        };
+        let attachment = |class_id: &str| attachments::Attachment {
+            id: format!("attachment_{}", class_id),
+            entity: format!("class:{}", class_id),
+            file_name: "scheda.pdf".into(),
+            hash: "abc".into(),
+            size: 3,
+            added_at: 1,
+        };
+        let mut stores = ClassStores::default();
+        stores.roster.classes = vec![class("old", "3a"), class("other", "3B")];
+        stores.grades.scores = vec![score("old"), score("other")];
+        stores.attachments.attachments = vec![attachment("old"), attachment("other")];
+
+        stores
+            .apply(archived.clone(), Some(CollisionStrategy::Replace), 2)
+            .unwrap();
+        let ids: Vec<&str> = stores
+            .roster
+            .classes
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, ["other", archived.class.id.as_str()]);
+        assert_eq!(stores.grades.scores, [score("other")]);
+        assert_eq!(stores.attachments.attachments, [attachment("other")]);
+
+        // Id of one class, name of another
+        let mut stores = ClassStores::default();
+        stores.roster.classes = vec![class(&archived.class.id, "3B"), class("old", "3A")];
+        let err = stores
+            .apply(archived, Some(CollisionStrategy::Replace), 2)
+            .unwrap_err();
+        assert_eq!(err.code, errors::roster::CLASS_EXISTS);
+        assert_eq!(stores.roster.classes.len(), 2);
+    }
+
+    #[test]
+    fn test_export_requires_zip_extension() {
+        let err = export_class_archive("class_1", "/tmp/classe.pdf").unwrap_err();
+        assert_eq!(err.code, errors::file::INVALID_FORMAT);
+    }
+}
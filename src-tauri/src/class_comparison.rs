@@ -0,0 +1,452 @@
+//! Cross-class comparison for the analytics view
+//!
+//! Handles:
+//! - One metric for every class over a date range, bucketed by day, week
+//!   or month: average noise level (daily histograms in `noise_history`),
+//!   attendance rate (`attendance` records) or behavior points (positive
+//!   entries minus negative ones, from the `behavior` log)
+//! - Chart-ready output: one list of bucket labels shared by all classes
+//!   and one series of values per class, `null` where nothing was recorded
+//!
+//! Noise values are in the chosen sound unit (see `sound_units`).
+//!
+//! Aggregated here so the analytics view doesn't pull every raw record
+//! over IPC. Weeks start on Monday and are labelled with that date.
+
+use crate::class_records::{self, AttendanceStore, BehaviorKind, BehaviorStore};
+use crate::errors::{self, BackendError};
+use crate::noise_history::{self, Histogram, NoiseHistoryStore};
+use crate::roster::{ClassData, RosterStore};
+use crate::sound_units::{LevelScale, SoundUnit};
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Longest range that can be compared
+const MAX_RANGE_DAYS: u64 = 366;
+
+/// What is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComparisonMetric {
+    /// Average noise level, in the chosen sound unit
+    Noise,
+    /// Share of recorded student-days present (0.0-1.0)
+    Attendance,
+    /// Positive behavior entries minus negative ones
+    Points,
+}
+
+/// Size of a bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Bucket {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+/// Dates compared, both included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonRange {
+    /// `YYYY-MM-DD`
+    pub from: String,
+    /// `YYYY-MM-DD`
+    pub to: String,
+    #[serde(default)]
+    pub bucket: Bucket,
+}
+
+/// Values of one class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassSeries {
+    pub class_id: String,
+    pub class_name: String,
+    pub students: usize,
+    /// One per label, `None` for buckets with no data
+    pub values: Vec<Option<f64>>,
+    /// The metric over the whole range
+    pub overall: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassComparison {
+    pub metric: ComparisonMetric,
+    /// Unit of the values when comparing noise
+    pub unit: Option<SoundUnit>,
+    pub bucket: Bucket,
+    /// Bucket labels: `YYYY-MM-DD` for days and weeks, `YYYY-MM` for months
+    pub labels: Vec<String>,
+    /// Classes by name
+    pub series: Vec<ClassSeries>,
+}
+
+struct Sources {
+    classes: Vec<ClassData>,
+    attendance: AttendanceStore,
+    behavior: BehaviorStore,
+    noise: NoiseHistoryStore,
+    scale: LevelScale,
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, BackendError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| {
+        BackendError::new(errors::system::INVALID_INPUT, "Date must be YYYY-MM-DD")
+            .with_details(date.to_string())
+    })
+}
+
+fn label(date: NaiveDate, bucket: Bucket) -> String {
+    match bucket {
+        Bucket::Day => date.format("%Y-%m-%d").to_string(),
+        Bucket::Week => {
+            let monday = date - Days::new(u64::from(date.weekday().num_days_from_monday()));
+            monday.format("%Y-%m-%d").to_string()
+        }
+        Bucket::Month => date.format("%Y-%m").to_string(),
+    }
+}
+
+/// Per-bucket accumulation of one class
+#[derive(Default)]
+struct Tally {
+    noise: Histogram,
+    present: u64,
+    recorded: u64,
+    points: i64,
+    entries: u64,
+}
+
+impl Tally {
+    fn value(&self, metric: ComparisonMetric, scale: &LevelScale) -> Option<f64> {
+        match metric {
+            ComparisonMetric::Noise => scale.convert_opt(self.noise.mean()),
+            ComparisonMetric::Attendance => {
+                (self.recorded > 0).then(|| self.present as f64 / self.recorded as f64)
+            }
+            ComparisonMetric::Points => (self.entries > 0).then_some(self.points as f64),
+        }
+    }
+
+    fn merge(&mut self, other: &Tally) {
+        self.noise.merge(&other.noise);
+        self.present += other.present;
+        self.recorded += other.recorded;
+        self.points += other.points;
+        self.entries += other.entries;
+    }
+}
+
+/// Tally of the bucket `date` falls in, `None` outside the range
+fn tally<'t, 'a>(
+    tallies: &'t mut BTreeMap<&'a str, Tally>,
+    days: &'a BTreeMap<String, String>,
+    date: &str,
+) -> Option<&'t mut Tally> {
+    let label = days.get(date)?;
+    Some(tallies.entry(label.as_str()).or_default())
+}
+
+fn class_series(
+    class: &ClassData,
+    metric: ComparisonMetric,
+    days: &BTreeMap<String, String>,
+    labels: &[String],
+    sources: &Sources,
+) -> ClassSeries {
+    let mut tallies: BTreeMap<&str, Tally> = BTreeMap::new();
+
+    match metric {
+        ComparisonMetric::Noise => {
+            if let Some(history) = sources.noise.classes.get(&class.id) {
+                for (date, histogram) in &history.days {
+                    if let Some(t) = tally(&mut tallies, days, date) {
+                        t.noise.merge(histogram);
+                    }
+                }
+            }
+        }
+        ComparisonMetric::Attendance => {
+            for record in sources
+                .attendance
+                .records
+                .iter()
+                .filter(|r| r.class_id == class.id)
+            {
+                if let Some(t) = tally(&mut tallies, days, &record.date) {
+                    t.recorded += 1;
+                    t.present += u64::from(!record.absent);
+                }
+            }
+        }
+        ComparisonMetric::Points => {
+            for entry in sources
+                .behavior
+                .entries
+                .iter()
+                .filter(|e| e.class_id == class.id)
+            {
+                let points = match entry.kind {
+                    BehaviorKind::Positive => 1,
+                    BehaviorKind::Negative => -1,
+                    BehaviorKind::Note => continue,
+                };
+                if let Some(t) = tally(
+                    &mut tallies,
+                    days,
+                    &class_records::date_string(entry.timestamp),
+                ) {
+                    t.points += points;
+                    t.entries += 1;
+                }
+            }
+        }
+    }
+
+    let mut overall = Tally::default();
+    for t in tallies.values() {
+        overall.merge(t);
+    }
+    ClassSeries {
+        class_id: class.id.clone(),
+        class_name: class.name.clone(),
+        students: class.students.len(),
+        values: labels
+            .iter()
+            .map(|l| {
+                tallies
+                    .get(l.as_str())
+                    .and_then(|t| t.value(metric, &sources.scale))
+            })
+            .collect(),
+        overall: overall.value(metric, &sources.scale),
+    }
+}
+
+fn build_comparison(
+    metric: ComparisonMetric,
+    from: NaiveDate,
+    to: NaiveDate,
+    bucket: Bucket,
+    sources: &Sources,
+) -> ClassComparison {
+    // Every date of the range and the label of its bucket
+    let days: BTreeMap<String, String> = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| (d.format("%Y-%m-%d").to_string(), label(d, bucket)))
+        .collect();
+    let mut labels: Vec<String> = days.values().cloned().collect();
+    labels.sort();
+    labels.dedup();
+
+    let mut series: Vec<ClassSeries> = sources
+        .classes
+        .iter()
+        .map(|class| class_series(class, metric, &days, &labels, sources))
+        .collect();
+    series.sort_by_key(|s| s.class_name.to_lowercase());
+    ClassComparison {
+        metric,
+        unit: (metric == ComparisonMetric::Noise).then_some(sources.scale.unit),
+        bucket,
+        labels,
+        series,
+    }
+}
+
+/// Compare every class on `metric` over `range`
+pub fn compare_classes(
+    metric: ComparisonMetric,
+    range: &ComparisonRange,
+) -> Result<ClassComparison, BackendError> {
+    let from = parse_date(&range.from)?;
+    let to = parse_date(&range.to)?;
+    if to < from || (to - from).num_days() as u64 >= MAX_RANGE_DAYS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "The range must go forward and span at most {} days",
+                MAX_RANGE_DAYS
+            ),
+        )
+        .with_details(format!("{} - {}", range.from, range.to)));
+    }
+    if metric == ComparisonMetric::Noise {
+        // Include noise samples of the running lesson
+        noise_history::flush_pending()?;
+    }
+    let sources = Sources {
+        classes: RosterStore::load()?.classes,
+        attendance: AttendanceStore::load()?,
+        behavior: BehaviorStore::load()?,
+        noise: NoiseHistoryStore::load()?,
+        scale: LevelScale::current(),
+    };
+    Ok(build_comparison(metric, from, to, range.bucket, &sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_records::{AttendanceRecord, BehaviorEntry};
+    use crate::roster::ClassBuilder;
+    use chrono::{Local, TimeZone};
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn class(id: &str, name: &str) -> ClassData {
+        ClassBuilder::new(id, name).build()
+    }
+
+    fn sources() -> Sources {
+        let attendance = [
+            ("a", "2026-03-02", false),
+            ("a", "2026-03-02", true),
+            ("a", "2026-03-09", false),
+            ("b", "2026-03-03", false),
+            // Outside the range
+            ("b", "2026-02-27", true),
+        ];
+        let behavior = [
+            ("a", "2026-03-03", BehaviorKind::Positive),
+            ("a", "2026-03-04", BehaviorKind::Positive),
+            ("a", "2026-03-10", BehaviorKind::Negative),
+            ("a", "2026-03-10", BehaviorKind::Note),
+        ];
+        let mut noise = NoiseHistoryStore::default();
+        let mut day = Histogram::default();
+        day.add(40.0);
+        day.add(60.0);
+        noise
+            .classes
+            .entry("b".into())
+            .or_default()
+            .days
+            .insert("2026-03-10".into(), day);
+        Sources {
+            classes: vec![class("b", "4B"), class("a", "3A")],
+            attendance: AttendanceStore {
+                records: attendance
+                    .iter()
+                    .map(|(class_id, date, absent)| AttendanceRecord {
+                        class_id: class_id.to_string(),
+                        student_id: "s".into(),
+                        date: date.to_string(),
+                        absent: *absent,
+                    })
+                    .collect(),
+            },
+            behavior: BehaviorStore {
+                entries: behavior
+                    .iter()
+                    .map(|(class_id, day, kind)| BehaviorEntry {
+                        id: day.to_string(),
+                        class_id: class_id.to_string(),
+                        student_id: "s".into(),
+                        timestamp: Local
+                            .from_local_datetime(&date(day).and_hms_opt(10, 0, 0).unwrap())
+                            .earliest()
+                            .unwrap()
+                            .timestamp_millis() as u64,
+                        kind: *kind,
+                        note: String::new(),
+                    })
+                    .collect(),
+            },
+            noise,
+            scale: LevelScale::default(),
+        }
+    }
+
+    #[test]
+    fn test_weekly_series() {
+        let sources = sources();
+        let from = date("2026-03-01");
+        let to = date("2026-03-12");
+
+        let attendance = build_comparison(
+            ComparisonMetric::Attendance,
+            from,
+            to,
+            Bucket::Week,
+            &sources,
+        );
+        // 2026-03-01 is a Sunday, so the range touches three weeks
+        assert_eq!(
+            attendance.labels,
+            ["2026-02-23", "2026-03-02", "2026-03-09"]
+        );
+        let names: Vec<&str> = attendance
+            .series
+            .iter()
+            .map(|s| s.class_name.as_str())
+            .collect();
+        assert_eq!(names, ["3A", "4B"]);
+        assert_eq!(attendance.series[0].values, [None, Some(0.5), Some(1.0)]);
+        assert_eq!(attendance.series[0].overall, Some(2.0 / 3.0));
+        assert_eq!(attendance.series[1].values, [None, Some(1.0), None]);
+        assert_eq!(attendance.unit, None);
+
+        let points = build_comparison(ComparisonMetric::Points, from, to, Bucket::Week, &sources);
+        assert_eq!(points.series[0].values, [None, Some(2.0), Some(-1.0)]);
+        assert_eq!(points.series[0].overall, Some(1.0));
+        assert_eq!(points.series[1].overall, None);
+
+        let noise = build_comparison(ComparisonMetric::Noise, from, to, Bucket::Week, &sources);
+        assert_eq!(noise.unit, Some(SoundUnit::Percent));
+        assert_eq!(noise.series[1].values, [None, None, Some(50.0)]);
+        assert_eq!(noise.series[0].overall, None);
+
+        let dbfs = Sources {
+            scale: LevelScale {
+                unit: SoundUnit::Dbfs,
+                spl_offset: None,
+            },
+            ..sources
+        };
+        let noise = build_comparison(ComparisonMetric::Noise, from, to, Bucket::Week, &dbfs);
+        assert_eq!(noise.unit, Some(SoundUnit::Dbfs));
+        assert_eq!(noise.series[1].overall, Some(-55.0));
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(label(date("2026-03-04"), Bucket::Day), "2026-03-04");
+        assert_eq!(label(date("2026-03-08"), Bucket::Week), "2026-03-02");
+        assert_eq!(label(date("2026-03-09"), Bucket::Week), "2026-03-09");
+        assert_eq!(label(date("2026-03-31"), Bucket::Month), "2026-03");
+
+        let monthly = build_comparison(
+            ComparisonMetric::Attendance,
+            date("2026-02-27"),
+            date("2026-03-03"),
+            Bucket::Month,
+            &sources(),
+        );
+        assert_eq!(monthly.labels, ["2026-02", "2026-03"]);
+        assert_eq!(monthly.series[1].values, [Some(0.0), Some(1.0)]);
+        assert_eq!(monthly.series[1].overall, Some(0.5));
+
+        let range = |from: &str, to: &str| ComparisonRange {
+            from: from.into(),
+            to: to.into(),
+            bucket: Bucket::Day,
+        };
+        assert!(
+            compare_classes(ComparisonMetric::Points, &range("2026-03-02", "2026-03-01")).is_err()
+        );
+        assert!(
+            compare_classes(ComparisonMetric::Points, &range("2025-01-01", "2026-03-01")).is_err()
+        );
+        assert!(
+            compare_classes(ComparisonMetric::Points, &range("2026-13-01", "2026-03-01")).is_err()
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Per-class records: attendance, behavior log and seating charts
+//!
+//! Handles:
+//! - Daily attendance snapshots taken from the roster's `absent` flags
+//! - Behavior entries (positive / negative / note) per student
+//! - One seating chart per class
+//!
+//! Stored in the `attendance`, `behavior` and `seating` data collections.
+
+use crate::accommodations;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::roster::{ClassData, RosterStore};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+const ATTENDANCE_COLLECTION: &str = "attendance";
+const BEHAVIOR_COLLECTION: &str = "behavior";
+const SEATING_COLLECTION: &str = "seating";
+
+/// Attendance of one student on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceRecord {
+    pub class_id: String,
+    pub student_id: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub absent: bool,
+}
+
+/// Kind of behavior entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BehaviorKind {
+    Positive,
+    Negative,
+    Note,
+}
+
+impl BehaviorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BehaviorKind::Positive => "positive",
+            BehaviorKind::Negative => "negative",
+            BehaviorKind::Note => "note",
+        }
+    }
+}
+
+/// A behavior log entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BehaviorEntry {
+    pub id: String,
+    pub class_id: String,
+    pub student_id: String,
+    pub timestamp: u64,
+    pub kind: BehaviorKind,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// A student's seat (0-based row and column)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Seat {
+    pub student_id: String,
+    pub row: u32,
+    pub column: u32,
+}
+
+/// Seating chart of a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatingChart {
+    pub class_id: String,
+    pub rows: u32,
+    pub columns: u32,
+    pub seats: Vec<Seat>,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+/// Persisted attendance records
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttendanceStore {
+    #[serde(default)]
+    pub records: Vec<AttendanceRecord>,
+}
+
+impl AttendanceStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(ATTENDANCE_COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(ATTENDANCE_COLLECTION, self)
+    }
+
+    /// Records of a class, oldest day first
+    pub fn for_class(&self, class_id: &str) -> Vec<AttendanceRecord> {
+        let mut records: Vec<AttendanceRecord> = self
+            .records
+            .iter()
+            .filter(|r| r.class_id == class_id)
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| a.date.cmp(&b.date));
+        records
+    }
+
+    /// Record one student present on `date`, keeping the rest of the day
+    pub fn mark_present(&mut self, class_id: &str, student_id: &str, date: &str) {
+        match self
+            .records
+            .iter_mut()
+            .find(|r| r.class_id == class_id && r.student_id == student_id && r.date == date)
+        {
+            Some(record) => record.absent = false,
+            None => self.records.push(AttendanceRecord {
+                class_id: class_id.to_string(),
+                student_id: student_id.to_string(),
+                date: date.to_string(),
+                absent: false,
+            }),
+        }
+    }
+
+    /// Store the class's current absences for `date`, replacing that day
+    pub fn record_day(&mut self, class: &ClassData, date: &str) -> usize {
+        self.records
+            .retain(|r| !(r.class_id == class.id && r.date == date));
+        self.records
+            .extend(class.students.iter().map(|s| AttendanceRecord {
+                class_id: class.id.clone(),
+                student_id: s.id.clone(),
+                date: date.to_string(),
+                absent: s.absent,
+            }));
+        class.students.len()
+    }
+}
+
+/// Persisted behavior entries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BehaviorStore {
+    #[serde(default)]
+    pub entries: Vec<BehaviorEntry>,
+}
+
+impl BehaviorStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(BEHAVIOR_COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(BEHAVIOR_COLLECTION, self)
+    }
+
+    /// Entries of a class, oldest first
+    pub fn for_class(&self, class_id: &str) -> Vec<BehaviorEntry> {
+        let mut entries: Vec<BehaviorEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.class_id == class_id)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| e.timestamp);
+        entries
+    }
+}
+
+/// Persisted seating charts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeatingStore {
+    #[serde(default)]
+    pub charts: Vec<SeatingChart>,
+}
+
+impl SeatingStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(SEATING_COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(SEATING_COLLECTION, self)
+    }
+
+    pub fn find(&self, class_id: &str) -> Option<&SeatingChart> {
+        self.charts.iter().find(|c| c.class_id == class_id)
+    }
+
+    /// Insert or replace the chart of a class
+    pub fn set(&mut self, chart: SeatingChart) {
+        self.charts.retain(|c| c.class_id != chart.class_id);
+        self.charts.push(chart);
+    }
+}
+
+pub(crate) fn load_class(class_id: &str) -> Result<ClassData, BackendError> {
+    RosterStore::load()?.find(class_id).cloned().ok_or_else(|| {
+        BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+            .with_details(class_id.to_string())
+    })
+}
+
+fn invalid_input(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(details.to_string())
+}
+
+/// Local `YYYY-MM-DD` for epoch millis
+pub fn date_string(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map_or_else(String::new, |dt| dt.format("%Y-%m-%d").to_string())
+}
+
+/// Record today's attendance of a class from its absence flags
+///
+/// Returns the number of students recorded.
+pub fn record_attendance(class_id: &str) -> Result<usize, BackendError> {
+    let class = load_class(class_id)?;
+    let mut store = AttendanceStore::load()?;
+    let count = store.record_day(&class, &date_string(clock::now_millis()));
+    store.save()?;
+    Ok(count)
+}
+
+/// Record a student present today, e.g. when their ID card is read
+///
+/// Also clears their absence flag, so a later `record_attendance` of the
+/// class keeps them present.
+pub fn record_arrival(class_id: &str, student_id: &str) -> Result<(), BackendError> {
+    let mut rosters = RosterStore::load()?;
+    let class = rosters
+        .classes
+        .iter_mut()
+        .find(|c| c.id == class_id)
+        .ok_or_else(|| {
+            BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                .with_details(class_id.to_string())
+        })?;
+    let student = class
+        .students
+        .iter_mut()
+        .find(|s| s.id == student_id)
+        .ok_or_else(|| {
+            BackendError::new(errors::grades::STUDENT_NOT_FOUND, "Student not found")
+                .with_details(student_id.to_string())
+        })?;
+    let now = clock::now_millis();
+    if student.absent {
+        student.absent = false;
+        class.updated_at = now;
+        rosters.save()?;
+    }
+    let mut store = AttendanceStore::load()?;
+    store.mark_present(class_id, student_id, &date_string(now));
+    store.save()
+}
+
+/// Add a behavior entry for a student
+pub fn add_behavior_entry(
+    class_id: &str,
+    student_id: &str,
+    kind: BehaviorKind,
+    note: &str,
+) -> Result<BehaviorEntry, BackendError> {
+    let class = load_class(class_id)?;
+    if !class.students.iter().any(|s| s.id == student_id) {
+        return Err(invalid_input("Student is not in this class", student_id));
+    }
+
+    let now = clock::now_millis();
+    let entry = BehaviorEntry {
+        id: format!("behavior_{}_{}", now, student_id),
+        class_id: class.id,
+        student_id: student_id.to_string(),
+        timestamp: now,
+        kind,
+        note: note.trim().to_string(),
+    };
+    let mut store = BehaviorStore::load()?;
+    store.entries.push(entry.clone());
+    store.save()?;
+    Ok(entry)
+}
+
+/// Check a chart against its class: seats in the grid, no double booking,
+/// accommodations respected
+pub fn validate_seating(chart: &SeatingChart, class: &ClassData) -> Result<(), BackendError> {
+    let mut taken = std::collections::HashSet::new();
+    for seat in &chart.seats {
+        if seat.row >= chart.rows || seat.column >= chart.columns {
+            return Err(invalid_input(
+                "Seat is outside the chart",
+                format!("{} ({}, {})", seat.student_id, seat.row, seat.column),
+            ));
+        }
+        if !taken.insert((seat.row, seat.column)) {
+            return Err(invalid_input(
+                "Two students share a seat",
+                format!("({}, {})", seat.row, seat.column),
+            ));
+        }
+        if !class.students.iter().any(|s| s.id == seat.student_id) {
+            return Err(invalid_input(
+                "Student is not in this class",
+                &seat.student_id,
+            ));
+        }
+    }
+    accommodations::check_seating(chart, class)
+}
+
+/// Save the seating chart of a class
+pub fn save_seating_chart(chart: SeatingChart) -> Result<(), BackendError> {
+    let class = load_class(&chart.class_id)?;
+    validate_seating(&chart, &class)?;
+    let mut store = SeatingStore::load()?;
+    store.set(SeatingChart {
+        updated_at: clock::now_millis(),
+        ..chart
+    });
+    store.save()
+}
+
+/// Seating chart of a class, if one was saved
+pub fn get_seating_chart(class_id: &str) -> Result<Option<SeatingChart>, BackendError> {
+    Ok(SeatingStore::load()?.find(class_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roster::ClassBuilder;
+
+    fn class() -> ClassData {
+        let mut class = ClassBuilder::new("class_1", "3A")
+            .created_at(1)
+            .students(&["Mario Rossi", "Anna Bianchi"])
+            .build();
+        class.students[1].absent = true;
+        class
+    }
+
+    #[test]
+    fn test_record_day_replaces_same_day() {
+        let class = class();
+        let mut store = AttendanceStore::default();
+        assert_eq!(store.record_day(&class, "2026-03-02"), 2);
+        assert_eq!(store.record_day(&class, "2026-03-02"), 2);
+        store.record_day(&class, "2026-03-01");
+
+        let records = store.for_class(&class.id);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].date, "2026-03-01");
+        assert!(records.iter().filter(|r| r.absent).count() == 2);
+    }
+
+    #[test]
+    fn test_mark_present_updates_the_day() {
+        let class = class();
+        let absent = class.students[1].id.clone();
+        let mut store = AttendanceStore::default();
+        store.record_day(&class, "2026-03-02");
+        store.mark_present(&class.id, &absent, "2026-03-02");
+        store.mark_present(&class.id, &absent, "2026-03-03");
+
+        let records = store.for_class(&class.id);
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| !r.absent));
+        assert_eq!(records[2].date, "2026-03-03");
+    }
+
+    #[test]
+    fn test_validate_seating() {
+        let class = class();
+        let seat = |i: usize, row, column| Seat {
+            student_id: class.students[i].id.clone(),
+            row,
+            column,
+        };
+        let mut chart = SeatingChart {
+            class_id: class.id.clone(),
+            rows: 2,
+            columns: 2,
+            seats: vec![seat(0, 0, 0), seat(1, 1, 1)],
+            updated_at: 0,
+        };
+        assert!(validate_seating(&chart, &class).is_ok());
+
+        chart.seats[1] = seat(1, 0, 0);
+        assert!(validate_seating(&chart, &class).is_err());
+        chart.seats[1] = seat(1, 2, 0);
+        assert!(validate_seating(&chart, &class).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+//! Classroom "traffic light" state machine
+//!
+//! Handles:
+//! - The current work mode: green (talking allowed), yellow (whisper),
+//!   red (silence), each with its allowed noise level
+//! - Manual changes from the teacher, and automatic changes from noise
+//!   levels when in auto mode
+//! - `classroom-state-changed` to every window (main, overlay, projector,
+//!   tray menu), so they never disagree
+//! - `noise-level` with each reported level, the level in the chosen unit
+//!   (see `sound_units`) and whether the mic is muted (see `input_mute`),
+//!   so a muted mic isn't shown as a silent class
+//! - Ignoring levels during quiet hours (see `quiet_hours`): breaks, tests
+//!   and the time outside school never change the light
+//!
+//! Auto transition rules (`classroom_state_rules` config key):
+//! - Noise must stay above a threshold for `escalateAfterMs` before the
+//!   light turns yellow/red, so a single shout doesn't flip it
+//! - Calming down requires `hysteresisDb` below the threshold for
+//!   `calmAfterMs`, and steps down one color at a time (red → yellow → green)
+
+use crate::agc;
+use crate::background_audio;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::event_throttle;
+use crate::file_ops;
+use crate::input_mute;
+use crate::noise_history;
+use crate::quiet_hours;
+use crate::sound_units::{LevelScale, SoundUnit};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted on every state change
+pub const STATE_EVENT: &str = "classroom-state-changed";
+/// Emitted with every reported level (throttled)
+pub const LEVEL_EVENT: &str = "noise-level";
+const RULES_KEY: &str = "classroom_state_rules";
+
+static MACHINE: Mutex<Option<StateMachine>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateColor {
+    Green,
+    Yellow,
+    Red,
+}
+
+impl StateColor {
+    fn calmer(self) -> Self {
+        match self {
+            StateColor::Red => StateColor::Yellow,
+            _ => StateColor::Green,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMode {
+    Manual,
+    Auto,
+}
+
+/// Auto transition thresholds and timings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransitionRules {
+    /// Noise level (same scale as the noise meter) above which yellow applies
+    pub yellow_above: f64,
+    pub red_above: f64,
+    pub hysteresis_db: f64,
+    pub escalate_after_ms: u64,
+    pub calm_after_ms: u64,
+}
+
+impl Default for TransitionRules {
+    fn default() -> Self {
+        Self {
+            yellow_above: 60.0,
+            red_above: 75.0,
+            hysteresis_db: 3.0,
+            escalate_after_ms: 3_000,
+            calm_after_ms: 10_000,
+        }
+    }
+}
+
+impl TransitionRules {
+    /// Highest noise level allowed in a color; `None` for green
+    pub fn allowed_max(&self, color: StateColor) -> Option<f64> {
+        match color {
+            StateColor::Green => None,
+            StateColor::Yellow => Some(self.red_above),
+            StateColor::Red => Some(self.yellow_above),
+        }
+    }
+
+    /// Color a level calls for, given the current color (for hysteresis)
+    fn target(&self, level: f64, current: StateColor) -> StateColor {
+        let margin = |threshold: f64, color: StateColor| {
+            if current >= color {
+                threshold - self.hysteresis_db
+            } else {
+                threshold
+            }
+        };
+        if level > margin(self.red_above, StateColor::Red) {
+            StateColor::Red
+        } else if level > margin(self.yellow_above, StateColor::Yellow) {
+            StateColor::Yellow
+        } else {
+            StateColor::Green
+        }
+    }
+}
+
+/// What the views display
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassroomState {
+    pub color: StateColor,
+    pub mode: StateMode,
+    pub changed_at: u64,
+    /// Noise level above which the class is too loud for this color
+    pub allowed_max: Option<f64>,
+    /// Whether the last change came from the noise level
+    pub automatic: bool,
+}
+
+#[derive(Debug, Clone)]
+struct StateMachine {
+    color: StateColor,
+    mode: StateMode,
+    changed_at: u64,
+    automatic: bool,
+    /// Color the noise level has asked for, and since when
+    pending: Option<(StateColor, u64)>,
+}
+
+impl StateMachine {
+    fn new(now: u64) -> Self {
+        Self {
+            color: StateColor::Green,
+            mode: StateMode::Manual,
+            changed_at: now,
+            automatic: false,
+            pending: None,
+        }
+    }
+
+    fn snapshot(&self, rules: &TransitionRules) -> ClassroomState {
+        ClassroomState {
+            color: self.color,
+            mode: self.mode,
+            changed_at: self.changed_at,
+            allowed_max: rules.allowed_max(self.color),
+            automatic: self.automatic,
+        }
+    }
+
+    /// Manual override; returns whether anything changed
+    fn set(&mut self, color: StateColor, mode: StateMode, now: u64) -> bool {
+        let changed = color != self.color || mode != self.mode;
+        if color != self.color {
+            self.changed_at = now;
+        }
+        self.color = color;
+        self.mode = mode;
+        self.automatic = false;
+        self.pending = None;
+        changed
+    }
+
+    /// Feed a noise level; returns whether the color changed
+    fn observe(&mut self, level: f64, now: u64, rules: &TransitionRules) -> bool {
+        if self.mode != StateMode::Auto {
+            return false;
+        }
+        let target = rules.target(level, self.color);
+        if target == self.color {
+            self.pending = None;
+            return false;
+        }
+        let since = match self.pending {
+            Some((color, since)) if color == target => since,
+            _ => {
+                self.pending = Some((target, now));
+                now
+            }
+        };
+        let escalating = target > self.color;
+        let wait = if escalating {
+            rules.escalate_after_ms
+        } else {
+            rules.calm_after_ms
+        };
+        if now < since + wait {
+            return false;
+        }
+        self.color = if escalating {
+            target
+        } else {
+            self.color.calmer()
+        };
+        self.changed_at = now;
+        self.automatic = true;
+        // Calming further needs a fresh quiet period
+        self.pending = None;
+        true
+    }
+}
+
+/// Load the auto transition rules
+pub fn get_rules() -> TransitionRules {
+    file_ops::load_config(RULES_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn with_machine<T>(f: impl FnOnce(&mut StateMachine) -> T) -> T {
+    let mut guard = MACHINE.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(|| StateMachine::new(clock::now_millis())))
+}
+
+fn emit(app: &AppHandle, state: &ClassroomState) {
+    let _ = app.emit(STATE_EVENT, state);
+}
+
+/// Current state
+pub fn get_classroom_state() -> ClassroomState {
+    let rules = get_rules();
+    with_machine(|m| m.snapshot(&rules))
+}
+
+/// Set the color and/or mode; a color without a mode switches to manual
+/// so the noise level doesn't immediately override the teacher
+pub fn set_classroom_state(
+    app: &AppHandle,
+    color: Option<StateColor>,
+    mode: Option<StateMode>,
+) -> Result<ClassroomState, BackendError> {
+    if color.is_none() && mode.is_none() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Specify a color or a mode",
+        ));
+    }
+    let rules = get_rules();
+    let (changed, state) = with_machine(|m| {
+        let changed = m.set(
+            color.unwrap_or(m.color),
+            mode.unwrap_or(if color.is_some() {
+                StateMode::Manual
+            } else {
+                m.mode
+            }),
+            clock::now_millis(),
+        );
+        (changed, m.snapshot(&rules))
+    });
+    if changed {
+        emit(app, &state);
+    }
+    Ok(state)
+}
+
+/// Payload of `noise-level`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseLevel {
+    /// After gain compensation (see `agc`), 0-100
+    pub level: f64,
+    /// `level` in `unit`
+    pub value: f64,
+    pub unit: SoundUnit,
+    pub gain: f64,
+    /// Mic muted or at zero volume; `None` when unknown
+    pub muted: Option<bool>,
+    /// Monitoring paused by a quiet-hour rule: the level is shown but not
+    /// acted on or recorded
+    pub paused: bool,
+}
+
+/// Feed the current noise level; changes the color in auto mode
+///
+/// The level is gain-compensated first when `agc` is on. During quiet
+/// hours it only reaches the meter.
+pub fn report_noise_level(app: &AppHandle, level: f64) -> Result<ClassroomState, BackendError> {
+    if !level.is_finite() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Noise level must be a number",
+        ));
+    }
+    let paused = quiet_hours::current_reason().is_some();
+    let (level, gain) = if paused {
+        (level, 0.0)
+    } else {
+        agc::apply(level, clock::now_millis())
+    };
+    if !paused {
+        noise_history::record(level);
+    }
+    let scale = LevelScale::current();
+    event_throttle::emit(
+        app,
+        LEVEL_EVENT,
+        "input",
+        &NoiseLevel {
+            level,
+            value: scale.convert(level),
+            unit: scale.unit,
+            gain,
+            muted: input_mute::meter_muted(),
+            paused,
+        },
+    );
+    let rules = get_rules();
+    if paused {
+        // Loud moments before the pause don't count towards after it
+        return Ok(with_machine(|m| {
+            m.pending = None;
+            m.snapshot(&rules)
+        }));
+    }
+    let (previous, changed, state) = with_machine(|m| {
+        let previous = m.color;
+        let changed = m.observe(level, clock::now_millis(), &rules);
+        (previous, changed, m.snapshot(&rules))
+    });
+    if changed {
+        emit(app, &state);
+        // The noise alert sounds on escalation; keep music from drowning it
+        if state.color > previous {
+            let _ = background_audio::duck_background_audio(
+                app,
+                background_audio::ALERT_DUCK.as_millis() as u64,
+            );
+        }
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto() -> StateMachine {
+        let mut machine = StateMachine::new(0);
+        machine.set(StateColor::Green, StateMode::Auto, 0);
+        machine
+    }
+
+    #[test]
+    fn test_escalates_after_hold_time() {
+        let rules = TransitionRules::default();
+        let mut m = auto();
+        assert!(!m.observe(80.0, 1_000, &rules));
+        assert!(!m.observe(80.0, 3_999, &rules));
+        assert!(m.observe(80.0, 4_000, &rules));
+        // Jumps straight to red
+        assert_eq!(m.color, StateColor::Red);
+        assert!(m.automatic);
+    }
+
+    #[test]
+    fn test_short_spike_is_ignored() {
+        let rules = TransitionRules::default();
+        let mut m = auto();
+        m.observe(80.0, 0, &rules);
+        m.observe(40.0, 2_000, &rules);
+        assert!(!m.observe(80.0, 3_500, &rules));
+        assert_eq!(m.color, StateColor::Green);
+    }
+
+    #[test]
+    fn test_calms_one_step_at_a_time_with_hysteresis() {
+        let rules = TransitionRules::default();
+        let mut m = auto();
+        m.set(StateColor::Red, StateMode::Auto, 0);
+
+        // 73 is below red_above but within the hysteresis band
+        assert!(!m.observe(73.0, 0, &rules));
+        assert!(!m.observe(73.0, 20_000, &rules));
+        assert_eq!(m.color, StateColor::Red);
+
+        m.observe(30.0, 20_000, &rules);
+        assert!(m.observe(30.0, 30_000, &rules));
+        assert_eq!(m.color, StateColor::Yellow);
+        assert!(!m.observe(30.0, 35_000, &rules));
+        assert!(m.observe(30.0, 45_000, &rules));
+        assert_eq!(m.color, StateColor::Green);
+    }
+
+    #[test]
+    fn test_manual_mode_ignores_noise() {
+        let rules = TransitionRules::default();
+        let mut m = StateMachine::new(0);
+        assert!(!m.observe(90.0, 0, &rules));
+        assert!(!m.observe(90.0, 60_000, &rules));
+        assert_eq!(m.color, StateColor::Green);
+        assert_eq!(m.snapshot(&rules).allowed_max, None);
+        m.set(StateColor::Red, StateMode::Manual, 1);
+        assert_eq!(m.snapshot(&rules).allowed_max, Some(60.0));
+    }
+}
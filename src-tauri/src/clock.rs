@@ -0,0 +1,47 @@
+//! Time helpers shared by backend subsystems
+//!
+//! All persisted timestamps are Unix epoch milliseconds, matching the
+//! `Date.now()` values the frontend stores (e.g. `createdAt` in classStore).
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Last measured offset of the system clock (NTP minus local), see `clock_sync`
+static SKEW_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Current time as Unix epoch milliseconds
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Record the measured clock offset
+pub fn set_skew_millis(skew: i64) {
+    SKEW_MS.store(skew, Ordering::Relaxed);
+}
+
+/// Measured clock offset (0 until a check succeeds)
+pub fn skew_millis() -> i64 {
+    SKEW_MS.load(Ordering::Relaxed)
+}
+
+/// Current time corrected by the measured offset
+///
+/// Use for timestamps compared across PCs (e.g. cloud sync pointers), where
+/// a school PC's wrong clock would otherwise decide which copy is newer.
+pub fn corrected_now_millis() -> u64 {
+    now_millis().saturating_add_signed(skew_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now_millis_is_after_2024() {
+        // 2024-01-01T00:00:00Z
+        assert!(now_millis() > 1_704_067_200_000);
+    }
+}
@@ -0,0 +1,202 @@
+//! System clock check against NTP
+//!
+//! Handles:
+//! - Measuring the system clock's offset with a single SNTP query (tried
+//!   against a few public servers, skipped quietly when offline)
+//! - Warning all windows via `clock-skew-warning` when the offset is large
+//!   enough to put the bell schedule or sync timestamps visibly off
+//! - Recording the offset in `clock`, so timestamps compared across PCs
+//!   (cloud sync pointers) use corrected time
+//!
+//! School PCs are often minutes off, and sometimes days when the CMOS
+//! battery is flat. The system clock is never changed; that needs admin
+//! rights teachers don't have.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted when the measured skew exceeds `WARN_THRESHOLD_MS`
+pub const SKEW_EVENT: &str = "clock-skew-warning";
+
+/// Skew above which the teacher is warned
+pub const WARN_THRESHOLD_MS: i64 = 60_000;
+
+const NTP_SERVERS: &[&str] = &[
+    "pool.ntp.org:123",
+    "time.google.com:123",
+    "time.cloudflare.com:123",
+];
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+/// Result of a clock check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSkewReport {
+    pub server: String,
+    /// NTP time minus system time; positive when the PC is behind
+    pub skew_ms: i64,
+    pub round_trip_ms: i64,
+    pub checked_at: u64,
+    pub threshold_ms: i64,
+    pub exceeds_threshold: bool,
+}
+
+/// Offset and delay of one NTP exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    offset_ms: i64,
+    round_trip_ms: i64,
+}
+
+fn to_ntp(millis: u64) -> [u8; 8] {
+    let secs = millis / 1000 + NTP_UNIX_OFFSET_SECS;
+    let frac = ((millis % 1000) << 32) / 1000;
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&(secs as u32).to_be_bytes());
+    bytes[4..].copy_from_slice(&(frac as u32).to_be_bytes());
+    bytes
+}
+
+fn from_ntp(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    (secs - NTP_UNIX_OFFSET_SECS as i64) * 1000 + ((frac * 1000) >> 32)
+}
+
+/// Offset and round trip from the four NTP timestamps (RFC 5905)
+fn compute_sample(sent: i64, server_received: i64, server_sent: i64, received: i64) -> Sample {
+    Sample {
+        offset_ms: ((server_received - sent) + (server_sent - received)) / 2,
+        round_trip_ms: (received - sent) - (server_sent - server_received),
+    }
+}
+
+/// One SNTP exchange with `server` ("host:port")
+fn query(server: &str, timeout: Duration) -> Result<Sample, BackendError> {
+    let unreachable = |e: &dyn ToString| {
+        BackendError::new(errors::clock::NTP_UNAVAILABLE, "Time server unreachable")
+            .with_details(format!("{}: {}", server, e.to_string()))
+    };
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| unreachable(&e))?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| unreachable(&e))?;
+    socket.connect(server).map_err(|e| unreachable(&e))?;
+
+    // LI 0, version 3, client mode; our transmit time comes back as the
+    // originate timestamp, which ties the reply to this request
+    let mut request = [0u8; 48];
+    request[0] = 0x1B;
+    let sent = clock::now_millis();
+    request[40..48].copy_from_slice(&to_ntp(sent));
+    socket.send(&request).map_err(|e| unreachable(&e))?;
+
+    let mut reply = [0u8; 48];
+    let len = socket.recv(&mut reply).map_err(|e| unreachable(&e))?;
+    let received = clock::now_millis();
+
+    let mode = reply[0] & 0x07;
+    let stratum = reply[1];
+    if len < 48 || mode != 4 || stratum == 0 || reply[24..32] != request[40..48] {
+        return Err(unreachable(&"invalid reply"));
+    }
+    Ok(compute_sample(
+        sent as i64,
+        from_ntp(&reply[32..40]),
+        from_ntp(&reply[40..48]),
+        received as i64,
+    ))
+}
+
+/// Measure the clock offset against the first reachable server
+///
+/// Records the offset for corrected timestamps and emits
+/// `clock-skew-warning` when it exceeds the threshold.
+pub fn check_clock_skew(app: &AppHandle) -> Result<ClockSkewReport, BackendError> {
+    let mut last_error = None;
+    for server in NTP_SERVERS {
+        match query(server, QUERY_TIMEOUT) {
+            Ok(sample) => {
+                let report = report(server, sample);
+                clock::set_skew_millis(report.skew_ms);
+                if report.exceeds_threshold {
+                    let _ = app.emit(SKEW_EVENT, &report);
+                }
+                return Ok(report);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        BackendError::new(errors::clock::NTP_UNAVAILABLE, "No time server configured")
+    }))
+}
+
+fn report(server: &str, sample: Sample) -> ClockSkewReport {
+    ClockSkewReport {
+        server: server.trim_end_matches(":123").to_string(),
+        skew_ms: sample.offset_ms,
+        round_trip_ms: sample.round_trip_ms,
+        checked_at: clock::now_millis(),
+        threshold_ms: WARN_THRESHOLD_MS,
+        exceeds_threshold: sample.offset_ms.abs() > WARN_THRESHOLD_MS,
+    }
+}
+
+/// Check at startup and every few hours; failures (offline) are ignored
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let _ = check_clock_skew(&app);
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntp_timestamp_round_trip() {
+        let millis = 1_709_214_330_250;
+        assert_eq!(from_ntp(&to_ntp(millis)), millis as i64);
+    }
+
+    #[test]
+    fn test_compute_sample() {
+        // PC is 5 minutes behind, 40 ms each way, 20 ms at the server
+        let skew = 300_000;
+        let sample = compute_sample(1_000, 1_040 + skew, 1_060 + skew, 1_100);
+        assert_eq!(sample.offset_ms, skew);
+        assert_eq!(sample.round_trip_ms, 80);
+        assert!(report("pool.ntp.org:123", sample).exceeds_threshold);
+    }
+
+    #[test]
+    fn test_query_against_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let handle = std::thread::spawn(move || {
+            let mut request = [0u8; 48];
+            let (_, peer) = server.recv_from(&mut request).unwrap();
+            let now = to_ntp(clock::now_millis() + 90_000);
+            let mut reply = [0u8; 48];
+            reply[0] = 0x1C;
+            reply[1] = 2;
+            reply[24..32].copy_from_slice(&request[40..48]);
+            reply[32..40].copy_from_slice(&now);
+            reply[40..48].copy_from_slice(&now);
+            server.send_to(&reply, peer).unwrap();
+        });
+        let sample = query(&addr, QUERY_TIMEOUT).unwrap();
+        handle.join().unwrap();
+        assert!((sample.offset_ms - 90_000).abs() < 1_000);
+    }
+}
@@ -0,0 +1,515 @@
+//! Cloud backup targets (WebDAV / Nextcloud, S3-compatible)
+//!
+//! Handles:
+//! - Storing the endpoint in config and the credentials in the keychain
+//! - Selecting the active target (the last one configured)
+//! - Uploading local backup archives, including ones queued while offline
+//! - Retrying transient failures (flaky school Wi-Fi)
+//! - Detecting conflicting backups from another PC via a `latest.json` pointer
+//! - Restoring the latest (or a chosen) remote archive
+//!
+//! Backups are always created locally first (see `backup`), then uploaded;
+//! anything not yet uploaded is retried on the next `backup_to_cloud`.
+//! Nothing is configured or uploaded without a backup passphrase, and
+//! archives made before one was set stay local.
+
+use crate::backup;
+use crate::clock;
+use crate::cloud_s3;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::secrets;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::Duration;
+
+const WEBDAV_CONFIG_KEY: &str = "cloud_webdav";
+const TARGET_CONFIG_KEY: &str = "cloud_target";
+const SYNC_COLLECTION: &str = "cloud_sync";
+const POINTER_FILE: &str = "latest.json";
+
+/// Upload attempts before giving up on an archive
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Base delay between attempts (doubled each retry)
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// WebDAV endpoint settings (password lives in the keychain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebDavConfig {
+    /// Collection URL, e.g. `https://cloud.school.it/remote.php/dav/files/rossi/Classroom/`
+    pub url: String,
+    pub username: String,
+}
+
+impl WebDavConfig {
+    fn secret_name(&self) -> String {
+        format!("webdav:{}@{}", self.username, self.url)
+    }
+
+    fn file_url(&self, file_name: &str) -> String {
+        format!("{}{}", self.url, file_name)
+    }
+}
+
+/// Remote pointer to the most recent backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemotePointer {
+    pub file_name: String,
+    pub created_at: u64,
+    pub install_id: String,
+}
+
+/// Local record of what has been uploaded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloudSyncState {
+    #[serde(default)]
+    pub install_id: String,
+    #[serde(default)]
+    pub uploaded: Vec<String>,
+    /// Pointer observed after our last successful sync or restore
+    #[serde(default)]
+    pub last_pointer: Option<RemotePointer>,
+}
+
+impl CloudSyncState {
+    fn load() -> Result<Self, BackendError> {
+        let mut state: Self = file_ops::load_data(SYNC_COLLECTION)?;
+        if state.install_id.is_empty() {
+            let mut bytes = [0u8; 8];
+            getrandom::fill(&mut bytes).map_err(|e| {
+                BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+                    .with_details(e.to_string())
+            })?;
+            state.install_id = hex::encode(bytes);
+        }
+        Ok(state)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(SYNC_COLLECTION, self)
+    }
+}
+
+/// Outcome of a cloud backup run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudBackupResult {
+    /// Archive created by this run
+    pub created: backup::BackupInfo,
+    /// Archives uploaded in this run (including previously queued ones)
+    pub uploaded: Vec<String>,
+    /// Archives still waiting for upload
+    pub pending: Vec<String>,
+    /// Set when another PC pushed a newer backup since our last sync; the
+    /// remote `latest.json` pointer is left untouched in that case
+    pub conflict: Option<RemotePointer>,
+}
+
+/// Remote storage that backup archives are uploaded to
+pub(crate) trait BackupTarget {
+    /// Upload without overwriting; returns false if the name is already taken
+    fn put_new(&self, file_name: &str, bytes: &[u8]) -> Result<bool, BackendError>;
+
+    /// Upload, replacing any existing file
+    fn put(&self, file_name: &str, bytes: &[u8]) -> Result<(), BackendError>;
+
+    /// Download a file; `Ok(None)` if it does not exist
+    fn get(&self, file_name: &str) -> Result<Option<Vec<u8>>, BackendError>;
+}
+
+fn get_pointer(target: &dyn BackupTarget) -> Result<Option<RemotePointer>, BackendError> {
+    Ok(target
+        .get(POINTER_FILE)?
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+}
+
+/// Record which target `backup_to_cloud` uses
+pub(crate) fn set_active_target(name: &str) -> Result<(), BackendError> {
+    file_ops::save_config(TARGET_CONFIG_KEY, serde_json::Value::from(name))
+}
+
+/// The configured target (WebDAV when none was chosen explicitly)
+fn active_target() -> Result<Box<dyn BackupTarget>, BackendError> {
+    let name = file_ops::load_config(TARGET_CONFIG_KEY)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from));
+    match name.as_deref() {
+        Some(cloud_s3::TARGET_NAME) => Ok(Box::new(cloud_s3::S3Client::from_settings()?)),
+        _ => Ok(Box::new(WebDavClient::from_settings()?)),
+    }
+}
+
+/// Whether a cloud target is set up with its credentials and a backup
+/// passphrase, so `backup_to_cloud` can upload
+pub fn is_configured() -> bool {
+    active_target().is_ok() && backup::require_passphrase().is_ok()
+}
+
+/// Normalize and validate a WebDAV collection URL
+///
+/// Plain HTTP is only accepted for localhost (testing); student data must
+/// travel encrypted.
+pub fn normalize_url(url: &str) -> Result<String, BackendError> {
+    let url = url.trim();
+    let is_local = ["http://localhost", "http://127.0.0.1"]
+        .iter()
+        .any(|p| url.starts_with(p));
+    if !url.starts_with("https://") && !is_local {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Server URL must use https://",
+        ));
+    }
+    Ok(if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    })
+}
+
+/// Decide whether the remote pointer conflicts with our last known state
+///
+/// A conflict means another installation uploaded a backup we have not seen.
+pub fn detect_conflict(
+    remote: Option<&RemotePointer>,
+    last_seen: Option<&RemotePointer>,
+    install_id: &str,
+) -> bool {
+    match remote {
+        None => false,
+        Some(r) if r.install_id == install_id => false,
+        Some(r) => last_seen.is_none_or(|seen| r.created_at > seen.created_at),
+    }
+}
+
+fn get_config() -> Result<WebDavConfig, BackendError> {
+    file_ops::load_config(WEBDAV_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or_else(|| {
+            BackendError::new(
+                errors::backup::NOT_CONFIGURED,
+                "WebDAV backup is not configured",
+            )
+        })
+}
+
+/// Keychain entry of the configured account
+pub(crate) fn configured_secret() -> Option<String> {
+    get_config().ok().map(|c| c.secret_name())
+}
+
+/// Minimal blocking WebDAV client
+struct WebDavClient {
+    config: WebDavConfig,
+    auth_header: String,
+    agent: ureq::Agent,
+}
+
+impl WebDavClient {
+    fn new(config: WebDavConfig, password: &str) -> Self {
+        let credentials = format!("{}:{}", config.username, password);
+        Self {
+            auth_header: format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ),
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(10))
+                .timeout(Duration::from_secs(120))
+                .build(),
+            config,
+        }
+    }
+
+    fn from_settings() -> Result<Self, BackendError> {
+        let config = get_config()?;
+        let password = secrets::get_secret(&config.secret_name())?.ok_or_else(|| {
+            BackendError::new(errors::backup::NOT_CONFIGURED, "WebDAV password is missing")
+        })?;
+        Ok(Self::new(config, &password))
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        self.agent
+            .request(method, url)
+            .set("Authorization", &self.auth_header)
+    }
+
+    /// Check the collection exists and credentials work
+    fn check(&self) -> Result<(), BackendError> {
+        self.request("PROPFIND", &self.config.url)
+            .set("Depth", "0")
+            .call()
+            .map(|_| ())
+            .map_err(map_ureq_error)
+    }
+}
+
+impl BackupTarget for WebDavClient {
+    fn put_new(&self, file_name: &str, bytes: &[u8]) -> Result<bool, BackendError> {
+        match self
+            .request("PUT", &self.config.file_url(file_name))
+            .set("If-None-Match", "*")
+            .send_bytes(bytes)
+        {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(412, _)) => Ok(false),
+            Err(e) => Err(map_ureq_error(e)),
+        }
+    }
+
+    fn put(&self, file_name: &str, bytes: &[u8]) -> Result<(), BackendError> {
+        self.request("PUT", &self.config.file_url(file_name))
+            .send_bytes(bytes)
+            .map(|_| ())
+            .map_err(map_ureq_error)
+    }
+
+    fn get(&self, file_name: &str) -> Result<Option<Vec<u8>>, BackendError> {
+        match self.request("GET", &self.config.file_url(file_name)).call() {
+            Ok(response) => {
+                let mut buf = Vec::new();
+                response.into_reader().read_to_end(&mut buf).map_err(|e| {
+                    BackendError::new(errors::backup::UNREACHABLE, "Download interrupted")
+                        .with_details(e.to_string())
+                })?;
+                Ok(Some(buf))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(map_ureq_error(e)),
+        }
+    }
+}
+
+pub(crate) fn map_ureq_error(e: ureq::Error) -> BackendError {
+    match e {
+        ureq::Error::Status(code @ (401 | 403), _) => BackendError::new(
+            errors::backup::REMOTE_ERROR,
+            "Backup server rejected the credentials",
+        )
+        .with_details(format!("HTTP {}", code)),
+        ureq::Error::Status(code, _) => BackendError::new(
+            errors::backup::REMOTE_ERROR,
+            "Backup server returned an error",
+        )
+        .with_details(format!("HTTP {}", code)),
+        ureq::Error::Transport(t) => {
+            BackendError::new(errors::backup::UNREACHABLE, "Backup server is unreachable")
+                .with_details(t.to_string())
+        }
+    }
+}
+
+/// Run an operation with exponential backoff on network failures
+pub(crate) fn with_retry<T>(
+    mut op: impl FnMut() -> Result<T, BackendError>,
+) -> Result<T, BackendError> {
+    let mut delay = RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if e.code == errors::backup::UNREACHABLE && attempt < MAX_ATTEMPTS => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Configure the WebDAV target and verify the credentials
+pub fn configure_webdav(url: &str, username: &str, password: &str) -> Result<(), BackendError> {
+    backup::require_passphrase()?;
+    let config = WebDavConfig {
+        url: normalize_url(url)?,
+        username: username.trim().to_string(),
+    };
+
+    WebDavClient::new(config.clone(), password).check()?;
+
+    secrets::set_secret(&config.secret_name(), password)?;
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Invalid WebDAV configuration",
+        )
+        .with_details(e.to_string())
+    })?;
+    file_ops::save_config(WEBDAV_CONFIG_KEY, value)?;
+    set_active_target("webdav")
+}
+
+/// Whether a local archive is encrypted (and so may be uploaded)
+fn is_encrypted(file_name: &str) -> bool {
+    backup::read_local_backup(file_name)
+        .and_then(|bytes| backup::read_manifest(&bytes))
+        .is_ok_and(|manifest| manifest.encrypted)
+}
+
+/// Create a backup and upload it together with any queued archives
+pub fn backup_to_cloud() -> Result<CloudBackupResult, BackendError> {
+    backup::require_passphrase()?;
+    let target = active_target()?;
+    let created = backup::create_local_backup("backup")?;
+    let mut state = CloudSyncState::load()?;
+
+    let mut pending: Vec<backup::BackupInfo> = backup::list_local_backups()?
+        .into_iter()
+        .filter(|b| b.file_name.starts_with("classroom-backup-"))
+        .filter(|b| !state.uploaded.contains(&b.file_name))
+        .filter(|b| is_encrypted(&b.file_name))
+        .collect();
+    pending.sort_by_key(|b| b.created_at);
+
+    let mut uploaded = Vec::new();
+    for info in &pending {
+        let bytes = backup::read_local_backup(&info.file_name)?;
+        let remote_name = format!("{}-{}", state.install_id, info.file_name);
+
+        match with_retry(|| target.put_new(&remote_name, &bytes)) {
+            // `false` means the archive is already on the server (an earlier
+            // run stopped before recording it); either way it is uploaded now
+            Ok(_) => {}
+            // Offline: keep the rest queued for next time
+            Err(e) if e.code == errors::backup::UNREACHABLE => break,
+            Err(e) => return Err(e),
+        }
+
+        state.uploaded.push(info.file_name.clone());
+        uploaded.push(remote_name);
+        state.save()?;
+    }
+
+    let mut conflict = None;
+    if let Some(latest_remote) = uploaded.last() {
+        let remote_pointer = with_retry(|| get_pointer(target.as_ref()))?;
+        if detect_conflict(
+            remote_pointer.as_ref(),
+            state.last_pointer.as_ref(),
+            &state.install_id,
+        ) {
+            conflict = remote_pointer;
+        } else {
+            let pointer = RemotePointer {
+                file_name: latest_remote.clone(),
+                // Compared with pointers written by other PCs, so use
+                // NTP-corrected time (see `clock_sync`)
+                created_at: clock::corrected_now_millis(),
+                install_id: state.install_id.clone(),
+            };
+            let bytes = serde_json::to_vec_pretty(&pointer).map_err(|e| {
+                BackendError::new(errors::backup::ARCHIVE_ERROR, "Failed to write pointer")
+                    .with_details(e.to_string())
+            })?;
+            with_retry(|| target.put(POINTER_FILE, &bytes))?;
+            state.last_pointer = Some(pointer);
+            state.save()?;
+        }
+    }
+
+    let pending = pending
+        .iter()
+        .map(|b| b.file_name.clone())
+        .filter(|name| !state.uploaded.contains(name))
+        .collect();
+
+    Ok(CloudBackupResult {
+        created,
+        uploaded,
+        pending,
+        conflict,
+    })
+}
+
+/// Restore a remote archive (the one `latest.json` points to by default)
+pub fn restore_from_cloud(
+    file_name: Option<String>,
+) -> Result<backup::BackupManifest, BackendError> {
+    let target = active_target()?;
+    let mut state = CloudSyncState::load()?;
+
+    let pointer = with_retry(|| get_pointer(target.as_ref()))?;
+    let file_name = match file_name.or_else(|| pointer.as_ref().map(|p| p.file_name.clone())) {
+        Some(name) => name,
+        None => {
+            return Err(BackendError::new(
+                errors::backup::REMOTE_ERROR,
+                "No backup found on the server",
+            ))
+        }
+    };
+
+    let bytes = with_retry(|| target.get(&file_name))?.ok_or_else(|| {
+        BackendError::new(
+            errors::backup::REMOTE_ERROR,
+            "Backup not found on the server",
+        )
+        .with_details(file_name.clone())
+    })?;
+
+    let manifest = backup::restore_archive(&bytes)?;
+
+    // The restored state is now our baseline for conflict detection
+    state.last_pointer = pointer;
+    state.save()?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pointer(created_at: u64, install_id: &str) -> RemotePointer {
+        RemotePointer {
+            file_name: "x.zip".into(),
+            created_at,
+            install_id: install_id.into(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://cloud.school.it/dav").unwrap(),
+            "https://cloud.school.it/dav/"
+        );
+        assert!(normalize_url("http://cloud.school.it/dav").is_err());
+        assert!(normalize_url("http://localhost:8080/dav/").is_ok());
+    }
+
+    #[test]
+    fn test_detect_conflict() {
+        // Nothing on the server yet
+        assert!(!detect_conflict(None, None, "me"));
+        // Our own pointer never conflicts
+        assert!(!detect_conflict(Some(&pointer(10, "me")), None, "me"));
+        // Another PC uploaded and we never synced
+        assert!(detect_conflict(Some(&pointer(10, "other")), None, "me"));
+        // Another PC uploaded after what we last saw
+        assert!(detect_conflict(
+            Some(&pointer(20, "other")),
+            Some(&pointer(10, "other")),
+            "me"
+        ));
+        // Already seen
+        assert!(!detect_conflict(
+            Some(&pointer(10, "other")),
+            Some(&pointer(10, "other")),
+            "me"
+        ));
+    }
+
+    #[test]
+    fn test_retry_stops_on_non_network_errors() {
+        let mut calls = 0;
+        let result: Result<(), BackendError> = with_retry(|| {
+            calls += 1;
+            Err(BackendError::new(errors::backup::REMOTE_ERROR, "boom"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
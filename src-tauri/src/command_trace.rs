@@ -0,0 +1,266 @@
+//! Opt-in trace of command invocations
+//!
+//! For field reports like "save_config silently not called": with the
+//! `command_trace` setting on, every invoke reaching the backend is kept
+//! in memory (newest `MAX_ENTRIES`) with its window, sanitized arguments,
+//! time spent in the handler and outcome.
+//!
+//! The outcome is what the IPC boundary sees: a command refused by a guard
+//! (app lock, role, observer window, read-only mode) records the guard's
+//! error code, a command name the backend doesn't know records `unknown`,
+//! anything else `dispatched`. Tauri doesn't hand the response back to the
+//! handler, so errors returned by the command itself are not traced. The
+//! duration covers the whole call for synchronous commands; async ones
+//! (`run_blocking`, jobs) are only scheduled within it.
+//!
+//! Arguments that look like secrets (PINs, passwords, tokens) are
+//! redacted and long strings and lists are cut, so a trace can be attached
+//! to a bug report.
+
+use crate::clock;
+use crate::errors::BackendError;
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::ipc::{Invoke, InvokeBody, InvokeResolver};
+use tauri::Runtime;
+
+const CONFIG_KEY: &str = "command_trace";
+
+/// Invocations kept
+pub const MAX_ENTRIES: usize = 1000;
+
+/// Longest string argument kept whole
+const MAX_STRING_CHARS: usize = 200;
+/// Longest list argument kept whole
+const MAX_LIST_ITEMS: usize = 20;
+
+/// Not traced: reading the trace, and the noise meter reporting
+/// continuously
+const UNTRACED: &[&str] = &["get_command_trace", "report_noise_level"];
+
+/// Argument names (lower case) redacted when they contain one of these
+const SECRET_MARKERS: &[&str] = &["password", "passphrase", "secret", "token", "accesskey"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+static ENTRIES: Mutex<VecDeque<TraceEntry>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    /// Code of the guard rejection during the current invoke
+    static REJECTION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// How an invoke ended at the IPC boundary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TraceOutcome {
+    /// Passed the guards and reached the command
+    Dispatched,
+    /// Refused by a guard
+    Rejected { code: String },
+    /// No command with this name
+    Unknown,
+}
+
+/// One invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEntry {
+    pub seq: u64,
+    pub at: u64,
+    pub command: String,
+    /// Label of the calling window
+    pub window: String,
+    pub args: Value,
+    pub duration_ms: f64,
+    pub outcome: TraceOutcome,
+}
+
+/// The trace and whether it is recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTrace {
+    pub enabled: bool,
+    /// Newest first
+    pub entries: Vec<TraceEntry>,
+}
+
+/// Restore the setting at startup
+pub fn start() {
+    let enabled = file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Turn tracing on or off (kept across restarts)
+pub fn set_enabled(enabled: bool) -> Result<(), BackendError> {
+    file_ops::save_config(CONFIG_KEY, Value::Bool(enabled))?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The newest `limit` entries (all when `None`)
+pub fn get_command_trace(limit: Option<usize>) -> CommandTrace {
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    CommandTrace {
+        enabled: ENABLED.load(Ordering::Relaxed),
+        entries: entries
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(MAX_ENTRIES))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with("pin") || SECRET_MARKERS.iter().any(|m| name.contains(m))
+}
+
+/// Copy of `value` with secrets redacted and long strings and lists cut
+fn sanitize(value: &Value) -> Value {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_STRING_CHARS => Value::String(format!(
+            "{}… ({} chars)",
+            s.chars().take(MAX_STRING_CHARS).collect::<String>(),
+            s.chars().count()
+        )),
+        Value::Array(items) => {
+            let mut kept: Vec<Value> = items.iter().take(MAX_LIST_ITEMS).map(sanitize).collect();
+            if items.len() > MAX_LIST_ITEMS {
+                kept.push(Value::String(format!(
+                    "… ({} more)",
+                    items.len() - MAX_LIST_ITEMS
+                )));
+            }
+            Value::Array(kept)
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, v)| {
+                    let v = if is_secret(name) && !v.is_null() {
+                        Value::String("[redacted]".to_string())
+                    } else {
+                        sanitize(v)
+                    };
+                    (name.clone(), v)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn record(entry: TraceEntry) {
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    entries.push_back(entry);
+    while entries.len() > MAX_ENTRIES {
+        entries.pop_front();
+    }
+}
+
+/// Reject an invoke from a guard, noting the code for the trace
+pub fn reject<R: Runtime>(resolver: InvokeResolver<R>, error: BackendError) {
+    REJECTION.with(|r| *r.borrow_mut() = Some(error.code.clone()));
+    resolver.reject(error);
+}
+
+/// Wrap the app's command handler (outside the other guards) with tracing
+pub fn trace_guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let command = invoke.message.command().to_string();
+        if !ENABLED.load(Ordering::Relaxed) || UNTRACED.contains(&command.as_str()) {
+            return handler(invoke);
+        }
+        let window = invoke.message.webview_ref().label().to_string();
+        let args = match invoke.message.payload() {
+            InvokeBody::Json(value) => sanitize(value),
+            InvokeBody::Raw(bytes) => Value::String(format!("<{} bytes>", bytes.len())),
+        };
+        let at = clock::now_millis();
+        let started = Instant::now();
+        REJECTION.with(|r| r.borrow_mut().take());
+
+        let handled = handler(invoke);
+
+        let outcome = match REJECTION.with(|r| r.borrow_mut().take()) {
+            Some(code) => TraceOutcome::Rejected { code },
+            None if handled => TraceOutcome::Dispatched,
+            None => TraceOutcome::Unknown,
+        };
+        record(TraceEntry {
+            seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+            at,
+            command,
+            window,
+            args,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            outcome,
+        });
+        handled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize_args() {
+        let args = json!({
+            "pin": "1234",
+            "newPin": "5678",
+            "passphrase": "correct horse",
+            "secretAccessKey": "abc",
+            "token": null,
+            "mapping": { "next": "ArrowRight" },
+            "note": "x".repeat(MAX_STRING_CHARS + 5),
+            "students": (0..25).collect::<Vec<_>>(),
+        });
+        let clean = sanitize(&args);
+        for key in ["pin", "newPin", "passphrase", "secretAccessKey"] {
+            assert_eq!(clean[key], "[redacted]", "{}", key);
+        }
+        assert_eq!(clean["token"], Value::Null);
+        assert_eq!(clean["mapping"]["next"], "ArrowRight");
+        assert!(clean["note"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("… ({} chars)", MAX_STRING_CHARS + 5)));
+        let students = clean["students"].as_array().unwrap();
+        assert_eq!(students.len(), MAX_LIST_ITEMS + 1);
+        assert_eq!(students[MAX_LIST_ITEMS], "… (5 more)");
+    }
+
+    #[test]
+    fn test_trace_keeps_newest() {
+        for seq in 0..MAX_ENTRIES as u64 + 3 {
+            record(TraceEntry {
+                seq,
+                at: 0,
+                command: "save_config".into(),
+                window: "main".into(),
+                args: Value::Null,
+                duration_ms: 0.0,
+                outcome: TraceOutcome::Dispatched,
+            });
+        }
+        let trace = get_command_trace(Some(2));
+        let seqs: Vec<u64> = trace.entries.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [MAX_ENTRIES as u64 + 2, MAX_ENTRIES as u64 + 1]);
+        assert_eq!(get_command_trace(None).entries.len(), MAX_ENTRIES);
+    }
+}
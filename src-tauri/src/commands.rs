@@ -7,12 +7,104 @@
 //! const result = await invoke('read_csv', { path: '/path/to/file.csv' });
 //! ```
 
-use crate::errors::BackendError;
+use crate::accommodations;
+use crate::actions;
+use crate::agc;
+use crate::ambient_light;
+use crate::analytics;
+use crate::annotation;
+use crate::app_lock;
+use crate::appointments;
+use crate::attachments;
+use crate::audio_output;
+use crate::audio_presets;
+use crate::audio_supervisor;
+use crate::background_audio;
+use crate::backup;
+use crate::badge_reader;
+use crate::badge_scan;
+use crate::class_archive;
+use crate::class_comparison;
+use crate::class_records;
+use crate::classroom_state;
+use crate::clock_sync;
+use crate::cloud;
+use crate::cloud_s3;
+use crate::command_trace;
+use crate::companion_auth;
+use crate::content_protection;
+use crate::controller;
+use crate::data_integrity;
+use crate::day_overview;
+use crate::devices;
+use crate::diagnostics;
+use crate::display_layout;
+use crate::documents;
+use crate::end_of_day;
+use crate::errors::{self, BackendError};
+use crate::event_throttle;
+use crate::exam_mode;
+use crate::exit_tickets;
+use crate::feedback;
 use crate::file_ops;
-use crate::window;
+use crate::file_ops::config_repair;
+use crate::file_ops::data_location;
+use crate::file_ops::import_adapters;
+use crate::file_ops::import_progress::NoProgress;
+use crate::file_ops::import_transforms;
+use crate::forms_import;
+use crate::freeze_broadcast;
+use crate::fuzzy;
+use crate::grade_export;
+use crate::gradebook;
+use crate::hid;
+use crate::image_optimize;
+use crate::import_history;
+use crate::input_mute;
+use crate::insights;
+use crate::jobs;
+use crate::kiosk;
+use crate::lan_network;
+use crate::lan_tls;
+use crate::locale;
+use crate::mailer;
+use crate::mood_checkin;
+use crate::noise_history;
+use crate::observer;
+use crate::ocr;
+use crate::perf_stats;
 use crate::permissions;
+use crate::photos;
+use crate::pointer_highlight;
+use crate::presentation_safe;
+use crate::profile_settings;
+use crate::projector_dim;
+use crate::quick_notes;
+use crate::quiet_hours;
+use crate::read_only_mode;
+use crate::recovery;
+use crate::research_export;
+use crate::roles;
+use crate::roster;
+use crate::roster_import;
+use crate::roster_merge;
+use crate::roster_sync;
+use crate::schedule;
+use crate::screenshot;
+use crate::seating_solver;
+use crate::settings_reset;
+use crate::shutdown;
+use crate::sound_units;
+use crate::startup;
+use crate::state::AppState;
+use crate::substitute;
+use crate::timers;
+use crate::weekly_summary;
+use crate::window;
 use serde_json::Value;
-use tauri::WebviewWindow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::{AppHandle, State, WebviewWindow};
 
 // ============================================================================
 // File Operations Commands
@@ -32,7 +124,7 @@ use tauri::WebviewWindow;
 ///   .catch(err => console.error(err.code)); // e.g., "FILE_NOT_FOUND"
 /// ```
 #[tauri::command]
-pub fn read_csv(path: String) -> Result<Value, BackendError> {
+pub fn read_csv(path: String) -> Result<file_ops::CsvTable, BackendError> {
     file_ops::read_csv(&path)
 }
 
@@ -44,6 +136,7 @@ pub fn read_csv(path: String) -> Result<Value, BackendError> {
 ///
 /// # Returns
 /// Empty result with structured BackendError on failure
+/// (`CONFIG_VALIDATION_FAILED` when the value does not match the key's schema)
 ///
 /// # Example
 /// ```javascript
@@ -59,6 +152,8 @@ pub fn save_config(key: String, value: Value) -> Result<(), BackendError> {
 
 /// Load configuration value
 ///
+/// Served from memory; the config file is only re-read after it changed.
+///
 /// # Arguments
 /// * `key` - Configuration key
 ///
@@ -75,6 +170,73 @@ pub fn load_config(key: String) -> Result<Value, BackendError> {
     file_ops::load_config(&key)
 }
 
+/// Get where the app keeps its files
+///
+/// # Returns
+/// { path, defaultPath, custom, fallback, configuredPath }; `fallback` is
+/// true when the configured directory (e.g. a network drive) was
+/// unreachable at startup and the default one is in use
+///
+/// # Example
+/// ```javascript
+/// const dir = await invoke('get_data_directory');
+/// if (dir.fallback) warn(`${dir.configuredPath} is not reachable`);
+/// ```
+#[tauri::command]
+pub fn get_data_directory() -> Result<data_location::DataDirectoryStatus, BackendError> {
+    data_location::get_data_directory()
+}
+
+/// Move config, data and photos to another directory (roaming profile,
+/// network drive)
+///
+/// Runs as a background job (`job-progress` events, kind `migration`).
+/// Existing app data in the target is used instead of being overwritten;
+/// the old copy stays where it was.
+///
+/// # Arguments
+/// * `path` - Absolute, writable directory; null moves back to the default
+///
+/// # Returns
+/// { status, copiedFiles, adoptedExisting }
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_data_directory', { path: 'H:\\Classroom' });
+/// ```
+#[tauri::command]
+pub async fn set_data_directory(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<data_location::DataDirectoryChange, BackendError> {
+    let change = jobs::run("migration", "Move data directory", move |context| {
+        data_location::set_data_directory(path.as_deref(), context)
+    })
+    .await?;
+    // Moving to a writable folder ends read-only mode
+    read_only_mode::detect(&app);
+    Ok(change)
+}
+
+/// Get whether the app is in read-only mode
+///
+/// Entered at startup when the data directory can't be written; commands
+/// that save data then fail with `READ_ONLY_MODE`. Changes are announced
+/// as `read-only-mode` events.
+///
+/// # Returns
+/// { readOnly, path, reason }
+///
+/// # Example
+/// ```javascript
+/// const { readOnly, reason } = await invoke('get_read_only_status');
+/// if (readOnly) showBanner(reason);
+/// ```
+#[tauri::command]
+pub fn get_read_only_status(state: State<'_, AppState>) -> read_only_mode::ReadOnlyStatus {
+    read_only_mode::get_read_only_status(&state)
+}
+
 // ============================================================================
 // Window Management Commands
 // ============================================================================
@@ -122,6 +284,175 @@ pub fn set_window_position(
     window::set_window_position(&window, constrained)
 }
 
+/// Get the safe-area insets of a monitor (camera notch, rounded corners)
+///
+/// # Arguments
+/// * `monitor_index` - Monitor to query (default: the one the calling window is on)
+///
+/// # Returns
+/// { top, left, bottom, right, scaleFactor }, insets in logical pixels
+/// (all zero outside macOS or on screens without a notch)
+///
+/// # Example
+/// ```javascript
+/// const area = await invoke('get_safe_area');
+/// document.body.style.paddingTop = `${area.top}px`;
+/// ```
+#[tauri::command]
+pub async fn get_safe_area(
+    app: AppHandle,
+    window: WebviewWindow,
+    monitor_index: Option<usize>,
+) -> Result<window::SafeArea, BackendError> {
+    run_blocking(move || window::get_safe_area(&app, &window, monitor_index)).await
+}
+
+/// Get what window management works in this session
+///
+/// On Linux this depends on the display server: under native Wayland the
+/// compositor ignores always-on-top and window positions.
+///
+/// # Returns
+/// { displayServer, session, desktop, strategy, alwaysOnTop,
+///   absolutePositioning, monitorTargeting, clickThrough, hints }
+///
+/// # Example
+/// ```javascript
+/// const caps = await invoke('get_window_capabilities');
+/// if (!caps.alwaysOnTop) showHints(caps.hints);
+/// ```
+#[tauri::command]
+pub fn get_window_capabilities() -> window::WindowCapabilities {
+    window::get_window_capabilities()
+}
+
+/// Get the saved zoom factor of a window
+///
+/// # Arguments
+/// * `window_label` - e.g. "main", "annotation", "observer"
+///
+/// # Returns
+/// The factor (1.0 when none is saved)
+///
+/// # Example
+/// ```javascript
+/// const zoom = await invoke('get_webview_zoom', { windowLabel: 'annotation' });
+/// ```
+#[tauri::command]
+pub fn get_webview_zoom(window_label: String) -> f64 {
+    window::get_webview_zoom(&window_label)
+}
+
+/// Zoom a window's content, e.g. to make the projector readable from the
+/// back of the room, without changing the other windows
+///
+/// The factor is saved for that window and applied whenever it loads.
+///
+/// # Arguments
+/// * `window_label` - Window to zoom
+/// * `factor` - 0.5 to 3.0 (1.0 resets)
+///
+/// # Returns
+/// The factor applied (rounded to 2 decimals)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_webview_zoom', { windowLabel: 'annotation', factor: 1.5 });
+/// ```
+#[tauri::command]
+pub fn set_webview_zoom(
+    app: AppHandle,
+    window_label: String,
+    factor: f64,
+) -> Result<f64, BackendError> {
+    window::set_webview_zoom(&app, &window_label, factor)
+}
+
+/// Hide a window from screen sharing and recordings, e.g. the gradebook
+/// while the teacher shares their screen in a video call
+///
+/// Uses `SetWindowDisplayAffinity` on Windows and the window's
+/// `sharingType` on macOS; the teacher still sees the window. Saved per
+/// window and applied whenever it loads.
+///
+/// # Arguments
+/// * `window_label` - Window to protect
+/// * `enabled` - false to allow capturing it again
+///
+/// # Returns
+/// `{ windowLabel, enabled, supported }`; `supported` is false on Linux,
+/// where compositors offer no such protection
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_content_protection', { windowLabel: 'main', enabled: true });
+/// ```
+#[tauri::command]
+pub fn set_content_protection(
+    app: AppHandle,
+    window_label: String,
+    enabled: bool,
+) -> Result<content_protection::ContentProtection, BackendError> {
+    content_protection::set_content_protection(&app, &window_label, enabled)
+}
+
+/// Get the windows protected from screen capture
+///
+/// # Returns
+/// `{ supported, windows: [label] }`
+#[tauri::command]
+pub fn get_content_protection() -> content_protection::ContentProtectionStatus {
+    content_protection::get_content_protection()
+}
+
+/// Turn presentation-safe mode on or off before sharing the screen in a
+/// video call
+///
+/// On: every window but the projector layers (annotation, dim, pointer)
+/// is protected from capture and hidden. Off: they come back with their
+/// previous protection. Also toggled by the global shortcut
+/// (`Ctrl+Shift+F12` by default) and the `display.presentation_safe`
+/// action. Emits `presentation-safe-changed`.
+///
+/// # Returns
+/// `{ enabled, hidden: [label], shortcut }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_presentation_safe', { enabled: true });
+/// ```
+#[tauri::command]
+pub fn set_presentation_safe(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<presentation_safe::PresentationSafeStatus, BackendError> {
+    presentation_safe::set_presentation_safe(&app, enabled)
+}
+
+/// Get whether presentation-safe mode is on
+#[tauri::command]
+pub fn get_presentation_safe() -> presentation_safe::PresentationSafeStatus {
+    presentation_safe::get_presentation_safe()
+}
+
+/// Change the global shortcut toggling presentation-safe mode
+///
+/// # Arguments
+/// * `shortcut` - Accelerator as printed on the keyboard layout, null for
+///   no shortcut; keys used by the OS or a presenter binding are refused
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_presentation_safe_shortcut', { shortcut: 'Ctrl+Alt+H' });
+/// ```
+#[tauri::command]
+pub fn set_presentation_safe_shortcut(
+    app: AppHandle,
+    shortcut: Option<String>,
+) -> Result<presentation_safe::PresentationSafeStatus, BackendError> {
+    presentation_safe::set_presentation_safe_shortcut(&app, shortcut)
+}
+
 // ============================================================================
 // Permission Commands
 // ============================================================================
@@ -180,6 +511,4027 @@ pub fn request_microphone_permission() -> Result<permissions::PermissionStatus,
     permissions::request_microphone_permission()
 }
 
+/// Check whether the microphone is muted in the OS or by its switch
+///
+/// A flat meter usually means a muted mic rather than a silent class.
+/// `noise-level` events carry the same `muted` flag while the meter runs.
+///
+/// # Returns
+/// `{ muted, volume }`; `muted` is true at zero input volume too, and
+/// `null` when the device doesn't report it; `volume` is 0.0-1.0
+///
+/// # Example
+/// ```javascript
+/// const mic = await invoke('get_input_mute_state');
+/// if (mic.muted) showWarning('Microfono disattivato');
+/// ```
+#[tauri::command]
+pub async fn get_input_mute_state() -> Result<input_mute::InputMuteState, BackendError> {
+    run_blocking(input_mute::get_input_mute_state).await
+}
+
+/// Request permission to capture the screen (window screenshots)
+///
+/// Only macOS asks the user (Screen Recording); on Linux `available`
+/// reports whether `grim` / ImageMagick is installed.
+///
+/// # Example
+/// ```javascript
+/// const status = await invoke('request_screen_capture_permission');
+/// if (!status.granted) showWarning(status.details ?? status.message);
+/// ```
+#[tauri::command]
+pub fn request_screen_capture_permission() -> Result<permissions::PermissionStatus, BackendError> {
+    permissions::request_screen_capture_permission()
+}
+
+/// Check camera access (no prompt)
+///
+/// # Returns
+/// PermissionStatus { granted, available, message, details }
+///
+/// # Example
+/// ```javascript
+/// const camera = await invoke('get_camera_permission');
+/// autoTheme.disabled = !camera.granted;
+/// ```
+#[tauri::command]
+pub fn get_camera_permission() -> permissions::PermissionStatus {
+    permissions::camera_permission()
+}
+
+/// Estimate the room brightness from one camera frame, to pick the
+/// projector theme (needs the camera permission and ffmpeg)
+///
+/// # Returns
+/// { brightness (0-1), suggestedTheme: "lightHighContrast" | "darkHighContrast" | null }
+///
+/// # Example
+/// ```javascript
+/// const light = await invoke('sample_ambient_light');
+/// if (light.suggestedTheme) setProjectorTheme(light.suggestedTheme);
+/// ```
+#[tauri::command]
+pub async fn sample_ambient_light() -> Result<ambient_light::AmbientLight, BackendError> {
+    run_blocking(ambient_light::sample_ambient_light).await
+}
+
+/// Start reading student ID cards (Code 128 or QR) from the camera,
+/// e.g. at the classroom door
+///
+/// Emits `badge-scanned` `{ studentId, studentName, classId, code }` when
+/// a card's code is a student's id or school email, and `badge-unknown`
+/// `{ code }` otherwise. A card held up counts once. Needs the camera
+/// permission and ffmpeg.
+///
+/// # Arguments
+/// * `class_id` - Only match students of this class (all classes if omitted)
+///
+/// # Returns
+/// { running, classId }
+///
+/// # Example
+/// ```javascript
+/// await listen('badge-scanned', ({ payload }) => markPresent(payload.studentId));
+/// await invoke('start_badge_scan', { classId: '3A' });
+/// ```
+#[tauri::command]
+pub async fn start_badge_scan(
+    app: AppHandle,
+    class_id: Option<String>,
+) -> Result<badge_scan::BadgeScanStatus, BackendError> {
+    run_blocking(move || badge_scan::start_badge_scan(&app, class_id)).await
+}
+
+/// Stop reading ID cards and release the camera
+#[tauri::command]
+pub async fn stop_badge_scan() -> Result<(), BackendError> {
+    run_blocking(|| {
+        badge_scan::stop_badge_scan();
+        Ok(())
+    })
+    .await
+}
+
+/// Get whether ID cards are being read
+///
+/// # Returns
+/// { running, classId }
+#[tauri::command]
+pub fn get_badge_scan_status() -> badge_scan::BadgeScanStatus {
+    badge_scan::get_badge_scan_status()
+}
+
+/// Get the USB badge reader settings and whether it is listened to
+///
+/// # Returns
+/// { config: { enabled, prefix, suffix, minLength, maxKeyIntervalMs, classId, allowWithoutPrefix }, listening }
+#[tauri::command]
+pub fn get_badge_reader() -> badge_reader::BadgeReaderStatus {
+    badge_reader::get_badge_reader()
+}
+
+/// Set up a keyboard-wedge badge reader (USB RFID or barcode reader)
+///
+/// While enabled, codes the reader types are picked up even when another
+/// application has focus: the student is recorded present today and
+/// `badge-scanned` is emitted to the main window with `recorded: true`
+/// (`badge-unknown` `{ codeLength }` when the code matches nobody). Needs
+/// the Input Monitoring permission on macOS and the `input` group on Linux.
+///
+/// # Arguments
+/// * `config` - { enabled, prefix, suffix ("\n" for Enter), minLength,
+///   maxKeyIntervalMs (10-200), classId, allowWithoutPrefix }; enabling
+///   needs a prefix unless `allowWithoutPrefix` is set
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_badge_reader', {
+///   config: { enabled: true, prefix: '%', suffix: '\n', minLength: 8, maxKeyIntervalMs: 40, classId: null, allowWithoutPrefix: false },
+/// });
+/// ```
+#[tauri::command]
+pub async fn set_badge_reader(
+    app: AppHandle,
+    config: badge_reader::BadgeReaderConfig,
+) -> Result<badge_reader::BadgeReaderStatus, BackendError> {
+    run_blocking(move || badge_reader::set_badge_reader(&app, config)).await
+}
+
+// ============================================================================
+// Exit Ticket Commands
+// ============================================================================
+
+/// Open an exit ticket with a prompt for the current lesson
+///
+/// # Arguments
+/// * `prompt` - Question shown on companion devices
+/// * `lesson_id` - Lesson to attach responses to (generated if omitted)
+///
+/// # Returns
+/// The opened session, or `EXIT_TICKET_ALREADY_OPEN` if one is running
+///
+/// # Example
+/// ```javascript
+/// const session = await invoke('start_exit_ticket', {
+///   prompt: 'Cosa hai imparato oggi?'
+/// });
+/// console.log(session.lesson_id);
+/// ```
+#[tauri::command]
+pub fn start_exit_ticket(
+    prompt: String,
+    lesson_id: Option<String>,
+) -> Result<exit_tickets::ExitTicketSession, BackendError> {
+    exit_tickets::start_exit_ticket(&prompt, lesson_id)
+}
+
+/// Close the open exit ticket and return the collected responses
+///
+/// # Example
+/// ```javascript
+/// const session = await invoke('close_exit_ticket');
+/// console.log(`${session.responses.length} responses`);
+/// ```
+#[tauri::command]
+pub fn close_exit_ticket() -> Result<exit_tickets::ExitTicketSession, BackendError> {
+    exit_tickets::close_exit_ticket()
+}
+
+/// Submit a response to the open exit ticket
+///
+/// Entry point for companion devices; the device token is verified, then
+/// text is length-checked and passed through the profanity filter
+/// configured in `exit_ticket_filter`.
+///
+/// # Example
+/// ```javascript
+/// await invoke('submit_exit_ticket', { token, text: 'Le frazioni' })
+///   .catch(err => console.error(err.code)); // e.g., "EXIT_TICKET_NOT_OPEN"
+/// ```
+#[tauri::command]
+pub fn submit_exit_ticket(
+    token: String,
+    text: String,
+) -> Result<exit_tickets::ExitTicketResponse, BackendError> {
+    exit_tickets::submit_exit_ticket(&token, &text)
+}
+
+/// Get all exit ticket sessions stored for a lesson
+///
+/// # Example
+/// ```javascript
+/// const sessions = await invoke('get_exit_tickets', { lessonId: 'lesson_1712345678' });
+/// ```
+#[tauri::command]
+pub fn get_exit_tickets(
+    lesson_id: String,
+) -> Result<Vec<exit_tickets::ExitTicketSession>, BackendError> {
+    exit_tickets::get_exit_tickets(&lesson_id)
+}
+
+// ============================================================================
+// Mood Check-in Commands
+// ============================================================================
+
+/// Open an anonymous mood check-in on companion devices
+///
+/// Only the number of answers per choice is stored, never which device
+/// gave which answer.
+///
+/// # Arguments
+/// * `options` - `{ prompt, scale, lessonId }`; `scale` lists 2-7 choices
+///   from worst to best (default 😞 🙁 😐 🙂 😄), `lessonId` is generated if
+///   omitted
+///
+/// # Returns
+/// The opened check-in, or `MOOD_CHECKIN_ALREADY_OPEN` if one is running
+///
+/// # Example
+/// ```javascript
+/// const checkin = await invoke('start_mood_checkin', {
+///   options: { prompt: 'Come ti senti oggi?', lessonId }
+/// });
+/// ```
+#[tauri::command]
+pub fn start_mood_checkin(
+    options: mood_checkin::MoodOptions,
+) -> Result<mood_checkin::MoodCheckin, BackendError> {
+    mood_checkin::start_mood_checkin(&options)
+}
+
+/// Answer the open mood check-in
+///
+/// Entry point for companion devices; the device token is verified and
+/// each device is counted once.
+///
+/// # Arguments
+/// * `choice` - Index into the check-in's scale
+///
+/// # Example
+/// ```javascript
+/// await invoke('submit_mood_checkin', { token, choice: 3 })
+///   .catch(err => console.error(err.code)); // e.g., "MOOD_CHECKIN_ALREADY_ANSWERED"
+/// ```
+#[tauri::command]
+pub fn submit_mood_checkin(token: String, choice: usize) -> Result<(), BackendError> {
+    mood_checkin::submit_mood_checkin(&token, choice)
+}
+
+/// Close the open mood check-in and return its results
+///
+/// # Returns
+/// `{ id, lessonId, prompt, scale, counts, openedAt, closedAt }`
+#[tauri::command]
+pub fn close_mood_checkin() -> Result<mood_checkin::MoodCheckin, BackendError> {
+    mood_checkin::close_mood_checkin()
+}
+
+/// Get the mood check-ins stored for a lesson
+#[tauri::command]
+pub fn get_mood_checkins(
+    lesson_id: String,
+) -> Result<Vec<mood_checkin::MoodCheckin>, BackendError> {
+    mood_checkin::get_mood_checkins(&lesson_id)
+}
+
+// ============================================================================
+// Exam Mode Commands
+// ============================================================================
+
+/// Start an exam: the main window goes fullscreen and is watched
+///
+/// Losing focus or leaving fullscreen is logged and emitted as
+/// `exam-integrity-event` until `stop_exam_mode`. If the log can't be
+/// saved, `exam-log-error` carries the error.
+///
+/// # Arguments
+/// * `title` - Name of the exam, shown in the exported log
+/// * `class_id` - Class taking it, if any
+/// * `reassert_fullscreen` - Put fullscreen back when it is left
+///
+/// # Example
+/// ```javascript
+/// await invoke('start_exam_mode', { title: 'Verifica di storia', classId, reassertFullscreen: true });
+/// await listen('exam-integrity-event', (e) => console.log(e.payload.kind, e.payload.awayMs));
+/// ```
+#[tauri::command]
+pub fn start_exam_mode(
+    app: AppHandle,
+    title: String,
+    class_id: Option<String>,
+    reassert_fullscreen: Option<bool>,
+) -> Result<exam_mode::ExamSession, BackendError> {
+    exam_mode::start_exam_mode(
+        &app,
+        &title,
+        class_id.as_deref(),
+        reassert_fullscreen.unwrap_or(true),
+    )
+}
+
+/// End the running exam and return its integrity log (teacher only)
+#[tauri::command]
+pub fn stop_exam_mode(app: AppHandle) -> Result<exam_mode::ExamSession, BackendError> {
+    exam_mode::stop_exam_mode(&app)
+}
+
+/// Get the running exam (null if none)
+#[tauri::command]
+pub fn get_exam_status() -> Option<exam_mode::ExamSession> {
+    exam_mode::get_exam_status()
+}
+
+/// Get past and running exams with their integrity logs, newest first
+#[tauri::command]
+pub fn list_exam_sessions() -> Result<Vec<exam_mode::ExamSession>, BackendError> {
+    exam_mode::list_exam_sessions()
+}
+
+/// Export the integrity log of an exam to a CSV file (teacher only)
+///
+/// # Returns
+/// The path written
+///
+/// # Example
+/// ```javascript
+/// await invoke('export_exam_log', { sessionId: session.id, path: 'D:\\Verifiche\\storia-log.csv' });
+/// ```
+#[tauri::command]
+pub async fn export_exam_log(session_id: String, path: String) -> Result<String, BackendError> {
+    run_blocking(move || exam_mode::export_exam_log(&session_id, &path)).await
+}
+
+// ============================================================================
+// Companion Device Commands
+// ============================================================================
+
+/// Open a pairing window and return the code to show on the projector
+///
+/// # Returns
+/// { code, expires_at } - code is valid for 5 minutes
+///
+/// # Example
+/// ```javascript
+/// const { code } = await invoke('start_device_pairing');
+/// ```
+#[tauri::command]
+pub fn start_device_pairing() -> Result<companion_auth::PairingSession, BackendError> {
+    companion_auth::start_pairing()
+}
+
+/// Close the pairing window before it expires
+#[tauri::command]
+pub fn stop_device_pairing() -> Result<(), BackendError> {
+    companion_auth::stop_pairing()
+}
+
+/// Pair a companion device using the displayed code
+///
+/// # Arguments
+/// * `code` - 6-digit pairing code
+/// * `device_name` - Display name chosen on the device
+///
+/// # Returns
+/// { device_id, token, expires_at } or `INVALID_PAIRING_CODE` / `PAIRING_CLOSED`
+///
+/// # Example
+/// ```javascript
+/// const { token } = await invoke('pair_device', { code: '482913', deviceName: 'Tablet 7' });
+/// ```
+#[tauri::command]
+pub fn pair_device(
+    code: String,
+    device_name: String,
+) -> Result<companion_auth::DeviceToken, BackendError> {
+    companion_auth::pair_device(&code, &device_name)
+}
+
+/// Exchange a still-valid device token for a fresh one
+#[tauri::command]
+pub fn refresh_device_token(token: String) -> Result<companion_auth::DeviceToken, BackendError> {
+    companion_auth::refresh_device_token(&token)
+}
+
+/// List paired companion devices (including revoked ones)
+#[tauri::command]
+pub fn list_devices() -> Result<Vec<companion_auth::CompanionDevice>, BackendError> {
+    companion_auth::list_devices()
+}
+
+/// Rename a paired companion device
+#[tauri::command]
+pub fn rename_device(device_id: String, name: String) -> Result<(), BackendError> {
+    companion_auth::rename_device(&device_id, &name)
+}
+
+/// Revoke a companion device; its tokens stop working immediately
+///
+/// # Example
+/// ```javascript
+/// await invoke('revoke_device', { id: 'dev_3fa2c01b9e4d7a55' });
+/// ```
+#[tauri::command]
+pub fn revoke_device(id: String) -> Result<(), BackendError> {
+    companion_auth::revoke_device(&id)
+}
+
+// ============================================================================
+// LAN TLS Commands
+// ============================================================================
+
+/// Get LAN TLS status and certificate fingerprint
+///
+/// # Returns
+/// { enabled, fingerprint, created_at } - fingerprint is SHA-256, colon-separated
+#[tauri::command]
+pub fn get_tls_info() -> Result<lan_tls::TlsInfo, BackendError> {
+    lan_tls::get_tls_info()
+}
+
+/// Enable or disable TLS for the LAN channel
+///
+/// Enabling generates a self-signed certificate on first use.
+///
+/// # Example
+/// ```javascript
+/// const info = await invoke('set_lan_tls_enabled', { enabled: true });
+/// console.log(info.fingerprint);
+/// ```
+#[tauri::command]
+pub fn set_lan_tls_enabled(enabled: bool) -> Result<lan_tls::TlsInfo, BackendError> {
+    lan_tls::set_enabled(enabled)
+}
+
+/// Replace the LAN certificate; paired devices must rescan the QR code
+#[tauri::command]
+pub fn regenerate_tls_certificate() -> Result<lan_tls::TlsInfo, BackendError> {
+    lan_tls::regenerate_certificate()?;
+    lan_tls::get_tls_info()
+}
+
+/// Get the string to render as the pairing QR code
+///
+/// Contains the open pairing code and, when TLS is enabled, the certificate
+/// fingerprint so companion apps can pin it.
+///
+/// # Example
+/// ```javascript
+/// await invoke('start_device_pairing');
+/// const payload = await invoke('get_pairing_qr_payload');
+/// // "classroom://pair?code=482913&tls=1&fp=3FA2..."
+/// ```
+#[tauri::command]
+pub fn get_pairing_qr_payload() -> Result<String, BackendError> {
+    lan_tls::get_pairing_qr_payload()
+}
+
+// ============================================================================
+// LAN Network Commands
+// ============================================================================
+
+/// List local network interfaces (IPv4 and private addresses first)
+///
+/// # Example
+/// ```javascript
+/// const ifaces = await invoke('list_network_interfaces');
+/// // [{ name: 'Ethernet', ip: '192.168.1.20', is_ipv4: true, is_private: true, ... }]
+/// ```
+#[tauri::command]
+pub fn list_network_interfaces() -> Result<Vec<lan_network::NetworkInterface>, BackendError> {
+    lan_network::list_network_interfaces()
+}
+
+/// Get the interface/port the LAN server binds to
+#[tauri::command]
+pub fn get_lan_bind_config() -> lan_network::LanBindConfig {
+    lan_network::get_bind_config()
+}
+
+/// Bind the LAN server to a specific interface and port
+///
+/// # Arguments
+/// * `interface` - Interface name from `list_network_interfaces`, or null for all
+/// * `port` - TCP port (1024-65535)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_lan_bind_config', { interface: 'Ethernet', port: 8765 })
+///   .catch(err => console.error(err.code)); // e.g., "NETWORK_INTERFACE_NOT_FOUND"
+/// ```
+#[tauri::command]
+pub fn set_lan_bind_config(
+    interface: Option<String>,
+    port: u16,
+) -> Result<lan_network::LanBindConfig, BackendError> {
+    lan_network::set_bind_config(lan_network::LanBindConfig { interface, port })
+}
+
+/// Check whether devices are likely able to reach the LAN server
+///
+/// # Returns
+/// List of { code, severity, message } hints (e.g. FIREWALL_ACTIVE, PORT_IN_USE)
+#[tauri::command]
+pub fn check_lan_reachability() -> Result<Vec<lan_network::NetworkHint>, BackendError> {
+    lan_network::check_network_reachability(&lan_network::get_bind_config())
+}
+
+/// Serve a read-only page on the LAN, e.g. for parents waiting in the
+/// corridor on parents' evening
+///
+/// Binds to the interface of the LAN server (`set_lan_bind_config`) on its
+/// own port. The page refreshes itself and needs no login, so anyone on
+/// the network can read it. While LAN TLS is enabled
+/// (`set_lan_tls_enabled`) it is served over HTTPS with the LAN
+/// certificate.
+///
+/// # Arguments
+/// * `content_id` - "notice", "schedule" (today's bell schedule) or "queue"
+///   (the conference queue, see `add_appointment`)
+/// * `port` - TCP port (1024-65535, default 8767)
+/// * `notice` - Text shown on top of the page (up to 500 characters)
+///
+/// # Returns
+/// { running, contentId, notice, port, tls, urls }
+///
+/// # Example
+/// ```javascript
+/// const kiosk = await invoke('start_kiosk_page', {
+///   contentId: 'schedule',
+///   port: 8767,
+///   notice: 'Colloqui in aula 12',
+/// });
+/// showQrPoster(kiosk.urls[0]);
+/// ```
+#[tauri::command]
+pub fn start_kiosk_page(
+    state: State<'_, AppState>,
+    content_id: kiosk::KioskContent,
+    port: Option<u16>,
+    notice: Option<String>,
+) -> Result<kiosk::KioskStatus, BackendError> {
+    state.kiosk().start(content_id, port, notice)
+}
+
+/// Stop serving the kiosk page
+#[tauri::command]
+pub fn stop_kiosk_page(state: State<'_, AppState>) {
+    state.kiosk().stop();
+}
+
+/// Get whether the kiosk page is served, and at which addresses
+///
+/// # Returns
+/// { running, contentId, notice, port, urls }
+#[tauri::command]
+pub fn get_kiosk_status(state: State<'_, AppState>) -> kiosk::KioskStatus {
+    state.kiosk().status()
+}
+
+/// Add a parent-teacher conference to the queue
+///
+/// Booked appointments are kept in time order; walk-ins (no `time`) join
+/// the end. The name is shown on the projector and on the kiosk page.
+///
+/// # Arguments
+/// * `name` - Name to display, e.g. "Fam. Rossi" (up to 80 characters)
+/// * `student_id` - Student the conference is about
+/// * `slot_minutes` - Length of the slot (1-120, default 10)
+/// * `time` - Booked start today, "HH:MM"
+///
+/// # Returns
+/// The queue: { current, waiting: [{ number, name, studentId, slotMinutes,
+/// scheduledAt, expectedAt, expectedEndAt, lateMinutes }], done,
+/// runningLateMinutes, updatedAt }
+///
+/// # Example
+/// ```javascript
+/// await invoke('add_appointment', {
+///   name: 'Fam. Rossi',
+///   studentId: 'student_123',
+///   slotMinutes: 15,
+///   time: '17:30',
+/// });
+/// await listen('appointment-queue', (e) => renderQueue(e.payload));
+/// ```
+#[tauri::command]
+pub fn add_appointment(
+    app: AppHandle,
+    name: String,
+    student_id: Option<String>,
+    slot_minutes: Option<u32>,
+    time: Option<String>,
+) -> Result<appointments::QueueState, BackendError> {
+    appointments::add_appointment(&app, &name, student_id, slot_minutes, time.as_deref())
+}
+
+/// Close the conference in progress and call the next one in the queue
+///
+/// # Returns
+/// The queue with recalculated expected times
+#[tauri::command]
+pub fn call_next(app: AppHandle) -> appointments::QueueState {
+    appointments::call_next(&app)
+}
+
+/// Take a waiting appointment off the queue (e.g. a no-show)
+///
+/// # Arguments
+/// * `number` - Ticket number of the appointment
+#[tauri::command]
+pub fn remove_appointment(
+    app: AppHandle,
+    number: u32,
+) -> Result<appointments::QueueState, BackendError> {
+    appointments::remove_appointment(&app, number)
+}
+
+/// Empty the conference queue and restart ticket numbers
+#[tauri::command]
+pub fn clear_appointment_queue(app: AppHandle) -> appointments::QueueState {
+    appointments::clear_queue(&app)
+}
+
+/// Get the conference queue with expected times as of now
+///
+/// The times are recalculated from when the current conference actually
+/// started, so an overrun moves every later appointment.
+#[tauri::command]
+pub fn get_queue_state() -> appointments::QueueState {
+    appointments::get_queue_state()
+}
+
+// ============================================================================
+// Backup & Cloud Commands
+// ============================================================================
+
+/// Run blocking work (network, archives) off the main thread
+async fn run_blocking<T: Send + 'static>(
+    job: impl FnOnce() -> Result<T, BackendError> + Send + 'static,
+) -> Result<T, BackendError> {
+    tauri::async_runtime::spawn_blocking(job)
+        .await
+        .map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Background task failed")
+                .with_details(e.to_string())
+        })?
+}
+
+/// Create a local backup archive of config and data
+///
+/// # Returns
+/// { file_name, path, size, created_at }
+#[tauri::command]
+pub async fn create_backup() -> Result<backup::BackupInfo, BackendError> {
+    jobs::run("backup", "Local backup", |_| {
+        backup::create_local_backup("backup")
+    })
+    .await
+}
+
+/// List local backup archives, newest first
+#[tauri::command]
+pub fn list_backups() -> Result<Vec<backup::BackupInfo>, BackendError> {
+    backup::list_local_backups()
+}
+
+/// Set the passphrase used to encrypt backups (empty string disables encryption)
+///
+/// Stored in the OS keychain; it is required to restore encrypted backups
+/// on another PC.
+#[tauri::command]
+pub fn set_backup_passphrase(passphrase: String) -> Result<(), BackendError> {
+    backup::set_passphrase(&passphrase)
+}
+
+/// Configure a WebDAV/Nextcloud backup target
+///
+/// Verifies the credentials, stores the password in the OS keychain and
+/// the URL/username in config. Requires a backup passphrase
+/// (`BACKUP_PASSPHRASE_REQUIRED` otherwise), so archives are always
+/// encrypted before upload.
+///
+/// # Example
+/// ```javascript
+/// await invoke('configure_webdav', {
+///   url: 'https://cloud.school.it/remote.php/dav/files/rossi/Classroom/',
+///   username: 'rossi',
+///   password: '...'
+/// }).catch(err => console.error(err.code)); // e.g., "CLOUD_REMOTE_ERROR"
+/// ```
+#[tauri::command]
+pub async fn configure_webdav(
+    url: String,
+    username: String,
+    password: String,
+) -> Result<(), BackendError> {
+    run_blocking(move || cloud::configure_webdav(&url, &username, &password)).await
+}
+
+/// Configure an S3-compatible backup target (AWS S3, MinIO, ...)
+///
+/// Verifies the bucket is reachable, stores the secret key in the OS
+/// keychain and makes S3 the active cloud target. Archives larger than
+/// 16 MiB are uploaded in parts. Requires a backup passphrase
+/// (`BACKUP_PASSPHRASE_REQUIRED` otherwise).
+///
+/// # Arguments
+/// * `endpoint` - Server URL without path, e.g. `https://minio.school.it:9000`
+/// * `bucket` - Bucket name
+/// * `region` - Optional region (defaults to `us-east-1`, as MinIO does)
+///
+/// # Example
+/// ```javascript
+/// await invoke('configure_s3', {
+///   endpoint: 'https://minio.school.it:9000',
+///   bucket: 'classroom-backups',
+///   region: null,
+///   accessKeyId: 'rossi',
+///   secretAccessKey: '...'
+/// });
+/// ```
+#[tauri::command]
+pub async fn configure_s3(
+    endpoint: String,
+    bucket: String,
+    region: Option<String>,
+    access_key_id: String,
+    secret_access_key: String,
+) -> Result<(), BackendError> {
+    run_blocking(move || {
+        cloud_s3::configure_s3(
+            &endpoint,
+            &bucket,
+            region.as_deref(),
+            &access_key_id,
+            &secret_access_key,
+        )
+    })
+    .await
+}
+
+/// Create a backup and upload it (plus any archives queued while offline)
+///
+/// # Returns
+/// { created, uploaded, pending, conflict } - `conflict` is set when another
+/// PC pushed a newer backup since the last sync. Fails with
+/// `BACKUP_PASSPHRASE_REQUIRED` until a backup passphrase is set; archives
+/// made without one are never uploaded
+#[tauri::command]
+pub async fn backup_to_cloud() -> Result<cloud::CloudBackupResult, BackendError> {
+    jobs::run("backup", "Cloud backup", |_| cloud::backup_to_cloud()).await
+}
+
+/// Restore the latest cloud backup (or a specific remote file)
+///
+/// The current state is saved as a local `pre-restore` backup first.
+#[tauri::command]
+pub async fn restore_from_cloud(
+    file_name: Option<String>,
+) -> Result<backup::BackupManifest, BackendError> {
+    jobs::run("backup", "Restore from cloud", move |_| {
+        cloud::restore_from_cloud(file_name)
+    })
+    .await
+}
+
+// ============================================================================
+// Roster Commands
+// ============================================================================
+
+/// Get all saved classes with their students
+#[tauri::command]
+pub fn get_classes() -> Result<Vec<roster::ClassData>, BackendError> {
+    roster::list_classes()
+}
+
+/// Preview a roster file before importing it
+///
+/// Auto-detects Argo, Axios and ClasseViva exports (CSV, XLS, XLSX) and
+/// skips their preamble and footer lines.
+///
+/// # Arguments
+/// * `path` - CSV or spreadsheet to read
+/// * `transforms` - Column fix-ups run before the names are read (trim,
+///   titleCase, split, concat, replace), as sent to `import_roster_file`
+///
+/// # Returns
+/// { format, class_name, students, errors } - `format` is one of
+/// "argo", "axios", "classeviva", "teams", "generic"
+///
+/// # Example
+/// ```javascript
+/// const preview = await invoke('preview_roster_import', {
+///   path,
+///   transforms: [{ op: 'split', column: 'Studente', into: ['Cognome', 'Nome'] }],
+/// });
+/// ```
+#[tauri::command]
+pub async fn preview_roster_import(
+    path: String,
+    transforms: Option<Vec<import_transforms::Transform>>,
+) -> Result<import_adapters::RosterImport, BackendError> {
+    run_blocking(move || {
+        import_adapters::import_roster_file(
+            Path::new(&path),
+            &transforms.unwrap_or_default(),
+            &NoProgress,
+        )
+    })
+    .await
+}
+
+/// Whether text recognition of photographed class lists is available
+///
+/// # Returns
+/// { available, engine } - `engine` is the Tesseract version, null when
+/// Tesseract isn't installed
+///
+/// # Example
+/// ```javascript
+/// const { available } = await invoke('get_ocr_status');
+/// setScanButtonVisible(available);
+/// ```
+#[tauri::command]
+pub async fn get_ocr_status() -> Result<ocr::OcrStatus, BackendError> {
+    run_blocking(|| Ok(ocr::get_ocr_status())).await
+}
+
+/// Read a photo or scan of a printed class list into a table
+///
+/// The rows go through the same header search and validation as
+/// `preview_roster_import`; cells read with low confidence are listed so
+/// they can be checked before importing.
+///
+/// # Arguments
+/// * `path` - Image of the list (.png, .jpg, .tif, .bmp)
+///
+/// # Returns
+/// { rows, uncertain: [{ row, column, text, confidence }], import } -
+/// `import` is shaped like the result of `preview_roster_import`
+///
+/// # Example
+/// ```javascript
+/// const table = await invoke('ocr_image_to_table', { path });
+/// showRosterPreview(table.import.students, table.uncertain);
+/// ```
+#[tauri::command]
+pub async fn ocr_image_to_table(path: String) -> Result<ocr::OcrTable, BackendError> {
+    run_blocking(move || ocr::ocr_image_to_table(&path)).await
+}
+
+/// Import a roster file into a class (created if `class_id` is null)
+///
+/// A file whose content was imported before is not imported again; a
+/// changed version of a file imported before (same file name) becomes a
+/// pending roster update showing the differences. Rows with a missing or
+/// duplicate name are left out and listed in the import report; with
+/// `strict`, any of them rejects the whole file and nothing is saved.
+///
+/// Runs as an "import" job. Besides `job-progress`, it emits `job-stage`
+/// events `{ jobId, name, done, total }` as it goes through the `reading`
+/// (bytes), `decoding` (bytes), `parsing` (rows), `validating` (data rows)
+/// and `persisting` (students) stages.
+///
+/// # Arguments
+/// * `path` - CSV or spreadsheet exported from the electronic registry
+/// * `class_id` - Existing class to update, or null for a new class
+/// * `class_name` - Name for a new class
+/// * `transforms` - Column fix-ups run before the names are read (see
+///   `preview_roster_import`)
+/// * `force` - Import as is, skipping the re-import checks
+/// * `strict` - All or nothing: fail with `IMPORT_STRICT_REJECTED` (details
+///   list every invalid row) instead of leaving the rows out
+///
+/// # Returns
+/// `{ status: "imported", classId, students }`,
+/// `{ status: "alreadyImported", classId, importedAt, reportId }`,
+/// `{ status: "changed", update }` (apply with `apply_roster_update`) or
+/// `{ status: "unchanged", classId }`
+///
+/// # Example
+/// ```javascript
+/// await listen('job-stage', ({ payload }) => {
+///   setImportStatus(payload.name, payload.done, payload.total);
+/// });
+/// const outcome = await invoke('import_roster_file', { path, classId: null, className: '3A' });
+/// if (outcome.status === 'changed') showRosterDiff(outcome.update);
+/// ```
+#[tauri::command]
+pub async fn import_roster_file(
+    path: String,
+    class_id: Option<String>,
+    class_name: String,
+    transforms: Option<Vec<import_transforms::Transform>>,
+    force: Option<bool>,
+    strict: Option<bool>,
+) -> Result<roster_import::RosterImportOutcome, BackendError> {
+    jobs::run("import", "Roster import", move |job| {
+        roster_import::import_roster_file(
+            Path::new(&path),
+            class_id.as_deref(),
+            &class_name,
+            &transforms.unwrap_or_default(),
+            force.unwrap_or(false),
+            strict.unwrap_or(false),
+            job,
+        )
+    })
+    .await
+}
+
+/// Get the reports of past imports, newest first
+///
+/// Covers roster files, watched-folder roster updates and Forms results,
+/// failed imports included.
+///
+/// # Arguments
+/// * `limit` - At most this many reports (default: all kept, up to 200)
+///
+/// # Returns
+/// `[{ id, kind, source: { fileName, hash, size }, format, columns, classId,
+/// rows, imported, skipped, errors, success, failure, startedAt, durationMs }]`
+///
+/// # Example
+/// ```javascript
+/// const [last] = await invoke('get_import_history', { limit: 1 });
+/// console.log(`${last.source.fileName}: ${last.imported} of ${last.rows} rows`);
+/// ```
+#[tauri::command]
+pub fn get_import_history(
+    limit: Option<usize>,
+) -> Result<Vec<import_history::ImportReport>, BackendError> {
+    import_history::get_import_history(limit)
+}
+
+/// Get the watched roster import folder (null if not configured)
+#[tauri::command]
+pub fn get_roster_watch_folder() -> Option<String> {
+    roster_sync::get_watch_folder()
+}
+
+/// Set the folder watched for updated class CSVs (null to stop watching)
+///
+/// Files are matched to classes by name: `3A.csv` updates class "3A".
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_roster_watch_folder', { folder: '\\\\server\\segreteria\\classi' });
+/// await listen('roster-update-available', (event) => {
+///   const { id, className, diff, errors } = event.payload;
+/// });
+/// ```
+#[tauri::command]
+pub fn set_roster_watch_folder(folder: Option<String>) -> Result<(), BackendError> {
+    roster_sync::set_watch_folder(folder.as_deref())
+}
+
+/// Check the watched folder now instead of waiting for the next poll
+///
+/// # Returns
+/// Updates found by this scan (each is also emitted as `roster-update-available`)
+#[tauri::command]
+pub async fn scan_roster_folder(
+    app: AppHandle,
+) -> Result<Vec<roster_sync::RosterUpdate>, BackendError> {
+    jobs::run("sync", "Roster folder scan", move |_| {
+        roster_sync::scan_now(&app)
+    })
+    .await
+}
+
+/// Get roster updates waiting to be applied
+///
+/// # Returns
+/// Array of { id, file_name, class_name, class_id, detected_at, students, errors, diff }
+#[tauri::command]
+pub fn get_pending_roster_updates() -> Result<Vec<roster_sync::RosterUpdate>, BackendError> {
+    roster_sync::get_pending_updates()
+}
+
+/// Apply a pending roster update to its class (creating it if needed)
+///
+/// Students already in the class keep their id, absences and notes. Rows
+/// listed in the update's `errors` are left out.
+///
+/// # Arguments
+/// * `update_id` - Id of the pending update
+/// * `strict` - All or nothing: fail with `IMPORT_STRICT_REJECTED` if the
+///   update has any errors; it stays pending
+///
+/// # Returns
+/// The class id
+#[tauri::command]
+pub fn apply_roster_update(
+    update_id: String,
+    strict: Option<bool>,
+) -> Result<String, BackendError> {
+    roster_sync::apply_update(&update_id, strict.unwrap_or(false))
+}
+
+/// Dismiss a pending roster update
+#[tauri::command]
+pub fn dismiss_roster_update(update_id: String) -> Result<(), BackendError> {
+    roster_sync::dismiss_update(&update_id)
+}
+
+/// Show what merging a list of students into a class would change,
+/// without changing anything
+///
+/// Names are matched as in roster imports: a close spelling is a rename
+/// that keeps the student's id and history. `merge_roster` with the same
+/// arguments makes exactly these changes.
+///
+/// # Arguments
+/// * `class_id` - Class to merge into
+/// * `records` - `[{ name, email? }]`
+/// * `strategy` - "replace" (students missing from the list are removed)
+///   or "append" (they stay)
+///
+/// # Returns
+/// `{ classId, strategy, added, removed: [{ studentId, name }],
+/// renamed: [{ studentId, from, to, score }], emailChanges: [{ studentId,
+/// name, email }], unchanged, errors }`
+///
+/// # Example
+/// ```javascript
+/// const preview = await invoke('preview_roster_merge', { classId, records, strategy: 'replace' });
+/// confirm(`${preview.added.length} added, ${preview.removed.length} removed, ` +
+///   `${preview.renamed.length} renamed`);
+/// ```
+#[tauri::command]
+pub async fn preview_roster_merge(
+    class_id: String,
+    records: Vec<roster_merge::MergeRecord>,
+    strategy: roster_merge::MergeStrategy,
+) -> Result<roster_merge::MergePreview, BackendError> {
+    run_blocking(move || roster_merge::preview_roster_merge(&class_id, &records, strategy)).await
+}
+
+/// Merge a list of students into a class (see `preview_roster_merge`)
+///
+/// # Returns
+/// The changes made, as in `preview_roster_merge`; fails with
+/// `INVALID_ROSTER` when the list has validation errors
+#[tauri::command]
+pub async fn merge_roster(
+    class_id: String,
+    records: Vec<roster_merge::MergeRecord>,
+    strategy: roster_merge::MergeStrategy,
+) -> Result<roster_merge::MergePreview, BackendError> {
+    run_blocking(move || roster_merge::merge_roster(&class_id, &records, strategy)).await
+}
+
+// ============================================================================
+// Document Commands
+// ============================================================================
+
+/// Generate a Word document from a template
+///
+/// Placeholders like `{{student.name}}` are replaced with values from
+/// `data`; `{{#each students}}...{{/each}}` repeats a table row.
+///
+/// # Arguments
+/// * `template_path` - .docx/.dotx template
+/// * `data` - JSON object with the values to merge
+/// * `output_path` - Where to save the generated .docx
+///
+/// # Returns
+/// { path, missing_fields } - placeholders without data are left empty
+///
+/// # Example
+/// ```javascript
+/// await invoke('generate_docx_from_template', {
+///   templatePath: 'C:\\Modelli\\attestato.docx',
+///   data: { class: { name: '3A' }, month: 'Marzo' },
+///   outputPath: 'C:\\Documenti\\attestato-3A.docx'
+/// });
+/// ```
+#[tauri::command]
+pub async fn generate_docx_from_template(
+    template_path: String,
+    data: Value,
+    output_path: String,
+) -> Result<documents::GeneratedDocument, BackendError> {
+    run_blocking(move || {
+        documents::generate_docx_from_template(&template_path, &data, &output_path)
+    })
+    .await
+}
+
+/// Generate one document per student of a class (e.g. parent letters)
+///
+/// Each document receives `data` plus `student`, `class` and `students`.
+/// Files are named after the student.
+#[tauri::command]
+pub async fn generate_class_documents(
+    template_path: String,
+    class_id: String,
+    data: Value,
+    output_dir: String,
+) -> Result<Vec<documents::GeneratedDocument>, BackendError> {
+    jobs::run("report", "Class documents", move |job| {
+        documents::generate_class_documents(
+            &template_path,
+            &class_id,
+            &data,
+            &output_dir,
+            &mut |done, total| {
+                job.check_cancelled()?;
+                job.progress(done, total, format!("{}/{}", done, total));
+                Ok(())
+            },
+        )
+    })
+    .await
+}
+
+// ============================================================================
+// Email & Weekly Summary Commands
+// ============================================================================
+
+/// Configure the SMTP server used to send emails
+///
+/// Verifies the connection and stores the password in the OS keychain.
+///
+/// # Example
+/// ```javascript
+/// await invoke('configure_smtp', {
+///   config: {
+///     host: 'smtp.gmail.com',
+///     port: 587,
+///     security: 'starttls',
+///     username: 'rossi@scuola.edu.it',
+///     from: 'Prof. Rossi <rossi@scuola.edu.it>'
+///   },
+///   password: '...'
+/// });
+/// ```
+#[tauri::command]
+pub async fn configure_smtp(
+    config: mailer::SmtpConfig,
+    password: String,
+) -> Result<(), BackendError> {
+    run_blocking(move || mailer::configure_smtp(config, &password)).await
+}
+
+/// Configure the weekly summary (every Friday at `time`)
+///
+/// # Example
+/// ```javascript
+/// await invoke('configure_weekly_summary', {
+///   options: {
+///     enabled: true,
+///     time: '16:00',
+///     delivery: { type: 'email', to: ['dirigente@scuola.edu.it'] }
+///   }
+/// });
+/// // or delivery: { type: 'folder', path: 'D:\\Riepiloghi' }
+/// ```
+#[tauri::command]
+pub fn configure_weekly_summary(
+    options: weekly_summary::WeeklySummaryOptions,
+) -> Result<(), BackendError> {
+    weekly_summary::configure(options)
+}
+
+/// Get the weekly summary settings
+#[tauri::command]
+pub fn get_weekly_summary_config() -> weekly_summary::WeeklySummaryOptions {
+    weekly_summary::get_options()
+}
+
+/// Get the log of generated summaries, newest first
+///
+/// # Returns
+/// Array of { generated_at, period_start, period_end, path, emailed_to, success, error }
+#[tauri::command]
+pub fn get_weekly_summary_log() -> Result<Vec<weekly_summary::SummaryLogEntry>, BackendError> {
+    weekly_summary::get_log()
+}
+
+/// Generate (and deliver) a summary of the last 7 days now
+#[tauri::command]
+pub async fn generate_weekly_summary_now() -> Result<weekly_summary::SummaryLogEntry, BackendError>
+{
+    jobs::run("report", "Weekly summary", |_| {
+        weekly_summary::generate_now()
+    })
+    .await
+}
+
+/// Set up the end-of-day routine, run every day at `time`
+///
+/// The actions run in a fixed order: stop monitoring, back up (or sync to
+/// the cloud, which includes the backup), write the daily summary, then
+/// lock or quit. When the PC was off at `time`, the next start catches up
+/// on the backup, sync and summary only. An empty `actions` turns the
+/// routine off.
+///
+/// # Arguments
+/// * `time` - Local time, "HH:MM"
+/// * `actions` - Any of "stopMonitoring", "backup", "cloudSync",
+///   "dailySummary", and "lock" or "quit"
+///
+/// # Returns
+/// The saved settings: { enabled, time, actions }. Fails when "lock" is
+/// chosen without an app lock PIN, or "cloudSync" without a cloud target.
+///
+/// # Example
+/// ```javascript
+/// await invoke('configure_end_of_day', {
+///   time: '14:15',
+///   actions: ['stopMonitoring', 'cloudSync', 'dailySummary', 'quit'],
+/// });
+/// await listen('end-of-day', (e) => stopMeter(e.payload));
+/// ```
+#[tauri::command]
+pub fn configure_end_of_day(
+    time: String,
+    actions: Vec<end_of_day::EndOfDayAction>,
+) -> Result<end_of_day::EndOfDayConfig, BackendError> {
+    end_of_day::configure(&time, actions)
+}
+
+/// Get the end-of-day routine settings
+#[tauri::command]
+pub fn get_end_of_day_config() -> end_of_day::EndOfDayConfig {
+    end_of_day::get_config()
+}
+
+/// Get past end-of-day runs, newest first
+///
+/// # Returns
+/// Array of { slot, ranAt, late, steps: [{ action, success, error }] }
+#[tauri::command]
+pub fn get_end_of_day_log() -> Result<Vec<end_of_day::EndOfDayRun>, BackendError> {
+    end_of_day::get_log()
+}
+
+// ============================================================================
+// Class Records & Archive Commands
+// ============================================================================
+
+/// Record today's attendance of a class from its absence flags
+///
+/// # Returns
+/// Number of students recorded
+#[tauri::command]
+pub fn record_attendance(class_id: String) -> Result<usize, BackendError> {
+    class_records::record_attendance(&class_id)
+}
+
+/// Add a behavior entry for a student
+///
+/// # Arguments
+/// * `kind` - "positive", "negative" or "note"
+///
+/// # Example
+/// ```javascript
+/// await invoke('add_behavior_entry', {
+///   classId: 'class_123',
+///   studentId: 'student_1',
+///   kind: 'positive',
+///   note: 'Ha aiutato un compagno'
+/// });
+/// ```
+#[tauri::command]
+pub fn add_behavior_entry(
+    class_id: String,
+    student_id: String,
+    kind: class_records::BehaviorKind,
+    note: String,
+) -> Result<class_records::BehaviorEntry, BackendError> {
+    class_records::add_behavior_entry(&class_id, &student_id, kind, &note)
+}
+
+/// Jot a quick note that goes away on its own
+///
+/// Notes are kept in memory until the end of the running lesson (per the
+/// bell schedule), `ttl` seconds, or an hour outside lessons, unless
+/// promoted to the journal with `promote_quick_note`.
+///
+/// # Arguments
+/// * `text` - The note (up to 500 characters)
+/// * `ttl` - Seconds to keep it (up to 12 hours); null for the lesson
+/// * `class_id`, `student_id` - Who the note is about (needed to promote it)
+///
+/// # Returns
+/// `{ id, text, classId, studentId, createdAt, expiresAt }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('add_quick_note', {
+///   text: 'Marco forgot homework',
+///   ttl: null,
+///   classId: 'class_123',
+///   studentId: 'student_1'
+/// });
+/// ```
+#[tauri::command]
+pub fn add_quick_note(
+    text: String,
+    ttl: Option<u64>,
+    class_id: Option<String>,
+    student_id: Option<String>,
+) -> Result<quick_notes::QuickNote, BackendError> {
+    quick_notes::add_quick_note(&text, ttl, class_id.as_deref(), student_id.as_deref())
+}
+
+/// Get the quick notes not yet expired, oldest first
+#[tauri::command]
+pub fn list_quick_notes() -> Vec<quick_notes::QuickNote> {
+    quick_notes::list_quick_notes()
+}
+
+/// Keep a quick note about a student as a "note" behavior entry
+///
+/// # Returns
+/// The behavior entry; the quick note is removed
+#[tauri::command]
+pub fn promote_quick_note(id: String) -> Result<class_records::BehaviorEntry, BackendError> {
+    quick_notes::promote_quick_note(&id)
+}
+
+/// Save the seating chart of a class (replaces the previous one)
+///
+/// # Example
+/// ```javascript
+/// await invoke('save_seating_chart', {
+///   chart: {
+///     classId: 'class_123',
+///     rows: 5,
+///     columns: 6,
+///     seats: [{ studentId: 'student_1', row: 0, column: 2 }]
+///   }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_seating_chart(chart: class_records::SeatingChart) -> Result<(), BackendError> {
+    class_records::save_seating_chart(chart)
+}
+
+/// Get the seating chart of a class (null if none was saved)
+#[tauri::command]
+pub fn get_seating_chart(
+    class_id: String,
+) -> Result<Option<class_records::SeatingChart>, BackendError> {
+    class_records::get_seating_chart(&class_id)
+}
+
+/// Suggest seating charts for a class, best first
+///
+/// Keeps apart the given pairs and the students whose accommodations say
+/// so, seats front-row students in row 0, and with `balanceByLevel` evens
+/// out the gradebook average of each row. Candidates that still break a
+/// rule list it in `violations`.
+///
+/// # Arguments
+/// * `constraints` - `{ rows, columns, keepApart: [[id, id]], frontRow: [id],
+///   balanceByLevel, candidates }` (grid up to 12x12, 1-10 candidates,
+///   default 3)
+///
+/// # Returns
+/// `[{ chart, violations, levelSpread }]`; `chart` can be passed to
+/// `save_seating_chart`
+///
+/// # Example
+/// ```javascript
+/// const [best] = await invoke('suggest_seating', {
+///   classId,
+///   constraints: { rows: 5, columns: 6, keepApart: [['s1', 's7']], balanceByLevel: true }
+/// });
+/// if (!best.violations.length) await invoke('save_seating_chart', { chart: best.chart });
+/// ```
+#[tauri::command]
+pub async fn suggest_seating(
+    class_id: String,
+    constraints: seating_solver::SeatingConstraints,
+) -> Result<Vec<seating_solver::SeatingCandidate>, BackendError> {
+    run_blocking(move || seating_solver::suggest_seating(&class_id, &constraints)).await
+}
+
+// ============================================================================
+// Accommodation Commands
+// ============================================================================
+
+/// Replace the accommodations of a student
+///
+/// They are enforced by `pick_student`, `generate_groups` and
+/// `save_seating_chart` (row 0 is the front row).
+///
+/// # Arguments
+/// * `accommodations` - `[{ type, details? }]`, `type` one of `noColdCall`,
+///   `frontSeat`, `keepApart` (with `studentId`)
+///
+/// # Returns
+/// The updated student
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_student_accommodations', {
+///   classId, studentId,
+///   accommodations: [
+///     { type: 'noColdCall', details: 'Only volunteers' },
+///     { type: 'keepApart', studentId: 'student_2' }
+///   ]
+/// });
+/// ```
+#[tauri::command]
+pub fn set_student_accommodations(
+    class_id: String,
+    student_id: String,
+    accommodations: Vec<accommodations::Accommodation>,
+) -> Result<roster::Student, BackendError> {
+    accommodations::set_student_accommodations(&class_id, &student_id, accommodations)
+}
+
+/// Pick a random present student, never one flagged "never cold-call"
+///
+/// # Arguments
+/// * `exclude` - Student ids already picked
+///
+/// # Returns
+/// The student, or `NO_ELIGIBLE_STUDENT` when nobody is left
+///
+/// # Example
+/// ```javascript
+/// const student = await invoke('pick_student', { classId, exclude: calledIds });
+/// ```
+#[tauri::command]
+pub fn pick_student(
+    class_id: String,
+    exclude: Option<Vec<String>>,
+) -> Result<roster::Student, BackendError> {
+    accommodations::pick_student(&class_id, &exclude.unwrap_or_default())
+}
+
+/// Split the present students into random groups of at most `size`,
+/// keeping apart the students who must be
+///
+/// # Returns
+/// The groups, or `ACCOMMODATION_VIOLATED` when the pairs can't all be
+/// separated at this size
+///
+/// # Example
+/// ```javascript
+/// const groups = await invoke('generate_groups', { classId, size: 4 });
+/// ```
+#[tauri::command]
+pub fn generate_groups(
+    class_id: String,
+    size: usize,
+) -> Result<Vec<Vec<roster::Student>>, BackendError> {
+    accommodations::generate_groups(&class_id, size)
+}
+
+/// Export a whole class to a zip archive
+///
+/// The archive contains roster.csv, attendance.csv, behavior.csv,
+/// seating.json (when a chart exists), report.pdf and the data needed to
+/// import the class again.
+///
+/// # Arguments
+/// * `class_id` - Class to export
+/// * `path` - Destination .zip file
+///
+/// # Returns
+/// { path, size, manifest }
+///
+/// # Example
+/// ```javascript
+/// const info = await invoke('export_class_archive', {
+///   classId: 'class_123',
+///   path: 'D:\\Archivio\\3A-2025-26.zip'
+/// });
+/// ```
+#[tauri::command]
+pub async fn export_class_archive(
+    class_id: String,
+    path: String,
+) -> Result<class_archive::ClassArchiveInfo, BackendError> {
+    jobs::run("export", "Class archive export", move |_| {
+        class_archive::export_class_archive(&class_id, &path)
+    })
+    .await
+}
+
+/// Preview a class archive before importing it
+///
+/// # Returns
+/// { manifest, students, attendance_days, behavior_entries, has_seating,
+///   collision: { class_id, class_name } | null }
+#[tauri::command]
+pub async fn preview_class_archive(
+    path: String,
+) -> Result<class_archive::ClassArchivePreview, BackendError> {
+    run_blocking(move || class_archive::preview_class_archive(&path)).await
+}
+
+/// Import a class archive created by `export_class_archive`
+///
+/// # Arguments
+/// * `path` - Archive .zip file
+/// * `on_collision` - "replace" or "copy" when the class already exists;
+///   omitted, the import fails with CLASS_ALREADY_EXISTS. "replace" also
+///   fails when the archive's id and name match two different classes;
+///   replacing a class of another id deletes its grades, attachments and
+///   photos
+///
+/// # Returns
+/// Id of the imported class
+///
+/// # Example
+/// ```javascript
+/// const preview = await invoke('preview_class_archive', { path });
+/// const classId = await invoke('import_class_archive', {
+///   path,
+///   onCollision: preview.collision ? 'copy' : null
+/// });
+/// ```
+#[tauri::command]
+pub async fn import_class_archive(
+    path: String,
+    on_collision: Option<class_archive::CollisionStrategy>,
+) -> Result<String, BackendError> {
+    jobs::run("import", "Class archive import", move |_| {
+        class_archive::import_class_archive(&path, on_collision)
+    })
+    .await
+}
+
+// ============================================================================
+// Substitute Pack Commands
+// ============================================================================
+
+/// Build the substitute pack of a class for a day
+///
+/// The pack only holds first names (with a surname initial when two are
+/// the same), the seating chart, the bell schedule of that weekday and the
+/// accommodations without their details. It is kept for the `substitute`
+/// profile, which can only see packs and the classroom tools.
+///
+/// # Arguments
+/// * `date` - `YYYY-MM-DD`
+/// * `name_order` - "givenFirst" (default) or "surnameFirst", how names are
+///   written in the roster
+/// * `pdf_path` - Also print the pack to this .pdf file
+///
+/// # Returns
+/// `{ classId, className, date, generatedAt, students: [{ firstName,
+/// accommodations }], seating: { rows, columns, seats } | null, schedule }`
+///
+/// # Example
+/// ```javascript
+/// const pack = await invoke('generate_substitute_pack', {
+///   classId, date: '2026-10-19', nameOrder: 'surnameFirst', pdfPath: '/tmp/supplenza.pdf'
+/// });
+/// ```
+#[tauri::command]
+pub async fn generate_substitute_pack(
+    class_id: String,
+    date: String,
+    name_order: Option<substitute::NameOrder>,
+    pdf_path: Option<String>,
+) -> Result<substitute::SubstitutePack, BackendError> {
+    run_blocking(move || {
+        substitute::generate_substitute_pack(
+            &class_id,
+            &date,
+            name_order.unwrap_or_default(),
+            pdf_path.as_deref(),
+        )
+    })
+    .await
+}
+
+/// Get the kept substitute packs, newest first
+///
+/// # Example
+/// ```javascript
+/// const [pack] = await invoke('get_substitute_packs');
+/// ```
+#[tauri::command]
+pub fn get_substitute_packs() -> Result<Vec<substitute::SubstitutePack>, BackendError> {
+    substitute::get_substitute_packs()
+}
+
+// ============================================================================
+// Device Inventory Commands
+// ============================================================================
+
+/// Add a shared device (tablet, calculator, ...) to the inventory
+///
+/// # Arguments
+/// * `label` - Label on the device, unique ("Tablet 07")
+/// * `kind` - "tablet", "calculator", ...
+/// * `serial` - Serial number, if known
+///
+/// # Example
+/// ```javascript
+/// const device = await invoke('add_device', { label: 'Tablet 07', kind: 'tablet', serial: null });
+/// ```
+#[tauri::command]
+pub fn add_device(
+    label: String,
+    kind: String,
+    serial: Option<String>,
+) -> Result<devices::Device, BackendError> {
+    devices::add_device(&label, &kind, serial.as_deref())
+}
+
+/// Hand a device to a student for the lesson
+///
+/// A device still held by another student is taken back from them first.
+///
+/// # Returns
+/// { id, deviceId, classId, studentId, assignedAt, returnedAt }
+///
+/// # Example
+/// ```javascript
+/// await invoke('assign_device', { studentId: 'student_1', deviceId: device.id });
+/// ```
+#[tauri::command]
+pub fn assign_device(
+    student_id: String,
+    device_id: String,
+) -> Result<devices::DeviceAssignment, BackendError> {
+    devices::assign_device(&student_id, &device_id)
+}
+
+/// Take a device back
+///
+/// # Returns
+/// The closed handout, or null if nobody had the device
+#[tauri::command]
+pub fn return_device(device_id: String) -> Result<Option<devices::DeviceAssignment>, BackendError> {
+    devices::return_device(&device_id)
+}
+
+/// Report a problem with a device
+///
+/// The student holding the device is recorded with the issue.
+///
+/// # Example
+/// ```javascript
+/// await invoke('report_device_issue', { deviceId: device.id, description: 'Schermo crepato' });
+/// ```
+#[tauri::command]
+pub fn report_device_issue(
+    device_id: String,
+    description: String,
+) -> Result<devices::DeviceIssue, BackendError> {
+    devices::report_device_issue(&device_id, &description)
+}
+
+/// Mark a device issue fixed
+#[tauri::command]
+pub fn resolve_device_issue(issue_id: String) -> Result<devices::DeviceIssue, BackendError> {
+    devices::resolve_device_issue(&issue_id)
+}
+
+/// Get every device with its current holder and open issues
+///
+/// # Returns
+/// Array of { id, label, kind, serial, addedAt, assignment, openIssues },
+/// sorted by label
+#[tauri::command]
+pub fn get_device_inventory() -> Result<Vec<devices::DeviceStatus>, BackendError> {
+    devices::get_device_inventory()
+}
+
+// ============================================================================
+// Background Job Commands
+// ============================================================================
+
+/// List background jobs (running and recently finished), newest first
+///
+/// Backups, syncs, imports and report generation run as jobs; every change
+/// is also emitted as a `job-progress` event.
+///
+/// # Returns
+/// Array of { id, kind, label, state, progress, message, created_at,
+/// finished_at, error } - `state` is one of "queued", "running",
+/// "completed", "failed", "cancelled"
+///
+/// # Example
+/// ```javascript
+/// const jobs = await invoke('list_jobs');
+/// await listen('job-progress', (event) => updateActivityPanel(event.payload));
+/// ```
+#[tauri::command]
+pub fn list_jobs() -> Vec<jobs::JobInfo> {
+    jobs::list_jobs()
+}
+
+/// Get a single background job
+#[tauri::command]
+pub fn get_job(id: String) -> Result<jobs::JobInfo, BackendError> {
+    jobs::get_job(&id)
+}
+
+/// Request cancellation of a background job
+///
+/// Jobs stop at their next checkpoint and end in the "cancelled" state.
+#[tauri::command]
+pub fn cancel_job(id: String) -> Result<jobs::JobInfo, BackendError> {
+    jobs::cancel_job(&id)
+}
+
+// ============================================================================
+// Settings Reset Commands
+// ============================================================================
+
+/// Reset one setting to its default
+///
+/// A `pre-reset` backup is created first.
+///
+/// # Returns
+/// { backup, removed, kept } or null if the setting was not set
+#[tauri::command]
+pub async fn reset_setting(
+    key: String,
+) -> Result<Option<settings_reset::ResetResult>, BackendError> {
+    run_blocking(move || settings_reset::reset_setting(&key)).await
+}
+
+/// Get a confirmation token for `factory_reset` (valid for 2 minutes)
+#[tauri::command]
+pub fn request_factory_reset() -> Result<String, BackendError> {
+    settings_reset::request_factory_reset()
+}
+
+/// Restore all settings to defaults
+///
+/// # Arguments
+/// * `confirm_token` - Token from `request_factory_reset`
+/// * `keep_data` - Keep rosters, exit tickets and other data, photos,
+///   attachments and the profile PINs
+///
+/// PINs and account passwords in the keychain are deleted, except the
+/// backup passphrase.
+///
+/// # Returns
+/// { backup, removed, kept } - `backup` is a regular local backup (see
+/// `list_backups`); `kept` lists the keychain entries left in place
+///
+/// # Example
+/// ```javascript
+/// const token = await invoke('request_factory_reset');
+/// if (confirm('Ripristinare le impostazioni iniziali?')) {
+///   await invoke('factory_reset', { confirmToken: token, keepData: true });
+/// }
+/// ```
+#[tauri::command]
+pub async fn factory_reset(
+    confirm_token: String,
+    keep_data: bool,
+) -> Result<settings_reset::ResetResult, BackendError> {
+    run_blocking(move || settings_reset::factory_reset(&confirm_token, keep_data)).await
+}
+
+/// Get the repair made to a damaged config file during this run
+///
+/// The damage is usually found at startup, before the UI listens for the
+/// `config-recovered` event, so the UI asks on load.
+///
+/// # Returns
+/// `{ recoveredAt, corruptFile, error, recovered, lost }` or null
+///
+/// # Example
+/// ```javascript
+/// const recovery = await invoke('get_config_recovery');
+/// if (recovery?.lost.length) showLostSettings(recovery.lost);
+/// ```
+#[tauri::command]
+pub fn get_config_recovery() -> Option<config_repair::ConfigRecovery> {
+    config_repair::last_recovery()
+}
+
+// ============================================================================
+// Locale Commands
+// ============================================================================
+
+/// Get the OS locale as a BCP 47 tag (e.g. "it-IT")
+#[tauri::command]
+pub fn get_system_locale() -> String {
+    locale::get_system_locale()
+}
+
+/// Get the app language ("it" or "en")
+///
+/// Defaults to the system language when supported, Italian otherwise.
+#[tauri::command]
+pub fn get_app_language() -> locale::Language {
+    locale::app_language()
+}
+
+/// Set the app language used for reports, exports and error messages
+///
+/// # Arguments
+/// * `lang` - "it" or "en" (a locale tag such as "en-GB" is accepted too)
+#[tauri::command]
+pub fn set_app_language(lang: String) -> Result<locale::Language, BackendError> {
+    locale::set_app_language(&lang)
+}
+
+/// Format a timestamp as a date in the app language
+///
+/// # Arguments
+/// * `millis` - Epoch milliseconds
+/// * `style` - "short" (default, 16/10/2026) or "long" (16 ottobre 2026)
+#[tauri::command]
+pub fn format_date(millis: u64, style: Option<locale::DateStyle>) -> String {
+    locale::format_date(millis, style.unwrap_or_default(), locale::app_language())
+}
+
+/// Format a number with the app language's separators
+///
+/// # Example
+/// ```javascript
+/// await invoke('format_number', { value: 1234.5, decimals: 1 }); // "1.234,5"
+/// ```
+#[tauri::command]
+pub fn format_number(value: f64, decimals: Option<usize>) -> String {
+    locale::format_number(value, decimals.unwrap_or(0), locale::app_language())
+}
+
+/// Translate a backend error's message into the app language
+///
+/// Code and details are kept; unknown codes are returned unchanged.
+#[tauri::command]
+pub fn localize_error(error: BackendError) -> BackendError {
+    locale::localize_error(error, locale::app_language())
+}
+
+// ============================================================================
+// Lesson Recovery Commands
+// ============================================================================
+
+/// Save the transient lesson state for crash recovery
+///
+/// Call whenever the timer, noise session or unsaved attendance marks
+/// change; the backend writes the latest state to disk every few seconds.
+/// An empty state removes the recovery file.
+///
+/// # Example
+/// ```javascript
+/// await invoke('save_lesson_state', {
+///   state: { classId: 'class_1', timer: { label: null, durationMs: 600000, remainingMs: 420000, running: true } }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_lesson_state(state: recovery::LessonState) {
+    recovery::save_lesson_state(state)
+}
+
+/// Get the lesson interrupted by the previous run (crash, power cut)
+///
+/// # Returns
+/// `{ savedAt, state }`, or `null` when there is nothing to resume
+///
+/// # Example
+/// ```javascript
+/// const recovery = await invoke('get_recovery_state');
+/// if (recovery && confirm('Riprendere la lezione interrotta?')) {
+///   restoreLesson(recovery.state);
+/// }
+/// await invoke('discard_recovery_state');
+/// ```
+#[tauri::command]
+pub fn get_recovery_state() -> Option<recovery::RecoverySnapshot> {
+    recovery::get_recovery_state()
+}
+
+/// Forget the interrupted lesson once it was resumed or declined
+#[tauri::command]
+pub fn discard_recovery_state() -> Result<(), BackendError> {
+    recovery::discard_recovery_state()
+}
+
+/// Remove the recovery file when the lesson ends normally
+#[tauri::command]
+pub fn clear_lesson_state() -> Result<(), BackendError> {
+    recovery::clear_lesson_state()
+}
+
+/// Get how the previous run ended
+///
+/// # Returns
+/// `{ startedAt, endedAt, clean }`, or `null` on first launch; `clean` is
+/// false when the app was killed or crashed instead of shutting down
+///
+/// # Example
+/// ```javascript
+/// const previous = await invoke('get_previous_session');
+/// if (previous && !previous.clean) {
+///   notify("L'app non è stata chiusa correttamente l'ultima volta.");
+/// }
+/// ```
+#[tauri::command]
+pub fn get_previous_session() -> Option<shutdown::PreviousSession> {
+    shutdown::get_previous_session()
+}
+
+// ============================================================================
+// Audio Supervision Commands
+// ============================================================================
+
+/// Get how failed audio streams are restarted
+///
+/// # Returns
+/// `{ maxRestarts, initialBackoffMs, maxBackoffMs }`
+#[tauri::command]
+pub fn get_audio_restart_policy() -> audio_supervisor::RestartPolicy {
+    audio_supervisor::load_policy()
+}
+
+/// Set how failed audio streams are restarted
+///
+/// Streams report failures with an `audio-stream-error` event; after
+/// `maxRestarts` consecutive failures (`retryInMs: null`) they stay stopped.
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_audio_restart_policy', {
+///   policy: { maxRestarts: 5, initialBackoffMs: 500, maxBackoffMs: 10000 }
+/// });
+/// await listen('audio-stream-error', (e) => {
+///   if (e.payload.retryInMs === null) showError('Monitoraggio audio interrotto');
+/// });
+/// ```
+#[tauri::command]
+pub fn set_audio_restart_policy(
+    policy: audio_supervisor::RestartPolicy,
+) -> Result<(), BackendError> {
+    audio_supervisor::save_policy(&policy)
+}
+
+// ============================================================================
+// Event Throttling Commands
+// ============================================================================
+
+/// Get the maximum rate of each throttled event channel
+///
+/// # Returns
+/// Map of channel name to events per second (0 = unlimited), e.g.
+/// `{ "job-progress": 10, "noise-level": 20, ... }`
+#[tauri::command]
+pub fn get_event_rates() -> BTreeMap<String, u32> {
+    event_throttle::get_rates()
+}
+
+/// Limit how often an event channel is delivered to the UI
+///
+/// Faster events are coalesced; the latest value always arrives.
+///
+/// # Arguments
+/// * `channel` - Event name, e.g. "noise-level"
+/// * `max_per_second` - 1-120, or 0 to disable throttling
+///
+/// # Example
+/// ```javascript
+/// // Slow PC: fewer noise meter updates
+/// await invoke('set_event_rate', { channel: 'noise-level', maxPerSecond: 8 });
+/// ```
+#[tauri::command]
+pub fn set_event_rate(
+    channel: String,
+    max_per_second: u32,
+) -> Result<BTreeMap<String, u32>, BackendError> {
+    event_throttle::set_rate(&channel, max_per_second)
+}
+
+// ============================================================================
+// Diagnostics & Feedback Commands
+// ============================================================================
+
+/// Get resource usage of the app
+///
+/// # Returns
+/// `{ rssBytes, totalMemoryBytes, cpuPercent, eventQueueDepths, activeJobs,
+/// audioUnderruns, dataSizeBytes }`; `cpuPercent` covers the time since
+/// the previous call (0 on the first)
+///
+/// # Example
+/// ```javascript
+/// const stats = await invoke('get_performance_stats');
+/// console.log(`${(stats.rssBytes / 1048576).toFixed(0)} MB`);
+/// ```
+#[tauri::command]
+pub async fn get_performance_stats() -> Result<perf_stats::PerformanceStats, BackendError> {
+    run_blocking(perf_stats::get_performance_stats).await
+}
+
+/// Get the sanitized diagnostics bundle attached to feedback
+///
+/// Lets the user see what would be shared. Credentials, addresses, paths,
+/// class names and data contents are not included.
+#[tauri::command]
+pub async fn get_diagnostics_bundle() -> Result<diagnostics::DiagnosticsBundle, BackendError> {
+    run_blocking(diagnostics::collect).await
+}
+
+/// Check the app's data for damage (runs as a "report" job)
+///
+/// Backs the "Troubleshoot" button. Reads the settings file, every data
+/// collection, attachments (re-hashed) and student photos; nothing is
+/// changed.
+///
+/// # Returns
+/// `{ checkedAt, ok, checked: { config, data, attachments, photos }, issues }`;
+/// each issue has `area`, `target`, `severity` ("warning" / "error"),
+/// `problem`, `suggestion` and an optional `repair` (`{ action: "resetSetting",
+/// key }`, `{ action: "restoreBackup" }`, `{ action: "removeAttachment",
+/// attachmentId }`, `{ action: "reimportPhotos", classId }`)
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('verify_data_integrity');
+/// if (!report.ok) showTroubleshooter(report.issues);
+/// ```
+#[tauri::command]
+pub async fn verify_data_integrity() -> Result<data_integrity::IntegrityReport, BackendError> {
+    jobs::run("report", "Data integrity check", |job| {
+        data_integrity::verify_data_integrity(job)
+    })
+    .await
+}
+
+/// Get the report of the checks run at startup
+///
+/// The app only opens when none failed, so the UI gets to see warnings
+/// here: no audio output, sound packs missing from the build, damaged data
+/// collections.
+///
+/// # Returns
+/// `{ checkedAt, ok, checks: [{ name, status, error }] }`, `status` being
+/// "passed", "warning" or "failed"; `null` before the checks ran
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('get_startup_report');
+/// const warnings = report?.checks.filter(c => c.status === 'warning') ?? [];
+/// ```
+#[tauri::command]
+pub fn get_startup_report() -> Option<startup::StartupReport> {
+    startup::get_startup_report()
+}
+
+/// Send feedback to the developers
+///
+/// Posts to the configured feedback endpoint; when offline (or none is
+/// configured) a zip bundle is saved instead, for attaching to an email.
+///
+/// # Arguments
+/// * `text` - The user's message (1-5000 characters)
+/// * `include_diagnostics` - Attach the sanitized diagnostics bundle
+///
+/// # Returns
+/// `{ delivery: "sent" | "saved", path, sendError }`
+///
+/// # Example
+/// ```javascript
+/// const result = await invoke('submit_feedback', { text, includeDiagnostics: true });
+/// if (result.delivery === 'saved') {
+///   alert(`Salvato in ${result.path}: allegalo a un'email.`);
+/// }
+/// ```
+#[tauri::command]
+pub async fn submit_feedback(
+    text: String,
+    include_diagnostics: bool,
+) -> Result<feedback::FeedbackResult, BackendError> {
+    run_blocking(move || feedback::submit_feedback(&text, include_diagnostics)).await
+}
+
+/// Get the traced command invocations, newest first
+///
+/// Only recorded while tracing is on (see `set_command_trace`). An
+/// invoke refused by the app lock, role, observer window or read-only mode
+/// shows the refusal code; errors returned by the command itself are not
+/// traced.
+///
+/// # Arguments
+/// * `limit` - At most this many entries (default: all kept, up to 1000)
+///
+/// # Returns
+/// `{ enabled, entries: [{ seq, at, command, window, args, durationMs,
+/// outcome: { status: "dispatched" | "rejected" | "unknown", code } }] }`;
+/// secret arguments are redacted
+///
+/// # Example
+/// ```javascript
+/// const trace = await invoke('get_command_trace', { limit: 50 });
+/// const saves = trace.entries.filter(e => e.command === 'save_config');
+/// ```
+#[tauri::command]
+pub fn get_command_trace(limit: Option<usize>) -> command_trace::CommandTrace {
+    command_trace::get_command_trace(limit)
+}
+
+/// Turn command tracing on or off (kept across restarts)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_command_trace', { enabled: true });
+/// ```
+#[tauri::command]
+pub fn set_command_trace(enabled: bool) -> Result<(), BackendError> {
+    command_trace::set_enabled(enabled)
+}
+
+// ============================================================================
+// Analytics Commands
+// ============================================================================
+
+/// Opt in or out of anonymous usage analytics
+///
+/// Off by default. Opting out deletes all counters collected so far.
+#[tauri::command]
+pub fn set_analytics_consent(enabled: bool) -> Result<(), BackendError> {
+    analytics::set_consent(enabled)
+}
+
+/// Count one use of a feature (no-op without consent)
+///
+/// # Arguments
+/// * `feature` - Fixed identifier such as "timer.start" (a-z, 0-9, `.`, `_`, `-`)
+#[tauri::command]
+pub fn record_feature_usage(feature: String) -> Result<(), BackendError> {
+    analytics::record_event(&feature)
+}
+
+/// Show exactly what the next analytics upload would contain
+///
+/// # Example
+/// ```javascript
+/// const { consent, endpoint, payload } = await invoke('get_analytics_preview');
+/// showJson(payload); // { version, appVersion, os, periodStart, periodEnd, counters }
+/// ```
+#[tauri::command]
+pub async fn get_analytics_preview() -> Result<analytics::AnalyticsPreview, BackendError> {
+    run_blocking(analytics::preview).await
+}
+
+// ============================================================================
+// Action Commands
+// ============================================================================
+
+/// List every action the command palette or a controller can trigger
+///
+/// # Returns
+/// Array of `{ id, title, category, handler: "backend" | "frontend", argsSchema }`,
+/// titles in the app language
+#[tauri::command]
+pub fn list_backend_actions() -> Vec<actions::ActionDescriptor> {
+    actions::list_actions(locale::app_language())
+}
+
+/// Run an action by id
+///
+/// Arguments are validated against the action's schema. Backend actions
+/// run directly; frontend actions are forwarded as an `action-invoked`
+/// event `{ id, args }` for the UI to carry out. Each action is refused
+/// like the command it stands for (`APP_LOCKED`, `FORBIDDEN`,
+/// `READ_ONLY_MODE`).
+///
+/// # Example
+/// ```javascript
+/// await invoke('execute_action', { id: 'timer.start', args: { minutes: 5 } })
+///   .catch(err => console.error(err.code)); // e.g., "ACTION_NOT_FOUND", "INVALID_INPUT"
+/// ```
+#[tauri::command]
+pub async fn execute_action(
+    app: AppHandle,
+    webview: tauri::Webview,
+    id: String,
+    args: Option<Value>,
+) -> Result<actions::ActionResult, BackendError> {
+    actions::execute_action(&app, webview.label(), &id, args.unwrap_or(Value::Null)).await
+}
+
+// ============================================================================
+// Controller Commands
+// ============================================================================
+
+/// Get the Stream Deck / external controller listener state
+///
+/// # Returns
+/// `{ enabled, port, running, url, token }`; `url` and `token` go into the
+/// controller plugin (`POST {url}/actions/<id>` with `Authorization: Bearer {token}`)
+#[tauri::command]
+pub fn get_controller_info(
+    state: State<'_, AppState>,
+) -> Result<controller::ControllerInfo, BackendError> {
+    state.controller().info()
+}
+
+/// Enable or disable the local controller listener
+///
+/// # Arguments
+/// * `enabled` - Whether to listen on 127.0.0.1
+/// * `port` - Optional port (default 8766)
+///
+/// # Example
+/// ```javascript
+/// const info = await invoke('set_controller_enabled', { enabled: true });
+/// ```
+#[tauri::command]
+pub fn set_controller_enabled(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<controller::ControllerInfo, BackendError> {
+    state.controller().set_enabled(&app, enabled, port)
+}
+
+/// Replace the controller token; buttons using the old one stop working
+#[tauri::command]
+pub fn regenerate_controller_token(
+    state: State<'_, AppState>,
+) -> Result<controller::ControllerInfo, BackendError> {
+    controller::regenerate_token()?;
+    state.controller().info()
+}
+
+// ============================================================================
+// Presenter Remote Commands
+// ============================================================================
+
+/// Get presenter remote key bindings
+///
+/// # Returns
+/// `{ enabled, bindings: [{ shortcut, action, args }] }`
+#[tauri::command]
+pub fn get_presenter_bindings() -> hid::PresenterConfig {
+    hid::get_config()
+}
+
+/// Save presenter remote key bindings and register them globally
+///
+/// # Arguments
+/// * `config` - `{ enabled, bindings }`; actions are ids from `list_backend_actions`
+///
+/// # Returns
+/// The saved config and the keys that could not be registered
+///
+/// # Example
+/// ```javascript
+/// const status = await invoke('set_presenter_bindings', {
+///   config: { enabled: true, bindings: [{ shortcut: 'PageDown', action: 'timer.next_phase' }] }
+/// });
+/// status.failed.forEach(f => console.warn(`${f.shortcut} is in use`));
+/// ```
+#[tauri::command]
+pub fn set_presenter_bindings(
+    app: AppHandle,
+    config: hid::PresenterConfig,
+) -> Result<hid::PresenterStatus, BackendError> {
+    hid::set_config(&app, config)
+}
+
+/// Check a shortcut for the keyboard layout before saving it
+///
+/// Characters are read as printed on the layout: on an Italian keyboard
+/// "Ctrl+Shift+;" is Ctrl+Shift+",". Characters typed with AltGr or
+/// missing from the layout are refused with `INVALID_INPUT`.
+///
+/// # Arguments
+/// * `accel` - Accelerator as typed ("Ctrl+Shift+;", "PageDown")
+/// * `layout` - "us" or "it" (default: the `keyboard_layout` setting, else
+///   the system locale's)
+///
+/// # Returns
+/// `{ input, layout, effective, label, conflicts }`; `conflicts` lists
+/// `{ kind: "presenterBinding", shortcut, action }` and
+/// `{ kind: "reserved", shortcut }` (kept by the OS)
+///
+/// # Example
+/// ```javascript
+/// const check = await invoke('validate_accelerator', { accel: 'Ctrl+Shift+;' });
+/// if (check.conflicts.length) warn(`${check.label} is already used`);
+/// ```
+#[tauri::command]
+pub fn validate_accelerator(
+    accel: String,
+    layout: Option<hid::KeyboardLayout>,
+) -> Result<hid::AcceleratorCheck, BackendError> {
+    hid::validate_accelerator(&accel, layout)
+}
+
+// ============================================================================
+// Timer Sequence Commands
+// ============================================================================
+
+/// Start a timer sequence, replacing any running one
+///
+/// Phases advance in the backend; listen to `timer-sequence-phase` for
+/// phase changes (with the sound to play) and `timer-tick` for the countdown.
+///
+/// # Arguments
+/// * `sequence` - `{ name, phases: [{ label, durationSecs, color, sound? }], rounds }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('create_timer_sequence', {
+///   sequence: {
+///     name: 'Rotazione',
+///     rounds: 3,
+///     phases: [
+///       { label: 'Lavoro', durationSecs: 1200, color: '#2E7D32', sound: 'bell' },
+///       { label: 'Pausa', durationSecs: 300, color: '#F9A825', sound: 'chime' }
+///     ]
+///   }
+/// });
+/// ```
+#[tauri::command]
+pub fn create_timer_sequence(
+    app: AppHandle,
+    sequence: timers::TimerSequence,
+) -> Result<timers::SequenceState, BackendError> {
+    timers::create_timer_sequence(&app, sequence)
+}
+
+/// End the current phase and start the next one
+#[tauri::command]
+pub fn skip_phase(app: AppHandle) -> Result<timers::SequenceState, BackendError> {
+    timers::skip_phase(&app)
+}
+
+/// Get the running sequence's phase and remaining time (`null` if idle)
+#[tauri::command]
+pub fn get_sequence_state() -> Option<timers::SequenceState> {
+    timers::get_sequence_state()
+}
+
+/// Stop the running sequence
+#[tauri::command]
+pub fn stop_timer_sequence() -> Result<(), BackendError> {
+    timers::stop_timer_sequence()
+}
+
+/// Set the voice announcements of a timer sequence
+///
+/// At each milestone of every phase the backend emits `timer-announcement`
+/// with the phrase in the app language (`{ timerId, phaseLabel,
+/// remainingSecs, text, language }`) for the frontend to speak; background
+/// music is ducked meanwhile. An empty list turns announcements off.
+///
+/// # Arguments
+/// * `timer_id` - Name of the sequence
+/// * `milestones` - `[{ remainingSecs, phrases? }]`, at most 10; `0`
+///   announces the end of the phase, `phrases` (`{ it, en }`) replaces the
+///   built-in phrase and may use `{label}`, `{minutes}` and `{seconds}`
+///
+/// # Returns
+/// The milestones, furthest from the end first
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_timer_announcements', {
+///   timerId: 'Rotazione',
+///   milestones: [
+///     { remainingSecs: 300 },
+///     { remainingSecs: 0, phrases: { it: '{label} finito!' } }
+///   ]
+/// });
+/// listen('timer-announcement', ({ payload }) =>
+///   speechSynthesis.speak(Object.assign(new SpeechSynthesisUtterance(payload.text),
+///     { lang: payload.language })));
+/// ```
+#[tauri::command]
+pub fn set_timer_announcements(
+    timer_id: String,
+    milestones: Vec<timers::Milestone>,
+) -> Result<Vec<timers::Milestone>, BackendError> {
+    timers::set_timer_announcements(&timer_id, milestones)
+}
+
+/// Get the voice announcements of a timer sequence (empty if none)
+#[tauri::command]
+pub fn get_timer_announcements(timer_id: String) -> Vec<timers::Milestone> {
+    timers::get_timer_announcements(&timer_id)
+}
+
+// ============================================================================
+// Classroom State Commands
+// ============================================================================
+
+/// Get the traffic light state shared by all views
+///
+/// # Returns
+/// `{ color: "green" | "yellow" | "red", mode: "manual" | "auto", changedAt, allowedMax, automatic }`
+#[tauri::command]
+pub fn get_classroom_state() -> classroom_state::ClassroomState {
+    classroom_state::get_classroom_state()
+}
+
+/// Set the traffic light color and/or mode
+///
+/// Setting only a color switches to manual mode. Every window receives
+/// `classroom-state-changed`.
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_classroom_state', { color: 'red' });
+/// await invoke('set_classroom_state', { mode: 'auto' });
+/// ```
+#[tauri::command]
+pub fn set_classroom_state(
+    app: AppHandle,
+    color: Option<classroom_state::StateColor>,
+    mode: Option<classroom_state::StateMode>,
+) -> Result<classroom_state::ClassroomState, BackendError> {
+    classroom_state::set_classroom_state(&app, color, mode)
+}
+
+/// Feed the current noise level to the state machine (auto mode)
+///
+/// Every window receives it back as `noise-level` (`{ level, muted }`),
+/// `muted` telling a muted mic from a silent class.
+///
+/// # Arguments
+/// * `level` - Noise meter level, same scale as the `classroom_state_rules` thresholds
+#[tauri::command]
+pub fn report_noise_level(
+    app: AppHandle,
+    level: f64,
+) -> Result<classroom_state::ClassroomState, BackendError> {
+    classroom_state::report_noise_level(&app, level)
+}
+
+/// Get the automatic gain compensation setting and the current gain
+///
+/// # Returns
+/// `{ enabled, targetLevel, gain, maxGain }`
+#[tauri::command]
+pub fn get_agc() -> agc::AgcStatus {
+    agc::get_agc()
+}
+
+/// Turn automatic gain compensation of noise levels on or off
+///
+/// Reported levels are shifted so their average over the last minutes sits
+/// at `target_level`, evening out quiet and sensitive microphones; the
+/// shift never exceeds `maxGain` (20 points) either way.
+///
+/// # Arguments
+/// * `enabled` - Whether to compensate
+/// * `target_level` - Average level to aim for, 0-100 (default 50; kept if omitted)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_agc', { enabled: true, targetLevel: 45 });
+/// ```
+#[tauri::command]
+pub fn set_agc(enabled: bool, target_level: Option<f64>) -> Result<agc::AgcStatus, BackendError> {
+    agc::set_agc(enabled, target_level)
+}
+
+/// Get the unit sound levels are shown in
+///
+/// # Returns
+/// `{ unit, label, calibrated }`; `calibrated` is false while dB SPL values
+/// are only an estimate (no room calibration saved)
+#[tauri::command]
+pub fn get_sound_unit() -> sound_units::SoundUnitInfo {
+    sound_units::get_sound_unit_info()
+}
+
+/// Set the unit of noise-level events, the day overview, class
+/// comparisons and the research export
+///
+/// Light thresholds stay in meter points (0-100) whatever the unit.
+///
+/// # Arguments
+/// * `unit` - "percent" (default), "dbfs" or "dbspl" (approximate)
+///
+/// # Example
+/// ```javascript
+/// const info = await invoke('set_sound_unit', { unit: 'dbspl' });
+/// ```
+#[tauri::command]
+pub fn set_sound_unit(
+    unit: sound_units::SoundUnit,
+) -> Result<sound_units::SoundUnitInfo, BackendError> {
+    sound_units::set_sound_unit(unit)
+}
+
+/// Get the quiet-hour rules and whether they pause noise monitoring now
+///
+/// # Returns
+/// `{ rules, paused, reason }`; `reason` is the rule pausing monitoring
+#[tauri::command]
+pub fn get_monitoring_quiet_hours() -> quiet_hours::QuietHoursStatus {
+    quiet_hours::get_monitoring_quiet_hours()
+}
+
+/// Set when noise monitoring pauses on its own
+///
+/// While paused, reported levels still reach the meter (flagged `paused`)
+/// but never change the traffic light and aren't recorded in the noise
+/// history.
+///
+/// # Arguments
+/// * `rules` - Any of `{ kind: "breaks" }`, `{ kind: "period", label }`,
+///   `{ kind: "outsideSchool", marginMinutes }`, `{ kind: "exams" }`,
+///   `{ kind: "window", start, end, weekdays }`; empty to never pause
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_monitoring_quiet_hours', {
+///   rules: [{ kind: 'breaks' }, { kind: 'outsideSchool', marginMinutes: 10 }],
+/// });
+/// ```
+#[tauri::command]
+pub fn set_monitoring_quiet_hours(
+    rules: Vec<quiet_hours::QuietRule>,
+) -> Result<quiet_hours::QuietHoursStatus, BackendError> {
+    quiet_hours::set_monitoring_quiet_hours(rules)
+}
+
+// ============================================================================
+// Bell Schedule Commands
+// ============================================================================
+
+/// Get the weekly bell schedule
+#[tauri::command]
+pub fn get_bell_schedule() -> schedule::BellSchedule {
+    schedule::get_bell_schedule()
+}
+
+/// Save the weekly bell schedule
+///
+/// # Arguments
+/// * `schedule` - `{ periods: [{ label, kind: "lesson" | "break", start: "HH:MM", end: "HH:MM", weekdays: [1..7] }] }`
+///
+/// # Errors
+/// INVALID_INPUT if a time is malformed or a period ends before it starts
+#[tauri::command]
+pub fn set_bell_schedule(
+    schedule: schedule::BellSchedule,
+) -> Result<schedule::BellSchedule, BackendError> {
+    schedule::set_bell_schedule(schedule)
+}
+
+/// Get the current period and time until the next bell
+///
+/// The same payload is emitted every 30 seconds as `period-countdown`.
+///
+/// # Example
+/// ```javascript
+/// const { next, remainingMinutes } = await invoke('get_time_remaining_in_period');
+/// if (next?.kind === 'break') label = `${remainingMinutes} min all'intervallo`;
+/// ```
+#[tauri::command]
+pub fn get_time_remaining_in_period() -> schedule::PeriodCountdown {
+    schedule::get_time_remaining_in_period()
+}
+
+// ============================================================================
+// Attachment Commands
+// ============================================================================
+
+/// Attach a file to a class or lesson
+///
+/// The file is copied into managed storage; identical files are stored once.
+///
+/// # Arguments
+/// * `entity` - `class:<id>` or `lesson:<id>`
+/// * `path` - File chosen by the user
+///
+/// # Example
+/// ```javascript
+/// const att = await invoke('attach_file', { entity: 'lesson:lesson_42', path: selected });
+/// ```
+#[tauri::command]
+pub async fn attach_file(
+    entity: String,
+    path: String,
+) -> Result<attachments::Attachment, BackendError> {
+    run_blocking(move || attachments::attach_file(&entity, &path)).await
+}
+
+/// List files attached to a class or lesson
+#[tauri::command]
+pub fn list_attachments(entity: String) -> Result<Vec<attachments::Attachment>, BackendError> {
+    attachments::list_attachments(&entity)
+}
+
+/// Open an attachment with the system's default application
+#[tauri::command]
+pub fn open_attachment(app: AppHandle, id: String) -> Result<(), BackendError> {
+    attachments::open_attachment(&app, &id)
+}
+
+/// Remove an attachment
+#[tauri::command]
+pub fn remove_attachment(id: String) -> Result<(), BackendError> {
+    attachments::remove_attachment(&id)
+}
+
+// ============================================================================
+// Student Photo Commands
+// ============================================================================
+
+/// Import student photos from a folder (runs as an "import" job)
+///
+/// Photos are resized and stored as `photos/<class_id>/<student_id>.jpg`.
+///
+/// # Arguments
+/// * `class_id` - Class whose students the files are matched to
+/// * `path` - Folder delivered by the photographer
+/// * `matching_strategy` - "id" (file name is the student id) or "name"
+///
+/// # Returns
+/// `{ imported, unmatchedFiles, duplicateFiles, studentsWithoutPhoto, failed }`
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('import_photos_from_folder', {
+///   classId: '3A', path: folder, matchingStrategy: 'name'
+/// });
+/// ```
+#[tauri::command]
+pub async fn import_photos_from_folder(
+    class_id: String,
+    path: String,
+    matching_strategy: photos::MatchingStrategy,
+) -> Result<photos::PhotoImportReport, BackendError> {
+    jobs::run("import", "Photo import", move |job| {
+        photos::import_photos_from_folder(job, &class_id, &path, matching_strategy)
+    })
+    .await
+}
+
+/// Shrink and re-encode an image (e.g. pasted or dropped) into a file
+///
+/// The image is turned upright and scaled down to fit `max_dim` (never
+/// up). Photo import and image attachments do this on their own.
+///
+/// # Arguments
+/// * `source` - `{ path }` or `{ bytes }` (at most 50 MB)
+/// * `max_dim` - Longest side in pixels, 64-8192 (default 2048)
+/// * `quality` - JPEG quality, 30-95 (default 80)
+/// * `output_path` - .jpg/.jpeg or .png; transparency is flattened onto
+///   white in a JPEG
+///
+/// # Returns
+/// `{ path, format, width, height, originalBytes, bytes }`
+///
+/// # Example
+/// ```javascript
+/// const bytes = Array.from(new Uint8Array(await blob.arrayBuffer()));
+/// const report = await invoke('optimize_image', {
+///   source: { bytes },
+///   outputPath: '/tmp/pasted.jpg',
+/// });
+/// ```
+#[tauri::command]
+pub async fn optimize_image(
+    source: image_optimize::ImageSource,
+    max_dim: Option<u32>,
+    quality: Option<u8>,
+    output_path: String,
+) -> Result<image_optimize::OptimizeReport, BackendError> {
+    run_blocking(move || image_optimize::optimize_image(source, max_dim, quality, &output_path))
+        .await
+}
+
+// ============================================================================
+// Name Matching Commands
+// ============================================================================
+
+/// Match names from an import against known names
+///
+/// Ignores case, accents, apostrophes and word order, and tolerates small
+/// misspellings. Each target is matched at most once.
+///
+/// # Arguments
+/// * `candidates` - Names to look up (e.g., from a file)
+/// * `targets` - Known names (e.g., a class's students)
+/// * `threshold` - Minimum similarity, 0.0 to 1.0 (default 0.9)
+///
+/// # Returns
+/// One `{ candidate, target, targetIndex, score, exact }` per candidate;
+/// `target` is null when nothing is similar enough
+///
+/// # Example
+/// ```javascript
+/// const matches = await invoke('match_names', {
+///   candidates: ["D'AMICO Niccolo"],
+///   targets: ['Niccolò D’Amico', 'Anna Verdi'],
+/// });
+/// ```
+#[tauri::command]
+pub fn match_names(
+    candidates: Vec<String>,
+    targets: Vec<String>,
+    threshold: Option<f64>,
+) -> Result<Vec<fuzzy::NameMatch>, BackendError> {
+    let threshold = threshold.unwrap_or(fuzzy::DEFAULT_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Threshold must be between 0 and 1",
+        ));
+    }
+    Ok(fuzzy::match_names(&candidates, &targets, threshold))
+}
+
+// ============================================================================
+// Clock Check Commands
+// ============================================================================
+
+/// Compare the system clock with an NTP server
+///
+/// The measured offset is applied to timestamps compared across PCs (cloud
+/// sync). Emits `clock-skew-warning` when the skew exceeds the threshold.
+/// Also runs automatically at startup.
+///
+/// # Returns
+/// `{ server, skewMs, roundTripMs, checkedAt, thresholdMs, exceedsThreshold }`;
+/// `skewMs` is positive when the PC is behind. Fails with
+/// `CLOCK_NTP_UNAVAILABLE` when offline.
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('check_clock_skew');
+/// if (report.exceedsThreshold) showClockWarning(report.skewMs);
+/// ```
+#[tauri::command]
+pub async fn check_clock_skew(app: AppHandle) -> Result<clock_sync::ClockSkewReport, BackendError> {
+    run_blocking(move || clock_sync::check_clock_skew(&app)).await
+}
+
+// ============================================================================
+// Observer Window Commands
+// ============================================================================
+
+/// Set the PIN needed to open the observer window
+///
+/// # Arguments
+/// * `pin` - 4 to 12 digits
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_observer_pin', { pin: '2468' });
+/// ```
+#[tauri::command]
+pub fn set_observer_pin(pin: String) -> Result<(), BackendError> {
+    observer::set_observer_pin(&pin)
+}
+
+/// Open the read-only observer window for a supervisor
+///
+/// The window shows live classroom data; any command that could modify
+/// data is rejected with `OBSERVER_READ_ONLY` when invoked from it.
+///
+/// # Arguments
+/// * `pin` - The observer PIN
+///
+/// # Returns
+/// Fails with `OBSERVER_INVALID_PIN`, or `OBSERVER_LOCKED_OUT` after
+/// repeated wrong PINs
+///
+/// # Example
+/// ```javascript
+/// await invoke('open_observer_window', { pin });
+/// ```
+#[tauri::command]
+pub fn open_observer_window(app: AppHandle, pin: String) -> Result<(), BackendError> {
+    observer::open_observer_window(&app, &pin)
+}
+
+// ============================================================================
+// Profile Commands
+// ============================================================================
+
+/// List profiles and the active one
+///
+/// # Returns
+/// `{ profiles: [{ id, name, role, hasPin, createdAt }], activeProfileId }`;
+/// no active profile means full (teacher) access
+///
+/// # Example
+/// ```javascript
+/// const { profiles, activeProfileId } = await invoke('list_profiles');
+/// ```
+#[tauri::command]
+pub fn list_profiles() -> Result<roles::ProfileStore, BackendError> {
+    roles::list_profiles()
+}
+
+/// Add a profile (teacher only)
+///
+/// # Arguments
+/// * `name` - Display name (e.g., "Supplente")
+/// * `role` - "teacher", "assistant" or "observer"
+/// * `pin` - Optional PIN asked when switching up to this profile
+///
+/// # Example
+/// ```javascript
+/// await invoke('create_profile', { name: 'Supplente', role: 'assistant' });
+/// ```
+#[tauri::command]
+pub fn create_profile(
+    name: String,
+    role: roles::Role,
+    pin: Option<String>,
+) -> Result<roles::Profile, BackendError> {
+    roles::create_profile(&name, role, pin.as_deref())
+}
+
+/// Set or clear a profile's PIN (teacher only)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_profile_pin', { profileId, pin: '2468' });
+/// ```
+#[tauri::command]
+pub fn set_profile_pin(
+    profile_id: String,
+    pin: Option<String>,
+) -> Result<roles::Profile, BackendError> {
+    roles::set_profile_pin(&profile_id, pin.as_deref())
+}
+
+/// Delete a profile other than the active one (teacher only)
+///
+/// # Example
+/// ```javascript
+/// await invoke('delete_profile', { profileId });
+/// ```
+#[tauri::command]
+pub fn delete_profile(profile_id: String) -> Result<(), BackendError> {
+    roles::delete_profile(&profile_id)
+}
+
+/// Switch the active profile
+///
+/// Switching to a profile with a higher role needs its PIN (when it has
+/// one); switching up to a teacher profile always does. Every role may
+/// switch. Privileged commands fail with `FORBIDDEN` for lower roles.
+///
+/// # Arguments
+/// * `profile_id` - Profile to activate; null leaves profiles (teacher only)
+/// * `pin` - The target profile's PIN
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_active_profile', { profileId: teacherId, pin });
+/// ```
+#[tauri::command]
+pub fn set_active_profile(
+    profile_id: Option<String>,
+    pin: Option<String>,
+) -> Result<roles::ProfileStore, BackendError> {
+    roles::set_active_profile(profile_id.as_deref(), pin.as_deref())
+}
+
+// ============================================================================
+// App Lock Commands
+// ============================================================================
+
+/// Get the app lock settings and whether the app is locked
+///
+/// # Returns
+/// `{ enabled, locked, timeoutMinutes }`
+///
+/// # Example
+/// ```javascript
+/// const { locked } = await invoke('get_app_lock_status');
+/// ```
+#[tauri::command]
+pub fn get_app_lock_status() -> app_lock::AppLockStatus {
+    app_lock::get_app_lock_status()
+}
+
+/// Enable the inactivity lock with a PIN, or disable it
+///
+/// The PIN is stored as an Argon2 hash in the system keychain. Enabling
+/// returns a one-time `recoveryCode` for `recover_app_lock`.
+///
+/// # Arguments
+/// * `pin` - 4 to 12 digits; null disables the lock
+/// * `timeout_minutes` - Idle time before locking (1-240)
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_app_lock', { pin: '2468', timeoutMinutes: 5 });
+/// ```
+#[tauri::command]
+pub fn set_app_lock(
+    pin: Option<String>,
+    timeout_minutes: u32,
+) -> Result<app_lock::AppLockStatus, BackendError> {
+    app_lock::set_app_lock(pin.as_deref(), timeout_minutes)
+}
+
+/// Lock the app now
+///
+/// While locked, commands returning student data fail with `APP_LOCKED`.
+/// Emits `app-lock-changed`.
+///
+/// # Example
+/// ```javascript
+/// await invoke('lock_app');
+/// ```
+#[tauri::command]
+pub fn lock_app(app: AppHandle) -> Result<app_lock::AppLockStatus, BackendError> {
+    app_lock::lock_app(&app)
+}
+
+/// Unlock the app with the PIN
+///
+/// # Returns
+/// Fails with `APP_LOCK_INVALID_PIN`, or `APP_LOCK_LOCKED_OUT` after
+/// repeated wrong PINs, or `APP_LOCK_PIN_MISSING` when this PC's keychain
+/// has no PIN (use `recover_app_lock`)
+///
+/// # Example
+/// ```javascript
+/// await invoke('unlock_app', { pin });
+/// ```
+#[tauri::command]
+pub async fn unlock_app(
+    app: AppHandle,
+    pin: String,
+) -> Result<app_lock::AppLockStatus, BackendError> {
+    run_blocking(move || app_lock::unlock_app(&app, &pin)).await
+}
+
+/// Set a new lock PIN with the recovery code and unlock
+///
+/// For when the PIN is forgotten or missing from this PC's keychain.
+///
+/// # Arguments
+/// * `recovery_code` - The code shown when the lock was set up
+/// * `new_pin` - 4 to 12 digits
+///
+/// # Example
+/// ```javascript
+/// await invoke('recover_app_lock', { recoveryCode, newPin: '1357' });
+/// ```
+#[tauri::command]
+pub async fn recover_app_lock(
+    app: AppHandle,
+    recovery_code: String,
+    new_pin: String,
+) -> Result<app_lock::AppLockStatus, BackendError> {
+    run_blocking(move || app_lock::recover_app_lock(&app, &recovery_code, &new_pin)).await
+}
+
+// ============================================================================
+// Annotation Commands
+// ============================================================================
+
+/// Open the transparent annotation overlay fullscreen on the projector
+///
+/// # Arguments
+/// * `monitor_index` - Monitor to cover; defaults to the first monitor that
+///   isn't the primary one
+///
+/// # Returns
+/// `{ open, clickThrough }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('open_annotation_overlay', {});
+/// ```
+#[tauri::command]
+pub fn open_annotation_overlay(
+    app: AppHandle,
+    monitor_index: Option<usize>,
+) -> Result<annotation::AnnotationOverlayStatus, BackendError> {
+    annotation::open_annotation_overlay(&app, monitor_index)
+}
+
+/// Close the annotation overlay
+///
+/// # Example
+/// ```javascript
+/// await invoke('close_annotation_overlay');
+/// ```
+#[tauri::command]
+pub fn close_annotation_overlay(app: AppHandle) -> Result<(), BackendError> {
+    annotation::close_annotation_overlay(&app)
+}
+
+/// Whether the overlay is open and lets clicks through
+///
+/// # Example
+/// ```javascript
+/// const { open, clickThrough } = await invoke('get_annotation_overlay_status');
+/// ```
+#[tauri::command]
+pub fn get_annotation_overlay_status(app: AppHandle) -> annotation::AnnotationOverlayStatus {
+    annotation::get_annotation_overlay_status(&app)
+}
+
+/// Let the mouse reach the app under the overlay, or catch it for drawing
+/// directly on the projector
+///
+/// # Arguments
+/// * `enabled` - `true` for click-through
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_annotation_click_through', { enabled: false });
+/// ```
+#[tauri::command]
+pub fn set_annotation_click_through(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<annotation::AnnotationOverlayStatus, BackendError> {
+    annotation::set_annotation_click_through(&app, enabled)
+}
+
+/// Send a stroke drawn in the teacher window to the overlay
+///
+/// Resending a stroke with the same id replaces it, so strokes can be
+/// streamed while they are drawn.
+///
+/// # Arguments
+/// * `stroke` - `{ id, tool: "pen" | "highlighter", color: "#rrggbb", width, points: [{ x, y }] }`
+///   with coordinates from 0 to 1
+///
+/// # Example
+/// ```javascript
+/// await invoke('relay_annotation_stroke', {
+///   stroke: { id: 's1', tool: 'pen', color: '#e53935', width: 6, points: [{ x: 0.2, y: 0.3 }] }
+/// });
+/// ```
+#[tauri::command]
+pub fn relay_annotation_stroke(
+    app: AppHandle,
+    stroke: annotation::Stroke,
+) -> Result<(), BackendError> {
+    annotation::relay_annotation_stroke(&app, stroke)
+}
+
+/// Remove the last stroke from the overlay
+///
+/// # Example
+/// ```javascript
+/// await invoke('undo_annotation');
+/// ```
+#[tauri::command]
+pub fn undo_annotation(app: AppHandle) -> Result<(), BackendError> {
+    annotation::undo_annotation(&app)
+}
+
+/// Remove all strokes from the overlay
+///
+/// # Example
+/// ```javascript
+/// await invoke('clear_annotations');
+/// ```
+#[tauri::command]
+pub fn clear_annotations(app: AppHandle) -> Result<(), BackendError> {
+    annotation::clear_annotations(&app)
+}
+
+// ============================================================================
+// Projector Dim Commands
+// ============================================================================
+
+/// Dim the projector with an optional message ("Eyes on me")
+///
+/// Calling it again while dimmed changes the level and message.
+///
+/// # Arguments
+/// * `level` - Opacity of the dimming layer, 0.1 to 1
+/// * `message` - Text shown on the layer (max 120 characters)
+///
+/// # Returns
+/// `{ dimmed, dim: { level, message } }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('dim_projector', { level: 0.85, message: 'Eyes on me' });
+/// ```
+#[tauri::command]
+pub fn dim_projector(
+    app: AppHandle,
+    level: f64,
+    message: Option<String>,
+) -> Result<projector_dim::ProjectorDimStatus, BackendError> {
+    projector_dim::dim_projector(&app, level, message)
+}
+
+/// Remove the dimming layer from the projector
+///
+/// # Example
+/// ```javascript
+/// await invoke('undim_projector');
+/// ```
+#[tauri::command]
+pub fn undim_projector(app: AppHandle) -> Result<projector_dim::ProjectorDimStatus, BackendError> {
+    projector_dim::undim_projector(&app)
+}
+
+/// Current projector dimming (the dim window reads this on load)
+///
+/// # Example
+/// ```javascript
+/// const { dimmed, dim } = await invoke('get_projector_dim');
+/// ```
+#[tauri::command]
+pub fn get_projector_dim() -> projector_dim::ProjectorDimStatus {
+    projector_dim::get_projector_dim()
+}
+
+// ============================================================================
+// Freeze Broadcast Commands
+// ============================================================================
+
+/// Emergency freeze for fire drills and lockdowns: the projector dims fully
+/// with the message, the attention chime plays on the alerts output and
+/// windows and companion devices are notified
+///
+/// Calling it again while frozen changes the message. A step that fails
+/// (no projector, no audio device) doesn't stop the others; it is listed in
+/// `freeze.warnings`.
+///
+/// # Arguments
+/// * `message` - Announcement shown everywhere (max 120 characters)
+///
+/// # Returns
+/// `{ frozen, freeze: { message, startedAt, warnings } }`
+///
+/// # Example
+/// ```javascript
+/// const { freeze } = await invoke('broadcast_freeze', {
+///   message: 'Evacuazione: lasciate tutto e mettetevi in fila'
+/// });
+/// if (freeze.warnings.length) showWarnings(freeze.warnings);
+/// ```
+#[tauri::command]
+pub fn broadcast_freeze(
+    app: AppHandle,
+    message: String,
+) -> Result<freeze_broadcast::FreezeStatus, BackendError> {
+    freeze_broadcast::broadcast_freeze(&app, &message)
+}
+
+/// End the freeze and undim the projector
+///
+/// # Example
+/// ```javascript
+/// await invoke('release_freeze');
+/// ```
+#[tauri::command]
+pub fn release_freeze(app: AppHandle) -> Result<freeze_broadcast::FreezeStatus, BackendError> {
+    freeze_broadcast::release_freeze(&app)
+}
+
+/// Current freeze (windows also get `freeze-changed`)
+///
+/// # Example
+/// ```javascript
+/// const { frozen, freeze } = await invoke('get_freeze_status');
+/// ```
+#[tauri::command]
+pub fn get_freeze_status() -> freeze_broadcast::FreezeStatus {
+    freeze_broadcast::get_freeze_status()
+}
+
+/// Current freeze, for companion devices
+///
+/// Entry point for companion devices; the device token is verified.
+/// Devices ask on connect and while connected, so a freeze reaches them
+/// even when started before they joined.
+///
+/// # Example
+/// ```javascript
+/// const { frozen, freeze } = await invoke('get_device_freeze', { token });
+/// ```
+#[tauri::command]
+pub fn get_device_freeze(token: String) -> Result<freeze_broadcast::FreezeStatus, BackendError> {
+    freeze_broadcast::get_device_freeze(&token)
+}
+
+// ============================================================================
+// Pointer Highlight Commands
+// ============================================================================
+
+/// Show (or change) or hide a laser-pointer highlight following the cursor
+/// on the projector
+///
+/// The cursor is sampled natively and sent to the `pointer` window as
+/// `pointer-highlight-moved` ({ x, y } in logical pixels, or null when the
+/// cursor is on another screen). The highlight stays above the annotation
+/// overlay.
+///
+/// # Arguments
+/// * `enabled` - false removes the highlight
+/// * `color` - `#rrggbb`
+/// * `size` - Diameter in pixels, 8 to 200
+///
+/// # Returns
+/// { enabled, highlight: { color, size } | null }
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_pointer_highlight', { enabled: true, color: '#ff2020', size: 36 });
+/// ```
+#[tauri::command]
+pub fn set_pointer_highlight(
+    app: AppHandle,
+    enabled: bool,
+    color: String,
+    size: u32,
+) -> Result<pointer_highlight::PointerHighlightStatus, BackendError> {
+    pointer_highlight::set_pointer_highlight(&app, enabled, color, size)
+}
+
+/// Get the pointer highlight state
+///
+/// # Example
+/// ```javascript
+/// const { enabled, highlight } = await invoke('get_pointer_highlight');
+/// ```
+#[tauri::command]
+pub fn get_pointer_highlight() -> pointer_highlight::PointerHighlightStatus {
+    pointer_highlight::get_pointer_highlight()
+}
+
+// ============================================================================
+// Screenshot Commands
+// ============================================================================
+
+/// Save a screenshot of an app window as PNG
+///
+/// Used to archive the end-of-lesson leaderboard or seating chart from the
+/// projector window. Checks the screen-capture permission first.
+///
+/// # Arguments
+/// * `window_label` - Window to capture (e.g., "main")
+/// * `path` - Target `.png` file; defaults to
+///   `screenshots/<label>-<timestamp>.png` in the config directory
+///
+/// # Returns
+/// `{ path, width, height }`
+///
+/// # Example
+/// ```javascript
+/// const shot = await invoke('capture_window_screenshot', { windowLabel: 'main' });
+/// ```
+#[tauri::command]
+pub async fn capture_window_screenshot(
+    app: AppHandle,
+    window_label: String,
+    path: Option<String>,
+) -> Result<screenshot::ScreenshotInfo, BackendError> {
+    run_blocking(move || {
+        screenshot::capture_window_screenshot(&app, &window_label, path.as_deref())
+    })
+    .await
+}
+
+// ============================================================================
+// Background Audio Commands
+// ============================================================================
+
+/// Play focus music or noise in the background
+///
+/// Replaces whatever is playing. The volume drops automatically while the
+/// noise alert sounds.
+///
+/// # Arguments
+/// * `source` - `{ kind: "file", path }` (mp3, ogg, flac, wav; looped) or
+///   `{ kind: "noise", color: "white" | "pink" }`
+/// * `volume` - 0 to 1
+/// * `fade_ms` - Fade-in duration (max 30 s)
+/// * `stop_at_period_end` - Fade out when the current period ends
+///
+/// # Returns
+/// `{ playing, source, volume, ducked, stopsAt }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('play_background_audio', {
+///   source: { kind: 'noise', color: 'pink' },
+///   volume: 0.3,
+///   fadeMs: 3000,
+///   stopAtPeriodEnd: true
+/// });
+/// ```
+#[tauri::command]
+pub fn play_background_audio(
+    app: AppHandle,
+    source: background_audio::AudioSource,
+    volume: f32,
+    fade_ms: Option<u64>,
+    stop_at_period_end: Option<bool>,
+) -> Result<background_audio::BackgroundAudioStatus, BackendError> {
+    background_audio::play_background_audio(
+        &app,
+        source,
+        volume,
+        fade_ms,
+        stop_at_period_end.unwrap_or(false),
+    )
+}
+
+/// Stop the background audio
+///
+/// # Arguments
+/// * `fade_ms` - Fade-out duration (max 30 s)
+///
+/// # Example
+/// ```javascript
+/// await invoke('stop_background_audio', { fadeMs: 2000 });
+/// ```
+#[tauri::command]
+pub fn stop_background_audio(app: AppHandle, fade_ms: Option<u64>) -> Result<(), BackendError> {
+    background_audio::stop_background_audio(&app, fade_ms)
+}
+
+/// Lower the background audio while a sound plays (e.g. a custom alert)
+///
+/// # Arguments
+/// * `duration_ms` - How long to stay ducked (max 60 s)
+///
+/// # Example
+/// ```javascript
+/// await invoke('duck_background_audio', { durationMs: 3000 });
+/// ```
+#[tauri::command]
+pub fn duck_background_audio(app: AppHandle, duration_ms: u64) -> Result<(), BackendError> {
+    background_audio::duck_background_audio(&app, duration_ms)
+}
+
+/// Current background audio
+///
+/// # Example
+/// ```javascript
+/// const { playing, stopsAt } = await invoke('get_background_audio_status');
+/// ```
+#[tauri::command]
+pub fn get_background_audio_status() -> background_audio::BackgroundAudioStatus {
+    background_audio::get_background_audio_status()
+}
+
+// ============================================================================
+// Audio Output Commands
+// ============================================================================
+
+/// List audio output devices
+///
+/// # Returns
+/// `[{ id, name, isDefault }]`
+///
+/// # Example
+/// ```javascript
+/// const outputs = await invoke('list_audio_output_devices');
+/// ```
+#[tauri::command]
+pub async fn list_audio_output_devices() -> Result<Vec<audio_output::OutputDevice>, BackendError> {
+    run_blocking(audio_output::list_audio_output_devices).await
+}
+
+/// Get the output device chosen for alerts and for music
+///
+/// # Returns
+/// `{ alerts, music }`, device ids or `null` for the system default
+///
+/// # Example
+/// ```javascript
+/// const { alerts, music } = await invoke('get_audio_output_routing');
+/// ```
+#[tauri::command]
+pub fn get_audio_output_routing() -> audio_output::OutputRouting {
+    audio_output::get_output_routing()
+}
+
+/// Send alerts or music to an output device
+///
+/// # Arguments
+/// * `purpose` - "alerts" or "music"
+/// * `device_id` - Id from `list_audio_output_devices`, or `null` for the
+///   system default
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_output_device', { purpose: 'music', deviceId: 'HDMI (Projector)' });
+/// ```
+#[tauri::command]
+pub async fn set_output_device(
+    purpose: audio_output::AudioPurpose,
+    device_id: Option<String>,
+) -> Result<audio_output::OutputRouting, BackendError> {
+    run_blocking(move || audio_output::set_output_device(purpose, device_id)).await
+}
+
+/// Get the volume safety limit
+///
+/// # Returns
+/// `{ maxDb, rampMs }`
+///
+/// # Example
+/// ```javascript
+/// const { maxDb, rampMs } = await invoke('get_volume_safety');
+/// ```
+#[tauri::command]
+pub fn get_volume_safety() -> audio_output::VolumeSafety {
+    audio_output::get_volume_safety()
+}
+
+/// Cap the volume and ramp-in of every sound the app plays
+///
+/// Applies to alerts, announcements and background music, including music
+/// already playing.
+///
+/// # Arguments
+/// * `max_db` - Maximum gain, -40 to 0 dB (0 = no cap)
+/// * `ramp_ms` - Minimum fade-in, 0 to 5000 ms
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_volume_safety', { maxDb: -10, rampMs: 500 });
+/// ```
+#[tauri::command]
+pub fn set_volume_safety(
+    max_db: f32,
+    ramp_ms: u64,
+) -> Result<audio_output::VolumeSafety, BackendError> {
+    audio_output::set_volume_safety(max_db, ramp_ms)
+}
+
+/// Play an alert on the alerts output, ducking background music
+///
+/// # Arguments
+/// * `path` - Audio file to play (cut at 10 s); the built-in chime if omitted
+///
+/// # Example
+/// ```javascript
+/// await invoke('play_alert_chime', {});
+/// ```
+#[tauri::command]
+pub fn play_alert_chime(app: AppHandle, path: Option<String>) -> Result<(), BackendError> {
+    audio_output::play_alert_chime(&app, path.as_deref())
+}
+
+// ============================================================================
+// Noise History Commands
+// ============================================================================
+
+/// Start recording noise levels for a class and activity, or stop
+///
+/// Levels reported with `report_noise_level` are recorded while a context
+/// is set.
+///
+/// # Arguments
+/// * `class_id` - Class being metered; `null` stops recording
+/// * `activity` - Activity type, e.g. "group work" or "silent work"
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_noise_context', { classId: 'class_1', activity: 'group work' });
+/// await invoke('set_noise_context', { classId: null });
+/// ```
+#[tauri::command]
+pub fn set_noise_context(
+    class_id: Option<String>,
+    activity: Option<String>,
+) -> Result<(), BackendError> {
+    noise_history::set_noise_context(class_id.map(|class_id| noise_history::NoiseContext {
+        class_id,
+        activity: activity.unwrap_or_else(|| "lesson".to_string()),
+    }))
+}
+
+/// Get the current noise recording context
+///
+/// # Returns
+/// `{ classId, activity }` or `null` when not recording
+#[tauri::command]
+pub fn get_noise_context() -> Option<noise_history::NoiseContext> {
+    noise_history::get_noise_context()
+}
+
+/// Suggest yellow/red thresholds for a class from its noise history
+///
+/// # Arguments
+/// * `class_id` - Class to analyze
+///
+/// # Returns
+/// `{ classId, overall, activities: [{ activity, samples, enoughData, median, yellowAbove, redAbove }] }`
+///
+/// # Example
+/// ```javascript
+/// const { overall } = await invoke('suggest_thresholds', { classId: 'class_1' });
+/// if (overall.enoughData) {
+///   await invoke('save_config', {
+///     key: 'classroom_state_rules',
+///     value: { ...rules, yellowAbove: overall.yellowAbove, redAbove: overall.redAbove }
+///   });
+/// }
+/// ```
+#[tauri::command]
+pub async fn suggest_thresholds(
+    class_id: String,
+) -> Result<noise_history::ThresholdSuggestions, BackendError> {
+    run_blocking(move || noise_history::suggest_thresholds(&class_id)).await
+}
+
+// ============================================================================
+// Audio Preset Commands
+// ============================================================================
+
+/// Export thresholds, calibration, volume safety and output routing to a
+/// preset file other classrooms can import
+///
+/// # Arguments
+/// * `path` - Target `.json` file
+/// * `name` - Label shown on import (e.g. "Lab 2 – Epson projector")
+///
+/// # Example
+/// ```javascript
+/// await invoke('export_audio_presets', { path: '/media/usb/lab2.json', name: 'Lab 2' });
+/// ```
+#[tauri::command]
+pub async fn export_audio_presets(
+    path: String,
+    name: Option<String>,
+) -> Result<audio_presets::AudioPreset, BackendError> {
+    run_blocking(move || audio_presets::export_audio_presets(Path::new(&path), name)).await
+}
+
+/// Apply an audio preset file
+///
+/// Nothing is changed unless every setting in the file is valid.
+///
+/// # Arguments
+/// * `path` - Preset file from `export_audio_presets`
+///
+/// # Returns
+/// `{ name, applied, ignored }` (config keys)
+///
+/// # Example
+/// ```javascript
+/// const { applied } = await invoke('import_audio_presets', { path });
+/// ```
+#[tauri::command]
+pub async fn import_audio_presets(
+    path: String,
+) -> Result<audio_presets::PresetImportResult, BackendError> {
+    run_blocking(move || audio_presets::import_audio_presets(Path::new(&path))).await
+}
+
+// ============================================================================
+// Day Overview Commands
+// ============================================================================
+
+/// Get attendance, behavior entries, noise summary, device handouts and
+/// pending items of every class for a date, in one query
+///
+/// # Arguments
+/// * `date` - Local date, `YYYY-MM-DD`
+///
+/// # Returns
+/// `{ date, classes: [{ classId, className, students, attendance, behavior, noise, devices, pending }], pending, exitTickets }`
+///
+/// # Example
+/// ```javascript
+/// const today = new Date().toLocaleDateString('sv'); // YYYY-MM-DD
+/// const { classes, pending } = await invoke('get_day_overview', { date: today });
+/// ```
+#[tauri::command]
+pub async fn get_day_overview(date: String) -> Result<day_overview::DayOverview, BackendError> {
+    run_blocking(move || day_overview::get_day_overview(&date)).await
+}
+
+/// Compare every class on one metric over a date range, ready for a chart
+///
+/// # Arguments
+/// * `metric` - "noise" (average level), "attendance" (share present,
+///   0-1) or "points" (positive minus negative behavior entries)
+/// * `range` - `{ from, to, bucket }`, dates `YYYY-MM-DD` (both included,
+///   at most 366 days), `bucket` "day", "week" (default) or "month"
+///
+/// # Returns
+/// `{ metric, bucket, labels, series: [{ classId, className, students, values, overall }] }`;
+/// `values` has one entry per label, null where nothing was recorded
+///
+/// # Example
+/// ```javascript
+/// const { labels, series } = await invoke('compare_classes', {
+///   metric: 'attendance',
+///   range: { from: '2026-09-14', to: '2026-10-16', bucket: 'week' }
+/// });
+/// chart.setData(labels, series.map(s => ({ name: s.className, data: s.values })));
+/// ```
+#[tauri::command]
+pub async fn compare_classes(
+    metric: class_comparison::ComparisonMetric,
+    range: class_comparison::ComparisonRange,
+) -> Result<class_comparison::ClassComparison, BackendError> {
+    run_blocking(move || class_comparison::compare_classes(metric, &range)).await
+}
+
+// ============================================================================
+// Insight Commands
+// ============================================================================
+
+/// Get the trends currently detected, newest first
+///
+/// Checked in the background every 30 minutes; each new one is also sent
+/// as `insight-detected`.
+///
+/// # Returns
+/// `[{ id, classId, className, detectedAt, type, ... }]`, `type` one of
+/// `absenceStreak` (`studentId`, `studentName`, `weekday`, `dates`) or
+/// `risingNoise` (`fromLevel`, `toLevel`, `days`)
+///
+/// # Example
+/// ```javascript
+/// const insights = await invoke('get_insights');
+/// await listen('insight-detected', ({ payload }) => notify(payload));
+/// ```
+#[tauri::command]
+pub fn get_insights() -> Result<Vec<insights::Insight>, BackendError> {
+    insights::get_insights()
+}
+
+/// Get the thresholds of each insight type
+#[tauri::command]
+pub fn get_insight_thresholds() -> insights::InsightThresholds {
+    insights::get_insight_thresholds()
+}
+
+/// Save the thresholds of each insight type and check again with them
+///
+/// # Arguments
+/// * `thresholds` - `{ absenceStreak: { enabled, weeks }, risingNoise: { enabled, days, minRise } }`
+///
+/// # Returns
+/// The insights found with the new thresholds
+///
+/// # Example
+/// ```javascript
+/// await invoke('set_insight_thresholds', {
+///   thresholds: { absenceStreak: { enabled: true, weeks: 4 }, risingNoise: { enabled: false } }
+/// });
+/// ```
+#[tauri::command]
+pub async fn set_insight_thresholds(
+    app: AppHandle,
+    thresholds: insights::InsightThresholds,
+) -> Result<Vec<insights::Insight>, BackendError> {
+    run_blocking(move || insights::set_insight_thresholds(&app, thresholds)).await
+}
+
+// ============================================================================
+// Research Export Commands
+// ============================================================================
+
+/// Export a pseudonymized, k-anonymized dataset for research partners
+///
+/// Daily noise statistics and attendance rates of every class, without
+/// names. Ids become pseudonyms salted with a key that never leaves this
+/// PC's keychain; classes under `k` students are left out and student
+/// attendance is given in bands shared by at least `k` classmates.
+///
+/// # Arguments
+/// * `range` - `{ from, to }`, `YYYY-MM-DD`, at most 366 days
+/// * `path` - Output .json file
+/// * `k` - Smallest group size (2-50, default 5)
+///
+/// # Returns
+/// `{ path, classes, students, suppressedClasses, suppressedStudents }`
+///
+/// # Example
+/// ```javascript
+/// const info = await invoke('export_research_dataset', {
+///   range: { from: '2026-09-14', to: '2026-12-22' }, path: '/home/me/clima.json'
+/// });
+/// ```
+#[tauri::command]
+pub async fn export_research_dataset(
+    range: research_export::ResearchRange,
+    path: String,
+    k: Option<usize>,
+) -> Result<research_export::ResearchExportInfo, BackendError> {
+    run_blocking(move || research_export::export_research_dataset(&range, &path, k)).await
+}
+
+// ============================================================================
+// Gradebook Commands
+// ============================================================================
+
+/// Record an assessment score for a student
+///
+/// # Arguments
+/// * `student_id` - Student being graded
+/// * `assessment` - Assessment name, e.g. "Verifica di storia"
+/// * `value` - Grade 1–10 as a number or in Italian notation (`"6+"`, `"7-"`, `"6½"`, `"6/7"`)
+/// * `weight` - Relative weight (default 1; 0 records without counting)
+///
+/// # Example
+/// ```javascript
+/// await invoke('add_score', { studentId, assessment: 'Verifica 1', value: '6+', weight: 2 });
+/// ```
+#[tauri::command]
+pub async fn add_score(
+    student_id: String,
+    assessment: String,
+    value: gradebook::GradeInput,
+    weight: Option<f64>,
+) -> Result<gradebook::Score, BackendError> {
+    run_blocking(move || {
+        gradebook::add_score(&student_id, &assessment, value, weight.unwrap_or(1.0))
+    })
+    .await
+}
+
+/// Delete a recorded score
+///
+/// # Arguments
+/// * `score_id` - Id returned by `add_score`
+#[tauri::command]
+pub async fn delete_score(score_id: String) -> Result<(), BackendError> {
+    run_blocking(move || gradebook::delete_score(&score_id)).await
+}
+
+/// Get a student's scores, oldest first
+///
+/// # Arguments
+/// * `student_id` - Student to list
+#[tauri::command]
+pub async fn get_student_scores(student_id: String) -> Result<Vec<gradebook::Score>, BackendError> {
+    run_blocking(move || gradebook::get_student_scores(&student_id)).await
+}
+
+/// Get a student's average
+///
+/// # Arguments
+/// * `student_id` - Student to average
+/// * `strategy` - `"weighted"` (default), `"average"` or `"median"`
+///
+/// # Returns
+/// `{ studentId, strategy, scores, value, display, reportGrade }`, e.g.
+/// `value: 6.17, display: "6+", reportGrade: 6`
+///
+/// # Example
+/// ```javascript
+/// const avg = await invoke('get_student_average', { studentId, strategy: 'weighted' });
+/// ```
+#[tauri::command]
+pub async fn get_student_average(
+    student_id: String,
+    strategy: Option<gradebook::AverageStrategy>,
+) -> Result<gradebook::StudentAverage, BackendError> {
+    run_blocking(move || {
+        gradebook::get_student_average(
+            &student_id,
+            strategy.unwrap_or(gradebook::AverageStrategy::Weighted),
+        )
+    })
+    .await
+}
+
+// ============================================================================
+// Grade Export Commands
+// ============================================================================
+
+/// Export a class's grades as CSV or XLSX for upload to the registry
+///
+/// # Arguments
+/// * `class_id` - Class to export
+/// * `format` - `"csv"` or `"xlsx"`
+/// * `template` - Template id (`"scores"`, `"averages"` or a school template)
+/// * `path` - Output file; its extension must match the format
+///
+/// # Returns
+/// `{ path, rows, size }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('export_grades', {
+///   classId: 'class_1', format: 'xlsx', template: 'averages', path: '/home/me/3A-medie.xlsx'
+/// });
+/// ```
+#[tauri::command]
+pub async fn export_grades(
+    class_id: String,
+    format: grade_export::ExportFormat,
+    template: String,
+    path: String,
+) -> Result<grade_export::GradeExportInfo, BackendError> {
+    run_blocking(move || grade_export::export_grades(&class_id, format, &template, &path)).await
+}
+
+/// List grade export templates (built-in first)
+#[tauri::command]
+pub async fn list_grade_templates() -> Result<Vec<grade_export::GradeExportTemplate>, BackendError>
+{
+    run_blocking(grade_export::list_grade_templates).await
+}
+
+/// Add or replace a school export template
+///
+/// # Arguments
+/// * `template` - `{ id, name, rows: 'scores' | 'averages', columns: [{ header, field }], delimiter, decimalComma, dateFormat, strategy, header }`
+///
+/// # Example
+/// ```javascript
+/// await invoke('save_grade_template', { template: {
+///   id: 'registro-medie', name: 'Registro – medie', rows: 'averages',
+///   columns: [
+///     { header: 'Codice', field: 'studentId' },
+///     { header: 'Materia', field: 'fixed', value: 'STO' },
+///     { header: 'Voto', field: 'reportGrade' }
+///   ]
+/// } });
+/// ```
+#[tauri::command]
+pub async fn save_grade_template(
+    template: grade_export::GradeExportTemplate,
+) -> Result<grade_export::GradeExportTemplate, BackendError> {
+    run_blocking(move || grade_export::save_grade_template(template)).await
+}
+
+/// Delete a school export template
+///
+/// # Arguments
+/// * `id` - Template id
+#[tauri::command]
+pub async fn delete_grade_template(id: String) -> Result<(), BackendError> {
+    run_blocking(move || grade_export::delete_grade_template(&id)).await
+}
+
+// ============================================================================
+// Forms Import Commands
+// ============================================================================
+
+/// Match a Google Forms / Microsoft Forms quiz export against a class
+/// without recording anything
+///
+/// # Arguments
+/// * `class_id` - Class that took the quiz
+/// * `path` - Responses export (.csv, or .xlsx from Microsoft Forms)
+/// * `max_points` - Maximum score, needed for Microsoft Forms exports
+///
+/// # Returns
+/// `{ source, matched: [{ row, studentId, studentName, respondent, matchedBy, points, maxPoints, grade }], unmatched: [{ row, name, email, reason }], missingStudents, applied: false }`
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('preview_forms_results', { classId, path });
+/// ```
+#[tauri::command]
+pub async fn preview_forms_results(
+    class_id: String,
+    path: String,
+    max_points: Option<f64>,
+) -> Result<forms_import::FormsImportReport, BackendError> {
+    run_blocking(move || {
+        forms_import::preview_forms_results(&class_id, Path::new(&path), max_points)
+    })
+    .await
+}
+
+/// Record the matched results of a Forms quiz export as one assessment
+///
+/// Scores become 1–10 grades (proportional, nearest quarter). Unmatched
+/// respondents are reported, not recorded; with `strict`, any of them
+/// rejects the whole file instead.
+///
+/// # Arguments
+/// * `class_id` - Class that took the quiz
+/// * `path` - Responses export
+/// * `assessment` - Assessment name for the gradebook
+/// * `weight` - Relative weight (default 1)
+/// * `max_points` - Maximum score, needed for Microsoft Forms exports
+/// * `strict` - All or nothing: fail with `IMPORT_STRICT_REJECTED` (details
+///   list every problem row) and record nothing if any respondent is
+///   unmatched, duplicated or has no score
+///
+/// # Example
+/// ```javascript
+/// const report = await invoke('import_forms_results', {
+///   classId, path, assessment: 'Quiz Rivoluzione francese', maxPoints: 20
+/// });
+/// // All or nothing
+/// await invoke('import_forms_results', { classId, path, assessment, strict: true });
+/// ```
+#[tauri::command]
+pub async fn import_forms_results(
+    class_id: String,
+    path: String,
+    assessment: String,
+    weight: Option<f64>,
+    max_points: Option<f64>,
+    strict: Option<bool>,
+) -> Result<forms_import::FormsImportReport, BackendError> {
+    run_blocking(move || {
+        forms_import::import_forms_results(
+            &class_id,
+            Path::new(&path),
+            &assessment,
+            weight.unwrap_or(1.0),
+            max_points,
+            strict.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+// ============================================================================
+// Profile Settings Commands
+// ============================================================================
+
+/// Show which personal settings differ between two profiles
+///
+/// # Arguments
+/// * `from` - Profile id (`"default"`: no active profile)
+/// * `to` - Profile id to compare with
+///
+/// # Returns
+/// `{ from, to, different: [{ key, from, to }], same: [key] }`; `null`
+/// values mean the default applies
+///
+/// # Example
+/// ```javascript
+/// const { different } = await invoke('diff_profile_settings', { from: 'default', to: newProfile.id });
+/// ```
+#[tauri::command]
+pub async fn diff_profile_settings(
+    from: String,
+    to: String,
+) -> Result<profile_settings::ProfileSettingsDiff, BackendError> {
+    run_blocking(move || profile_settings::diff_profile_settings(&from, &to)).await
+}
+
+/// Copy personal settings from one profile to another
+///
+/// # Arguments
+/// * `from` - Source profile id (`"default"`: no active profile)
+/// * `to` - Target profile id
+/// * `keys` - Settings to copy; all personal settings when omitted
+///
+/// # Returns
+/// The keys copied
+///
+/// # Example
+/// ```javascript
+/// await invoke('copy_settings_between_profiles', {
+///   from: 'default', to: newProfile.id, keys: ['classroom_state_rules', 'presenter_bindings']
+/// });
+/// ```
+#[tauri::command]
+pub async fn copy_settings_between_profiles(
+    from: String,
+    to: String,
+    keys: Option<Vec<String>>,
+) -> Result<Vec<String>, BackendError> {
+    run_blocking(move || profile_settings::copy_settings_between_profiles(&from, &to, keys)).await
+}
+
+// ============================================================================
+// Display Layout Commands
+// ============================================================================
+
+/// Get the current monitor layout
+///
+/// # Returns
+/// { layoutId, monitors: [{ name, x, y, width, height, scaleFactor }], known, restored }
+///
+/// # Example
+/// ```javascript
+/// const display = await invoke('get_display_configuration');
+/// listen('display-configuration-changed', e => console.log(e.payload.restored));
+/// ```
+#[tauri::command]
+pub fn get_display_configuration(
+    app: AppHandle,
+) -> Result<display_layout::DisplayConfiguration, BackendError> {
+    display_layout::get_display_configuration(&app)
+}
+
+/// Remember where the windows are for the current monitor layout
+///
+/// Arrangements are also saved automatically when a layout is left.
+///
+/// # Returns
+/// The current display configuration (now `known`)
+///
+/// # Example
+/// ```javascript
+/// await invoke('save_window_layout');
+/// ```
+#[tauri::command]
+pub fn save_window_layout(
+    app: AppHandle,
+) -> Result<display_layout::DisplayConfiguration, BackendError> {
+    display_layout::save_window_layout(&app)
+}
+
 // ============================================================================
 // Utility Commands
 // ============================================================================
@@ -0,0 +1,475 @@
+//! Companion device authentication for the LAN channel
+//!
+//! Handles:
+//! - Per-session pairing codes shown on the projector
+//! - Signed, short-lived device tokens (HMAC-SHA256)
+//! - Device naming, listing and revocation
+//! - Persisted allowlist of paired devices
+//!
+//! Token format: `<device_id>.<expires_at_ms>.<hex signature>`. The signing
+//! secret is generated on first use and stored with the allowlist, so tokens
+//! from another installation (or from before a reset) never verify.
+//!
+//! There is no LAN listener yet: pairing, token refresh and the companion
+//! submissions (`exit_tickets`, `mood_checkin`, `freeze_broadcast`) are
+//! reachable only as local Tauri commands.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Mutex;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const STORE_COLLECTION: &str = "companion_devices";
+
+/// How long a pairing code stays valid
+pub const PAIRING_CODE_TTL_MS: u64 = 5 * 60 * 1000;
+
+/// How long an issued token stays valid (roughly one lesson block)
+pub const TOKEN_TTL_MS: u64 = 2 * 60 * 60 * 1000;
+
+/// Failed attempts after which the pairing code is burned
+const MAX_PAIRING_ATTEMPTS: u32 = 20;
+
+/// Maximum length of a device display name
+const MAX_DEVICE_NAME_CHARS: usize = 40;
+
+static STORE: SharedStore = SharedStore::new(AuthStore::load, AuthStore::save);
+
+/// A paired companion device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionDevice {
+    pub id: String,
+    pub name: String,
+    pub paired_at: u64,
+    pub last_seen: u64,
+    pub revoked: bool,
+}
+
+/// Active pairing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingSession {
+    pub code: String,
+    pub expires_at: u64,
+    #[serde(default)]
+    pub failed_attempts: u32,
+}
+
+/// Result of a successful pairing or token refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceToken {
+    pub device_id: String,
+    pub token: String,
+    pub expires_at: u64,
+}
+
+/// Persisted auth state: signing secret, allowlist and pairing window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthStore {
+    /// Hex-encoded HMAC secret (generated lazily)
+    #[serde(default)]
+    secret: String,
+    #[serde(default)]
+    pub devices: Vec<CompanionDevice>,
+    #[serde(default)]
+    pub pairing: Option<PairingSession>,
+}
+
+impl AuthStore {
+    fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STORE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STORE_COLLECTION, self)
+    }
+
+    fn secret_bytes(&mut self) -> Result<Vec<u8>, BackendError> {
+        if self.secret.is_empty() {
+            self.secret = hex::encode(random_bytes::<32>()?);
+        }
+        hex::decode(&self.secret).map_err(|e| {
+            BackendError::new(errors::file::INVALID_FORMAT, "Corrupted device auth secret")
+                .with_details(e.to_string())
+        })
+    }
+
+    fn sign(&mut self, payload: &str) -> Result<String, BackendError> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret_bytes()?).map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to initialize signer")
+                .with_details(e.to_string())
+        })?;
+        mac.update(payload.as_bytes());
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn issue_token(&mut self, device_id: &str, now: u64) -> Result<DeviceToken, BackendError> {
+        let expires_at = now + TOKEN_TTL_MS;
+        let payload = format!("{}.{}", device_id, expires_at);
+        let signature = self.sign(&payload)?;
+        Ok(DeviceToken {
+            device_id: device_id.to_string(),
+            token: format!("{}.{}", payload, signature),
+            expires_at,
+        })
+    }
+
+    /// Open a pairing window with a fresh 6-digit code
+    pub fn start_pairing(&mut self, now: u64) -> Result<PairingSession, BackendError> {
+        let bytes = random_bytes::<4>()?;
+        let code = format!("{:06}", u32::from_le_bytes(bytes) % 1_000_000);
+        let session = PairingSession {
+            code,
+            expires_at: now + PAIRING_CODE_TTL_MS,
+            failed_attempts: 0,
+        };
+        self.pairing = Some(session.clone());
+        Ok(session)
+    }
+
+    /// Pair a new device using the code shown on screen
+    pub fn pair(
+        &mut self,
+        code: &str,
+        device_name: &str,
+        now: u64,
+    ) -> Result<DeviceToken, BackendError> {
+        let pairing = match self.pairing.as_mut() {
+            Some(p) if p.expires_at > now => p,
+            _ => {
+                self.pairing = None;
+                return Err(BackendError::new(
+                    errors::auth::PAIRING_CLOSED,
+                    "Pairing is not currently open",
+                ));
+            }
+        };
+
+        if pairing.code != code.trim() {
+            pairing.failed_attempts += 1;
+            if pairing.failed_attempts >= MAX_PAIRING_ATTEMPTS {
+                self.pairing = None;
+            }
+            return Err(BackendError::new(
+                errors::auth::INVALID_PAIRING_CODE,
+                "Invalid pairing code",
+            ));
+        }
+
+        let name: String = device_name
+            .trim()
+            .chars()
+            .take(MAX_DEVICE_NAME_CHARS)
+            .collect();
+        let name = if name.is_empty() {
+            format!("Device {}", self.devices.len() + 1)
+        } else {
+            name
+        };
+
+        let device_id = format!("dev_{}", hex::encode(random_bytes::<8>()?));
+        self.devices.push(CompanionDevice {
+            id: device_id.clone(),
+            name,
+            paired_at: now,
+            last_seen: now,
+            revoked: false,
+        });
+
+        self.issue_token(&device_id, now)
+    }
+
+    /// Verify a token and return the device it belongs to
+    pub fn verify(&mut self, token: &str, now: u64) -> Result<CompanionDevice, BackendError> {
+        let invalid = || BackendError::new(errors::auth::INVALID_TOKEN, "Invalid device token");
+
+        let mut parts = token.rsplitn(2, '.');
+        let signature = parts.next().ok_or_else(invalid)?;
+        let payload = parts.next().ok_or_else(invalid)?;
+        let (device_id, expires_at) = payload.split_once('.').ok_or_else(invalid)?;
+        let expires_at: u64 = expires_at.parse().map_err(|_| invalid())?;
+
+        let signature = hex::decode(signature).map_err(|_| invalid())?;
+        let mut mac = HmacSha256::new_from_slice(&self.secret_bytes()?).map_err(|_| invalid())?;
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| invalid())?;
+
+        if expires_at <= now {
+            return Err(BackendError::new(
+                errors::auth::TOKEN_EXPIRED,
+                "Device token has expired",
+            ));
+        }
+
+        let device = self
+            .devices
+            .iter_mut()
+            .find(|d| d.id == device_id)
+            .ok_or_else(invalid)?;
+
+        if device.revoked {
+            return Err(BackendError::new(
+                errors::auth::DEVICE_REVOKED,
+                "Device access has been revoked",
+            ));
+        }
+
+        device.last_seen = now;
+        Ok(device.clone())
+    }
+
+    /// Exchange a still-valid token for a fresh one
+    pub fn refresh(&mut self, token: &str, now: u64) -> Result<DeviceToken, BackendError> {
+        let device = self.verify(token, now)?;
+        self.issue_token(&device.id, now)
+    }
+
+    fn device_mut(&mut self, device_id: &str) -> Result<&mut CompanionDevice, BackendError> {
+        self.devices
+            .iter_mut()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| {
+                BackendError::new(errors::auth::DEVICE_NOT_FOUND, "Device not found")
+                    .with_details(device_id.to_string())
+            })
+    }
+}
+
+/// The persisted store behind one lock
+///
+/// Every load → change → save holds the lock, so a revoke can't be undone
+/// by an authentication that loaded the store before it and saves its
+/// `last_seen` update after.
+struct SharedStore {
+    lock: Mutex<()>,
+    load: fn() -> Result<AuthStore, BackendError>,
+    save: fn(&AuthStore) -> Result<(), BackendError>,
+}
+
+impl SharedStore {
+    const fn new(
+        load: fn() -> Result<AuthStore, BackendError>,
+        save: fn(&AuthStore) -> Result<(), BackendError>,
+    ) -> Self {
+        Self {
+            lock: Mutex::new(()),
+            load,
+            save,
+        }
+    }
+
+    fn read<T>(&self, f: impl FnOnce(AuthStore) -> T) -> Result<T, BackendError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(f((self.load)()?))
+    }
+
+    /// Change the store and save it if `f` succeeds
+    fn update<T>(
+        &self,
+        f: impl FnOnce(&mut AuthStore) -> Result<T, BackendError>,
+    ) -> Result<T, BackendError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut store = (self.load)()?;
+        let result = f(&mut store)?;
+        (self.save)(&store)?;
+        Ok(result)
+    }
+}
+
+/// Fill a fixed-size buffer from the OS random source
+fn random_bytes<const N: usize>() -> Result<[u8; N], BackendError> {
+    let mut buf = [0u8; N];
+    getrandom::fill(&mut buf).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    Ok(buf)
+}
+
+/// Open a pairing window and return the code to display
+pub fn start_pairing() -> Result<PairingSession, BackendError> {
+    STORE.update(|store| store.start_pairing(clock::now_millis()))
+}
+
+/// Get the currently open pairing window
+pub fn current_pairing() -> Result<PairingSession, BackendError> {
+    STORE
+        .read(|store| store.pairing)?
+        .filter(|p| p.expires_at > clock::now_millis())
+        .ok_or_else(|| {
+            BackendError::new(
+                errors::auth::PAIRING_CLOSED,
+                "Pairing is not currently open",
+            )
+        })
+}
+
+/// Close the pairing window early
+pub fn stop_pairing() -> Result<(), BackendError> {
+    STORE.update(|store| {
+        store.pairing = None;
+        Ok(())
+    })
+}
+
+/// Pair a device with the current pairing code
+pub fn pair_device(code: &str, device_name: &str) -> Result<DeviceToken, BackendError> {
+    // Saved even on failure so attempt counters survive
+    STORE.update(|store| Ok(store.pair(code, device_name, clock::now_millis())))?
+}
+
+/// Verify a device token (used by every companion-facing entry point)
+pub fn authenticate(token: &str) -> Result<CompanionDevice, BackendError> {
+    STORE.update(|store| store.verify(token, clock::now_millis()))
+}
+
+/// Exchange a valid token for a fresh one
+pub fn refresh_device_token(token: &str) -> Result<DeviceToken, BackendError> {
+    STORE.update(|store| store.refresh(token, clock::now_millis()))
+}
+
+/// List all paired devices, including revoked ones
+pub fn list_devices() -> Result<Vec<CompanionDevice>, BackendError> {
+    STORE.read(|store| store.devices)
+}
+
+/// Rename a paired device
+pub fn rename_device(device_id: &str, name: &str) -> Result<(), BackendError> {
+    let name: String = name.trim().chars().take(MAX_DEVICE_NAME_CHARS).collect();
+    if name.is_empty() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Device name cannot be empty",
+        ));
+    }
+    STORE.update(|store| {
+        store.device_mut(device_id)?.name = name;
+        Ok(())
+    })
+}
+
+/// Revoke a device so its tokens stop verifying
+pub fn revoke_device(device_id: &str) -> Result<(), BackendError> {
+    STORE.update(|store| {
+        store.device_mut(device_id)?.revoked = true;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paired_store() -> (AuthStore, DeviceToken) {
+        let mut store = AuthStore::default();
+        let code = store.start_pairing(1_000).unwrap().code;
+        let token = store.pair(&code, "Tablet 7", 2_000).unwrap();
+        (store, token)
+    }
+
+    #[test]
+    fn test_pair_and_verify() {
+        let (mut store, token) = paired_store();
+        let device = store.verify(&token.token, 3_000).unwrap();
+        assert_eq!(device.id, token.device_id);
+        assert_eq!(device.name, "Tablet 7");
+    }
+
+    #[test]
+    fn test_wrong_code_and_expired_window() {
+        let mut store = AuthStore::default();
+        let code = store.start_pairing(0).unwrap().code;
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+        let err = store.pair(wrong, "x", 1).unwrap_err();
+        assert_eq!(err.code, errors::auth::INVALID_PAIRING_CODE);
+
+        let err = store.pair(&code, "x", PAIRING_CODE_TTL_MS + 1).unwrap_err();
+        assert_eq!(err.code, errors::auth::PAIRING_CLOSED);
+    }
+
+    #[test]
+    fn test_tampered_token_rejected() {
+        let (mut store, token) = paired_store();
+        // Try to impersonate another device by swapping the id
+        let forged = token
+            .token
+            .replacen(&token.device_id, "dev_0000000000000000", 1);
+        let err = store.verify(&forged, 3_000).unwrap_err();
+        assert_eq!(err.code, errors::auth::INVALID_TOKEN);
+    }
+
+    #[test]
+    fn test_expired_and_revoked_tokens() {
+        let (mut store, token) = paired_store();
+        let err = store.verify(&token.token, token.expires_at).unwrap_err();
+        assert_eq!(err.code, errors::auth::TOKEN_EXPIRED);
+
+        store.device_mut(&token.device_id).unwrap().revoked = true;
+        let err = store.verify(&token.token, 3_000).unwrap_err();
+        assert_eq!(err.code, errors::auth::DEVICE_REVOKED);
+    }
+
+    #[test]
+    fn test_token_from_other_installation_rejected() {
+        let (_, token) = paired_store();
+        let (mut other, _) = paired_store();
+        assert!(other.verify(&token.token, 3_000).is_err());
+    }
+
+    static DISK: Mutex<Option<AuthStore>> = Mutex::new(None);
+
+    fn load_disk() -> Result<AuthStore, BackendError> {
+        Ok(DISK.lock().unwrap().clone().unwrap_or_default())
+    }
+
+    fn save_disk(store: &AuthStore) -> Result<(), BackendError> {
+        // Widen the window between load and save
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        *DISK.lock().unwrap() = Some(store.clone());
+        Ok(())
+    }
+
+    #[test]
+    fn test_revoke_while_authenticating() {
+        let (store, token) = paired_store();
+        *DISK.lock().unwrap() = Some(store);
+        static SHARED: SharedStore = SharedStore::new(load_disk, save_disk);
+
+        let device_token = token.token.clone();
+        // Authenticate until refused, bounded in case a stale save lets the
+        // device back in
+        let authenticating = std::thread::spawn(move || {
+            let mut results = Vec::new();
+            while results.len() < 1_000 {
+                let result = SHARED.update(|store| store.verify(&device_token, 3_000));
+                let refused = result.is_err();
+                results.push(result);
+                if refused {
+                    break;
+                }
+            }
+            results
+        });
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        SHARED
+            .update(|store| {
+                store.device_mut(&token.device_id)?.revoked = true;
+                Ok(())
+            })
+            .unwrap();
+        let results = authenticating.join().unwrap();
+
+        // Once revoked, stays revoked: no authentication saved a stale copy
+        assert!(SHARED.read(|store| store.devices[0].revoked).unwrap());
+        let last = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(last.code, errors::auth::DEVICE_REVOKED);
+        let err = SHARED
+            .update(|store| store.verify(&token.token, 3_000))
+            .unwrap_err();
+        assert_eq!(err.code, errors::auth::DEVICE_REVOKED);
+    }
+}
@@ -0,0 +1,147 @@
+//! Keeping windows with student data out of screen sharing
+//!
+//! Handles:
+//! - Marking a window as protected: it shows up black (Windows, through
+//!   `SetWindowDisplayAffinity`) or not at all (macOS, through the window's
+//!   `sharingType`) in screen sharing, recordings and screenshots taken by
+//!   other apps, while the teacher still sees it normally
+//! - Remembering protected windows by label (config `content_protection`),
+//!   so a window is protected again whenever it is opened or reloaded
+//!
+//! Linux compositors have no such setting; there the choice is saved but
+//! `supported` is false so the UI can say so.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+const CONFIG_KEY: &str = "content_protection";
+
+/// Protection of one window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentProtection {
+    pub window_label: String,
+    pub enabled: bool,
+    /// Whether this platform can hide windows from capture
+    pub supported: bool,
+}
+
+/// Protected windows and whether the platform honors it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentProtectionStatus {
+    pub supported: bool,
+    pub windows: Vec<String>,
+}
+
+pub fn is_supported() -> bool {
+    cfg!(any(target_os = "windows", target_os = "macos"))
+}
+
+/// Labels of the protected windows
+pub fn protected_windows() -> Vec<String> {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn get_content_protection() -> ContentProtectionStatus {
+    ContentProtectionStatus {
+        supported: is_supported(),
+        windows: protected_windows(),
+    }
+}
+
+/// `labels` with `label` added or removed, sorted
+fn toggled(mut labels: Vec<String>, label: &str, enabled: bool) -> Vec<String> {
+    labels.retain(|l| l != label);
+    if enabled {
+        labels.push(label.to_string());
+    }
+    labels.sort();
+    labels
+}
+
+/// Apply protection to an open window
+pub fn protect(window: &WebviewWindow, enabled: bool) -> Result<(), BackendError> {
+    window.set_content_protected(enabled).map_err(|e| {
+        BackendError::new(
+            errors::window::PROTECTION_FAILED,
+            "Failed to change screen capture protection",
+        )
+        .with_details(format!("{}: {}", window.label(), e))
+    })
+}
+
+/// Protect a window from screen capture, or stop protecting it
+///
+/// The window doesn't have to be open: the setting applies when it next
+/// loads.
+pub fn set_content_protection(
+    app: &AppHandle,
+    label: &str,
+    enabled: bool,
+) -> Result<ContentProtection, BackendError> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Window label is required",
+        ));
+    }
+    if let Some(window) = app.get_webview_window(label) {
+        protect(&window, enabled)?;
+    }
+    let labels = toggled(protected_windows(), label, enabled);
+    let value = serde_json::to_value(&labels).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize setting")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(ContentProtection {
+        window_label: label.to_string(),
+        enabled,
+        supported: is_supported(),
+    })
+}
+
+/// Re-apply a window's saved protection (page load hook in `lib.rs`)
+pub fn apply_saved(webview: &tauri::Webview) {
+    let label = webview.label();
+    if !protected_windows().iter().any(|l| l == label) {
+        return;
+    }
+    if let Some(window) = webview.app_handle().get_webview_window(label) {
+        if let Err(e) = protect(&window, true) {
+            eprintln!("Content protection: {}", e.message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggled() {
+        let labels = toggled(Vec::new(), "main", true);
+        let labels = toggled(labels, "gradebook", true);
+        assert_eq!(labels, ["gradebook", "main"]);
+        // Enabling twice keeps one entry
+        assert_eq!(toggled(labels.clone(), "main", true), labels);
+        assert_eq!(toggled(labels, "main", false), ["gradebook"]);
+    }
+
+    #[test]
+    fn test_saved_labels_match_schema() {
+        use crate::file_ops::config_schema;
+        let labels = toggled(vec!["observer".into()], "main", true);
+        let value = serde_json::to_value(&labels).unwrap();
+        assert!(config_schema::validate(CONFIG_KEY, &value).is_ok());
+        let duplicated = serde_json::json!(["main", "main"]);
+        assert!(config_schema::validate(CONFIG_KEY, &duplicated).is_err());
+    }
+}
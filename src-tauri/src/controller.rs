@@ -0,0 +1,560 @@
+//! Local HTTP listener for Stream Deck and other external controllers
+//!
+//! Handles:
+//! - An opt-in listener on `127.0.0.1` (`controller_listener` config key)
+//! - Token authentication (`Authorization: Bearer <token>` or `?token=`),
+//!   the token lives in the system keychain
+//! - Mapping requests onto the action dispatcher:
+//!   - `GET /actions` lists the available actions
+//!   - `POST /actions/<id>` runs one, the JSON body is its `args`; refused
+//!     while the app is locked, and checked like the command the action
+//!     stands for
+//! - At most `MAX_CONNECTIONS` requests handled at once; more get a 503
+//!
+//! Stream Deck "Web Requests"-style plugins run on the same PC, so the
+//! listener never binds to the LAN.
+
+use crate::actions;
+use crate::app_lock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::locale;
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::AppHandle;
+
+const CONFIG_KEY: &str = "controller_listener";
+pub(crate) const TOKEN_SECRET: &str = "controller_token";
+
+/// Default port of the controller listener
+pub const DEFAULT_CONTROLLER_PORT: u16 = 8766;
+
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const MAX_BODY_BYTES: usize = 16 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_CONNECTIONS: usize = 8;
+
+/// Handle of the listener, kept in `AppState`
+pub struct ControllerListener {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// A connection counted towards `MAX_CONNECTIONS`, released on drop
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Persisted listener settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControllerConfig {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for ControllerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_CONTROLLER_PORT,
+        }
+    }
+}
+
+/// Listener state shown in settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerInfo {
+    pub enabled: bool,
+    pub port: u16,
+    pub running: bool,
+    /// Base URL to enter in the controller plugin
+    pub url: String,
+    /// Token to enter in the controller plugin; `None` until first enabled
+    pub token: Option<String>,
+}
+
+/// A parsed HTTP request
+#[derive(Debug, Clone, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    query_token: Option<String>,
+    bearer: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Response status and JSON body
+#[derive(Debug, Clone, PartialEq)]
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, err: &BackendError) -> Self {
+        Self {
+            status,
+            body: json!({ "error": err }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            423 => "Locked",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Load the persisted listener settings
+pub fn get_config() -> ControllerConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn token() -> Result<Option<String>, BackendError> {
+    secrets::get_secret(TOKEN_SECRET)
+}
+
+/// Create a new token; plugins using the old one stop working
+pub fn regenerate_token() -> Result<String, BackendError> {
+    let mut buf = [0u8; 24];
+    getrandom::fill(&mut buf).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    let token = hex::encode(buf);
+    secrets::set_secret(TOKEN_SECRET, &token)?;
+    Ok(token)
+}
+
+impl ControllerListener {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(None),
+        }
+    }
+
+    /// Current listener state
+    pub fn info(&self) -> Result<ControllerInfo, BackendError> {
+        let config = get_config();
+        let running = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|r| r.port);
+        Ok(ControllerInfo {
+            enabled: config.enabled,
+            port: config.port,
+            running: running.is_some(),
+            url: format!("http://127.0.0.1:{}", running.unwrap_or(config.port)),
+            token: token()?,
+        })
+    }
+
+    /// Enable or disable the listener, restarting it on the new port
+    pub fn set_enabled(
+        &self,
+        app: &AppHandle,
+        enabled: bool,
+        port: Option<u16>,
+    ) -> Result<ControllerInfo, BackendError> {
+        let config = ControllerConfig {
+            enabled,
+            port: port.unwrap_or(get_config().port),
+        };
+        if config.port < 1024 {
+            return Err(BackendError::new(
+                errors::system::INVALID_INPUT,
+                "Port must be between 1024 and 65535",
+            ));
+        }
+        self.stop();
+        if enabled {
+            if token()?.is_none() {
+                regenerate_token()?;
+            }
+            self.listen(app.clone(), config.port)?;
+        }
+        let value = serde_json::to_value(&config).map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+                .with_details(e.to_string())
+        })?;
+        file_ops::save_config(CONFIG_KEY, value)?;
+        self.info()
+    }
+
+    /// Start the listener at launch if it is enabled
+    pub fn start(&self, app: AppHandle) {
+        let config = get_config();
+        if config.enabled {
+            if let Err(e) = self.listen(app, config.port) {
+                eprintln!("Controller listener not started: {}", e);
+            }
+        }
+    }
+
+    fn listen(&self, app: AppHandle, port: u16) -> Result<(), BackendError> {
+        let listener =
+            TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).map_err(|e| {
+                BackendError::new(errors::lan::NETWORK_ERROR, "Failed to open controller port")
+                    .with_details(format!("{}: {}", port, e))
+            })?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let active = Arc::new(AtomicUsize::new(0));
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(mut stream) = stream else { continue };
+                if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    let _ = stream.set_write_timeout(Some(READ_TIMEOUT));
+                    let busy =
+                        BackendError::new(errors::system::UNKNOWN_ERROR, "Too many requests");
+                    write_response(&mut stream, &Response::error(503, &busy));
+                    continue;
+                }
+                let app = app.clone();
+                let slot = Slot(active.clone());
+                std::thread::spawn(move || {
+                    let _slot = slot;
+                    handle_connection(&app, stream);
+                });
+            }
+        });
+        *self.running.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(Running { port, stop, thread });
+        Ok(())
+    }
+
+    /// Stop the listener if it is running
+    pub fn stop(&self) {
+        let Some(running) = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        else {
+            return;
+        };
+        running.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept so the thread sees the flag and frees the port
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, running.port));
+        let _ = running.thread.join();
+    }
+}
+
+impl Default for ControllerListener {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn handle_connection(app: &AppHandle, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(READ_TIMEOUT));
+    let response = match read_request(&mut stream) {
+        Ok(request) => match token() {
+            Ok(expected) => route(
+                &request,
+                expected.as_deref(),
+                app_lock::is_locked(),
+                |id, args| {
+                    tauri::async_runtime::block_on(actions::execute_action(
+                        app,
+                        actions::CONTROLLER_SOURCE,
+                        id,
+                        args,
+                    ))
+                },
+            ),
+            Err(e) => Response::error(500, &e),
+        },
+        Err((status, e)) => Response::error(status, &e),
+    };
+    write_response(&mut stream, &response);
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) {
+    let body = response.body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        body
+    );
+}
+
+fn bad_request(message: &str) -> (u16, BackendError) {
+    (
+        400,
+        BackendError::new(errors::system::INVALID_INPUT, message),
+    )
+}
+
+fn read_request(stream: impl Read) -> Result<Request, (u16, BackendError)> {
+    let mut reader = BufReader::new(stream.take((MAX_HEADER_BYTES + MAX_BODY_BYTES) as u64));
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|_| bad_request("Malformed request"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query_token: query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(str::to_string),
+        bearer: None,
+        body: Vec::new(),
+    };
+
+    let mut content_length = 0;
+    let mut header_bytes = line.len();
+    loop {
+        line.clear();
+        let n = reader
+            .read_line(&mut line)
+            .map_err(|_| bad_request("Malformed headers"))?;
+        header_bytes += n;
+        if header_bytes > MAX_HEADER_BYTES {
+            return Err(bad_request("Headers too large"));
+        }
+        let header = line.trim_end();
+        if n == 0 || header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse()
+                .map_err(|_| bad_request("Invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            request.bearer = value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err((
+            413,
+            BackendError::new(errors::system::INVALID_INPUT, "Request body too large"),
+        ));
+    }
+    request.body = vec![0; content_length];
+    reader
+        .read_exact(&mut request.body)
+        .map_err(|_| bad_request("Incomplete request body"))?;
+    Ok(request)
+}
+
+/// Compare without leaking where the first mismatch is
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn route(
+    request: &Request,
+    expected_token: Option<&str>,
+    locked: bool,
+    execute: impl FnOnce(&str, Value) -> Result<actions::ActionResult, BackendError>,
+) -> Response {
+    let given = request.bearer.as_deref().or(request.query_token.as_deref());
+    let authorized = matches!((given, expected_token), (Some(g), Some(e)) if tokens_match(g, e));
+    if !authorized {
+        return Response::error(
+            401,
+            &BackendError::new(errors::auth::INVALID_TOKEN, "Invalid controller token"),
+        );
+    }
+
+    let path = request.path.trim_end_matches('/');
+    match (request.method.as_str(), path) {
+        ("GET", "/actions") => Response::ok(json!(actions::list_actions(locale::app_language()))),
+        ("POST", _) if path.starts_with("/actions/") && locked => Response::error(
+            423,
+            &BackendError::new(errors::lock::LOCKED, "The app is locked"),
+        ),
+        ("POST", _) if path.starts_with("/actions/") => {
+            let id = &path["/actions/".len()..];
+            let args = if request.body.iter().all(u8::is_ascii_whitespace) {
+                Value::Null
+            } else {
+                match serde_json::from_slice(&request.body) {
+                    Ok(args) => args,
+                    Err(e) => {
+                        return Response::error(
+                            400,
+                            &BackendError::new(errors::system::INVALID_INPUT, "Body is not JSON")
+                                .with_details(e.to_string()),
+                        )
+                    }
+                }
+            };
+            match execute(id, args) {
+                Ok(result) => Response::ok(json!(result)),
+                Err(e) if e.code == errors::action::NOT_FOUND => Response::error(404, &e),
+                Err(e) if e.code == errors::system::INVALID_INPUT => Response::error(400, &e),
+                Err(e) if e.code == errors::lock::LOCKED => Response::error(423, &e),
+                Err(e)
+                    if [
+                        errors::role::FORBIDDEN,
+                        errors::observer::READ_ONLY,
+                        errors::system::READ_ONLY_MODE,
+                    ]
+                    .contains(&e.code.as_str()) =>
+                {
+                    Response::error(403, &e)
+                }
+                Err(e) => Response::error(500, &e),
+            }
+        }
+        (_, "/actions") => Response::error(
+            405,
+            &BackendError::new(errors::system::INVALID_INPUT, "Method not allowed"),
+        ),
+        _ => Response::error(
+            404,
+            &BackendError::new(errors::action::NOT_FOUND, "Unknown path")
+                .with_details(path.to_string()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{ActionHandler, ActionResult};
+
+    fn executed(id: &str, args: Value) -> Result<ActionResult, BackendError> {
+        Ok(ActionResult {
+            id: id.to_string(),
+            handler: ActionHandler::Frontend,
+            result: args,
+        })
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /actions/timer.start?token=abc HTTP/1.1\r\nHost: localhost\r\n\
+                   Authorization: Bearer xyz\r\nContent-Length: 14\r\n\r\n{\"minutes\": 5}";
+        let request = read_request(raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/actions/timer.start");
+        assert_eq!(request.query_token.as_deref(), Some("abc"));
+        assert_eq!(request.bearer.as_deref(), Some("xyz"));
+        assert_eq!(request.body, b"{\"minutes\": 5}");
+
+        let raw = "POST /actions/x HTTP/1.1\r\nContent-Length: 99999\r\n\r\n";
+        assert_eq!(read_request(raw.as_bytes()).unwrap_err().0, 413);
+    }
+
+    #[test]
+    fn test_route_requires_token() {
+        let mut request = Request {
+            method: "POST".to_string(),
+            path: "/actions/student.pick".to_string(),
+            query_token: None,
+            bearer: Some("wrong".to_string()),
+            body: b"{\"classId\":\"3A\"}".to_vec(),
+        };
+        assert_eq!(route(&request, Some("secret"), false, executed).status, 401);
+        assert_eq!(route(&request, None, false, executed).status, 401);
+
+        request.bearer = None;
+        request.query_token = Some("secret".to_string());
+        let response = route(&request, Some("secret"), false, executed);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["id"], "student.pick");
+        assert_eq!(response.body["result"]["classId"], "3A");
+    }
+
+    #[test]
+    fn test_route_maps_errors_to_status() {
+        let request = Request {
+            method: "POST".to_string(),
+            path: "/actions/nope".to_string(),
+            query_token: None,
+            bearer: Some("t".to_string()),
+            body: Vec::new(),
+        };
+        let not_found = route(&request, Some("t"), false, |id, _| {
+            Err(BackendError::new(errors::action::NOT_FOUND, "Unknown action").with_details(id))
+        });
+        assert_eq!(not_found.status, 404);
+        let forbidden = route(&request, Some("t"), false, |_, _| {
+            Err(BackendError::new(errors::role::FORBIDDEN, "Not allowed"))
+        });
+        assert_eq!(forbidden.status, 403);
+
+        // Nothing runs while the app is locked
+        let locked = route(&request, Some("t"), true, |_, _| {
+            panic!("action ran while locked")
+        });
+        assert_eq!(locked.status, 423);
+        assert_eq!(locked.body["error"]["code"], errors::lock::LOCKED);
+
+        let bad_json = Request {
+            body: b"{not json".to_vec(),
+            ..request.clone()
+        };
+        assert_eq!(route(&bad_json, Some("t"), false, executed).status, 400);
+
+        let wrong_method = Request {
+            method: "DELETE".to_string(),
+            path: "/actions".to_string(),
+            ..request
+        };
+        assert_eq!(route(&wrong_method, Some("t"), false, executed).status, 405);
+    }
+}
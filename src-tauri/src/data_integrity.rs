@@ -0,0 +1,455 @@
+//! Data integrity check for the "Troubleshoot" button
+//!
+//! Handles:
+//! - Parsing the config file and validating every setting against its
+//!   schema (see `file_ops::config_schema`)
+//! - Parsing every data collection (`data/*.json`); the collections are the
+//!   app's database, so this stands in for a database integrity check
+//! - Re-hashing managed attachments and finding blobs nothing refers to
+//! - Decoding stored student photos and finding photos of deleted classes
+//!
+//! Nothing is changed: every problem comes with a suggestion and, where a
+//! command can fix it, the repair the UI can offer (`repair`).
+
+use crate::attachments::{self, Attachment, AttachmentStore};
+use crate::clock;
+use crate::errors::BackendError;
+use crate::file_ops::{self, config_schema};
+use crate::jobs::JobContext;
+use crate::photos;
+use crate::roster::RosterStore;
+use image::ImageReader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Wasted space or leftovers; the app works
+    Warning,
+    /// Data can't be read or doesn't match what was stored
+    Error,
+}
+
+/// Fix the UI can run for an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum RepairAction {
+    /// `reset_setting` with `key`
+    #[serde(rename_all = "camelCase")]
+    ResetSetting { key: String },
+    /// Restore the latest backup
+    RestoreBackup,
+    /// `remove_attachment` with `attachment_id`
+    #[serde(rename_all = "camelCase")]
+    RemoveAttachment { attachment_id: String },
+    /// `import_photos_from_folder` for `class_id`
+    #[serde(rename_all = "camelCase")]
+    ReimportPhotos { class_id: String },
+}
+
+/// One problem found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    /// "config", "data", "attachments" or "photos"
+    pub area: String,
+    /// Setting key, collection, attachment or file concerned
+    pub target: String,
+    pub severity: Severity,
+    pub problem: String,
+    pub suggestion: String,
+    pub repair: Option<RepairAction>,
+}
+
+/// Result of `verify_data_integrity`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub checked_at: u64,
+    /// No errors (warnings allowed)
+    pub ok: bool,
+    /// Items checked per area
+    pub checked: BTreeMap<String, usize>,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+fn issue(
+    area: &str,
+    target: impl Into<String>,
+    severity: Severity,
+    problem: impl Into<String>,
+    suggestion: &str,
+    repair: Option<RepairAction>,
+) -> IntegrityIssue {
+    IntegrityIssue {
+        area: area.to_string(),
+        target: target.into(),
+        severity,
+        problem: problem.into(),
+        suggestion: suggestion.to_string(),
+        repair,
+    }
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Config file: readable JSON, every setting valid; returns the keys checked
+fn check_config(dir: &Path, issues: &mut Vec<IntegrityIssue>) -> usize {
+    let Ok(content) = fs::read_to_string(dir.join(file_ops::CONFIG_FILENAME)) else {
+        return 0;
+    };
+    let config = match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(config)) => config,
+        Ok(_) => {
+            issues.push(issue(
+                "config",
+                file_ops::CONFIG_FILENAME,
+                Severity::Error,
+                "Settings file is not a JSON object",
+                "Restore a backup, or reset all settings",
+                Some(RepairAction::RestoreBackup),
+            ));
+            return 0;
+        }
+        Err(e) => {
+            issues.push(issue(
+                "config",
+                file_ops::CONFIG_FILENAME,
+                Severity::Error,
+                format!("Settings file is damaged: {}", e),
+                "Restore a backup, or reset all settings",
+                Some(RepairAction::RestoreBackup),
+            ));
+            return 0;
+        }
+    };
+    for (key, value) in &config {
+        if let Err(e) = config_schema::validate(key, value) {
+            issues.push(issue(
+                "config",
+                key.as_str(),
+                Severity::Error,
+                e.details.unwrap_or(e.message),
+                "Reset this setting to its default",
+                Some(RepairAction::ResetSetting { key: key.clone() }),
+            ));
+        }
+    }
+    config.len()
+}
+
+/// Data collections: every file parses; returns the files checked
+pub(crate) fn check_data(dir: &Path, issues: &mut Vec<IntegrityIssue>) -> usize {
+    let files: Vec<PathBuf> = files_in(&dir.join(file_ops::DATA_DIR))
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    for path in &files {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| serde_json::from_str::<Value>(&c).map_err(|e| e.to_string()));
+        if let Err(e) = parsed {
+            issues.push(issue(
+                "data",
+                file_name(path),
+                Severity::Error,
+                format!("Data file is damaged: {}", e),
+                "Restore a backup from before the damage",
+                Some(RepairAction::RestoreBackup),
+            ));
+        }
+    }
+    files.len()
+}
+
+/// Attachments: each stored file exists and matches its hash; blobs no
+/// attachment refers to are reported. Returns the attachments checked.
+fn check_attachments(
+    dir: &Path,
+    records: &[Attachment],
+    mut progress: impl FnMut(usize, usize, &str) -> Result<(), BackendError>,
+    issues: &mut Vec<IntegrityIssue>,
+) -> Result<usize, BackendError> {
+    let storage = dir.join(attachments::STORAGE_DIR);
+    // Stored name → problem; content shared by several attachments is
+    // hashed once
+    let mut checked: HashMap<String, Option<&str>> = HashMap::new();
+    for (done, attachment) in records.iter().enumerate() {
+        progress(done, records.len(), &attachment.file_name)?;
+        let stored = attachment.stored_name();
+        let problem = *checked.entry(stored.clone()).or_insert_with(|| {
+            let path = storage.join(&stored);
+            if !path.is_file() {
+                Some("Stored file is missing")
+            } else if attachments::hash_file(&path).ok().as_deref() != Some(&attachment.hash) {
+                Some("Stored file doesn't match its checksum")
+            } else {
+                None
+            }
+        });
+        if let Some(problem) = problem {
+            issues.push(issue(
+                "attachments",
+                attachment.file_name.as_str(),
+                Severity::Error,
+                problem,
+                "Remove the attachment and attach the original file again",
+                Some(RepairAction::RemoveAttachment {
+                    attachment_id: attachment.id.clone(),
+                }),
+            ));
+        }
+    }
+    for path in files_in(&storage) {
+        let name = file_name(&path);
+        if !checked.contains_key(&name) {
+            issues.push(issue(
+                "attachments",
+                name,
+                Severity::Warning,
+                "File is not used by any attachment",
+                "It can be deleted to free space",
+                None,
+            ));
+        }
+    }
+    Ok(records.len())
+}
+
+/// Student photos: each decodes, each class still exists; returns the
+/// photos checked
+fn check_photos(dir: &Path, class_ids: &HashSet<&str>, issues: &mut Vec<IntegrityIssue>) -> usize {
+    let Ok(entries) = fs::read_dir(dir.join(photos::PHOTOS_DIR)) else {
+        return 0;
+    };
+    let mut class_dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    class_dirs.sort();
+
+    let mut checked = 0;
+    for class_dir in class_dirs {
+        let class_id = file_name(&class_dir);
+        if !class_ids.contains(class_id.as_str()) {
+            issues.push(issue(
+                "photos",
+                class_id,
+                Severity::Warning,
+                "Photos of a class that no longer exists",
+                "The folder can be deleted to free space",
+                None,
+            ));
+            continue;
+        }
+        for path in files_in(&class_dir) {
+            checked += 1;
+            let decoded = ImageReader::open(&path)
+                .and_then(|r| r.with_guessed_format())
+                .map_err(|e| e.to_string())
+                .and_then(|r| r.decode().map_err(|e| e.to_string()));
+            if let Err(e) = decoded {
+                issues.push(issue(
+                    "photos",
+                    format!("{}/{}", class_id, file_name(&path)),
+                    Severity::Error,
+                    format!("Photo can't be read: {}", e),
+                    "Import the class's photos again",
+                    Some(RepairAction::ReimportPhotos {
+                        class_id: class_id.clone(),
+                    }),
+                ));
+            }
+        }
+    }
+    checked
+}
+
+fn verify_in(
+    dir: &Path,
+    records: &[Attachment],
+    class_ids: &HashSet<&str>,
+    progress: impl FnMut(usize, usize, &str) -> Result<(), BackendError>,
+) -> Result<IntegrityReport, BackendError> {
+    let mut issues = Vec::new();
+    let mut checked = BTreeMap::new();
+    checked.insert("config".to_string(), check_config(dir, &mut issues));
+    checked.insert("data".to_string(), check_data(dir, &mut issues));
+    checked.insert(
+        "attachments".to_string(),
+        check_attachments(dir, records, progress, &mut issues)?,
+    );
+    checked.insert(
+        "photos".to_string(),
+        check_photos(dir, class_ids, &mut issues),
+    );
+    Ok(IntegrityReport {
+        checked_at: clock::now_millis(),
+        ok: !issues.iter().any(|i| i.severity == Severity::Error),
+        checked,
+        issues,
+    })
+}
+
+/// Check config, data collections, attachments and photos
+///
+/// Collections that can't be loaded are reported by the data check; the
+/// attachment and photo checks then work with what could be read.
+pub fn verify_data_integrity(context: &JobContext) -> Result<IntegrityReport, BackendError> {
+    let records = AttachmentStore::load().unwrap_or_default().attachments;
+    let roster = RosterStore::load().unwrap_or_default();
+    let class_ids: HashSet<&str> = roster.classes.iter().map(|c| c.id.as_str()).collect();
+    verify_in(
+        &file_ops::get_config_dir()?,
+        &records,
+        &class_ids,
+        |done, total, file| {
+            context.check_cancelled()?;
+            context.progress(done, total, file);
+            Ok(())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attachment(id: &str, file_name: &str, content: &[u8], dir: &Path) -> Attachment {
+        let storage = dir.join(attachments::STORAGE_DIR);
+        fs::create_dir_all(&storage).unwrap();
+        let source = dir.join("source.tmp");
+        fs::write(&source, content).unwrap();
+        let hash = attachments::hash_file(&source).unwrap();
+        fs::remove_file(&source).unwrap();
+        let attachment = Attachment {
+            id: id.to_string(),
+            entity: "class:3A".to_string(),
+            file_name: file_name.to_string(),
+            hash,
+            size: content.len() as u64,
+            added_at: 0,
+        };
+        fs::write(storage.join(attachment.stored_name()), content).unwrap();
+        attachment
+    }
+
+    #[test]
+    fn test_config_and_data_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = json!({
+            "window_config": "overlay",
+            "webview_zoom": { "main": 8 },
+            "unknown_key": true
+        });
+        fs::write(
+            dir.path().join(file_ops::CONFIG_FILENAME),
+            config.to_string(),
+        )
+        .unwrap();
+        let data = dir.path().join(file_ops::DATA_DIR);
+        fs::create_dir_all(&data).unwrap();
+        fs::write(data.join("rosters.json"), r#"{"classes":[]}"#).unwrap();
+        fs::write(data.join("gradebook.json"), r#"{"grades":[{"#).unwrap();
+
+        let mut issues = Vec::new();
+        assert_eq!(check_config(dir.path(), &mut issues), 3);
+        assert_eq!(check_data(dir.path(), &mut issues), 2);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[0].repair,
+            Some(RepairAction::ResetSetting {
+                key: "webview_zoom".into()
+            })
+        );
+        assert_eq!(issues[1].target, "gradebook.json");
+        assert_eq!(issues[1].repair, Some(RepairAction::RestoreBackup));
+
+        fs::write(dir.path().join(file_ops::CONFIG_FILENAME), "{ broken").unwrap();
+        let mut issues = Vec::new();
+        check_config(dir.path(), &mut issues);
+        assert_eq!(issues[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_attachment_and_photo_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = attachment("a1", "worksheet.pdf", b"worksheet", dir.path());
+        let shared = Attachment {
+            id: "a2".into(),
+            ..good.clone()
+        };
+        let tampered = attachment("a3", "slides.pptx", b"slides", dir.path());
+        let storage = dir.path().join(attachments::STORAGE_DIR);
+        fs::write(storage.join(tampered.stored_name()), b"changed").unwrap();
+        let missing = Attachment {
+            id: "a4".into(),
+            hash: "0".repeat(64),
+            ..good.clone()
+        };
+        fs::write(storage.join("orphan.bin"), b"old").unwrap();
+
+        let photos = dir.path().join(photos::PHOTOS_DIR);
+        fs::create_dir_all(photos.join("3A")).unwrap();
+        fs::create_dir_all(photos.join("deleted")).unwrap();
+        image::RgbImage::new(4, 4)
+            .save(photos.join("3A").join("s1.jpg"))
+            .unwrap();
+        fs::write(photos.join("3A").join("s2.jpg"), b"not a jpeg").unwrap();
+
+        let records = [good, shared, tampered, missing];
+        let class_ids: HashSet<&str> = ["3A"].into_iter().collect();
+        let report = verify_in(dir.path(), &records, &class_ids, |_, _, _| Ok(())).unwrap();
+        assert!(!report.ok);
+        assert_eq!(report.checked["attachments"], 4);
+        assert_eq!(report.checked["photos"], 2);
+
+        let problems: Vec<_> = report
+            .issues
+            .iter()
+            .map(|i| (i.area.as_str(), i.severity, i.repair.clone()))
+            .collect();
+        let remove = |id: &str| {
+            Some(RepairAction::RemoveAttachment {
+                attachment_id: id.into(),
+            })
+        };
+        assert_eq!(
+            problems,
+            [
+                ("attachments", Severity::Error, remove("a3")),
+                ("attachments", Severity::Error, remove("a4")),
+                ("attachments", Severity::Warning, None),
+                (
+                    "photos",
+                    Severity::Error,
+                    Some(RepairAction::ReimportPhotos {
+                        class_id: "3A".into()
+                    })
+                ),
+                ("photos", Severity::Warning, None),
+            ]
+        );
+    }
+}
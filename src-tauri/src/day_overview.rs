@@ -0,0 +1,511 @@
+//! Day overview for the dashboard
+//!
+//! Handles:
+//! - Aggregating, for one date and every class: the attendance recorded
+//!   that day, behavior log entries, a noise summary (from the daily
+//!   histograms in `noise_history`), the devices handed out (`devices`)
+//!   and pending items
+//! - Pending items: attendance not yet recorded, roster updates waiting in
+//!   the import folder, devices not given back, an exit ticket still open
+//!
+//! One query instead of a dozen invokes stitched together in the frontend.
+//! Classes are not owned by a profile, so "all classes" means every class
+//! on this PC.
+
+use crate::class_records::{self, AttendanceStore, BehaviorEntry, BehaviorStore};
+use crate::classroom_state::{self, TransitionRules};
+use crate::devices::{DeviceIssue, DeviceStore};
+use crate::errors::{self, BackendError};
+use crate::exit_tickets::{ExitTicketSession, ExitTicketStore};
+use crate::noise_history::{self, Histogram, NoiseHistoryStore};
+use crate::roster::{ClassData, RosterStore};
+use crate::roster_sync::{self, RosterUpdate};
+use crate::sound_units::{LevelScale, SoundUnit};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Attendance of a class on the date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceSummary {
+    /// Whether attendance was recorded for the date
+    pub recorded: bool,
+    pub present: usize,
+    pub absent: usize,
+    /// Names of absent students
+    pub absent_students: Vec<String>,
+}
+
+/// Noise levels of a class on the date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseSummary {
+    pub samples: u64,
+    /// In `unit`
+    pub median: Option<f64>,
+    /// 95th percentile (short spikes are left out), in `unit`
+    pub peak: Option<f64>,
+    pub unit: SoundUnit,
+    /// Share of samples above the current yellow / red thresholds (0-100)
+    pub above_yellow_percent: f64,
+    pub above_red_percent: f64,
+}
+
+/// Something waiting for the teacher
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PendingItem {
+    #[serde(rename_all = "camelCase")]
+    AttendanceNotRecorded { class_id: String },
+    #[serde(rename_all = "camelCase")]
+    RosterUpdate {
+        update_id: String,
+        file_name: String,
+        class_id: Option<String>,
+    },
+    /// Devices of the class's students still out
+    #[serde(rename_all = "camelCase")]
+    DevicesNotReturned {
+        class_id: String,
+        devices: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    OpenExitTicket {
+        session_id: String,
+        prompt: String,
+        responses: usize,
+    },
+}
+
+/// A device handed to a student of the class on the date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceHandout {
+    pub device_id: String,
+    pub label: String,
+    pub student_name: String,
+    pub assigned_at: u64,
+    pub returned_at: Option<u64>,
+    /// Issues reported on the device while the student had it
+    pub issues: Vec<DeviceIssue>,
+}
+
+/// One class on the date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassDayOverview {
+    pub class_id: String,
+    pub class_name: String,
+    pub students: usize,
+    pub attendance: AttendanceSummary,
+    /// Behavior log entries of the date, oldest first
+    pub behavior: Vec<BehaviorEntry>,
+    /// `None` when the class was not metered that day
+    pub noise: Option<NoiseSummary>,
+    /// Device handouts of the date, oldest first
+    pub devices: Vec<DeviceHandout>,
+    pub pending: Vec<PendingItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayOverview {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub classes: Vec<ClassDayOverview>,
+    /// Pending items not tied to a class (new classes, exit tickets)
+    pub pending: Vec<PendingItem>,
+    /// Exit ticket sessions opened on the date
+    pub exit_tickets: usize,
+}
+
+/// Everything the overview is built from
+struct Sources {
+    classes: Vec<ClassData>,
+    attendance: AttendanceStore,
+    behavior: BehaviorStore,
+    noise: NoiseHistoryStore,
+    devices: DeviceStore,
+    updates: Vec<RosterUpdate>,
+    sessions: Vec<ExitTicketSession>,
+    rules: TransitionRules,
+    scale: LevelScale,
+}
+
+fn parse_date(date: &str) -> Result<String, BackendError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|_| {
+            BackendError::new(errors::system::INVALID_INPUT, "Date must be YYYY-MM-DD")
+                .with_details(date.to_string())
+        })
+}
+
+fn share_above(histogram: &Histogram, level: f64) -> f64 {
+    let total = histogram.total();
+    if total == 0 {
+        return 0.0;
+    }
+    let above: u64 = histogram
+        .counts
+        .iter()
+        .enumerate()
+        .filter(|(bucket, _)| *bucket as f64 > level)
+        .map(|(_, count)| count)
+        .sum();
+    above as f64 * 100.0 / total as f64
+}
+
+fn noise_summary(
+    histogram: &Histogram,
+    rules: &TransitionRules,
+    scale: &LevelScale,
+) -> NoiseSummary {
+    NoiseSummary {
+        samples: histogram.total(),
+        median: scale.convert_opt(histogram.percentile(0.5)),
+        peak: scale.convert_opt(histogram.percentile(0.95)),
+        unit: scale.unit,
+        above_yellow_percent: share_above(histogram, rules.yellow_above),
+        above_red_percent: share_above(histogram, rules.red_above),
+    }
+}
+
+fn roster_item(update: &RosterUpdate) -> PendingItem {
+    PendingItem::RosterUpdate {
+        update_id: update.id.clone(),
+        file_name: update.file_name.clone(),
+        class_id: update.class_id.clone(),
+    }
+}
+
+fn class_overview(class: &ClassData, date: &str, sources: &Sources) -> ClassDayOverview {
+    let records: Vec<_> = sources
+        .attendance
+        .records
+        .iter()
+        .filter(|r| r.class_id == class.id && r.date == date)
+        .collect();
+    let student_name = |id: &str| {
+        class
+            .students
+            .iter()
+            .find(|s| s.id == id)
+            .map_or_else(|| id.to_string(), |s| s.name.clone())
+    };
+    let absent_students: Vec<String> = records
+        .iter()
+        .filter(|r| r.absent)
+        .map(|r| student_name(&r.student_id))
+        .collect();
+    let attendance = AttendanceSummary {
+        recorded: !records.is_empty(),
+        present: records.len() - absent_students.len(),
+        absent: absent_students.len(),
+        absent_students,
+    };
+
+    let behavior = sources
+        .behavior
+        .for_class(&class.id)
+        .into_iter()
+        .filter(|e| class_records::date_string(e.timestamp) == date)
+        .collect();
+
+    let noise = sources
+        .noise
+        .classes
+        .get(&class.id)
+        .and_then(|history| history.days.get(date))
+        .filter(|histogram| histogram.total() > 0)
+        .map(|histogram| noise_summary(histogram, &sources.rules, &sources.scale));
+
+    let devices = sources
+        .devices
+        .assignments
+        .iter()
+        .filter(|a| a.class_id == class.id && class_records::date_string(a.assigned_at) == date)
+        .map(|a| DeviceHandout {
+            device_id: a.device_id.clone(),
+            label: sources
+                .devices
+                .find(&a.device_id)
+                .map_or_else(|| a.device_id.clone(), |d| d.label.clone()),
+            student_name: student_name(&a.student_id),
+            assigned_at: a.assigned_at,
+            returned_at: a.returned_at,
+            issues: sources
+                .devices
+                .issues
+                .iter()
+                .filter(|i| {
+                    i.device_id == a.device_id
+                        && i.student_id.as_deref() == Some(a.student_id.as_str())
+                        && i.reported_at >= a.assigned_at
+                        && a.returned_at
+                            .is_none_or(|returned| i.reported_at <= returned)
+                })
+                .cloned()
+                .collect(),
+        })
+        .collect();
+
+    let mut pending = Vec::new();
+    if !attendance.recorded && !class.students.is_empty() {
+        pending.push(PendingItem::AttendanceNotRecorded {
+            class_id: class.id.clone(),
+        });
+    }
+    pending.extend(
+        sources
+            .updates
+            .iter()
+            .filter(|u| u.class_id.as_deref() == Some(class.id.as_str()))
+            .map(roster_item),
+    );
+    let out: Vec<String> = sources
+        .devices
+        .assignments
+        .iter()
+        .filter(|a| a.class_id == class.id && a.returned_at.is_none())
+        .filter_map(|a| sources.devices.find(&a.device_id))
+        .map(|d| d.label.clone())
+        .collect();
+    if !out.is_empty() {
+        pending.push(PendingItem::DevicesNotReturned {
+            class_id: class.id.clone(),
+            devices: out,
+        });
+    }
+
+    ClassDayOverview {
+        class_id: class.id.clone(),
+        class_name: class.name.clone(),
+        students: class.students.len(),
+        attendance,
+        behavior,
+        noise,
+        devices,
+        pending,
+    }
+}
+
+fn build_overview(date: String, sources: &Sources) -> DayOverview {
+    let mut classes: Vec<ClassDayOverview> = sources
+        .classes
+        .iter()
+        .map(|class| class_overview(class, &date, sources))
+        .collect();
+    classes.sort_by_key(|c| c.class_name.to_lowercase());
+
+    let known = |id: &str| sources.classes.iter().any(|c| c.id == id);
+    let mut pending: Vec<PendingItem> = sources
+        .updates
+        .iter()
+        .filter(|u| !u.class_id.as_deref().is_some_and(known))
+        .map(roster_item)
+        .collect();
+    pending.extend(sources.sessions.iter().filter(|s| s.is_open()).map(|s| {
+        PendingItem::OpenExitTicket {
+            session_id: s.id.clone(),
+            prompt: s.prompt.clone(),
+            responses: s.responses.len(),
+        }
+    }));
+    let exit_tickets = sources
+        .sessions
+        .iter()
+        .filter(|s| class_records::date_string(s.opened_at) == date)
+        .count();
+
+    DayOverview {
+        date,
+        classes,
+        pending,
+        exit_tickets,
+    }
+}
+
+/// Overview of every class on `date` (`YYYY-MM-DD`, local time)
+pub fn get_day_overview(date: &str) -> Result<DayOverview, BackendError> {
+    let date = parse_date(date)?;
+    // Include noise samples of the running lesson
+    noise_history::flush_pending()?;
+    let sources = Sources {
+        classes: RosterStore::load()?.classes,
+        attendance: AttendanceStore::load()?,
+        behavior: BehaviorStore::load()?,
+        noise: NoiseHistoryStore::load()?,
+        devices: DeviceStore::load()?,
+        updates: roster_sync::get_pending_updates()?,
+        sessions: ExitTicketStore::load()?.sessions,
+        rules: classroom_state::get_rules(),
+        scale: LevelScale::current(),
+    };
+    Ok(build_overview(date, &sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_records::{AttendanceRecord, BehaviorKind};
+    use crate::roster::ClassBuilder;
+    use crate::roster_sync::RosterDiff;
+    use chrono::{Local, TimeZone};
+
+    fn millis(date: &str, hour: u32) -> u64 {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Local
+            .from_local_datetime(&day.and_hms_opt(hour, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .timestamp_millis() as u64
+    }
+
+    fn class(id: &str, name: &str) -> ClassData {
+        ClassBuilder::new(id, name)
+            .student(&format!("{}_s0", id), "Anna")
+            .student(&format!("{}_s1", id), "Marco")
+            .build()
+    }
+
+    fn sources() -> Sources {
+        let mut noise = NoiseHistoryStore::default();
+        let mut day = Histogram::default();
+        for level in 1..=100 {
+            day.add(f64::from(level));
+        }
+        noise
+            .classes
+            .entry("b".into())
+            .or_default()
+            .days
+            .insert("2026-03-02".into(), day);
+        Sources {
+            classes: vec![class("b", "4B"), class("a", "3A")],
+            attendance: AttendanceStore {
+                records: ["b_s0", "b_s1"]
+                    .iter()
+                    .map(|s| AttendanceRecord {
+                        class_id: "b".into(),
+                        student_id: s.to_string(),
+                        date: "2026-03-02".into(),
+                        absent: *s == "b_s1",
+                    })
+                    .collect(),
+            },
+            behavior: BehaviorStore {
+                entries: [("2026-03-02", "on the day"), ("2026-03-03", "next day")]
+                    .iter()
+                    .map(|(date, note)| BehaviorEntry {
+                        id: note.to_string(),
+                        class_id: "b".into(),
+                        student_id: "b_s0".into(),
+                        timestamp: millis(date, 10),
+                        kind: BehaviorKind::Note,
+                        note: note.to_string(),
+                    })
+                    .collect(),
+            },
+            noise,
+            devices: DeviceStore::default(),
+            updates: vec![RosterUpdate {
+                id: "u1".into(),
+                file_name: "5C.csv".into(),
+                class_name: "5C".into(),
+                class_id: None,
+                detected_at: 0,
+                students: vec![],
+                errors: vec![],
+                diff: RosterDiff::default(),
+                hash: None,
+            }],
+            sessions: vec![],
+            rules: TransitionRules::default(),
+            scale: LevelScale::default(),
+        }
+    }
+
+    #[test]
+    fn test_overview_per_class() {
+        let overview = build_overview("2026-03-02".into(), &sources());
+        let names: Vec<_> = overview.classes.iter().map(|c| &c.class_name).collect();
+        assert_eq!(names, ["3A", "4B"]);
+
+        let a = &overview.classes[0];
+        assert!(!a.attendance.recorded);
+        assert_eq!(
+            a.pending,
+            [PendingItem::AttendanceNotRecorded {
+                class_id: "a".into()
+            }]
+        );
+        assert_eq!(a.noise, None);
+
+        let b = &overview.classes[1];
+        assert_eq!((b.attendance.present, b.attendance.absent), (1, 1));
+        assert_eq!(b.attendance.absent_students, ["Marco"]);
+        assert!(b.pending.is_empty());
+        assert_eq!(b.behavior.len(), 1);
+        assert_eq!(b.behavior[0].note, "on the day");
+        let noise = b.noise.as_ref().unwrap();
+        assert_eq!(noise.median, Some(50.0));
+        // Default rules: yellow above 60, red above 75
+        assert_eq!(noise.above_yellow_percent, 40.0);
+        assert_eq!(noise.above_red_percent, 25.0);
+
+        // Thresholds apply to meter levels whatever the unit
+        let mut spl = sources();
+        spl.scale.unit = SoundUnit::Dbspl;
+        let overview = build_overview("2026-03-02".into(), &spl);
+        let noise = overview.classes[1].noise.as_ref().unwrap();
+        assert_eq!((noise.median, noise.unit), (Some(65.0), SoundUnit::Dbspl));
+        assert_eq!(noise.above_yellow_percent, 40.0);
+
+        // The update for a new class is not tied to any class
+        assert!(matches!(
+            overview.pending[..],
+            [PendingItem::RosterUpdate { ref update_id, .. }] if update_id == "u1"
+        ));
+    }
+
+    #[test]
+    fn test_device_handouts() {
+        let mut sources = sources();
+        let store = &mut sources.devices;
+        let tablet = store.add("Tablet 07", "tablet", None, 0).unwrap();
+        let calc = store.add("Calc 3", "calculator", None, 0).unwrap();
+        store
+            .assign(&tablet.id, "b", "b_s0", millis("2026-03-02", 9))
+            .unwrap();
+        store
+            .report_issue(&tablet.id, "Cracked screen", millis("2026-03-02", 10))
+            .unwrap();
+        store.give_back(&tablet.id, millis("2026-03-02", 11));
+        // Next day's handout, still out
+        store
+            .assign(&calc.id, "b", "b_s1", millis("2026-03-03", 9))
+            .unwrap();
+
+        let overview = build_overview("2026-03-02".into(), &sources);
+        let b = &overview.classes[1];
+        assert_eq!(b.devices.len(), 1);
+        assert_eq!(b.devices[0].label, "Tablet 07");
+        assert_eq!(b.devices[0].student_name, "Anna");
+        assert_eq!(b.devices[0].issues[0].description, "Cracked screen");
+        assert_eq!(
+            b.pending,
+            [PendingItem::DevicesNotReturned {
+                class_id: "b".into(),
+                devices: vec!["Calc 3".into()]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date(" 2026-03-02 ").unwrap(), "2026-03-02");
+        assert!(parse_date("02/03/2026").is_err());
+        assert!(parse_date("2026-02-30").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Inventory of shared classroom devices
+//!
+//! Handles:
+//! - The school's tablets, calculators and the like, added once
+//! - Handing a device to a student for the lesson and getting it back; a
+//!   device handed to someone else is returned by the previous holder
+//! - Issues reported on a device (cracked screen, missing charger), with
+//!   the student holding it at the time
+//!
+//! Replaces the paper list teachers keep per lesson. Stored in the
+//! `devices` data collection; handouts and issues of a day show up in that
+//! day's overview (see `day_overview`).
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::roster::RosterStore;
+use serde::{Deserialize, Serialize};
+
+const COLLECTION: &str = "devices";
+
+/// A shared device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    pub id: String,
+    /// Label on the device ("Tablet 07")
+    pub label: String,
+    /// "tablet", "calculator", ...
+    pub kind: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    pub added_at: u64,
+}
+
+/// A device handed to a student
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAssignment {
+    pub id: String,
+    pub device_id: String,
+    pub class_id: String,
+    pub student_id: String,
+    pub assigned_at: u64,
+    /// `None` while the student has it
+    #[serde(default)]
+    pub returned_at: Option<u64>,
+}
+
+/// A problem reported on a device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceIssue {
+    pub id: String,
+    pub device_id: String,
+    pub description: String,
+    /// Holder when the issue was reported
+    #[serde(default)]
+    pub class_id: Option<String>,
+    #[serde(default)]
+    pub student_id: Option<String>,
+    pub reported_at: u64,
+    #[serde(default)]
+    pub resolved_at: Option<u64>,
+}
+
+/// A device with its current state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceStatus {
+    #[serde(flatten)]
+    pub device: Device,
+    /// Who has it now
+    pub assignment: Option<DeviceAssignment>,
+    /// Issues not yet resolved
+    pub open_issues: Vec<DeviceIssue>,
+}
+
+/// Persisted inventory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceStore {
+    pub devices: Vec<Device>,
+    pub assignments: Vec<DeviceAssignment>,
+    pub issues: Vec<DeviceIssue>,
+}
+
+fn device_not_found(device_id: &str) -> BackendError {
+    BackendError::new(errors::inventory::DEVICE_NOT_FOUND, "Device not found")
+        .with_details(device_id.to_string())
+}
+
+fn invalid_input(message: &str) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message)
+}
+
+impl DeviceStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    pub fn find(&self, device_id: &str) -> Option<&Device> {
+        self.devices.iter().find(|d| d.id == device_id)
+    }
+
+    /// Current holder of a device
+    pub fn current(&self, device_id: &str) -> Option<&DeviceAssignment> {
+        self.assignments
+            .iter()
+            .find(|a| a.device_id == device_id && a.returned_at.is_none())
+    }
+
+    pub fn add(
+        &mut self,
+        label: &str,
+        kind: &str,
+        serial: Option<&str>,
+        now: u64,
+    ) -> Result<Device, BackendError> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(invalid_input("Device label is required"));
+        }
+        if self
+            .devices
+            .iter()
+            .any(|d| d.label.to_lowercase() == label.to_lowercase())
+        {
+            return Err(BackendError::new(
+                errors::inventory::DEVICE_EXISTS,
+                "A device with this label already exists",
+            )
+            .with_details(label.to_string()));
+        }
+        let device = Device {
+            id: format!("device_{}_{}", now, self.devices.len()),
+            label: label.to_string(),
+            kind: kind.trim().to_lowercase(),
+            serial: serial
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+            added_at: now,
+        };
+        self.devices.push(device.clone());
+        Ok(device)
+    }
+
+    /// Mark the device returned; returns the closed assignment, if any
+    pub fn give_back(&mut self, device_id: &str, now: u64) -> Option<DeviceAssignment> {
+        let assignment = self
+            .assignments
+            .iter_mut()
+            .find(|a| a.device_id == device_id && a.returned_at.is_none())?;
+        assignment.returned_at = Some(now);
+        Some(assignment.clone())
+    }
+
+    /// Hand a device to a student of `class_id`, taking it back from its
+    /// previous holder
+    pub fn assign(
+        &mut self,
+        device_id: &str,
+        class_id: &str,
+        student_id: &str,
+        now: u64,
+    ) -> Result<DeviceAssignment, BackendError> {
+        if self.find(device_id).is_none() {
+            return Err(device_not_found(device_id));
+        }
+        if let Some(current) = self.current(device_id) {
+            if current.student_id == student_id {
+                return Ok(current.clone());
+            }
+        }
+        self.give_back(device_id, now);
+        let assignment = DeviceAssignment {
+            id: format!("assignment_{}_{}", now, self.assignments.len()),
+            device_id: device_id.to_string(),
+            class_id: class_id.to_string(),
+            student_id: student_id.to_string(),
+            assigned_at: now,
+            returned_at: None,
+        };
+        self.assignments.push(assignment.clone());
+        Ok(assignment)
+    }
+
+    /// Record an issue, with the device's current holder
+    pub fn report_issue(
+        &mut self,
+        device_id: &str,
+        description: &str,
+        now: u64,
+    ) -> Result<DeviceIssue, BackendError> {
+        if self.find(device_id).is_none() {
+            return Err(device_not_found(device_id));
+        }
+        let description = description.trim();
+        if description.is_empty() {
+            return Err(invalid_input("Describe the issue"));
+        }
+        let holder = self.current(device_id);
+        let issue = DeviceIssue {
+            id: format!("issue_{}_{}", now, self.issues.len()),
+            device_id: device_id.to_string(),
+            description: description.to_string(),
+            class_id: holder.map(|a| a.class_id.clone()),
+            student_id: holder.map(|a| a.student_id.clone()),
+            reported_at: now,
+            resolved_at: None,
+        };
+        self.issues.push(issue.clone());
+        Ok(issue)
+    }
+
+    pub fn resolve_issue(&mut self, issue_id: &str, now: u64) -> Result<DeviceIssue, BackendError> {
+        let issue = self
+            .issues
+            .iter_mut()
+            .find(|i| i.id == issue_id)
+            .ok_or_else(|| {
+                BackendError::new(errors::inventory::ISSUE_NOT_FOUND, "Device issue not found")
+                    .with_details(issue_id.to_string())
+            })?;
+        issue.resolved_at.get_or_insert(now);
+        Ok(issue.clone())
+    }
+
+    /// Every device with its holder and open issues, by label
+    pub fn statuses(&self) -> Vec<DeviceStatus> {
+        let mut statuses: Vec<DeviceStatus> = self
+            .devices
+            .iter()
+            .map(|device| DeviceStatus {
+                device: device.clone(),
+                assignment: self.current(&device.id).cloned(),
+                open_issues: self
+                    .issues
+                    .iter()
+                    .filter(|i| i.device_id == device.id && i.resolved_at.is_none())
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+        statuses.sort_by_key(|s| s.device.label.to_lowercase());
+        statuses
+    }
+}
+
+/// Add a device to the inventory
+pub fn add_device(label: &str, kind: &str, serial: Option<&str>) -> Result<Device, BackendError> {
+    let mut store = DeviceStore::load()?;
+    let device = store.add(label, kind, serial, clock::now_millis())?;
+    store.save()?;
+    Ok(device)
+}
+
+/// Hand a device to a student (looked up in every class)
+pub fn assign_device(student_id: &str, device_id: &str) -> Result<DeviceAssignment, BackendError> {
+    let roster = RosterStore::load()?;
+    let class = roster
+        .classes
+        .iter()
+        .find(|c| c.students.iter().any(|s| s.id == student_id))
+        .ok_or_else(|| {
+            BackendError::new(errors::grades::STUDENT_NOT_FOUND, "Student not found")
+                .with_details(student_id.to_string())
+        })?;
+    let mut store = DeviceStore::load()?;
+    let assignment = store.assign(device_id, &class.id, student_id, clock::now_millis())?;
+    store.save()?;
+    Ok(assignment)
+}
+
+/// Take a device back; `None` if nobody had it
+pub fn return_device(device_id: &str) -> Result<Option<DeviceAssignment>, BackendError> {
+    let mut store = DeviceStore::load()?;
+    if store.find(device_id).is_none() {
+        return Err(device_not_found(device_id));
+    }
+    let returned = store.give_back(device_id, clock::now_millis());
+    store.save()?;
+    Ok(returned)
+}
+
+/// Report a problem with a device
+pub fn report_device_issue(
+    device_id: &str,
+    description: &str,
+) -> Result<DeviceIssue, BackendError> {
+    let mut store = DeviceStore::load()?;
+    let issue = store.report_issue(device_id, description, clock::now_millis())?;
+    store.save()?;
+    Ok(issue)
+}
+
+/// Mark an issue fixed
+pub fn resolve_device_issue(issue_id: &str) -> Result<DeviceIssue, BackendError> {
+    let mut store = DeviceStore::load()?;
+    let issue = store.resolve_issue(issue_id, clock::now_millis())?;
+    store.save()?;
+    Ok(issue)
+}
+
+/// The inventory with holders and open issues
+pub fn get_device_inventory() -> Result<Vec<DeviceStatus>, BackendError> {
+    Ok(DeviceStore::load()?.statuses())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handout_and_return() {
+        let mut store = DeviceStore::default();
+        let tablet = store.add(" Tablet 07 ", "Tablet", Some(""), 1).unwrap();
+        assert_eq!(tablet.label, "Tablet 07");
+        assert_eq!(tablet.kind, "tablet");
+        assert_eq!(tablet.serial, None);
+        assert_eq!(
+            store.add("tablet 07", "tablet", None, 2).unwrap_err().code,
+            errors::inventory::DEVICE_EXISTS
+        );
+
+        let first = store.assign(&tablet.id, "c1", "s1", 10).unwrap();
+        // Assigning twice to the same student keeps the handout
+        assert_eq!(store.assign(&tablet.id, "c1", "s1", 11).unwrap(), first);
+        // Handed on: the first student gave it back
+        let second = store.assign(&tablet.id, "c1", "s2", 20).unwrap();
+        assert_eq!(store.assignments[0].returned_at, Some(20));
+        assert_eq!(store.current(&tablet.id), Some(&second));
+
+        assert_eq!(store.give_back(&tablet.id, 30).unwrap().student_id, "s2");
+        assert_eq!(store.current(&tablet.id), None);
+        assert_eq!(store.give_back(&tablet.id, 31), None);
+
+        assert_eq!(
+            store.assign("missing", "c1", "s1", 40).unwrap_err().code,
+            errors::inventory::DEVICE_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_issues_record_holder() {
+        let mut store = DeviceStore::default();
+        let calc = store.add("Calc 3", "calculator", None, 1).unwrap();
+        let loose = store.report_issue(&calc.id, "Missing cover", 2).unwrap();
+        assert_eq!(loose.student_id, None);
+
+        store.assign(&calc.id, "c1", "s1", 3).unwrap();
+        let issue = store.report_issue(&calc.id, " Cracked screen ", 4).unwrap();
+        assert_eq!(issue.description, "Cracked screen");
+        assert_eq!(issue.student_id.as_deref(), Some("s1"));
+        assert!(store.report_issue(&calc.id, "  ", 5).is_err());
+
+        store.resolve_issue(&loose.id, 6).unwrap();
+        let statuses = store.statuses();
+        assert_eq!(statuses[0].open_issues, [issue]);
+        assert_eq!(statuses[0].assignment.as_ref().unwrap().student_id, "s1");
+        assert_eq!(
+            store.resolve_issue("nope", 7).unwrap_err().code,
+            errors::inventory::ISSUE_NOT_FOUND
+        );
+    }
+}
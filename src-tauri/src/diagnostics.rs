@@ -0,0 +1,193 @@
+//! Sanitized diagnostics bundle for bug reports
+//!
+//! Handles:
+//! - Collecting app/OS versions, resource usage and recent job failures
+//! - Listing settings and data collections without personal data
+//!
+//! Everything that could identify a teacher, student or school is left out
+//! or redacted: config values of credential/address-like keys, job labels
+//! (they contain class names), error details (they contain paths) and the
+//! contents of data collections (only sizes are included).
+
+use crate::errors::BackendError;
+use crate::file_ops;
+use crate::jobs;
+use crate::locale;
+use crate::perf_stats;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+
+const REDACTED: &str = "[redacted]";
+
+/// Key words whose values are never included
+const SENSITIVE_WORDS: &[&str] = &["to", "from", "cc", "key", "name", "interface"];
+
+/// Key fragments whose values are never included (`username`, `smtpHost`)
+const SENSITIVE_FRAGMENTS: &[&str] = &[
+    "user", "url", "endpoint", "host", "path", "folder", "bucket", "email", "address", "password",
+    "secret", "token",
+];
+
+/// Job failures included in the bundle
+const MAX_JOB_ERRORS: usize = 10;
+
+/// A failed background job, without its label or error details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobFailure {
+    pub kind: String,
+    pub code: String,
+    pub message: String,
+    pub finished_at: Option<u64>,
+}
+
+/// Diagnostics attached to feedback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsBundle {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub os_locale: String,
+    pub app_language: String,
+    pub performance: Option<perf_stats::PerformanceStats>,
+    /// Config values with sensitive entries redacted
+    pub settings: Value,
+    /// Data collection file sizes in bytes
+    pub data_files: BTreeMap<String, u64>,
+    pub recent_job_failures: Vec<JobFailure>,
+}
+
+/// Whether a snake_case or camelCase key contains a sensitive word
+fn is_sensitive_key(key: &str) -> bool {
+    key.split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(split_camel)
+        .any(|part| {
+            SENSITIVE_WORDS.contains(&part.as_str())
+                || SENSITIVE_FRAGMENTS.iter().any(|f| part.contains(f))
+        })
+}
+
+/// `accessKeyId` → ["access", "key", "id"]
+fn split_camel(word: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    for c in word.chars() {
+        if c.is_ascii_uppercase() && !parts.last().is_some_and(|p| p.is_empty()) {
+            parts.push(String::new());
+        }
+        parts.last_mut().unwrap().push(c.to_ascii_lowercase());
+    }
+    parts
+}
+
+/// Redact values under sensitive keys, recursively
+pub fn sanitize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let v = if is_sensitive_key(k) && !v.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        sanitize(v)
+                    };
+                    (k.clone(), v)
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(sanitize).collect()),
+        other => other.clone(),
+    }
+}
+
+fn settings() -> Result<Value, BackendError> {
+    let path = file_ops::get_config_dir()?.join(file_ops::CONFIG_FILENAME);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(Value::Object(Map::new()));
+    };
+    Ok(match serde_json::from_str::<Value>(&content) {
+        Ok(config) => sanitize(&config),
+        Err(_) => Value::String("<unreadable config file>".to_string()),
+    })
+}
+
+fn data_files() -> Result<BTreeMap<String, u64>, BackendError> {
+    let dir = file_ops::get_config_dir()?.join(file_ops::DATA_DIR);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((e.file_name().to_string_lossy().to_string(), meta.len()))
+        })
+        .collect())
+}
+
+fn recent_job_failures() -> Vec<JobFailure> {
+    jobs::list_jobs()
+        .into_iter()
+        .filter_map(|job| {
+            let error = job.error?;
+            Some(JobFailure {
+                kind: job.kind,
+                code: error.code,
+                message: error.message,
+                finished_at: job.finished_at,
+            })
+        })
+        .take(MAX_JOB_ERRORS)
+        .collect()
+}
+
+/// Collect the diagnostics bundle
+pub fn collect() -> Result<DiagnosticsBundle, BackendError> {
+    Ok(DiagnosticsBundle {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        os_locale: locale::get_system_locale(),
+        app_language: locale::app_language().code().to_string(),
+        performance: perf_stats::get_performance_stats().ok(),
+        settings: settings()?,
+        data_files: data_files()?,
+        recent_job_failures: recent_job_failures(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize_redacts_sensitive_keys() {
+        let config = json!({
+            "theme": "Energy",
+            "mailer_smtp": {
+                "host": "smtp.scuola.it",
+                "port": 587,
+                "username": "m.rossi",
+                "from": "m.rossi@scuola.it"
+            },
+            "cloud_s3": { "accessKeyId": "AKIA", "region": "eu-south-1" },
+            "roster_watch_folder": "C:\\Users\\rossi\\Classi",
+            "weekly_summary": { "delivery": { "type": "email", "to": ["a@b.it"] } },
+            "lan_bind": { "interface": null, "port": 8765 }
+        });
+        let clean = sanitize(&config);
+        assert_eq!(clean["theme"], "Energy");
+        assert_eq!(clean["mailer_smtp"]["host"], REDACTED);
+        assert_eq!(clean["mailer_smtp"]["port"], 587);
+        assert_eq!(clean["mailer_smtp"]["username"], REDACTED);
+        assert_eq!(clean["mailer_smtp"]["from"], REDACTED);
+        assert_eq!(clean["cloud_s3"]["accessKeyId"], REDACTED);
+        assert_eq!(clean["cloud_s3"]["region"], "eu-south-1");
+        assert_eq!(clean["roster_watch_folder"], REDACTED);
+        assert_eq!(clean["weekly_summary"]["delivery"]["to"], REDACTED);
+        assert_eq!(clean["lan_bind"]["interface"], Value::Null);
+    }
+}
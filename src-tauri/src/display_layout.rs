@@ -0,0 +1,393 @@
+//! Window arrangement per monitor layout
+//!
+//! Handles:
+//! - Identifying the monitor layout (which screens are connected, where and
+//!   at what resolution)
+//! - Watching for monitors being connected or disconnected; Tauri has no
+//!   hot-plug event, so the layout is polled
+//! - Remembering where the app's windows were on each layout (saved when
+//!   leaving a layout, or on request) in the `window_layouts` data
+//!   collection, and putting them back when a known layout returns
+//! - Moving the projector layers (annotation, dim, pointer) onto the projector after
+//!   every change
+//! - Emitting `display-configuration-changed` for the frontend
+//!
+//! Positions are physical pixels in desktop coordinates, as reported by
+//! the platform.
+
+use crate::annotation::OVERLAY_LABEL;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::pointer_highlight::POINTER_LABEL;
+use crate::projector_dim::DIM_LABEL;
+use crate::window;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+const COLLECTION: &str = "window_layouts";
+
+pub const DISPLAY_CHANGED_EVENT: &str = "display-configuration-changed";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Windows that always cover the projector instead of keeping a position
+const PROJECTOR_LAYERS: &[&str] = &[OVERLAY_LABEL, DIM_LABEL, POINTER_LABEL];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+impl MonitorInfo {
+    fn from_monitor(monitor: &Monitor) -> Self {
+        Self {
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+/// Where one window was
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowPlacement {
+    pub label: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedLayout {
+    pub monitors: Vec<MonitorInfo>,
+    pub windows: Vec<WindowPlacement>,
+}
+
+/// Saved arrangements by layout id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayoutStore {
+    pub layouts: BTreeMap<String, SavedLayout>,
+}
+
+impl WindowLayoutStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+}
+
+/// Payload of `display-configuration-changed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayConfiguration {
+    pub layout_id: String,
+    pub monitors: Vec<MonitorInfo>,
+    /// An arrangement is saved for this layout
+    pub known: bool,
+    /// Windows put back where they were (after a change)
+    #[serde(default)]
+    pub restored: Vec<String>,
+}
+
+/// Stable id of a monitor layout: the same screens in the same places give
+/// the same id, whatever order the platform lists them in
+pub fn layout_id(monitors: &[MonitorInfo]) -> String {
+    let mut parts: Vec<String> = monitors
+        .iter()
+        .map(|m| {
+            format!(
+                "{}:{}x{}@{},{}",
+                m.name.as_deref().unwrap_or("?"),
+                m.width,
+                m.height,
+                m.x,
+                m.y
+            )
+        })
+        .collect();
+    parts.sort();
+    parts.join("|")
+}
+
+/// Whether a placement would still be visible (its centre is on a monitor)
+fn is_on_screen(placement: &WindowPlacement, monitors: &[MonitorInfo]) -> bool {
+    let cx = placement.x as i64 + placement.width as i64 / 2;
+    let cy = placement.y as i64 + placement.height as i64 / 2;
+    monitors.iter().any(|m| {
+        (m.x as i64..m.x as i64 + m.width as i64).contains(&cx)
+            && (m.y as i64..m.y as i64 + m.height as i64).contains(&cy)
+    })
+}
+
+fn current_monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>, BackendError> {
+    let monitors = app.available_monitors().map_err(|e| {
+        BackendError::new(errors::window::MONITOR_NOT_FOUND, "Failed to list monitors")
+            .with_details(e.to_string())
+    })?;
+    Ok(monitors.iter().map(MonitorInfo::from_monitor).collect())
+}
+
+fn placement_of(label: &str, window: &WebviewWindow) -> Option<WindowPlacement> {
+    let position = window::get_window_position(window).ok()?;
+    Some(WindowPlacement {
+        label: label.to_string(),
+        x: position.x,
+        y: position.y,
+        width: position.width,
+        height: position.height,
+        fullscreen: window.is_fullscreen().unwrap_or(false),
+        maximized: window.is_maximized().unwrap_or(false),
+    })
+}
+
+/// Placements of the open windows (projector layers excluded)
+fn current_placements(app: &AppHandle) -> Vec<WindowPlacement> {
+    let mut placements: Vec<WindowPlacement> = app
+        .webview_windows()
+        .iter()
+        .filter(|(label, _)| !PROJECTOR_LAYERS.contains(&label.as_str()))
+        .filter_map(|(label, window)| placement_of(label, window))
+        .collect();
+    placements.sort_by(|a, b| a.label.cmp(&b.label));
+    placements
+}
+
+fn window_error(message: &str, e: tauri::Error) -> BackendError {
+    BackendError::new(errors::window::INVALID_POSITION, message).with_details(e.to_string())
+}
+
+fn apply_placement(
+    window: &WebviewWindow,
+    placement: &WindowPlacement,
+) -> Result<(), BackendError> {
+    // Leave fullscreen/maximized first or the move is ignored
+    window
+        .set_fullscreen(false)
+        .map_err(|e| window_error("Failed to leave fullscreen", e))?;
+    window
+        .unmaximize()
+        .map_err(|e| window_error("Failed to unmaximize window", e))?;
+    window
+        .set_position(PhysicalPosition::new(placement.x, placement.y))
+        .map_err(|e| window_error("Failed to move window", e))?;
+    window
+        .set_size(PhysicalSize::new(placement.width, placement.height))
+        .map_err(|e| window_error("Failed to resize window", e))?;
+    if placement.fullscreen {
+        window
+            .set_fullscreen(true)
+            .map_err(|e| window_error("Failed to enter fullscreen", e))?;
+    } else if placement.maximized {
+        window
+            .maximize()
+            .map_err(|e| window_error("Failed to maximize window", e))?;
+    }
+    Ok(())
+}
+
+/// Put open windows back where they were on a layout; returns their labels
+fn restore_layout(app: &AppHandle, saved: &SavedLayout, monitors: &[MonitorInfo]) -> Vec<String> {
+    saved
+        .windows
+        .iter()
+        .filter(|p| is_on_screen(p, monitors))
+        .filter_map(|p| {
+            let window = app.get_webview_window(&p.label)?;
+            apply_placement(&window, p).ok()?;
+            Some(p.label.clone())
+        })
+        .collect()
+}
+
+/// Move the open projector layers fullscreen onto the projector
+fn move_projector_layers(app: &AppHandle) -> Result<(), BackendError> {
+    let layers: Vec<WebviewWindow> = PROJECTOR_LAYERS
+        .iter()
+        .filter_map(|label| app.get_webview_window(label))
+        .collect();
+    if layers.is_empty() {
+        return Ok(());
+    }
+    let monitor = window::projector_monitor(app, None)?;
+    for layer in layers {
+        apply_placement(
+            &layer,
+            &WindowPlacement {
+                label: layer.label().to_string(),
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+                fullscreen: true,
+                maximized: false,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn remember(
+    store: &mut WindowLayoutStore,
+    monitors: &[MonitorInfo],
+    windows: Vec<WindowPlacement>,
+) {
+    if windows.is_empty() {
+        return;
+    }
+    store.layouts.insert(
+        layout_id(monitors),
+        SavedLayout {
+            monitors: monitors.to_vec(),
+            windows,
+        },
+    );
+}
+
+/// The current monitor layout
+pub fn get_display_configuration(app: &AppHandle) -> Result<DisplayConfiguration, BackendError> {
+    let monitors = current_monitors(app)?;
+    let layout_id = layout_id(&monitors);
+    let known = WindowLayoutStore::load()?.layouts.contains_key(&layout_id);
+    Ok(DisplayConfiguration {
+        layout_id,
+        monitors,
+        known,
+        restored: Vec::new(),
+    })
+}
+
+/// Save the current window arrangement for the current layout
+pub fn save_window_layout(app: &AppHandle) -> Result<DisplayConfiguration, BackendError> {
+    let monitors = current_monitors(app)?;
+    let mut store = WindowLayoutStore::load()?;
+    remember(&mut store, &monitors, current_placements(app));
+    store.save()?;
+    get_display_configuration(app)
+}
+
+/// React to a layout change: save where windows were on the old layout,
+/// restore the new one if known, move the projector layers
+fn on_layout_changed(
+    app: &AppHandle,
+    previous: &[MonitorInfo],
+    last_placements: Vec<WindowPlacement>,
+    monitors: Vec<MonitorInfo>,
+) -> Result<DisplayConfiguration, BackendError> {
+    let mut store = WindowLayoutStore::load()?;
+    remember(&mut store, previous, last_placements);
+    store.save()?;
+
+    let layout_id = layout_id(&monitors);
+    let saved = store.layouts.get(&layout_id);
+    let restored = saved
+        .map(|saved| restore_layout(app, saved, &monitors))
+        .unwrap_or_default();
+    move_projector_layers(app)?;
+    Ok(DisplayConfiguration {
+        known: saved.is_some(),
+        layout_id,
+        monitors,
+        restored,
+    })
+}
+
+/// Watch the monitor layout and emit `display-configuration-changed`
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut monitors = current_monitors(&app).unwrap_or_default();
+        let mut placements = current_placements(&app);
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let Ok(now) = current_monitors(&app) else {
+                continue;
+            };
+            // Some platforms briefly report no monitors while reconfiguring
+            if now.is_empty() {
+                continue;
+            }
+            if layout_id(&now) == layout_id(&monitors) {
+                placements = current_placements(&app);
+                continue;
+            }
+            let previous = std::mem::replace(&mut monitors, now.clone());
+            let last = std::mem::take(&mut placements);
+            match on_layout_changed(&app, &previous, last, now) {
+                Ok(configuration) => {
+                    let _ = app.emit(DISPLAY_CHANGED_EVENT, configuration);
+                }
+                Err(e) => eprintln!("Display layout change failed: {}", e.message),
+            }
+            placements = current_placements(&app);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, x: i32, width: u32) -> MonitorInfo {
+        MonitorInfo {
+            name: Some(name.to_string()),
+            x,
+            y: 0,
+            width,
+            height: 1080,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_layout_id_ignores_order() {
+        let laptop = monitor("eDP-1", 0, 1920);
+        let projector = monitor("HDMI-1", 1920, 1280);
+        assert_eq!(
+            layout_id(&[laptop.clone(), projector.clone()]),
+            layout_id(&[projector.clone(), laptop.clone()])
+        );
+        assert_ne!(
+            layout_id(std::slice::from_ref(&laptop)),
+            layout_id(&[laptop, projector])
+        );
+    }
+
+    #[test]
+    fn test_placement_on_screen() {
+        let monitors = [monitor("eDP-1", 0, 1920)];
+        let mut placement = WindowPlacement {
+            label: "main".into(),
+            x: 1800,
+            y: 100,
+            width: 200,
+            height: 200,
+            fullscreen: false,
+            maximized: false,
+        };
+        // Centre at x = 1900: still on the laptop screen
+        assert!(is_on_screen(&placement, &monitors));
+        placement.x = 2000;
+        assert!(!is_on_screen(&placement, &monitors));
+    }
+}
@@ -0,0 +1,719 @@
+//! DOCX document generation from templates
+//!
+//! Handles:
+//! - Merging `{{field}}` placeholders in a Word template with JSON data
+//!   (body, headers, footers and footnotes)
+//! - Repeating a table row or paragraph with `{{#each list}} ... {{/each}}`
+//! - Producing one document per student of a class (parent letters,
+//!   "quietest class of the month" certificates)
+//!
+//! Parts are read as XML events (`quick-xml`); only the text of `<w:t>`
+//! nodes is merged, never tags, attributes or field codes. Word often
+//! splits typed text over several runs (spell check, edits, a hyperlink or
+//! content control over part of it), so placeholders are first re-joined
+//! into a single `<w:t>` node. The formatting of the run where a
+//! placeholder starts is kept.
+
+pub mod pdf;
+
+use crate::errors::{self, BackendError};
+use crate::roster::RosterStore;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const MAIN_PART: &str = "word/document.xml";
+
+/// Result of generating a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedDocument {
+    pub path: String,
+    /// Placeholders with no matching data (rendered empty)
+    pub missing_fields: Vec<String>,
+}
+
+fn template_error(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::document::TEMPLATE_INVALID, message).with_details(details.to_string())
+}
+
+/// Parts of the package that may contain placeholders
+fn is_template_part(name: &str) -> bool {
+    name == MAIN_PART
+        || name == "word/footnotes.xml"
+        || name == "word/endnotes.xml"
+        || (name.starts_with("word/header") && name.ends_with(".xml"))
+        || (name.starts_with("word/footer") && name.ends_with(".xml"))
+}
+
+// ============================================================================
+// XML events
+// ============================================================================
+
+type Events = Vec<Event<'static>>;
+
+fn read_events(xml: &str) -> Result<Events, BackendError> {
+    let mut reader = Reader::from_str(xml);
+    let mut events = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return Ok(events),
+            Ok(event) => events.push(event.into_owned()),
+            Err(e) => {
+                return Err(template_error(
+                    "Template part is not valid XML",
+                    format!("at byte {}: {}", reader.buffer_position(), e),
+                ))
+            }
+        }
+    }
+}
+
+fn write_events(events: &[Event<'static>]) -> Result<String, BackendError> {
+    let mut writer = Writer::new(Vec::new());
+    for event in events {
+        writer
+            .write_event(event)
+            .map_err(|e| template_error("Failed to write document", e))?;
+    }
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| template_error("Failed to write document", e))
+}
+
+fn is_start(event: &Event, name: &[u8]) -> bool {
+    matches!(event, Event::Start(e) if e.name().as_ref() == name)
+}
+
+fn is_end(event: &Event, name: &[u8]) -> bool {
+    matches!(event, Event::End(e) if e.name().as_ref() == name)
+}
+
+/// Whether `events[i]` is the text of a `<w:t>` node
+fn is_run_text(events: &[Event], i: usize) -> bool {
+    matches!(events[i], Event::Text(_)) && i > 0 && is_start(&events[i - 1], b"w:t")
+}
+
+fn text_of(event: &Event) -> Result<String, BackendError> {
+    match event {
+        Event::Text(text) => text
+            .unescape()
+            .map(|t| t.into_owned())
+            .map_err(|e| template_error("Template part is not valid XML", e)),
+        _ => Ok(String::new()),
+    }
+}
+
+fn text_event(text: &str) -> Event<'static> {
+    Event::Text(BytesText::new(text).into_owned())
+}
+
+/// `<w:t>` keeping leading and trailing spaces
+fn preserved_text_start() -> Event<'static> {
+    Event::Start(BytesStart::new("w:t").with_attributes([("xml:space", "preserve")]))
+}
+
+/// Index of the end tag of the element starting at `start`
+fn element_end(events: &[Event], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, event) in events.iter().enumerate().skip(start) {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// ============================================================================
+// Placeholder normalization
+// ============================================================================
+
+/// A `<w:t>` text node and the paragraph it belongs to
+struct TextNode {
+    /// Index of the `<w:t>` start tag; the text follows it
+    start: usize,
+    paragraph: usize,
+}
+
+/// `<w:t>` nodes with text, by innermost paragraph
+///
+/// Text boxes (`w:txbxContent`, in both branches of `mc:AlternateContent`)
+/// hold paragraphs of their own inside a run of the outer one.
+fn find_text_nodes(events: &[Event]) -> Vec<TextNode> {
+    let mut nodes = Vec::new();
+    let mut open = Vec::new();
+    let mut paragraphs = 0;
+    for i in 0..events.len() {
+        if is_start(&events[i], b"w:p") {
+            open.push(paragraphs);
+            paragraphs += 1;
+        } else if is_end(&events[i], b"w:p") {
+            open.pop();
+        } else if is_run_text(events, i) {
+            nodes.push(TextNode {
+                start: i - 1,
+                paragraph: open.last().copied().unwrap_or(usize::MAX),
+            });
+        }
+    }
+    nodes
+}
+
+/// Move every placeholder split over several runs into its first run
+///
+/// Runs of a paragraph are joined whatever wraps them: hyperlinks, content
+/// controls (`w:sdt`), smart tags.
+fn normalize_placeholders(events: &mut [Event<'static>]) -> Result<(), BackendError> {
+    let nodes = find_text_nodes(events);
+    let mut contents = nodes
+        .iter()
+        .map(|n| text_of(&events[n.start + 1]))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut modified = vec![false; nodes.len()];
+
+    let mut paragraphs: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        paragraphs.entry(node.paragraph).or_default().push(i);
+    }
+
+    // Process each paragraph's nodes as one string
+    for members in paragraphs.values() {
+        // Owner node for every character of the paragraph text
+        let chars: Vec<(char, usize)> = members
+            .iter()
+            .flat_map(|&i| contents[i].chars().map(move |c| (c, i)))
+            .collect();
+        let text: String = chars.iter().map(|(c, _)| c).collect();
+        if !text.contains("{{") {
+            continue;
+        }
+
+        let mut owners: Vec<usize> = chars.iter().map(|(_, i)| *i).collect();
+        let char_starts: Vec<usize> = text.char_indices().map(|(b, _)| b).collect();
+        let mut search = 0;
+        while let Some(open) = text[search..].find("{{").map(|o| search + o) {
+            let Some(close) = text[open..].find("}}").map(|c| open + c + 2) else {
+                break;
+            };
+            let from = char_starts.partition_point(|&b| b < open);
+            let to = char_starts.partition_point(|&b| b < close);
+            let owner = owners[from];
+            for o in &mut owners[from..to] {
+                if *o != owner {
+                    modified[*o] = true;
+                    modified[owner] = true;
+                    *o = owner;
+                }
+            }
+            search = close;
+        }
+
+        for &i in members {
+            contents[i].clear();
+        }
+        for ((c, _), owner) in chars.iter().zip(owners) {
+            contents[owner].push(*c);
+        }
+    }
+
+    for (i, node) in nodes.iter().enumerate() {
+        if modified[i] {
+            // Moved text may now start or end with a space
+            events[node.start] = preserved_text_start();
+            events[node.start + 1] = text_event(&contents[i]);
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Merging
+// ============================================================================
+
+fn lookup<'a>(path: &str, scopes: &[&'a Value]) -> Option<&'a Value> {
+    scopes.iter().find_map(|scope| {
+        if path == "this" {
+            return Some(*scope);
+        }
+        path.split('.')
+            .try_fold(*scope, |value, key| value.get(key))
+            .filter(|v| !v.is_null())
+    })
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Replace `{{field}}` placeholders in a node's text; loop markers are
+/// removed. Returns the lines of the result, split where a value has a
+/// line break.
+fn merge_text(text: &str, scopes: &[&Value], missing: &mut BTreeSet<String>) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut pos = 0;
+    while let Some(open) = text[pos..].find("{{").map(|o| pos + o) {
+        let Some(close) = text[open..].find("}}").map(|c| open + c) else {
+            break;
+        };
+        lines.last_mut().unwrap().push_str(&text[pos..open]);
+        let key = text[open + 2..close].trim();
+        if !key.starts_with('#') && !key.starts_with('/') {
+            match lookup(key, scopes) {
+                Some(value) => {
+                    let value = value_text(value);
+                    let mut value_lines = value.split('\n');
+                    lines
+                        .last_mut()
+                        .unwrap()
+                        .push_str(value_lines.next().unwrap_or_default());
+                    lines.extend(value_lines.map(str::to_string));
+                }
+                None => {
+                    missing.insert(key.to_string());
+                }
+            }
+        }
+        pos = close + 2;
+    }
+    lines.last_mut().unwrap().push_str(&text[pos..]);
+    lines
+}
+
+/// Replace `{{field}}` placeholders in every `<w:t>` node
+fn merge_fields(
+    events: &[Event<'static>],
+    scopes: &[&Value],
+    missing: &mut BTreeSet<String>,
+) -> Result<Events, BackendError> {
+    let mut out = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        if !is_run_text(events, i) {
+            out.push(event.clone());
+            continue;
+        }
+        let text = text_of(event)?;
+        if !text.contains("{{") {
+            out.push(event.clone());
+            continue;
+        }
+        // Line breaks in values become Word line breaks
+        for (n, line) in merge_text(&text, scopes, missing).iter().enumerate() {
+            if n > 0 {
+                out.push(Event::End(BytesEnd::new("w:t")));
+                out.push(Event::Empty(BytesStart::new("w:br")));
+                out.push(preserved_text_start());
+            }
+            out.push(text_event(line));
+        }
+    }
+    Ok(out)
+}
+
+/// The first `{{#each list}}` marker: its list name and the element it
+/// repeats (the enclosing table row, or paragraph), as event indices
+fn find_loop(
+    events: &[Event],
+    from: usize,
+) -> Result<Option<(String, usize, usize)>, BackendError> {
+    let mut open: Vec<(&[u8], usize)> = Vec::new();
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(e) => open.push((e.name().into_inner(), i)),
+            Event::End(_) => {
+                open.pop();
+            }
+            _ if i >= from && is_run_text(events, i) => {
+                let text = text_of(&events[i])?;
+                let Some(marker) = text.find("{{#each ") else {
+                    continue;
+                };
+                let Some(marker_end) = text[marker..].find("}}").map(|e| marker + e) else {
+                    continue;
+                };
+                let list_name = text[marker + 8..marker_end].trim().to_string();
+                let enclosing = |name: &[u8]| open.iter().rev().find(|(n, _)| *n == name);
+                let Some(&(_, start)) = enclosing(b"w:tr").or_else(|| enclosing(b"w:p")) else {
+                    continue;
+                };
+                if let Some(end) = element_end(events, start) {
+                    return Ok(Some((list_name, start, end)));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Expand `{{#each list}}` blocks, once per item of the list
+fn expand_loops(
+    mut events: Events,
+    root: &Value,
+    missing: &mut BTreeSet<String>,
+) -> Result<Events, BackendError> {
+    let mut search = 0;
+    while let Some((list_name, start, end)) = find_loop(&events, search)? {
+        let items = match lookup(&list_name, &[root]) {
+            Some(Value::Array(items)) => items.clone(),
+            _ => {
+                missing.insert(list_name);
+                Vec::new()
+            }
+        };
+        let mut rendered = Vec::new();
+        for item in &items {
+            rendered.extend(merge_fields(&events[start..=end], &[item, root], missing)?);
+        }
+        search = start + rendered.len();
+        events.splice(start..=end, rendered);
+    }
+    Ok(events)
+}
+
+/// Merge one XML part with data
+pub fn render_part(
+    xml: &str,
+    data: &Value,
+    missing: &mut BTreeSet<String>,
+) -> Result<String, BackendError> {
+    let mut events = read_events(xml)?;
+    normalize_placeholders(&mut events)?;
+    let events = expand_loops(events, data, missing)?;
+    write_events(&merge_fields(&events, &[data], missing)?)
+}
+
+/// Merge a template package (bytes of a .docx) with data
+pub fn render_docx(template: &[u8], data: &Value) -> Result<(Vec<u8>, Vec<String>), BackendError> {
+    let mut archive = ZipArchive::new(Cursor::new(template))
+        .map_err(|e| template_error("Template is not a valid .docx file", e))?;
+    if archive.by_name(MAIN_PART).is_err() {
+        return Err(template_error(
+            "Template is not a Word document",
+            "word/document.xml missing",
+        ));
+    }
+
+    let mut missing = BTreeSet::new();
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| template_error("Failed to read template entry", e))?;
+        let name = entry.name().to_string();
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| template_error("Failed to read template entry", e))?;
+
+        if is_template_part(&name) {
+            let xml = String::from_utf8(bytes)
+                .map_err(|e| template_error("Template part is not UTF-8", e))?;
+            bytes = render_part(&xml, data, &mut missing)?.into_bytes();
+        }
+
+        writer
+            .start_file(name.as_str(), options)
+            .and_then(|_| writer.write_all(&bytes).map_err(Into::into))
+            .map_err(|e| template_error("Failed to write document", e))?;
+    }
+
+    let cursor = writer
+        .finish()
+        .map_err(|e| template_error("Failed to write document", e))?;
+    Ok((cursor.into_inner(), missing.into_iter().collect()))
+}
+
+fn read_template(template_path: &str) -> Result<Vec<u8>, BackendError> {
+    let path = Path::new(template_path);
+    let is_docx = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("docx") || e.eq_ignore_ascii_case("dotx"));
+    if !is_docx {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "Template must be a Word document (.docx or .dotx)",
+        ));
+    }
+    fs::read(path).map_err(|e| {
+        BackendError::new(
+            errors::file::NOT_FOUND,
+            format!("Template not found: {}", path.display()),
+        )
+        .with_details(e.to_string())
+    })
+}
+
+fn validate_output(output_path: &Path) -> Result<(), BackendError> {
+    let is_docx = output_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("docx"));
+    if !is_docx {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "Output file must have the .docx extension",
+        ));
+    }
+    match output_path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() || dir.is_dir() => Ok(()),
+        _ => Err(BackendError::new(
+            errors::file::NOT_FOUND,
+            "Output folder does not exist",
+        )),
+    }
+}
+
+/// Generate a document from a template and save it to `output_path`
+pub fn generate_docx_from_template(
+    template_path: &str,
+    data: &Value,
+    output_path: &str,
+) -> Result<GeneratedDocument, BackendError> {
+    let output = Path::new(output_path);
+    validate_output(output)?;
+    let (bytes, missing_fields) = render_docx(&read_template(template_path)?, data)?;
+    fs::write(output, bytes)?;
+    Ok(GeneratedDocument {
+        path: output.display().to_string(),
+        missing_fields,
+    })
+}
+
+/// File-system safe version of a student name
+fn safe_file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '\'') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Generate one document per student of a class into `output_dir`
+///
+/// Each document gets `data` plus `class.name` and `student.name` /
+/// `student.notes`, and `students` (the whole class) for lists.
+/// `progress(done, total)` is called before each student; returning an
+/// error stops the generation.
+pub fn generate_class_documents(
+    template_path: &str,
+    class_id: &str,
+    data: &Value,
+    output_dir: &str,
+    progress: &mut dyn FnMut(usize, usize) -> Result<(), BackendError>,
+) -> Result<Vec<GeneratedDocument>, BackendError> {
+    let dir = PathBuf::from(output_dir);
+    if !dir.is_dir() {
+        return Err(BackendError::new(
+            errors::file::NOT_FOUND,
+            "Output folder does not exist",
+        ));
+    }
+    let store = RosterStore::load()?;
+    let class = store.find(class_id).ok_or_else(|| {
+        BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+            .with_details(class_id.to_string())
+    })?;
+    let template = read_template(template_path)?;
+
+    let mut base = match data {
+        Value::Object(map) => map.clone(),
+        _ => Map::new(),
+    };
+    base.insert("class".into(), json!({ "name": class.name }));
+    base.insert("students".into(), json!(class.students));
+
+    let mut generated = Vec::new();
+    for (i, student) in class.students.iter().enumerate() {
+        progress(i, class.students.len())?;
+        let mut merged = base.clone();
+        merged.insert("student".into(), json!(student));
+        let (bytes, missing_fields) = render_docx(&template, &Value::Object(merged))?;
+
+        let path = dir.join(format!("{}.docx", safe_file_stem(&student.name)));
+        fs::write(&path, bytes)?;
+        generated.push(GeneratedDocument {
+            path: path.display().to_string(),
+            missing_fields,
+        });
+    }
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(runs: &[&str]) -> String {
+        let runs: String = runs
+            .iter()
+            .map(|t| format!("<w:r><w:rPr><w:b/></w:rPr><w:t>{}</w:t></w:r>", t))
+            .collect();
+        format!("<w:p>{}</w:p>", runs)
+    }
+
+    fn render(xml: &str, data: &Value) -> (String, Vec<String>) {
+        let mut missing = BTreeSet::new();
+        let out = render_part(xml, data, &mut missing).unwrap();
+        // Still well-formed
+        read_events(&out).unwrap();
+        (out, missing.into_iter().collect())
+    }
+
+    #[test]
+    fn test_split_placeholders_are_merged() {
+        let xml = paragraph(&["Gentile famiglia di {{stu", "dent.", "name}}, ciao"]);
+        let data = json!({ "student": { "name": "Mario <Rossi>" } });
+        let (out, missing) = render(&xml, &data);
+        assert!(out.contains("Gentile famiglia di Mario &lt;Rossi&gt;"));
+        assert!(!out.contains("{{"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_table_row_loop_and_missing_fields() {
+        let row = format!(
+            "<w:tr><w:tc>{}</w:tc></w:tr>",
+            paragraph(&["{{#each students}}{{name}} - {{class.name}}{{/each}}"])
+        );
+        let xml = format!("<w:tbl>{}</w:tbl>{}", row, paragraph(&["{{unknown}}"]));
+        let data = json!({
+            "class": { "name": "3A" },
+            "students": [{ "name": "Anna" }, { "name": "Luca" }]
+        });
+        let (out, missing) = render(&xml, &data);
+        assert_eq!(out.matches("<w:tr>").count(), 2);
+        assert!(out.contains("Anna - 3A"));
+        assert!(out.contains("Luca - 3A"));
+        assert_eq!(missing, vec!["unknown"]);
+    }
+
+    #[test]
+    fn test_text_nodes_skip_other_w_t_tags() {
+        let xml = "<w:p><w:r><w:tab/><w:t xml:space=\"preserve\">a </w:t></w:r></w:p><w:tbl><w:tr></w:tr></w:tbl>";
+        let events = read_events(xml).unwrap();
+        let nodes = find_text_nodes(&events);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(text_of(&events[nodes[0].start + 1]).unwrap(), "a ");
+    }
+
+    #[test]
+    fn test_line_breaks_and_field_codes() {
+        // `{{` in a field code or an attribute is not a placeholder
+        let xml = "<w:p><w:r><w:instrText>{{x}}</w:instrText></w:r>\
+            <w:r><w:t>{{note}}</w:t></w:r></w:p>";
+        let (out, missing) = render(xml, &json!({ "note": "Riga 1\nRiga 2 & altro" }));
+        assert!(out.contains("<w:instrText>{{x}}</w:instrText>"));
+        assert!(out.contains(
+            "<w:t>Riga 1</w:t><w:br/><w:t xml:space=\"preserve\">Riga 2 &amp; altro</w:t>"
+        ));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_placeholder_split_by_hyperlink() {
+        let xml = "<w:p><w:r><w:t>Scheda di {{student.</w:t></w:r>\
+            <w:hyperlink r:id=\"rId5\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr>\
+            <w:t>name}} (profilo)</w:t></w:r></w:hyperlink></w:p>";
+        let (out, missing) = render(xml, &json!({ "student": { "name": "Anna" } }));
+        assert!(out.contains("Scheda di Anna</w:t>"));
+        assert!(out.contains("<w:hyperlink r:id=\"rId5\">"));
+        assert!(out.contains(" (profilo)</w:t></w:r></w:hyperlink>"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_placeholders_in_content_controls() {
+        let xml = "<w:p><w:r><w:t>Alunno: </w:t></w:r>\
+            <w:sdt><w:sdtPr><w:alias w:val=\"Nome\"/></w:sdtPr><w:sdtContent>\
+            <w:r><w:t>{{student.</w:t></w:r></w:sdtContent></w:sdt>\
+            <w:r><w:t>name}}, classe {{class.name}}</w:t></w:r></w:p>\
+            <w:sdt><w:sdtContent><w:p><w:r><w:t>{{class.name}}</w:t></w:r></w:p></w:sdtContent></w:sdt>";
+        let data = json!({ "student": { "name": "Luca" }, "class": { "name": "2B" } });
+        let (out, missing) = render(xml, &data);
+        assert!(out.contains("<w:sdtContent><w:r><w:t xml:space=\"preserve\">Luca</w:t>"));
+        assert!(out.contains(", classe 2B</w:t>"));
+        assert!(out.contains("<w:sdtContent><w:p><w:r><w:t>2B</w:t>"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_text_box_alternate_content() {
+        // A text box is written twice: DrawingML and a VML fallback, each
+        // with its own paragraphs inside a run of the outer one
+        let text_box =
+            |runs: &[&str]| format!("<w:txbxContent>{}</w:txbxContent>", paragraph(runs));
+        let xml = format!(
+            "<w:p><w:r><w:t>Classe {{{{cla</w:t></w:r><w:r><mc:AlternateContent>\
+             <mc:Choice Requires=\"wps\"><w:drawing><wps:txbx>{}</wps:txbx></w:drawing></mc:Choice>\
+             <mc:Fallback><w:pict><v:textbox>{}</v:textbox></w:pict></mc:Fallback>\
+             </mc:AlternateContent></w:r><w:r><w:t>ss.name}}}}</w:t></w:r></w:p>",
+            text_box(&["{{student.", "name}}"]),
+            text_box(&["{{student.name}}"]),
+        );
+        let data = json!({ "student": { "name": "Anna" }, "class": { "name": "3A" } });
+        let (out, missing) = render(&xml, &data);
+        assert_eq!(out.matches("Anna").count(), 2);
+        assert!(out.contains("Classe 3A"));
+        assert!(!out.contains("{{"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_xml_is_a_template_error() {
+        let err = render_part("<w:p><w:t>a</w:p>", &json!({}), &mut BTreeSet::new()).unwrap_err();
+        assert_eq!(err.code, errors::document::TEMPLATE_INVALID);
+    }
+
+    #[test]
+    fn test_render_docx_package() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("[Content_Types].xml", options).unwrap();
+        writer.write_all(b"<Types/>").unwrap();
+        writer.start_file(MAIN_PART, options).unwrap();
+        writer
+            .write_all(paragraph(&["Classe {{class.name}}"]).as_bytes())
+            .unwrap();
+        let template = writer.finish().unwrap().into_inner();
+
+        let (bytes, missing) =
+            render_docx(&template, &json!({ "class": { "name": "2B" } })).unwrap();
+        assert!(missing.is_empty());
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut xml = String::new();
+        archive
+            .by_name(MAIN_PART)
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        assert!(xml.contains("Classe 2B"));
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+
+        assert!(render_docx(b"not a zip", &json!({})).is_err());
+        assert_eq!(safe_file_stem("Rossi/Mario: 3A"), "Rossi_Mario_ 3A");
+    }
+}
@@ -0,0 +1,238 @@
+//! Minimal text-only PDF writer
+//!
+//! Enough for printable reports: A4 pages, Helvetica (regular and bold),
+//! automatic page breaks. Text is encoded as WinAnsi, which covers Italian
+//! accented letters; other characters are replaced with `?`.
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+/// Approximate characters per line at body size (Helvetica averages ~0.5em)
+const WRAP_COLUMNS: usize = 95;
+
+const BODY_SIZE: f32 = 10.0;
+const HEADING_SIZE: f32 = 16.0;
+const SUBHEADING_SIZE: f32 = 12.0;
+
+/// A text document built line by line
+#[derive(Debug, Default)]
+pub struct TextPdf {
+    pages: Vec<String>,
+    current: String,
+    y: f32,
+}
+
+impl TextPdf {
+    pub fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Document title
+    pub fn heading(&mut self, text: &str) {
+        self.write_line(text, HEADING_SIZE, true);
+        self.space(6.0);
+    }
+
+    /// Section title
+    pub fn subheading(&mut self, text: &str) {
+        self.space(8.0);
+        self.write_line(text, SUBHEADING_SIZE, true);
+        self.space(2.0);
+    }
+
+    /// Body text, wrapped to the page width
+    pub fn paragraph(&mut self, text: &str) {
+        for line in wrap(text, WRAP_COLUMNS) {
+            self.write_line(&line, BODY_SIZE, false);
+        }
+    }
+
+    fn space(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    fn write_line(&mut self, text: &str, size: f32, bold: bool) {
+        let leading = size * 1.3;
+        if self.y - leading < MARGIN {
+            self.new_page();
+        }
+        self.y -= leading;
+        self.current.push_str(&format!(
+            "BT /{} {} Tf {} {} Td ({}) Tj ET\n",
+            if bold { "F2" } else { "F1" },
+            size,
+            MARGIN,
+            self.y,
+            escape(text)
+        ));
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Serialize the document
+    pub fn finish(mut self) -> Vec<u8> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.new_page();
+        }
+
+        // Objects: 1 catalog, 2 pages, 3-4 fonts, then (page, content) pairs
+        let page_count = self.pages.len();
+        let page_ids: Vec<usize> = (0..page_count).map(|i| 5 + i * 2).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{} 0 R", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                page_count
+            )
+            .into_bytes(),
+            font_object("Helvetica"),
+            font_object("Helvetica-Bold"),
+        ];
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    page_ids[i] + 1
+                )
+                .into_bytes(),
+            );
+            let stream = encode(content);
+            let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+            object.extend_from_slice(&stream);
+            object.extend_from_slice(b"\nendstream");
+            objects.push(object);
+        }
+
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        out.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        out
+    }
+}
+
+fn font_object(name: &str) -> Vec<u8> {
+    format!(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+        name
+    )
+    .into_bytes()
+}
+
+/// Escape a PDF literal string
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' | '\r' | '\t' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Content stream text to WinAnsi bytes (Latin-1 range maps directly)
+fn encode(content: &str) -> Vec<u8> {
+    content
+        .chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '’' => b'\'',
+            '“' | '”' => b'"',
+            '–' | '—' => b'-',
+            c if (c as u32) < 0x80 || (0xA0..=0xFF).contains(&(c as u32)) => c as u32 as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Greedy word wrap at `columns` characters
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for raw in text.lines() {
+        let mut line = String::new();
+        for word in raw.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_structure_and_pagination() {
+        let mut pdf = TextPdf::new();
+        pdf.heading("Registro (3A)");
+        for i in 0..120 {
+            pdf.paragraph(&format!("Riga {} perché", i));
+        }
+        let bytes = pdf.finish();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("Registro \\(3A\\)"));
+        assert!(text.contains("/Count 3"));
+        // "é" is a single WinAnsi byte
+        assert!(bytes.windows(7).any(|w| w == b"perch\xE9)"));
+
+        // xref offsets point at the objects
+        let xref = text.rfind("startxref\n").unwrap();
+        let offset: usize = text[xref + 10..].lines().next().unwrap().parse().unwrap();
+        assert!(bytes[offset..].starts_with(b"xref"));
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("uno due tre", 7), vec!["uno due", "tre"]);
+        assert!(wrap("", 10).is_empty());
+    }
+}
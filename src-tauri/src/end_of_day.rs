@@ -0,0 +1,473 @@
+//! End-of-day routine
+//!
+//! Handles:
+//! - A daily time and the actions to run then (`end_of_day` config key):
+//!   stop monitoring, back up, sync to the cloud, write the daily summary,
+//!   and lock or quit the app
+//! - Running the actions in that order, whatever order they were chosen
+//!   in; a failed step is logged and the others still run
+//! - `end-of-day` with the run's outcome, sent before the app locks or
+//!   quits, so the frontend stops its meter and tells the teacher
+//!
+//! Classroom PCs are often switched off at the wall at the end of the day;
+//! this gets the data flushed and backed up while the app still runs.
+//!
+//! If the app was not running at the set time, the backup, sync and that
+//! day's summary run at the next start. Stopping monitoring, locking and
+//! quitting only run within a few minutes of the set time, never in the
+//! middle of the next morning's lesson.
+
+use crate::app_lock;
+use crate::backup;
+use crate::clock;
+use crate::cloud;
+use crate::day_overview::{self, DayOverview};
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::locale::{self, Language};
+use crate::noise_history;
+use crate::shutdown;
+use crate::state::AppState;
+use crate::weekly_summary::{self, escape_html, local_millis, parse_time};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CONFIG_KEY: &str = "end_of_day";
+const STATE_COLLECTION: &str = "end_of_day";
+/// Emitted with the `EndOfDayRun` once the routine has run
+pub const END_OF_DAY_EVENT: &str = "end-of-day";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How late (in minutes) the routine may start and still end the session
+const GRACE_MINUTES: i64 = 15;
+/// Runs kept in the log (about a school month)
+const MAX_LOG_ENTRIES: usize = 30;
+
+/// Serializes scheduled runs and configuration changes
+static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+/// A step of the routine; the declaration order is the order they run in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EndOfDayAction {
+    /// Stop audio streams and end the noise session, writing its samples
+    StopMonitoring,
+    /// Local backup archive
+    Backup,
+    /// Cloud backup (`cloud`); makes its own local backup first
+    CloudSync,
+    /// Day overview of every class, saved with the weekly summaries
+    DailySummary,
+    Lock,
+    Quit,
+}
+
+impl EndOfDayAction {
+    /// Whether the step only makes sense at the set time
+    fn ends_session(self) -> bool {
+        matches!(
+            self,
+            EndOfDayAction::StopMonitoring | EndOfDayAction::Lock | EndOfDayAction::Quit
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndOfDayConfig {
+    pub enabled: bool,
+    /// Local time, `HH:MM`
+    pub time: String,
+    pub actions: Vec<EndOfDayAction>,
+}
+
+impl Default for EndOfDayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: "17:00".to_string(),
+            actions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub action: EndOfDayAction,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// One run of the routine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndOfDayRun {
+    /// Set time the run was for (epoch ms)
+    pub slot: u64,
+    pub ran_at: u64,
+    /// Caught up at a later start; session-ending steps were left out
+    pub late: bool,
+    pub steps: Vec<StepResult>,
+}
+
+/// Persisted scheduler state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EndOfDayState {
+    #[serde(default)]
+    last_slot: Option<u64>,
+    #[serde(default)]
+    log: Vec<EndOfDayRun>,
+}
+
+impl EndOfDayState {
+    fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STATE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STATE_COLLECTION, self)
+    }
+}
+
+/// Most recent `time` that is not after `now`
+pub fn latest_slot(now: NaiveDateTime, time: NaiveTime) -> NaiveDateTime {
+    let slot = now.date().and_time(time);
+    if slot > now {
+        slot - chrono::Duration::days(1)
+    } else {
+        slot
+    }
+}
+
+/// Steps to run, in order; a late run leaves out the session-ending ones
+fn plan(actions: &[EndOfDayAction], late: bool) -> Vec<EndOfDayAction> {
+    let mut steps: Vec<EndOfDayAction> = actions
+        .iter()
+        .copied()
+        .filter(|a| !(late && a.ends_session()))
+        .collect();
+    steps.sort();
+    steps.dedup();
+    // The cloud backup already makes the local one
+    if steps.contains(&EndOfDayAction::CloudSync) {
+        steps.retain(|a| *a != EndOfDayAction::Backup);
+    }
+    steps
+}
+
+fn summary_title(lang: Language) -> &'static str {
+    lang.pick("Riepilogo del giorno", "Daily summary")
+}
+
+/// HTML report of a day overview, in the app language
+pub fn build_daily_summary(overview: &DayOverview, lang: Language) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\"><head><meta charset=\"utf-8\">\
+         <title>{} {}</title>\
+         <style>body{{font-family:sans-serif}}</style>\
+         </head><body>\n<h1>{} {}</h1>\n",
+        lang.code(),
+        summary_title(lang),
+        overview.date,
+        summary_title(lang),
+        overview.date
+    );
+    if overview.classes.is_empty() {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            lang.pick("Nessuna classe registrata.", "No classes yet.")
+        ));
+    }
+    for class in &overview.classes {
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<ul>\n",
+            escape_html(&class.class_name)
+        ));
+        let attendance = &class.attendance;
+        if attendance.recorded {
+            let mut line = format!(
+                "{} {}, {} {}",
+                lang.pick("Presenti:", "Present:"),
+                attendance.present,
+                lang.pick("assenti:", "absent:"),
+                attendance.absent
+            );
+            if !attendance.absent_students.is_empty() {
+                line.push_str(&format!(
+                    " ({})",
+                    escape_html(&attendance.absent_students.join(", "))
+                ));
+            }
+            html.push_str(&format!("<li>{}</li>\n", line));
+        } else {
+            html.push_str(&format!(
+                "<li>{}</li>\n",
+                lang.pick("Presenze non registrate", "Attendance not recorded")
+            ));
+        }
+        html.push_str(&format!(
+            "<li>{} {}</li>\n",
+            lang.pick("Note di comportamento:", "Behavior notes:"),
+            class.behavior.len()
+        ));
+        if let Some(noise) = &class.noise {
+            let level =
+                |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| format!("{:.0}", v));
+            html.push_str(&format!(
+                "<li>{} {} {}, {} {} {}</li>\n",
+                lang.pick("Rumore mediano:", "Median noise:"),
+                level(noise.median),
+                noise.unit.label(),
+                lang.pick("picco:", "peak:"),
+                level(noise.peak),
+                noise.unit.label()
+            ));
+        }
+        let out = class
+            .devices
+            .iter()
+            .filter(|d| d.returned_at.is_none())
+            .count();
+        if out > 0 {
+            html.push_str(&format!(
+                "<li>{} {}</li>\n",
+                lang.pick("Dispositivi non restituiti:", "Devices not returned:"),
+                out
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+    let pending = overview.pending.len()
+        + overview
+            .classes
+            .iter()
+            .map(|c| c.pending.len())
+            .sum::<usize>();
+    if pending > 0 {
+        html.push_str(&format!(
+            "<p>{} {}</p>\n",
+            lang.pick("Cose in sospeso:", "Pending items:"),
+            pending
+        ));
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// Write the summary of `date` next to the weekly summaries
+fn write_daily_summary(date: NaiveDate) -> Result<(), BackendError> {
+    let date = date.format("%Y-%m-%d").to_string();
+    let lang = locale::app_language();
+    let html = build_daily_summary(&day_overview::get_day_overview(&date)?, lang);
+    let dir = file_ops::get_config_dir()?.join(weekly_summary::SUMMARIES_DIR);
+    fs::create_dir_all(&dir)?;
+    let file_name = format!(
+        "{}-{}.html",
+        lang.pick("riepilogo-giornaliero", "daily-summary"),
+        date
+    );
+    fs::write(dir.join(file_name), html)?;
+    Ok(())
+}
+
+fn run_step(app: &AppHandle, action: EndOfDayAction, date: NaiveDate) -> StepResult {
+    let result = match action {
+        EndOfDayAction::StopMonitoring => {
+            app.state::<AppState>().stop_all_audio_streams();
+            noise_history::set_noise_context(None)
+        }
+        EndOfDayAction::Backup => backup::create_local_backup("backup").map(|_| ()),
+        EndOfDayAction::CloudSync => cloud::backup_to_cloud().map(|_| ()),
+        EndOfDayAction::DailySummary => write_daily_summary(date),
+        EndOfDayAction::Lock => app_lock::lock_app(app).map(|_| ()),
+        // Done last, after the run is saved
+        EndOfDayAction::Quit => Ok(()),
+    };
+    StepResult {
+        action,
+        success: result.is_ok(),
+        error: result.err().map(|e| e.message),
+    }
+}
+
+pub fn get_config() -> EndOfDayConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Validate and save the routine; no actions turns it off
+///
+/// Today's time, if already past, counts as done, so setting the routine
+/// up in the evening does not quit the app on the spot.
+pub fn configure(time: &str, actions: Vec<EndOfDayAction>) -> Result<EndOfDayConfig, BackendError> {
+    let parsed = parse_time(time)?;
+    let actions = plan(&actions, false);
+    if actions.contains(&EndOfDayAction::Lock) && actions.contains(&EndOfDayAction::Quit) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Choose either locking or quitting the app",
+        ));
+    }
+    if actions.contains(&EndOfDayAction::Lock) && !app_lock::get_app_lock_status().enabled {
+        return Err(BackendError::new(
+            errors::lock::NOT_CONFIGURED,
+            "Set a PIN before choosing to lock the app",
+        ));
+    }
+    if actions.contains(&EndOfDayAction::CloudSync) && !cloud::is_configured() {
+        return Err(BackendError::new(
+            errors::backup::NOT_CONFIGURED,
+            "Configure a cloud backup before choosing to sync",
+        ));
+    }
+
+    let config = EndOfDayConfig {
+        enabled: !actions.is_empty(),
+        time: parsed.format("%H:%M").to_string(),
+        actions,
+    };
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid end-of-day settings")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = EndOfDayState::load()?;
+    state.last_slot = Some(local_millis(latest_slot(
+        Local::now().naive_local(),
+        parsed,
+    )));
+    state.save()?;
+    Ok(config)
+}
+
+/// Run the routine if its time has passed since the last run
+fn run_if_due(app: &AppHandle) -> Result<Option<EndOfDayRun>, BackendError> {
+    let config = get_config();
+    if !config.enabled {
+        return Ok(None);
+    }
+    let now = Local::now().naive_local();
+    let slot_time = latest_slot(now, parse_time(&config.time)?);
+    let slot = local_millis(slot_time);
+
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = EndOfDayState::load()?;
+    if state.last_slot.is_some_and(|last| last >= slot) {
+        return Ok(None);
+    }
+    let late = now - slot_time > chrono::Duration::minutes(GRACE_MINUTES);
+    let steps = plan(&config.actions, late);
+    let run = EndOfDayRun {
+        slot,
+        ran_at: clock::now_millis(),
+        late,
+        steps: steps
+            .iter()
+            .map(|action| run_step(app, *action, slot_time.date()))
+            .collect(),
+    };
+    state.last_slot = Some(slot);
+    state.log.insert(0, run.clone());
+    state.log.truncate(MAX_LOG_ENTRIES);
+    state.save()?;
+    let _ = app.emit(END_OF_DAY_EVENT, &run);
+
+    if steps.contains(&EndOfDayAction::Quit) {
+        shutdown::shutdown(app);
+        app.exit(0);
+    }
+    Ok(Some(run))
+}
+
+/// Past runs, newest first
+pub fn get_log() -> Result<Vec<EndOfDayRun>, BackendError> {
+    Ok(EndOfDayState::load()?.log)
+}
+
+/// Start the background thread that runs the routine when due
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = run_if_due(&app) {
+            eprintln!("End of day: {}", e);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_overview::{AttendanceSummary, ClassDayOverview};
+    use EndOfDayAction::*;
+
+    #[test]
+    fn test_slot_and_plan() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let time = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+        let evening = day.and_hms_opt(17, 5, 0).unwrap();
+        assert_eq!(latest_slot(evening, time), day.and_time(time));
+        let morning = day.and_hms_opt(8, 0, 0).unwrap();
+        assert_eq!(
+            latest_slot(morning, time),
+            day.pred_opt().unwrap().and_time(time)
+        );
+
+        let chosen = [Quit, DailySummary, Backup, StopMonitoring, Backup];
+        assert_eq!(
+            plan(&chosen, false),
+            [StopMonitoring, Backup, DailySummary, Quit]
+        );
+        // Caught up the next morning: only the data steps
+        assert_eq!(plan(&chosen, true), [Backup, DailySummary]);
+        assert_eq!(plan(&[Backup, CloudSync, Lock], true), [CloudSync]);
+
+        let config: EndOfDayConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "time": "16:30",
+            "actions": ["cloudSync", "stopMonitoring"]
+        }))
+        .unwrap();
+        assert_eq!(config.actions, [CloudSync, StopMonitoring]);
+    }
+
+    #[test]
+    fn test_daily_summary() {
+        let class = |name: &str, recorded| ClassDayOverview {
+            class_id: name.to_string(),
+            class_name: name.to_string(),
+            students: 3,
+            attendance: AttendanceSummary {
+                recorded,
+                present: 2,
+                absent: 1,
+                absent_students: vec!["Rossi Mario".to_string()],
+            },
+            behavior: Vec::new(),
+            noise: None,
+            devices: Vec::new(),
+            pending: Vec::new(),
+        };
+        let overview = DayOverview {
+            date: "2024-03-04".to_string(),
+            classes: vec![class("3A <sc>", true), class("4B", false)],
+            pending: Vec::new(),
+            exit_tickets: 0,
+        };
+        let html = build_daily_summary(&overview, Language::It);
+        assert!(html.contains("<h1>Riepilogo del giorno 2024-03-04</h1>"));
+        assert!(html.contains("<h2>3A &lt;sc&gt;</h2>"));
+        assert!(html.contains("Presenti: 2, assenti: 1 (Rossi Mario)"));
+        assert!(html.contains("Presenze non registrate"));
+        assert!(!html.contains("Cose in sospeso"));
+    }
+}
@@ -44,6 +44,9 @@ pub mod window {
     pub const NOT_FOUND: &str = "WINDOW_NOT_FOUND";
     pub const INVALID_POSITION: &str = "INVALID_WINDOW_POSITION";
     pub const MONITOR_NOT_FOUND: &str = "MONITOR_NOT_FOUND";
+    pub const CREATE_FAILED: &str = "WINDOW_CREATE_FAILED";
+    pub const CAPTURE_FAILED: &str = "WINDOW_CAPTURE_FAILED";
+    pub const PROTECTION_FAILED: &str = "WINDOW_PROTECTION_FAILED";
 }
 
 /// Permission errors
@@ -51,12 +54,203 @@ pub mod permission {
     pub const MICROPHONE_DENIED: &str = "MICROPHONE_DENIED";
     pub const MICROPHONE_UNAVAILABLE: &str = "MICROPHONE_UNAVAILABLE";
     pub const PERMISSION_ERROR: &str = "PERMISSION_ERROR";
+    pub const SCREEN_CAPTURE_DENIED: &str = "SCREEN_CAPTURE_DENIED";
+    pub const SCREEN_CAPTURE_UNAVAILABLE: &str = "SCREEN_CAPTURE_UNAVAILABLE";
+    pub const CAMERA_DENIED: &str = "CAMERA_DENIED";
+    pub const CAMERA_UNAVAILABLE: &str = "CAMERA_UNAVAILABLE";
+    pub const KEYBOARD_ACCESS_DENIED: &str = "KEYBOARD_ACCESS_DENIED";
+}
+
+/// Exit ticket errors
+pub mod exit_ticket {
+    pub const NO_ACTIVE_SESSION: &str = "EXIT_TICKET_NOT_OPEN";
+    pub const ALREADY_OPEN: &str = "EXIT_TICKET_ALREADY_OPEN";
+    pub const INVALID_RESPONSE: &str = "EXIT_TICKET_INVALID_RESPONSE";
+}
+
+/// Mood check-in errors
+pub mod mood {
+    pub const NO_ACTIVE_CHECKIN: &str = "MOOD_CHECKIN_NOT_OPEN";
+    pub const ALREADY_OPEN: &str = "MOOD_CHECKIN_ALREADY_OPEN";
+    pub const ALREADY_ANSWERED: &str = "MOOD_CHECKIN_ALREADY_ANSWERED";
+    pub const INVALID_CHOICE: &str = "MOOD_CHECKIN_INVALID_CHOICE";
+}
+
+/// Companion device authentication errors
+pub mod auth {
+    pub const INVALID_PAIRING_CODE: &str = "INVALID_PAIRING_CODE";
+    pub const PAIRING_CLOSED: &str = "PAIRING_CLOSED";
+    pub const INVALID_TOKEN: &str = "INVALID_TOKEN";
+    pub const TOKEN_EXPIRED: &str = "TOKEN_EXPIRED";
+    pub const DEVICE_REVOKED: &str = "DEVICE_REVOKED";
+    pub const DEVICE_NOT_FOUND: &str = "DEVICE_NOT_FOUND";
+}
+
+/// LAN channel errors
+pub mod lan {
+    pub const TLS_ERROR: &str = "LAN_TLS_ERROR";
+    pub const INTERFACE_NOT_FOUND: &str = "NETWORK_INTERFACE_NOT_FOUND";
+    pub const NETWORK_ERROR: &str = "NETWORK_ERROR";
+}
+
+/// Backup and cloud sync errors
+pub mod backup {
+    pub const ARCHIVE_ERROR: &str = "BACKUP_ARCHIVE_ERROR";
+    pub const UNSUPPORTED_VERSION: &str = "BACKUP_UNSUPPORTED_VERSION";
+    pub const WRONG_PASSPHRASE: &str = "BACKUP_WRONG_PASSPHRASE";
+    pub const PASSPHRASE_REQUIRED: &str = "BACKUP_PASSPHRASE_REQUIRED";
+    pub const NOT_CONFIGURED: &str = "CLOUD_NOT_CONFIGURED";
+    pub const REMOTE_ERROR: &str = "CLOUD_REMOTE_ERROR";
+    pub const UNREACHABLE: &str = "CLOUD_UNREACHABLE";
+    pub const SECRET_STORE_ERROR: &str = "SECRET_STORE_ERROR";
+}
+
+/// Class roster errors
+pub mod roster {
+    pub const CLASS_NOT_FOUND: &str = "CLASS_NOT_FOUND";
+    pub const INVALID_ROSTER: &str = "INVALID_ROSTER";
+    pub const UPDATE_NOT_FOUND: &str = "ROSTER_UPDATE_NOT_FOUND";
+    pub const FOLDER_NOT_FOUND: &str = "ROSTER_FOLDER_NOT_FOUND";
+    pub const CLASS_EXISTS: &str = "CLASS_ALREADY_EXISTS";
+    pub const INVALID_TRANSFORM: &str = "ROSTER_INVALID_TRANSFORM";
+}
+
+/// Document generation errors
+pub mod document {
+    pub const TEMPLATE_INVALID: &str = "DOCX_TEMPLATE_INVALID";
+}
+
+/// Email errors
+pub mod mail {
+    pub const NOT_CONFIGURED: &str = "MAIL_NOT_CONFIGURED";
+    pub const SEND_FAILED: &str = "MAIL_SEND_FAILED";
+    pub const INVALID_ADDRESS: &str = "MAIL_INVALID_ADDRESS";
+}
+
+/// Configuration errors
+pub mod config {
+    pub const VALIDATION_FAILED: &str = "CONFIG_VALIDATION_FAILED";
+    pub const INVALID_CONFIRMATION: &str = "RESET_CONFIRMATION_INVALID";
+}
+
+/// Background job errors
+pub mod job {
+    pub const NOT_FOUND: &str = "JOB_NOT_FOUND";
+    pub const CANCELLED: &str = "JOB_CANCELLED";
+}
+
+/// Attachment errors
+pub mod attachment {
+    pub const NOT_FOUND: &str = "ATTACHMENT_NOT_FOUND";
+    pub const TOO_LARGE: &str = "ATTACHMENT_TOO_LARGE";
+}
+
+/// Timer sequence errors
+pub mod timer {
+    pub const NOT_RUNNING: &str = "TIMER_SEQUENCE_NOT_RUNNING";
+}
+
+/// Command palette / controller action errors
+pub mod action {
+    pub const NOT_FOUND: &str = "ACTION_NOT_FOUND";
+}
+
+/// Clock check errors
+pub mod clock {
+    pub const NTP_UNAVAILABLE: &str = "CLOCK_NTP_UNAVAILABLE";
+}
+
+/// Observer window errors
+pub mod observer {
+    pub const READ_ONLY: &str = "OBSERVER_READ_ONLY";
+    pub const PIN_NOT_SET: &str = "OBSERVER_PIN_NOT_SET";
+    pub const INVALID_PIN: &str = "OBSERVER_INVALID_PIN";
+    pub const LOCKED_OUT: &str = "OBSERVER_LOCKED_OUT";
+}
+
+/// Profile and role errors
+pub mod role {
+    pub const FORBIDDEN: &str = "FORBIDDEN";
+    pub const PROFILE_NOT_FOUND: &str = "PROFILE_NOT_FOUND";
+    pub const INVALID_PIN: &str = "PROFILE_INVALID_PIN";
+    pub const PIN_NOT_SET: &str = "PROFILE_PIN_NOT_SET";
+}
+
+/// App lock errors
+pub mod lock {
+    pub const LOCKED: &str = "APP_LOCKED";
+    pub const NOT_CONFIGURED: &str = "APP_LOCK_NOT_CONFIGURED";
+    pub const INVALID_PIN: &str = "APP_LOCK_INVALID_PIN";
+    pub const LOCKED_OUT: &str = "APP_LOCK_LOCKED_OUT";
+    pub const PIN_MISSING: &str = "APP_LOCK_PIN_MISSING";
+}
+
+/// Audio playback errors
+pub mod audio {
+    pub const OUTPUT_UNAVAILABLE: &str = "AUDIO_OUTPUT_UNAVAILABLE";
+    pub const DECODE_FAILED: &str = "AUDIO_DECODE_FAILED";
+}
+
+/// Gradebook errors
+pub mod grades {
+    pub const INVALID_GRADE: &str = "INVALID_GRADE";
+    pub const STUDENT_NOT_FOUND: &str = "STUDENT_NOT_FOUND";
+    pub const SCORE_NOT_FOUND: &str = "SCORE_NOT_FOUND";
+    pub const TEMPLATE_NOT_FOUND: &str = "GRADE_TEMPLATE_NOT_FOUND";
+}
+
+/// Ambient light sampling errors
+pub mod ambient {
+    pub const SAMPLE_FAILED: &str = "AMBIENT_SAMPLE_FAILED";
+}
+
+/// Exam mode errors
+pub mod exam {
+    pub const ALREADY_ACTIVE: &str = "EXAM_ALREADY_ACTIVE";
+    pub const NOT_ACTIVE: &str = "EXAM_NOT_ACTIVE";
+    pub const SESSION_NOT_FOUND: &str = "EXAM_SESSION_NOT_FOUND";
+}
+
+/// Device inventory errors
+pub mod inventory {
+    pub const DEVICE_NOT_FOUND: &str = "INVENTORY_DEVICE_NOT_FOUND";
+    pub const DEVICE_EXISTS: &str = "INVENTORY_DEVICE_EXISTS";
+    pub const ISSUE_NOT_FOUND: &str = "INVENTORY_ISSUE_NOT_FOUND";
+}
+
+/// Student accommodation errors
+pub mod accommodation {
+    pub const NO_ELIGIBLE_STUDENT: &str = "NO_ELIGIBLE_STUDENT";
+    pub const VIOLATED: &str = "ACCOMMODATION_VIOLATED";
+}
+
+/// Quick note errors
+pub mod notes {
+    pub const NOT_FOUND: &str = "QUICK_NOTE_NOT_FOUND";
+}
+
+/// File import errors
+pub mod import {
+    /// A strict import found problems and recorded nothing
+    pub const STRICT_REJECTED: &str = "IMPORT_STRICT_REJECTED";
+}
+
+/// Conference queue errors
+pub mod appointment {
+    pub const NOT_FOUND: &str = "APPOINTMENT_NOT_FOUND";
+}
+
+/// Text recognition errors
+pub mod ocr {
+    pub const ENGINE_UNAVAILABLE: &str = "OCR_ENGINE_UNAVAILABLE";
+    pub const FAILED: &str = "OCR_FAILED";
 }
 
 /// System errors
 pub mod system {
     pub const UNKNOWN_ERROR: &str = "UNKNOWN_ERROR";
     pub const INVALID_INPUT: &str = "INVALID_INPUT";
+    pub const READ_ONLY_MODE: &str = "READ_ONLY_MODE";
 }
 
 impl fmt::Display for BackendError {
@@ -0,0 +1,273 @@
+//! Throttling and coalescing of backend → frontend events
+//!
+//! High-frequency events (job progress, noise levels, timer ticks) can flood
+//! the webview on low-end classroom PCs. Each channel has a maximum rate:
+//! - An event is emitted at once when the channel is idle
+//! - Events arriving faster are coalesced: only the latest payload per key
+//!   is kept and sent when the interval has passed
+//! - Nothing is dropped for good: the last payload of a burst always
+//!   arrives, so final states (e.g. a completed job) are never lost
+//!
+//! Keys separate independent streams on one channel (one job id per key).
+//! Rates are set at runtime and persisted in the `event_rates` config key.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+const RATES_CONFIG_KEY: &str = "event_rates";
+
+/// Upper bound for a configured rate, in events per second
+pub const MAX_RATE: u32 = 120;
+
+/// Default maximum rates, in events per second
+const DEFAULT_RATES: &[(&str, u32)] = &[
+    ("job-progress", 10),
+    ("job-stage", 10),
+    ("noise-level", 20),
+    ("timer-tick", 4),
+    ("sync-progress", 5),
+];
+
+static THROTTLER: OnceLock<Mutex<Throttler>> = OnceLock::new();
+
+/// What to do with an offered event
+#[derive(Debug, PartialEq)]
+enum Decision {
+    EmitNow,
+    /// Held back; a flush must run at the given instant
+    Deferred(Instant),
+    /// Held back; a flush is already scheduled
+    Coalesced,
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    last_emit: Option<Instant>,
+    pending: BTreeMap<String, Value>,
+    flush_at: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Throttler {
+    /// Minimum interval per channel; missing means unthrottled
+    intervals: HashMap<String, Duration>,
+    channels: HashMap<String, ChannelState>,
+}
+
+fn interval_for(rate: u32) -> Option<Duration> {
+    (rate > 0).then(|| Duration::from_secs(1) / rate)
+}
+
+impl Throttler {
+    fn with_rates(rates: &BTreeMap<String, u32>) -> Self {
+        let mut throttler = Self::default();
+        for (channel, &rate) in rates {
+            throttler.set_rate(channel, rate);
+        }
+        throttler
+    }
+
+    fn set_rate(&mut self, channel: &str, rate: u32) {
+        match interval_for(rate) {
+            Some(interval) => {
+                self.intervals.insert(channel.to_string(), interval);
+            }
+            None => {
+                self.intervals.remove(channel);
+            }
+        }
+    }
+
+    fn offer(&mut self, channel: &str, key: &str, payload: Value, now: Instant) -> Decision {
+        let Some(&interval) = self.intervals.get(channel) else {
+            return Decision::EmitNow;
+        };
+        let state = self.channels.entry(channel.to_string()).or_default();
+        let next_allowed = state.last_emit.map(|t| t + interval);
+
+        if next_allowed.is_none_or(|t| now >= t) && state.flush_at.is_none() {
+            state.last_emit = Some(now);
+            // A newer event supersedes anything still held for this key
+            state.pending.remove(key);
+            return Decision::EmitNow;
+        }
+
+        state.pending.insert(key.to_string(), payload);
+        if state.flush_at.is_some() {
+            return Decision::Coalesced;
+        }
+        let flush_at = next_allowed.unwrap_or(now);
+        state.flush_at = Some(flush_at);
+        Decision::Deferred(flush_at)
+    }
+
+    /// Take the held events of a channel once its flush is due
+    fn take_pending(&mut self, channel: &str, now: Instant) -> Vec<Value> {
+        let Some(state) = self.channels.get_mut(channel) else {
+            return Vec::new();
+        };
+        state.flush_at = None;
+        if state.pending.is_empty() {
+            return Vec::new();
+        }
+        state.last_emit = Some(now);
+        std::mem::take(&mut state.pending).into_values().collect()
+    }
+}
+
+fn throttler() -> std::sync::MutexGuard<'static, Throttler> {
+    THROTTLER
+        .get_or_init(|| Mutex::new(Throttler::with_rates(&get_rates())))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Configured maximum rates per channel (events per second, 0 = unlimited)
+pub fn get_rates() -> BTreeMap<String, u32> {
+    let mut rates: BTreeMap<String, u32> = DEFAULT_RATES
+        .iter()
+        .map(|&(channel, rate)| (channel.to_string(), rate))
+        .collect();
+    let saved: BTreeMap<String, u32> = file_ops::load_config(RATES_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    rates.extend(saved);
+    rates
+}
+
+/// Change the maximum rate of a channel; applies immediately
+pub fn set_rate(channel: &str, max_per_second: u32) -> Result<BTreeMap<String, u32>, BackendError> {
+    if channel.is_empty() || max_per_second > MAX_RATE {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Rate must be between 0 and {} events per second", MAX_RATE),
+        )
+        .with_details(format!("{}: {}", channel, max_per_second)));
+    }
+    let mut saved: BTreeMap<String, u32> = file_ops::load_config(RATES_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    saved.insert(channel.to_string(), max_per_second);
+    file_ops::save_config(RATES_CONFIG_KEY, serde_json::json!(saved))?;
+
+    throttler().set_rate(channel, max_per_second);
+    Ok(get_rates())
+}
+
+/// Events currently held back, per channel (only channels with any)
+pub fn queue_depths() -> BTreeMap<String, usize> {
+    throttler()
+        .channels
+        .iter()
+        .filter(|(_, state)| !state.pending.is_empty())
+        .map(|(channel, state)| (channel.clone(), state.pending.len()))
+        .collect()
+}
+
+/// Emit `payload` on `channel`, throttled per the channel's rate
+///
+/// `key` identifies the stream within the channel whose events may replace
+/// each other (e.g. a job id); use "" when there is only one.
+pub fn emit<P: Serialize>(app: &AppHandle, channel: &str, key: &str, payload: &P) {
+    let Ok(value) = serde_json::to_value(payload) else {
+        return;
+    };
+    let decision = throttler().offer(channel, key, value.clone(), Instant::now());
+    match decision {
+        Decision::EmitNow => {
+            let _ = app.emit(channel, value);
+        }
+        Decision::Coalesced => {}
+        Decision::Deferred(flush_at) => {
+            let app = app.clone();
+            let channel = channel.to_string();
+            std::thread::spawn(move || {
+                std::thread::sleep(flush_at.saturating_duration_since(Instant::now()));
+                let held = throttler().take_pending(&channel, Instant::now());
+                for value in held {
+                    let _ = app.emit(&channel, value);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn throttler_at(rate: u32) -> Throttler {
+        let mut throttler = Throttler::default();
+        throttler.set_rate("noise-level", rate);
+        throttler
+    }
+
+    #[test]
+    fn test_events_within_interval_are_coalesced() {
+        let mut throttler = throttler_at(10);
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+
+        assert_eq!(
+            throttler.offer("noise-level", "", json!(1), t0),
+            Decision::EmitNow
+        );
+        assert_eq!(
+            throttler.offer("noise-level", "", json!(2), ms(20)),
+            Decision::Deferred(ms(100))
+        );
+        assert_eq!(
+            throttler.offer("noise-level", "", json!(3), ms(40)),
+            Decision::Coalesced
+        );
+        assert_eq!(
+            throttler.take_pending("noise-level", ms(100)),
+            vec![json!(3)]
+        );
+
+        // The flush counts as an emit for the next interval
+        assert!(matches!(
+            throttler.offer("noise-level", "", json!(4), ms(150)),
+            Decision::Deferred(_)
+        ));
+        assert_eq!(
+            throttler.offer("other", "", json!(5), ms(150)),
+            Decision::EmitNow
+        );
+    }
+
+    #[test]
+    fn test_keys_are_coalesced_separately() {
+        let mut throttler = throttler_at(10);
+        let t0 = Instant::now();
+        throttler.offer("noise-level", "a", json!("a1"), t0);
+        throttler.offer("noise-level", "a", json!("a2"), t0);
+        throttler.offer("noise-level", "b", json!("b1"), t0);
+        throttler.offer("noise-level", "a", json!("a3"), t0);
+        assert_eq!(
+            throttler.take_pending("noise-level", t0 + Duration::from_millis(100)),
+            vec![json!("a3"), json!("b1")]
+        );
+    }
+
+    #[test]
+    fn test_zero_rate_disables_throttling() {
+        let mut throttler = throttler_at(0);
+        let t0 = Instant::now();
+        for i in 0..5 {
+            assert_eq!(
+                throttler.offer("noise-level", "", json!(i), t0),
+                Decision::EmitNow
+            );
+        }
+    }
+}
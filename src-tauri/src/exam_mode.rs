@@ -0,0 +1,498 @@
+//! Exam mode with an integrity watchdog
+//!
+//! Handles:
+//! - Starting an exam: the main window goes fullscreen and a session opens
+//! - Watching the main window while the exam runs: losing focus (another
+//!   app, the taskbar, a minimize) and leaving fullscreen are logged,
+//!   announced as `exam-integrity-event` and, if the session asks for it,
+//!   fullscreen is put back
+//! - Ending the exam (the window returns to how it was) and exporting the
+//!   session's integrity log as CSV (`;`-delimited, like the grade export)
+//!
+//! The watchdog can't stop a student from switching away; it makes it
+//! visible. Sessions live in the `exam_sessions` data collection and are
+//! saved on every event, so a crash mid-exam keeps the log. A failed save
+//! is announced as `exam-log-error`, so the teacher knows the log on disk
+//! is behind.
+
+use crate::class_records;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Window, WindowEvent};
+
+/// Emitted for every logged event of the running exam
+pub const INTEGRITY_EVENT: &str = "exam-integrity-event";
+/// Emitted with the error when the log could not be saved
+pub const LOG_ERROR_EVENT: &str = "exam-log-error";
+const COLLECTION: &str = "exam_sessions";
+const WATCHED_WINDOW: &str = "main";
+/// Excel only reads `;`-delimited UTF-8 correctly with a BOM
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Sessions kept; the oldest are dropped
+pub const MAX_SESSIONS: usize = 100;
+
+static ACTIVE: Mutex<Option<Watchdog>> = Mutex::new(None);
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityEventKind {
+    Started,
+    FocusLost,
+    FocusRegained,
+    FullscreenExited,
+    FullscreenRestored,
+    Ended,
+}
+
+impl IntegrityEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntegrityEventKind::Started => "started",
+            IntegrityEventKind::FocusLost => "focusLost",
+            IntegrityEventKind::FocusRegained => "focusRegained",
+            IntegrityEventKind::FullscreenExited => "fullscreenExited",
+            IntegrityEventKind::FullscreenRestored => "fullscreenRestored",
+            IntegrityEventKind::Ended => "ended",
+        }
+    }
+}
+
+/// One entry of the integrity log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityEvent {
+    pub session_id: String,
+    pub at: u64,
+    pub kind: IntegrityEventKind,
+    /// For `focusRegained`: how long the window was in the background
+    #[serde(default)]
+    pub away_ms: Option<u64>,
+    /// For `fullscreenExited`: fullscreen was put back
+    #[serde(default)]
+    pub reasserted: bool,
+}
+
+/// An exam and its integrity log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamSession {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub class_id: Option<String>,
+    /// Put fullscreen back when it is left
+    pub reassert_fullscreen: bool,
+    pub started_at: u64,
+    /// `None` while the exam runs
+    #[serde(default)]
+    pub ended_at: Option<u64>,
+    pub events: Vec<IntegrityEvent>,
+}
+
+impl ExamSession {
+    /// Times the window went to the background
+    pub fn focus_losses(&self) -> usize {
+        self.count(IntegrityEventKind::FocusLost)
+    }
+
+    pub fn fullscreen_exits(&self) -> usize {
+        self.count(IntegrityEventKind::FullscreenExited)
+    }
+
+    fn count(&self, kind: IntegrityEventKind) -> usize {
+        self.events.iter().filter(|e| e.kind == kind).count()
+    }
+}
+
+/// Persisted sessions, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExamStore {
+    pub sessions: Vec<ExamSession>,
+}
+
+impl ExamStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Insert or replace a session, dropping the oldest beyond `MAX_SESSIONS`
+    fn put(&mut self, session: &ExamSession) {
+        match self.sessions.iter_mut().find(|s| s.id == session.id) {
+            Some(existing) => *existing = session.clone(),
+            None => self.sessions.push(session.clone()),
+        }
+        if self.sessions.len() > MAX_SESSIONS {
+            let excess = self.sessions.len() - MAX_SESSIONS;
+            self.sessions.drain(..excess);
+        }
+    }
+}
+
+/// What the window reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Observation {
+    Focus(bool),
+    Fullscreen(bool),
+}
+
+/// State of the running exam
+#[derive(Debug)]
+struct Watchdog {
+    session: ExamSession,
+    /// Fullscreen before the exam, restored at the end
+    was_fullscreen: bool,
+    focused: bool,
+    fullscreen: bool,
+    /// When focus was lost
+    away_since: Option<u64>,
+}
+
+impl Watchdog {
+    fn new(session: ExamSession, was_fullscreen: bool) -> Self {
+        Self {
+            session,
+            was_fullscreen,
+            focused: true,
+            fullscreen: true,
+            away_since: None,
+        }
+    }
+
+    fn event(&self, kind: IntegrityEventKind, now: u64) -> IntegrityEvent {
+        IntegrityEvent {
+            session_id: self.session.id.clone(),
+            at: now,
+            kind,
+            away_ms: None,
+            reasserted: false,
+        }
+    }
+
+    fn push(&mut self, event: IntegrityEvent) -> IntegrityEvent {
+        self.session.events.push(event.clone());
+        event
+    }
+
+    fn log(&mut self, kind: IntegrityEventKind, now: u64) -> IntegrityEvent {
+        self.push(self.event(kind, now))
+    }
+
+    /// Log a change; repeated reports of the same state are ignored
+    fn observe(&mut self, observation: Observation, now: u64) -> Option<IntegrityEvent> {
+        match observation {
+            Observation::Focus(focused) if focused != self.focused => {
+                self.focused = focused;
+                if focused {
+                    let away = self
+                        .away_since
+                        .take()
+                        .map(|since| now.saturating_sub(since));
+                    Some(self.push(IntegrityEvent {
+                        away_ms: away,
+                        ..self.event(IntegrityEventKind::FocusRegained, now)
+                    }))
+                } else {
+                    self.away_since = Some(now);
+                    Some(self.log(IntegrityEventKind::FocusLost, now))
+                }
+            }
+            Observation::Fullscreen(fullscreen) if fullscreen != self.fullscreen => {
+                self.fullscreen = fullscreen;
+                if fullscreen {
+                    Some(self.log(IntegrityEventKind::FullscreenRestored, now))
+                } else {
+                    Some(self.push(IntegrityEvent {
+                        reasserted: self.session.reassert_fullscreen,
+                        ..self.event(IntegrityEventKind::FullscreenExited, now)
+                    }))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, session: &ExamSession) {
+    let result = ExamStore::load().and_then(|mut store| {
+        store.put(session);
+        store.save()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save exam log: {}", e.message);
+        let _ = app.emit(LOG_ERROR_EVENT, &e);
+    }
+}
+
+fn not_active() -> BackendError {
+    BackendError::new(errors::exam::NOT_ACTIVE, "No exam is running")
+}
+
+fn window_error(message: &str, e: tauri::Error) -> BackendError {
+    BackendError::new(errors::window::INVALID_POSITION, message).with_details(e.to_string())
+}
+
+/// Start an exam: the main window goes fullscreen and is watched until
+/// `stop_exam_mode`
+pub fn start_exam_mode<R: Runtime>(
+    app: &AppHandle<R>,
+    title: &str,
+    class_id: Option<&str>,
+    reassert_fullscreen: bool,
+) -> Result<ExamSession, BackendError> {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    if active.is_some() {
+        return Err(BackendError::new(
+            errors::exam::ALREADY_ACTIVE,
+            "An exam is already running",
+        ));
+    }
+    let window = app
+        .get_webview_window(WATCHED_WINDOW)
+        .ok_or_else(|| BackendError::new(errors::window::NOT_FOUND, "Main window not found"))?;
+    let was_fullscreen = window.is_fullscreen().unwrap_or(false);
+    window
+        .set_fullscreen(true)
+        .map_err(|e| window_error("Failed to enter fullscreen", e))?;
+    let _ = window.set_focus();
+
+    let now = clock::now_millis();
+    let session = ExamSession {
+        id: format!("exam_{}", now),
+        title: title.trim().to_string(),
+        class_id: class_id.map(String::from),
+        reassert_fullscreen,
+        started_at: now,
+        ended_at: None,
+        events: Vec::new(),
+    };
+    let mut watchdog = Watchdog::new(session, was_fullscreen);
+    let event = watchdog.log(IntegrityEventKind::Started, now);
+    save(app, &watchdog.session);
+    let _ = app.emit(INTEGRITY_EVENT, &event);
+    let session = watchdog.session.clone();
+    *active = Some(watchdog);
+    Ok(session)
+}
+
+/// End the running exam; the main window returns to how it was
+pub fn stop_exam_mode<R: Runtime>(app: &AppHandle<R>) -> Result<ExamSession, BackendError> {
+    let mut watchdog = ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(not_active)?;
+    let now = clock::now_millis();
+    let event = watchdog.log(IntegrityEventKind::Ended, now);
+    watchdog.session.ended_at = Some(now);
+    save(app, &watchdog.session);
+    let _ = app.emit(INTEGRITY_EVENT, &event);
+    if !watchdog.was_fullscreen {
+        if let Some(window) = app.get_webview_window(WATCHED_WINDOW) {
+            let _ = window.set_fullscreen(false);
+        }
+    }
+    Ok(watchdog.session)
+}
+
+/// The running exam, if any
+pub fn get_exam_status() -> Option<ExamSession> {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|w| w.session.clone())
+}
+
+/// Whether an exam is running
+pub fn is_running() -> bool {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Past and running exams, newest first
+pub fn list_exam_sessions() -> Result<Vec<ExamSession>, BackendError> {
+    Ok(ExamStore::load()?.sessions.into_iter().rev().collect())
+}
+
+/// Feed the watchdog a window event (called for every window; only the
+/// main window during an exam counts)
+pub fn on_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) {
+    if window.label() != WATCHED_WINDOW {
+        return;
+    }
+    let observation = match event {
+        WindowEvent::Focused(focused) => Observation::Focus(*focused),
+        WindowEvent::Resized(_) => match window.is_fullscreen() {
+            Ok(fullscreen) => Observation::Fullscreen(fullscreen),
+            Err(_) => return,
+        },
+        _ => return,
+    };
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(watchdog) = active.as_mut() else {
+        return;
+    };
+    let Some(event) = watchdog.observe(observation, clock::now_millis()) else {
+        return;
+    };
+    save(window.app_handle(), &watchdog.session);
+    drop(active);
+
+    if event.reasserted {
+        let _ = window.set_fullscreen(true);
+    }
+    let _ = window.app_handle().emit(INTEGRITY_EVENT, &event);
+}
+
+fn local_time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map_or_else(String::new, |dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// The integrity log of a session as CSV
+fn to_csv(session: &ExamSession) -> Result<Vec<u8>, BackendError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .terminator(csv::Terminator::CRLF)
+        .flexible(true)
+        .from_writer(UTF8_BOM.as_bytes().to_vec());
+    let write_error = |e: csv::Error| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write exam log")
+            .with_details(e.to_string())
+    };
+    writer
+        .write_record([format!(
+            "# {} - {}",
+            session.title,
+            class_records::date_string(session.started_at)
+        )])
+        .map_err(write_error)?;
+    writer
+        .write_record(["time", "event", "away_seconds", "fullscreen_reasserted"])
+        .map_err(write_error)?;
+    for event in &session.events {
+        writer
+            .write_record([
+                local_time(event.at),
+                event.kind.as_str().to_string(),
+                event
+                    .away_ms
+                    .map_or_else(String::new, |ms| format!("{:.1}", ms as f64 / 1000.0)),
+                if event.reasserted { "yes" } else { "" }.to_string(),
+            ])
+            .map_err(write_error)?;
+    }
+    writer.into_inner().map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write exam log")
+            .with_details(e.to_string())
+    })
+}
+
+/// Write the integrity log of a session to a CSV file
+pub fn export_exam_log(session_id: &str, path: &str) -> Result<String, BackendError> {
+    let session = match get_exam_status().filter(|s| s.id == session_id) {
+        Some(session) => session,
+        None => ExamStore::load()?
+            .sessions
+            .into_iter()
+            .find(|s| s.id == session_id)
+            .ok_or_else(|| {
+                BackendError::new(errors::exam::SESSION_NOT_FOUND, "Exam not found")
+                    .with_details(session_id.to_string())
+            })?,
+    };
+    std::fs::write(path, to_csv(&session)?).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write exam log")
+            .with_details(e.to_string())
+    })?;
+    Ok(path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchdog(reassert: bool) -> Watchdog {
+        Watchdog::new(
+            ExamSession {
+                id: "exam_1".into(),
+                title: "Verifica, capitolo 3".into(),
+                class_id: None,
+                reassert_fullscreen: reassert,
+                started_at: 0,
+                ended_at: None,
+                events: Vec::new(),
+            },
+            false,
+        )
+    }
+
+    #[test]
+    fn test_watchdog_logs_changes() {
+        let mut dog = watchdog(true);
+        assert_eq!(dog.observe(Observation::Focus(true), 1), None);
+        assert_eq!(dog.observe(Observation::Fullscreen(true), 1), None);
+
+        let lost = dog.observe(Observation::Focus(false), 1_000).unwrap();
+        assert_eq!(lost.kind, IntegrityEventKind::FocusLost);
+        assert_eq!(dog.observe(Observation::Focus(false), 1_500), None);
+        let back = dog.observe(Observation::Focus(true), 4_000).unwrap();
+        assert_eq!(back.away_ms, Some(3_000));
+
+        let exited = dog.observe(Observation::Fullscreen(false), 5_000).unwrap();
+        assert!(exited.reasserted);
+        let restored = dog.observe(Observation::Fullscreen(true), 5_100).unwrap();
+        assert_eq!(restored.kind, IntegrityEventKind::FullscreenRestored);
+
+        assert_eq!(dog.session.events, [lost, back, exited, restored]);
+        assert_eq!(dog.session.focus_losses(), 1);
+        assert_eq!(dog.session.fullscreen_exits(), 1);
+
+        let mut dog = watchdog(false);
+        assert!(
+            !dog.observe(Observation::Fullscreen(false), 1)
+                .unwrap()
+                .reasserted
+        );
+    }
+
+    #[test]
+    fn test_log_csv() {
+        let mut dog = watchdog(false);
+        dog.observe(Observation::Focus(false), 1_000);
+        dog.observe(Observation::Focus(true), 2_500);
+        let csv = String::from_utf8(to_csv(&dog.session).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.trim_start_matches(UTF8_BOM).lines().collect();
+        assert!(lines[0].starts_with("# Verifica, capitolo 3 - "));
+        assert_eq!(lines[1], "time;event;away_seconds;fullscreen_reasserted");
+        assert!(lines[2].ends_with(";focusLost;;"));
+        assert!(lines[3].ends_with(";focusRegained;1.5;"));
+    }
+
+    #[test]
+    fn test_store_keeps_newest() {
+        let mut store = ExamStore::default();
+        let mut session = watchdog(false).session;
+        for i in 0..MAX_SESSIONS + 2 {
+            session.id = format!("exam_{}", i);
+            store.put(&session);
+        }
+        session.title = "Updated".into();
+        store.put(&session);
+        assert_eq!(store.sessions.len(), MAX_SESSIONS);
+        assert_eq!(store.sessions[0].id, "exam_2");
+        assert_eq!(store.sessions.last().unwrap().title, "Updated");
+    }
+}
@@ -0,0 +1,370 @@
+//! Exit ticket collection for Classroom Management App
+//!
+//! Handles:
+//! - Opening/closing an exit ticket session with a prompt
+//! - Collecting short free-text responses from companion devices
+//! - Profanity filtering (configurable via the `exit_ticket_filter` setting)
+//! - Persisting sessions per lesson for later review
+//!
+//! Sessions are stored in the `exit_tickets` data collection, keyed by lesson id.
+
+use crate::clock;
+use crate::companion_auth;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+
+const STORE_COLLECTION: &str = "exit_tickets";
+const FILTER_CONFIG_KEY: &str = "exit_ticket_filter";
+
+/// Maximum length (in characters) of a single response
+pub const MAX_RESPONSE_CHARS: usize = 500;
+
+/// Words masked by default when filtering is enabled (Italian + English)
+const DEFAULT_BLOCKED_WORDS: &[&str] = &[
+    "cazzo",
+    "merda",
+    "stronzo",
+    "stronza",
+    "vaffanculo",
+    "minchia",
+    "coglione",
+    "troia",
+    "fuck",
+    "shit",
+    "bitch",
+    "asshole",
+    "bastard",
+];
+
+/// A single response submitted by a companion device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitTicketResponse {
+    pub device_id: String,
+    pub text: String,
+    pub submitted_at: u64,
+    /// True if the profanity filter masked part of the text
+    pub filtered: bool,
+}
+
+/// An exit ticket session attached to a lesson
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitTicketSession {
+    pub id: String,
+    pub lesson_id: String,
+    pub prompt: String,
+    pub opened_at: u64,
+    pub closed_at: Option<u64>,
+    pub responses: Vec<ExitTicketResponse>,
+}
+
+impl ExitTicketSession {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+}
+
+/// Profanity filter settings (stored under the `exit_ticket_filter` config key)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfanityFilter {
+    pub enabled: bool,
+    /// Extra words to mask in addition to the built-in list
+    pub extra_words: Vec<String>,
+}
+
+impl Default for ProfanityFilter {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            extra_words: Vec::new(),
+        }
+    }
+}
+
+impl ProfanityFilter {
+    /// Load filter settings, falling back to defaults if unset or malformed
+    pub fn load() -> Self {
+        file_ops::load_config(FILTER_CONFIG_KEY)
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Mask blocked words with asterisks
+    ///
+    /// Matching is case-insensitive on whole words, so "Scunthorpe"-style
+    /// false positives inside longer words are avoided.
+    ///
+    /// # Returns
+    /// The (possibly) masked text and whether anything was masked
+    pub fn apply(&self, text: &str) -> (String, bool) {
+        if !self.enabled {
+            return (text.to_string(), false);
+        }
+
+        let mut output = String::with_capacity(text.len());
+        let mut word = String::new();
+        let mut filtered = false;
+
+        for c in text.chars().chain(std::iter::once('\0')) {
+            if c.is_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                if self.is_blocked(&word) {
+                    output.extend(std::iter::repeat_n('*', word.chars().count()));
+                    filtered = true;
+                } else {
+                    output.push_str(&word);
+                }
+                word.clear();
+            }
+            if c != '\0' {
+                output.push(c);
+            }
+        }
+
+        (output, filtered)
+    }
+
+    fn is_blocked(&self, word: &str) -> bool {
+        let lower = word.to_lowercase();
+        DEFAULT_BLOCKED_WORDS.contains(&lower.as_str())
+            || self.extra_words.iter().any(|w| w.to_lowercase() == lower)
+    }
+}
+
+/// Persisted exit ticket sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExitTicketStore {
+    pub sessions: Vec<ExitTicketSession>,
+}
+
+impl ExitTicketStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STORE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STORE_COLLECTION, self)
+    }
+
+    fn open_session_mut(&mut self) -> Option<&mut ExitTicketSession> {
+        self.sessions.iter_mut().find(|s| s.is_open())
+    }
+
+    /// Open a new session; only one session may be open at a time
+    pub fn start(
+        &mut self,
+        prompt: &str,
+        lesson_id: &str,
+        now: u64,
+    ) -> Result<ExitTicketSession, BackendError> {
+        if self.open_session_mut().is_some() {
+            return Err(BackendError::new(
+                errors::exit_ticket::ALREADY_OPEN,
+                "An exit ticket is already open",
+            ));
+        }
+
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            return Err(BackendError::new(
+                errors::system::INVALID_INPUT,
+                "Exit ticket prompt cannot be empty",
+            ));
+        }
+
+        let session = ExitTicketSession {
+            id: format!("exit_{}", now),
+            lesson_id: lesson_id.to_string(),
+            prompt: prompt.to_string(),
+            opened_at: now,
+            closed_at: None,
+            responses: Vec::new(),
+        };
+        self.sessions.push(session.clone());
+        Ok(session)
+    }
+
+    /// Record a response in the open session
+    ///
+    /// A device that answers twice replaces its previous answer.
+    pub fn submit(
+        &mut self,
+        device_id: &str,
+        text: &str,
+        filter: &ProfanityFilter,
+        now: u64,
+    ) -> Result<ExitTicketResponse, BackendError> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_RESPONSE_CHARS {
+            return Err(BackendError::new(
+                errors::exit_ticket::INVALID_RESPONSE,
+                format!("Response must be 1-{} characters", MAX_RESPONSE_CHARS),
+            ));
+        }
+
+        let session = self.open_session_mut().ok_or_else(|| {
+            BackendError::new(
+                errors::exit_ticket::NO_ACTIVE_SESSION,
+                "No exit ticket is currently open",
+            )
+        })?;
+
+        let (text, filtered) = filter.apply(text);
+        let response = ExitTicketResponse {
+            device_id: device_id.to_string(),
+            text,
+            submitted_at: now,
+            filtered,
+        };
+
+        session.responses.retain(|r| r.device_id != device_id);
+        session.responses.push(response.clone());
+        Ok(response)
+    }
+
+    /// Close the open session
+    pub fn close(&mut self, now: u64) -> Result<ExitTicketSession, BackendError> {
+        let session = self.open_session_mut().ok_or_else(|| {
+            BackendError::new(
+                errors::exit_ticket::NO_ACTIVE_SESSION,
+                "No exit ticket is currently open",
+            )
+        })?;
+        session.closed_at = Some(now);
+        Ok(session.clone())
+    }
+
+    /// All sessions recorded for a lesson, oldest first
+    pub fn for_lesson(&self, lesson_id: &str) -> Vec<ExitTicketSession> {
+        self.sessions
+            .iter()
+            .filter(|s| s.lesson_id == lesson_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Open an exit ticket for a lesson
+///
+/// If `lesson_id` is not provided, a new lesson id is derived from the
+/// current timestamp and returned in the session.
+pub fn start_exit_ticket(
+    prompt: &str,
+    lesson_id: Option<String>,
+) -> Result<ExitTicketSession, BackendError> {
+    let now = clock::now_millis();
+    let lesson_id = lesson_id.unwrap_or_else(|| format!("lesson_{}", now));
+
+    let mut store = ExitTicketStore::load()?;
+    let session = store.start(prompt, &lesson_id, now)?;
+    store.save()?;
+    Ok(session)
+}
+
+/// Record a response from an authenticated companion device
+pub fn submit_exit_ticket(token: &str, text: &str) -> Result<ExitTicketResponse, BackendError> {
+    let device = companion_auth::authenticate(token)?;
+    let mut store = ExitTicketStore::load()?;
+    let response = store.submit(
+        &device.id,
+        text,
+        &ProfanityFilter::load(),
+        clock::now_millis(),
+    )?;
+    store.save()?;
+    Ok(response)
+}
+
+/// Close the currently open exit ticket
+pub fn close_exit_ticket() -> Result<ExitTicketSession, BackendError> {
+    let mut store = ExitTicketStore::load()?;
+    let session = store.close(clock::now_millis())?;
+    store.save()?;
+    Ok(session)
+}
+
+/// Get all exit ticket sessions for a lesson
+pub fn get_exit_tickets(lesson_id: &str) -> Result<Vec<ExitTicketSession>, BackendError> {
+    Ok(ExitTicketStore::load()?.for_lesson(lesson_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_masks_whole_words_only() {
+        let filter = ProfanityFilter::default();
+        let (text, filtered) = filter.apply("Che MERDA di compito, ma la classe è ok");
+        assert!(filtered);
+        assert_eq!(text, "Che ***** di compito, ma la classe è ok");
+
+        // "shitake" contains a blocked word but is not one
+        let (text, filtered) = filter.apply("I like shitake mushrooms");
+        assert!(!filtered);
+        assert_eq!(text, "I like shitake mushrooms");
+    }
+
+    #[test]
+    fn test_filter_extra_words_and_disabled() {
+        let filter = ProfanityFilter {
+            enabled: true,
+            extra_words: vec!["Noioso".to_string()],
+        };
+        assert_eq!(filter.apply("troppo noioso!").0, "troppo ******!");
+
+        let disabled = ProfanityFilter {
+            enabled: false,
+            extra_words: Vec::new(),
+        };
+        assert_eq!(disabled.apply("merda").0, "merda");
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let mut store = ExitTicketStore::default();
+        let filter = ProfanityFilter::default();
+
+        assert!(store.submit("dev1", "hello", &filter, 1).is_err());
+
+        store
+            .start("Cosa hai imparato oggi?", "lesson_1", 10)
+            .unwrap();
+        assert!(store.start("Another", "lesson_1", 11).is_err());
+
+        store.submit("dev1", "Le frazioni", &filter, 12).unwrap();
+        store.submit("dev2", "Niente", &filter, 13).unwrap();
+        // Resubmission replaces the previous answer
+        store
+            .submit("dev1", "Le frazioni equivalenti", &filter, 14)
+            .unwrap();
+
+        let closed = store.close(20).unwrap();
+        assert_eq!(closed.responses.len(), 2);
+        assert_eq!(closed.closed_at, Some(20));
+        assert!(store.close(21).is_err());
+
+        let sessions = store.for_lesson("lesson_1");
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0]
+            .responses
+            .iter()
+            .any(|r| r.text == "Le frazioni equivalenti"));
+    }
+
+    #[test]
+    fn test_submit_rejects_oversized_response() {
+        let mut store = ExitTicketStore::default();
+        store.start("Prompt", "lesson_1", 1).unwrap();
+        let long = "a".repeat(MAX_RESPONSE_CHARS + 1);
+        let err = store
+            .submit("dev1", &long, &ProfanityFilter::default(), 2)
+            .unwrap_err();
+        assert_eq!(err.code, errors::exit_ticket::INVALID_RESPONSE);
+    }
+}
@@ -0,0 +1,204 @@
+//! In-app feedback
+//!
+//! Handles:
+//! - Posting feedback to the `feedback_endpoint` URL when configured
+//! - Saving a zip bundle to `feedback/` when offline or no endpoint is set,
+//!   so the teacher can attach it to an email
+//! - Attaching the sanitized diagnostics bundle when the user opts in
+
+use crate::clock;
+use crate::diagnostics::{self, DiagnosticsBundle};
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::time::Duration;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const ENDPOINT_KEY: &str = "feedback_endpoint";
+const FEEDBACK_DIR: &str = "feedback";
+
+pub const MAX_FEEDBACK_LEN: usize = 5_000;
+
+/// What is sent or saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackReport {
+    pub text: String,
+    pub created_at: u64,
+    pub app_version: String,
+    pub diagnostics: Option<DiagnosticsBundle>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackDelivery {
+    /// Posted to the feedback endpoint
+    Sent,
+    /// Saved as a zip bundle for emailing
+    Saved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackResult {
+    pub delivery: FeedbackDelivery,
+    /// Bundle path when saved
+    pub path: Option<String>,
+    /// Why the endpoint could not be used, when it was configured
+    pub send_error: Option<BackendError>,
+}
+
+fn endpoint() -> Option<String> {
+    file_ops::load_config(ENDPOINT_KEY)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .filter(|url| !url.is_empty())
+}
+
+fn to_json(report: &FeedbackReport) -> Result<String, BackendError> {
+    serde_json::to_string_pretty(report).map_err(|e| {
+        BackendError::new(
+            errors::system::UNKNOWN_ERROR,
+            "Failed to serialize feedback",
+        )
+        .with_details(e.to_string())
+    })
+}
+
+fn post(url: &str, report: &FeedbackReport) -> Result<(), BackendError> {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&to_json(report)?)
+        .map_err(|e| {
+            BackendError::new(errors::lan::NETWORK_ERROR, "Failed to send feedback")
+                .with_details(e.to_string())
+        })?;
+    Ok(())
+}
+
+/// Zip with the text as `feedback.txt` and everything as `report.json`
+fn bundle(report: &FeedbackReport) -> Result<Vec<u8>, BackendError> {
+    let archive_error = |e: &dyn ToString| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write feedback bundle")
+            .with_details(e.to_string())
+    };
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in [
+        ("feedback.txt", report.text.clone()),
+        ("report.json", to_json(report)?),
+    ] {
+        writer
+            .start_file(name, options)
+            .map_err(|e| archive_error(&e))?;
+        writer
+            .write_all(content.as_bytes())
+            .map_err(|e| archive_error(&e))?;
+    }
+    let cursor = writer.finish().map_err(|e| archive_error(&e))?;
+    Ok(cursor.into_inner())
+}
+
+fn save_bundle(report: &FeedbackReport) -> Result<String, BackendError> {
+    let dir = file_ops::get_config_dir()?.join(FEEDBACK_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "feedback-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, bundle(report)?)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Send feedback, or save it as a bundle when it cannot be sent
+pub fn submit_feedback(
+    text: &str,
+    include_diagnostics: bool,
+) -> Result<FeedbackResult, BackendError> {
+    let text = text.trim();
+    if text.is_empty() || text.chars().count() > MAX_FEEDBACK_LEN {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Feedback must be 1-{} characters", MAX_FEEDBACK_LEN),
+        ));
+    }
+    let report = FeedbackReport {
+        text: text.to_string(),
+        created_at: clock::now_millis(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        diagnostics: if include_diagnostics {
+            Some(diagnostics::collect()?)
+        } else {
+            None
+        },
+    };
+
+    let send_error = match endpoint() {
+        Some(url) => match post(&url, &report) {
+            Ok(()) => {
+                return Ok(FeedbackResult {
+                    delivery: FeedbackDelivery::Sent,
+                    path: None,
+                    send_error: None,
+                })
+            }
+            Err(e) => Some(e),
+        },
+        None => None,
+    };
+
+    Ok(FeedbackResult {
+        delivery: FeedbackDelivery::Saved,
+        path: Some(save_bundle(&report)?),
+        send_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_bundle_contains_text_and_report() {
+        let report = FeedbackReport {
+            text: "Il timer non suona".to_string(),
+            created_at: 1_000,
+            app_version: "0.1.0".to_string(),
+            diagnostics: None,
+        };
+        let bytes = bundle(&report).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let mut text = String::new();
+        archive
+            .by_name("feedback.txt")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "Il timer non suona");
+
+        let mut json = String::new();
+        archive
+            .by_name("report.json")
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        let parsed: FeedbackReport = serde_json::from_str(&json).unwrap();
+        assert!(parsed.diagnostics.is_none());
+    }
+
+    #[test]
+    fn test_rejects_empty_feedback() {
+        let err = submit_feedback("   ", false).unwrap_err();
+        assert_eq!(err.code, errors::system::INVALID_INPUT);
+    }
+}
@@ -4,15 +4,58 @@
 //! - CSV file parsing and validation
 //! - Configuration file persistence
 //! - Error handling with proper encoding detection
+//!
+//! Registry-specific roster formats live in `import_adapters` (column
+//! fix-ups sent by the frontend in `import_transforms`, recognised header
+//! names in `header_synonyms`); schemas for config
+//! values live in `config_schema`, the in-memory copy of the config file in
+//! `config_cache` (repaired by `config_repair` when damaged), that of the
+//! data collections in `data_cache`, the choice of directory in
+//! `data_location`.
+//!
+//! Config and collections are only read and written through this module
+//! (`load_config`, `load_data`, ...), from commands and background threads
+//! alike; `AppState` doesn't hold them.
 
 use crate::errors::{BackendError, self};
-use serde_json::{json, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+pub mod config_cache;
+pub mod config_repair;
+pub mod config_schema;
+pub mod data_cache;
+pub mod data_location;
+pub mod header_synonyms;
+pub mod import_adapters;
+pub mod import_progress;
+pub mod import_transforms;
+
+use config_cache::ConfigCache;
+use data_cache::DataCache;
+
+static CONFIG_CACHE: ConfigCache = ConfigCache::new();
+static DATA_CACHE: DataCache = DataCache::new();
+/// Set in read-only mode (see `read_only_mode`)
+static WRITES_BLOCKED: AtomicBool = AtomicBool::new(false);
 
 const CONFIG_DIR: &str = "classroom_config";
-const CONFIG_FILENAME: &str = "app_config.json";
+pub const CONFIG_FILENAME: &str = "app_config.json";
+pub const DATA_DIR: &str = "data";
+
+/// Next to the executable, switches to portable mode
+pub const PORTABLE_FLAG: &str = "portable.flag";
+/// Folder next to the executable holding everything in portable mode
+const PORTABLE_DIR: &str = "data";
+const PORTABLE_WEBVIEW_DIR: &str = "webview";
 
 /// Maximum allowed directory depth to prevent excessive path traversal
 const MAX_PATH_DEPTH: usize = 10;
@@ -92,11 +135,12 @@ fn validate_csv_path(path: &Path, allowed_base: &Path) -> Result<PathBuf, Backen
 /// * `path` - Path to CSV file (will be validated for security)
 ///
 /// # Returns
-/// * `Value` - Parsed CSV data as JSON
+/// * `CsvTable` - Parsed CSV data, serialized as
+///   `{ success, records, count }`
 ///
 /// # Security
 /// This function validates the path before reading to prevent path traversal attacks.
-pub fn read_csv(path: &str) -> Result<Value, BackendError> {
+pub fn read_csv(path: &str) -> Result<CsvTable, BackendError> {
     let path = Path::new(path);
 
     // Get allowed base directory (app data dir)
@@ -129,89 +173,253 @@ pub fn read_csv(path: &str) -> Result<Value, BackendError> {
 
     // Detect encoding and decode
     let content = detect_and_decode(&bytes)?;
+    CsvTable::parse(content)
+}
 
-    // Parse CSV (basic implementation - can be enhanced)
-    let records = parse_csv(&content)?;
-
-    Ok(json!({
-        "success": true,
-        "records": records,
-        "count": records.len(),
-    }))
+fn empty_csv_error() -> BackendError {
+    BackendError::new(errors::file::INVALID_FORMAT, "CSV file is empty or invalid")
 }
 
-/// Save configuration to app config file
+/// A parsed CSV file, ready to send
 ///
-/// Creates directory structure if needed
-pub fn save_config(key: &str, value: Value) -> Result<(), BackendError> {
-    let config_path = get_config_path()?;
+/// Serializes as `{ success, records: [[field, ...], ...], count }`. The
+/// records are written to JSON while they are parsed, each field going
+/// straight from the reused record buffer to the output, so the file is
+/// parsed once and no `String` or `Value` per field is built.
+#[derive(Debug, Clone)]
+pub struct CsvTable {
+    records: Box<RawValue>,
+    count: usize,
+}
 
-    // Create config directory if doesn't exist
-    if !config_path.parent().unwrap().exists() {
-        fs::create_dir_all(config_path.parent().unwrap()).map_err(|e| {
-            BackendError::new(errors::file::IO_ERROR, "Failed to create config directory")
+impl CsvTable {
+    /// Parse decoded CSV text
+    ///
+    /// Text the reader rejects or that holds no record fails with
+    /// `INVALID_FILE_FORMAT` here, before anything is sent.
+    pub fn parse(content: String) -> Result<Self, BackendError> {
+        let records = CsvRecords {
+            content: &content,
+            count: Cell::new(0),
+            failed: RefCell::new(None),
+        };
+        let json = serde_json::value::to_raw_value(&records);
+        if let Some(e) = records.failed.into_inner() {
+            return Err(e);
+        }
+        let records_json = json.map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize CSV")
                 .with_details(e.to_string())
         })?;
+        Ok(Self {
+            records: records_json,
+            count: records.count.get(),
+        })
     }
+}
 
-    // Load existing config or create new
-    let mut config = if config_path.exists() {
-        let content = fs::read_to_string(&config_path).map_err(|e| {
-            BackendError::new(errors::file::IO_ERROR, "Failed to read config file")
-                .with_details(e.to_string())
-        })?;
-        serde_json::from_str(&content)
-            .unwrap_or_else(|_| json!({}))
-    } else {
-        json!({})
-    };
-
-    // Update value
-    config[key] = value;
-
-    // Write back
-    let json_str = serde_json::to_string_pretty(&config).map_err(|e| {
-        BackendError::new(errors::file::IO_ERROR, "Failed to serialize config")
-            .with_details(e.to_string())
-    })?;
+impl Serialize for CsvTable {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("success", &true)?;
+        map.serialize_entry("records", &self.records)?;
+        map.serialize_entry("count", &self.count)?;
+        map.end()
+    }
+}
 
-    fs::write(&config_path, json_str).map_err(|e| {
-        BackendError::new(errors::file::IO_ERROR, "Failed to write config file")
-            .with_details(e.to_string())
-    })?;
+/// The records of CSV text as a JSON array of arrays
+///
+/// A parse error is kept in `failed` so `CsvTable::parse` can return it
+/// with its own code.
+struct CsvRecords<'a> {
+    content: &'a str,
+    count: Cell<usize>,
+    failed: RefCell<Option<BackendError>>,
+}
+
+impl Serialize for CsvRecords<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeSeq};
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut written = None;
+        let parsed = for_each_csv_record(self.content, |record| {
+            seq.serialize_element(&CsvFields(record)).map_err(|e| {
+                written = Some(e);
+                BackendError::new(errors::system::UNKNOWN_ERROR, "Serialization failed")
+            })
+        });
+        if let Some(e) = written {
+            return Err(e);
+        }
+        match parsed {
+            Ok(count) => self.count.set(count),
+            Err(e) => {
+                let message = e.message.clone();
+                *self.failed.borrow_mut() = Some(e);
+                return Err(S::Error::custom(message));
+            }
+        }
+        seq.end()
+    }
+}
+
+/// One record's fields, borrowed from the record buffer
+struct CsvFields<'a>(&'a csv::StringRecord);
 
+impl Serialize for CsvFields<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+/// Refuse saving config and data collections (read-only mode), so writes
+/// from background tasks or unlisted commands fail up front too
+pub(crate) fn block_writes(blocked: bool) {
+    WRITES_BLOCKED.store(blocked, Ordering::SeqCst);
+}
+
+fn check_writes_allowed() -> Result<(), BackendError> {
+    if WRITES_BLOCKED.load(Ordering::SeqCst) {
+        return Err(BackendError::new(
+            errors::system::READ_ONLY_MODE,
+            "The data folder is read-only; changes can't be saved",
+        ));
+    }
     Ok(())
 }
 
+/// Save configuration to app config file
+///
+/// Creates directory structure if needed. Values of keys with a schema
+/// (see `config_schema`) are validated first. Fails with `READ_ONLY_MODE`
+/// in read-only mode.
+pub fn save_config(key: &str, value: Value) -> Result<(), BackendError> {
+    CONFIG_CACHE.save(key, value)
+}
+
+/// Remove a key from the config file so its default applies again
+///
+/// Returns whether the key was present.
+pub fn remove_config(key: &str) -> Result<bool, BackendError> {
+    CONFIG_CACHE.delete(key)
+}
+
 /// Load configuration from app config file
+///
+/// Served from `config_cache`; the file is only re-read after it changed.
 pub fn load_config(key: &str) -> Result<Value, BackendError> {
-    let config_path = get_config_path()?;
+    CONFIG_CACHE.load(key)
+}
 
-    if !config_path.exists() {
-        return Ok(Value::Null);
-    }
+/// Load a data collection (exit tickets, rosters, ...) from the data directory
+///
+/// Collections live next to the config file as `data/<collection>.json`.
+/// A missing file yields `T::default()` so first use needs no setup.
+/// Served from `data_cache`; the file is only re-read after it changed.
+pub fn load_data<T: DeserializeOwned + Default>(collection: &str) -> Result<T, BackendError> {
+    DATA_CACHE.load(&get_data_path(collection)?)
+}
 
-    let content = fs::read_to_string(&config_path).map_err(|e| {
-        BackendError::new(errors::file::IO_ERROR, "Failed to read config file")
-            .with_details(e.to_string())
-    })?;
+/// Save a data collection to the data directory
+///
+/// Creates the data directory if needed. Fails with `READ_ONLY_MODE` in
+/// read-only mode.
+pub fn save_data<T: Serialize>(collection: &str, data: &T) -> Result<(), BackendError> {
+    check_writes_allowed()?;
+    DATA_CACHE.save(&get_data_path(collection)?, data)
+}
 
-    let config: Value = serde_json::from_str(&content)
-        .map_err(|e| {
-            BackendError::new(errors::file::INVALID_FORMAT, "Invalid config file format")
-                .with_details(e.to_string())
-        })?;
+/// Get the application config directory (parent of the config file)
+///
+/// The platform default unless moved with `data_location::set_data_directory`.
+pub fn get_config_dir() -> Result<PathBuf, BackendError> {
+    Ok(data_location::config_dir(&default_config_dir()?))
+}
+
+/// Run `f` with the app's directory moved to a fresh temporary one, for
+/// tests that go through the real stores; they run one at a time
+#[cfg(test)]
+pub(crate) fn with_temp_data_dir<T>(f: impl FnOnce(&Path) -> T) -> T {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = tempfile::TempDir::new().unwrap();
+    data_location::use_dir(Some(dir.path()));
+    let result = f(dir.path());
+    data_location::use_dir(None);
+    result
+}
+
+/// Get the file path of a data collection
+///
+/// Collection names are restricted to `[a-z0-9_]` so they can never
+/// escape the data directory.
+fn get_data_path(collection: &str) -> Result<PathBuf, BackendError> {
+    if collection.is_empty()
+        || !collection
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Invalid data collection name",
+        )
+        .with_details(collection.to_string()));
+    }
 
-    Ok(config.get(key).unwrap_or(&Value::Null).clone())
+    Ok(get_config_dir()?
+        .join(DATA_DIR)
+        .join(format!("{}.json", collection)))
 }
 
 /// Get the configuration file path
+fn get_config_path() -> Result<PathBuf, BackendError> {
+    Ok(get_config_dir()?.join(CONFIG_FILENAME))
+}
+
+/// Portable directory for an executable in `exe_dir`, if flagged
+fn portable_dir_in(exe_dir: &Path) -> Option<PathBuf> {
+    exe_dir
+        .join(PORTABLE_FLAG)
+        .is_file()
+        .then(|| exe_dir.join(PORTABLE_DIR))
+}
+
+/// `data/` next to the executable when `portable.flag` is there (run from a
+/// USB stick without installing); checked once per run
+pub fn portable_dir() -> Option<PathBuf> {
+    static PORTABLE: OnceLock<Option<PathBuf>> = OnceLock::new();
+    PORTABLE
+        .get_or_init(|| {
+            let exe = env::current_exe().ok()?;
+            portable_dir_in(exe.parent()?)
+        })
+        .clone()
+}
+
+/// Webview storage (local storage, cache) in portable mode, kept with the
+/// portable data instead of the PC's profile
+pub fn portable_webview_dir() -> Option<PathBuf> {
+    portable_dir().map(|dir| dir.join(PORTABLE_WEBVIEW_DIR))
+}
+
+/// Default application directory
 ///
 /// Uses platform-specific app data directories:
 /// - Windows: %APPDATA%/classroom_config/
 /// - macOS: ~/Library/Application Support/classroom_config/
 /// - Linux: ~/.config/classroom_config/ or $XDG_CONFIG_HOME
-fn get_config_path() -> Result<PathBuf, BackendError> {
+///
+/// In portable mode, `data/` next to the executable instead, webview
+/// storage included (see `window::prepare_webview_data_dir`). Keychain
+/// secrets (cloud passwords, PINs) still belong to each PC; the app lock
+/// stays off where its PIN is missing.
+fn default_config_dir() -> Result<PathBuf, BackendError> {
+    if let Some(dir) = portable_dir() {
+        return Ok(dir);
+    }
+
     // Tauri 2.x compatible path resolution
     // Falls back to standard OS app data directories
     #[cfg(target_os = "windows")]
@@ -258,11 +466,11 @@ fn get_config_path() -> Result<PathBuf, BackendError> {
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     let data_dir = env::temp_dir();
 
-    Ok(data_dir.join(CONFIG_DIR).join(CONFIG_FILENAME))
+    Ok(data_dir.join(CONFIG_DIR))
 }
 
 /// Detect encoding and decode bytes to String
-fn detect_and_decode(bytes: &[u8]) -> Result<String, BackendError> {
+pub(crate) fn detect_and_decode(bytes: &[u8]) -> Result<String, BackendError> {
     // Try UTF-8 first (most common)
     if let Ok(s) = std::str::from_utf8(bytes) {
         return Ok(s.to_string());
@@ -310,26 +518,60 @@ fn detect_and_decode(bytes: &[u8]) -> Result<String, BackendError> {
     Ok(decoded)
 }
 
-/// Parse CSV content into records
-fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, BackendError> {
-    let mut records = Vec::new();
+/// Call `visit` for every CSV record, in order
+///
+/// The record buffer is reused between rows, so no per-field `String` is
+/// allocated; fields can be read with `record.get(i)`/`iter()` or
+/// deserialized into borrowed structs (`record.deserialize::<Row<'_>>`).
+/// Quoted fields, `""` escapes and CRLF are handled; blank lines are
+/// skipped, rows may have different lengths and fields are trimmed.
+///
+/// Returns the number of records visited.
+pub fn for_each_csv_record<F>(content: &str, visit: F) -> Result<usize, BackendError>
+where
+    F: FnMut(&csv::StringRecord) -> Result<(), BackendError>,
+{
+    for_each_delimited_record(content, b',', visit)
+}
 
-    for line in content.lines() {
-        let record: Vec<String> = line
-            .split(',')
-            .map(|field| field.trim().to_string())
-            .collect();
-        records.push(record);
+/// `for_each_csv_record` for text split on `delimiter` (`;`, tab, ...)
+pub(crate) fn for_each_delimited_record<F>(
+    content: &str,
+    delimiter: u8,
+    mut visit: F,
+) -> Result<usize, BackendError>
+where
+    F: FnMut(&csv::StringRecord) -> Result<(), BackendError>,
+{
+    // `Trim::All` rewrites every record; trimming only the records that
+    // need it keeps clean files (the usual case) on the fast path
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+    let mut record = csv::StringRecord::new();
+    let mut count = 0;
+
+    while reader.read_record(&mut record).map_err(|e| {
+        BackendError::new(errors::file::INVALID_FORMAT, "Invalid CSV content")
+            .with_details(e.to_string())
+    })? {
+        if record
+            .iter()
+            .any(|f| f.starts_with(char::is_whitespace) || f.ends_with(char::is_whitespace))
+        {
+            record.trim();
+        }
+        visit(&record)?;
+        count += 1;
     }
 
-    if records.is_empty() {
-        return Err(BackendError::new(
-            errors::file::INVALID_FORMAT,
-            "CSV file is empty or invalid",
-        ));
+    if count == 0 {
+        return Err(empty_csv_error());
     }
 
-    Ok(records)
+    Ok(count)
 }
 
 // UTF-16 helper extensions
@@ -359,18 +601,83 @@ impl Utf16Decode for String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
     use std::fs;
     use std::io::Write;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_portable_dir_needs_flag() {
+        let exe_dir = TempDir::new().unwrap();
+        assert_eq!(portable_dir_in(exe_dir.path()), None);
+        fs::write(exe_dir.path().join(PORTABLE_FLAG), "").unwrap();
+        assert_eq!(
+            portable_dir_in(exe_dir.path()),
+            Some(exe_dir.path().join("data"))
+        );
+    }
+
+    fn csv_rows(content: &str) -> Result<Vec<Vec<String>>, BackendError> {
+        let mut rows = Vec::new();
+        for_each_csv_record(content, |record| {
+            rows.push(record.iter().map(str::to_string).collect());
+            Ok(())
+        })?;
+        Ok(rows)
+    }
+
     #[test]
     fn test_csv_parse() {
         let csv = "Name,Age,Grade\nAlice,25,A\nBob,23,B";
-        let records = parse_csv(csv).unwrap();
+        let records = csv_rows(csv).unwrap();
         assert_eq!(records.len(), 3);
         assert_eq!(records[0], vec!["Name", "Age", "Grade"]);
     }
 
+    #[test]
+    fn test_csv_quoted_fields_and_borrowed_rows() {
+        #[derive(serde::Deserialize)]
+        struct Row<'a> {
+            name: &'a str,
+            note: &'a str,
+        }
+
+        let csv = "name,note\r\n\"Rossi, Anna\",\"Dice \"\"ciao\"\"\"\r\n\r\nBianchi ,  \n";
+        assert_eq!(
+            csv_rows(csv).unwrap()[1],
+            vec!["Rossi, Anna", "Dice \"ciao\""]
+        );
+
+        let mut names = Vec::new();
+        let count = for_each_csv_record(csv, |record| {
+            let row: Row = record.deserialize(None).unwrap();
+            names.push(format!("{}:{}", row.name, row.note));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(names[2], "Bianchi:");
+    }
+
+    #[test]
+    fn test_csv_table_json() {
+        let csv = "nome,voto\n\"Rossi, Anna\",8\n Verdi ,\n";
+        let table = CsvTable::parse(csv.to_string()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&table).unwrap(),
+            json!({
+                "success": true,
+                "records": [["nome", "voto"], ["Rossi, Anna", "8"], ["Verdi", ""]],
+                "count": 3,
+            })
+        );
+        assert_eq!(
+            CsvTable::parse(String::new()).unwrap_err().code,
+            errors::file::INVALID_FORMAT
+        );
+        assert!(CsvTable::parse("\r\n\n".to_string()).is_err());
+    }
+
     #[test]
     fn test_encoding_utf8() {
         let bytes = "Hello, UTF-8!".as_bytes();
@@ -381,10 +688,17 @@ mod tests {
     #[test]
     fn test_csv_empty_error() {
         let csv = "";
-        let result = parse_csv(csv);
+        let result = csv_rows(csv);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_data_path_rejects_traversal() {
+        assert!(get_data_path("../app_config").is_err());
+        assert!(get_data_path("exit/tickets").is_err());
+        assert!(get_data_path("").is_err());
+    }
+
     // ============================================================================
     // CSV Path Validation Tests (Security)
     // ============================================================================
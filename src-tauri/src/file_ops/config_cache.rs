@@ -0,0 +1,218 @@
+//! In-memory cache of the config file
+//!
+//! `load_config` used to read and parse `app_config.json` on every call. The
+//! parsed document is now kept in memory and written through on save. The
+//! file's modification time and size are checked on each access, so writes
+//! from outside the cache (backup restore, factory reset, a user editing
+//! the file) are picked up. A file that no longer parses is repaired by
+//! `config_repair`.
+
+use crate::errors::{self, BackendError};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use super::{config_repair, config_schema};
+
+/// Identity of the file contents the cache was built from
+type Stamp = Option<(SystemTime, u64)>;
+
+struct Cached {
+    path: PathBuf,
+    stamp: Stamp,
+    doc: Value,
+}
+
+/// Parsed config document shared by all callers
+pub struct ConfigCache {
+    inner: Mutex<Option<Cached>>,
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+fn read_doc(path: &Path) -> Result<Value, BackendError> {
+    if !path.exists() {
+        return Ok(json!({}));
+    }
+    let bytes = fs::read(path).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to read config file")
+            .with_details(e.to_string())
+    })?;
+    let content = String::from_utf8_lossy(&bytes);
+    match serde_json::from_str::<Value>(&content) {
+        Ok(doc) if doc.is_object() => Ok(doc),
+        Ok(_) => config_repair::repair(path, &content, "Config is not a JSON object"),
+        Err(e) => config_repair::repair(path, &content, &e.to_string()),
+    }
+}
+
+impl ConfigCache {
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(None),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Cached>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cached document for `path`, re-read if the file changed on disk
+    fn current<'a>(
+        cache: &'a mut Option<Cached>,
+        path: &Path,
+    ) -> Result<&'a mut Cached, BackendError> {
+        let on_disk = stamp(path);
+        let fresh = cache
+            .as_ref()
+            .is_some_and(|c| c.path == path && c.stamp == on_disk);
+        if !fresh {
+            let doc = read_doc(path)?;
+            *cache = Some(Cached {
+                path: path.to_path_buf(),
+                // A repair rewrote the file
+                stamp: stamp(path),
+                doc,
+            });
+        }
+        Ok(cache.as_mut().expect("cache was just filled"))
+    }
+
+    /// Value of `key`, `Null` when unset
+    pub fn get(&self, path: &Path, key: &str) -> Result<Value, BackendError> {
+        let mut cache = self.lock();
+        let cached = Self::current(&mut cache, path)?;
+        Ok(cached.doc.get(key).cloned().unwrap_or(Value::Null))
+    }
+
+    /// Set `key` and write the file
+    ///
+    /// Refused while the file can't be read or repaired, so a damaged file
+    /// is never overwritten with only this key.
+    pub fn set(&self, path: &Path, key: &str, value: Value) -> Result<(), BackendError> {
+        let mut cache = self.lock();
+        let mut doc = Self::current(&mut cache, path)?.doc.clone();
+        doc[key] = value;
+        Self::write(&mut cache, path, doc)
+    }
+
+    /// Remove `key` and write the file; returns whether it was present
+    pub fn remove(&self, path: &Path, key: &str) -> Result<bool, BackendError> {
+        if !path.exists() {
+            return Ok(false);
+        }
+        let mut cache = self.lock();
+        let mut doc = Self::current(&mut cache, path)?.doc.clone();
+        let removed = doc
+            .as_object_mut()
+            .is_some_and(|map| map.remove(key).is_some());
+        if removed {
+            Self::write(&mut cache, path, doc)?;
+        }
+        Ok(removed)
+    }
+
+    /// Value of `key` in the app config file, `Null` when unset
+    pub fn load(&self, key: &str) -> Result<Value, BackendError> {
+        self.get(&super::get_config_path()?, key)
+    }
+
+    /// Validate `value` against the key's schema and save it to the app
+    /// config file; fails with `READ_ONLY_MODE` in read-only mode
+    pub fn save(&self, key: &str, value: Value) -> Result<(), BackendError> {
+        super::check_writes_allowed()?;
+        config_schema::validate(key, &value)?;
+        self.set(&super::get_config_path()?, key, value)
+    }
+
+    /// Remove `key` from the app config file; returns whether it was present
+    pub fn delete(&self, key: &str) -> Result<bool, BackendError> {
+        super::check_writes_allowed()?;
+        self.remove(&super::get_config_path()?, key)
+    }
+
+    /// Drop the cached document (next access re-reads the file)
+    pub fn invalidate(&self) {
+        *self.lock() = None;
+    }
+
+    fn write(cache: &mut Option<Cached>, path: &Path, doc: Value) -> Result<(), BackendError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                BackendError::new(errors::file::IO_ERROR, "Failed to create config directory")
+                    .with_details(e.to_string())
+            })?;
+        }
+        let json_str = serde_json::to_string_pretty(&doc).map_err(|e| {
+            BackendError::new(errors::file::IO_ERROR, "Failed to serialize config")
+                .with_details(e.to_string())
+        })?;
+        fs::write(path, json_str).map_err(|e| {
+            *cache = None;
+            BackendError::new(errors::file::IO_ERROR, "Failed to write config file")
+                .with_details(e.to_string())
+        })?;
+        *cache = Some(Cached {
+            path: path.to_path_buf(),
+            stamp: stamp(path),
+            doc,
+        });
+        Ok(())
+    }
+}
+
+impl Default for ConfigCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cache_writes_through_and_sees_external_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cfg").join("app_config.json");
+        let cache = ConfigCache::new();
+
+        assert_eq!(cache.get(&path, "theme").unwrap(), Value::Null);
+        cache.set(&path, "theme", json!("Energy")).unwrap();
+        cache.set(&path, "volume", json!(3)).unwrap();
+        assert_eq!(cache.get(&path, "theme").unwrap(), json!("Energy"));
+
+        let on_disk: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk["volume"], json!(3));
+
+        // Replaced outside the cache (e.g. backup restore)
+        fs::write(&path, r#"{"theme":"Calm","extra":true}"#).unwrap();
+        assert_eq!(cache.get(&path, "theme").unwrap(), json!("Calm"));
+
+        assert!(cache.remove(&path, "extra").unwrap());
+        assert!(!cache.remove(&path, "extra").unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cache.get(&path, "theme").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_damaged_file_that_cant_be_moved_aside_is_kept() {
+        let dir = TempDir::new().unwrap();
+        // Too long a name to take the `.corrupt-<timestamp>` suffix, so the
+        // repair can't move the file aside
+        let path = dir.path().join(format!("{}.json", "c".repeat(240)));
+        let damaged = "{\n  \"theme\": \"Calm\",\n  \"volume\": ";
+        fs::write(&path, damaged).unwrap();
+        let cache = ConfigCache::new();
+
+        assert!(cache.set(&path, "theme", json!("Energy")).is_err());
+        assert!(cache.remove(&path, "theme").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), damaged);
+    }
+}
@@ -0,0 +1,215 @@
+//! Recovery of a damaged config file
+//!
+//! A config file that no longer parses (power loss during a write, a bad
+//! hand edit) used to be replaced by an empty document on the next save,
+//! silently dropping every setting. Instead:
+//! - The damaged file is moved aside as `app_config.corrupt-<timestamp>.json`
+//! - Top-level settings that still parse and pass their schema are kept
+//! - What was kept and lost is reported as a `config-recovered` event and
+//!   kept for `last_recovery`, since the damage is usually found at startup
+//!   before the UI listens
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+use super::config_schema;
+
+/// Event emitted after a damaged config file was repaired
+pub const RECOVERED_EVENT: &str = "config-recovered";
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static LAST: Mutex<Option<ConfigRecovery>> = Mutex::new(None);
+
+/// What a repair kept and lost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRecovery {
+    pub recovered_at: u64,
+    /// Where the damaged file was moved
+    pub corrupt_file: String,
+    /// Why the file couldn't be read
+    pub error: String,
+    /// Settings kept
+    pub recovered: Vec<String>,
+    /// Settings found whose value was unreadable or invalid
+    pub lost: Vec<String>,
+}
+
+/// Emit `config-recovered` for repairs from now on
+pub fn start(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// The repair made during this run, if any
+pub fn last_recovery() -> Option<ConfigRecovery> {
+    LAST.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Parse one `"key": value` pair at the start of `text`; returns the key,
+/// the value (if it parses) and the length consumed
+fn entry(text: &str) -> Option<(String, Option<(Value, usize)>)> {
+    let mut keys = serde_json::Deserializer::from_str(text).into_iter::<String>();
+    let key = keys.next()?.ok()?;
+    let after_key = keys.byte_offset();
+    let rest = text[after_key..].trim_start();
+    let Some(value_text) = rest.strip_prefix(':') else {
+        return Some((key, None));
+    };
+    let value_start = text.len() - value_text.len();
+    let mut values = serde_json::Deserializer::from_str(value_text).into_iter::<Value>();
+    let value = match values.next() {
+        Some(Ok(value)) => Some((value, value_start + values.byte_offset())),
+        _ => None,
+    };
+    Some((key, value))
+}
+
+/// Start of the next top-level key after a damaged entry
+///
+/// The file is written pretty-printed, so top-level keys start a line
+/// indented by two spaces.
+fn resync(text: &str) -> Option<usize> {
+    text.get(1..)?.find("\n  \"").map(|i| i + 2)
+}
+
+/// Keep the top-level settings of a damaged document that still parse;
+/// returns them and the keys that were lost
+pub(crate) fn salvage(content: &str) -> (Map<String, Value>, Vec<String>) {
+    let mut kept = Map::new();
+    let mut lost = Vec::new();
+    let Some(start) = content.find('{') else {
+        return (kept, lost);
+    };
+    let mut rest = &content[start + 1..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() || rest.starts_with('}') {
+            break;
+        }
+        match entry(rest) {
+            Some((key, Some((value, consumed)))) => {
+                if config_schema::validate(&key, &value).is_ok() {
+                    kept.insert(key, value);
+                } else {
+                    lost.push(key);
+                }
+                rest = &rest[consumed..];
+            }
+            damaged => {
+                if let Some((key, None)) = damaged {
+                    lost.push(key);
+                }
+                match resync(rest) {
+                    Some(next) => rest = &rest[next..],
+                    None => break,
+                }
+            }
+        }
+    }
+    // A key repeated after resync keeps its readable value
+    lost.retain(|key| !kept.contains_key(key));
+    lost.dedup();
+    (kept, lost)
+}
+
+/// Move the damaged file at `path` aside and write what could be salvaged
+/// in its place; returns the repaired document
+pub(crate) fn repair(path: &Path, content: &str, error: &str) -> Result<Value, BackendError> {
+    let now = clock::now_millis();
+    let corrupt = path.with_extension(format!("corrupt-{}.json", now));
+    fs::rename(path, &corrupt).map_err(|e| {
+        BackendError::new(
+            errors::file::IO_ERROR,
+            "Failed to move damaged config aside",
+        )
+        .with_details(format!("{}: {}", path.display(), e))
+    })?;
+
+    let (kept, lost) = salvage(content);
+    let recovery = ConfigRecovery {
+        recovered_at: now,
+        corrupt_file: corrupt.to_string_lossy().to_string(),
+        error: error.to_string(),
+        recovered: kept.keys().cloned().collect(),
+        lost,
+    };
+    let doc = Value::Object(kept);
+    let json_str = serde_json::to_string_pretty(&doc).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    fs::write(path, json_str).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write config file")
+            .with_details(e.to_string())
+    })?;
+
+    eprintln!(
+        "Config file was damaged ({}); kept {} settings, moved it to {}",
+        error,
+        recovery.recovered.len(),
+        recovery.corrupt_file
+    );
+    if let Some(app) = APP.get() {
+        let _ = app.emit(RECOVERED_EVENT, &recovery);
+    }
+    *LAST.lock().unwrap_or_else(|e| e.into_inner()) = Some(recovery);
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_salvage_truncated_file() {
+        let full = serde_json::to_string_pretty(&json!({
+            "app_language": "it",
+            "theme": "Calm",
+            "window_config": "overlay"
+        }))
+        .unwrap();
+        // Power loss in the middle of the last value
+        let cut = &full[..full.find("overl").unwrap()];
+        let (kept, lost) = salvage(cut);
+        assert_eq!(kept.keys().collect::<Vec<_>>(), ["app_language", "theme"]);
+        assert_eq!(lost, ["window_config"]);
+
+        assert_eq!(salvage("\0\0\0\0"), (Map::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_salvage_skips_damaged_entry() {
+        let content = "{\n  \"theme\": \"Calm\",\n  \"lan_bind\": { \"port\": 80,, },\n  \
+            \"webview_zoom\": { \"main\": 9 },\n  \"volume\": 3\n}";
+        let (kept, lost) = salvage(content);
+        assert_eq!(kept["theme"], "Calm");
+        assert_eq!(kept["volume"], 3);
+        // Unparseable, and parseable but against its schema
+        assert_eq!(lost, ["lan_bind", "webview_zoom"]);
+    }
+
+    #[test]
+    fn test_repair_moves_file_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app_config.json");
+        let content = "{\n  \"theme\": \"Calm\",\n  \"volume\": ";
+        fs::write(&path, content).unwrap();
+
+        let doc = repair(&path, content, "EOF while parsing").unwrap();
+        assert_eq!(doc, json!({ "theme": "Calm" }));
+        let on_disk: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk, doc);
+
+        let recovery = last_recovery().unwrap();
+        assert_eq!(recovery.lost, ["volume"]);
+        assert_eq!(fs::read_to_string(&recovery.corrupt_file).unwrap(), content);
+        assert!(recovery.corrupt_file.contains("app_config.corrupt-"));
+    }
+}
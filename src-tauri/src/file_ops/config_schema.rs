@@ -0,0 +1,472 @@
+//! JSON schemas for config keys
+//!
+//! `save_config` validates values for the keys listed here, so a bad write
+//! (e.g. `window_config` set to a number) is rejected instead of breaking
+//! startup. Keys without a schema are stored as-is; frontend-only settings
+//! stay free-form.
+
+use crate::errors::{self, BackendError};
+use jsonschema::Validator;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Schema for a config key, if it has one
+fn schema_for(key: &str) -> Option<Value> {
+    let schema = match key {
+        "agc" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "targetLevel": { "type": "number", "minimum": 0, "maximum": 100 }
+            }
+        }),
+        "sound_unit" => json!({ "type": "string", "enum": ["percent", "dbfs", "dbspl"] }),
+        "badge_reader" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "prefix": { "type": "string", "maxLength": 4 },
+                "suffix": { "type": "string", "minLength": 1, "maxLength": 4 },
+                "minLength": { "type": "integer", "minimum": 1, "maximum": 64 },
+                "maxKeyIntervalMs": { "type": "integer", "minimum": 10, "maximum": 200 },
+                "classId": { "type": ["string", "null"] },
+                "allowWithoutPrefix": { "type": "boolean" }
+            }
+        }),
+        "ocr" => json!({
+            "type": "object",
+            "properties": {
+                "tesseractPath": { "type": ["string", "null"], "minLength": 1 },
+                "languages": {
+                    "type": ["string", "null"],
+                    "pattern": "^[A-Za-z_]+(\\+[A-Za-z_]+)*$"
+                }
+            }
+        }),
+        "monitoring_quiet_hours" => json!({
+            "type": "object",
+            "properties": {
+                "rules": {
+                    "type": "array",
+                    "maxItems": 50,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "kind": {
+                                "enum": ["breaks", "period", "outsideSchool", "exams", "window"]
+                            },
+                            "label": { "type": "string", "minLength": 1 },
+                            "marginMinutes": { "type": "integer", "minimum": 0, "maximum": 240 },
+                            "start": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                            "end": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                            "weekdays": {
+                                "type": "array",
+                                "items": { "type": "integer", "minimum": 1, "maximum": 7 }
+                            }
+                        },
+                        "required": ["kind"]
+                    }
+                }
+            }
+        }),
+        "volume_safety" => json!({
+            "type": "object",
+            "properties": {
+                "maxDb": { "type": "number", "minimum": -40, "maximum": 0 },
+                "rampMs": { "type": "integer", "minimum": 0, "maximum": 5000 }
+            }
+        }),
+        "content_protection" => json!({
+            "type": "array",
+            "items": { "type": "string", "minLength": 1 },
+            "uniqueItems": true
+        }),
+        "presentation_safe" => json!({
+            "type": "object",
+            "properties": {
+                "shortcut": { "type": ["string", "null"], "minLength": 1 }
+            }
+        }),
+        "window_config" => json!({
+            "type": "string",
+            "enum": ["normal", "overlay", "fullscreen"]
+        }),
+        "webview_zoom" => json!({
+            "type": "object",
+            "additionalProperties": { "type": "number", "minimum": 0.5, "maximum": 3.0 }
+        }),
+        "event_rates" => json!({
+            "type": "object",
+            "additionalProperties": { "type": "integer", "minimum": 0, "maximum": 120 }
+        }),
+        "feedback_endpoint" => json!({ "type": "string", "pattern": "^https://" }),
+        "exit_ticket_filter" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "extra_words": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["enabled"]
+        }),
+        "audio_outputs" => json!({
+            "type": "object",
+            "properties": {
+                "alerts": { "type": ["string", "null"] },
+                "music": { "type": ["string", "null"] }
+            },
+            "additionalProperties": false
+        }),
+        "audio_restart_policy" => json!({
+            "type": "object",
+            "properties": {
+                "maxRestarts": { "type": "integer", "minimum": 0, "maximum": 100 },
+                "initialBackoffMs": { "type": "integer", "minimum": 0 },
+                "maxBackoffMs": { "type": "integer", "minimum": 0, "maximum": 600000 }
+            },
+            "required": ["maxRestarts", "initialBackoffMs", "maxBackoffMs"]
+        }),
+        "analytics_consent" => json!({ "type": "boolean" }),
+        "analytics_endpoint" => json!({ "type": "string", "pattern": "^https://" }),
+        "app_language" => json!({ "type": "string", "enum": ["it", "en"] }),
+        "app_lock" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "timeoutMinutes": { "type": "integer", "minimum": 1, "maximum": 240 },
+                "recoveryHash": { "type": "string" }
+            }
+        }),
+        "command_trace" => json!({ "type": "boolean" }),
+        "cloud_target" => json!({ "type": "string", "enum": ["webdav", "s3"] }),
+        "cloud_webdav" => json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "pattern": "^https?://" },
+                "username": { "type": "string" }
+            },
+            "required": ["url", "username"]
+        }),
+        "cloud_s3" => json!({
+            "type": "object",
+            "properties": {
+                "endpoint": { "type": "string", "pattern": "^https?://" },
+                "bucket": { "type": "string", "minLength": 3, "maxLength": 63 },
+                "region": { "type": "string", "minLength": 1 },
+                "access_key_id": { "type": "string", "minLength": 1 }
+            },
+            "required": ["endpoint", "bucket", "region", "access_key_id"]
+        }),
+        "lan_bind" => json!({
+            "type": "object",
+            "properties": {
+                "interface": { "type": ["string", "null"] },
+                "port": { "type": "integer", "minimum": 1024, "maximum": 65535 }
+            }
+        }),
+        "lan_tls_enabled" => json!({ "type": "boolean" }),
+        "bell_schedule" => json!({
+            "type": "object",
+            "properties": {
+                "periods": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "label": { "type": "string" },
+                            "kind": { "enum": ["lesson", "break"] },
+                            "start": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                            "end": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                            "weekdays": {
+                                "type": "array",
+                                "items": { "type": "integer", "minimum": 1, "maximum": 7 }
+                            }
+                        },
+                        "required": ["label", "kind", "start", "end", "weekdays"]
+                    }
+                }
+            }
+        }),
+        "classroom_state_rules" => json!({
+            "type": "object",
+            "properties": {
+                "yellowAbove": { "type": "number" },
+                "redAbove": { "type": "number" },
+                "hysteresisDb": { "type": "number", "minimum": 0 },
+                "escalateAfterMs": { "type": "integer", "minimum": 0 },
+                "calmAfterMs": { "type": "integer", "minimum": 0 }
+            }
+        }),
+        "noise_calibration" => json!({
+            "type": "object",
+            "properties": {
+                "offsetDb": { "type": "number", "minimum": -60, "maximum": 60 },
+                "gain": { "type": "number", "exclusiveMinimum": 0, "maximum": 10 },
+                "calibratedAt": { "type": "integer", "minimum": 0 }
+            }
+        }),
+        "keyboard_layout" => json!({ "type": "string", "enum": ["us", "it"] }),
+        "presenter_bindings" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "bindings": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "shortcut": { "type": "string", "minLength": 1 },
+                            "action": { "type": "string", "minLength": 1 }
+                        },
+                        "required": ["shortcut", "action"]
+                    }
+                }
+            }
+        }),
+        "controller_listener" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "port": { "type": "integer", "minimum": 1024, "maximum": 65535 }
+            }
+        }),
+        "mailer_smtp" => json!({
+            "type": "object",
+            "properties": {
+                "host": { "type": "string", "minLength": 1 },
+                "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+                "security": { "enum": ["starttls", "tls"] },
+                "username": { "type": "string" },
+                "from": { "type": "string", "minLength": 3 }
+            },
+            "required": ["host", "port", "security", "username", "from"]
+        }),
+        "roster_watch_folder" => json!({ "type": ["string", "null"] }),
+        "timer_announcements" => json!({
+            "type": "object",
+            "additionalProperties": {
+                "type": "array",
+                "maxItems": 10,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "remainingSecs": { "type": "integer", "minimum": 0, "maximum": 14400 },
+                        "phrases": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string", "minLength": 1 }
+                        }
+                    },
+                    "required": ["remainingSecs"]
+                }
+            }
+        }),
+        "insight_thresholds" => json!({
+            "type": "object",
+            "properties": {
+                "absenceStreak": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "weeks": { "type": "integer", "minimum": 2, "maximum": 10 }
+                    }
+                },
+                "risingNoise": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "days": { "type": "integer", "minimum": 5, "maximum": 60 },
+                        "minRise": { "type": "number", "minimum": 0.5, "maximum": 60 }
+                    }
+                }
+            }
+        }),
+        "import_header_synonyms" => {
+            let names = json!({
+                "type": "array",
+                "items": { "type": "string", "minLength": 1, "maxLength": 100 },
+                "maxItems": 100
+            });
+            json!({
+                "type": "object",
+                "properties": {
+                    "fullName": names,
+                    "surname": names,
+                    "givenName": names,
+                    "email": names,
+                    "score": names
+                },
+                "additionalProperties": false
+            })
+        }
+        "end_of_day" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "time": { "type": "string", "pattern": "^([01][0-9]|2[0-3]):[0-5][0-9]$" },
+                "actions": {
+                    "type": "array",
+                    "items": {
+                        "enum": [
+                            "stopMonitoring",
+                            "backup",
+                            "cloudSync",
+                            "dailySummary",
+                            "lock",
+                            "quit"
+                        ]
+                    },
+                    "uniqueItems": true
+                }
+            },
+            "required": ["enabled", "time", "actions"]
+        }),
+        "weekly_summary" => json!({
+            "type": "object",
+            "properties": {
+                "enabled": { "type": "boolean" },
+                "time": { "type": "string", "pattern": "^([01][0-9]|2[0-3]):[0-5][0-9]$" },
+                "delivery": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "properties": {
+                                "type": { "const": "folder" },
+                                "path": { "type": "string" }
+                            },
+                            "required": ["type", "path"]
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "type": { "const": "email" },
+                                "to": {
+                                    "type": "array",
+                                    "items": { "type": "string" },
+                                    "minItems": 1
+                                }
+                            },
+                            "required": ["type", "to"]
+                        }
+                    ]
+                }
+            },
+            "required": ["enabled", "time", "delivery"]
+        }),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// Compiled validators, built on first use
+fn validator(key: &str) -> Option<&'static Validator> {
+    static VALIDATORS: OnceLock<HashMap<&'static str, Validator>> = OnceLock::new();
+    VALIDATORS
+        .get_or_init(|| {
+            SCHEMA_KEYS
+                .iter()
+                .filter_map(|&key| {
+                    let schema = schema_for(key)?;
+                    // Schemas are static; a broken one is a programming error
+                    let validator = jsonschema::validator_for(&schema)
+                        .unwrap_or_else(|e| panic!("invalid schema for {}: {}", key, e));
+                    Some((key, validator))
+                })
+                .collect()
+        })
+        .get(key)
+}
+
+/// Keys with a schema
+pub const SCHEMA_KEYS: &[&str] = &[
+    "analytics_consent",
+    "analytics_endpoint",
+    "window_config",
+    "webview_zoom",
+    "volume_safety",
+    "app_language",
+    "app_lock",
+    "audio_outputs",
+    "audio_restart_policy",
+    "bell_schedule",
+    "event_rates",
+    "exit_ticket_filter",
+    "feedback_endpoint",
+    "classroom_state_rules",
+    "insight_thresholds",
+    "noise_calibration",
+    "agc",
+    "sound_unit",
+    "monitoring_quiet_hours",
+    "ocr",
+    "badge_reader",
+    "cloud_target",
+    "cloud_webdav",
+    "cloud_s3",
+    "command_trace",
+    "content_protection",
+    "controller_listener",
+    "end_of_day",
+    "import_header_synonyms",
+    "keyboard_layout",
+    "lan_bind",
+    "lan_tls_enabled",
+    "mailer_smtp",
+    "presentation_safe",
+    "presenter_bindings",
+    "roster_watch_folder",
+    "timer_announcements",
+    "weekly_summary",
+];
+
+/// Check a value against the schema of its key
+///
+/// Fails with `CONFIG_VALIDATION_FAILED`; details carry the JSON path of
+/// the offending value (`/position/width: ...`).
+pub fn validate(key: &str, value: &Value) -> Result<(), BackendError> {
+    let Some(validator) = validator(key) else {
+        return Ok(());
+    };
+    validator.validate(value).map_err(|e| {
+        let path = e.instance_path.to_string();
+        let path = if path.is_empty() { "/" } else { path.as_str() };
+        BackendError::new(
+            errors::config::VALIDATION_FAILED,
+            format!("Invalid value for setting \"{}\"", key),
+        )
+        .with_details(format!("{}: {}", path, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_schemas_compile() {
+        for key in SCHEMA_KEYS {
+            assert!(validator(key).is_some(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_path() {
+        assert!(validate("window_config", &json!("overlay")).is_ok());
+        assert!(validate("webview_zoom", &json!({ "annotation": 1.5 })).is_ok());
+        assert!(validate("webview_zoom", &json!({ "main": 8 })).is_err());
+        let err = validate("window_config", &json!(3)).unwrap_err();
+        assert_eq!(err.code, errors::config::VALIDATION_FAILED);
+
+        let err = validate("lan_bind", &json!({ "interface": null, "port": 80 })).unwrap_err();
+        assert!(err.details.unwrap().starts_with("/port:"));
+
+        let summary = json!({
+            "enabled": true,
+            "time": "16:00",
+            "delivery": { "type": "email", "to": [] }
+        });
+        assert!(validate("weekly_summary", &summary).is_err());
+
+        // Keys without a schema are free-form
+        assert!(validate("theme", &json!(42)).is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+//! In-memory cache of data collections
+//!
+//! `load_data` used to read and parse `data/<collection>.json` on every
+//! call. The parsed documents are now kept in memory, one per file, and
+//! written through on save. As in `config_cache`, the file's modification
+//! time and size are checked on each access, so writes from outside the
+//! cache (backup restore, factory reset, a moved data folder) are picked up.
+
+use crate::errors::{self, BackendError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Identity of the file contents a document was parsed from
+type Stamp = Option<(SystemTime, u64)>;
+
+struct Cached {
+    stamp: Stamp,
+    doc: Value,
+}
+
+/// Parsed data collections shared by all callers
+pub struct DataCache {
+    inner: Mutex<BTreeMap<PathBuf, Cached>>,
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+fn invalid(path: &Path, e: impl std::fmt::Display) -> BackendError {
+    let collection = path.file_stem().unwrap_or_default().to_string_lossy();
+    BackendError::new(errors::file::INVALID_FORMAT, "Invalid data file format")
+        .with_details(format!("{}: {}", collection, e))
+}
+
+impl DataCache {
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, Cached>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The collection at `path`, `T::default()` when there is no file
+    pub fn load<T: DeserializeOwned + Default>(&self, path: &Path) -> Result<T, BackendError> {
+        let on_disk = stamp(path);
+        let mut cache = self.lock();
+        if on_disk.is_none() {
+            cache.remove(path);
+            return Ok(T::default());
+        }
+        let fresh = cache.get(path).is_some_and(|c| c.stamp == on_disk);
+        if !fresh {
+            let content = fs::read_to_string(path).map_err(|e| {
+                BackendError::new(errors::file::IO_ERROR, "Failed to read data file")
+                    .with_details(e.to_string())
+            })?;
+            let doc = serde_json::from_str(&content).map_err(|e| invalid(path, e))?;
+            cache.insert(
+                path.to_path_buf(),
+                Cached {
+                    stamp: on_disk,
+                    doc,
+                },
+            );
+        }
+        T::deserialize(&cache[path].doc).map_err(|e| invalid(path, e))
+    }
+
+    /// Write the collection at `path`, creating its directory if needed
+    pub fn save<T: Serialize>(&self, path: &Path, data: &T) -> Result<(), BackendError> {
+        let serialize_error = |e: serde_json::Error| {
+            BackendError::new(errors::file::IO_ERROR, "Failed to serialize data")
+                .with_details(e.to_string())
+        };
+        let doc = serde_json::to_value(data).map_err(serialize_error)?;
+        let json_str = serde_json::to_string_pretty(&doc).map_err(serialize_error)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                BackendError::new(errors::file::IO_ERROR, "Failed to create data directory")
+                    .with_details(e.to_string())
+            })?;
+        }
+
+        let mut cache = self.lock();
+        fs::write(path, json_str).map_err(|e| {
+            cache.remove(path);
+            BackendError::new(errors::file::IO_ERROR, "Failed to write data file")
+                .with_details(e.to_string())
+        })?;
+        cache.insert(
+            path.to_path_buf(),
+            Cached {
+                stamp: stamp(path),
+                doc,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Default for DataCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Tickets {
+        #[serde(default)]
+        open: Vec<String>,
+    }
+
+    #[test]
+    fn test_cache_writes_through_and_sees_external_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data").join("exit_tickets.json");
+        let cache = DataCache::new();
+
+        assert_eq!(cache.load::<Tickets>(&path).unwrap(), Tickets::default());
+        let tickets = Tickets {
+            open: vec!["Le frazioni".into()],
+        };
+        cache.save(&path, &tickets).unwrap();
+        assert_eq!(cache.load::<Tickets>(&path).unwrap(), tickets);
+        let on_disk: Tickets = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk, tickets);
+
+        // Replaced outside the cache (e.g. backup restore)
+        fs::write(&path, r#"{"open":["Le equazioni","I poligoni"]}"#).unwrap();
+        assert_eq!(cache.load::<Tickets>(&path).unwrap().open.len(), 2);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cache.load::<Tickets>(&path).unwrap(), Tickets::default());
+    }
+
+    #[test]
+    fn test_invalid_file_names_the_collection() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("exit_tickets.json");
+        fs::write(&path, "{\"open\": [").unwrap();
+        let err = DataCache::new().load::<Tickets>(&path).unwrap_err();
+        assert_eq!(err.code, errors::file::INVALID_FORMAT);
+        assert!(err.details.unwrap().starts_with("exit_tickets: "));
+    }
+}
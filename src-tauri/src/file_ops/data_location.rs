@@ -0,0 +1,432 @@
+//! Where the app keeps its files
+//!
+//! By default config, data collections, photos and backups live in the
+//! platform config directory (see `default_config_dir`). Schools can move
+//! them to a roaming profile or network drive:
+//! - The chosen directory is recorded in `data_location.json`, which always
+//!   stays in the default directory
+//! - Moving copies every file over (with job progress) and switches the
+//!   resolver; the old copy is left in place
+//! - If the chosen directory already holds app data (another PC moved
+//!   there first) it is used as is, nothing is copied
+//! - Moving back to the default directory always copies: what's left there
+//!   is the stale copy from before the move, so it's replaced, files deleted
+//!   since included
+//! - If the directory can't be reached at startup (network drive offline)
+//!   the default directory is used for the session and the status reports
+//!   the fallback
+//!
+//! The location is resolved once per run; `set_data_directory` updates it.
+//! In portable mode (see `file_ops::portable_dir`) the files stay next to
+//! the executable and can't be moved.
+
+use crate::errors::{self, BackendError};
+use crate::jobs::JobContext;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::CONFIG_FILENAME;
+
+/// Pointer file kept in the default directory
+const LOCATION_FILE: &str = "data_location.json";
+const WRITE_PROBE: &str = ".write-test";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LocationFile {
+    path: Option<String>,
+}
+
+/// Resolved location for this run
+#[derive(Debug, Clone)]
+struct Resolved {
+    dir: PathBuf,
+    /// Configured directory that couldn't be used
+    unavailable: Option<PathBuf>,
+}
+
+static RESOLVED: Mutex<Option<Resolved>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirectoryStatus {
+    /// Directory in use
+    pub path: String,
+    pub default_path: String,
+    /// A custom directory is configured
+    pub custom: bool,
+    /// The configured directory was unreachable at startup; the default is
+    /// in use until the app restarts with it available
+    pub fallback: bool,
+    pub configured_path: Option<String>,
+    /// Running from a USB stick; the directory can't be changed
+    pub portable: bool,
+}
+
+/// Result of `set_data_directory`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirectoryChange {
+    pub status: DataDirectoryStatus,
+    /// Files copied (0 when existing data was adopted)
+    pub copied_files: usize,
+    /// The target already held app data, which is now in use
+    pub adopted_existing: bool,
+}
+
+fn io_error(message: &str, path: &Path, e: std::io::Error) -> BackendError {
+    BackendError::new(errors::file::IO_ERROR, message).with_details(format!(
+        "{}: {}",
+        path.display(),
+        e
+    ))
+}
+
+/// Whether `dir` exists (or can be created) and accepts writes
+pub(crate) fn check_writable(dir: &Path) -> Result<(), BackendError> {
+    fs::create_dir_all(dir).map_err(|e| io_error("Directory can't be created", dir, e))?;
+    let probe = dir.join(WRITE_PROBE);
+    fs::write(&probe, b"ok").map_err(|e| {
+        BackendError::new(errors::file::PERMISSION_DENIED, "Directory is not writable")
+            .with_details(format!("{}: {}", dir.display(), e))
+    })?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn read_location(default_dir: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(default_dir.join(LOCATION_FILE)).ok()?;
+    let file: LocationFile = serde_json::from_str(&content).ok()?;
+    file.path.filter(|p| !p.is_empty()).map(PathBuf::from)
+}
+
+fn write_location(default_dir: &Path, path: Option<&Path>) -> Result<(), BackendError> {
+    let file = default_dir.join(LOCATION_FILE);
+    match path {
+        Some(path) => {
+            fs::create_dir_all(default_dir)
+                .map_err(|e| io_error("Failed to create config directory", default_dir, e))?;
+            let content = serde_json::to_string_pretty(&LocationFile {
+                path: Some(path.to_string_lossy().to_string()),
+            })
+            .map_err(|e| {
+                BackendError::new(errors::file::IO_ERROR, "Failed to serialize data location")
+                    .with_details(e.to_string())
+            })?;
+            fs::write(&file, content)
+                .map_err(|e| io_error("Failed to save data location", &file, e))
+        }
+        None => match fs::remove_file(&file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(io_error("Failed to reset data location", &file, e))
+            }
+            _ => Ok(()),
+        },
+    }
+}
+
+fn resolve(default_dir: &Path) -> Resolved {
+    match read_location(default_dir) {
+        None => Resolved {
+            dir: default_dir.to_path_buf(),
+            unavailable: None,
+        },
+        Some(dir) if dir.is_dir() && check_writable(&dir).is_ok() => Resolved {
+            dir,
+            unavailable: None,
+        },
+        Some(dir) => {
+            eprintln!(
+                "Data directory {} is unavailable, using {}",
+                dir.display(),
+                default_dir.display()
+            );
+            Resolved {
+                dir: default_dir.to_path_buf(),
+                unavailable: Some(dir),
+            }
+        }
+    }
+}
+
+fn resolved(default_dir: &Path) -> Resolved {
+    RESOLVED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| resolve(default_dir))
+        .clone()
+}
+
+/// Directory holding the app's files (used by `file_ops::get_config_dir`)
+pub(super) fn config_dir(default_dir: &Path) -> PathBuf {
+    resolved(default_dir).dir
+}
+
+fn status_of(default_dir: &Path, resolved: &Resolved) -> DataDirectoryStatus {
+    let configured = read_location(default_dir);
+    DataDirectoryStatus {
+        path: resolved.dir.to_string_lossy().to_string(),
+        default_path: default_dir.to_string_lossy().to_string(),
+        custom: configured.is_some(),
+        fallback: resolved.unavailable.is_some(),
+        configured_path: configured.map(|p| p.to_string_lossy().to_string()),
+        portable: super::portable_dir().is_some(),
+    }
+}
+
+/// Files under `dir` (relative paths), skipping the location pointer
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>, BackendError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current)
+            .map_err(|e| io_error("Failed to read directory", &current, e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| io_error("Failed to read directory", &current, e))?
+                .path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                if relative != Path::new(LOCATION_FILE) && relative != Path::new(WRITE_PROBE) {
+                    files.push(relative.to_path_buf());
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Copy every file from `from` to `to`, reporting progress; the copy is
+/// checked by size
+fn copy_tree(
+    from: &Path,
+    to: &Path,
+    mut progress: impl FnMut(usize, usize, &Path) -> Result<(), BackendError>,
+) -> Result<usize, BackendError> {
+    let files = list_files(from)?;
+    for (done, relative) in files.iter().enumerate() {
+        progress(done, files.len(), relative)?;
+        let (source, target) = (from.join(relative), to.join(relative));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| io_error("Failed to create directory", parent, e))?;
+        }
+        let copied =
+            fs::copy(&source, &target).map_err(|e| io_error("Failed to copy", &source, e))?;
+        let expected = fs::metadata(&source).map(|m| m.len()).unwrap_or(copied);
+        if copied != expected {
+            return Err(
+                BackendError::new(errors::file::IO_ERROR, "Copy is incomplete")
+                    .with_details(target.to_string_lossy().to_string()),
+            );
+        }
+    }
+    Ok(files.len())
+}
+
+/// Remove files under `dir` that aren't in `keep` (relative paths)
+fn remove_stale(dir: &Path, keep: &[PathBuf]) -> Result<(), BackendError> {
+    for relative in list_files(dir)? {
+        if keep.binary_search(&relative).is_err() {
+            let path = dir.join(&relative);
+            fs::remove_file(&path).map_err(|e| io_error("Failed to remove", &path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Bring the files in `current` over to `target`: adopt data already
+/// there, except in the default directory, which only ever holds the copy
+/// left behind by an earlier move. Returns the files copied and whether
+/// existing data was adopted
+fn move_files(
+    default_dir: &Path,
+    current: &Path,
+    target: &Path,
+    progress: impl FnMut(usize, usize, &Path) -> Result<(), BackendError>,
+) -> Result<(usize, bool), BackendError> {
+    let to_default = target == default_dir;
+    if !to_default && target.join(CONFIG_FILENAME).exists() {
+        return Ok((0, true));
+    }
+    let copied = copy_tree(current, target, progress)?;
+    if to_default {
+        remove_stale(target, &list_files(current)?)?;
+    }
+    Ok((copied, false))
+}
+
+/// Current location
+pub fn get_data_directory() -> Result<DataDirectoryStatus, BackendError> {
+    let default_dir = super::default_config_dir()?;
+    Ok(status_of(&default_dir, &resolved(&default_dir)))
+}
+
+/// Move the app's files to `path` (`None`: back to the default directory)
+pub fn set_data_directory(
+    path: Option<&str>,
+    context: &JobContext,
+) -> Result<DataDirectoryChange, BackendError> {
+    if super::portable_dir().is_some() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "The data directory can't be moved in portable mode",
+        ));
+    }
+    set_data_directory_in(&super::default_config_dir()?, path, context)
+}
+
+fn set_data_directory_in(
+    default_dir: &Path,
+    path: Option<&str>,
+    context: &JobContext,
+) -> Result<DataDirectoryChange, BackendError> {
+    let target = match path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) => PathBuf::from(p),
+        None => default_dir.to_path_buf(),
+    };
+    if !target.is_absolute() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Data directory must be an absolute path",
+        )
+        .with_details(target.to_string_lossy().to_string()));
+    }
+    let current = resolved(default_dir).dir;
+    if target == current {
+        return Ok(DataDirectoryChange {
+            status: status_of(default_dir, &resolved(default_dir)),
+            copied_files: 0,
+            adopted_existing: false,
+        });
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Data directory can't be inside the current one or contain it",
+        )
+        .with_details(target.to_string_lossy().to_string()));
+    }
+    check_writable(&target)?;
+
+    let (copied_files, adopted_existing) =
+        move_files(default_dir, &current, &target, |done, total, file| {
+            context.check_cancelled()?;
+            context.progress(done, total, file.to_string_lossy());
+            Ok(())
+        })?;
+
+    let custom = (target != default_dir).then_some(target.as_path());
+    write_location(default_dir, custom)?;
+    let resolved = Resolved {
+        dir: target,
+        unavailable: None,
+    };
+    *RESOLVED.lock().unwrap_or_else(|e| e.into_inner()) = Some(resolved.clone());
+    super::CONFIG_CACHE.invalidate();
+    Ok(DataDirectoryChange {
+        status: status_of(default_dir, &resolved),
+        copied_files,
+        adopted_existing,
+    })
+}
+
+/// Use `dir` for the rest of this run (`None`: resolve it again)
+#[cfg(test)]
+pub(super) fn use_dir(dir: Option<&Path>) {
+    *RESOLVED.lock().unwrap_or_else(|e| e.into_inner()) = dir.map(|dir| Resolved {
+        dir: dir.to_path_buf(),
+        unavailable: None,
+    });
+    super::CONFIG_CACHE.invalidate();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_falls_back_when_unavailable() {
+        let default_dir = tempfile::tempdir().unwrap();
+        assert_eq!(resolve(default_dir.path()).dir, default_dir.path());
+
+        let network = tempfile::tempdir().unwrap();
+        write_location(default_dir.path(), Some(network.path())).unwrap();
+        let resolved = resolve(default_dir.path());
+        assert_eq!(resolved.dir, network.path());
+        assert!(resolved.unavailable.is_none());
+
+        // Drive offline
+        let offline = network.path().join("gone");
+        write_location(default_dir.path(), Some(&offline)).unwrap();
+        let resolved = resolve(default_dir.path());
+        assert_eq!(resolved.dir, default_dir.path());
+        assert_eq!(resolved.unavailable, Some(offline));
+        let status = status_of(default_dir.path(), &resolved);
+        assert!(status.custom && status.fallback);
+
+        write_location(default_dir.path(), None).unwrap();
+        assert!(read_location(default_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_copy_tree() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        fs::write(from.path().join(CONFIG_FILENAME), "{}").unwrap();
+        fs::write(from.path().join(LOCATION_FILE), "{}").unwrap();
+        fs::create_dir_all(from.path().join("data")).unwrap();
+        fs::write(from.path().join("data").join("rosters.json"), "[]").unwrap();
+
+        let mut seen = Vec::new();
+        let copied = copy_tree(from.path(), to.path(), |done, total, _| {
+            seen.push((done, total));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(copied, 2);
+        assert_eq!(seen, [(0, 2), (1, 2)]);
+        assert!(to.path().join("data").join("rosters.json").exists());
+        // The pointer stays with the default directory
+        assert!(!to.path().join(LOCATION_FILE).exists());
+    }
+
+    #[test]
+    fn test_move_back_to_default_copies() {
+        let default_dir = tempfile::tempdir().unwrap();
+        let custom = tempfile::tempdir().unwrap();
+        let rosters = Path::new("data").join("rosters.json");
+        let notes = Path::new("data").join("notes.json");
+        fs::create_dir_all(default_dir.path().join("data")).unwrap();
+        fs::write(default_dir.path().join(CONFIG_FILENAME), "{}").unwrap();
+        fs::write(default_dir.path().join(&rosters), "[]").unwrap();
+        fs::write(default_dir.path().join(&notes), "[]").unwrap();
+        write_location(default_dir.path(), Some(custom.path())).unwrap();
+        let none = |_: usize, _: usize, _: &Path| Ok(());
+
+        // Default -> custom
+        let moved = move_files(default_dir.path(), default_dir.path(), custom.path(), none);
+        assert_eq!(moved.unwrap(), (3, false));
+
+        // Changes made while on the custom directory
+        fs::write(custom.path().join(&rosters), r#"[{"id":"1"}]"#).unwrap();
+        fs::remove_file(custom.path().join(&notes)).unwrap();
+
+        // Custom -> default: the old copy is replaced, not adopted
+        let moved = move_files(default_dir.path(), custom.path(), default_dir.path(), none);
+        assert_eq!(moved.unwrap(), (2, false));
+        let content = fs::read_to_string(default_dir.path().join(&rosters)).unwrap();
+        assert_eq!(content, r#"[{"id":"1"}]"#);
+        assert!(!default_dir.path().join(&notes).exists());
+        assert!(default_dir.path().join(LOCATION_FILE).exists());
+
+        // Another directory with app data is still adopted
+        let shared = tempfile::tempdir().unwrap();
+        fs::write(shared.path().join(CONFIG_FILENAME), "{}").unwrap();
+        let moved = move_files(default_dir.path(), default_dir.path(), shared.path(), none);
+        assert_eq!(moved.unwrap(), (0, true));
+    }
+}
@@ -0,0 +1,220 @@
+//! Header names recognised when mapping import columns
+//!
+//! Handles:
+//! - Built-in names of the columns imports look for, in Italian, English
+//!   and German ("Cognome", "Surname", "Nachname")
+//! - Extra names for a school's own exports, in the
+//!   `import_header_synonyms` config key:
+//!   `{ "surname": ["Familienname"], "email": ["Mail istituzionale"] }`
+//! - Matching header cells after folding (see `fuzzy::fold`), so case,
+//!   accents and punctuation don't matter: "E-Mail", "e_mail" and
+//!   "E mail" are the same header, "Schüler" matches "Schuler"
+//!
+//! Used by the roster header search (`import_adapters`) and the quiz
+//! results import (`forms_import`). Extra names are added to the built-in
+//! ones, never replace them.
+
+use crate::file_ops;
+use crate::fuzzy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const CONFIG_KEY: &str = "import_header_synonyms";
+
+/// A column imports look for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HeaderField {
+    /// One column with the whole name ("Cognome e nome", "Schüler")
+    FullName,
+    Surname,
+    GivenName,
+    Email,
+    /// Quiz total score
+    Score,
+}
+
+const FULL_NAME: &[&str] = &[
+    // Italian
+    "cognome e nome",
+    "cognome nome",
+    "nome e cognome",
+    "nome cognome",
+    "alunno",
+    "alunna",
+    "alunno/a",
+    "alunni",
+    "nominativo",
+    "studente",
+    "studentessa",
+    "nome completo",
+    "nome studente",
+    // English
+    "student",
+    "student name",
+    "name",
+    "full name",
+    "pupil",
+    "pupil name",
+    // German
+    "schüler",
+    "schülerin",
+    "schüler/in",
+    "schülername",
+    "name, vorname",
+    "nachname, vorname",
+    "vollständiger name",
+];
+const SURNAME: &[&str] = &[
+    "cognome",
+    "surname",
+    "last name",
+    "lastname",
+    "family name",
+    "nachname",
+    "familienname",
+];
+const GIVEN_NAME: &[&str] = &[
+    "nome",
+    "first name",
+    "firstname",
+    "given name",
+    "forename",
+    "vorname",
+];
+const EMAIL: &[&str] = &[
+    "email",
+    "e-mail",
+    "email address",
+    "e-mail address",
+    "mail",
+    "indirizzo email",
+    "indirizzo e-mail",
+    "posta elettronica",
+    "e-mail-adresse",
+    "email-adresse",
+];
+const SCORE: &[&str] = &[
+    "score",
+    "total score",
+    "total points",
+    "points",
+    "punteggio",
+    "punteggio totale",
+    "punti totali",
+    "punti",
+    "punkte",
+    "punktzahl",
+    "gesamtpunktzahl",
+];
+
+impl HeaderField {
+    const ALL: [HeaderField; 5] = [
+        HeaderField::FullName,
+        HeaderField::Surname,
+        HeaderField::GivenName,
+        HeaderField::Email,
+        HeaderField::Score,
+    ];
+
+    fn built_in(self) -> &'static [&'static str] {
+        match self {
+            HeaderField::FullName => FULL_NAME,
+            HeaderField::Surname => SURNAME,
+            HeaderField::GivenName => GIVEN_NAME,
+            HeaderField::Email => EMAIL,
+            HeaderField::Score => SCORE,
+        }
+    }
+}
+
+/// Folded header names per field
+#[derive(Debug, Clone)]
+pub struct HeaderDictionary {
+    names: BTreeMap<HeaderField, Vec<String>>,
+}
+
+impl HeaderDictionary {
+    /// The built-in names plus `extra`
+    pub fn with_extra(extra: &BTreeMap<HeaderField, Vec<String>>) -> Self {
+        let names = HeaderField::ALL
+            .into_iter()
+            .map(|field| {
+                let mut names: Vec<String> = field
+                    .built_in()
+                    .iter()
+                    .copied()
+                    .chain(extra.get(&field).into_iter().flatten().map(String::as_str))
+                    .map(fuzzy::fold)
+                    .filter(|name| !name.is_empty())
+                    .collect();
+                names.sort();
+                names.dedup();
+                (field, names)
+            })
+            .collect();
+        Self { names }
+    }
+
+    /// The built-in names plus those in the config
+    pub fn load() -> Self {
+        let extra = file_ops::load_config(CONFIG_KEY)
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        Self::with_extra(&extra)
+    }
+
+    /// Whether `cell` is a header of `field`
+    pub fn matches(&self, field: HeaderField, cell: &str) -> bool {
+        let folded = fuzzy::fold(cell);
+        self.names[&field].binary_search(&folded).is_ok()
+    }
+
+    /// Index of the first header of `field` in a row
+    pub fn find(&self, field: HeaderField, row: &[String]) -> Option<usize> {
+        row.iter().position(|cell| self.matches(field, cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_headers_in_three_languages() {
+        let dictionary = HeaderDictionary::with_extra(&BTreeMap::new());
+        for (surname, given) in [
+            ("Cognome", "Nome"),
+            ("SURNAME", "First name"),
+            ("last_name", "first_name"),
+            ("Nachname", "Vorname"),
+        ] {
+            let header = row(&["Nr.", surname, given]);
+            assert_eq!(dictionary.find(HeaderField::Surname, &header), Some(1));
+            assert_eq!(dictionary.find(HeaderField::GivenName, &header), Some(2));
+        }
+        assert!(dictionary.matches(HeaderField::FullName, "Schüler/in"));
+        assert!(dictionary.matches(HeaderField::FullName, "Cognome/Nome:"));
+        assert!(dictionary.matches(HeaderField::Email, "E-Mail-Adresse"));
+        assert!(dictionary.matches(HeaderField::Score, "Punktzahl"));
+        assert!(!dictionary.matches(HeaderField::Surname, "Classe"));
+    }
+
+    #[test]
+    fn test_extra_names_from_config() {
+        let extra: BTreeMap<HeaderField, Vec<String>> = serde_json::from_value(
+            serde_json::json!({ "surname": ["Famiglia"], "email": ["Mail istituzionale"] }),
+        )
+        .unwrap();
+        let dictionary = HeaderDictionary::with_extra(&extra);
+        assert!(dictionary.matches(HeaderField::Surname, "FAMIGLIA"));
+        assert!(dictionary.matches(HeaderField::Surname, "Cognome"));
+        assert!(dictionary.matches(HeaderField::Email, "mail  istituzionale"));
+        assert!(!dictionary.matches(HeaderField::GivenName, "Famiglia"));
+    }
+}
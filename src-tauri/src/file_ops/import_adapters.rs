@@ -0,0 +1,659 @@
+//! Roster import adapters
+//!
+//! Each file format is an `ImportAdapter` (detect, map, parse) in its own
+//! module, with its own tests:
+//! - `argo`, `axios`, `classeviva`: Italian electronic registry exports
+//! - `teams`: Microsoft Teams member lists
+//! - `generic`: any other CSV or spreadsheet with a recognisable header
+//!
+//! Registry exports come as CSV or XLS/XLSX with their own quirks:
+//! - Preamble lines (school name, "Classe: 3A", print date) before the header
+//! - Header rows split over two lines (merged group labels like "Alunno")
+//! - Names in separate "Cognome"/"Nome" columns or a single upper-case
+//!   "COGNOME NOME" column, sometimes prefixed with a row number
+//! - Footer lines ("Totale alunni: 24", "Stampato il ...")
+//!
+//! This module holds what they share: reading rows from CSV or
+//! spreadsheets, the header search (column names from `header_synonyms`)
+//! and the name clean-up. The format is
+//! auto-detected from the first rows; adapters added with `register` are
+//! tried before the built-in ones. Imports report their stages as they go
+//! (see `import_progress`). Quiz results (`forms_import`) yield
+//! scores, not rosters, and only share `read_rows`.
+
+use crate::errors::{self, BackendError};
+use crate::roster::ParsedRoster;
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::header_synonyms::{HeaderDictionary, HeaderField};
+use super::import_progress::{ImportProgress, ImportStage, NoProgress};
+use super::import_transforms::{self, Transform};
+
+pub mod argo;
+pub mod axios;
+pub mod classeviva;
+pub mod generic;
+pub mod teams;
+
+/// Rows searched for registry markers and the header row
+const HEADER_SCAN_ROWS: usize = 15;
+
+/// Larger files are rejected (a whole-school export of 100k students is
+/// about 8 MB; a class export a few hundred KB)
+const MAX_IMPORT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Bytes read between two `reading` reports
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Rows parsed between two `parsing` reports
+const PARSE_REPORT_ROWS: usize = 1_000;
+
+/// Extensions accepted by `import_roster_file`
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["csv", "txt", "xls", "xlsx", "ods"];
+
+/// Lines that end the student list in every format
+const COMMON_FOOTERS: &[&str] = &["totale", "stampato", "data stampa", "pagina", "firma"];
+
+/// A roster file format
+///
+/// `map` and `parse` default to the shared header search and name reading,
+/// so most adapters only say how to recognise their files.
+pub trait ImportAdapter: Send + Sync {
+    /// Format id reported in `RosterImport::format` ("argo")
+    fn id(&self) -> &'static str;
+
+    /// Whether the rows come from this format
+    fn detect(&self, rows: &[Vec<String>]) -> bool;
+
+    /// Locate the header and the name columns
+    fn map(&self, rows: &[Vec<String>]) -> Option<ColumnMap> {
+        find_header(rows)
+    }
+
+    /// Read the student names from the data rows
+    fn parse(&self, rows: &[Vec<String>], map: &ColumnMap) -> ParsedRoster {
+        read_names(rows, map, &[])
+    }
+}
+
+const BUILT_IN: &[&dyn ImportAdapter] = &[
+    &argo::Argo,
+    &axios::Axios,
+    &classeviva::ClasseViva,
+    &teams::Teams,
+];
+
+static REGISTERED: RwLock<Vec<&'static dyn ImportAdapter>> = RwLock::new(Vec::new());
+
+/// Add an adapter; it is tried before the built-in ones (the last
+/// registered first)
+pub fn register(adapter: Box<dyn ImportAdapter>) {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Box::leak(adapter));
+}
+
+/// Adapter for the rows; `generic` when no format is recognised
+fn detect_adapter(rows: &[Vec<String>]) -> &'static dyn ImportAdapter {
+    let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+    registered
+        .iter()
+        .rev()
+        .chain(BUILT_IN)
+        .find(|a| a.detect(rows))
+        .copied()
+        .unwrap_or(&generic::Generic)
+}
+
+/// Id of the format the rows come from
+pub fn detect_format(rows: &[Vec<String>]) -> &'static str {
+    detect_adapter(rows).id()
+}
+
+/// Result of importing a roster file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterImport {
+    /// Id of the adapter that read the file ("argo", "generic", ...)
+    pub format: String,
+    /// Class name found in the preamble ("Classe: 3A"), if any
+    pub class_name: Option<String>,
+    /// Name field → header of the column it was read from ("name", or
+    /// "surname" and "givenName"); empty when no header was found
+    pub columns: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub roster: ParsedRoster,
+}
+
+/// Where the student name is in a data row
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameLayout {
+    Full(usize),
+    Split { surname: usize, given: usize },
+}
+
+/// Where the students are in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    /// Index of the header row
+    pub header_row: usize,
+    /// First data row
+    pub data_start: usize,
+    pub layout: NameLayout,
+}
+
+impl ColumnMap {
+    /// Name field → header of the column it is read from
+    fn columns(&self, rows: &[Vec<String>]) -> BTreeMap<String, String> {
+        let mut columns = BTreeMap::new();
+        let header = &rows[self.header_row];
+        let mut column = |field: &str, col: usize| {
+            if let Some(name) = header.get(col) {
+                columns.insert(field.to_string(), name.trim().to_string());
+            }
+        };
+        match self.layout {
+            NameLayout::Full(col) => column("name", col),
+            NameLayout::Split { surname, given } => {
+                column("surname", surname);
+                column("givenName", given);
+            }
+        }
+        columns
+    }
+}
+
+fn normalize_cell(cell: &str) -> String {
+    cell.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(':')
+        .to_string()
+}
+
+/// Recognise a header row by its name columns
+fn detect_layout(row: &[String], dictionary: &HeaderDictionary) -> Option<NameLayout> {
+    let find = |field| dictionary.find(field, row);
+
+    match (find(HeaderField::Surname), find(HeaderField::GivenName)) {
+        (Some(surname), Some(given)) => Some(NameLayout::Split { surname, given }),
+        (surname, given) => find(HeaderField::FullName)
+            .or(surname)
+            .or(given)
+            .map(NameLayout::Full),
+    }
+}
+
+/// Locate the header among the first rows
+///
+/// When two consecutive rows both look like headers (merged group labels
+/// over specific columns), the second, more specific one wins.
+pub fn find_header(rows: &[Vec<String>]) -> Option<ColumnMap> {
+    let dictionary = HeaderDictionary::load();
+    let limit = rows.len().min(HEADER_SCAN_ROWS);
+    let index = (0..limit).find(|&i| detect_layout(&rows[i], &dictionary).is_some())?;
+    let (header_row, layout) = match rows
+        .get(index + 1)
+        .and_then(|next| detect_layout(next, &dictionary))
+    {
+        Some(layout) => (index + 1, layout),
+        None => (index, detect_layout(&rows[index], &dictionary)?),
+    };
+    Some(ColumnMap {
+        header_row,
+        data_start: header_row + 1,
+        layout,
+    })
+}
+
+/// Whether any of `markers` (lower case) appears in the first rows
+pub fn preamble_contains(rows: &[Vec<String>], markers: &[&str]) -> bool {
+    let preamble = rows
+        .iter()
+        .take(HEADER_SCAN_ROWS)
+        .flatten()
+        .map(|c| c.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    markers.iter().any(|m| preamble.contains(m))
+}
+
+/// Find "Classe: 3A" (or "Classe" | "3A" in adjacent cells) in the preamble
+fn find_class_name(preamble: &[Vec<String>]) -> Option<String> {
+    for row in preamble {
+        for (i, cell) in row.iter().enumerate() {
+            let lower = cell.trim().to_lowercase();
+            if !lower.starts_with("classe") || lower.starts_with("classeviva") {
+                continue;
+            }
+            let value = match cell.split_once(':') {
+                Some((_, value)) => value.trim().to_string(),
+                None if lower == "classe" => row[i + 1..]
+                    .iter()
+                    .find(|c| !c.trim().is_empty())
+                    .map(|c| c.trim().to_string())
+                    .unwrap_or_default(),
+                None => continue,
+            };
+            if !value.is_empty() {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+/// Drop a leading row number ("12. ROSSI MARIO", "3) Bianchi")
+fn strip_numbering(name: &str) -> &str {
+    let rest = name.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == name.len() {
+        return name;
+    }
+    let rest = rest.trim_start_matches(['.', ')']);
+    if rest.starts_with(char::is_whitespace) {
+        rest.trim_start()
+    } else {
+        name
+    }
+}
+
+/// Convert all-caps names to title case ("D'ANGELO ANNA" -> "D'Angelo Anna")
+fn title_case(name: &str) -> String {
+    if name.chars().any(char::is_lowercase) {
+        return name.to_string();
+    }
+    import_transforms::title_case(name)
+}
+
+/// Footer prefixes must end at a word boundary ("Firmani" is a student)
+fn is_footer(cell: &str, prefix: &str) -> bool {
+    cell.strip_prefix(prefix)
+        .is_some_and(|rest| !rest.starts_with(char::is_alphanumeric))
+}
+
+/// Read student names from the data rows of `map`, skipping footer lines
+/// (`COMMON_FOOTERS` plus `footers`)
+pub fn read_names(rows: &[Vec<String>], map: &ColumnMap, footers: &[&str]) -> ParsedRoster {
+    read_names_where(rows, map, footers, |_| true)
+}
+
+/// `read_names`, keeping only the rows for which `keep` holds
+pub fn read_names_where(
+    rows: &[Vec<String>],
+    map: &ColumnMap,
+    footers: &[&str],
+    keep: impl Fn(&[String]) -> bool,
+) -> ParsedRoster {
+    let footers: Vec<&str> = COMMON_FOOTERS.iter().chain(footers).copied().collect();
+    let mut roster = ParsedRoster::default();
+    for (index, row) in rows.iter().enumerate().skip(map.data_start) {
+        let Some(first) = row.iter().find(|c| !c.trim().is_empty()) else {
+            continue;
+        };
+        let first = first.trim().to_lowercase();
+        if footers.iter().any(|f| is_footer(&first, f)) || !keep(row) {
+            continue;
+        }
+
+        let cell = |col: usize| row.get(col).map_or("", |c| c.trim());
+        let name = match map.layout {
+            NameLayout::Full(col) => strip_numbering(cell(col)).to_string(),
+            NameLayout::Split { surname, given } => {
+                format!("{} {}", strip_numbering(cell(surname)), cell(given))
+            }
+        };
+        // Stray quotes inside a field are kept by the CSV reader
+        // ("2. \"DE LUCA\" CHIARA"); no name contains one
+        let name = name.replace('"', "");
+        roster.push_name(index + 1, &title_case(name.trim()));
+    }
+    roster.finish()
+}
+
+/// Import a roster from rows of cells
+///
+/// Without a header the names are read from the first column.
+pub fn import_rows(rows: &[Vec<String>]) -> RosterImport {
+    let adapter = detect_adapter(rows);
+    let map = adapter.map(rows);
+    let columns = map.as_ref().map_or_else(BTreeMap::new, |m| m.columns(rows));
+    let map = map.unwrap_or(ColumnMap {
+        header_row: 0,
+        data_start: 0,
+        layout: NameLayout::Full(0),
+    });
+
+    RosterImport {
+        format: adapter.id().to_string(),
+        class_name: find_class_name(&rows[..map.data_start.min(rows.len())]),
+        columns,
+        roster: adapter.parse(rows, &map),
+    }
+}
+
+/// Guess the delimiter from the first non-empty line (`,` `;` tab `|`)
+///
+/// Preamble lines rarely contain delimiters, so the line with the most
+/// candidates among the first rows is used.
+fn guess_delimiter(content: &str) -> u8 {
+    let lines: Vec<&str> = content.lines().take(HEADER_SCAN_ROWS).collect();
+    [b';', b',', b'\t', b'|']
+        .into_iter()
+        .map(|d| {
+            (
+                d,
+                lines
+                    .iter()
+                    .map(|l| l.bytes().filter(|&b| b == d).count())
+                    .max()
+                    .unwrap_or(0),
+            )
+        })
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map_or(b',', |(d, _)| d)
+}
+
+/// Read delimited text into rows, honouring double-quoted fields
+///
+/// Quoted fields may span lines (free-text answers in Forms exports) and
+/// stay one row, as in a spreadsheet. Blank lines become empty rows so row
+/// numbers in messages match the file.
+fn csv_rows(
+    content: &str,
+    progress: &dyn ImportProgress,
+) -> Result<Vec<Vec<String>>, BackendError> {
+    let delimiter = guess_delimiter(content);
+    let bytes = content.as_bytes();
+    let mut rows = Vec::new();
+    let mut next_report = PARSE_REPORT_ROWS;
+    super::for_each_delimited_record(content, delimiter, |record| {
+        // The reader skips blank lines; they sit between where the previous
+        // record ended and this one, which may be inside a CRLF
+        let end = record.position().map_or(0, |p| p.byte() as usize);
+        let skipped = &bytes[end..];
+        let skipped = &skipped[..skipped
+            .iter()
+            .take_while(|&&b| matches!(b, b'\r' | b'\n'))
+            .count()];
+        let mut blank = skipped.iter().filter(|&&b| b == b'\n').count();
+        if end > 0 && bytes[end - 1] == b'\r' && skipped.first() == Some(&b'\n') {
+            blank -= 1;
+        }
+        rows.resize_with(rows.len() + blank, Vec::new);
+        rows.push(record.iter().map(str::to_string).collect());
+        if rows.len() >= next_report {
+            progress.report(ImportStage::Parsing, rows.len(), None);
+            next_report = rows.len() + PARSE_REPORT_ROWS;
+        }
+        Ok(())
+    })?;
+    Ok(rows)
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.trim().to_string(),
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        other => other.to_string(),
+    }
+}
+
+/// Read the first sheet of an XLS/XLSX/ODS workbook
+fn spreadsheet_rows(
+    bytes: &[u8],
+    progress: &dyn ImportProgress,
+) -> Result<Vec<Vec<String>>, BackendError> {
+    let invalid = |e: String| {
+        BackendError::new(errors::file::INVALID_FORMAT, "Failed to read spreadsheet")
+            .with_details(e)
+    };
+    progress.report(ImportStage::Decoding, 0, Some(bytes.len()));
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes.to_vec()))
+        .map_err(|e| invalid(e.to_string()))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| invalid("Workbook has no sheets".to_string()))?
+        .map_err(|e| invalid(e.to_string()))?;
+    progress.report(ImportStage::Decoding, bytes.len(), Some(bytes.len()));
+
+    // Keep spreadsheet row numbers in error messages
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+    let total = first_row + range.height();
+    progress.report(ImportStage::Parsing, 0, Some(total));
+    let mut rows = vec![Vec::new(); first_row];
+    for row in range.rows() {
+        rows.push(row.iter().map(cell_text).collect());
+        if rows.len() % PARSE_REPORT_ROWS == 0 {
+            progress.report(ImportStage::Parsing, rows.len(), Some(total));
+        }
+    }
+    progress.report(ImportStage::Parsing, rows.len(), Some(rows.len()));
+    Ok(rows)
+}
+
+/// Office files are zip (XLSX/ODS) or OLE2 (XLS) containers
+fn is_spreadsheet(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0])
+}
+
+/// Read the first sheet of a workbook, or the rows of CSV text
+pub(crate) fn read_rows(bytes: &[u8]) -> Result<Vec<Vec<String>>, BackendError> {
+    read_rows_with(bytes, &NoProgress)
+}
+
+/// `read_rows`, reporting the decoding and parsing stages
+fn read_rows_with(
+    bytes: &[u8],
+    progress: &dyn ImportProgress,
+) -> Result<Vec<Vec<String>>, BackendError> {
+    if is_spreadsheet(bytes) {
+        return spreadsheet_rows(bytes, progress);
+    }
+    progress.report(ImportStage::Decoding, 0, Some(bytes.len()));
+    let content = super::detect_and_decode(bytes)?;
+    progress.report(ImportStage::Decoding, bytes.len(), Some(bytes.len()));
+    progress.report(ImportStage::Parsing, 0, None);
+    let rows = csv_rows(&content, progress)?;
+    progress.report(ImportStage::Parsing, rows.len(), Some(rows.len()));
+    Ok(rows)
+}
+
+/// Read a file in chunks, reporting the bytes read so far
+fn read_file(path: &Path, size: u64, progress: &dyn ImportProgress) -> io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let mut bytes = Vec::with_capacity(size as usize);
+    let mut chunk = vec![0; READ_CHUNK];
+    progress.report(ImportStage::Reading, 0, Some(size as usize));
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => bytes.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+        // The file may have grown since it was measured
+        let total = bytes.len().max(size as usize);
+        progress.report(ImportStage::Reading, bytes.len(), Some(total));
+    }
+    Ok(bytes)
+}
+
+/// Import a roster from file contents (CSV text or spreadsheet), after
+/// running `transforms` on its columns
+pub fn import_roster_bytes(
+    bytes: &[u8],
+    transforms: &[Transform],
+    progress: &dyn ImportProgress,
+) -> Result<RosterImport, BackendError> {
+    let mut rows = read_rows_with(bytes, progress)?;
+    if !transforms.is_empty() {
+        let header = detect_adapter(&rows).map(&rows).map(|m| m.header_row);
+        import_transforms::apply(&mut rows, header, transforms)?;
+    }
+    progress.report(ImportStage::Validating, 0, None);
+    let import = import_rows(&rows);
+    let checked = import.roster.students.len() + import.roster.errors.len();
+    progress.report(ImportStage::Validating, checked, Some(checked));
+    Ok(import)
+}
+
+/// Import a roster file chosen by the user (see `import_roster_bytes`)
+pub fn import_roster_file(
+    path: &Path,
+    transforms: &[Transform],
+    progress: &dyn ImportProgress,
+) -> Result<RosterImport, BackendError> {
+    let supported = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "File must be a CSV or spreadsheet (.csv, .xls, .xlsx, .ods)",
+        ));
+    }
+
+    let metadata = fs::metadata(path).map_err(|e| {
+        BackendError::new(
+            errors::file::NOT_FOUND,
+            format!("File not found: {}", path.display()),
+        )
+        .with_details(e.to_string())
+    })?;
+    if metadata.len() > MAX_IMPORT_SIZE {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "File is too large to be a roster",
+        ));
+    }
+
+    let bytes = read_file(path, metadata.len(), progress).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to read roster file")
+            .with_details(e.to_string())
+    })?;
+    import_roster_bytes(&bytes, transforms, progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(strip_numbering("12. ROSSI"), "ROSSI");
+        assert_eq!(strip_numbering("3) Bianchi"), "Bianchi");
+        assert_eq!(strip_numbering("2B"), "2B");
+        assert_eq!(title_case("DELL'ORTO LUCA"), "Dell'Orto Luca");
+        assert_eq!(title_case("McKenzie"), "McKenzie");
+        assert_eq!(
+            csv_rows("a,\"b, c\",\"d \"\"e\"\"\"", &NoProgress).unwrap(),
+            vec![vec!["a", "b, c", "d \"e\""]]
+        );
+        assert_eq!(
+            csv_rows("\u{feff}a;\"one\r\ntwo\"\r\n\r\nb;c\n", &NoProgress).unwrap(),
+            vec![vec!["a", "one\r\ntwo"], vec![], vec!["b", "c"]]
+        );
+        assert!(csv_rows("", &NoProgress).is_err());
+        assert!(import_roster_bytes(b"PK\x03\x04broken", &[], &NoProgress).is_err());
+    }
+
+    #[test]
+    fn test_registered_adapter_is_tried_first() {
+        struct School;
+        impl ImportAdapter for School {
+            fn id(&self) -> &'static str {
+                "school-export"
+            }
+            fn detect(&self, rows: &[Vec<String>]) -> bool {
+                preamble_contains(rows, &["school export v2"])
+            }
+        }
+
+        let rows = csv_rows(
+            "School export v2 - Argo Software\nCognome;Nome\nRossi;Mario\n",
+            &NoProgress,
+        )
+        .unwrap();
+        assert_eq!(detect_format(&rows), "argo");
+        register(Box::new(School));
+        let result = import_rows(&rows);
+        assert_eq!(result.format, "school-export");
+        assert_eq!(result.roster.students, vec!["Rossi Mario"]);
+    }
+
+    #[test]
+    fn test_transforms_run_before_parsing() {
+        let content = "Classe: 3A\nN.;Allievo\n1;ROSSI MARIO\n2;DE LUCA ANNA\n";
+        let transforms: Vec<Transform> = serde_json::from_value(serde_json::json!([
+            { "op": "split", "column": "Allievo", "into": ["Cognome", "Nome"] }
+        ]))
+        .unwrap();
+        let result = import_roster_bytes(content.as_bytes(), &transforms, &NoProgress).unwrap();
+        assert_eq!(result.class_name.as_deref(), Some("3A"));
+        assert_eq!(result.columns["givenName"], "Nome");
+        assert_eq!(result.roster.students, vec!["Rossi Mario", "De Luca Anna"]);
+    }
+
+    #[test]
+    fn test_file_import_reports_stages() {
+        #[derive(Default)]
+        struct Recorder(std::cell::RefCell<Vec<(ImportStage, usize, Option<usize>)>>);
+        impl ImportProgress for Recorder {
+            fn report(&self, stage: ImportStage, done: usize, total: Option<usize>) {
+                self.0.borrow_mut().push((stage, done, total));
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("3A.csv");
+        let content = format!("Cognome;Nome\n{}", "Rossi;Mario\n".repeat(10_000));
+        fs::write(&path, &content).unwrap();
+        let recorder = Recorder::default();
+        import_roster_file(&path, &[], &recorder).unwrap();
+
+        let reports = recorder.0.into_inner();
+        let mut stages: Vec<ImportStage> = reports.iter().map(|r| r.0).collect();
+        stages.dedup();
+        assert_eq!(
+            stages,
+            [
+                ImportStage::Reading,
+                ImportStage::Decoding,
+                ImportStage::Parsing,
+                ImportStage::Validating
+            ]
+        );
+        let bytes = content.len();
+        let reading: Vec<usize> = reports
+            .iter()
+            .filter(|r| r.0 == ImportStage::Reading)
+            .map(|r| r.1)
+            .collect();
+        assert_eq!(reading, [0, READ_CHUNK, bytes]);
+        // Parsing reports as rows come, not only at the start and the end
+        let parsing: Vec<usize> = reports
+            .iter()
+            .filter(|r| r.0 == ImportStage::Parsing)
+            .map(|r| r.1)
+            .collect();
+        let expected: Vec<usize> = std::iter::once(0)
+            .chain((1..=10).map(|i| i * PARSE_REPORT_ROWS))
+            .chain([10_001])
+            .collect();
+        assert_eq!(parsing, expected);
+        assert_eq!(
+            reports.last(),
+            Some(&(ImportStage::Validating, 10_000, Some(10_000)))
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Argo (ScuolaNext / DidUP) class lists
+//!
+//! "Argo Software" in the preamble; lists end with "Elenco generato ...".
+
+use super::{read_names, ColumnMap, ImportAdapter};
+use crate::roster::ParsedRoster;
+
+pub struct Argo;
+
+impl ImportAdapter for Argo {
+    fn id(&self) -> &'static str {
+        "argo"
+    }
+
+    fn detect(&self, rows: &[Vec<String>]) -> bool {
+        super::preamble_contains(rows, &["argo software", "scuolanext", "didup"])
+    }
+
+    fn parse(&self, rows: &[Vec<String>], map: &ColumnMap) -> ParsedRoster {
+        read_names(rows, map, &["elenco generato"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_ops::import_adapters::{import_rows, read_rows, RosterImport};
+
+    fn import(content: &str) -> RosterImport {
+        import_rows(&read_rows(content.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_argo_preamble_and_footer() {
+        let csv = "\
+Istituto Comprensivo \"G. Verdi\";;;\n\
+Argo Software - ScuolaNext;;;\n\
+Classe: 3A;;;\n\
+;;;\n\
+N.;Cognome;Nome;Data di nascita\n\
+1;ROSSI;MARIO;01/02/2012\n\
+2;FIRMANI;LUCA;05/06/2012\n\
+3;D'ANGELO;ANNA MARIA;03/04/2012\n\
+;;;\n\
+Totale alunni: 3;;;\n";
+        let result = import(csv);
+        assert_eq!(result.format, "argo");
+        assert_eq!(result.class_name.as_deref(), Some("3A"));
+        assert_eq!(
+            result.roster.students,
+            vec!["Rossi Mario", "Firmani Luca", "D'Angelo Anna Maria"]
+        );
+        assert!(result.roster.errors.is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+//! Axios class lists
+//!
+//! Header split over two rows (an "Alunno" group label over "Cognome" and
+//! "Nome"), which the shared header search handles.
+
+use super::{read_names, ColumnMap, ImportAdapter};
+use crate::roster::ParsedRoster;
+
+pub struct Axios;
+
+impl ImportAdapter for Axios {
+    fn id(&self) -> &'static str {
+        "axios"
+    }
+
+    fn detect(&self, rows: &[Vec<String>]) -> bool {
+        super::preamble_contains(rows, &["axios"])
+    }
+
+    fn parse(&self, rows: &[Vec<String>], map: &ColumnMap) -> ParsedRoster {
+        read_names(rows, map, &["registro elettronico axios"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_ops::import_adapters::{import_rows, read_rows, RosterImport};
+
+    fn import(content: &str) -> RosterImport {
+        import_rows(&read_rows(content.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_axios_merged_header() {
+        let csv = "\
+Registro Elettronico Axios,,\n\
+Classe,2B,\n\
+,Alunno,\n\
+N.,Cognome,Nome\n\
+1,Bianchi,Luca\n\
+2,Verdi,Sara\n";
+        let result = import(csv);
+        assert_eq!(result.format, "axios");
+        assert_eq!(result.class_name.as_deref(), Some("2B"));
+        assert_eq!(result.roster.students, vec!["Bianchi Luca", "Verdi Sara"]);
+    }
+}
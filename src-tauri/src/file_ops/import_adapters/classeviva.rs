@@ -0,0 +1,50 @@
+//! ClasseViva (Spaggiari) class lists
+//!
+//! A single upper-case "COGNOME NOME" column with numbered rows.
+
+use super::{read_names, ColumnMap, ImportAdapter};
+use crate::roster::ParsedRoster;
+
+pub struct ClasseViva;
+
+impl ImportAdapter for ClasseViva {
+    fn id(&self) -> &'static str {
+        "classeviva"
+    }
+
+    fn detect(&self, rows: &[Vec<String>]) -> bool {
+        super::preamble_contains(rows, &["spaggiari", "classeviva", "classe viva"])
+    }
+
+    fn parse(&self, rows: &[Vec<String>], map: &ColumnMap) -> ParsedRoster {
+        read_names(rows, map, &["infoschool"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_ops::import_adapters::{import_rows, read_rows, RosterImport};
+
+    fn import(content: &str) -> RosterImport {
+        import_rows(&read_rows(content.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_classeviva_single_column() {
+        let csv = "\
+Gruppo Spaggiari Parma - ClasseViva\n\
+Elenco alunni classe 1C\n\
+Alunno\n\
+1. ESPOSITO GENNARO\n\
+2. \"DE LUCA\" CHIARA\n\
+Stampato il 12/09/2024\n";
+        let result = import(csv);
+        assert_eq!(result.format, "classeviva");
+        assert_eq!(result.columns["name"], "Alunno");
+        assert_eq!(
+            result.roster.students,
+            vec!["Esposito Gennaro", "De Luca Chiara"]
+        );
+        assert_eq!(result.roster.errors, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,51 @@
+//! Any other CSV or spreadsheet
+//!
+//! Used when no other adapter recognises the file: the shared header
+//! search, or the first column when there is no header.
+
+use super::ImportAdapter;
+
+pub struct Generic;
+
+impl ImportAdapter for Generic {
+    fn id(&self) -> &'static str {
+        "generic"
+    }
+
+    fn detect(&self, _rows: &[Vec<String>]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_ops::import_adapters::{import_rows, read_rows, RosterImport};
+
+    fn import(content: &str) -> RosterImport {
+        import_rows(&read_rows(content.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_generic_csv() {
+        let result = import("Cognome;Nome\nRossi;Mario\n\nBianchi; Anna \n");
+        assert_eq!(result.format, "generic");
+        assert_eq!(result.roster.students, vec!["Rossi Mario", "Bianchi Anna"]);
+        assert!(result.roster.errors.is_empty());
+        assert_eq!(result.columns["surname"], "Cognome");
+        assert_eq!(result.columns["givenName"], "Nome");
+
+        // German header
+        let result = import("Nachname,Vorname,Klasse\nMüller,Hans,7b\n");
+        assert_eq!(result.roster.students, vec!["Müller Hans"]);
+        assert_eq!(result.columns["surname"], "Nachname");
+
+        let result = import("name,age\nLuca,11\n,12\nluca,11");
+        assert_eq!(result.roster.students, vec!["Luca"]);
+        assert_eq!(result.roster.errors.len(), 2);
+
+        // No header: first column
+        let result = import("Mario Rossi\nAnna Bianchi");
+        assert_eq!(result.roster.students, vec!["Mario Rossi", "Anna Bianchi"]);
+        assert!(result.columns.is_empty());
+    }
+}
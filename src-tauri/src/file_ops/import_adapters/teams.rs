@@ -0,0 +1,78 @@
+//! Microsoft Teams member lists
+//!
+//! The class team's members exported to CSV (`Get-TeamUser | Export-Csv`,
+//! or the admin center's export): `User` (the sign-in address), `Name` or
+//! `DisplayName`, and `Role`. Owners are the teachers and are left out.
+
+use super::{normalize_cell, read_names_where, ColumnMap, ImportAdapter, NameLayout};
+use crate::roster::ParsedRoster;
+
+const NAME_HEADERS: &[&str] = &["name", "displayname", "display name"];
+const USER_HEADERS: &[&str] = &["user", "userprincipalname", "upn"];
+const ROLE_HEADER: &str = "role";
+const OWNER_ROLE: &str = "owner";
+
+pub struct Teams;
+
+/// Header row with its name and role columns
+fn find_columns(rows: &[Vec<String>]) -> Option<(usize, usize, usize)> {
+    rows.iter()
+        .take(super::HEADER_SCAN_ROWS)
+        .enumerate()
+        .find_map(|(index, row)| {
+            let cells: Vec<String> = row.iter().map(|c| normalize_cell(c)).collect();
+            let find = |names: &[&str]| cells.iter().position(|c| names.contains(&c.as_str()));
+            find(USER_HEADERS)?;
+            Some((index, find(NAME_HEADERS)?, find(&[ROLE_HEADER])?))
+        })
+}
+
+impl ImportAdapter for Teams {
+    fn id(&self) -> &'static str {
+        "teams"
+    }
+
+    fn detect(&self, rows: &[Vec<String>]) -> bool {
+        find_columns(rows).is_some()
+    }
+
+    fn map(&self, rows: &[Vec<String>]) -> Option<ColumnMap> {
+        let (header_row, name, _) = find_columns(rows)?;
+        Some(ColumnMap {
+            header_row,
+            data_start: header_row + 1,
+            layout: NameLayout::Full(name),
+        })
+    }
+
+    fn parse(&self, rows: &[Vec<String>], map: &ColumnMap) -> ParsedRoster {
+        let role = find_columns(rows).map(|(_, _, role)| role);
+        read_names_where(rows, map, &[], |row| {
+            role.and_then(|col| row.get(col))
+                .is_none_or(|r| !r.trim().eq_ignore_ascii_case(OWNER_ROLE))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_ops::import_adapters::{import_rows, read_rows};
+
+    #[test]
+    fn test_teams_members_without_owners() {
+        let csv = "\
+UserId,User,Name,Role\n\
+a1,m.bianchi@scuola.it,Maria Bianchi,Owner\n\
+b2,l.rossi@studenti.scuola.it,Luca Rossi,Member\n\
+c3,s.verdi@studenti.scuola.it,Sara Verdi,member\n";
+        let result = import_rows(&read_rows(csv.as_bytes()).unwrap());
+        assert_eq!(result.format, "teams");
+        assert_eq!(result.columns["name"], "Name");
+        assert_eq!(result.roster.students, vec!["Luca Rossi", "Sara Verdi"]);
+        assert!(result.roster.errors.is_empty());
+
+        // A plain name list is not a Teams export
+        let result = import_rows(&read_rows(b"Name,Role\nLuca Rossi,student\n").unwrap());
+        assert_eq!(result.format, "generic");
+    }
+}
@@ -0,0 +1,60 @@
+//! Stages of a file import, reported as they happen
+//!
+//! On a slow network drive a roster file can take long enough that a
+//! spinner looks like a hang. Imports run as jobs report each stage with
+//! its own counts (see `JobContext::stage`), so the import dialog can say
+//! where it is:
+//! - `reading`: bytes read from the file
+//! - `decoding`: bytes decoded (text encoding or spreadsheet container)
+//! - `parsing`: rows split into cells
+//! - `validating`: data rows checked as student names
+//! - `persisting`: students saved to the class
+//!
+//! Every stage reports `0` of its total when it starts and the total when
+//! it is done; reading also reports as chunks arrive, parsing every
+//! thousand rows.
+
+use crate::jobs::JobContext;
+use serde::{Deserialize, Serialize};
+
+/// A stage of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStage {
+    Reading,
+    Decoding,
+    Parsing,
+    Validating,
+    Persisting,
+}
+
+impl ImportStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportStage::Reading => "reading",
+            ImportStage::Decoding => "decoding",
+            ImportStage::Parsing => "parsing",
+            ImportStage::Validating => "validating",
+            ImportStage::Persisting => "persisting",
+        }
+    }
+}
+
+/// Receives the stages of an import
+pub trait ImportProgress {
+    /// `done` of `total` (`None` when unknown) in `stage`
+    fn report(&self, stage: ImportStage, done: usize, total: Option<usize>);
+}
+
+/// For imports nobody watches (previews, watched-folder updates)
+pub struct NoProgress;
+
+impl ImportProgress for NoProgress {
+    fn report(&self, _stage: ImportStage, _done: usize, _total: Option<usize>) {}
+}
+
+impl ImportProgress for JobContext {
+    fn report(&self, stage: ImportStage, done: usize, total: Option<usize>) {
+        self.stage(stage.as_str(), done, total);
+    }
+}
@@ -0,0 +1,452 @@
+//! Column transforms applied while importing a roster
+//!
+//! The frontend can fix up a badly formatted file without editing it by
+//! sending a list of steps, run in order on every data row:
+//!
+//! ```json
+//! [
+//!   { "op": "trim", "column": "Studente" },
+//!   { "op": "replace", "column": "Studente", "pattern": "^\\d+\\.\\s*", "with": "" },
+//!   { "op": "split", "column": "Studente", "into": ["Cognome", "Nome"] }
+//! ]
+//! ```
+//!
+//! Columns are named by header (case-insensitive) or by 0-based index.
+//! Steps writing to a header that doesn't exist add the column, so a split
+//! into "Cognome"/"Nome" is then read like any registry export.
+//!
+//! The language has no loops, conditions or variables, and patterns use
+//! the `regex` crate (no backreferences, linear in the cell length), so a
+//! file of any content costs at most `steps × cells` to transform. Steps
+//! are checked and compiled once, before the first row.
+
+use crate::errors::{self, BackendError};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Steps accepted in one import
+pub const MAX_STEPS: usize = 32;
+
+/// Longest `replace` pattern accepted
+const MAX_PATTERN_LEN: usize = 500;
+
+/// Compiled size limit of a `replace` pattern
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// A column of the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+    /// 0-based position
+    Index(usize),
+    /// Header text
+    Header(String),
+}
+
+/// One step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Transform {
+    /// Remove surrounding whitespace and collapse inner runs
+    Trim { column: Column },
+    /// "ROSSI mario" -> "Rossi Mario"
+    TitleCase { column: Column },
+    /// Split "Surname Name" into two columns; the last `given_words` words
+    /// (default 1) are the given name
+    #[serde(rename_all = "camelCase")]
+    Split {
+        column: Column,
+        into: [Column; 2],
+        given_words: Option<usize>,
+    },
+    /// Join the non-empty cells of `columns` with `separator` (default a
+    /// space)
+    Concat {
+        columns: Vec<Column>,
+        separator: Option<String>,
+        into: Column,
+    },
+    /// Replace every match of `pattern`; `with` may refer to groups as `$1`
+    Replace {
+        column: Column,
+        pattern: String,
+        #[serde(default)]
+        with: String,
+    },
+}
+
+impl Transform {
+    /// Columns named by header
+    fn headers(&self) -> Vec<&str> {
+        let columns: Vec<&Column> = match self {
+            Transform::Trim { column }
+            | Transform::TitleCase { column }
+            | Transform::Replace { column, .. } => vec![column],
+            Transform::Split { column, into, .. } => [column].into_iter().chain(into).collect(),
+            Transform::Concat { columns, into, .. } => columns.iter().chain([into]).collect(),
+        };
+        columns
+            .into_iter()
+            .filter_map(|c| match c {
+                Column::Header(name) => Some(name.as_str()),
+                Column::Index(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// A step with its columns resolved to indexes
+enum Step {
+    Trim(usize),
+    TitleCase(usize),
+    Split {
+        column: usize,
+        surname: usize,
+        given: usize,
+        given_words: usize,
+    },
+    Concat {
+        columns: Vec<usize>,
+        separator: String,
+        into: usize,
+    },
+    Replace {
+        column: usize,
+        regex: Regex,
+        with: String,
+    },
+}
+
+fn invalid(step: usize, message: impl Into<String>) -> BackendError {
+    BackendError::new(
+        errors::roster::INVALID_TRANSFORM,
+        "Invalid import transform",
+    )
+    .with_details(format!("Step {}: {}", step + 1, message.into()))
+}
+
+fn same_header(cell: &str, name: &str) -> bool {
+    cell.trim().to_lowercase() == name.trim().to_lowercase()
+}
+
+/// Resolves columns against the header row, adding output columns to it
+struct Resolver<'a> {
+    header: Option<&'a mut Vec<String>>,
+    step: usize,
+}
+
+impl Resolver<'_> {
+    fn source(&self, column: &Column) -> Result<usize, BackendError> {
+        match column {
+            Column::Index(index) => Ok(*index),
+            Column::Header(name) => self
+                .header
+                .as_ref()
+                .and_then(|header| header.iter().position(|c| same_header(c, name)))
+                .ok_or_else(|| invalid(self.step, format!("No column \"{}\"", name))),
+        }
+    }
+
+    fn target(&mut self, column: &Column) -> Result<usize, BackendError> {
+        if let Ok(index) = self.source(column) {
+            return Ok(index);
+        }
+        let (Column::Header(name), Some(header)) = (column, self.header.as_mut()) else {
+            return Err(invalid(self.step, "Output column needs a header row"));
+        };
+        header.push(name.trim().to_string());
+        Ok(header.len() - 1)
+    }
+}
+
+fn compile(
+    transforms: &[Transform],
+    header: Option<&mut Vec<String>>,
+) -> Result<Vec<Step>, BackendError> {
+    if transforms.len() > MAX_STEPS {
+        return Err(invalid(
+            MAX_STEPS,
+            format!("At most {} steps are allowed", MAX_STEPS),
+        ));
+    }
+    let mut resolver = Resolver { header, step: 0 };
+    let mut steps = Vec::with_capacity(transforms.len());
+    for (index, transform) in transforms.iter().enumerate() {
+        resolver.step = index;
+        let step = match transform {
+            Transform::Trim { column } => Step::Trim(resolver.source(column)?),
+            Transform::TitleCase { column } => Step::TitleCase(resolver.source(column)?),
+            Transform::Split {
+                column,
+                into,
+                given_words,
+            } => {
+                let given_words = given_words.unwrap_or(1);
+                if given_words == 0 {
+                    return Err(invalid(index, "givenWords must be at least 1"));
+                }
+                Step::Split {
+                    column: resolver.source(column)?,
+                    surname: resolver.target(&into[0])?,
+                    given: resolver.target(&into[1])?,
+                    given_words,
+                }
+            }
+            Transform::Concat {
+                columns,
+                separator,
+                into,
+            } => {
+                if columns.is_empty() {
+                    return Err(invalid(index, "No columns to join"));
+                }
+                Step::Concat {
+                    columns: columns
+                        .iter()
+                        .map(|c| resolver.source(c))
+                        .collect::<Result<_, _>>()?,
+                    separator: separator.clone().unwrap_or_else(|| " ".to_string()),
+                    into: resolver.target(into)?,
+                }
+            }
+            Transform::Replace {
+                column,
+                pattern,
+                with,
+            } => {
+                if pattern.len() > MAX_PATTERN_LEN {
+                    return Err(invalid(index, "Pattern is too long"));
+                }
+                let regex = RegexBuilder::new(pattern)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .map_err(|e| invalid(index, e.to_string()))?;
+                Step::Replace {
+                    column: resolver.source(column)?,
+                    regex,
+                    with: with.clone(),
+                }
+            }
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+/// Capitalise the first letter of every word, lower-case the rest
+/// ("D'ANGELO anna" -> "D'Angelo Anna")
+pub fn title_case(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if word_start {
+            out.extend(c.to_uppercase());
+        } else {
+            out.extend(c.to_lowercase());
+        }
+        word_start = !c.is_alphanumeric();
+    }
+    out
+}
+
+fn set(row: &mut Vec<String>, column: usize, value: String) {
+    if row.len() <= column {
+        row.resize(column + 1, String::new());
+    }
+    row[column] = value;
+}
+
+fn run(step: &Step, row: &mut Vec<String>) {
+    let cell = |row: &[String], column: usize| row.get(column).cloned().unwrap_or_default();
+    match step {
+        Step::Trim(column) => {
+            let value = cell(row, *column);
+            set(
+                row,
+                *column,
+                value.split_whitespace().collect::<Vec<_>>().join(" "),
+            );
+        }
+        Step::TitleCase(column) => {
+            let value = title_case(&cell(row, *column));
+            set(row, *column, value);
+        }
+        Step::Split {
+            column,
+            surname,
+            given,
+            given_words,
+        } => {
+            let value = cell(row, *column);
+            let words: Vec<&str> = value.split_whitespace().collect();
+            // A single word is a surname
+            let split = words.len() - (*given_words).min(words.len().saturating_sub(1));
+            set(row, *surname, words[..split].join(" "));
+            set(row, *given, words[split..].join(" "));
+        }
+        Step::Concat {
+            columns,
+            separator,
+            into,
+        } => {
+            let value = columns
+                .iter()
+                .map(|c| cell(row, *c))
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| v.trim().to_string())
+                .collect::<Vec<_>>()
+                .join(separator);
+            set(row, *into, value);
+        }
+        Step::Replace {
+            column,
+            regex,
+            with,
+        } => {
+            let value = cell(row, *column);
+            let replaced = regex.replace_all(&value, with.as_str()).into_owned();
+            set(row, *column, replaced);
+        }
+    }
+}
+
+/// Run `transforms` on the rows after `header_row` (all rows without one)
+///
+/// Without a detected header (the header isn't one the adapters know),
+/// the first row holding one of the column names is the header. Output
+/// columns named by header are added to the header row.
+pub fn apply(
+    rows: &mut [Vec<String>],
+    header_row: Option<usize>,
+    transforms: &[Transform],
+) -> Result<(), BackendError> {
+    if transforms.is_empty() {
+        return Ok(());
+    }
+    let header_row = header_row.or_else(|| {
+        let names: Vec<&str> = transforms.iter().flat_map(Transform::headers).collect();
+        rows.iter()
+            .position(|r| r.iter().any(|c| names.iter().any(|n| same_header(c, n))))
+    });
+    let (header, data) = match header_row {
+        Some(index) if index < rows.len() => {
+            let (head, data) = rows.split_at_mut(index + 1);
+            (head.last_mut(), data)
+        }
+        _ => (None, rows),
+    };
+    let steps = compile(transforms, header)?;
+    for row in data {
+        for step in &steps {
+            run(step, row);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows(lines: &[&[&str]]) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|l| l.iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    fn parse(steps: serde_json::Value) -> Vec<Transform> {
+        serde_json::from_value(steps).unwrap()
+    }
+
+    #[test]
+    fn test_steps_run_in_order() {
+        let mut data = rows(&[
+            &["Registro"],
+            &["N.", "Studente"],
+            &["1", "  1. DE LUCA   anna "],
+            &["2", "ROSSI"],
+        ]);
+        let steps = parse(json!([
+            { "op": "trim", "column": "studente" },
+            { "op": "replace", "column": "Studente", "pattern": "^\\d+\\.\\s*" },
+            { "op": "titleCase", "column": 1 },
+            { "op": "split", "column": "Studente", "into": ["Cognome", "Nome"] },
+            { "op": "concat", "columns": ["Nome", "Cognome"], "into": "Nominativo" }
+        ]));
+        apply(&mut data, Some(1), &steps).unwrap();
+
+        assert_eq!(data[0], ["Registro"]);
+        assert_eq!(data[1], ["N.", "Studente", "Cognome", "Nome", "Nominativo"]);
+        assert_eq!(
+            data[2],
+            ["1", "De Luca Anna", "De Luca", "Anna", "Anna De Luca"]
+        );
+        assert_eq!(data[3], ["2", "Rossi", "Rossi", "", "Rossi"]);
+    }
+
+    #[test]
+    fn test_headerless_file() {
+        let mut data = rows(&[&["rossi mario"], &["bianchi anna maria"]]);
+        let steps = parse(json!([
+            { "op": "titleCase", "column": 0 },
+            { "op": "split", "column": 0, "into": [1, 2], "givenWords": 2 }
+        ]));
+        apply(&mut data, None, &steps).unwrap();
+        assert_eq!(data[0], ["Rossi Mario", "Rossi", "Mario"]);
+        assert_eq!(data[1], ["Bianchi Anna Maria", "Bianchi", "Anna Maria"]);
+
+        // An unknown header is found by name
+        let mut data = rows(&[&["Classe 3A"], &["Allievo"], &["Rossi Mario"]]);
+        let steps =
+            parse(json!([{ "op": "split", "column": "allievo", "into": ["Cognome", "Nome"] }]));
+        apply(&mut data, None, &steps).unwrap();
+        assert_eq!(data[0], ["Classe 3A"]);
+        assert_eq!(data[1], ["Allievo", "Cognome", "Nome"]);
+        assert_eq!(data[2], ["Rossi Mario", "Rossi", "Mario"]);
+    }
+
+    #[test]
+    fn test_invalid_steps() {
+        let check = |steps: serde_json::Value, details: &str| {
+            let mut data = rows(&[&["Studente"], &["Rossi Mario"]]);
+            let err = apply(&mut data, Some(0), &parse(steps)).unwrap_err();
+            assert_eq!(err.code, errors::roster::INVALID_TRANSFORM);
+            assert!(
+                err.details.as_deref().unwrap().contains(details),
+                "{:?}",
+                err
+            );
+            // Nothing was changed
+            assert_eq!(data, rows(&[&["Studente"], &["Rossi Mario"]]));
+        };
+        check(
+            json!([{ "op": "trim", "column": 0 }, { "op": "trim", "column": "Classe" }]),
+            "Step 2: No column \"Classe\"",
+        );
+        check(
+            json!([{ "op": "replace", "column": 0, "pattern": "(" }]),
+            "Step 1",
+        );
+        check(
+            json!([{ "op": "replace", "column": 0, "pattern": "a{1000}{1000}" }]),
+            "Step 1",
+        );
+        check(
+            json!([{ "op": "concat", "columns": [], "into": 1 }]),
+            "No columns",
+        );
+        check(
+            json!([{ "op": "split", "column": 0, "into": [1, 2], "givenWords": 0 }]),
+            "givenWords",
+        );
+        check(
+            json!(vec![json!({ "op": "trim", "column": 0 }); MAX_STEPS + 1]),
+            "At most",
+        );
+
+        let unknown = serde_json::from_value::<Vec<Transform>>(json!([{ "op": "eval" }]));
+        assert!(unknown.is_err());
+    }
+}
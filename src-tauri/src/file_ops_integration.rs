@@ -8,10 +8,17 @@ mod integration_tests {
     use std::path::PathBuf;
     use tempfile::TempDir;
 
+    /// The records `read_csv` would send for `content`
+    fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, crate::errors::BackendError> {
+        let table = file_ops::CsvTable::parse(content.to_string())?;
+        let json = serde_json::to_value(&table).unwrap();
+        Ok(serde_json::from_value(json["records"].clone()).unwrap())
+    }
+
     #[test]
     fn test_csv_with_utf8_encoding() {
         let csv_content = "Name,Age,City\nAlice,25,Roma\nBob,30,Milano";
-        let records = file_ops::parse_csv(csv_content).expect("Failed to parse CSV");
+        let records = parse_csv(csv_content).expect("Failed to parse CSV");
 
         assert_eq!(records.len(), 3);
         assert_eq!(records[0], vec!["Name", "Age", "City"]);
@@ -22,7 +29,7 @@ mod integration_tests {
     #[test]
     fn test_csv_with_whitespace_trimming() {
         let csv_content = "Name , Age , Class\n  Alice  , 25 ,  A  ";
-        let records = file_ops::parse_csv(csv_content).expect("Failed to parse CSV");
+        let records = parse_csv(csv_content).expect("Failed to parse CSV");
 
         // Should trim whitespace
         assert_eq!(records[1], vec!["Alice", "25", "A"]);
@@ -31,7 +38,7 @@ mod integration_tests {
     #[test]
     fn test_csv_empty_file_error() {
         let csv_content = "";
-        let result = file_ops::parse_csv(csv_content);
+        let result = parse_csv(csv_content);
 
         assert!(result.is_err(), "Should return error for empty CSV");
     }
@@ -53,7 +60,7 @@ mod integration_tests {
 3,Charlie,Verdi,3B
 4,Diana,Neri,3B"#;
 
-        let records = file_ops::parse_csv(csv_content).expect("Failed to parse CSV");
+        let records = parse_csv(csv_content).expect("Failed to parse CSV");
 
         assert_eq!(records.len(), 5); // Header + 4 students
         assert_eq!(records[0], vec!["ID", "Nome", "Cognome", "Classe"]);
@@ -64,7 +71,7 @@ mod integration_tests {
     #[test]
     fn test_csv_italian_characters() {
         let csv_content = "Nome,Nota\nFrancesco,Molto bravo ✓\nGiuseppe,Eccellente!";
-        let records = file_ops::parse_csv(csv_content).expect("Failed to parse CSV");
+        let records = parse_csv(csv_content).expect("Failed to parse CSV");
 
         assert_eq!(records[1][0], "Francesco");
         assert!(records[1][1].contains("bravo"));
@@ -75,7 +82,7 @@ mod integration_tests {
         // CSV with invalid format should still parse (it's lenient)
         let csv_content = "A,B,C\nD,E"; // Missing last column in second row
 
-        let result = file_ops::parse_csv(csv_content);
+        let result = parse_csv(csv_content);
         assert!(result.is_ok(), "Should handle missing columns gracefully");
     }
 }
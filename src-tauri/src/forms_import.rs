@@ -0,0 +1,692 @@
+//! Quiz results from Google Forms / Microsoft Forms
+//!
+//! Handles:
+//! - Reading the responses export (CSV, or XLSX from Microsoft Forms):
+//!   respondent email, name (one column or "Cognome" + "Nome") and total
+//!   score, with column names from `header_synonyms`
+//! - Matching respondents to the students of a class: by school email
+//!   first, then by fuzzy name (see `fuzzy`)
+//! - Converting scores to 1–10 grades and recording them as one
+//!   assessment in the gradebook
+//! - Reporting respondents that could not be matched, and students with no
+//!   response
+//! - A strict mode for schools that want all-or-nothing loads: any
+//!   unmatched respondent rejects the file with a report of every problem
+//!   row, and nothing is recorded
+//!
+//! A student matched by name has the respondent's email saved on import,
+//! so the next quiz matches by email. Google exports the score as
+//! `7 / 10`; Microsoft exports only the points, so the maximum must be
+//! given.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops::header_synonyms::{HeaderDictionary, HeaderField};
+use crate::file_ops::import_adapters;
+use crate::fuzzy;
+use crate::gradebook::{self, GradeStore};
+use crate::import_history::{self, ImportKind, ImportSource};
+use crate::roster::{ClassData, RosterStore};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+const SUPPORTED_EXTENSIONS: &[&str] = &["csv", "xlsx"];
+
+/// Headers only Microsoft Forms writes
+const MICROSOFT_MARKERS: &[&str] = &["completion time", "ora di completamento", "total points"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormsSource {
+    Google,
+    Microsoft,
+}
+
+impl FormsSource {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FormsSource::Google => "google",
+            FormsSource::Microsoft => "microsoft",
+        }
+    }
+}
+
+/// One response row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Respondent {
+    /// Row in the file (1-based, header included)
+    pub row: usize,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub points: Option<f64>,
+    pub max_points: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormsResults {
+    pub source: FormsSource,
+    /// Field ("score", "email", "name", "surname", "firstName") → header
+    /// of the column it was read from
+    pub columns: BTreeMap<String, String>,
+    pub respondents: Vec<Respondent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchedBy {
+    Email,
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchedResult {
+    pub row: usize,
+    pub student_id: String,
+    pub student_name: String,
+    /// Name as typed in the form
+    pub respondent: Option<String>,
+    pub matched_by: MatchedBy,
+    pub points: f64,
+    pub max_points: f64,
+    pub grade: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnmatchedReason {
+    /// No student with this email or a similar name
+    NoMatch,
+    /// The student was already matched by an earlier row
+    Duplicate,
+    /// Score missing or not a number
+    NoScore,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedRespondent {
+    pub row: usize,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub reason: UnmatchedReason,
+}
+
+/// Outcome of a preview or an import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormsImportReport {
+    pub source: FormsSource,
+    pub matched: Vec<MatchedResult>,
+    pub unmatched: Vec<UnmatchedRespondent>,
+    /// Students of the class without a response
+    pub missing_students: Vec<String>,
+    /// Whether the grades were recorded
+    pub applied: bool,
+}
+
+fn invalid_file(message: &str, details: impl Into<String>) -> BackendError {
+    BackendError::new(errors::file::INVALID_FORMAT, message).with_details(details)
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.trim()
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+}
+
+/// `7 / 10` → (7, Some(10)); `7` → (7, None)
+fn parse_score(text: &str) -> Option<(f64, Option<f64>)> {
+    match text.split_once('/') {
+        Some((points, max)) => Some((parse_number(points)?, Some(parse_number(max)?))),
+        None => Some((parse_number(text)?, None)),
+    }
+}
+
+fn non_empty(text: Option<&String>) -> Option<String> {
+    text.map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// Read respondents from the rows of a Forms export
+pub fn parse_forms_rows(rows: &[Vec<String>]) -> Result<FormsResults, BackendError> {
+    let header_row = rows
+        .first()
+        .ok_or_else(|| invalid_file("File is empty", ""))?;
+    let header: Vec<String> = header_row.iter().map(|h| fuzzy::fold(h)).collect();
+    let dictionary = HeaderDictionary::load();
+    let find = |field| dictionary.find(field, header_row);
+    let score = find(HeaderField::Score).ok_or_else(|| {
+        invalid_file(
+            "No score column found; export the responses of a quiz",
+            header_row.join(", "),
+        )
+    })?;
+    let email = find(HeaderField::Email);
+    // Separate surname and first name columns win over a single "Nome"
+    let split_name = find(HeaderField::Surname).zip(find(HeaderField::GivenName));
+    let name = find(HeaderField::FullName).or_else(|| find(HeaderField::GivenName));
+    if email.is_none() && name.is_none() && split_name.is_none() {
+        return Err(invalid_file(
+            "No email or name column found",
+            header_row.join(", "),
+        ));
+    }
+    let mut columns = BTreeMap::new();
+    let mut column = |field: &str, col: Option<usize>| {
+        if let Some(col) = col {
+            columns.insert(field.to_string(), header_row[col].trim().to_string());
+        }
+    };
+    column("score", Some(score));
+    column("email", email);
+    match split_name {
+        Some((surname, first)) => {
+            column("surname", Some(surname));
+            column("firstName", Some(first));
+        }
+        None => column("name", name),
+    }
+    let source = if header
+        .iter()
+        .any(|h| MICROSOFT_MARKERS.contains(&h.as_str()))
+    {
+        FormsSource::Microsoft
+    } else {
+        FormsSource::Google
+    };
+
+    let respondents = rows
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, row)| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|(i, row)| {
+            let name = match split_name {
+                Some((surname, first)) => {
+                    let full = format!(
+                        "{} {}",
+                        row.get(surname).map_or("", |s| s.trim()),
+                        row.get(first).map_or("", |s| s.trim())
+                    );
+                    non_empty(Some(&full))
+                }
+                None => name.and_then(|c| non_empty(row.get(c))),
+            };
+            let parsed = row.get(score).and_then(|s| parse_score(s));
+            Respondent {
+                row: i + 1,
+                email: email
+                    .and_then(|c| non_empty(row.get(c)))
+                    .map(|e| e.to_lowercase()),
+                name,
+                points: parsed.map(|(points, _)| points),
+                max_points: parsed.and_then(|(_, max)| max),
+            }
+        })
+        .collect();
+    Ok(FormsResults {
+        source,
+        columns,
+        respondents,
+    })
+}
+
+/// 1–10 grade from points: proportional, at least 1, to the nearest quarter
+pub fn grade_from_points(points: f64, max_points: f64) -> f64 {
+    let ratio = (points / max_points).clamp(0.0, 1.0);
+    ((ratio * 10.0 * 4.0).round() / 4.0).clamp(gradebook::MIN_GRADE, gradebook::MAX_GRADE)
+}
+
+/// Match respondents to students and compute grades
+pub fn match_results(
+    results: &FormsResults,
+    class: &ClassData,
+    max_points: Option<f64>,
+) -> FormsImportReport {
+    let mut matched: Vec<MatchedResult> = Vec::new();
+    let mut unmatched = Vec::new();
+    let mut by_name: Vec<&Respondent> = Vec::new();
+    let mut taken = vec![false; class.students.len()];
+
+    let unmatched_as = |r: &Respondent, reason| -> UnmatchedRespondent {
+        UnmatchedRespondent {
+            row: r.row,
+            name: r.name.clone(),
+            email: r.email.clone(),
+            reason,
+        }
+    };
+    let mut scored: Vec<(&Respondent, f64, f64)> = Vec::new();
+    for respondent in &results.respondents {
+        match (respondent.points, respondent.max_points.or(max_points)) {
+            (Some(points), Some(max)) if max > 0.0 => scored.push((respondent, points, max)),
+            _ => unmatched.push(unmatched_as(respondent, UnmatchedReason::NoScore)),
+        }
+    }
+
+    let result = |index: usize, respondent: &Respondent, by, points, max| {
+        let student = &class.students[index];
+        MatchedResult {
+            row: respondent.row,
+            student_id: student.id.clone(),
+            student_name: student.name.clone(),
+            respondent: respondent.name.clone(),
+            matched_by: by,
+            points,
+            max_points: max,
+            grade: grade_from_points(points, max),
+        }
+    };
+
+    // Email first: exact and unambiguous
+    let mut scores_by_row = std::collections::HashMap::new();
+    for &(respondent, points, max) in &scored {
+        scores_by_row.insert(respondent.row, (points, max));
+        let index = respondent.email.as_deref().and_then(|email| {
+            class.students.iter().position(|s| {
+                s.email
+                    .as_deref()
+                    .is_some_and(|e| e.eq_ignore_ascii_case(email))
+            })
+        });
+        match index {
+            Some(i) if taken[i] => {
+                unmatched.push(unmatched_as(respondent, UnmatchedReason::Duplicate))
+            }
+            Some(i) => {
+                taken[i] = true;
+                matched.push(result(i, respondent, MatchedBy::Email, points, max));
+            }
+            None => by_name.push(respondent),
+        }
+    }
+
+    // Then names, against students not matched by email
+    let free: Vec<usize> = (0..class.students.len()).filter(|i| !taken[*i]).collect();
+    let targets: Vec<String> = free
+        .iter()
+        .map(|i| class.students[*i].name.clone())
+        .collect();
+    let candidates: Vec<String> = by_name
+        .iter()
+        .map(|r| r.name.clone().unwrap_or_default())
+        .collect();
+    let name_matches = fuzzy::match_names(&candidates, &targets, fuzzy::DEFAULT_THRESHOLD);
+    for (respondent, m) in by_name.iter().zip(name_matches) {
+        let (points, max) = scores_by_row[&respondent.row];
+        match m.target_index {
+            Some(t) => {
+                taken[free[t]] = true;
+                matched.push(result(free[t], respondent, MatchedBy::Name, points, max));
+            }
+            // Same name as a student matched by an earlier row
+            None if respondent.name.as_deref().is_some_and(|name| {
+                matched
+                    .iter()
+                    .any(|m| fuzzy::similarity(name, &m.student_name) >= fuzzy::DEFAULT_THRESHOLD)
+            }) =>
+            {
+                unmatched.push(unmatched_as(respondent, UnmatchedReason::Duplicate))
+            }
+            None => unmatched.push(unmatched_as(respondent, UnmatchedReason::NoMatch)),
+        }
+    }
+
+    matched.sort_by_key(|m| m.row);
+    unmatched.sort_by_key(|u| u.row);
+    FormsImportReport {
+        source: results.source,
+        matched,
+        unmatched,
+        missing_students: class
+            .students
+            .iter()
+            .zip(&taken)
+            .filter(|(_, taken)| !**taken)
+            .map(|(s, _)| s.name.clone())
+            .collect(),
+        applied: false,
+    }
+}
+
+fn read_results(path: &Path) -> Result<FormsResults, BackendError> {
+    let supported = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return Err(invalid_file(
+            "File must be a Forms export (.csv or .xlsx)",
+            path.display().to_string(),
+        ));
+    }
+    if std::fs::metadata(path)?.len() > MAX_FILE_BYTES {
+        return Err(invalid_file(
+            "File is too large to be a quiz export",
+            path.display().to_string(),
+        ));
+    }
+    let bytes = std::fs::read(path)?;
+    parse_forms_rows(&import_adapters::read_rows(&bytes)?)
+}
+
+fn load_class(store: &RosterStore, class_id: &str) -> Result<ClassData, BackendError> {
+    store.find(class_id).cloned().ok_or_else(|| {
+        BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+            .with_details(class_id.to_string())
+    })
+}
+
+/// Match a Forms export against a class without recording anything
+pub fn preview_forms_results(
+    class_id: &str,
+    path: &Path,
+    max_points: Option<f64>,
+) -> Result<FormsImportReport, BackendError> {
+    let class = load_class(&RosterStore::load()?, class_id)?;
+    Ok(match_results(&read_results(path)?, &class, max_points))
+}
+
+/// One line per respondent a strict import rejects the file for
+fn strict_problems(report: &FormsImportReport) -> Vec<String> {
+    report
+        .unmatched
+        .iter()
+        .map(|u| {
+            let who = [u.name.as_deref(), u.email.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", ");
+            let problem = match u.reason {
+                UnmatchedReason::NoMatch => "No matching student",
+                UnmatchedReason::Duplicate => "Student already matched by an earlier row",
+                UnmatchedReason::NoScore => "Score missing or not a number",
+            };
+            if who.is_empty() {
+                format!("Row {}: {}", u.row, problem)
+            } else {
+                format!("Row {}: {} ({})", u.row, problem, who)
+            }
+        })
+        .collect()
+}
+
+/// Record the matched results of a Forms export as one assessment
+///
+/// With `strict`, any unmatched respondent fails the import with
+/// `IMPORT_STRICT_REJECTED` before anything is saved. The grades and the
+/// students' emails are saved as one unit (see `ImportStores::save_all`).
+pub fn import_forms_results(
+    class_id: &str,
+    path: &Path,
+    assessment: &str,
+    weight: f64,
+    max_points: Option<f64>,
+    strict: bool,
+) -> Result<FormsImportReport, BackendError> {
+    let source = ImportSource::from_path(path);
+    import_history::track(ImportKind::Forms, source, |history| {
+        history.class_id = Some(class_id.to_string());
+        let results = read_results(path)?;
+        history.format = Some(results.source.code().to_string());
+        history.columns = results.columns.clone();
+        history.rows = results.respondents.len();
+        let report =
+            match record_results(&results, class_id, assessment, weight, max_points, strict) {
+                Ok(report) => report,
+                Err(Rejected::Problems(report)) => {
+                    history.errors = strict_problems(&report);
+                    history.skipped = report.unmatched.len();
+                    return Err(BackendError::new(
+                        errors::import::STRICT_REJECTED,
+                        "Quiz results have unmatched respondents; nothing was recorded",
+                    )
+                    .with_details(history.errors.join("; ")));
+                }
+                Err(Rejected::Error(e)) => return Err(e),
+            };
+        history.imported = report.matched.len();
+        history.skipped = report.unmatched.len();
+        Ok(report)
+    })
+}
+
+/// Why `record_results` recorded nothing
+#[derive(Debug)]
+enum Rejected {
+    /// Strict import with unmatched respondents
+    Problems(FormsImportReport),
+    Error(BackendError),
+}
+
+impl From<BackendError> for Rejected {
+    fn from(e: BackendError) -> Self {
+        Rejected::Error(e)
+    }
+}
+
+/// Stores an import writes
+#[derive(Debug, Clone)]
+struct ImportStores {
+    grades: GradeStore,
+    roster: RosterStore,
+}
+
+impl ImportStores {
+    fn load() -> Result<Self, BackendError> {
+        Ok(Self {
+            grades: GradeStore::load()?,
+            roster: RosterStore::load()?,
+        })
+    }
+
+    /// Save the grades, then the roster if `roster_changed`; if the roster
+    /// fails, put the previous grades back
+    fn save_all(&self, previous: &ImportStores, roster_changed: bool) -> Result<(), BackendError> {
+        self.grades.save()?;
+        if roster_changed {
+            if let Err(e) = self.roster.save() {
+                if let Err(undo_err) = previous.grades.save() {
+                    eprintln!("Failed to roll back quiz import: {}", undo_err.message);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn record_results(
+    results: &FormsResults,
+    class_id: &str,
+    assessment: &str,
+    weight: f64,
+    max_points: Option<f64>,
+    strict: bool,
+) -> Result<FormsImportReport, Rejected> {
+    let previous = ImportStores::load()?;
+    let class = load_class(&previous.roster, class_id)?;
+    let mut report = match_results(results, &class, max_points);
+    if strict && !report.unmatched.is_empty() {
+        return Err(Rejected::Problems(report));
+    }
+    let grades: Vec<(String, f64)> = report
+        .matched
+        .iter()
+        .map(|m| (m.student_id.clone(), m.grade))
+        .collect();
+    let mut stores = previous.clone();
+    stores.grades.scores.extend(gradebook::class_scores(
+        &class,
+        assessment,
+        weight,
+        &grades,
+        clock::now_millis(),
+    )?);
+
+    // Remember emails of students matched by name
+    let emails: Vec<(&str, &str)> = report
+        .matched
+        .iter()
+        .filter(|m| m.matched_by == MatchedBy::Name)
+        .filter_map(|m| {
+            let respondent = results.respondents.iter().find(|r| r.row == m.row)?;
+            Some((m.student_id.as_str(), respondent.email.as_deref()?))
+        })
+        .collect();
+    let mut changed = false;
+    if let Some(class) = stores.roster.classes.iter_mut().find(|c| c.id == class_id) {
+        for student in class.students.iter_mut().filter(|s| s.email.is_none()) {
+            if let Some((_, email)) = emails.iter().find(|(id, _)| *id == student.id) {
+                student.email = Some(email.to_string());
+                changed = true;
+            }
+        }
+    }
+    stores.save_all(&previous, changed)?;
+    report.applied = true;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_ops;
+    use crate::roster::ClassBuilder;
+
+    fn rows(csv: &str) -> Vec<Vec<String>> {
+        import_adapters::read_rows(csv.as_bytes()).unwrap()
+    }
+
+    fn class() -> ClassData {
+        let mut class = ClassBuilder::new("c1", "3A")
+            .students(&["Rossi Mario", "Bianchi Anna", "Verdi Luca"])
+            .build();
+        class.students[0].email = Some("m.rossi@scuola.it".into());
+        class
+    }
+
+    #[test]
+    fn test_parse_google_export() {
+        let results = parse_forms_rows(&rows(
+            "Informazioni cronologiche,Indirizzo email,Punteggio,Nome e cognome,\"Domanda 1\"\n\
+             2026/03/02 10:01,M.Rossi@scuola.it,8 / 10,mario rossi,\"risposta\nsu due righe\"\n\
+             2026/03/02 10:02,anna@gmail.com,\"6,5 / 10\",Anna Bianchi,b\n",
+        ))
+        .unwrap();
+        assert_eq!(results.source, FormsSource::Google);
+        assert_eq!(results.columns["score"], "Punteggio");
+        assert_eq!(results.columns["name"], "Nome e cognome");
+        assert_eq!(results.respondents.len(), 2);
+        let first = &results.respondents[0];
+        assert_eq!(first.email.as_deref(), Some("m.rossi@scuola.it"));
+        assert_eq!((first.points, first.max_points), (Some(8.0), Some(10.0)));
+        assert_eq!(results.respondents[1].points, Some(6.5));
+        assert_eq!(results.respondents[1].row, 3);
+    }
+
+    #[test]
+    fn test_parse_microsoft_export() {
+        let results = parse_forms_rows(&rows(
+            "ID;Start time;Completion time;Email;Name;Total points;Quiz feedback\n\
+             1;x;y;anon;Luca Verdi;14;\n",
+        ))
+        .unwrap();
+        assert_eq!(results.source, FormsSource::Microsoft);
+        assert_eq!(results.respondents[0].points, Some(14.0));
+        assert_eq!(results.respondents[0].max_points, None);
+        assert!(parse_forms_rows(&rows("Email,Answer\na@b.it,x\n")).is_err());
+    }
+
+    #[test]
+    fn test_match_results() {
+        let results = parse_forms_rows(&rows(
+            "Email,Name,Score\n\
+             M.ROSSI@scuola.it,Super Mario,9 / 10\n\
+             anna@gmail.com,Anna Bianchi,6 / 10\n\
+             anna@gmail.com,Anna Bianchi,7 / 10\n\
+             x@y.it,Giulia Neri,5 / 10\n\
+             z@y.it,Luca Verdi,\n",
+        ))
+        .unwrap();
+        let report = match_results(&results, &class(), None);
+        let matched: Vec<_> = report
+            .matched
+            .iter()
+            .map(|m| (m.student_id.as_str(), m.matched_by, m.grade))
+            .collect();
+        assert_eq!(
+            matched,
+            [("s0", MatchedBy::Email, 9.0), ("s1", MatchedBy::Name, 6.0)]
+        );
+        let unmatched: Vec<_> = report.unmatched.iter().map(|u| (u.row, u.reason)).collect();
+        assert_eq!(
+            unmatched,
+            [
+                (4, UnmatchedReason::Duplicate),
+                (5, UnmatchedReason::NoMatch),
+                (6, UnmatchedReason::NoScore)
+            ]
+        );
+        assert_eq!(report.missing_students, ["Verdi Luca"]);
+
+        // What a strict import reports
+        assert_eq!(
+            strict_problems(&report),
+            [
+                "Row 4: Student already matched by an earlier row (Anna Bianchi, anna@gmail.com)",
+                "Row 5: No matching student (Giulia Neri, x@y.it)",
+                "Row 6: Score missing or not a number (Luca Verdi, z@y.it)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_roster_save_restores_grades() {
+        file_ops::with_temp_data_dir(|dir| {
+            let previous = ImportStores {
+                grades: GradeStore::default(),
+                roster: RosterStore {
+                    classes: vec![class()],
+                },
+            };
+            previous.grades.save().unwrap();
+            previous.roster.save().unwrap();
+            let mut stores = previous.clone();
+            stores.grades.scores =
+                gradebook::class_scores(&class(), "Quiz", 1.0, &[("s1".into(), 8.0)], 1).unwrap();
+            stores.roster.classes[0].students[1].email = Some("anna@gmail.com".into());
+
+            // The roster file can't be written
+            let roster_file = dir.join("data").join("rosters.json");
+            std::fs::remove_file(&roster_file).unwrap();
+            std::fs::create_dir(&roster_file).unwrap();
+
+            let err = stores.save_all(&previous, true).unwrap_err();
+            assert_eq!(err.code, errors::file::IO_ERROR);
+            assert!(GradeStore::load().unwrap().scores.is_empty());
+
+            // Without roster changes only the grades are written
+            stores.save_all(&previous, false).unwrap();
+            assert_eq!(GradeStore::load().unwrap().scores.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_grade_from_points() {
+        assert_eq!(grade_from_points(14.0, 20.0), 7.0);
+        assert_eq!(grade_from_points(13.0, 20.0), 6.5);
+        assert_eq!(grade_from_points(0.0, 20.0), 1.0);
+        assert_eq!(grade_from_points(25.0, 20.0), 10.0);
+        assert_eq!(grade_from_points(2.0, 3.0), 6.75);
+    }
+}
@@ -0,0 +1,163 @@
+//! Emergency "freeze" broadcast (fire drill, lockdown)
+//!
+//! Handles:
+//! - One action for the announcements of the school safety plan: the
+//!   projector dims fully with the message on top (see `projector_dim`),
+//!   the attention chime plays on the alerts output (see `audio_output`)
+//!   and every window and companion device is told
+//! - Releasing the freeze, which removes the dimming
+//!
+//! Every step is attempted even when an earlier one fails: a projector
+//! that is unplugged must not keep the message from reaching the devices.
+//! Steps that failed are listed in the freeze so the teacher knows.
+//!
+//! Windows get `freeze-changed`; companion devices ask for the current
+//! freeze with their token through the LAN channel (`get_device_freeze`),
+//! which also works while the app is locked.
+
+use crate::audio_output;
+use crate::clock;
+use crate::companion_auth;
+use crate::errors::{self, BackendError};
+use crate::projector_dim;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted to every window when a freeze starts, changes or is released
+pub const FREEZE_EVENT: &str = "freeze-changed";
+
+static STATE: Mutex<Option<Freeze>> = Mutex::new(None);
+
+/// A freeze in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Freeze {
+    pub message: String,
+    pub started_at: u64,
+    /// Steps that failed ("projector: …"), empty when all went through
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FreezeStatus {
+    pub frozen: bool,
+    pub freeze: Option<Freeze>,
+}
+
+fn status(freeze: Option<Freeze>) -> FreezeStatus {
+    FreezeStatus {
+        frozen: freeze.is_some(),
+        freeze,
+    }
+}
+
+fn set_state(app: &AppHandle, freeze: Option<Freeze>) -> FreezeStatus {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = freeze.clone();
+    let status = status(freeze);
+    let _ = app.emit(FREEZE_EVENT, &status);
+    status
+}
+
+fn validate_message(message: &str) -> Result<String, BackendError> {
+    let message = message.trim();
+    if message.is_empty() || message.chars().count() > projector_dim::MAX_MESSAGE_CHARS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "The message must be 1-{} characters",
+                projector_dim::MAX_MESSAGE_CHARS
+            ),
+        ));
+    }
+    Ok(message.to_string())
+}
+
+/// Note a failed step, keeping the freeze going
+fn warn_on(warnings: &mut Vec<String>, step: &str, result: Result<(), BackendError>) {
+    if let Err(e) = result {
+        eprintln!("Freeze broadcast: {} failed: {}", step, e.message);
+        warnings.push(format!("{}: {}", step, e.message));
+    }
+}
+
+/// Current freeze
+pub fn get_freeze_status() -> FreezeStatus {
+    status(STATE.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Current freeze, for an authenticated companion device
+pub fn get_device_freeze(token: &str) -> Result<FreezeStatus, BackendError> {
+    companion_auth::authenticate(token)?;
+    Ok(get_freeze_status())
+}
+
+/// Freeze the classroom with `message`, or change the message of the
+/// running freeze (the chime plays again)
+pub fn broadcast_freeze(app: &AppHandle, message: &str) -> Result<FreezeStatus, BackendError> {
+    let message = validate_message(message)?;
+    let mut warnings = Vec::new();
+    warn_on(
+        &mut warnings,
+        "projector",
+        projector_dim::dim_projector(app, projector_dim::MAX_LEVEL, Some(message.clone()))
+            .map(|_| ()),
+    );
+    warn_on(
+        &mut warnings,
+        "sound",
+        audio_output::play_alert_chime(app, None),
+    );
+    let started_at = get_freeze_status()
+        .freeze
+        .map(|f| f.started_at)
+        .unwrap_or_else(clock::now_millis);
+    Ok(set_state(
+        app,
+        Some(Freeze {
+            message,
+            started_at,
+            warnings,
+        }),
+    ))
+}
+
+/// End the freeze and undim the projector
+pub fn release_freeze(app: &AppHandle) -> Result<FreezeStatus, BackendError> {
+    if get_freeze_status().frozen {
+        projector_dim::undim_projector(app)?;
+    }
+    Ok(set_state(app, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_message() {
+        assert_eq!(
+            validate_message("  Evacuazione: uscire in fila ").unwrap(),
+            "Evacuazione: uscire in fila"
+        );
+        assert!(validate_message("   ").is_err());
+        assert!(validate_message(&"x".repeat(projector_dim::MAX_MESSAGE_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_failed_steps_become_warnings() {
+        let mut warnings = Vec::new();
+        warn_on(&mut warnings, "sound", Ok(()));
+        warn_on(
+            &mut warnings,
+            "projector",
+            Err(BackendError::new(
+                errors::window::CREATE_FAILED,
+                "No projector connected",
+            )),
+        );
+        assert_eq!(warnings, ["projector: No projector connected"]);
+        assert!(!status(None).frozen);
+    }
+}
@@ -0,0 +1,163 @@
+//! Fuzzy name matching for imports
+//!
+//! Handles:
+//! - Folding names for comparison: accents removed, apostrophes dropped,
+//!   case and separators ignored ("D'Amìco" ≈ "Damico" ≈ "d amico")
+//! - Scoring two names (0.0-1.0) with Jaro-Winkler and normalized
+//!   Levenshtein, independent of word order ("Rossi Mario" = "Mario Rossi")
+//! - One-to-one matching of a list of names against another
+//!
+//! Used by photo import and roster merges, where names typed in the
+//! electronic registry rarely match a photographer's or a colleague's
+//! spelling exactly.
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Default minimum score for a match
+pub const DEFAULT_THRESHOLD: f64 = 0.9;
+
+/// Best target for a candidate name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameMatch {
+    pub candidate: String,
+    /// Matched target, `None` if nothing scored above the threshold
+    pub target: Option<String>,
+    /// Index into the targets
+    pub target_index: Option<usize>,
+    pub score: f64,
+    /// Equal after folding
+    pub exact: bool,
+}
+
+/// Lowercase, accent-free words; apostrophes join, other punctuation splits
+pub fn fold(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in name.nfd().filter(|c| !is_combining_mark(*c)) {
+        match c {
+            '\'' | '’' | '`' | '´' => {}
+            c if c.is_alphanumeric() => folded.extend(c.to_lowercase()),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Folded words in alphabetical order
+fn sorted_key(name: &str) -> String {
+    let folded = fold(name);
+    let mut words: Vec<&str> = folded.split(' ').collect();
+    words.sort_unstable();
+    words.join(" ")
+}
+
+/// Similarity of two names, 1.0 when equal after folding
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (sorted_key(a), sorted_key(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    // Word-order-independent strings plus a join-insensitive variant, so
+    // "De Luca" and "Deluca" compare well too
+    let joined = |s: &str| s.replace(' ', "");
+    strsim::jaro_winkler(&a, &b)
+        .max(strsim::normalized_levenshtein(&a, &b))
+        .max(strsim::jaro_winkler(&joined(&a), &joined(&b)))
+}
+
+/// Match each candidate to at most one target (and vice versa)
+///
+/// Pairs are assigned best score first, so the closest spelling wins when
+/// two candidates resemble the same target. Results are in candidate order.
+pub fn match_names(candidates: &[String], targets: &[String], threshold: f64) -> Vec<NameMatch> {
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+    for (c, candidate) in candidates.iter().enumerate() {
+        for (t, target) in targets.iter().enumerate() {
+            let score = similarity(candidate, target);
+            if score >= threshold {
+                pairs.push((score, c, t));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut results: Vec<NameMatch> = candidates
+        .iter()
+        .map(|candidate| NameMatch {
+            candidate: candidate.clone(),
+            target: None,
+            target_index: None,
+            score: 0.0,
+            exact: false,
+        })
+        .collect();
+    let mut target_used = vec![false; targets.len()];
+    for (score, c, t) in pairs {
+        if results[c].target.is_some() || target_used[t] {
+            continue;
+        }
+        target_used[t] = true;
+        results[c] = NameMatch {
+            candidate: candidates[c].clone(),
+            target: Some(targets[t].clone()),
+            target_index: Some(t),
+            score,
+            exact: sorted_key(&candidates[c]) == sorted_key(&targets[t]),
+        };
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fold_handles_italian_names() {
+        assert_eq!(fold("  Niccolò  D'Amico "), "niccolo damico");
+        assert_eq!(fold("NICCOLO' D’AMICO"), "niccolo damico");
+        assert_eq!(fold("Rossi_Mario-Luigi"), "rossi mario luigi");
+        assert_eq!(fold("Zoë Ünal"), "zoe unal");
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("Rossi Mario", "mario rossi"), 1.0);
+        assert_eq!(similarity("D'Amico Niccolò", "Niccolo Damico"), 1.0);
+        assert!(similarity("De Luca Anna", "Deluca Anna") > 0.95);
+        assert!(similarity("Mario Rosi", "Mario Rossi") > 0.9);
+        assert!(similarity("Mario Rossi", "Anna Verdi") < 0.7);
+        assert_eq!(similarity("", "Anna"), 0.0);
+    }
+
+    #[test]
+    fn test_match_names_is_one_to_one() {
+        let candidates = names(&["ROSSI_Mario", "Rossi Maria", "Bianchi Luca", "Sconosciuto"]);
+        let targets = names(&["Mario Rossi", "Maria Rossi", "Luca Bianchi"]);
+        let matches = match_names(&candidates, &targets, DEFAULT_THRESHOLD);
+
+        assert_eq!(matches[0].target_index, Some(0));
+        assert!(matches[0].exact);
+        assert_eq!(matches[1].target_index, Some(1));
+        assert_eq!(matches[2].target.as_deref(), Some("Luca Bianchi"));
+        assert_eq!(matches[3].target, None);
+
+        // Two candidates close to the same target: the closer one wins
+        let matches = match_names(
+            &names(&["Mario Rosi", "Mario Rossi"]),
+            &names(&["Mario Rossi"]),
+            0.8,
+        );
+        assert_eq!(matches[0].target, None);
+        assert_eq!(matches[1].target_index, Some(0));
+    }
+}
@@ -0,0 +1,725 @@
+//! Gradebook export for registry upload
+//!
+//! Handles:
+//! - Export templates: the column layout, delimiter, decimal separator and
+//!   date format a registry's bulk upload expects. Two built-in templates
+//!   (one row per score, one row per student average); schools add their
+//!   own in the `grade_export_templates` data collection
+//! - Writing a class's grades as CSV (UTF-8 with BOM, so Excel detects the
+//!   encoding) or as a single-sheet XLSX workbook
+//!
+//! The XLSX file is written directly (SpreadsheetML with inline strings);
+//! registries only read the first sheet's cells.
+
+use crate::class_records;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::gradebook::{self, AverageStrategy, GradeStore, Score};
+use crate::roster::ClassData;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const COLLECTION: &str = "grade_export_templates";
+const UTF8_BOM: &str = "\u{feff}";
+const MAX_COLUMNS: usize = 40;
+const MAX_ID_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// What each row of the export is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowMode {
+    /// One row per recorded score
+    Scores,
+    /// One row per student with their average
+    Averages,
+}
+
+/// Content of a column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "field", rename_all = "camelCase")]
+pub enum ColumnField {
+    StudentName,
+    StudentId,
+    ClassName,
+    /// Same text on every row (subject code, teacher code, ...)
+    Fixed {
+        value: String,
+    },
+    // Score rows
+    Assessment,
+    /// Numeric grade
+    Grade,
+    /// Grade as written (`6+`)
+    GradeLabel,
+    Weight,
+    Date,
+    // Average rows
+    Average,
+    /// Nearest quarter in Italian notation
+    AverageDisplay,
+    ReportGrade,
+    ScoreCount,
+}
+
+impl ColumnField {
+    fn allowed_in(&self, mode: RowMode) -> bool {
+        match self {
+            ColumnField::StudentName
+            | ColumnField::StudentId
+            | ColumnField::ClassName
+            | ColumnField::Fixed { .. } => true,
+            ColumnField::Assessment
+            | ColumnField::Grade
+            | ColumnField::GradeLabel
+            | ColumnField::Weight
+            | ColumnField::Date => mode == RowMode::Scores,
+            ColumnField::Average
+            | ColumnField::AverageDisplay
+            | ColumnField::ReportGrade
+            | ColumnField::ScoreCount => mode == RowMode::Averages,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportColumn {
+    pub header: String,
+    #[serde(flatten)]
+    pub field: ColumnField,
+}
+
+/// Column layout of an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GradeExportTemplate {
+    pub id: String,
+    pub name: String,
+    pub rows: RowMode,
+    pub columns: Vec<ExportColumn>,
+    /// CSV only
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Write `6,5` instead of `6.5` in CSV
+    #[serde(default)]
+    pub decimal_comma: bool,
+    /// chrono format of `date` columns
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Average used by average rows
+    #[serde(default = "default_strategy")]
+    pub strategy: AverageStrategy,
+    /// Write the header row
+    #[serde(default = "default_true")]
+    pub header: bool,
+    /// Built-in templates can't be changed or deleted
+    #[serde(default)]
+    pub built_in: bool,
+}
+
+fn default_delimiter() -> char {
+    ';'
+}
+
+fn default_date_format() -> String {
+    "%d/%m/%Y".to_string()
+}
+
+fn default_strategy() -> AverageStrategy {
+    AverageStrategy::Weighted
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn column(header: &str, field: ColumnField) -> ExportColumn {
+    ExportColumn {
+        header: header.to_string(),
+        field,
+    }
+}
+
+/// Templates shipped with the app
+pub fn built_in_templates() -> Vec<GradeExportTemplate> {
+    vec![
+        GradeExportTemplate {
+            id: "scores".into(),
+            name: "Voti (una riga per voto)".into(),
+            rows: RowMode::Scores,
+            columns: vec![
+                column("Alunno", ColumnField::StudentName),
+                column("Data", ColumnField::Date),
+                column("Prova", ColumnField::Assessment),
+                column("Voto", ColumnField::Grade),
+                column("Peso", ColumnField::Weight),
+            ],
+            delimiter: default_delimiter(),
+            decimal_comma: true,
+            date_format: default_date_format(),
+            strategy: default_strategy(),
+            header: true,
+            built_in: true,
+        },
+        GradeExportTemplate {
+            id: "averages".into(),
+            name: "Medie (una riga per alunno)".into(),
+            rows: RowMode::Averages,
+            columns: vec![
+                column("Alunno", ColumnField::StudentName),
+                column("Media", ColumnField::Average),
+                column("Voto proposto", ColumnField::ReportGrade),
+                column("Numero voti", ColumnField::ScoreCount),
+            ],
+            delimiter: default_delimiter(),
+            decimal_comma: true,
+            date_format: default_date_format(),
+            strategy: default_strategy(),
+            header: true,
+            built_in: true,
+        },
+    ]
+}
+
+/// Templates added by the school
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateStore {
+    #[serde(default)]
+    pub templates: Vec<GradeExportTemplate>,
+}
+
+impl TemplateStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GradeExportInfo {
+    pub path: String,
+    pub rows: usize,
+    pub size: u64,
+}
+
+/// A cell of the export
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+fn invalid(message: &str, details: impl Into<String>) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(details)
+}
+
+fn export_error(message: &str, e: impl ToString) -> BackendError {
+    BackendError::new(errors::file::IO_ERROR, message).with_details(e.to_string())
+}
+
+fn validate_template(template: &GradeExportTemplate) -> Result<(), BackendError> {
+    let id_ok = !template.id.is_empty()
+        && template.id.chars().count() <= MAX_ID_CHARS
+        && template
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !id_ok {
+        return Err(invalid(
+            "Template id must be 1 to 40 of a-z, 0-9, - and _",
+            template.id.clone(),
+        ));
+    }
+    if template.name.trim().is_empty() {
+        return Err(invalid("Template needs a name", template.id.clone()));
+    }
+    if template.columns.is_empty() || template.columns.len() > MAX_COLUMNS {
+        return Err(invalid(
+            "Template must have 1 to 40 columns",
+            template.columns.len().to_string(),
+        ));
+    }
+    if let Some(column) = template
+        .columns
+        .iter()
+        .find(|c| !c.field.allowed_in(template.rows))
+    {
+        return Err(invalid(
+            "Column does not fit the template's row type",
+            column.header.clone(),
+        ));
+    }
+    if !template.delimiter.is_ascii()
+        || matches!(template.delimiter, '"' | '\r' | '\n')
+        || (template.decimal_comma && template.delimiter == ',')
+    {
+        return Err(invalid(
+            "Invalid CSV delimiter",
+            template.delimiter.to_string(),
+        ));
+    }
+    // Formatting a date with an invalid format panics; reject it here
+    if chrono::format::StrftimeItems::new(&template.date_format)
+        .any(|item| matches!(item, chrono::format::Item::Error))
+    {
+        return Err(invalid("Invalid date format", template.date_format.clone()));
+    }
+    Ok(())
+}
+
+/// Built-in and school templates
+pub fn list_grade_templates() -> Result<Vec<GradeExportTemplate>, BackendError> {
+    let mut templates = built_in_templates();
+    templates.extend(TemplateStore::load()?.templates);
+    Ok(templates)
+}
+
+fn find_template(id: &str) -> Result<GradeExportTemplate, BackendError> {
+    list_grade_templates()?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| {
+            BackendError::new(
+                errors::grades::TEMPLATE_NOT_FOUND,
+                "Export template not found",
+            )
+            .with_details(id.to_string())
+        })
+}
+
+/// Add or replace a school template
+pub fn save_grade_template(
+    template: GradeExportTemplate,
+) -> Result<GradeExportTemplate, BackendError> {
+    let template = GradeExportTemplate {
+        built_in: false,
+        ..template
+    };
+    validate_template(&template)?;
+    if built_in_templates().iter().any(|t| t.id == template.id) {
+        return Err(invalid(
+            "Built-in templates can't be changed; save under a new id",
+            template.id.clone(),
+        ));
+    }
+    let mut store = TemplateStore::load()?;
+    store.templates.retain(|t| t.id != template.id);
+    store.templates.push(template.clone());
+    store.save()?;
+    Ok(template)
+}
+
+/// Delete a school template
+pub fn delete_grade_template(id: &str) -> Result<(), BackendError> {
+    let mut store = TemplateStore::load()?;
+    let before = store.templates.len();
+    store.templates.retain(|t| t.id != id);
+    if store.templates.len() == before {
+        return Err(BackendError::new(
+            errors::grades::TEMPLATE_NOT_FOUND,
+            "Export template not found",
+        )
+        .with_details(id.to_string()));
+    }
+    store.save()
+}
+
+fn common_cell(field: &ColumnField, class: &ClassData, student_id: &str) -> Cell {
+    match field {
+        ColumnField::StudentName => Cell::Text(
+            class
+                .students
+                .iter()
+                .find(|s| s.id == student_id)
+                .map_or_else(|| student_id.to_string(), |s| s.name.clone()),
+        ),
+        ColumnField::StudentId => Cell::Text(student_id.to_string()),
+        ColumnField::ClassName => Cell::Text(class.name.clone()),
+        ColumnField::Fixed { value } => Cell::Text(value.clone()),
+        _ => Cell::Empty,
+    }
+}
+
+fn score_row(template: &GradeExportTemplate, class: &ClassData, score: &Score) -> Vec<Cell> {
+    template
+        .columns
+        .iter()
+        .map(|c| match &c.field {
+            ColumnField::Assessment => Cell::Text(score.assessment.clone()),
+            ColumnField::Grade => Cell::Number(score.value),
+            ColumnField::GradeLabel => Cell::Text(score.label.clone()),
+            ColumnField::Weight => Cell::Number(score.weight),
+            ColumnField::Date => Local
+                .timestamp_millis_opt(score.recorded_at as i64)
+                .single()
+                .map_or(Cell::Empty, |dt| {
+                    Cell::Text(dt.format(&template.date_format).to_string())
+                }),
+            field => common_cell(field, class, &score.student_id),
+        })
+        .collect()
+}
+
+fn average_row(
+    template: &GradeExportTemplate,
+    class: &ClassData,
+    student_id: &str,
+    scores: &[Score],
+) -> Vec<Cell> {
+    let average = gradebook::average(student_id, scores, template.strategy);
+    template
+        .columns
+        .iter()
+        .map(|c| match &c.field {
+            ColumnField::Average => average.value.map_or(Cell::Empty, Cell::Number),
+            ColumnField::AverageDisplay => average.display.clone().map_or(Cell::Empty, Cell::Text),
+            ColumnField::ReportGrade => average
+                .report_grade
+                .map_or(Cell::Empty, |g| Cell::Number(g.into())),
+            ColumnField::ScoreCount => Cell::Number(average.scores as f64),
+            field => common_cell(field, class, student_id),
+        })
+        .collect()
+}
+
+/// Rows of the export, without the header; students in roster order
+fn build_rows(
+    template: &GradeExportTemplate,
+    class: &ClassData,
+    scores: &[Score],
+) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for student in &class.students {
+        let student_scores: Vec<Score> = scores
+            .iter()
+            .filter(|s| s.student_id == student.id)
+            .cloned()
+            .collect();
+        match template.rows {
+            RowMode::Scores => rows.extend(
+                student_scores
+                    .iter()
+                    .map(|score| score_row(template, class, score)),
+            ),
+            RowMode::Averages => {
+                rows.push(average_row(template, class, &student.id, &student_scores))
+            }
+        }
+    }
+    rows
+}
+
+fn format_number(value: f64, decimal_comma: bool) -> String {
+    let text = if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", (value * 100.0).round() / 100.0)
+    };
+    if decimal_comma {
+        text.replace('.', ",")
+    } else {
+        text
+    }
+}
+
+fn to_csv(template: &GradeExportTemplate, rows: &[Vec<Cell>]) -> Result<Vec<u8>, BackendError> {
+    let delimiter = u8::try_from(template.delimiter)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| invalid("Invalid CSV delimiter", template.delimiter.to_string()))?;
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(UTF8_BOM.as_bytes().to_vec());
+    let write_error = |e: csv::Error| export_error("Failed to write grade export", e);
+    if template.header {
+        writer
+            .write_record(template.columns.iter().map(|c| &c.header))
+            .map_err(write_error)?;
+    }
+    for row in rows {
+        writer
+            .write_record(row.iter().map(|cell| match cell {
+                Cell::Text(text) => text.clone(),
+                Cell::Number(n) => format_number(*n, template.decimal_comma),
+                Cell::Empty => String::new(),
+            }))
+            .map_err(write_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| export_error("Failed to write grade export", e))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        // Control characters are not allowed in XML 1.0
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Spreadsheet column name of a 0-based index (0 → A, 26 → AA)
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn sheet_xml(template: &GradeExportTemplate, rows: &[Vec<Cell>]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>",
+    );
+    let header: Vec<Cell> = template
+        .columns
+        .iter()
+        .map(|c| Cell::Text(c.header.clone()))
+        .collect();
+    let all_rows = template.header.then_some(&header).into_iter().chain(rows);
+    for (r, row) in all_rows.enumerate() {
+        let _ = write!(xml, "<row r=\"{}\">", r + 1);
+        for (c, cell) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(c), r + 1);
+            match cell {
+                Cell::Text(text) => {
+                    let _ = write!(
+                        xml,
+                        "<c r=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                        reference,
+                        escape_xml(text)
+                    );
+                }
+                Cell::Number(n) => {
+                    let _ = write!(xml, "<c r=\"{}\"><v>{}</v></c>", reference, n);
+                }
+                Cell::Empty => {}
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+fn to_xlsx(template: &GradeExportTemplate, rows: &[Vec<Cell>]) -> Result<Vec<u8>, BackendError> {
+    let parts = [
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+             </Types>"
+                .to_string(),
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+             <sheets><sheet name=\"Voti\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>"
+                .to_string(),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        ("xl/worksheets/sheet1.xml", sheet_xml(template, rows)),
+    ];
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in parts {
+        writer
+            .start_file(name, options)
+            .and_then(|_| writer.write_all(content.as_bytes()).map_err(Into::into))
+            .map_err(|e| export_error("Failed to write workbook", e))?;
+    }
+    let cursor = writer
+        .finish()
+        .map_err(|e| export_error("Failed to write workbook", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Export a class's grades to `path` with a template
+pub fn export_grades(
+    class_id: &str,
+    format: ExportFormat,
+    template_id: &str,
+    path: &str,
+) -> Result<GradeExportInfo, BackendError> {
+    let path = Path::new(path);
+    let extension_ok = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(format.extension()));
+    if !extension_ok {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            format!(
+                "Output file must have the .{} extension",
+                format.extension()
+            ),
+        )
+        .with_details(path.display().to_string()));
+    }
+
+    let template = find_template(template_id)?;
+    let class = class_records::load_class(class_id)?;
+    let scores = GradeStore::load()?.for_class(class_id);
+    let rows = build_rows(&template, &class, &scores);
+    let bytes = match format {
+        ExportFormat::Csv => to_csv(&template, &rows)?,
+        ExportFormat::Xlsx => to_xlsx(&template, &rows)?,
+    };
+    std::fs::write(path, &bytes).map_err(|e| {
+        export_error(
+            "Failed to write grade export",
+            format!("{}: {}", path.display(), e),
+        )
+    })?;
+    Ok(GradeExportInfo {
+        path: path.display().to_string(),
+        rows: rows.len(),
+        size: bytes.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roster::ClassBuilder;
+    use calamine::{Data, Reader, Xlsx};
+
+    fn class() -> ClassData {
+        ClassBuilder::new("c1", "3A")
+            .students(&["Rossi Mario", "Bianchi \"Anna\""])
+            .build()
+    }
+
+    fn score(student: &str, value: f64, weight: f64) -> Score {
+        Score {
+            id: String::new(),
+            class_id: "c1".into(),
+            student_id: student.into(),
+            assessment: "Verifica; storia".into(),
+            value,
+            label: gradebook::format_grade(value),
+            weight,
+            recorded_at: 0,
+        }
+    }
+
+    fn template(id: &str) -> GradeExportTemplate {
+        built_in_templates()
+            .into_iter()
+            .find(|t| t.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_built_in_templates_are_valid() {
+        for template in built_in_templates() {
+            assert!(validate_template(&template).is_ok(), "{}", template.id);
+        }
+        let mut wrong = template("scores");
+        wrong.columns.push(column("Media", ColumnField::Average));
+        assert!(validate_template(&wrong).is_err());
+        let mut bad_date = template("scores");
+        bad_date.date_format = "%Q".into();
+        assert!(validate_template(&bad_date).is_err());
+    }
+
+    #[test]
+    fn test_csv_scores_layout() {
+        let scores = [score("s1", 6.5, 1.0), score("s0", 7.0, 2.0)];
+        let template = template("scores");
+        let rows = build_rows(&template, &class(), &scores);
+        let csv = String::from_utf8(to_csv(&template, &rows).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.trim_start_matches(UTF8_BOM).lines().collect();
+        assert_eq!(lines[0], "Alunno;Data;Prova;Voto;Peso");
+        // Roster order, fields with the delimiter or quotes are quoted
+        assert!(lines[1].starts_with("Rossi Mario;"));
+        assert!(lines[1].ends_with(";\"Verifica; storia\";7;2"));
+        assert!(lines[2].starts_with("\"Bianchi \"\"Anna\"\"\";"));
+        assert!(lines[2].ends_with(";6,5;1"));
+    }
+
+    #[test]
+    fn test_xlsx_averages_readable() {
+        let scores = [score("s0", 6.0, 1.0), score("s0", 7.5, 1.0)];
+        let template = template("averages");
+        let rows = build_rows(&template, &class(), &scores);
+        assert_eq!(rows.len(), 2);
+        let bytes = to_xlsx(&template, &rows).unwrap();
+
+        let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(bytes)).unwrap();
+        let sheet = workbook.worksheet_range("Voti").unwrap();
+        assert_eq!(sheet.get((0, 0)), Some(&Data::String("Alunno".into())));
+        assert_eq!(sheet.get((1, 0)), Some(&Data::String("Rossi Mario".into())));
+        assert_eq!(sheet.get((1, 1)), Some(&Data::Float(6.75)));
+        assert_eq!(sheet.get((1, 2)), Some(&Data::Float(7.0)));
+        // A student without scores has no average
+        assert_eq!(sheet.get((2, 3)), Some(&Data::Float(0.0)));
+        assert!(matches!(sheet.get((2, 1)), None | Some(Data::Empty)));
+    }
+
+    #[test]
+    fn test_column_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+    }
+}
@@ -0,0 +1,466 @@
+//! Gradebook: assessment scores and averages
+//!
+//! Handles:
+//! - Recording scores per student and assessment, with a weight
+//! - Italian 1–10 grades as teachers write them: `7`, `6.5`, `6,5`, `6+`
+//!   (+0.25), `7-` (−0.25), `6½`, `6/7` (halfway)
+//! - Averages per student: weighted, plain or median, with the rounding
+//!   used for the report card (half up: 5.5 → 6)
+//!
+//! Scores are stored in the `grades` data collection, with the class the
+//! student was in when the score was recorded.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::roster::{ClassData, RosterStore};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const COLLECTION: &str = "grades";
+
+pub const MIN_GRADE: f64 = 1.0;
+pub const MAX_GRADE: f64 = 10.0;
+pub const MAX_WEIGHT: f64 = 100.0;
+const MAX_ASSESSMENT_CHARS: usize = 80;
+
+/// Keeps ids of scores recorded in the same millisecond apart
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A recorded score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Score {
+    pub id: String,
+    pub class_id: String,
+    pub student_id: String,
+    pub assessment: String,
+    /// Numeric grade, 1 to 10
+    pub value: f64,
+    /// The grade as written (`6+`), for display
+    pub label: String,
+    /// Relative weight; 0 records the grade without counting it in the
+    /// weighted average
+    pub weight: f64,
+    pub recorded_at: u64,
+}
+
+/// A grade as sent by the frontend: a number or Italian notation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GradeInput {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AverageStrategy {
+    /// Weighted by each score's weight
+    Weighted,
+    /// Arithmetic mean, weights ignored
+    Average,
+    Median,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentAverage {
+    pub student_id: String,
+    pub strategy: AverageStrategy,
+    /// Scores counted
+    pub scores: usize,
+    /// Rounded to two decimals; `None` without scores
+    pub value: Option<f64>,
+    /// Nearest quarter in Italian notation (`6+`, `6½`, `7-`)
+    pub display: Option<String>,
+    /// Whole grade for the report card, halves rounded up
+    pub report_grade: Option<u8>,
+}
+
+/// Persisted scores
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GradeStore {
+    #[serde(default)]
+    pub scores: Vec<Score>,
+}
+
+impl GradeStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Scores of a student, oldest first
+    pub fn for_student(&self, student_id: &str) -> Vec<Score> {
+        let mut scores: Vec<Score> = self
+            .scores
+            .iter()
+            .filter(|s| s.student_id == student_id)
+            .cloned()
+            .collect();
+        scores.sort_by_key(|s| s.recorded_at);
+        scores
+    }
+
+    /// Scores of a class, oldest first
+    pub fn for_class(&self, class_id: &str) -> Vec<Score> {
+        let mut scores: Vec<Score> = self
+            .scores
+            .iter()
+            .filter(|s| s.class_id == class_id)
+            .cloned()
+            .collect();
+        scores.sort_by_key(|s| s.recorded_at);
+        scores
+    }
+}
+
+fn invalid_grade(details: impl Into<String>) -> BackendError {
+    BackendError::new(
+        errors::grades::INVALID_GRADE,
+        "Grade must be between 1 and 10 (e.g. 7, 6.5, 6+, 7-, 6½, 6/7)",
+    )
+    .with_details(details)
+}
+
+fn in_range(value: f64) -> bool {
+    (MIN_GRADE..=MAX_GRADE).contains(&value)
+}
+
+/// Parse a grade in Italian notation
+pub fn parse_grade(text: &str) -> Result<f64, BackendError> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let compact = compact.replace(',', ".");
+    let number = |s: &str| s.parse::<f64>().ok().filter(|v| v.is_finite());
+
+    let value = if let Some((low, high)) = compact.split_once('/') {
+        // "6/7": between two consecutive grades
+        match (number(low), number(high)) {
+            (Some(low), Some(high)) if high - low == 1.0 && low.fract() == 0.0 => Some(low + 0.5),
+            _ => None,
+        }
+    } else if let Some(base) = compact.strip_suffix('½') {
+        number(base).filter(|b| b.fract() == 0.0).map(|b| b + 0.5)
+    } else if let Some(base) = compact.strip_suffix('+') {
+        number(base).filter(|b| b.fract() == 0.0).map(|b| b + 0.25)
+    } else if let Some(base) = compact.strip_suffix('-') {
+        number(base).filter(|b| b.fract() == 0.0).map(|b| b - 0.25)
+    } else {
+        number(&compact)
+    };
+    value
+        .filter(|v| in_range(*v))
+        .ok_or_else(|| invalid_grade(text))
+}
+
+/// Nearest quarter in Italian notation (6.25 → "6+", 6.75 → "7-")
+pub fn format_grade(value: f64) -> String {
+    let quarters = (value * 4.0).round() as i64;
+    let (whole, rest) = (quarters.div_euclid(4), quarters.rem_euclid(4));
+    match rest {
+        0 => whole.to_string(),
+        1 => format!("{}+", whole),
+        2 => format!("{}½", whole),
+        _ => format!("{}-", whole + 1),
+    }
+}
+
+/// Whole grade for the report card: halves round up (5.5 → 6)
+pub fn report_grade(value: f64) -> u8 {
+    // Round to two decimals first, so 5.4999… from a weighted sum counts
+    // as the 5.5 it is
+    let value = (value * 100.0).round() / 100.0;
+    (value + 0.5).floor().clamp(MIN_GRADE, MAX_GRADE) as u8
+}
+
+fn compute(scores: &[Score], strategy: AverageStrategy) -> Option<f64> {
+    match strategy {
+        AverageStrategy::Weighted => {
+            let total_weight: f64 = scores.iter().map(|s| s.weight).sum();
+            (total_weight > 0.0)
+                .then(|| scores.iter().map(|s| s.value * s.weight).sum::<f64>() / total_weight)
+        }
+        AverageStrategy::Average => (!scores.is_empty())
+            .then(|| scores.iter().map(|s| s.value).sum::<f64>() / scores.len() as f64),
+        AverageStrategy::Median => {
+            let mut values: Vec<f64> = scores.iter().map(|s| s.value).collect();
+            values.sort_by(f64::total_cmp);
+            let mid = values.len() / 2;
+            match values.len() {
+                0 => None,
+                n if n % 2 == 0 => Some((values[mid - 1] + values[mid]) / 2.0),
+                _ => Some(values[mid]),
+            }
+        }
+    }
+}
+
+/// Average of a set of scores
+pub fn average(student_id: &str, scores: &[Score], strategy: AverageStrategy) -> StudentAverage {
+    let counted = match strategy {
+        AverageStrategy::Weighted => scores.iter().filter(|s| s.weight > 0.0).count(),
+        _ => scores.len(),
+    };
+    let value = compute(scores, strategy).map(|v| (v * 100.0).round() / 100.0);
+    StudentAverage {
+        student_id: student_id.to_string(),
+        strategy,
+        scores: counted,
+        value,
+        display: value.map(format_grade),
+        report_grade: value.map(report_grade),
+    }
+}
+
+fn validate_assessment(assessment: &str, weight: f64) -> Result<String, BackendError> {
+    let assessment = assessment.trim();
+    if assessment.is_empty() || assessment.chars().count() > MAX_ASSESSMENT_CHARS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "Assessment must be 1 to {} characters",
+                MAX_ASSESSMENT_CHARS
+            ),
+        ));
+    }
+    if !(0.0..=MAX_WEIGHT).contains(&weight) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Weight must be between 0 and {}", MAX_WEIGHT),
+        )
+        .with_details(weight.to_string()));
+    }
+    Ok(assessment.to_string())
+}
+
+fn score_id(now: u64) -> String {
+    format!("score_{}_{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Record a score for a student
+pub fn add_score(
+    student_id: &str,
+    assessment: &str,
+    value: GradeInput,
+    weight: f64,
+) -> Result<Score, BackendError> {
+    let assessment = validate_assessment(assessment, weight)?;
+    let (value, label) = match value {
+        GradeInput::Number(v) if in_range(v) => (v, format_grade(v)),
+        GradeInput::Number(v) => return Err(invalid_grade(v.to_string())),
+        GradeInput::Text(text) => (parse_grade(&text)?, text.trim().to_string()),
+    };
+
+    let class_id = RosterStore::load()?
+        .classes
+        .iter()
+        .find(|c| c.students.iter().any(|s| s.id == student_id))
+        .map(|c| c.id.clone())
+        .ok_or_else(|| {
+            BackendError::new(errors::grades::STUDENT_NOT_FOUND, "Student not found")
+                .with_details(student_id.to_string())
+        })?;
+
+    let now = clock::now_millis();
+    let score = Score {
+        id: score_id(now),
+        class_id,
+        student_id: student_id.to_string(),
+        assessment,
+        value,
+        label,
+        weight,
+        recorded_at: now,
+    };
+    let mut store = GradeStore::load()?;
+    store.scores.push(score.clone());
+    store.save()?;
+    Ok(score)
+}
+
+/// Record one assessment for several students of a class at once
+///
+/// `grades` pairs student ids with numeric grades; all are checked before
+/// anything is saved.
+pub fn add_class_scores(
+    class_id: &str,
+    assessment: &str,
+    weight: f64,
+    grades: &[(String, f64)],
+) -> Result<Vec<Score>, BackendError> {
+    let class = RosterStore::load()?
+        .find(class_id)
+        .cloned()
+        .ok_or_else(|| {
+            BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                .with_details(class_id.to_string())
+        })?;
+    let scores = class_scores(&class, assessment, weight, grades, clock::now_millis())?;
+    let mut store = GradeStore::load()?;
+    store.scores.extend(scores.iter().cloned());
+    store.save()?;
+    Ok(scores)
+}
+
+/// Checked scores of one assessment for students of `class`, not saved
+pub fn class_scores(
+    class: &ClassData,
+    assessment: &str,
+    weight: f64,
+    grades: &[(String, f64)],
+    now: u64,
+) -> Result<Vec<Score>, BackendError> {
+    let assessment = validate_assessment(assessment, weight)?;
+    for (student_id, value) in grades {
+        if !class.students.iter().any(|s| &s.id == student_id) {
+            return Err(
+                BackendError::new(errors::grades::STUDENT_NOT_FOUND, "Student not found")
+                    .with_details(student_id.clone()),
+            );
+        }
+        if !in_range(*value) {
+            return Err(invalid_grade(value.to_string()));
+        }
+    }
+
+    Ok(grades
+        .iter()
+        .map(|(student_id, value)| Score {
+            id: score_id(now),
+            class_id: class.id.clone(),
+            student_id: student_id.clone(),
+            assessment: assessment.clone(),
+            value: *value,
+            label: format_grade(*value),
+            weight,
+            recorded_at: now,
+        })
+        .collect())
+}
+
+/// Remove a score
+pub fn delete_score(score_id: &str) -> Result<(), BackendError> {
+    let mut store = GradeStore::load()?;
+    let before = store.scores.len();
+    store.scores.retain(|s| s.id != score_id);
+    if store.scores.len() == before {
+        return Err(
+            BackendError::new(errors::grades::SCORE_NOT_FOUND, "Score not found")
+                .with_details(score_id.to_string()),
+        );
+    }
+    store.save()
+}
+
+/// Scores of a student, oldest first
+pub fn get_student_scores(student_id: &str) -> Result<Vec<Score>, BackendError> {
+    Ok(GradeStore::load()?.for_student(student_id))
+}
+
+/// Average of a student's scores
+pub fn get_student_average(
+    student_id: &str,
+    strategy: AverageStrategy,
+) -> Result<StudentAverage, BackendError> {
+    let scores = GradeStore::load()?.for_student(student_id);
+    Ok(average(student_id, &scores, strategy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roster::ClassBuilder;
+
+    fn score(value: f64, weight: f64) -> Score {
+        Score {
+            id: String::new(),
+            class_id: "c".into(),
+            student_id: "s".into(),
+            assessment: "Verifica".into(),
+            value,
+            label: format_grade(value),
+            weight,
+            recorded_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_italian_grades() {
+        assert_eq!(parse_grade("7").unwrap(), 7.0);
+        assert_eq!(parse_grade("6,5").unwrap(), 6.5);
+        assert_eq!(parse_grade(" 6+ ").unwrap(), 6.25);
+        assert_eq!(parse_grade("7-").unwrap(), 6.75);
+        assert_eq!(parse_grade("6½").unwrap(), 6.5);
+        assert_eq!(parse_grade("6/7").unwrap(), 6.5);
+        for bad in ["", "11", "0", "10+", "1-", "6.5+", "6/8", "ottimo"] {
+            assert_eq!(
+                parse_grade(bad).unwrap_err().code,
+                errors::grades::INVALID_GRADE,
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_and_report_grade() {
+        assert_eq!(format_grade(6.0), "6");
+        assert_eq!(format_grade(6.25), "6+");
+        assert_eq!(format_grade(6.5), "6½");
+        assert_eq!(format_grade(6.75), "7-");
+        assert_eq!(format_grade(6.8), "7-");
+        assert_eq!(report_grade(5.5), 6);
+        assert_eq!(report_grade(5.49), 5);
+        assert_eq!(report_grade(5.499_999_9), 6);
+        assert_eq!(report_grade(9.9), 10);
+    }
+
+    #[test]
+    fn test_average_strategies() {
+        let scores = [score(4.0, 1.0), score(8.0, 3.0), score(6.0, 0.0)];
+        let weighted = average("s", &scores, AverageStrategy::Weighted);
+        assert_eq!(weighted.value, Some(7.0));
+        assert_eq!(weighted.scores, 2);
+        assert_eq!(
+            average("s", &scores, AverageStrategy::Average).value,
+            Some(6.0)
+        );
+        let median = average("s", &scores[..2], AverageStrategy::Median);
+        assert_eq!(median.value, Some(6.0));
+        assert_eq!(median.report_grade, Some(6));
+
+        let thirds = average(
+            "s",
+            &[score(6.0, 1.0), score(6.0, 1.0), score(6.5, 1.0)],
+            AverageStrategy::Average,
+        );
+        assert_eq!(thirds.value, Some(6.17));
+        assert_eq!(thirds.display.as_deref(), Some("6+"));
+
+        let empty = average("s", &[], AverageStrategy::Median);
+        assert_eq!((empty.value, empty.report_grade), (None, None));
+        // Only zero-weight scores: nothing to weigh
+        assert_eq!(
+            average("s", &[score(6.0, 0.0)], AverageStrategy::Weighted).value,
+            None
+        );
+    }
+
+    #[test]
+    fn test_scores_of_one_millisecond_get_distinct_ids() {
+        let class = ClassBuilder::new("c", "3A")
+            .students(&["Rossi Mario"])
+            .build();
+        let grades = [("s0".to_string(), 6.0), ("s0".to_string(), 7.0)];
+        let first = class_scores(&class, "Verifica", 1.0, &grades, 5).unwrap();
+        let second = class_scores(&class, "Verifica", 1.0, &grades[..1], 5).unwrap();
+        let mut ids: Vec<&str> = first.iter().chain(&second).map(|s| s.id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
+}
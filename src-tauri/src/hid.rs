@@ -0,0 +1,608 @@
+//! Presenter remote (clicker) bindings
+//!
+//! Handles:
+//! - Mapping keys sent by wireless presenters to actions
+//!   (`presenter_bindings` config key)
+//! - Registering them as global shortcuts, so they work while PowerPoint
+//!   or the browser has focus
+//! - Reporting keys another application already holds
+//! - Normalizing accelerators for the keyboard layout
+//!   (`keyboard_layout` config key, from the system locale by default)
+//!
+//! Presenter remotes show up as HID keyboards sending PageUp/PageDown,
+//! F5, Escape or "." (blank screen). Bound keys are taken away from other
+//! applications while enabled, so bindings are off by default.
+//!
+//! Global shortcuts match physical keys named after the US layout, so
+//! "Ctrl+Shift+;" typed on an Italian keyboard would bind the key labelled
+//! "ò". Accelerators are read as the characters printed on the user's
+//! layout instead: on Italian keyboards ";" is Shift+"," and binds the
+//! comma key. Characters needing AltGr, or missing from the layout, are
+//! refused.
+
+use crate::actions;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::locale::{self, Language};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+const CONFIG_KEY: &str = "presenter_bindings";
+const LAYOUT_CONFIG_KEY: &str = "keyboard_layout";
+
+/// Shortcuts the OS keeps for itself
+const RESERVED_SHORTCUTS: &[&str] = &[
+    "Alt+F4",
+    "Alt+Tab",
+    "Ctrl+Alt+Delete",
+    "Ctrl+Escape",
+    "Ctrl+Shift+Escape",
+    "Super+D",
+    "Super+L",
+    "Super+Tab",
+    "Super+Space",
+];
+
+/// Character keys of the US layout: key, plain character, with Shift
+const US_KEYS: &[(&str, char, char)] = &[
+    ("Backquote", '`', '~'),
+    ("Digit1", '1', '!'),
+    ("Digit2", '2', '@'),
+    ("Digit3", '3', '#'),
+    ("Digit4", '4', '$'),
+    ("Digit5", '5', '%'),
+    ("Digit6", '6', '^'),
+    ("Digit7", '7', '&'),
+    ("Digit8", '8', '*'),
+    ("Digit9", '9', '('),
+    ("Digit0", '0', ')'),
+    ("Minus", '-', '_'),
+    ("Equal", '=', '+'),
+    ("BracketLeft", '[', '{'),
+    ("BracketRight", ']', '}'),
+    ("Backslash", '\\', '|'),
+    ("Semicolon", ';', ':'),
+    ("Quote", '\'', '"'),
+    ("Comma", ',', '<'),
+    ("Period", '.', '>'),
+    ("Slash", '/', '?'),
+];
+
+/// Character keys of the Italian layout
+const IT_KEYS: &[(&str, char, char)] = &[
+    ("Backquote", '\\', '|'),
+    ("Digit1", '1', '!'),
+    ("Digit2", '2', '"'),
+    ("Digit3", '3', '£'),
+    ("Digit4", '4', '$'),
+    ("Digit5", '5', '%'),
+    ("Digit6", '6', '&'),
+    ("Digit7", '7', '/'),
+    ("Digit8", '8', '('),
+    ("Digit9", '9', ')'),
+    ("Digit0", '0', '='),
+    ("Minus", '\'', '?'),
+    ("Equal", 'ì', '^'),
+    ("BracketLeft", 'è', 'é'),
+    ("BracketRight", '+', '*'),
+    ("Semicolon", 'ò', 'ç'),
+    ("Quote", 'à', '°'),
+    ("Backslash", 'ù', '§'),
+    ("Comma", ',', ';'),
+    ("Period", '.', ':'),
+    ("Slash", '-', '_'),
+];
+
+/// Characters typed with AltGr on the Italian layout
+const IT_ALTGR: &[char] = &['[', ']', '{', '}', '@', '#', '€', '~', '`'];
+
+/// Shortcuts currently registered by this module
+static REGISTERED: Mutex<Vec<Shortcut>> = Mutex::new(Vec::new());
+
+/// One presenter key mapped to an action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenterBinding {
+    /// Accelerator, e.g. "PageDown" or "Shift+F5"
+    pub shortcut: String,
+    /// Action id from `list_backend_actions`
+    pub action: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+/// Persisted presenter settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresenterConfig {
+    pub enabled: bool,
+    pub bindings: Vec<PresenterBinding>,
+}
+
+impl Default for PresenterConfig {
+    fn default() -> Self {
+        let binding = |shortcut: &str, action: &str| PresenterBinding {
+            shortcut: shortcut.to_string(),
+            action: action.to_string(),
+            args: Value::Null,
+        };
+        Self {
+            enabled: false,
+            bindings: vec![
+                binding("PageDown", "timer.next_phase"),
+                binding("Period", "alert.acknowledge"),
+            ],
+        }
+    }
+}
+
+/// Keyboard layout accelerators are written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    Us,
+    It,
+}
+
+impl KeyboardLayout {
+    fn keys(&self) -> &'static [(&'static str, char, char)] {
+        match self {
+            KeyboardLayout::Us => US_KEYS,
+            KeyboardLayout::It => IT_KEYS,
+        }
+    }
+
+    fn altgr(&self) -> &'static [char] {
+        match self {
+            KeyboardLayout::Us => &[],
+            KeyboardLayout::It => IT_ALTGR,
+        }
+    }
+
+    /// Key typing `c`, and whether Shift is needed
+    fn locate(&self, c: char) -> Option<(&'static str, bool)> {
+        self.keys().iter().find_map(|&(key, plain, shifted)| {
+            if c == plain {
+                Some((key, false))
+            } else if c == shifted {
+                Some((key, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Character printed on a key, for labels
+    fn legend(&self, key: &str) -> Option<char> {
+        self.keys()
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, plain, _)| *plain)
+    }
+}
+
+/// Another use of an accelerator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ShortcutConflict {
+    /// A saved presenter binding
+    PresenterBinding { shortcut: String, action: String },
+    /// Kept by the operating system
+    Reserved { shortcut: String },
+}
+
+/// An accelerator resolved for a keyboard layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceleratorCheck {
+    pub input: String,
+    pub layout: KeyboardLayout,
+    /// What gets registered, in US key names ("Ctrl+Shift+Comma")
+    pub effective: String,
+    /// The keys to press as printed on the layout ("Ctrl+Shift+,")
+    pub label: String,
+    pub conflicts: Vec<ShortcutConflict>,
+}
+
+/// A binding whose key could not be registered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedBinding {
+    pub shortcut: String,
+    pub error: String,
+}
+
+/// Settings plus registration outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenterStatus {
+    pub config: PresenterConfig,
+    /// Keys held by another application or the OS
+    pub failed: Vec<FailedBinding>,
+}
+
+/// Load the persisted presenter settings
+pub fn get_config() -> PresenterConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The configured layout, else the one matching the system locale
+pub fn get_keyboard_layout() -> KeyboardLayout {
+    file_ops::load_config(LAYOUT_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_else(|| match Language::from_tag(&locale::get_system_locale()) {
+            Some(Language::It) => KeyboardLayout::It,
+            _ => KeyboardLayout::Us,
+        })
+}
+
+fn invalid_accelerator(message: &str, accelerator: &str) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(accelerator.to_string())
+}
+
+/// Resolve an accelerator typed for `layout` to the key that registers
+///
+/// Returns the effective accelerator, its label and the parsed shortcut.
+fn normalize(
+    accelerator: &str,
+    layout: KeyboardLayout,
+) -> Result<(String, String, Shortcut), BackendError> {
+    let accelerator = accelerator.trim();
+    // "Ctrl++": the last "+" is the key
+    let (modifiers, key) = match accelerator.strip_suffix("++") {
+        Some(rest) => (rest, "+"),
+        None => match accelerator.rsplit_once('+') {
+            Some((modifiers, key)) => (modifiers, key.trim()),
+            None => ("", accelerator),
+        },
+    };
+    if key.is_empty() {
+        return Err(invalid_accelerator("The shortcut has no key", accelerator));
+    }
+
+    let (mut ctrl, mut alt, mut shift, mut meta) = (false, false, false, false);
+    for token in modifiers
+        .split('+')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        match token.to_lowercase().as_str() {
+            "ctrl" | "control" => ctrl = true,
+            "alt" | "option" => alt = true,
+            "shift" => shift = true,
+            "super" | "cmd" | "command" | "meta" | "win" => meta = true,
+            "cmdorctrl" | "cmdorcontrol" | "commandorctrl" | "commandorcontrol" => {
+                if cfg!(target_os = "macos") {
+                    meta = true;
+                } else {
+                    ctrl = true;
+                }
+            }
+            "altgr" => {
+                return Err(invalid_accelerator(
+                    "AltGr cannot be used in shortcuts",
+                    accelerator,
+                ))
+            }
+            _ => return Err(invalid_accelerator("Unknown modifier", token)),
+        }
+    }
+
+    let mut chars = key.chars();
+    let (key_name, legend) = match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => {
+            let upper = c.to_ascii_uppercase();
+            (format!("Key{}", upper), upper.to_string())
+        }
+        (Some(c), None) => {
+            if layout.altgr().contains(&c) {
+                return Err(invalid_accelerator(
+                    "This character needs AltGr on the keyboard layout; choose another key",
+                    accelerator,
+                ));
+            }
+            let (name, needs_shift) = layout.locate(c).ok_or_else(|| {
+                invalid_accelerator("This character is not on the keyboard layout", accelerator)
+            })?;
+            shift |= needs_shift;
+            let legend = layout.legend(name).unwrap_or(c);
+            (name.to_string(), legend.to_string())
+        }
+        // Named keys (F5, PageDown, Space) are the same on every layout
+        _ => {
+            let parsed = Shortcut::from_str(key)
+                .map_err(|e| invalid_accelerator("Unknown key", &format!("{}: {}", key, e)))?;
+            let name = parsed.key.to_string();
+            (name.clone(), name)
+        }
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for (on, name) in [
+        (ctrl, "Ctrl"),
+        (alt, "Alt"),
+        (shift, "Shift"),
+        (meta, "Super"),
+    ] {
+        if on {
+            parts.push(name);
+        }
+    }
+    let effective = parts
+        .iter()
+        .copied()
+        .chain([key_name.as_str()])
+        .collect::<Vec<_>>()
+        .join("+");
+    let label = parts
+        .iter()
+        .copied()
+        .chain([legend.as_str()])
+        .collect::<Vec<_>>()
+        .join("+");
+    let shortcut = Shortcut::from_str(&effective)
+        .map_err(|e| invalid_accelerator("Invalid shortcut", &format!("{}: {}", effective, e)))?;
+    Ok((effective, label, shortcut))
+}
+
+/// Other uses of `shortcut`: saved presenter bindings and OS shortcuts
+fn conflicts(
+    shortcut: &Shortcut,
+    config: &PresenterConfig,
+    layout: KeyboardLayout,
+) -> Vec<ShortcutConflict> {
+    let bindings = config.bindings.iter().filter_map(|binding| {
+        let (_, _, other) = normalize(&binding.shortcut, layout).ok()?;
+        (other.id() == shortcut.id()).then(|| ShortcutConflict::PresenterBinding {
+            shortcut: binding.shortcut.clone(),
+            action: binding.action.clone(),
+        })
+    });
+    let reserved = RESERVED_SHORTCUTS.iter().filter_map(|reserved| {
+        let other = Shortcut::from_str(reserved).ok()?;
+        (other.id() == shortcut.id()).then(|| ShortcutConflict::Reserved {
+            shortcut: reserved.to_string(),
+        })
+    });
+    bindings.chain(reserved).collect()
+}
+
+/// Check an accelerator before saving it
+///
+/// `layout` defaults to `get_keyboard_layout`.
+pub fn validate_accelerator(
+    accelerator: &str,
+    layout: Option<KeyboardLayout>,
+) -> Result<AcceleratorCheck, BackendError> {
+    let layout = layout.unwrap_or_else(get_keyboard_layout);
+    let (effective, label, shortcut) = normalize(accelerator, layout)?;
+    Ok(AcceleratorCheck {
+        input: accelerator.to_string(),
+        layout,
+        effective,
+        label,
+        conflicts: conflicts(&shortcut, &get_config(), layout),
+    })
+}
+
+/// Check that every shortcut parses, is bound once and targets a known action
+fn validate(
+    config: &PresenterConfig,
+    layout: KeyboardLayout,
+) -> Result<Vec<Shortcut>, BackendError> {
+    let mut seen = HashSet::new();
+    config
+        .bindings
+        .iter()
+        .map(|binding| {
+            let (_, _, shortcut) = normalize(&binding.shortcut, layout).map_err(|e| {
+                BackendError::new(errors::system::INVALID_INPUT, "Invalid presenter key")
+                    .with_details(format!(
+                        "{}: {}",
+                        binding.shortcut,
+                        e.details.unwrap_or(e.message)
+                    ))
+            })?;
+            if !seen.insert(shortcut.id()) {
+                return Err(BackendError::new(
+                    errors::system::INVALID_INPUT,
+                    "Presenter key bound twice",
+                )
+                .with_details(binding.shortcut.clone()));
+            }
+            if !actions::is_known(&binding.action) {
+                return Err(
+                    BackendError::new(errors::action::NOT_FOUND, "Unknown action")
+                        .with_details(binding.action.clone()),
+                );
+            }
+            Ok(shortcut)
+        })
+        .collect()
+}
+
+/// Register the configured keys, replacing any previous registration
+fn apply(app: &AppHandle, config: &PresenterConfig) -> Result<Vec<FailedBinding>, BackendError> {
+    let shortcuts = validate(config, get_keyboard_layout())?;
+    let manager = app.global_shortcut();
+    let mut registered = REGISTERED.lock().unwrap_or_else(|e| e.into_inner());
+    for shortcut in registered.drain(..) {
+        let _ = manager.unregister(shortcut);
+    }
+    if !config.enabled {
+        return Ok(Vec::new());
+    }
+
+    let mut failed = Vec::new();
+    for (binding, shortcut) in config.bindings.iter().zip(shortcuts) {
+        let action = binding.action.clone();
+        let args = binding.args.clone();
+        let result = manager.on_shortcut(shortcut, move |app, _, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            let app = app.clone();
+            let (action, args) = (action.clone(), args.clone());
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) =
+                    actions::execute_action(&app, actions::CONTROLLER_SOURCE, &action, args).await
+                {
+                    eprintln!("Presenter action {} failed: {}", action, e);
+                }
+            });
+        });
+        match result {
+            Ok(()) => registered.push(shortcut),
+            Err(e) => failed.push(FailedBinding {
+                shortcut: binding.shortcut.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(failed)
+}
+
+/// Save new bindings and register them
+pub fn set_config(
+    app: &AppHandle,
+    config: PresenterConfig,
+) -> Result<PresenterStatus, BackendError> {
+    validate(&config, get_keyboard_layout())?;
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    let failed = apply(app, &config)?;
+    Ok(PresenterStatus { config, failed })
+}
+
+/// Register the saved bindings at launch
+pub fn start(app: &AppHandle) {
+    let config = get_config();
+    match apply(app, &config) {
+        Ok(failed) => {
+            for f in failed {
+                eprintln!("Presenter key {} not registered: {}", f.shortcut, f.error);
+            }
+        }
+        Err(e) => eprintln!("Presenter bindings not applied: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(shortcut: &str, action: &str) -> PresenterBinding {
+        PresenterBinding {
+            shortcut: shortcut.to_string(),
+            action: action.to_string(),
+            args: Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_default_bindings_are_valid() {
+        let config = PresenterConfig::default();
+        assert!(!config.enabled);
+        assert_eq!(validate(&config, KeyboardLayout::Us).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_bindings() {
+        let config = |bindings| PresenterConfig {
+            enabled: true,
+            bindings,
+        };
+        let validate = |config: &PresenterConfig| validate(config, KeyboardLayout::Us);
+        let err = validate(&config(vec![binding("NotAKey", "timer.pause")])).unwrap_err();
+        assert_eq!(err.code, errors::system::INVALID_INPUT);
+
+        let err = validate(&config(vec![binding("F5", "timer.explode")])).unwrap_err();
+        assert_eq!(err.code, errors::action::NOT_FOUND);
+
+        let err = validate(&config(vec![
+            binding("PageUp", "timer.pause"),
+            binding("pageup", "timer.stop"),
+        ]))
+        .unwrap_err();
+        assert_eq!(err.message, "Presenter key bound twice");
+
+        assert!(validate(&config(vec![binding("Shift+F5", "student.pick")])).is_ok());
+    }
+
+    #[test]
+    fn test_normalize_for_layout() {
+        let resolve = |accelerator: &str, layout| {
+            let (effective, label, _) = normalize(accelerator, layout).unwrap();
+            (effective, label)
+        };
+        // ";" is Shift+"," on Italian keyboards
+        assert_eq!(
+            resolve("Ctrl+Shift+;", KeyboardLayout::It),
+            ("Ctrl+Shift+Comma".into(), "Ctrl+Shift+,".into())
+        );
+        assert_eq!(
+            resolve("ctrl+shift+;", KeyboardLayout::Us),
+            ("Ctrl+Shift+Semicolon".into(), "Ctrl+Shift+;".into())
+        );
+        assert_eq!(
+            resolve("Ctrl+ò", KeyboardLayout::It),
+            ("Ctrl+Semicolon".into(), "Ctrl+ò".into())
+        );
+        assert_eq!(
+            resolve("CmdOrCtrl+Alt+?", KeyboardLayout::It).0,
+            if cfg!(target_os = "macos") {
+                "Alt+Shift+Minus"
+            } else {
+                "Ctrl+Alt+Shift+Minus"
+            }
+        );
+        assert_eq!(
+            resolve("Ctrl++", KeyboardLayout::It),
+            ("Ctrl+BracketRight".into(), "Ctrl++".into())
+        );
+        assert_eq!(
+            resolve("Shift+pagedown", KeyboardLayout::It),
+            ("Shift+PageDown".into(), "Shift+PageDown".into())
+        );
+        assert_eq!(resolve("alt+k", KeyboardLayout::It).0, "Alt+KeyK");
+
+        for bad in ["Ctrl+@", "Ctrl+<", "AltGr+E", "Hyper+A", "Ctrl+"] {
+            assert!(normalize(bad, KeyboardLayout::It).is_err(), "{}", bad);
+        }
+        assert!(normalize("Ctrl+@", KeyboardLayout::Us).is_ok());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let config = PresenterConfig {
+            enabled: true,
+            bindings: vec![binding("Ctrl+Shift+,", "timer.pause")],
+        };
+        let (_, _, shortcut) = normalize("Ctrl+Shift+;", KeyboardLayout::It).unwrap();
+        assert_eq!(
+            conflicts(&shortcut, &config, KeyboardLayout::It),
+            [ShortcutConflict::PresenterBinding {
+                shortcut: "Ctrl+Shift+,".into(),
+                action: "timer.pause".into()
+            }]
+        );
+        let (_, _, alt_f4) = normalize("alt+F4", KeyboardLayout::Us).unwrap();
+        assert_eq!(
+            conflicts(&alt_f4, &config, KeyboardLayout::Us),
+            [ShortcutConflict::Reserved {
+                shortcut: "Alt+F4".into()
+            }]
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Image compression for imported and pasted images
+//!
+//! Handles:
+//! - Decoding a JPEG or PNG (from a file or from pasted bytes), turning it
+//!   upright per its EXIF orientation, and scaling it down to fit a
+//!   maximum dimension (never up)
+//! - Re-encoding as JPEG at a given quality, or as PNG when the image has
+//!   transparency that JPEG would lose
+//! - `optimize_image` for the frontend (paste, drag and drop), and the
+//!   same steps on photo import (`photos`) and image attachments
+//!   (`attachments`), so multi-MB phone photos don't fill the data
+//!   directory
+//!
+//! Encoding uses the `image` crate's baseline JPEG encoder and the PNG
+//! encoder at maximum compression; mozjpeg and WebP would need native
+//! libraries the build doesn't ship. A resized phone photo still comes
+//! out around a tenth of the original.
+
+use crate::errors::{self, BackendError};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+
+pub const DEFAULT_MAX_DIM: u32 = 2048;
+pub const DEFAULT_QUALITY: u8 = 80;
+pub const MIN_MAX_DIM: u32 = 64;
+pub const MAX_MAX_DIM: u32 = 8192;
+pub const MIN_QUALITY: u8 = 30;
+pub const MAX_QUALITY: u8 = 95;
+/// Larger inputs are refused rather than decoded into memory
+pub const MAX_INPUT_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Where the image comes from: `{ path }` or `{ bytes }` (e.g. pasted)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSource {
+    Path(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+        }
+    }
+
+    /// Format for a file name's extension, if it's one that is written
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+/// An image re-encoded by `optimize`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+}
+
+/// Outcome of `optimize_image`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeReport {
+    pub path: String,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub original_bytes: u64,
+    pub bytes: u64,
+}
+
+fn invalid_image(details: impl ToString) -> BackendError {
+    BackendError::new(errors::file::INVALID_FORMAT, "Unreadable image")
+        .with_details(details.to_string())
+}
+
+fn encode_error(details: impl ToString) -> BackendError {
+    BackendError::new(errors::file::IO_ERROR, "Failed to encode image")
+        .with_details(details.to_string())
+}
+
+/// Decode, turn upright and scale down to fit `max_dim`
+pub fn decode(bytes: &[u8], max_dim: u32) -> Result<DynamicImage, BackendError> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(invalid_image)?;
+    let orientation = decoder.orientation().map_err(invalid_image)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid_image)?;
+    image.apply_orientation(orientation);
+    if image.width() > max_dim || image.height() > max_dim {
+        image = image.resize(max_dim, max_dim, FilterType::Triangle);
+    }
+    Ok(image)
+}
+
+/// Whether any pixel is less than fully opaque
+pub fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|p| p.0[3] < u8::MAX)
+}
+
+/// Drop the alpha channel, blending transparent areas onto white
+fn flatten(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend =
+            |c: u8| ((u16::from(c) * u16::from(a) + 255 * (255 - u16::from(a))) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// Encode as `format`; `quality` (1-100) applies to JPEG
+pub fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<Vec<u8>, BackendError> {
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut out, quality)
+            .encode_image(&flatten(image))
+            .map_err(encode_error)?,
+        ImageFormat::Png => image
+            .write_with_encoder(PngEncoder::new_with_quality(
+                &mut out,
+                CompressionType::Best,
+                PngFilter::Adaptive,
+            ))
+            .map_err(encode_error)?,
+    }
+    Ok(out)
+}
+
+/// Decode, shrink and re-encode in one go; PNG only when transparency
+/// needs it
+pub fn optimize(bytes: &[u8], max_dim: u32, quality: u8) -> Result<OptimizedImage, BackendError> {
+    let image = decode(bytes, max_dim)?;
+    let format = if has_transparency(&image) {
+        ImageFormat::Png
+    } else {
+        ImageFormat::Jpeg
+    };
+    Ok(OptimizedImage {
+        width: image.width(),
+        height: image.height(),
+        format,
+        bytes: encode(&image, format, quality)?,
+    })
+}
+
+fn read_source(source: ImageSource) -> Result<Vec<u8>, BackendError> {
+    let too_large = || {
+        BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Images are limited to {} MB", MAX_INPUT_BYTES / 1024 / 1024),
+        )
+    };
+    match source {
+        ImageSource::Path(path) => {
+            if std::fs::metadata(&path)?.len() > MAX_INPUT_BYTES {
+                return Err(too_large().with_details(path));
+            }
+            Ok(std::fs::read(path)?)
+        }
+        ImageSource::Bytes(bytes) if bytes.len() as u64 > MAX_INPUT_BYTES => Err(too_large()),
+        ImageSource::Bytes(bytes) => Ok(bytes),
+    }
+}
+
+/// Shrink and re-encode an image into `output_path` (.jpg/.jpeg or .png)
+///
+/// A transparent image written as JPEG is flattened onto white.
+pub fn optimize_image(
+    source: ImageSource,
+    max_dim: Option<u32>,
+    quality: Option<u8>,
+    output_path: &str,
+) -> Result<OptimizeReport, BackendError> {
+    let max_dim = max_dim.unwrap_or(DEFAULT_MAX_DIM);
+    if !(MIN_MAX_DIM..=MAX_MAX_DIM).contains(&max_dim) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "Maximum dimension must be {}-{} px",
+                MIN_MAX_DIM, MAX_MAX_DIM
+            ),
+        ));
+    }
+    let quality = quality.unwrap_or(DEFAULT_QUALITY);
+    if !(MIN_QUALITY..=MAX_QUALITY).contains(&quality) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Quality must be {}-{}", MIN_QUALITY, MAX_QUALITY),
+        ));
+    }
+    let output = Path::new(output_path);
+    let format = ImageFormat::from_path(output).ok_or_else(|| {
+        BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "Output file must be .jpg, .jpeg or .png",
+        )
+        .with_details(output_path.to_string())
+    })?;
+
+    let original = read_source(source)?;
+    let image = decode(&original, max_dim)?;
+    let encoded = encode(&image, format, quality)?;
+    let tmp = output.with_extension("tmp");
+    std::fs::write(&tmp, &encoded)?;
+    std::fs::rename(&tmp, output)?;
+    Ok(OptimizeReport {
+        path: output_path.to_string(),
+        format,
+        width: image.width(),
+        height: image.height(),
+        original_bytes: original.len() as u64,
+        bytes: encoded.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(image: &DynamicImage) -> Vec<u8> {
+        let mut out = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_optimize_shrinks_and_picks_format() {
+        let photo = DynamicImage::ImageRgb8(RgbImage::from_fn(600, 300, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        }));
+        let optimized = optimize(&png(&photo), 200, 80).unwrap();
+        assert_eq!((optimized.width, optimized.height), (200, 100));
+        assert_eq!(optimized.format, ImageFormat::Jpeg);
+        assert_eq!(&optimized.bytes[..2], &[0xFF, 0xD8]);
+
+        // Never scaled up; transparency keeps PNG
+        let mut logo = image::RgbaImage::from_pixel(40, 20, image::Rgba([0, 0, 255, 255]));
+        logo.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        let optimized = optimize(&png(&DynamicImage::ImageRgba8(logo)), 200, 80).unwrap();
+        assert_eq!((optimized.width, optimized.height), (40, 20));
+        assert_eq!(optimized.format, ImageFormat::Png);
+
+        assert!(optimize(b"not an image", 200, 80).is_err());
+    }
+
+    #[test]
+    fn test_optimize_image_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut logo = image::RgbaImage::from_pixel(64, 64, image::Rgba([0, 0, 0, 0]));
+        logo.put_pixel(60, 60, image::Rgba([255, 0, 0, 255]));
+        let source = ImageSource::Bytes(png(&DynamicImage::ImageRgba8(logo)));
+        let out = dir.path().join("pasted.jpg");
+        let report = optimize_image(source.clone(), None, Some(70), out.to_str().unwrap()).unwrap();
+        assert_eq!(report.format, ImageFormat::Jpeg);
+        // Transparent areas become white
+        let written = image::open(&out).unwrap().to_rgb8();
+        assert!(written.get_pixel(0, 0).0.iter().all(|&c| c > 240));
+
+        let bad = dir.path().join("pasted.gif");
+        assert!(optimize_image(source.clone(), None, None, bad.to_str().unwrap()).is_err());
+        assert!(optimize_image(source.clone(), Some(10), None, out.to_str().unwrap()).is_err());
+        assert!(optimize_image(source, None, Some(100), out.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! History of file imports
+//!
+//! Handles:
+//! - Recording a report for every roster or quiz-results import, failed
+//!   ones included: the file's SHA-256, row counts, validation errors, the
+//!   columns used and how long it took
+//! - Listing the reports, newest first
+//!
+//! Teachers use it to check whether an updated list was already imported;
+//! support uses it to debug a bad import after the fact. Reports live in
+//! the `import_history` data collection; the oldest are dropped beyond
+//! `MAX_REPORTS`. Recording is best effort and never fails an import.
+
+use crate::clock;
+use crate::errors::BackendError;
+use crate::file_ops;
+use crate::file_ops::import_adapters::RosterImport;
+use crate::roster::ParsedRoster;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+const COLLECTION: &str = "import_history";
+
+/// Reports kept
+pub const MAX_REPORTS: usize = 200;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What was imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportKind {
+    /// Roster file chosen by the teacher
+    Roster,
+    /// Pending roster update applied: a file from the watched folder, or a
+    /// changed roster file imported again (see `roster_sync`)
+    RosterUpdate,
+    /// Quiz results from Google / Microsoft Forms
+    Forms,
+}
+
+/// The imported file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSource {
+    pub file_name: String,
+    /// SHA-256 of the content (`None` if the file couldn't be read)
+    pub hash: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl ImportSource {
+    pub fn from_bytes(file_name: &str, bytes: &[u8]) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            hash: Some(hex::encode(Sha256::digest(bytes))),
+            size: Some(bytes.len() as u64),
+        }
+    }
+
+    /// Read `path` to hash it; an unreadable file is still recorded
+    pub fn from_path(path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match fs::read(path) {
+            Ok(bytes) => Self::from_bytes(&file_name, &bytes),
+            Err(_) => Self {
+                file_name,
+                hash: None,
+                size: None,
+            },
+        }
+    }
+}
+
+/// One import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub id: String,
+    pub kind: ImportKind,
+    pub source: ImportSource,
+    /// Detected format ("argo", "classeviva", "google", ...)
+    pub format: Option<String>,
+    /// Field → column header used
+    pub columns: BTreeMap<String, String>,
+    /// Class imported into
+    pub class_id: Option<String>,
+    /// Data rows read
+    pub rows: usize,
+    /// Rows that became students or grades
+    pub imported: usize,
+    /// Rows left out (validation errors, unmatched respondents)
+    pub skipped: usize,
+    pub errors: Vec<String>,
+    pub success: bool,
+    /// Why the import failed
+    pub failure: Option<String>,
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+impl ImportReport {
+    /// Fill in what a parsed roster file tells
+    pub fn describe_roster(&mut self, import: &RosterImport) {
+        self.format = Some(import.format.clone());
+        self.columns = import.columns.clone();
+        self.describe_names(&import.roster);
+    }
+
+    /// Fill in the counts of a parsed roster
+    pub fn describe_names(&mut self, roster: &ParsedRoster) {
+        self.rows = roster.students.len() + roster.errors.len();
+        self.skipped = roster.errors.len();
+        self.errors = roster.errors.clone();
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportHistoryStore {
+    pub reports: Vec<ImportReport>,
+}
+
+impl ImportHistoryStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Latest successful roster import (file or applied update) for which
+    /// `matches` holds
+    pub fn last_roster_import(
+        &self,
+        matches: impl Fn(&ImportReport) -> bool,
+    ) -> Option<&ImportReport> {
+        self.reports
+            .iter()
+            .rev()
+            .filter(|r| r.success && r.class_id.is_some())
+            .filter(|r| matches!(r.kind, ImportKind::Roster | ImportKind::RosterUpdate))
+            .find(|r| matches(r))
+    }
+
+    /// Append a report, dropping the oldest beyond `MAX_REPORTS`
+    fn push(&mut self, report: ImportReport) {
+        self.reports.push(report);
+        if self.reports.len() > MAX_REPORTS {
+            let excess = self.reports.len() - MAX_REPORTS;
+            self.reports.drain(..excess);
+        }
+    }
+}
+
+fn record(report: ImportReport) {
+    let result = ImportHistoryStore::load().and_then(|mut store| {
+        store.push(report);
+        store.save()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record import report: {}", e.message);
+    }
+}
+
+/// Run an import and record its report
+///
+/// `work` fills in what it learns (format, columns, counts, class) as it
+/// goes, so a failed import is recorded with everything known up to the
+/// failure.
+pub fn track<T>(
+    kind: ImportKind,
+    source: ImportSource,
+    work: impl FnOnce(&mut ImportReport) -> Result<T, BackendError>,
+) -> Result<T, BackendError> {
+    let started = Instant::now();
+    let now = clock::now_millis();
+    let mut report = ImportReport {
+        id: format!("import_{}_{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+        kind,
+        source,
+        format: None,
+        columns: BTreeMap::new(),
+        class_id: None,
+        rows: 0,
+        imported: 0,
+        skipped: 0,
+        errors: Vec::new(),
+        success: false,
+        failure: None,
+        started_at: now,
+        duration_ms: 0,
+    };
+    let result = work(&mut report);
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report.success = result.is_ok();
+    report.failure = result.as_ref().err().map(|e| e.message.clone());
+    record(report);
+    result
+}
+
+/// Import reports, newest first (`limit`: at most this many)
+pub fn get_import_history(limit: Option<usize>) -> Result<Vec<ImportReport>, BackendError> {
+    let store = ImportHistoryStore::load()?;
+    Ok(store
+        .reports
+        .into_iter()
+        .rev()
+        .take(limit.unwrap_or(MAX_REPORTS))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: usize) -> ImportReport {
+        ImportReport {
+            id: id.to_string(),
+            kind: ImportKind::Roster,
+            source: ImportSource::from_bytes("3A.csv", b"nome\nMario Rossi\n"),
+            format: Some("generic".into()),
+            columns: BTreeMap::new(),
+            class_id: None,
+            rows: 1,
+            imported: 1,
+            skipped: 0,
+            errors: Vec::new(),
+            success: true,
+            failure: None,
+            started_at: id as u64,
+            duration_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_store_keeps_newest_reports() {
+        let mut store = ImportHistoryStore::default();
+        for id in 0..MAX_REPORTS + 5 {
+            store.push(report(id));
+        }
+        assert_eq!(store.reports.len(), MAX_REPORTS);
+        assert_eq!(store.reports[0].id, "5");
+    }
+
+    #[test]
+    fn test_source_hash() {
+        let source = ImportSource::from_bytes("3A.csv", b"abc");
+        assert_eq!(
+            source.hash.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(source.size, Some(3));
+
+        let missing = ImportSource::from_path(Path::new("/nonexistent/3A.csv"));
+        assert_eq!(missing.file_name, "3A.csv");
+        assert!(missing.hash.is_none());
+    }
+}
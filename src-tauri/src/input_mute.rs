@@ -0,0 +1,299 @@
+//! Microphone mute state
+//!
+//! Handles:
+//! - Reading whether the default input is muted in the OS or by its
+//!   hardware switch, and its input volume:
+//!   - Windows: the capture endpoint's volume (`IAudioEndpointVolume`)
+//!   - macOS: the default input device's mute and volume (CoreAudio)
+//!   - Linux: `pactl` (PulseAudio, and PipeWire through its Pulse server),
+//!     else `wpctl` (PipeWire)
+//! - Keeping the last known state while the noise meter runs, so
+//!   `noise-level` events can say "mic muted" instead of showing a
+//!   perfectly silent class
+//!
+//! An input volume of zero counts as muted: the meter is just as flat.
+//! Devices that don't report a mute switch give `muted: null`.
+
+use crate::errors::{self, BackendError};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often the state is re-read while the meter runs
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// The meter counts as stopped after this long without a level
+const METER_IDLE: Duration = Duration::from_secs(10);
+
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    muted: None,
+    last_level: None,
+});
+
+struct Cache {
+    muted: Option<bool>,
+    last_level: Option<Instant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputMuteState {
+    /// Muted or at zero volume; `None` when the device doesn't say
+    pub muted: Option<bool>,
+    /// Input volume, 0.0-1.0
+    pub volume: Option<f32>,
+}
+
+impl InputMuteState {
+    fn new(switch: Option<bool>, volume: Option<f32>) -> Self {
+        let silent = volume.map(|v| v <= 0.0);
+        let muted = match (switch, silent) {
+            (Some(a), Some(b)) => Some(a || b),
+            (a, b) => a.or(b),
+        };
+        Self { muted, volume }
+    }
+}
+
+#[cfg_attr(
+    not(any(target_os = "windows", target_os = "macos", target_os = "linux")),
+    allow(dead_code)
+)]
+fn input_error(details: impl ToString) -> BackendError {
+    BackendError::new(
+        errors::permission::MICROPHONE_UNAVAILABLE,
+        "Could not read the microphone mute state",
+    )
+    .with_details(details.to_string())
+}
+
+/// Read the mute state of the default input
+pub fn get_input_mute_state() -> Result<InputMuteState, BackendError> {
+    let state = query()?;
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).muted = state.muted;
+    Ok(state)
+}
+
+/// Last known mute state for a level the meter just reported
+///
+/// Also keeps the background refresh going while levels arrive.
+pub fn meter_muted() -> Option<bool> {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.last_level = Some(Instant::now());
+    cache.muted
+}
+
+/// Re-read the state every few seconds while the meter runs
+pub fn start() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let active = CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_level
+            .is_some_and(|t| t.elapsed() < METER_IDLE);
+        if active {
+            let muted = query().ok().and_then(|s| s.muted);
+            CACHE.lock().unwrap_or_else(|e| e.into_inner()).muted = muted;
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Result<InputMuteState, BackendError> {
+    use crate::platform::windows::ComGuard;
+    use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
+    use windows::Win32::Media::Audio::{
+        eCapture, eConsole, IMMDeviceEnumerator, MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+    // Declared first so the COM objects below are released before it drops
+    let _com = ComGuard::new()?;
+    let fail = |e: windows::core::Error| input_error(e);
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(fail)?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eCapture, eConsole)
+            .map_err(fail)?;
+        let endpoint: IAudioEndpointVolume = device.Activate(CLSCTX_ALL, None).map_err(fail)?;
+        let muted = endpoint.GetMute().map_err(fail)?.as_bool();
+        let volume = endpoint.GetMasterVolumeLevelScalar().ok();
+        Ok(InputMuteState::new(Some(muted), volume))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn query() -> Result<InputMuteState, BackendError> {
+    let device =
+        core_audio::default_input_device().ok_or_else(|| input_error("No default input device"))?;
+    Ok(InputMuteState::new(
+        core_audio::input_mute(device),
+        core_audio::input_volume(device),
+    ))
+}
+
+#[cfg(target_os = "macos")]
+mod core_audio {
+    use std::ffi::c_void;
+
+    const SYSTEM_OBJECT: u32 = 1;
+    // Four-character codes: 'dIn ', 'mute', 'volm', 'glob', 'inpt'
+    const DEFAULT_INPUT_DEVICE: u32 = 0x6449_6E20;
+    const DEVICE_MUTE: u32 = 0x6D75_7465;
+    const DEVICE_VOLUME_SCALAR: u32 = 0x766F_6C6D;
+    const SCOPE_GLOBAL: u32 = 0x676C_6F62;
+    const SCOPE_INPUT: u32 = 0x696E_7074;
+    const ELEMENT_MAIN: u32 = 0;
+
+    #[repr(C)]
+    struct PropertyAddress {
+        selector: u32,
+        scope: u32,
+        element: u32,
+    }
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyData(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            data_size: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    /// Read a fixed-size property; `None` when the object doesn't have it
+    fn property<T: Default>(object: u32, selector: u32, scope: u32) -> Option<T> {
+        let address = PropertyAddress {
+            selector,
+            scope,
+            element: ELEMENT_MAIN,
+        };
+        let mut value = T::default();
+        let mut size = std::mem::size_of::<T>() as u32;
+        // SAFETY: `value` is a plain value of `size` bytes
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                object,
+                &address,
+                0,
+                std::ptr::null(),
+                &mut size,
+                &mut value as *mut T as *mut c_void,
+            )
+        };
+        (status == 0).then_some(value)
+    }
+
+    pub fn default_input_device() -> Option<u32> {
+        property::<u32>(SYSTEM_OBJECT, DEFAULT_INPUT_DEVICE, SCOPE_GLOBAL).filter(|&id| id != 0)
+    }
+
+    pub fn input_mute(device: u32) -> Option<bool> {
+        property::<u32>(device, DEVICE_MUTE, SCOPE_INPUT).map(|m| m != 0)
+    }
+
+    pub fn input_volume(device: u32) -> Option<f32> {
+        property::<f32>(device, DEVICE_VOLUME_SCALAR, SCOPE_INPUT)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn query() -> Result<InputMuteState, BackendError> {
+    use std::process::Command;
+
+    let run = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+    };
+    if let Some(mute) = run("pactl", &["get-source-mute", "@DEFAULT_SOURCE@"]) {
+        let volume = run("pactl", &["get-source-volume", "@DEFAULT_SOURCE@"])
+            .and_then(|out| parse_pactl_volume(&out));
+        return Ok(InputMuteState::new(parse_pactl_mute(&mute), volume));
+    }
+    run("wpctl", &["get-volume", "@DEFAULT_AUDIO_SOURCE@"])
+        .and_then(|out| parse_wpctl_volume(&out))
+        .ok_or_else(|| input_error("Neither pactl nor wpctl could read the default source"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn query() -> Result<InputMuteState, BackendError> {
+    Ok(InputMuteState::new(None, None))
+}
+
+/// "Mute: yes" (the word is translated with the locale, so only "no" and
+/// its absence are trusted to mean unmuted)
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_pactl_mute(output: &str) -> Option<bool> {
+    let value = output.trim().rsplit(':').next()?.trim();
+    match value {
+        "" => None,
+        "no" => Some(false),
+        _ => Some(true),
+    }
+}
+
+/// "Volume: front-left: 26214 /  40% / -23.88 dB,   front-right: ..."
+/// → the first channel's percentage
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_pactl_volume(output: &str) -> Option<f32> {
+    let percent = output.split('/').nth(1)?.trim().strip_suffix('%')?;
+    percent.trim().parse::<f32>().ok().map(|p| p / 100.0)
+}
+
+/// "Volume: 0.40" or "Volume: 0.40 [MUTED]"
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_wpctl_volume(output: &str) -> Option<InputMuteState> {
+    let rest = output.trim().strip_prefix("Volume:")?.trim();
+    let volume = rest.split_whitespace().next()?.parse::<f32>().ok()?;
+    Some(InputMuteState::new(
+        Some(rest.contains("[MUTED]")),
+        Some(volume),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_volume_counts_as_muted() {
+        assert_eq!(
+            InputMuteState::new(Some(false), Some(0.4)).muted,
+            Some(false)
+        );
+        assert_eq!(
+            InputMuteState::new(Some(false), Some(0.0)).muted,
+            Some(true)
+        );
+        assert_eq!(InputMuteState::new(Some(true), None).muted, Some(true));
+        assert_eq!(InputMuteState::new(None, Some(0.7)).muted, Some(false));
+        assert_eq!(InputMuteState::new(None, None).muted, None);
+    }
+
+    #[test]
+    fn test_parse_linux_tools() {
+        assert_eq!(parse_pactl_mute("Mute: yes\n"), Some(true));
+        assert_eq!(parse_pactl_mute("Mute: no\n"), Some(false));
+        assert_eq!(parse_pactl_mute("Muto: sì\n"), Some(true));
+        assert_eq!(parse_pactl_mute(""), None);
+        let volume = "Volume: front-left: 26214 /  40% / -23.88 dB,   front-right: 26214 /  40% / -23.88 dB\n";
+        assert_eq!(parse_pactl_volume(volume), Some(0.4));
+
+        let muted = parse_wpctl_volume("Volume: 0.40 [MUTED]\n").unwrap();
+        assert_eq!(muted.muted, Some(true));
+        assert_eq!(muted.volume, Some(0.4));
+        assert_eq!(
+            parse_wpctl_volume("Volume: 1.00\n").unwrap().muted,
+            Some(false)
+        );
+        assert!(parse_wpctl_volume("Object not found").is_none());
+    }
+}
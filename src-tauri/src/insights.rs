@@ -0,0 +1,481 @@
+//! Trend insights on attendance and noise
+//!
+//! Handles:
+//! - A background check (every `CHECK_INTERVAL`) for trends worth the
+//!   teacher's attention:
+//!   - a student absent on the same weekday several weeks in a row
+//!     (attendance records)
+//!   - a class's average noise rising steadily over the last days
+//!     (daily histograms in `noise_history`), from a least-squares line
+//! - Thresholds per insight type (config `insight_thresholds`)
+//! - The current insights (`insights` data collection) and the
+//!   `insight-detected` event, sent once when an insight first appears
+//!
+//! An insight keeps its id while the trend lasts (e.g. the streak growing
+//! by another Monday), so it is not announced again; once the trend ends
+//! it is dropped and would be announced anew.
+
+use crate::class_records::{self, AttendanceRecord, AttendanceStore};
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::noise_history::{self, NoiseHistoryStore};
+use crate::roster::{ClassData, RosterStore};
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with the insight when it is first detected
+pub const INSIGHT_EVENT: &str = "insight-detected";
+
+const COLLECTION: &str = "insights";
+const CONFIG_KEY: &str = "insight_thresholds";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// A streak must include an absence this recent to still count
+const STREAK_MAX_AGE_DAYS: u64 = 7;
+/// Days with noise data needed before calling a trend
+const MIN_NOISE_DAYS: usize = 5;
+
+/// Same-weekday absences
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AbsenceStreakThreshold {
+    pub enabled: bool,
+    /// Consecutive weeks absent on that weekday
+    pub weeks: u32,
+}
+
+impl Default for AbsenceStreakThreshold {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weeks: 3,
+        }
+    }
+}
+
+/// Steadily rising noise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RisingNoiseThreshold {
+    pub enabled: bool,
+    /// Days looked at, ending today
+    pub days: u32,
+    /// Rise of the fitted line over those days, in meter units
+    pub min_rise: f64,
+}
+
+impl Default for RisingNoiseThreshold {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            days: 14,
+            min_rise: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InsightThresholds {
+    pub absence_streak: AbsenceStreakThreshold,
+    pub rising_noise: RisingNoiseThreshold,
+}
+
+/// What was detected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InsightKind {
+    #[serde(rename_all = "camelCase")]
+    AbsenceStreak {
+        student_id: String,
+        student_name: String,
+        /// ISO weekday (1 = Monday … 7 = Sunday)
+        weekday: u8,
+        /// Days absent, oldest first
+        dates: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RisingNoise {
+        /// Fitted average level at the start and end of the window
+        from_level: f64,
+        to_level: f64,
+        days: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Insight {
+    /// Stable while the trend lasts
+    pub id: String,
+    pub class_id: String,
+    pub class_name: String,
+    pub detected_at: u64,
+    #[serde(flatten)]
+    pub kind: InsightKind,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InsightStore {
+    pub insights: Vec<Insight>,
+}
+
+impl InsightStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Replace the insights with `current`, keeping when known ones were
+    /// first detected; returns the new ones
+    fn update(&mut self, current: Vec<Insight>) -> Vec<Insight> {
+        let mut fresh = Vec::new();
+        let insights = current
+            .into_iter()
+            .map(|mut insight| {
+                match self.insights.iter().find(|i| i.id == insight.id) {
+                    Some(known) => insight.detected_at = known.detected_at,
+                    None => fresh.push(insight.clone()),
+                }
+                insight
+            })
+            .collect();
+        self.insights = insights;
+        self.insights
+            .sort_by(|a, b| b.detected_at.cmp(&a.detected_at).then(a.id.cmp(&b.id)));
+        fresh
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Students absent on the same weekday `weeks` recorded weeks in a row
+fn absence_streaks(
+    class: &ClassData,
+    records: &[AttendanceRecord],
+    threshold: &AbsenceStreakThreshold,
+    today: NaiveDate,
+    now: u64,
+) -> Vec<Insight> {
+    let weeks = threshold.weeks.max(2) as usize;
+    let mut by_student: BTreeMap<(&str, u8), Vec<(NaiveDate, bool)>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.class_id == class.id) {
+        if let Some(date) = parse_date(&record.date) {
+            let weekday = date.weekday().number_from_monday() as u8;
+            by_student
+                .entry((record.student_id.as_str(), weekday))
+                .or_default()
+                .push((date, record.absent));
+        }
+    }
+
+    let mut insights = Vec::new();
+    for ((student_id, weekday), mut days) in by_student {
+        days.sort();
+        if days.len() < weeks {
+            continue;
+        }
+        let latest = &days[days.len() - weeks..];
+        let consecutive = latest.windows(2).all(|w| w[0].0 + Days::new(7) == w[1].0);
+        let recent = latest[weeks - 1].0 + Days::new(STREAK_MAX_AGE_DAYS) >= today;
+        if !(consecutive && recent && latest.iter().all(|(_, absent)| *absent)) {
+            continue;
+        }
+        insights.push(Insight {
+            id: format!("absenceStreak:{}:{}:{}", class.id, student_id, weekday),
+            class_id: class.id.clone(),
+            class_name: class.name.clone(),
+            detected_at: now,
+            kind: InsightKind::AbsenceStreak {
+                student_id: student_id.to_string(),
+                student_name: class
+                    .students
+                    .iter()
+                    .find(|s| s.id == student_id)
+                    .map_or_else(|| student_id.to_string(), |s| s.name.clone()),
+                weekday,
+                dates: latest
+                    .iter()
+                    .map(|(d, _)| d.format("%Y-%m-%d").to_string())
+                    .collect(),
+            },
+        });
+    }
+    insights
+}
+
+/// Least-squares line through `(x, y)` points: (intercept, slope)
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let slope = sxy / sxx;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+/// A class whose daily average noise rose by `min_rise` over `days`
+fn rising_noise(
+    class: &ClassData,
+    noise: &NoiseHistoryStore,
+    threshold: &RisingNoiseThreshold,
+    today: NaiveDate,
+    now: u64,
+) -> Option<Insight> {
+    let span = threshold.days.max(2);
+    let start = today - Days::new(u64::from(span - 1));
+    let points: Vec<(f64, f64)> = noise
+        .classes
+        .get(&class.id)?
+        .days
+        .iter()
+        .filter_map(|(date, histogram)| {
+            let date = parse_date(date)?;
+            let day = (date - start).num_days();
+            let mean = histogram.mean()?;
+            (date >= start && date <= today).then_some((day as f64, mean))
+        })
+        .collect();
+    if points.len() < MIN_NOISE_DAYS {
+        return None;
+    }
+    let (intercept, slope) = fit_line(&points)?;
+    let last = f64::from(span - 1);
+    if slope * last < threshold.min_rise {
+        return None;
+    }
+    let round = |level: f64| (level * 10.0).round() / 10.0;
+    Some(Insight {
+        id: format!("risingNoise:{}", class.id),
+        class_id: class.id.clone(),
+        class_name: class.name.clone(),
+        detected_at: now,
+        kind: InsightKind::RisingNoise {
+            from_level: round(intercept),
+            to_level: round(intercept + slope * last),
+            days: span,
+        },
+    })
+}
+
+fn detect(
+    classes: &[ClassData],
+    attendance: &AttendanceStore,
+    noise: &NoiseHistoryStore,
+    thresholds: &InsightThresholds,
+    today: NaiveDate,
+    now: u64,
+) -> Vec<Insight> {
+    let mut insights = Vec::new();
+    for class in classes {
+        if thresholds.absence_streak.enabled {
+            insights.extend(absence_streaks(
+                class,
+                &attendance.records,
+                &thresholds.absence_streak,
+                today,
+                now,
+            ));
+        }
+        if thresholds.rising_noise.enabled {
+            insights.extend(rising_noise(
+                class,
+                noise,
+                &thresholds.rising_noise,
+                today,
+                now,
+            ));
+        }
+    }
+    insights
+}
+
+/// Current thresholds
+pub fn get_insight_thresholds() -> InsightThresholds {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Save the thresholds and check again with them
+pub fn set_insight_thresholds(
+    app: &AppHandle,
+    thresholds: InsightThresholds,
+) -> Result<Vec<Insight>, BackendError> {
+    let value = serde_json::to_value(&thresholds).map_err(|e| {
+        BackendError::new(
+            errors::system::UNKNOWN_ERROR,
+            "Failed to serialize thresholds",
+        )
+        .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    refresh_insights(app)
+}
+
+/// Current insights, newest first
+pub fn get_insights() -> Result<Vec<Insight>, BackendError> {
+    Ok(InsightStore::load()?.insights)
+}
+
+/// Look for trends now; new insights are emitted as `insight-detected`
+pub fn refresh_insights(app: &AppHandle) -> Result<Vec<Insight>, BackendError> {
+    noise_history::flush_pending()?;
+    let now = clock::now_millis();
+    let today = parse_date(&class_records::date_string(now)).unwrap_or_default();
+    let current = detect(
+        &RosterStore::load()?.classes,
+        &AttendanceStore::load()?,
+        &NoiseHistoryStore::load()?,
+        &get_insight_thresholds(),
+        today,
+        now,
+    );
+    let mut store = InsightStore::load()?;
+    let fresh = store.update(current);
+    store.save()?;
+    for insight in &fresh {
+        let _ = app.emit(INSIGHT_EVENT, insight);
+    }
+    Ok(store.insights)
+}
+
+/// Start the background check
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = refresh_insights(&app) {
+            eprintln!("Insight check failed: {}", e.message);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise_history::Histogram;
+    use crate::roster::ClassBuilder;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn class() -> ClassData {
+        ClassBuilder::new("c", "3A").build()
+    }
+
+    fn record(student: &str, day: &str, absent: bool) -> AttendanceRecord {
+        AttendanceRecord {
+            class_id: "c".into(),
+            student_id: student.into(),
+            date: day.into(),
+            absent,
+        }
+    }
+
+    #[test]
+    fn test_absence_streaks() {
+        // Mondays 2026-09-21 … 2026-10-12
+        let records = vec![
+            record("anna", "2026-09-21", false),
+            record("anna", "2026-09-28", true),
+            record("anna", "2026-10-05", true),
+            record("anna", "2026-10-12", true),
+            record("anna", "2026-10-13", false),
+            // A missing week breaks the streak
+            record("luca", "2026-09-21", true),
+            record("luca", "2026-10-05", true),
+            record("luca", "2026-10-12", true),
+        ];
+        let threshold = AbsenceStreakThreshold::default();
+        let today = date("2026-10-16");
+        let insights = absence_streaks(&class(), &records, &threshold, today, 1);
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].id, "absenceStreak:c:anna:1");
+        let InsightKind::AbsenceStreak { weekday, dates, .. } = &insights[0].kind else {
+            panic!("expected an absence streak");
+        };
+        assert_eq!(*weekday, 1);
+        assert_eq!(dates, &["2026-09-28", "2026-10-05", "2026-10-12"]);
+
+        // Old streaks don't count
+        assert!(absence_streaks(&class(), &records, &threshold, date("2026-11-30"), 1).is_empty());
+        let four = AbsenceStreakThreshold {
+            weeks: 4,
+            ..threshold
+        };
+        assert!(absence_streaks(&class(), &records, &four, today, 1).is_empty());
+    }
+
+    #[test]
+    fn test_rising_noise_and_new_insights() {
+        let mut noise = NoiseHistoryStore::default();
+        let days = &mut noise.classes.entry("c".into()).or_default().days;
+        for (i, level) in [40.0, 41.0, 43.0, 44.0, 46.0, 47.0].iter().enumerate() {
+            let mut histogram = Histogram::default();
+            histogram.add(*level);
+            days.insert(format!("2026-10-{:02}", 10 + i), histogram);
+        }
+        let threshold = RisingNoiseThreshold {
+            enabled: true,
+            days: 7,
+            min_rise: 5.0,
+        };
+        let today = date("2026-10-16");
+        let insight = rising_noise(&class(), &noise, &threshold, today, 1).unwrap();
+        let InsightKind::RisingNoise {
+            from_level,
+            to_level,
+            ..
+        } = insight.kind
+        else {
+            panic!("expected rising noise");
+        };
+        assert!(from_level < 40.0 && to_level > 48.0);
+        let steep = RisingNoiseThreshold {
+            min_rise: 20.0,
+            ..threshold
+        };
+        assert!(rising_noise(&class(), &noise, &steep, today, 1).is_none());
+
+        let mut store = InsightStore::default();
+        let found = detect(
+            &[class()],
+            &AttendanceStore::default(),
+            &noise,
+            &InsightThresholds {
+                rising_noise: threshold,
+                ..Default::default()
+            },
+            today,
+            5,
+        );
+        assert_eq!(store.update(found.clone()).len(), 1);
+        // Still there later: kept, not announced again
+        let later: Vec<Insight> = found
+            .into_iter()
+            .map(|i| Insight {
+                detected_at: 9,
+                ..i
+            })
+            .collect();
+        assert!(store.update(later).is_empty());
+        assert_eq!(store.insights[0].detected_at, 5);
+        assert!(store.update(Vec::new()).is_empty());
+        assert!(store.insights.is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+//! Background jobs with progress reporting
+//!
+//! Handles:
+//! - Running long work (backups, syncs, imports, report generation) on the
+//!   blocking thread pool under a job id
+//! - Tracking state and progress, emitted as `job-progress` events so the UI
+//!   can show a single activity panel (throttled per job, see
+//!   `event_throttle`)
+//! - Named stages with their own counts (e.g. an import's reading, parsing,
+//!   ...), emitted as `job-stage` events; each stage is throttled on its
+//!   own, so moving on to the next stage is never coalesced away
+//! - Cooperative cancellation: work checks `JobContext::check_cancelled`
+//!   between steps
+//!
+//! Commands still await the job and return its result; the registry only
+//! adds visibility and cancellation. Finished jobs are kept for a while so
+//! the panel can show recent activity.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::event_throttle;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::AppHandle;
+
+/// Event emitted on every job state or progress change
+pub const PROGRESS_EVENT: &str = "job-progress";
+
+/// Event emitted when a job enters or advances a stage
+pub const STAGE_EVENT: &str = "job-stage";
+
+/// Finished jobs kept in the registry
+const MAX_FINISHED_JOBS: usize = 50;
+
+static REGISTRY: Mutex<JobRegistry> = Mutex::new(JobRegistry::new());
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Lifecycle of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Current stage of a job, counted in the stage's own unit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStage {
+    pub name: String,
+    pub done: usize,
+    /// `None` when the total is unknown
+    pub total: Option<usize>,
+}
+
+/// Payload of `job-stage` events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StageEvent<'a> {
+    job_id: &'a str,
+    #[serde(flatten)]
+    stage: &'a JobStage,
+}
+
+/// Snapshot of a job, as sent to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    /// Category: "backup", "sync", "import", "export", "report", "migration"
+    pub kind: String,
+    /// Human-readable description
+    pub label: String,
+    pub state: JobState,
+    /// 0.0 - 1.0, `None` when the total is unknown
+    pub progress: Option<f32>,
+    pub message: Option<String>,
+    /// Latest stage, for jobs reporting stages
+    pub stage: Option<JobStage>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+    pub error: Option<BackendError>,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+/// All known jobs, keyed by id (ids sort by creation)
+struct JobRegistry {
+    jobs: BTreeMap<String, JobEntry>,
+}
+
+impl JobRegistry {
+    const fn new() -> Self {
+        Self {
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, info: JobInfo) -> Arc<AtomicBool> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.insert(
+            info.id.clone(),
+            JobEntry {
+                info,
+                cancel: cancel.clone(),
+            },
+        );
+        self.prune();
+        cancel
+    }
+
+    fn update(&mut self, id: &str, change: impl FnOnce(&mut JobInfo)) -> Option<JobInfo> {
+        let entry = self.jobs.get_mut(id)?;
+        change(&mut entry.info);
+        Some(entry.info.clone())
+    }
+
+    /// Drop the oldest finished jobs beyond the limit
+    fn prune(&mut self) {
+        let finished: Vec<String> = self
+            .jobs
+            .values()
+            .filter(|e| e.info.state.is_finished())
+            .map(|e| e.info.id.clone())
+            .collect();
+        let excess = finished.len().saturating_sub(MAX_FINISHED_JOBS);
+        for id in finished.into_iter().take(excess) {
+            self.jobs.remove(&id);
+        }
+    }
+
+    fn list(&self) -> Vec<JobInfo> {
+        self.jobs.values().rev().map(|e| e.info.clone()).collect()
+    }
+
+    fn cancel(&mut self, id: &str) -> Result<JobInfo, BackendError> {
+        let entry = self.jobs.get_mut(id).ok_or_else(|| not_found(id))?;
+        if !entry.info.state.is_finished() {
+            entry.cancel.store(true, Ordering::SeqCst);
+            entry.info.message = Some("Cancelling…".to_string());
+        }
+        Ok(entry.info.clone())
+    }
+}
+
+fn not_found(id: &str) -> BackendError {
+    BackendError::new(errors::job::NOT_FOUND, "Job not found").with_details(id.to_string())
+}
+
+fn registry() -> std::sync::MutexGuard<'static, JobRegistry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn emit(info: &JobInfo) {
+    if let Some(app) = APP.get() {
+        event_throttle::emit(app, PROGRESS_EVENT, &info.id, info);
+    }
+}
+
+fn update(id: &str, change: impl FnOnce(&mut JobInfo)) {
+    let info = registry().update(id, change);
+    if let Some(info) = info {
+        emit(&info);
+    }
+}
+
+/// Handle passed to running work
+pub struct JobContext {
+    id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// `Err(JOB_CANCELLED)` once cancellation was requested
+    pub fn check_cancelled(&self) -> Result<(), BackendError> {
+        if self.is_cancelled() {
+            Err(BackendError::new(
+                errors::job::CANCELLED,
+                "Cancelled by the user",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report `done` of `total` steps
+    pub fn progress(&self, done: usize, total: usize, message: impl Into<String>) {
+        let fraction = if total == 0 {
+            None
+        } else {
+            Some((done as f32 / total as f32).clamp(0.0, 1.0))
+        };
+        let message = message.into();
+        update(&self.id, |info| {
+            info.progress = fraction;
+            info.message = Some(message);
+        });
+    }
+
+    /// Report `done` of `total` in stage `name`
+    pub fn stage(&self, name: &str, done: usize, total: Option<usize>) {
+        let stage = JobStage {
+            name: name.to_string(),
+            done,
+            total,
+        };
+        update(&self.id, |info| info.stage = Some(stage.clone()));
+        if let Some(app) = APP.get() {
+            let event = StageEvent {
+                job_id: &self.id,
+                stage: &stage,
+            };
+            let key = format!("{}/{}", self.id, name);
+            event_throttle::emit(app, STAGE_EVENT, &key, &event);
+        }
+    }
+}
+
+/// Register the app handle used to emit progress events
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Run `work` as a tracked job and wait for its result
+pub async fn run<T: Send + 'static>(
+    kind: &str,
+    label: impl Into<String>,
+    work: impl FnOnce(&JobContext) -> Result<T, BackendError> + Send + 'static,
+) -> Result<T, BackendError> {
+    let now = clock::now_millis();
+    let id = format!("job_{}_{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let info = JobInfo {
+        id: id.clone(),
+        kind: kind.to_string(),
+        label: label.into(),
+        state: JobState::Queued,
+        progress: None,
+        message: None,
+        stage: None,
+        created_at: now,
+        finished_at: None,
+        error: None,
+    };
+    let cancel = registry().insert(info.clone());
+    emit(&info);
+
+    let context = JobContext {
+        id: id.clone(),
+        cancel,
+    };
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        update(&context.id, |info| info.state = JobState::Running);
+        // A job cancelled while queued never starts
+        context.check_cancelled()?;
+        work(&context)
+    })
+    .await
+    .map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Background task failed")
+            .with_details(e.to_string())
+    })
+    .and_then(|r| r);
+
+    update(&id, |info| {
+        info.finished_at = Some(clock::now_millis());
+        match &result {
+            Ok(_) => {
+                info.state = JobState::Completed;
+                info.progress = Some(1.0);
+            }
+            Err(e) if e.code == errors::job::CANCELLED => {
+                info.state = JobState::Cancelled;
+                info.message = None;
+            }
+            Err(e) => {
+                info.state = JobState::Failed;
+                info.error = Some(e.clone());
+            }
+        }
+    });
+    registry().prune();
+    result
+}
+
+/// All jobs, newest first
+pub fn list_jobs() -> Vec<JobInfo> {
+    registry().list()
+}
+
+/// A single job
+pub fn get_job(id: &str) -> Result<JobInfo, BackendError> {
+    registry()
+        .jobs
+        .get(id)
+        .map(|e| e.info.clone())
+        .ok_or_else(|| not_found(id))
+}
+
+/// Request cancellation of a job (no effect once it has finished)
+pub fn cancel_job(id: &str) -> Result<JobInfo, BackendError> {
+    let info = registry().cancel(id)?;
+    emit(&info);
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, state: JobState) -> JobInfo {
+        JobInfo {
+            id: id.to_string(),
+            kind: "backup".into(),
+            label: "Backup".into(),
+            state,
+            progress: None,
+            message: None,
+            stage: None,
+            created_at: 0,
+            finished_at: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_registry_prunes_oldest_finished_jobs() {
+        let mut registry = JobRegistry::new();
+        registry.insert(info("job_000_running", JobState::Running));
+        for i in 0..MAX_FINISHED_JOBS + 5 {
+            registry.insert(info(&format!("job_{:03}", i), JobState::Completed));
+        }
+        assert_eq!(registry.jobs.len(), MAX_FINISHED_JOBS + 1);
+        assert!(registry.jobs.contains_key("job_000_running"));
+        assert!(!registry.jobs.contains_key("job_000"));
+        assert_eq!(
+            registry.list()[0].id,
+            format!("job_{:03}", MAX_FINISHED_JOBS + 4)
+        );
+    }
+
+    #[test]
+    fn test_cancel_sets_flag_only_for_unfinished_jobs() {
+        let mut registry = JobRegistry::new();
+        let running = registry.insert(info("a", JobState::Running));
+        let done = registry.insert(info("b", JobState::Completed));
+
+        registry.cancel("a").unwrap();
+        registry.cancel("b").unwrap();
+        assert!(running.load(Ordering::SeqCst));
+        assert!(!done.load(Ordering::SeqCst));
+        assert_eq!(
+            registry.cancel("missing").unwrap_err().code,
+            errors::job::NOT_FOUND
+        );
+
+        let context = JobContext {
+            id: "a".into(),
+            cancel: running,
+        };
+        assert_eq!(
+            context.check_cancelled().unwrap_err().code,
+            errors::job::CANCELLED
+        );
+    }
+}
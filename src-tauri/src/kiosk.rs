@@ -0,0 +1,557 @@
+//! Read-only kiosk page on the LAN
+//!
+//! Handles:
+//! - An HTTP listener on the LAN interface chosen for the companion
+//!   server (see `lan_network`), on its own port; served over HTTPS with
+//!   the `lan_tls` certificate while LAN TLS is enabled
+//! - One self-refreshing HTML page, generated here without scripts:
+//!   - `notice`: only the notice
+//!   - `schedule`: today's bell schedule with the current period marked
+//!   - `queue`: the conference queue (`appointments`), who is in and who
+//!     is next with their expected times
+//! - An optional notice shown on top of the page ("Room 12, ring the
+//!   bell")
+//!
+//! For parents' evening, parents waiting in the corridor open the address
+//! on their phones. Anyone on the network can read the page and it has no
+//! login, so it only ever shows what the teacher picked; there is nothing
+//! to submit and no other path is served.
+
+use crate::appointments::{self, QueueState};
+use crate::errors::{self, BackendError};
+use crate::lan_network::{self, LanBindConfig};
+use crate::lan_tls;
+use crate::locale::{self, Language};
+use crate::schedule::{self, BellSchedule, PeriodKind};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Default port of the kiosk page
+pub const DEFAULT_KIOSK_PORT: u16 = 8767;
+pub const MAX_NOTICE_CHARS: usize = 500;
+/// Phones reload the page this often
+const REFRESH_SECONDS: u32 = 15;
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Handle of the served page, kept in `AppState`
+pub struct KioskServer {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    content: KioskContent,
+    notice: Option<String>,
+    address: SocketAddr,
+    tls: bool,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// What the page shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KioskContent {
+    Notice,
+    Schedule,
+    Queue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskStatus {
+    pub running: bool,
+    pub content_id: Option<KioskContent>,
+    pub notice: Option<String>,
+    pub port: Option<u16>,
+    /// Served over HTTPS; phones warn about the self-signed certificate
+    /// until it is accepted
+    pub tls: bool,
+    /// Addresses to open on a phone (or to put on a QR poster)
+    pub urls: Vec<String>,
+}
+
+/// Escape text for HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl KioskContent {
+    fn title(self, lang: Language) -> &'static str {
+        match self {
+            KioskContent::Notice => lang.pick("Avvisi", "Notices"),
+            KioskContent::Schedule => lang.pick("Orario di oggi", "Today's schedule"),
+            KioskContent::Queue => lang.pick("Colloqui", "Conferences"),
+        }
+    }
+
+    /// HTML of the page's main part
+    fn body(self, lang: Language, now: NaiveDateTime) -> String {
+        match self {
+            KioskContent::Notice => String::new(),
+            KioskContent::Schedule => render_schedule(&schedule::get_bell_schedule(), lang, now),
+            KioskContent::Queue => render_queue(&appointments::get_queue_state(), lang),
+        }
+    }
+}
+
+/// Who is in, then the waiting list with expected times
+fn render_queue(state: &QueueState, lang: Language) -> String {
+    let mut html = match &state.current {
+        Some(entry) => format!(
+            "<p class=\"current\">{} <b>{} · {}</b></p>",
+            lang.pick("In colloquio:", "Now seeing:"),
+            entry.appointment.number,
+            escape(&entry.appointment.name)
+        ),
+        None => String::new(),
+    };
+    if state.running_late_minutes > 0 {
+        html.push_str(&format!(
+            "<p class=\"late\">{} {} min</p>",
+            lang.pick("Ritardo:", "Running late:"),
+            state.running_late_minutes
+        ));
+    }
+    if state.waiting.is_empty() {
+        html.push_str(&format!(
+            "<p class=\"empty\">{}</p>",
+            lang.pick("Nessuno in attesa", "Nobody waiting")
+        ));
+        return html;
+    }
+    let rows: String = state
+        .waiting
+        .iter()
+        .map(|entry| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                entry.appointment.number,
+                escape(&entry.appointment.name),
+                appointments::local_time(entry.expected_at)
+            )
+        })
+        .collect();
+    html.push_str(&format!("<table>{}</table>", rows));
+    html
+}
+
+/// Today's periods as a table, the current one marked
+fn render_schedule(schedule: &BellSchedule, lang: Language, now: NaiveDateTime) -> String {
+    let weekday = now.date().weekday().number_from_monday() as u8;
+    let parse = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").ok();
+    let mut periods: Vec<_> = schedule
+        .periods
+        .iter()
+        .filter(|p| p.weekdays.contains(&weekday))
+        .filter_map(|p| Some((parse(&p.start)?, parse(&p.end)?, p)))
+        .collect();
+    if periods.is_empty() {
+        return format!(
+            "<p class=\"empty\">{}</p>",
+            lang.pick("Nessuna lezione oggi", "No lessons today")
+        );
+    }
+    periods.sort_by_key(|(start, _, _)| *start);
+    let rows: String = periods
+        .iter()
+        .map(|(start, end, p)| {
+            let mut classes = Vec::new();
+            if p.kind == PeriodKind::Break {
+                classes.push("break");
+            }
+            if *start <= now.time() && now.time() < *end {
+                classes.push("now");
+            }
+            format!(
+                "<tr class=\"{}\"><td>{}–{}</td><td>{}</td></tr>",
+                classes.join(" "),
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                escape(&p.label)
+            )
+        })
+        .collect();
+    format!("<table>{}</table>", rows)
+}
+
+/// The whole page
+fn render_page(
+    content: KioskContent,
+    notice: Option<&str>,
+    body: &str,
+    lang: Language,
+    now: NaiveDateTime,
+) -> String {
+    let title = escape(content.title(lang));
+    let notice = notice
+        .map(|n| {
+            format!(
+                "<p class=\"notice\">{}</p>",
+                escape(n).replace('\n', "<br>")
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "<!doctype html>\n<html lang=\"{lang}\"><head><meta charset=\"utf-8\">\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+<meta http-equiv=\"refresh\" content=\"{refresh}\"><title>{title}</title><style>\
+body{{font-family:system-ui,sans-serif;margin:0 auto;max-width:40rem;padding:1rem;color:#111;background:#fff}}\
+h1{{font-size:1.5rem}}.notice{{font-size:1.2rem;padding:.75rem;background:#fff4c2;border-radius:.5rem}}\
+table{{width:100%;border-collapse:collapse;font-size:1.1rem}}td{{padding:.5rem;border-bottom:1px solid #ddd}}\
+tr.break{{color:#666}}tr.now{{font-weight:bold;background:#e3f0ff}}.empty{{color:#666}}\
+.current{{font-size:1.4rem}}.late{{color:#a40}}\
+footer{{margin-top:1.5rem;color:#666;font-size:.9rem}}\
+</style></head><body><h1>{title}</h1>{notice}<main>{body}</main>\
+<footer>{updated} {time}</footer></body></html>\n",
+        lang = lang.code(),
+        refresh = REFRESH_SECONDS,
+        title = title,
+        notice = notice,
+        body = body,
+        updated = lang.pick("Aggiornato alle", "Updated at"),
+        time = now.format("%H:%M"),
+    )
+}
+
+/// Status line, content type and body for a request line
+fn respond(
+    request_line: &str,
+    page: impl FnOnce() -> String,
+) -> (&'static str, &'static str, String) {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return ("400 Bad Request", "text/plain", "Bad request".into());
+    };
+    if method != "GET" && method != "HEAD" {
+        return (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Read-only page".into(),
+        );
+    }
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    if path != "/" && path != "/index.html" {
+        return ("404 Not Found", "text/plain", "Not found".into());
+    }
+    let body = if method == "HEAD" {
+        String::new()
+    } else {
+        page()
+    };
+    ("200 OK", "text/html; charset=utf-8", body)
+}
+
+fn handle_connection(mut stream: impl Read + Write, content: KioskContent, notice: Option<&str>) {
+    let mut line = String::new();
+    let mut reader = BufReader::new((&mut stream).take(MAX_REQUEST_BYTES));
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    drop(reader);
+    let (status, content_type, body) = respond(&line, || {
+        let lang = locale::app_language();
+        let now = Local::now().naive_local();
+        render_page(content, notice, &content.body(lang, now), lang, now)
+    });
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+Content-Security-Policy: default-src 'none'; style-src 'unsafe-inline'\r\n\
+X-Content-Type-Options: nosniff\r\nReferrer-Policy: no-referrer\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.flush();
+}
+
+/// Serve one connection, over TLS when `tls` is set
+fn serve(
+    stream: TcpStream,
+    tls: Option<&Arc<rustls::ServerConfig>>,
+    content: KioskContent,
+    notice: Option<&str>,
+) {
+    // Also bounds the TLS handshake
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Some(config) = tls else {
+        return handle_connection(stream, content, notice);
+    };
+    let Ok(mut stream) = lan_tls::accept(config, stream) else {
+        return;
+    };
+    handle_connection(&mut stream, content, notice);
+    stream.conn.send_close_notify();
+    let _ = stream.flush();
+}
+
+/// Addresses phones can open for a listener bound to `address`
+fn page_urls(address: SocketAddr, tls: bool) -> Vec<String> {
+    let ips: Vec<IpAddr> = if address.ip().is_unspecified() {
+        lan_network::list_network_interfaces()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|i| i.is_ipv4 && !i.is_loopback)
+            .filter_map(|i| i.ip.parse().ok())
+            .collect()
+    } else {
+        vec![address.ip()]
+    };
+    let scheme = if tls { "https" } else { "http" };
+    ips.into_iter()
+        .map(|ip| format!("{}://{}/", scheme, SocketAddr::new(ip, address.port())))
+        .collect()
+}
+
+impl KioskServer {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(None),
+        }
+    }
+
+    pub fn status(&self) -> KioskStatus {
+        let kiosk = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        match kiosk.as_ref() {
+            Some(running) => KioskStatus {
+                running: true,
+                content_id: Some(running.content),
+                notice: running.notice.clone(),
+                port: Some(running.address.port()),
+                tls: running.tls,
+                urls: page_urls(running.address, running.tls),
+            },
+            None => KioskStatus {
+                running: false,
+                content_id: None,
+                notice: None,
+                port: None,
+                tls: false,
+                urls: Vec::new(),
+            },
+        }
+    }
+
+    /// Serve the page, replacing one already served
+    pub fn start(
+        &self,
+        content: KioskContent,
+        port: Option<u16>,
+        notice: Option<String>,
+    ) -> Result<KioskStatus, BackendError> {
+        let port = port.unwrap_or(DEFAULT_KIOSK_PORT);
+        if port < 1024 {
+            return Err(BackendError::new(
+                errors::system::INVALID_INPUT,
+                "Port must be between 1024 and 65535",
+            ));
+        }
+        let notice = notice
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty());
+        if notice
+            .as_ref()
+            .is_some_and(|n| n.chars().count() > MAX_NOTICE_CHARS)
+        {
+            return Err(BackendError::new(
+                errors::system::INVALID_INPUT,
+                format!("The notice is at most {} characters", MAX_NOTICE_CHARS),
+            ));
+        }
+        self.stop();
+
+        let tls = lan_tls::server_config()?;
+        let address = lan_network::resolve_bind_address(&LanBindConfig {
+            interface: lan_network::get_bind_config().interface,
+            port,
+        })?;
+        let listener = TcpListener::bind(address).map_err(|e| {
+            BackendError::new(errors::lan::NETWORK_ERROR, "Failed to open kiosk port")
+                .with_details(format!("{}: {}", address, e))
+        })?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (stop, notice, tls) = (stop.clone(), notice.clone(), tls.clone());
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let (notice, tls) = (notice.clone(), tls.clone());
+                    std::thread::spawn(move || {
+                        serve(stream, tls.as_ref(), content, notice.as_deref())
+                    });
+                }
+            })
+        };
+        *self.running.lock().unwrap_or_else(|e| e.into_inner()) = Some(Running {
+            content,
+            notice,
+            address,
+            tls: tls.is_some(),
+            stop,
+            thread,
+        });
+        Ok(self.status())
+    }
+
+    /// Stop serving the page
+    pub fn stop(&self) {
+        let Some(running) = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        else {
+            return;
+        };
+        running.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept so the thread sees the flag and frees the port
+        let wake = if running.address.ip().is_unspecified() {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), running.address.port())
+        } else {
+            running.address
+        };
+        let _ = TcpStream::connect_timeout(&wake, READ_TIMEOUT);
+        let _ = running.thread.join();
+    }
+}
+
+impl Default for KioskServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::BellPeriod;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_page_escapes_and_marks_current_period() {
+        let period = |label: &str, kind, start: &str, end: &str| BellPeriod {
+            label: label.to_string(),
+            kind,
+            start: start.to_string(),
+            end: end.to_string(),
+            weekdays: vec![1, 2, 3, 4, 5],
+        };
+        let schedule = BellSchedule {
+            periods: vec![
+                period("Intervallo", PeriodKind::Break, "10:50", "11:00"),
+                period("<b>Colloqui</b>", PeriodKind::Lesson, "16:00", "19:00"),
+            ],
+        };
+        // 2024-03-04 is a Monday
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let now = day.and_hms_opt(17, 5, 0).unwrap();
+        let body = render_schedule(&schedule, Language::It, now);
+        assert!(body.find("Intervallo").unwrap() < body.find("Colloqui").unwrap());
+        assert!(body.contains(
+            "<tr class=\"now\"><td>16:00–19:00</td><td>&lt;b&gt;Colloqui&lt;/b&gt;</td></tr>"
+        ));
+        assert!(body.contains("<tr class=\"break\">"));
+        let sunday = NaiveDate::from_ymd_opt(2024, 3, 10)
+            .unwrap()
+            .and_hms_opt(17, 0, 0)
+            .unwrap();
+        assert!(render_schedule(&schedule, Language::En, sunday).contains("No lessons today"));
+
+        let page = render_page(
+            KioskContent::Schedule,
+            Some("Aula 12\n<script>"),
+            &body,
+            Language::It,
+            now,
+        );
+        assert!(page.contains("<title>Orario di oggi</title>"));
+        assert!(page.contains("Aula 12<br>&lt;script&gt;"));
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("Aggiornato alle 17:05"));
+    }
+
+    #[test]
+    fn test_only_the_page_is_served() {
+        let page = || "page".to_string();
+        assert_eq!(respond("GET / HTTP/1.1\r\n", page).0, "200 OK");
+        assert_eq!(respond("GET /index.html?x=1 HTTP/1.1", page).2, "page");
+        assert_eq!(respond("HEAD / HTTP/1.1", page).2, "");
+        assert_eq!(
+            respond("GET /../config.json HTTP/1.1", page).0,
+            "404 Not Found"
+        );
+        assert_eq!(respond("POST / HTTP/1.1", page).0, "405 Method Not Allowed");
+        assert_eq!(respond("", page).0, "400 Bad Request");
+
+        let content: KioskContent = serde_json::from_value(serde_json::json!("schedule")).unwrap();
+        assert_eq!(content, KioskContent::Schedule);
+    }
+
+    #[test]
+    fn test_urls_follow_tls() {
+        let address: SocketAddr = "192.168.1.20:8767".parse().unwrap();
+        assert_eq!(page_urls(address, false), ["http://192.168.1.20:8767/"]);
+        assert_eq!(page_urls(address, true), ["https://192.168.1.20:8767/"]);
+    }
+
+    #[test]
+    fn test_queue_page() {
+        let entry = |number, name: &str, expected_at| appointments::QueueEntry {
+            appointment: appointments::Appointment {
+                number,
+                name: name.to_string(),
+                student_id: None,
+                slot_minutes: 10,
+                scheduled_at: None,
+            },
+            expected_at,
+            expected_end_at: expected_at + 600_000,
+            late_minutes: 0,
+        };
+        let at = 1_700_000_000_000;
+        let mut state = QueueState {
+            current: Some(entry(1, "Fam. Rossi", at)),
+            waiting: vec![entry(2, "<i>Bianchi</i>", at + 600_000)],
+            done: 0,
+            running_late_minutes: 5,
+            updated_at: at,
+        };
+        let body = render_queue(&state, Language::En);
+        assert!(body.contains("Now seeing: <b>1 · Fam. Rossi</b>"));
+        assert!(body.contains("Running late: 5 min"));
+        assert!(body.contains(&format!(
+            "<tr><td>2</td><td>&lt;i&gt;Bianchi&lt;/i&gt;</td><td>{}</td></tr>",
+            appointments::local_time(at + 600_000)
+        )));
+
+        state.current = None;
+        state.waiting.clear();
+        state.running_late_minutes = 0;
+        assert_eq!(
+            render_queue(&state, Language::It),
+            "<p class=\"empty\">Nessuno in attesa</p>"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Network interface selection for the companion LAN channel
+//!
+//! Handles:
+//! - Listing local network interfaces and their addresses
+//! - Persisting which interface/port the LAN server binds to
+//! - Platform-specific hints about firewalls likely blocking the port
+//!
+//! School PCs often have several adapters (Wi-Fi, Ethernet, VPN, Hyper-V),
+//! and binding to the wrong one is the most common "tablets can't connect".
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+
+const BIND_CONFIG_KEY: &str = "lan_bind";
+
+/// Default port of the companion LAN server
+pub const DEFAULT_LAN_PORT: u16 = 8765;
+
+/// A local network interface address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub ip: String,
+    pub is_ipv4: bool,
+    pub is_loopback: bool,
+    /// RFC 1918 / unique-local address (typical classroom LAN)
+    pub is_private: bool,
+}
+
+/// Where the LAN server should listen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanBindConfig {
+    /// Interface name; `None` binds to all interfaces
+    pub interface: Option<String>,
+    pub port: u16,
+}
+
+impl Default for LanBindConfig {
+    fn default() -> Self {
+        Self {
+            interface: None,
+            port: DEFAULT_LAN_PORT,
+        }
+    }
+}
+
+/// Severity of a firewall/network hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HintSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Structured hint shown in the LAN setup screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkHint {
+    pub code: String,
+    pub severity: HintSeverity,
+    pub message: String,
+}
+
+impl NetworkHint {
+    fn new(code: &str, severity: HintSeverity, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// List all local interface addresses, IPv4 first
+pub fn list_network_interfaces() -> Result<Vec<NetworkInterface>, BackendError> {
+    let addrs = if_addrs::get_if_addrs().map_err(|e| {
+        BackendError::new(
+            errors::lan::NETWORK_ERROR,
+            "Failed to list network interfaces",
+        )
+        .with_details(e.to_string())
+    })?;
+
+    let mut interfaces: Vec<NetworkInterface> = addrs
+        .into_iter()
+        .map(|iface| {
+            let ip = iface.ip();
+            NetworkInterface {
+                name: iface.name.clone(),
+                ip: ip.to_string(),
+                is_ipv4: ip.is_ipv4(),
+                is_loopback: iface.is_loopback(),
+                is_private: is_private_ip(&ip),
+            }
+        })
+        .collect();
+
+    interfaces.sort_by_key(|i| (!i.is_ipv4, i.is_loopback, !i.is_private));
+    Ok(interfaces)
+}
+
+/// Whether an address belongs to a private LAN range
+pub fn is_private_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_private() || v4.is_link_local(),
+        // fc00::/7 unique local addresses
+        IpAddr::V6(v6) => (v6.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+/// Load the persisted bind configuration
+pub fn get_bind_config() -> LanBindConfig {
+    file_ops::load_config(BIND_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Persist the bind configuration after validating the interface exists
+pub fn set_bind_config(config: LanBindConfig) -> Result<LanBindConfig, BackendError> {
+    if config.port < 1024 {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "LAN port must be between 1024 and 65535",
+        ));
+    }
+    if let Some(name) = &config.interface {
+        resolve_interface_ip(name, &list_network_interfaces()?)?;
+    }
+
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid bind configuration")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(BIND_CONFIG_KEY, value)?;
+    Ok(config)
+}
+
+/// Pick the IPv4 address of a named interface (falls back to IPv6)
+fn resolve_interface_ip(
+    name: &str,
+    interfaces: &[NetworkInterface],
+) -> Result<IpAddr, BackendError> {
+    interfaces
+        .iter()
+        .filter(|i| i.name == name)
+        .min_by_key(|i| !i.is_ipv4)
+        .and_then(|i| i.ip.parse().ok())
+        .ok_or_else(|| {
+            BackendError::new(
+                errors::lan::INTERFACE_NOT_FOUND,
+                "Network interface not found",
+            )
+            .with_details(name.to_string())
+        })
+}
+
+/// Resolve the socket address the LAN server should bind to
+pub fn resolve_bind_address(config: &LanBindConfig) -> Result<SocketAddr, BackendError> {
+    let ip = match &config.interface {
+        Some(name) => resolve_interface_ip(name, &list_network_interfaces()?)?,
+        None => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+    };
+    Ok(SocketAddr::new(ip, config.port))
+}
+
+/// Check whether the configured address is reachable from other devices
+///
+/// Returns structured hints; an empty list means nothing suspicious was found.
+pub fn check_network_reachability(
+    config: &LanBindConfig,
+) -> Result<Vec<NetworkHint>, BackendError> {
+    let mut hints = Vec::new();
+    let addr = resolve_bind_address(config)?;
+
+    if addr.ip().is_loopback() {
+        hints.push(NetworkHint::new(
+            "LOOPBACK_ONLY",
+            HintSeverity::Error,
+            "The selected interface is loopback; other devices cannot connect",
+        ));
+    } else if !addr.ip().is_unspecified() && !is_private_ip(&addr.ip()) {
+        hints.push(NetworkHint::new(
+            "PUBLIC_ADDRESS",
+            HintSeverity::Warning,
+            "The selected address is not a private LAN address; check you are on the school network",
+        ));
+    }
+
+    // Probe the port (the LAN server itself is not running during this check)
+    if let Err(e) = TcpListener::bind(addr) {
+        let (code, message) = match e.kind() {
+            std::io::ErrorKind::AddrInUse => (
+                "PORT_IN_USE",
+                format!("Port {} is already used by another program", config.port),
+            ),
+            std::io::ErrorKind::PermissionDenied => (
+                "PORT_FORBIDDEN",
+                format!("Not allowed to listen on port {}", config.port),
+            ),
+            _ => ("BIND_FAILED", format!("Cannot listen on {}: {}", addr, e)),
+        };
+        hints.push(NetworkHint::new(code, HintSeverity::Error, message));
+    }
+
+    hints.extend(firewall_hints(config.port));
+    Ok(hints)
+}
+
+/// Run a command and return its stdout if it succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn firewall_hints(port: u16) -> Vec<NetworkHint> {
+    command_output("netsh", &["advfirewall", "show", "currentprofile", "state"])
+        .map(|out| parse_windows_firewall(&out, port))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn firewall_hints(port: u16) -> Vec<NetworkHint> {
+    command_output(
+        "/usr/libexec/ApplicationFirewall/socketfilterfw",
+        &["--getglobalstate"],
+    )
+    .map(|out| parse_macos_firewall(&out, port))
+    .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn firewall_hints(port: u16) -> Vec<NetworkHint> {
+    if let Some(out) = command_output("ufw", &["status"]) {
+        return parse_ufw_status(&out, port);
+    }
+    if let Some(out) = command_output("firewall-cmd", &["--state"]) {
+        if out.trim() == "running" {
+            return vec![NetworkHint::new(
+                "FIREWALL_ACTIVE",
+                HintSeverity::Warning,
+                format!(
+                    "firewalld is running; allow the port with: firewall-cmd --add-port={}/tcp",
+                    port
+                ),
+            )];
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn firewall_hints(_port: u16) -> Vec<NetworkHint> {
+    Vec::new()
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_windows_firewall(output: &str, port: u16) -> Vec<NetworkHint> {
+    let enabled = output
+        .lines()
+        .any(|l| l.trim_start().starts_with("State") && l.contains("ON"));
+    if !enabled {
+        return Vec::new();
+    }
+    vec![NetworkHint::new(
+        "FIREWALL_ACTIVE",
+        HintSeverity::Warning,
+        format!(
+            "Windows Defender Firewall is on; when prompted, allow the app on private networks (TCP port {})",
+            port
+        ),
+    )]
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_macos_firewall(output: &str, _port: u16) -> Vec<NetworkHint> {
+    if !output.contains("enabled") {
+        return Vec::new();
+    }
+    vec![NetworkHint::new(
+        "FIREWALL_ACTIVE",
+        HintSeverity::Info,
+        "macOS firewall is on; accept \"Allow incoming connections\" when asked",
+    )]
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_ufw_status(output: &str, port: u16) -> Vec<NetworkHint> {
+    if !output.contains("Status: active") {
+        return Vec::new();
+    }
+    let port_str = port.to_string();
+    let allowed = output.lines().any(|l| {
+        l.split_whitespace()
+            .next()
+            .is_some_and(|rule| rule.split('/').next() == Some(port_str.as_str()))
+            && l.contains("ALLOW")
+    });
+    if allowed {
+        return Vec::new();
+    }
+    vec![NetworkHint::new(
+        "FIREWALL_ACTIVE",
+        HintSeverity::Warning,
+        format!(
+            "ufw is active and port {} is not allowed: sudo ufw allow {}/tcp",
+            port, port
+        ),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_ip_detection() {
+        assert!(is_private_ip(&"192.168.1.20".parse().unwrap()));
+        assert!(is_private_ip(&"10.0.0.5".parse().unwrap()));
+        assert!(!is_private_ip(&"8.8.8.8".parse().unwrap()));
+        assert!(is_private_ip(&"fd12:3456::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_resolve_interface_prefers_ipv4() {
+        let interfaces = vec![
+            NetworkInterface {
+                name: "eth0".into(),
+                ip: "fe80::1".into(),
+                is_ipv4: false,
+                is_loopback: false,
+                is_private: false,
+            },
+            NetworkInterface {
+                name: "eth0".into(),
+                ip: "192.168.1.20".into(),
+                is_ipv4: true,
+                is_loopback: false,
+                is_private: true,
+            },
+        ];
+        let ip = resolve_interface_ip("eth0", &interfaces).unwrap();
+        assert_eq!(ip.to_string(), "192.168.1.20");
+        assert!(resolve_interface_ip("wlan0", &interfaces).is_err());
+    }
+
+    #[test]
+    fn test_parse_ufw_status() {
+        let inactive = "Status: inactive\n";
+        assert!(parse_ufw_status(inactive, 8765).is_empty());
+
+        let blocked = "Status: active\n\nTo                         Action      From\n22/tcp                     ALLOW       Anywhere\n";
+        assert_eq!(parse_ufw_status(blocked, 8765).len(), 1);
+
+        let allowed = "Status: active\n\n8765/tcp                   ALLOW       Anywhere\n";
+        assert!(parse_ufw_status(allowed, 8765).is_empty());
+    }
+
+    #[test]
+    fn test_parse_windows_firewall() {
+        let on = "Domain Profile Settings:\n----------------------------------------------------------------------\nState                                 ON\n";
+        assert_eq!(parse_windows_firewall(on, 8765).len(), 1);
+        let off = "State                                 OFF\n";
+        assert!(parse_windows_firewall(off, 8765).is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! TLS material for the companion LAN channel
+//!
+//! Handles:
+//! - Generating and storing a self-signed server certificate (rcgen)
+//! - Exposing the certificate SHA-256 fingerprint for pinning
+//! - Building the pairing QR payload (pairing code + fingerprint)
+//! - The rustls server config LAN listeners wrap accepted connections
+//!   with while TLS is enabled (the kiosk page, see `kiosk`)
+//!
+//! School networks have no CA we can rely on, so companion apps pin the
+//! fingerprint scanned from the QR code instead of validating a chain.
+
+use crate::clock;
+use crate::companion_auth;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+const STORE_COLLECTION: &str = "lan_tls";
+const TLS_ENABLED_CONFIG_KEY: &str = "lan_tls_enabled";
+
+/// Hostnames embedded in the certificate
+const SUBJECT_ALT_NAMES: &[&str] = &["localhost", "classroom.local"];
+
+/// Stored certificate and private key (PEM)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+    pub fingerprint: String,
+    pub created_at: u64,
+}
+
+/// Public TLS status returned to the frontend (never includes the key)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub enabled: bool,
+    pub fingerprint: Option<String>,
+    pub created_at: Option<u64>,
+}
+
+/// Generate a new self-signed certificate
+pub fn generate_certificate(now: u64) -> Result<StoredCertificate, BackendError> {
+    let names: Vec<String> = SUBJECT_ALT_NAMES.iter().map(|s| s.to_string()).collect();
+    let certified = rcgen::generate_simple_self_signed(names).map_err(|e| {
+        BackendError::new(errors::lan::TLS_ERROR, "Failed to generate TLS certificate")
+            .with_details(e.to_string())
+    })?;
+
+    Ok(StoredCertificate {
+        cert_pem: certified.cert.pem(),
+        key_pem: certified.key_pair.serialize_pem(),
+        fingerprint: fingerprint(certified.cert.der()),
+        created_at: now,
+    })
+}
+
+/// SHA-256 fingerprint of a DER certificate as colon-separated uppercase hex
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Load the stored certificate, generating one on first use
+pub fn ensure_certificate() -> Result<StoredCertificate, BackendError> {
+    let stored: StoredCertificate = file_ops::load_data(STORE_COLLECTION)?;
+    if !stored.cert_pem.is_empty() && !stored.key_pem.is_empty() {
+        return Ok(stored);
+    }
+    regenerate_certificate()
+}
+
+/// Replace the stored certificate (paired devices must rescan the QR code)
+pub fn regenerate_certificate() -> Result<StoredCertificate, BackendError> {
+    let cert = generate_certificate(clock::now_millis())?;
+    file_ops::save_data(STORE_COLLECTION, &cert)?;
+    Ok(cert)
+}
+
+/// Server config presenting `stored`
+pub fn server_config_for(stored: &StoredCertificate) -> Result<Arc<ServerConfig>, BackendError> {
+    let tls_error = |details: String| {
+        BackendError::new(errors::lan::TLS_ERROR, "Invalid TLS certificate").with_details(details)
+    };
+    let cert = CertificateDer::from_pem_slice(stored.cert_pem.as_bytes())
+        .map_err(|e| tls_error(e.to_string()))?;
+    let key = PrivateKeyDer::from_pem_slice(stored.key_pem.as_bytes())
+        .map_err(|e| tls_error(e.to_string()))?;
+    // Explicit provider: the process-wide default is ambiguous when other
+    // crates enable a second one
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| tls_error(e.to_string()))?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .map_err(|e| tls_error(e.to_string()))?;
+    Ok(Arc::new(config))
+}
+
+/// Server config for LAN listeners, `None` while TLS is disabled
+///
+/// Read once when a listener starts; toggling TLS or regenerating the
+/// certificate applies from the next start.
+pub fn server_config() -> Result<Option<Arc<ServerConfig>>, BackendError> {
+    if !is_enabled() {
+        return Ok(None);
+    }
+    server_config_for(&ensure_certificate()?).map(Some)
+}
+
+/// Wrap an accepted connection; the handshake runs on the first read
+pub fn accept(
+    config: &Arc<ServerConfig>,
+    stream: TcpStream,
+) -> Result<StreamOwned<ServerConnection, TcpStream>, BackendError> {
+    let connection = ServerConnection::new(config.clone()).map_err(|e| {
+        BackendError::new(errors::lan::TLS_ERROR, "Failed to start TLS session")
+            .with_details(e.to_string())
+    })?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+/// Whether TLS is enabled for the LAN channel
+pub fn is_enabled() -> bool {
+    file_ops::load_config(TLS_ENABLED_CONFIG_KEY)
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Enable or disable TLS; enabling makes sure a certificate exists
+pub fn set_enabled(enabled: bool) -> Result<TlsInfo, BackendError> {
+    if enabled {
+        ensure_certificate()?;
+    }
+    file_ops::save_config(TLS_ENABLED_CONFIG_KEY, serde_json::Value::Bool(enabled))?;
+    get_tls_info()
+}
+
+/// Current TLS status
+pub fn get_tls_info() -> Result<TlsInfo, BackendError> {
+    let stored: StoredCertificate = file_ops::load_data(STORE_COLLECTION)?;
+    let has_cert = !stored.cert_pem.is_empty();
+    Ok(TlsInfo {
+        enabled: is_enabled(),
+        fingerprint: has_cert.then(|| stored.fingerprint.clone()),
+        created_at: has_cert.then_some(stored.created_at),
+    })
+}
+
+/// Build the URI encoded in the pairing QR code
+///
+/// Format: `classroom://pair?code=<code>&tls=<0|1>[&fp=<fingerprint>]`
+pub fn pairing_uri(code: &str, fingerprint: Option<&str>) -> String {
+    match fingerprint {
+        Some(fp) => format!(
+            "classroom://pair?code={}&tls=1&fp={}",
+            code,
+            fp.replace(':', "")
+        ),
+        None => format!("classroom://pair?code={}&tls=0", code),
+    }
+}
+
+/// Pairing QR payload for the currently open pairing window
+pub fn get_pairing_qr_payload() -> Result<String, BackendError> {
+    let session = companion_auth::current_pairing()?;
+    let fingerprint = if is_enabled() {
+        Some(ensure_certificate()?.fingerprint)
+    } else {
+        None
+    };
+    Ok(pairing_uri(&session.code, fingerprint.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_certificate() {
+        let cert = generate_certificate(42).unwrap();
+        assert!(cert.cert_pem.contains("BEGIN CERTIFICATE"));
+        assert!(cert.key_pem.contains("PRIVATE KEY"));
+        // 32 bytes -> 32 hex pairs separated by 31 colons
+        assert_eq!(cert.fingerprint.len(), 32 * 2 + 31);
+        assert_eq!(cert.created_at, 42);
+    }
+
+    #[test]
+    fn test_server_config_from_stored_pem() {
+        let cert = generate_certificate(42).unwrap();
+        assert!(server_config_for(&cert).is_ok());
+        let broken = StoredCertificate {
+            key_pem: String::new(),
+            ..cert
+        };
+        assert_eq!(
+            server_config_for(&broken).unwrap_err().code,
+            errors::lan::TLS_ERROR
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint(b"abc"), fingerprint(b"abc"));
+        assert!(fingerprint(b"abc").starts_with("BA:78:16:BF"));
+    }
+
+    #[test]
+    fn test_pairing_uri() {
+        assert_eq!(
+            pairing_uri("123456", Some("AB:CD")),
+            "classroom://pair?code=123456&tls=1&fp=ABCD"
+        );
+        assert_eq!(
+            pairing_uri("123456", None),
+            "classroom://pair?code=123456&tls=0"
+        );
+    }
+}
@@ -10,36 +10,485 @@
 //! For the decision on when to use Rust vs. Frontend:
 //! See docs/architecture.md and CLAUDE.md "Quando Usare Rust Backend"
 
+pub mod accommodations;
+pub mod agc;
+pub mod actions;
+pub mod ambient_light;
+pub mod analytics;
+pub mod annotation;
+pub mod app_lock;
+pub mod appointments;
+pub mod asset_protocol;
+pub mod attachments;
+pub mod audio_output;
+pub mod audio_presets;
+pub mod audio_supervisor;
+pub mod badge_reader;
+pub mod badge_scan;
+pub mod background_audio;
+pub mod backup;
+pub mod class_archive;
+pub mod class_comparison;
+pub mod class_records;
+pub mod classroom_state;
+pub mod clock;
+pub mod clock_sync;
+pub mod cloud;
+pub mod cloud_s3;
+pub mod command_trace;
 pub mod commands;
+pub mod companion_auth;
+pub mod content_protection;
+pub mod controller;
+pub mod data_integrity;
+pub mod day_overview;
+pub mod devices;
+pub mod diagnostics;
+pub mod display_layout;
+pub mod documents;
+pub mod end_of_day;
 pub mod errors;
+pub mod event_throttle;
+pub mod exam_mode;
+pub mod exit_tickets;
+pub mod feedback;
 pub mod file_ops;
+pub mod forms_import;
+pub mod freeze_broadcast;
+pub mod fuzzy;
+pub mod grade_export;
+pub mod gradebook;
+pub mod hid;
+pub mod image_optimize;
+pub mod import_history;
+pub mod input_mute;
+pub mod insights;
+pub mod jobs;
+pub mod kiosk;
+pub mod lan_network;
+pub mod lan_tls;
+pub mod locale;
+pub mod mailer;
+pub mod mood_checkin;
+pub mod noise_history;
+pub mod observer;
+pub mod ocr;
 pub mod window;
+pub mod perf_stats;
+pub mod profile_settings;
+pub mod projector_dim;
+pub mod quick_notes;
+pub mod quiet_hours;
+pub mod read_only_mode;
 pub mod permissions;
+pub mod pointer_highlight;
+pub mod presentation_safe;
+pub mod photos;
+pub mod platform;
+pub mod recovery;
+pub mod research_export;
+pub mod roles;
+pub mod roster;
+pub mod roster_import;
+pub mod roster_merge;
+pub mod roster_sync;
+pub mod schedule;
+pub mod screenshot;
+pub mod seating_solver;
+pub mod secrets;
+pub mod settings_reset;
+pub mod shutdown;
+pub mod sound_units;
+pub mod startup;
+pub mod state;
+pub mod substitute;
+pub mod timers;
+pub mod weekly_summary;
 
 /// Initialize and run the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    window::prepare_display_backend();
+    window::prepare_webview_data_dir();
+    let result = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        // Register all command handlers
-        .invoke_handler(tauri::generate_handler![
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(state::AppState::new())
+        .register_asynchronous_uri_scheme_protocol(
+            asset_protocol::SCHEME,
+            asset_protocol::handle,
+        )
+        // Register all command handlers, behind the app lock, the active
+        // profile's role, the observer window's read-only check and
+        // read-only mode, all traced when command tracing is on
+        .invoke_handler(command_trace::trace_guard(app_lock::lock_guard(roles::command_guard(
+            observer::read_only_guard(read_only_mode::guard(tauri::generate_handler![
             // File operations
             commands::read_csv,
             commands::save_config,
             commands::load_config,
+            commands::get_data_directory,
+            commands::set_data_directory,
+            commands::get_read_only_status,
             // Window management
             commands::get_window_position,
             commands::set_window_position,
+            commands::get_safe_area,
+            commands::get_window_capabilities,
+            commands::get_webview_zoom,
+            commands::set_webview_zoom,
+            commands::set_content_protection,
+            commands::get_content_protection,
+            commands::set_presentation_safe,
+            commands::get_presentation_safe,
+            commands::set_presentation_safe_shortcut,
             // Permissions
             commands::request_microphone_permission,
+            commands::get_input_mute_state,
+            commands::request_screen_capture_permission,
+            commands::get_camera_permission,
+            commands::sample_ambient_light,
+            commands::start_badge_scan,
+            commands::stop_badge_scan,
+            commands::get_badge_scan_status,
+            commands::get_badge_reader,
+            commands::set_badge_reader,
+            // Exit tickets
+            commands::start_exit_ticket,
+            commands::close_exit_ticket,
+            commands::submit_exit_ticket,
+            commands::get_exit_tickets,
+            commands::start_mood_checkin,
+            commands::submit_mood_checkin,
+            commands::close_mood_checkin,
+            commands::get_mood_checkins,
+            // Exam mode
+            commands::start_exam_mode,
+            commands::stop_exam_mode,
+            commands::get_exam_status,
+            commands::list_exam_sessions,
+            commands::export_exam_log,
+            // Companion devices
+            commands::start_device_pairing,
+            commands::stop_device_pairing,
+            commands::pair_device,
+            commands::refresh_device_token,
+            commands::list_devices,
+            commands::rename_device,
+            commands::revoke_device,
+            // LAN TLS
+            commands::get_tls_info,
+            commands::set_lan_tls_enabled,
+            commands::regenerate_tls_certificate,
+            commands::get_pairing_qr_payload,
+            // LAN network
+            commands::list_network_interfaces,
+            commands::get_lan_bind_config,
+            commands::set_lan_bind_config,
+            commands::check_lan_reachability,
+            commands::start_kiosk_page,
+            commands::stop_kiosk_page,
+            commands::get_kiosk_status,
+            commands::add_appointment,
+            commands::call_next,
+            commands::remove_appointment,
+            commands::clear_appointment_queue,
+            commands::get_queue_state,
+            // Backup & cloud
+            commands::create_backup,
+            commands::list_backups,
+            commands::set_backup_passphrase,
+            commands::configure_webdav,
+            commands::configure_s3,
+            commands::backup_to_cloud,
+            commands::restore_from_cloud,
+            // Rosters
+            commands::get_classes,
+            commands::preview_roster_import,
+            commands::get_ocr_status,
+            commands::ocr_image_to_table,
+            commands::import_roster_file,
+            commands::get_import_history,
+            commands::get_roster_watch_folder,
+            commands::set_roster_watch_folder,
+            commands::scan_roster_folder,
+            commands::get_pending_roster_updates,
+            commands::apply_roster_update,
+            commands::dismiss_roster_update,
+            commands::preview_roster_merge,
+            commands::merge_roster,
+            // Documents
+            commands::generate_docx_from_template,
+            commands::generate_class_documents,
+            // Email & weekly summary
+            commands::configure_smtp,
+            commands::configure_weekly_summary,
+            commands::get_weekly_summary_config,
+            commands::get_weekly_summary_log,
+            commands::generate_weekly_summary_now,
+            commands::configure_end_of_day,
+            commands::get_end_of_day_config,
+            commands::get_end_of_day_log,
+            // Class records & archive
+            commands::record_attendance,
+            commands::add_behavior_entry,
+            commands::add_quick_note,
+            commands::list_quick_notes,
+            commands::promote_quick_note,
+            commands::save_seating_chart,
+            commands::get_seating_chart,
+            commands::suggest_seating,
+            commands::set_student_accommodations,
+            commands::pick_student,
+            commands::generate_groups,
+            commands::export_class_archive,
+            commands::preview_class_archive,
+            commands::import_class_archive,
+            commands::generate_substitute_pack,
+            commands::get_substitute_packs,
+            // Device inventory
+            commands::add_device,
+            commands::assign_device,
+            commands::return_device,
+            commands::report_device_issue,
+            commands::resolve_device_issue,
+            commands::get_device_inventory,
+            // Background jobs
+            commands::list_jobs,
+            commands::get_job,
+            commands::cancel_job,
+            // Settings reset
+            commands::reset_setting,
+            commands::request_factory_reset,
+            commands::factory_reset,
+            commands::get_config_recovery,
+            // Locale
+            commands::get_system_locale,
+            commands::get_app_language,
+            commands::set_app_language,
+            commands::format_date,
+            commands::format_number,
+            commands::localize_error,
+            // Lesson recovery
+            commands::save_lesson_state,
+            commands::get_recovery_state,
+            commands::discard_recovery_state,
+            commands::clear_lesson_state,
+            commands::get_previous_session,
+            // Audio supervision
+            commands::get_audio_restart_policy,
+            commands::set_audio_restart_policy,
+            // Event throttling
+            commands::get_event_rates,
+            commands::set_event_rate,
+            // Diagnostics & feedback
+            commands::get_performance_stats,
+            commands::get_diagnostics_bundle,
+            commands::verify_data_integrity,
+            commands::get_startup_report,
+            commands::submit_feedback,
+            commands::get_command_trace,
+            commands::set_command_trace,
+            // Analytics
+            commands::set_analytics_consent,
+            commands::record_feature_usage,
+            commands::get_analytics_preview,
+            // Actions
+            commands::list_backend_actions,
+            commands::execute_action,
+            // Controller
+            commands::get_controller_info,
+            commands::set_controller_enabled,
+            commands::regenerate_controller_token,
+            // Presenter remotes
+            commands::get_presenter_bindings,
+            commands::set_presenter_bindings,
+            commands::validate_accelerator,
+            // Timer sequences
+            commands::create_timer_sequence,
+            commands::skip_phase,
+            commands::get_sequence_state,
+            commands::stop_timer_sequence,
+            commands::set_timer_announcements,
+            commands::get_timer_announcements,
+            // Classroom state
+            commands::get_classroom_state,
+            commands::set_classroom_state,
+            commands::report_noise_level,
+            commands::get_agc,
+            commands::set_agc,
+            commands::get_sound_unit,
+            commands::set_sound_unit,
+            commands::get_monitoring_quiet_hours,
+            commands::set_monitoring_quiet_hours,
+            // Bell schedule
+            commands::get_bell_schedule,
+            commands::set_bell_schedule,
+            commands::get_time_remaining_in_period,
+            // Attachments
+            commands::attach_file,
+            commands::list_attachments,
+            commands::open_attachment,
+            commands::remove_attachment,
+            // Student photos
+            commands::import_photos_from_folder,
+            commands::optimize_image,
+            // Name matching
+            commands::match_names,
+            // Clock check
+            commands::check_clock_skew,
+            // Observer window
+            commands::set_observer_pin,
+            commands::open_observer_window,
+            // Profiles and roles
+            commands::list_profiles,
+            commands::create_profile,
+            commands::set_profile_pin,
+            commands::delete_profile,
+            commands::set_active_profile,
+            // App lock
+            commands::get_app_lock_status,
+            commands::set_app_lock,
+            commands::lock_app,
+            commands::unlock_app,
+            commands::recover_app_lock,
+            // Annotations
+            commands::open_annotation_overlay,
+            commands::close_annotation_overlay,
+            commands::get_annotation_overlay_status,
+            commands::set_annotation_click_through,
+            commands::relay_annotation_stroke,
+            commands::undo_annotation,
+            commands::clear_annotations,
+            // Projector dimming
+            commands::dim_projector,
+            commands::undim_projector,
+            commands::get_projector_dim,
+            commands::broadcast_freeze,
+            commands::release_freeze,
+            commands::get_freeze_status,
+            commands::get_device_freeze,
+            // Pointer highlight
+            commands::set_pointer_highlight,
+            commands::get_pointer_highlight,
+            // Screenshots
+            commands::capture_window_screenshot,
+            // Background audio
+            commands::play_background_audio,
+            commands::stop_background_audio,
+            commands::duck_background_audio,
+            commands::get_background_audio_status,
+            // Audio outputs
+            commands::list_audio_output_devices,
+            commands::get_audio_output_routing,
+            commands::set_output_device,
+            commands::get_volume_safety,
+            commands::set_volume_safety,
+            commands::play_alert_chime,
+            // Noise history
+            commands::set_noise_context,
+            commands::get_noise_context,
+            commands::suggest_thresholds,
+            // Audio presets
+            commands::export_audio_presets,
+            commands::import_audio_presets,
+            // Day overview
+            commands::get_day_overview,
+            commands::compare_classes,
+            // Insights
+            commands::get_insights,
+            commands::get_insight_thresholds,
+            commands::set_insight_thresholds,
+            // Research export
+            commands::export_research_dataset,
+            // Gradebook
+            commands::add_score,
+            commands::delete_score,
+            commands::get_student_scores,
+            commands::get_student_average,
+            // Grade export
+            commands::export_grades,
+            commands::list_grade_templates,
+            commands::save_grade_template,
+            commands::delete_grade_template,
+            // Forms import
+            commands::preview_forms_results,
+            commands::import_forms_results,
+            // Profile settings
+            commands::diff_profile_settings,
+            commands::copy_settings_between_profiles,
+            // Display layout
+            commands::get_display_configuration,
+            commands::save_window_layout,
             // Utility
             commands::greet,
-        ])
+        ])),
+        ))))
         // Setup window on startup
         .setup(|app| {
+            file_ops::config_repair::start(app.handle().clone());
+            // The main window starts hidden; nothing else runs when a
+            // check fails
+            let report = startup::run_checks(app.handle());
+            if !report.ok {
+                startup::show_diagnostics(app.handle(), &report)?;
+                return Ok(());
+            }
             window::setup_window(app.handle())?;
+            read_only_mode::detect(app.handle());
+            command_trace::start();
+            jobs::init(app.handle().clone());
+            roster_sync::start_watcher(app.handle().clone());
+            weekly_summary::start_scheduler();
+            insights::start(app.handle().clone());
+            shutdown::start();
+            recovery::start();
+            analytics::start();
+            tauri::Manager::state::<state::AppState>(app)
+                .controller()
+                .start(app.handle().clone());
+            badge_reader::start(app.handle().clone());
+            end_of_day::start(app.handle().clone());
+            hid::start(app.handle());
+            input_mute::start();
+            presentation_safe::start(app.handle());
+            schedule::start(app.handle().clone());
+            clock_sync::start(app.handle().clone());
+            app_lock::start(app.handle().clone());
+            display_layout::start(app.handle().clone());
+            startup::show_main(app.handle());
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        // Saved per-window zoom and capture protection survive reloads and
+        // reopening; protection goes on before the page shows anything
+        .on_page_load(|webview, payload| match payload.event() {
+            tauri::webview::PageLoadEvent::Started => content_protection::apply_saved(webview),
+            tauri::webview::PageLoadEvent::Finished => window::apply_saved_zoom(webview),
+        })
+        // Projector layers must not outlive the main window; exam mode
+        // watches the main window's focus and fullscreen
+        .on_window_event(|window, event| {
+            exam_mode::on_window_event(window, event);
+            if window.label() == "main" && matches!(event, tauri::WindowEvent::Destroyed) {
+                let app = tauri::Manager::app_handle(window);
+                let _ = projector_dim::undim_projector(app);
+                let _ = annotation::close_annotation_overlay(app);
+                let _ = pointer_highlight::remove_pointer_highlight(app);
+            }
+        })
+        .build(tauri::generate_context!());
+    // Errors before any window could show (no webview runtime, the
+    // diagnostic window failing to open) only have the console
+    let app = match result {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("Classroom Management Tool could not start: {}", e);
+            std::process::exit(1);
+        }
+    };
+    app.run(|app, event| {
+        if let tauri::RunEvent::ExitRequested { .. } = event {
+            shutdown::shutdown(app);
+        }
+    });
 }
@@ -0,0 +1,303 @@
+//! App language and locale-aware formatting
+//!
+//! Handles:
+//! - Detecting the OS locale
+//! - Persisting the app language (`app_language` config key)
+//! - Date and number formatting for exports and reports
+//! - Localized messages for error codes shown to the user
+//!
+//! The UI ships in Italian and English. Without a saved choice the system
+//! language is used when supported, Italian otherwise.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use chrono::{Datelike, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+const LANGUAGE_CONFIG_KEY: &str = "app_language";
+
+/// Languages the app is translated into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    It,
+    En,
+}
+
+impl Language {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::It => "it",
+            Language::En => "en",
+        }
+    }
+
+    /// Parse a language or locale tag (`it`, `en-US`, `it_IT.UTF-8`)
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        match primary.as_str() {
+            "it" => Some(Language::It),
+            "en" => Some(Language::En),
+            _ => None,
+        }
+    }
+
+    /// Choose between the Italian and English variant of a text
+    pub fn pick<'a>(&self, it: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Language::It => it,
+            Language::En => en,
+        }
+    }
+}
+
+/// Date format length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    /// `16/10/2026` (it), `10/16/2026` (en)
+    #[default]
+    Short,
+    /// `16 ottobre 2026` (it), `October 16, 2026` (en)
+    Long,
+}
+
+const MONTHS_IT: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// OS locale as a BCP 47 tag (e.g. `it-IT`), `en-US` if unknown
+pub fn get_system_locale() -> String {
+    sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string())
+}
+
+/// Current app language
+pub fn app_language() -> Language {
+    file_ops::load_config(LANGUAGE_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .or_else(|| Language::from_tag(&get_system_locale()))
+        .unwrap_or_default()
+}
+
+/// Set the app language (`it` or `en`)
+pub fn set_app_language(lang: &str) -> Result<Language, BackendError> {
+    let language = Language::from_tag(lang).ok_or_else(|| {
+        BackendError::new(errors::system::INVALID_INPUT, "Unsupported language")
+            .with_details(lang.to_string())
+    })?;
+    file_ops::save_config(LANGUAGE_CONFIG_KEY, language.code().into())?;
+    Ok(language)
+}
+
+/// Format epoch millis as a local date
+pub fn format_date(millis: u64, style: DateStyle, lang: Language) -> String {
+    let Some(dt) = Local.timestamp_millis_opt(millis as i64).single() else {
+        return String::new();
+    };
+    let month = dt.month0() as usize;
+    match (style, lang) {
+        (DateStyle::Short, Language::It) => dt.format("%d/%m/%Y").to_string(),
+        (DateStyle::Short, Language::En) => dt.format("%m/%d/%Y").to_string(),
+        (DateStyle::Long, Language::It) => {
+            format!("{} {} {}", dt.day(), MONTHS_IT[month], dt.year())
+        }
+        (DateStyle::Long, Language::En) => {
+            format!("{} {}, {}", MONTHS_EN[month], dt.day(), dt.year())
+        }
+    }
+}
+
+/// Format a number with grouping (`1.234,5` in Italian, `1,234.5` in English)
+pub fn format_number(value: f64, decimals: usize, lang: Language) -> String {
+    let (group, decimal) = match lang {
+        Language::It => ('.', ','),
+        Language::En => (',', '.'),
+    };
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut out = String::new();
+    if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            out.push(group);
+        }
+        out.push(c);
+    }
+    if let Some(frac) = frac_part {
+        out.push(decimal);
+        out.push_str(frac);
+    }
+    out
+}
+
+/// User-facing message for an error code, when a translation exists
+pub fn error_message(code: &str, lang: Language) -> Option<&'static str> {
+    let (it, en) = match code {
+        errors::file::NOT_FOUND => ("File non trovato", "File not found"),
+        errors::file::PERMISSION_DENIED => ("Permesso negato", "Permission denied"),
+        errors::file::INVALID_FORMAT => ("Formato del file non valido", "Invalid file format"),
+        errors::file::ENCODING_ERROR => (
+            "Codifica del file non riconosciuta",
+            "Unrecognized file encoding",
+        ),
+        errors::file::IO_ERROR => ("Errore di lettura o scrittura", "Read or write error"),
+        errors::permission::MICROPHONE_DENIED => {
+            ("Accesso al microfono negato", "Microphone access denied")
+        }
+        errors::permission::KEYBOARD_ACCESS_DENIED => (
+            "Impossibile leggere la tastiera per il lettore di badge",
+            "The keyboard can't be read for the badge reader",
+        ),
+        errors::roster::CLASS_NOT_FOUND => ("Classe non trovata", "Class not found"),
+        errors::roster::INVALID_ROSTER => (
+            "L'elenco studenti contiene errori",
+            "The student list has errors",
+        ),
+        errors::import::STRICT_REJECTED => (
+            "Il file contiene errori; non è stato importato nulla",
+            "The file has errors; nothing was imported",
+        ),
+        errors::roster::CLASS_EXISTS => (
+            "Esiste già una classe con questo nome",
+            "A class with this name already exists",
+        ),
+        errors::accommodation::NO_ELIGIBLE_STUDENT => (
+            "Nessuno studente può essere scelto",
+            "No student can be picked",
+        ),
+        errors::accommodation::VIOLATED => (
+            "La richiesta non rispetta le esigenze di uno studente",
+            "The request breaks a student's accommodation",
+        ),
+        errors::backup::UNREACHABLE => (
+            "Server di backup non raggiungibile",
+            "Backup server unreachable",
+        ),
+        errors::backup::WRONG_PASSPHRASE => {
+            ("Password del backup errata", "Wrong backup passphrase")
+        }
+        errors::backup::PASSPHRASE_REQUIRED => (
+            "Imposta una password per i backup prima di usare il cloud",
+            "Set a backup passphrase before using cloud backup",
+        ),
+        errors::mail::NOT_CONFIGURED => ("Email non configurata", "Email is not configured"),
+        errors::mail::SEND_FAILED => ("Invio email non riuscito", "Failed to send email"),
+        errors::config::VALIDATION_FAILED => (
+            "Valore dell'impostazione non valido",
+            "Invalid setting value",
+        ),
+        errors::job::CANCELLED => ("Operazione annullata", "Operation cancelled"),
+        errors::ocr::ENGINE_UNAVAILABLE => (
+            "Per leggere le foto serve Tesseract, che non è installato",
+            "Reading photos needs Tesseract, which is not installed",
+        ),
+        errors::system::INVALID_INPUT => ("Dati non validi", "Invalid input"),
+        _ => return None,
+    };
+    Some(lang.pick(it, en))
+}
+
+/// Replace an error's message with the app-language version, if any
+pub fn localize_error(error: BackendError, lang: Language) -> BackendError {
+    match error_message(&error.code, lang) {
+        Some(message) => BackendError {
+            message: message.to_string(),
+            ..error
+        },
+        None => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_tag() {
+        assert_eq!(Language::from_tag("it-IT"), Some(Language::It));
+        assert_eq!(Language::from_tag("en_GB.UTF-8"), Some(Language::En));
+        assert_eq!(Language::from_tag("de-DE"), None);
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234567.891, 2, Language::It), "1.234.567,89");
+        assert_eq!(format_number(1234567.891, 2, Language::En), "1,234,567.89");
+        assert_eq!(format_number(-999.0, 0, Language::It), "-999");
+        assert_eq!(format_number(-0.001, 1, Language::En), "0.0");
+        assert_eq!(format_number(1000.0, 0, Language::It), "1.000");
+    }
+
+    #[test]
+    fn test_format_date_styles() {
+        let millis = Local
+            .with_ymd_and_hms(2026, 3, 5, 12, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        assert_eq!(
+            format_date(millis, DateStyle::Short, Language::It),
+            "05/03/2026"
+        );
+        assert_eq!(
+            format_date(millis, DateStyle::Short, Language::En),
+            "03/05/2026"
+        );
+        assert_eq!(
+            format_date(millis, DateStyle::Long, Language::It),
+            "5 marzo 2026"
+        );
+        assert_eq!(
+            format_date(millis, DateStyle::Long, Language::En),
+            "March 5, 2026"
+        );
+    }
+
+    #[test]
+    fn test_localize_error() {
+        let err = BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+            .with_details("class_1");
+        let localized = localize_error(err, Language::It);
+        assert_eq!(localized.message, "Classe non trovata");
+        assert_eq!(localized.details.as_deref(), Some("class_1"));
+
+        let unknown = BackendError::new("SOMETHING_ELSE", "Original");
+        assert_eq!(localize_error(unknown, Language::It).message, "Original");
+    }
+}
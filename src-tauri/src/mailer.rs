@@ -0,0 +1,221 @@
+//! Outgoing email over SMTP
+//!
+//! Handles:
+//! - Storing the SMTP server in config and the password in the keychain
+//! - Verifying the connection when configuring
+//! - Sending HTML messages with attachments (reports, exports)
+//!
+//! School accounts are usually Google Workspace or Microsoft 365, both of
+//! which accept STARTTLS on port 587 with an app password.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::secrets;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SMTP_CONFIG_KEY: &str = "mailer_smtp";
+
+/// Connection security
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// STARTTLS upgrade (port 587)
+    StartTls,
+    /// Implicit TLS (port 465)
+    Tls,
+}
+
+/// SMTP server settings (password lives in the keychain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    /// Sender, e.g. `Prof. Rossi <rossi@scuola.edu.it>`
+    pub from: String,
+}
+
+impl SmtpConfig {
+    fn secret_name(&self) -> String {
+        format!("smtp:{}@{}", self.username, self.host)
+    }
+}
+
+/// A file attached to a message
+#[derive(Debug, Clone)]
+pub struct MailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+fn send_error(message: &str, e: impl ToString) -> BackendError {
+    BackendError::new(errors::mail::SEND_FAILED, message).with_details(e.to_string())
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, BackendError> {
+    address
+        .trim()
+        .parse()
+        .map_err(|e: lettre::address::AddressError| {
+            BackendError::new(errors::mail::INVALID_ADDRESS, "Invalid email address")
+                .with_details(format!("{}: {}", address, e))
+        })
+}
+
+fn transport(config: &SmtpConfig, password: &str) -> Result<SmtpTransport, BackendError> {
+    let builder = match config.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&config.host),
+        SmtpSecurity::Tls => SmtpTransport::relay(&config.host),
+    }
+    .map_err(|e| send_error("Invalid SMTP server", e))?;
+
+    Ok(builder
+        .port(config.port)
+        .credentials(Credentials::new(
+            config.username.clone(),
+            password.to_string(),
+        ))
+        .timeout(Some(Duration::from_secs(30)))
+        .build())
+}
+
+fn get_config() -> Result<SmtpConfig, BackendError> {
+    file_ops::load_config(SMTP_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or_else(|| BackendError::new(errors::mail::NOT_CONFIGURED, "Email is not configured"))
+}
+
+/// Keychain entry of the configured account
+pub(crate) fn configured_secret() -> Option<String> {
+    get_config().ok().map(|c| c.secret_name())
+}
+
+/// Whether an SMTP server has been configured
+pub fn is_configured() -> bool {
+    get_config().is_ok()
+}
+
+/// Configure the SMTP server and verify the credentials
+pub fn configure_smtp(config: SmtpConfig, password: &str) -> Result<(), BackendError> {
+    parse_mailbox(&config.from)?;
+    let config = SmtpConfig {
+        host: config.host.trim().to_string(),
+        username: config.username.trim().to_string(),
+        ..config
+    };
+
+    let connected = transport(&config, password)?
+        .test_connection()
+        .map_err(|e| send_error("Could not connect to the SMTP server", e))?;
+    if !connected {
+        return Err(BackendError::new(
+            errors::mail::SEND_FAILED,
+            "Could not connect to the SMTP server",
+        ));
+    }
+
+    secrets::set_secret(&config.secret_name(), password)?;
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid SMTP configuration")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(SMTP_CONFIG_KEY, value)
+}
+
+/// Build a message (HTML body plus attachments)
+fn build_message(
+    from: &str,
+    to: &[String],
+    subject: &str,
+    html_body: &str,
+    attachments: &[MailAttachment],
+) -> Result<Message, BackendError> {
+    if to.is_empty() {
+        return Err(BackendError::new(
+            errors::mail::INVALID_ADDRESS,
+            "No recipients",
+        ));
+    }
+    let mut builder = Message::builder()
+        .from(parse_mailbox(from)?)
+        .subject(subject);
+    for address in to {
+        builder = builder.to(parse_mailbox(address)?);
+    }
+
+    let mut body = MultiPart::mixed().singlepart(SinglePart::html(html_body.to_string()));
+    for attachment in attachments {
+        let content_type = ContentType::parse(&attachment.content_type)
+            .map_err(|e| send_error("Invalid attachment type", e))?;
+        body = body.singlepart(
+            Attachment::new(attachment.file_name.clone())
+                .body(attachment.content.clone(), content_type),
+        );
+    }
+    builder
+        .multipart(body)
+        .map_err(|e| send_error("Failed to build email", e))
+}
+
+/// Send an HTML email through the configured server
+pub fn send_email(
+    to: &[String],
+    subject: &str,
+    html_body: &str,
+    attachments: &[MailAttachment],
+) -> Result<(), BackendError> {
+    let config = get_config()?;
+    let password = secrets::get_secret(&config.secret_name())?.ok_or_else(|| {
+        BackendError::new(errors::mail::NOT_CONFIGURED, "SMTP password is missing")
+    })?;
+
+    let message = build_message(&config.from, to, subject, html_body, attachments)?;
+    transport(&config, &password)?
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| send_error("Failed to send email", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_message_validates_addresses() {
+        let attachment = MailAttachment {
+            file_name: "report.html".into(),
+            content_type: "text/html; charset=utf-8".into(),
+            content: b"<p>ok</p>".to_vec(),
+        };
+        let message = build_message(
+            "Prof. Rossi <rossi@scuola.edu.it>",
+            &["genitori@example.com".into()],
+            "Riepilogo",
+            "<p>Ciao</p>",
+            &[attachment],
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+        assert!(raw.contains("Subject: Riepilogo"));
+        assert!(raw.contains("report.html"));
+
+        let err = build_message(
+            "rossi@scuola.edu.it",
+            &["not an address".into()],
+            "x",
+            "",
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(err.code, errors::mail::INVALID_ADDRESS);
+        assert!(build_message("rossi@scuola.edu.it", &[], "x", "", &[]).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+//! Anonymous "class mood" check-ins
+//!
+//! Handles:
+//! - Opening a check-in with a prompt and an emoji scale (e.g. 😞 … 😄)
+//! - Collecting one answer per companion device (see `companion_auth`)
+//! - Keeping only the count per choice: which device picked what is never
+//!   stored, so the results cannot be traced back to a student
+//! - Persisting the results per lesson, next to the lesson's exit tickets,
+//!   for wellbeing tracking over time
+//!
+//! To refuse a second answer, the open check-in remembers hashes of the
+//! devices that answered (salted with the session id); they are dropped
+//! when it closes. Stored in the `mood_checkins` data collection.
+
+use crate::clock;
+use crate::companion_auth;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const STORE_COLLECTION: &str = "mood_checkins";
+
+/// Scale used when none is given
+pub const DEFAULT_SCALE: &[&str] = &["😞", "🙁", "😐", "🙂", "😄"];
+
+const MIN_SCALE: usize = 2;
+const MAX_SCALE: usize = 7;
+const MAX_LABEL_CHARS: usize = 16;
+const MAX_PROMPT_CHARS: usize = 200;
+
+/// How a check-in is set up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MoodOptions {
+    /// Question shown on companion devices
+    pub prompt: String,
+    /// Choices from worst to best; `DEFAULT_SCALE` when empty
+    pub scale: Vec<String>,
+    /// Lesson to attach the results to (generated if omitted)
+    pub lesson_id: Option<String>,
+}
+
+/// A check-in and its aggregated results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoodCheckin {
+    pub id: String,
+    pub lesson_id: String,
+    pub prompt: String,
+    pub scale: Vec<String>,
+    /// Answers per choice, in scale order
+    pub counts: Vec<u32>,
+    pub opened_at: u64,
+    pub closed_at: Option<u64>,
+}
+
+impl MoodCheckin {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Mean position on the scale, 0.0 (worst) to 1.0 (best)
+    pub fn average(&self) -> Option<f64> {
+        let total = self.total();
+        if total == 0 || self.counts.len() < 2 {
+            return None;
+        }
+        let sum: f64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| i as f64 * count as f64)
+            .sum();
+        Some(sum / total as f64 / (self.counts.len() - 1) as f64)
+    }
+}
+
+/// Persisted check-ins
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoodStore {
+    pub checkins: Vec<MoodCheckin>,
+    /// Hashed ids of the devices that answered the open check-in
+    respondents: Vec<String>,
+}
+
+fn no_active_checkin() -> BackendError {
+    BackendError::new(
+        errors::mood::NO_ACTIVE_CHECKIN,
+        "No mood check-in is currently open",
+    )
+}
+
+fn invalid_input(message: &str) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message)
+}
+
+fn respondent_hash(checkin_id: &str, device_id: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}:{}", checkin_id, device_id)))
+}
+
+impl MoodStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STORE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STORE_COLLECTION, self)
+    }
+
+    fn open_checkin_mut(&mut self) -> Option<&mut MoodCheckin> {
+        self.checkins.iter_mut().find(|c| c.is_open())
+    }
+
+    /// Open a check-in; only one may be open at a time
+    pub fn start(
+        &mut self,
+        options: &MoodOptions,
+        lesson_id: &str,
+        now: u64,
+    ) -> Result<MoodCheckin, BackendError> {
+        if self.open_checkin_mut().is_some() {
+            return Err(BackendError::new(
+                errors::mood::ALREADY_OPEN,
+                "A mood check-in is already open",
+            ));
+        }
+        let prompt = options.prompt.trim();
+        if prompt.is_empty() || prompt.chars().count() > MAX_PROMPT_CHARS {
+            return Err(invalid_input(&format!(
+                "The prompt must be 1-{} characters",
+                MAX_PROMPT_CHARS
+            )));
+        }
+        let scale: Vec<String> = if options.scale.is_empty() {
+            DEFAULT_SCALE.iter().map(|s| s.to_string()).collect()
+        } else {
+            options.scale.iter().map(|s| s.trim().to_string()).collect()
+        };
+        if !(MIN_SCALE..=MAX_SCALE).contains(&scale.len()) {
+            return Err(invalid_input(&format!(
+                "The scale must have {}-{} choices",
+                MIN_SCALE, MAX_SCALE
+            )));
+        }
+        if scale
+            .iter()
+            .any(|s| s.is_empty() || s.chars().count() > MAX_LABEL_CHARS)
+        {
+            return Err(invalid_input(&format!(
+                "Each choice must be 1-{} characters",
+                MAX_LABEL_CHARS
+            )));
+        }
+
+        let checkin = MoodCheckin {
+            id: format!("mood_{}", now),
+            lesson_id: lesson_id.to_string(),
+            prompt: prompt.to_string(),
+            counts: vec![0; scale.len()],
+            scale,
+            opened_at: now,
+            closed_at: None,
+        };
+        self.respondents.clear();
+        self.checkins.push(checkin.clone());
+        Ok(checkin)
+    }
+
+    /// Count a device's answer (`choice`: index into the scale)
+    pub fn submit(&mut self, device_id: &str, choice: usize) -> Result<(), BackendError> {
+        let checkin = self
+            .checkins
+            .iter_mut()
+            .find(|c| c.is_open())
+            .ok_or_else(no_active_checkin)?;
+        if choice >= checkin.counts.len() {
+            return Err(BackendError::new(
+                errors::mood::INVALID_CHOICE,
+                "Choice is not on the scale",
+            )
+            .with_details(choice.to_string()));
+        }
+        let hash = respondent_hash(&checkin.id, device_id);
+        if self.respondents.contains(&hash) {
+            return Err(BackendError::new(
+                errors::mood::ALREADY_ANSWERED,
+                "This device already answered",
+            ));
+        }
+        checkin.counts[choice] += 1;
+        self.respondents.push(hash);
+        Ok(())
+    }
+
+    /// Close the open check-in, forgetting who answered
+    pub fn close(&mut self, now: u64) -> Result<MoodCheckin, BackendError> {
+        let checkin = self.open_checkin_mut().ok_or_else(no_active_checkin)?;
+        checkin.closed_at = Some(now);
+        let checkin = checkin.clone();
+        self.respondents.clear();
+        Ok(checkin)
+    }
+
+    /// Check-ins of a lesson, oldest first
+    pub fn for_lesson(&self, lesson_id: &str) -> Vec<MoodCheckin> {
+        self.checkins
+            .iter()
+            .filter(|c| c.lesson_id == lesson_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Open a mood check-in
+///
+/// If `options.lesson_id` is not provided, a new lesson id is derived from
+/// the current timestamp and returned in the check-in.
+pub fn start_mood_checkin(options: &MoodOptions) -> Result<MoodCheckin, BackendError> {
+    let now = clock::now_millis();
+    let lesson_id = options
+        .lesson_id
+        .clone()
+        .unwrap_or_else(|| format!("lesson_{}", now));
+
+    let mut store = MoodStore::load()?;
+    let checkin = store.start(options, &lesson_id, now)?;
+    store.save()?;
+    Ok(checkin)
+}
+
+/// Count an answer from an authenticated companion device
+pub fn submit_mood_checkin(token: &str, choice: usize) -> Result<(), BackendError> {
+    let device = companion_auth::authenticate(token)?;
+    let mut store = MoodStore::load()?;
+    store.submit(&device.id, choice)?;
+    store.save()
+}
+
+/// Close the open check-in and return its results
+pub fn close_mood_checkin() -> Result<MoodCheckin, BackendError> {
+    let mut store = MoodStore::load()?;
+    let checkin = store.close(clock::now_millis())?;
+    store.save()?;
+    Ok(checkin)
+}
+
+/// Get the check-ins of a lesson
+pub fn get_mood_checkins(lesson_id: &str) -> Result<Vec<MoodCheckin>, BackendError> {
+    Ok(MoodStore::load()?.for_lesson(lesson_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(scale: &[&str]) -> MoodOptions {
+        MoodOptions {
+            prompt: "Come stai oggi?".into(),
+            scale: scale.iter().map(|s| s.to_string()).collect(),
+            lesson_id: None,
+        }
+    }
+
+    #[test]
+    fn test_checkin_counts_one_answer_per_device() {
+        let mut store = MoodStore::default();
+        assert_eq!(
+            store.submit("dev1", 0).unwrap_err().code,
+            errors::mood::NO_ACTIVE_CHECKIN
+        );
+
+        let checkin = store.start(&options(&[]), "lesson_1", 10).unwrap();
+        assert_eq!(checkin.scale.len(), DEFAULT_SCALE.len());
+        assert!(store.start(&options(&[]), "lesson_1", 11).is_err());
+
+        store.submit("dev1", 4).unwrap();
+        store.submit("dev2", 2).unwrap();
+        assert_eq!(
+            store.submit("dev1", 0).unwrap_err().code,
+            errors::mood::ALREADY_ANSWERED
+        );
+        assert_eq!(
+            store.submit("dev3", 5).unwrap_err().code,
+            errors::mood::INVALID_CHOICE
+        );
+
+        let closed = store.close(20).unwrap();
+        assert_eq!(closed.counts, [0, 0, 1, 0, 1]);
+        assert_eq!(closed.average(), Some(0.75));
+        // Who answered is forgotten; only counts are stored
+        assert!(store.respondents.is_empty());
+        let stored = serde_json::to_string(&store).unwrap();
+        assert!(!stored.contains("dev1"));
+        assert_eq!(store.for_lesson("lesson_1"), [closed]);
+    }
+
+    #[test]
+    fn test_scale_validation() {
+        let mut store = MoodStore::default();
+        assert!(store.start(&options(&["👍"]), "l", 1).is_err());
+        assert!(store.start(&options(&["👍", " "]), "l", 1).is_err());
+        let blank = MoodOptions {
+            prompt: "  ".into(),
+            ..options(&[])
+        };
+        assert!(store.start(&blank, "l", 1).is_err());
+        let checkin = store.start(&options(&[" 👎 ", "👍"]), "l", 1).unwrap();
+        assert_eq!(checkin.scale, ["👎", "👍"]);
+        assert_eq!(checkin.average(), None);
+    }
+}
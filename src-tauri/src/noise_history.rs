@@ -0,0 +1,357 @@
+//! Noise history and threshold suggestions
+//!
+//! Handles:
+//! - Recording noise levels per class and activity type ("group work",
+//!   "silent work", ...) while a noise context is set; levels come from the
+//!   same feed as the classroom light (`report_noise_level`)
+//! - Storing them compactly as 1-unit histograms in the `noise_history`
+//!   collection (flushed every minute and on context change), per activity
+//!   and per day (the last `MAX_DAYS` days, for the day overview)
+//! - Suggesting yellow/red thresholds from percentiles, per activity and
+//!   overall, so teachers don't have to guess meter values
+//!
+//! Suggestions: yellow above the 80th percentile (the class's usual
+//! ceiling), red above the 95th, at least `MIN_GAP` apart.
+
+use crate::class_records;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const COLLECTION: &str = "noise_history";
+/// Levels above this are counted in the top bucket
+const MAX_LEVEL: usize = 140;
+const FLUSH_INTERVAL_MS: u64 = 60_000;
+/// Samples needed before suggesting anything (a few minutes of metering)
+pub const MIN_SAMPLES: u64 = 1_000;
+const YELLOW_PERCENTILE: f64 = 0.80;
+const RED_PERCENTILE: f64 = 0.95;
+const MIN_GAP: f64 = 5.0;
+const MAX_ACTIVITY_CHARS: usize = 40;
+/// Daily histograms kept per class
+const MAX_DAYS: usize = 120;
+
+/// Current context and samples not yet written
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// Counts per whole level (index = level)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    pub fn add(&mut self, level: f64) {
+        let bucket = (level.max(0.0).round() as usize).min(MAX_LEVEL);
+        if self.counts.len() <= bucket {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, extra) in self.counts.iter_mut().zip(&other.counts) {
+            *count += extra;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Average level
+    pub fn mean(&self) -> Option<f64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let sum: f64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(level, &count)| level as f64 * count as f64)
+            .sum();
+        Some(sum / total as f64)
+    }
+
+    /// Smallest level with at least `p` of the samples at or below it
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let rank = (p * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        self.counts.iter().enumerate().find_map(|(level, count)| {
+            seen += count;
+            (seen >= rank).then_some(level as f64)
+        })
+    }
+}
+
+/// Histograms of one class, per activity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassNoiseHistory {
+    pub activities: BTreeMap<String, Histogram>,
+    /// `YYYY-MM-DD` → all levels of that day
+    #[serde(default)]
+    pub days: BTreeMap<String, Histogram>,
+    pub updated_at: u64,
+}
+
+impl ClassNoiseHistory {
+    fn add_day(&mut self, date: String, histogram: &Histogram) {
+        self.days.entry(date).or_default().merge(histogram);
+        while self.days.len() > MAX_DAYS {
+            self.days.pop_first();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseHistoryStore {
+    pub classes: BTreeMap<String, ClassNoiseHistory>,
+}
+
+impl NoiseHistoryStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+}
+
+/// What is being recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseContext {
+    pub class_id: String,
+    pub activity: String,
+}
+
+#[derive(Debug)]
+struct Recorder {
+    context: NoiseContext,
+    pending: Histogram,
+    last_flush: u64,
+}
+
+/// Suggested thresholds for one activity (or all of them)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdSuggestion {
+    /// `None` for the class overall
+    pub activity: Option<String>,
+    pub samples: u64,
+    /// Whether there are enough samples to rely on
+    pub enough_data: bool,
+    pub median: Option<f64>,
+    pub yellow_above: Option<f64>,
+    pub red_above: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdSuggestions {
+    pub class_id: String,
+    pub overall: ThresholdSuggestion,
+    pub activities: Vec<ThresholdSuggestion>,
+}
+
+fn suggest(activity: Option<String>, histogram: &Histogram) -> ThresholdSuggestion {
+    let samples = histogram.total();
+    let yellow = histogram.percentile(YELLOW_PERCENTILE);
+    let red = histogram
+        .percentile(RED_PERCENTILE)
+        .zip(yellow)
+        .map(|(red, yellow)| red.max(yellow + MIN_GAP));
+    ThresholdSuggestion {
+        activity,
+        samples,
+        enough_data: samples >= MIN_SAMPLES,
+        median: histogram.percentile(0.5),
+        yellow_above: yellow,
+        red_above: red,
+    }
+}
+
+/// Normalize an activity label ("Group work " → "group work")
+fn normalize_activity(activity: &str) -> Result<String, BackendError> {
+    let activity = activity.trim().to_lowercase();
+    if activity.is_empty() || activity.chars().count() > MAX_ACTIVITY_CHARS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Activity must be 1 to {} characters", MAX_ACTIVITY_CHARS),
+        ));
+    }
+    Ok(activity)
+}
+
+fn flush(recorder: &mut Recorder, now: u64) -> Result<(), BackendError> {
+    recorder.last_flush = now;
+    if recorder.pending.total() == 0 {
+        return Ok(());
+    }
+    let mut store = NoiseHistoryStore::load()?;
+    let class = store
+        .classes
+        .entry(recorder.context.class_id.clone())
+        .or_default();
+    class
+        .activities
+        .entry(recorder.context.activity.clone())
+        .or_default()
+        .merge(&recorder.pending);
+    class.add_day(class_records::date_string(now), &recorder.pending);
+    class.updated_at = now;
+    store.save()?;
+    recorder.pending = Histogram::default();
+    Ok(())
+}
+
+/// Start recording for a class and activity, or stop with `None`
+pub fn set_noise_context(context: Option<NoiseContext>) -> Result<(), BackendError> {
+    let context = context
+        .map(|c| -> Result<NoiseContext, BackendError> {
+            Ok(NoiseContext {
+                activity: normalize_activity(&c.activity)?,
+                class_id: c.class_id,
+            })
+        })
+        .transpose()?;
+    let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    let now = clock::now_millis();
+    if let Some(current) = recorder.as_mut() {
+        flush(current, now)?;
+    }
+    *recorder = context.map(|context| Recorder {
+        context,
+        pending: Histogram::default(),
+        last_flush: now,
+    });
+    Ok(())
+}
+
+/// Current recording context
+pub fn get_noise_context() -> Option<NoiseContext> {
+    RECORDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|r| r.context.clone())
+}
+
+/// Count a level towards the current context (no-op without one)
+pub fn record(level: f64) {
+    let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(recorder) = recorder.as_mut() {
+        recorder.pending.add(level);
+        let now = clock::now_millis();
+        if now.saturating_sub(recorder.last_flush) >= FLUSH_INTERVAL_MS {
+            // A failed write keeps the samples for the next attempt
+            let _ = flush(recorder, now);
+        }
+    }
+}
+
+/// Write samples not yet flushed, so a read of the store includes them
+pub fn flush_pending() -> Result<(), BackendError> {
+    if let Some(recorder) = RECORDER.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        flush(recorder, clock::now_millis())?;
+    }
+    Ok(())
+}
+
+/// Suggest thresholds for a class from its history
+pub fn suggest_thresholds(class_id: &str) -> Result<ThresholdSuggestions, BackendError> {
+    flush_pending()?;
+    let store = NoiseHistoryStore::load()?;
+    let history = store.classes.get(class_id).cloned().unwrap_or_default();
+    let mut overall = Histogram::default();
+    let activities = history
+        .activities
+        .iter()
+        .map(|(activity, histogram)| {
+            overall.merge(histogram);
+            suggest(Some(activity.clone()), histogram)
+        })
+        .collect();
+    Ok(ThresholdSuggestions {
+        class_id: class_id.to_string(),
+        overall: suggest(None, &overall),
+        activities,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(levels: impl IntoIterator<Item = f64>) -> Histogram {
+        let mut h = Histogram::default();
+        for level in levels {
+            h.add(level);
+        }
+        h
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let h = histogram((1..=100).map(f64::from));
+        assert_eq!(h.percentile(0.5), Some(50.0));
+        assert_eq!(h.percentile(0.8), Some(80.0));
+        assert_eq!(h.percentile(1.0), Some(100.0));
+        assert_eq!(Histogram::default().percentile(0.5), None);
+        assert_eq!(h.mean(), Some(50.5));
+        assert_eq!(Histogram::default().mean(), None);
+        // Out-of-range levels are clamped, not dropped
+        let h = histogram([-5.0, 500.0]);
+        assert_eq!(h.total(), 2);
+        assert_eq!(h.percentile(1.0), Some(MAX_LEVEL as f64));
+    }
+
+    #[test]
+    fn test_suggestion_keeps_red_above_yellow() {
+        // A very steady class: every percentile is the same level
+        let steady = histogram(std::iter::repeat_n(55.0, 2_000));
+        let s = suggest(Some("silent work".into()), &steady);
+        assert!(s.enough_data);
+        assert_eq!(s.yellow_above, Some(55.0));
+        assert_eq!(s.red_above, Some(60.0));
+
+        let few = suggest(None, &histogram([40.0, 50.0]));
+        assert!(!few.enough_data);
+    }
+
+    #[test]
+    fn test_merge_and_normalize() {
+        let mut a = histogram([10.0, 20.0]);
+        a.merge(&histogram([20.0, 90.0]));
+        assert_eq!(a.total(), 4);
+        assert_eq!(a.counts[20], 2);
+        assert_eq!(normalize_activity(" Group Work ").unwrap(), "group work");
+        assert!(normalize_activity("  ").is_err());
+    }
+
+    #[test]
+    fn test_days_are_capped() {
+        let mut class = ClassNoiseHistory::default();
+        for day in 0..MAX_DAYS + 5 {
+            class.add_day(format!("day-{:04}", day), &histogram([50.0]));
+        }
+        class.add_day(format!("day-{:04}", MAX_DAYS + 4), &histogram([60.0]));
+        assert_eq!(class.days.len(), MAX_DAYS);
+        assert!(!class.days.contains_key("day-0000"));
+        assert_eq!(class.days[&format!("day-{:04}", MAX_DAYS + 4)].total(), 2);
+    }
+}
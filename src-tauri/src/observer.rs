@@ -0,0 +1,153 @@
+//! Read-only observer window for supervisors
+//!
+//! Handles:
+//! - A PIN (salted hash in the keychain) that must be entered to open
+//!   the observer window, so students can't open it from the teacher's PC
+//! - The `observer` window showing live classroom data (traffic light,
+//!   timers, period countdown, exit tickets)
+//! - The capability check: every command invoked from the observer window
+//!   goes through `read_only_guard`, which rejects anything not on the
+//!   read-only allow-list before it reaches the handler
+//!
+//! The window's Tauri capability (`capabilities/observer.json`) only grants
+//! event listening and closing, so plugin and window APIs are read-only too.
+
+use crate::command_trace;
+use crate::errors::{self, BackendError};
+use crate::secrets;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
+
+/// Label of the observer window
+pub const OBSERVER_LABEL: &str = "observer";
+const OBSERVER_ROUTE: &str = "index.html#/observer";
+pub(crate) const PIN_SECRET: &str = "observer_pin";
+
+/// Wrong PINs allowed before opening is blocked for `LOCKOUT`
+const MAX_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Commands the observer window may call; none of them modify data
+pub const READ_ONLY_COMMANDS: &[&str] = &[
+    "get_classroom_state",
+    "get_sequence_state",
+    "get_bell_schedule",
+    "get_time_remaining_in_period",
+    "get_exit_tickets",
+    "get_mood_checkins",
+    "get_freeze_status",
+    "get_classes",
+    "get_seating_chart",
+    "get_app_language",
+    "get_system_locale",
+    "format_date",
+    "format_number",
+    "localize_error",
+];
+
+/// Failed attempts and when the current lockout started
+static ATTEMPTS: Mutex<(u32, Option<Instant>)> = Mutex::new((0, None));
+
+/// Whether `command` may be invoked from the window labelled `label`
+pub fn is_allowed(label: &str, command: &str) -> bool {
+    label != OBSERVER_LABEL || READ_ONLY_COMMANDS.contains(&command)
+}
+
+/// Wrap the app's command handler with the observer capability check
+pub fn read_only_guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let label = invoke.message.webview_ref().label().to_string();
+        let command = invoke.message.command().to_string();
+        if is_allowed(&label, &command) {
+            return handler(invoke);
+        }
+        command_trace::reject(
+            invoke.resolver,
+            BackendError::new(
+                errors::observer::READ_ONLY,
+                "The observer window is read-only",
+            )
+            .with_details(command),
+        );
+        true
+    }
+}
+
+/// Set (or change) the observer PIN
+pub fn set_observer_pin(pin: &str) -> Result<(), BackendError> {
+    secrets::validate_pin(pin)?;
+    secrets::set_secret(PIN_SECRET, &secrets::hash_pin(pin)?)
+}
+
+/// Check a PIN, counting failures towards the lockout
+fn check_pin(pin: &str) -> Result<(), BackendError> {
+    let mut attempts = ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(since) = attempts.1 {
+        if since.elapsed() < LOCKOUT {
+            return Err(BackendError::new(
+                errors::observer::LOCKED_OUT,
+                "Too many wrong PINs, try again in a minute",
+            ));
+        }
+        *attempts = (0, None);
+    }
+    let stored = secrets::get_secret(PIN_SECRET)?.ok_or_else(|| {
+        BackendError::new(
+            errors::observer::PIN_NOT_SET,
+            "No observer PIN has been set",
+        )
+    })?;
+    if secrets::verify_pin(&stored, pin) {
+        *attempts = (0, None);
+        return Ok(());
+    }
+    attempts.0 += 1;
+    if attempts.0 >= MAX_ATTEMPTS {
+        attempts.1 = Some(Instant::now());
+    }
+    Err(BackendError::new(
+        errors::observer::INVALID_PIN,
+        "Wrong PIN",
+    ))
+}
+
+/// Open (or focus) the observer window after checking the PIN
+pub fn open_observer_window(app: &AppHandle, pin: &str) -> Result<(), BackendError> {
+    check_pin(pin)?;
+    let window_error = |e: tauri::Error| {
+        BackendError::new(
+            errors::window::CREATE_FAILED,
+            "Failed to open observer window",
+        )
+        .with_details(e.to_string())
+    };
+    if let Some(window) = app.get_webview_window(OBSERVER_LABEL) {
+        return window.set_focus().map_err(window_error);
+    }
+    WebviewWindowBuilder::new(app, OBSERVER_LABEL, WebviewUrl::App(OBSERVER_ROUTE.into()))
+        .title("Classroom – Observer")
+        .inner_size(1000.0, 700.0)
+        .build()
+        .map(|_| ())
+        .map_err(window_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observer_commands_are_filtered() {
+        assert!(is_allowed("main", "set_classroom_state"));
+        assert!(is_allowed(OBSERVER_LABEL, "get_classroom_state"));
+        assert!(!is_allowed(OBSERVER_LABEL, "set_classroom_state"));
+        assert!(!is_allowed(OBSERVER_LABEL, "record_attendance"));
+        assert!(!is_allowed(OBSERVER_LABEL, "open_observer_window"));
+        assert!(READ_ONLY_COMMANDS.iter().all(|c| c.starts_with("get_")
+            || ["format_date", "format_number", "localize_error"].contains(c)));
+    }
+}
@@ -0,0 +1,460 @@
+//! OCR of printed class lists
+//!
+//! Handles:
+//! - Reading a photo or scan of a printed class list with Tesseract, when
+//!   it is installed: the path in the `ocr` config key, else `tesseract`
+//!   on the PATH (on Windows also its default install folder). A
+//!   configured path is only run when it is a file named `tesseract`, and
+//!   no executable is used unless `--version` answers as Tesseract
+//! - Rebuilding the table from the words' positions: one row per printed
+//!   line, cells split at wide gaps and lined up on the columns of the
+//!   fullest row
+//! - Feeding the rows through the same header search, name clean-up and
+//!   validation as a CSV import (`file_ops::import_adapters`), and listing
+//!   the cells Tesseract wasn't sure about so the teacher can check them
+//!
+//! The photo is turned upright, scaled and converted to grayscale first
+//! (see `image_optimize`). The engine is not bundled with the app: Rust
+//! bindings need Tesseract's C++ libraries at build time and there is no
+//! pure-Rust engine with Italian models yet, so OCR stays an optional
+//! feature for PCs with Tesseract installed and `get_ocr_status` tells the
+//! frontend whether to offer it at all.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::file_ops::import_adapters::{self, RosterImport};
+use crate::image_optimize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CONFIG_KEY: &str = "ocr";
+/// Tesseract language packs tried, in order
+const DEFAULT_LANGUAGES: &str = "ita+eng";
+/// Longest side the photo is scaled to (Tesseract likes ~300 dpi pages)
+const OCR_MAX_DIM: u32 = 3500;
+/// Cells below this confidence (0-100) are listed for checking
+pub const LOW_CONFIDENCE: f64 = 60.0;
+/// A gap wider than this many line heights starts a new cell
+const CELL_GAP: f64 = 1.5;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "tif", "tiff", "bmp"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OcrConfig {
+    /// Tesseract executable, when not on the PATH
+    pub tesseract_path: Option<String>,
+    /// Tesseract languages, e.g. "ita+eng"
+    pub languages: Option<String>,
+}
+
+/// Whether OCR can be offered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrStatus {
+    pub available: bool,
+    /// First line of `tesseract --version`
+    pub engine: Option<String>,
+}
+
+/// A recognised cell that needs a second look
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UncertainCell {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+    /// Lowest word confidence in the cell, 0-100
+    pub confidence: f64,
+}
+
+/// Result of `ocr_image_to_table`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrTable {
+    /// The table as read, before any clean-up
+    pub rows: Vec<Vec<String>>,
+    pub uncertain: Vec<UncertainCell>,
+    /// The rows read as a roster, as `preview_roster_import` does
+    pub import: RosterImport,
+}
+
+/// (page, block, paragraph, line) of a word
+type LineId = (u32, u32, u32, u32);
+
+/// A word of Tesseract's TSV output
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    line: LineId,
+    left: i64,
+    width: i64,
+    height: i64,
+    confidence: f64,
+    text: String,
+}
+
+/// A cell being built
+#[derive(Debug, Clone)]
+struct Cell {
+    left: i64,
+    text: String,
+    confidence: f64,
+}
+
+fn engine_unavailable(details: impl ToString) -> BackendError {
+    BackendError::new(
+        errors::ocr::ENGINE_UNAVAILABLE,
+        "Text recognition needs Tesseract, which is not installed",
+    )
+    .with_details(details.to_string())
+}
+
+fn get_config() -> OcrConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a configured path may be run: an existing file named
+/// `tesseract` (`tesseract.exe`), never some other program
+fn is_tesseract_file(path: &Path) -> bool {
+    path.is_absolute()
+        && path.is_file()
+        && path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.eq_ignore_ascii_case("tesseract"))
+}
+
+/// Version line of `--version` output, if it is Tesseract's
+fn tesseract_version(output: &str) -> Option<String> {
+    let line = output.lines().next()?.trim();
+    line.to_ascii_lowercase()
+        .starts_with("tesseract ")
+        .then(|| line.to_string())
+}
+
+/// Executables tried, in order
+fn candidates(config: &OcrConfig) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = config
+        .tesseract_path
+        .iter()
+        .map(PathBuf::from)
+        .filter(|path| is_tesseract_file(path))
+        .collect();
+    paths.push(PathBuf::from("tesseract"));
+    #[cfg(target_os = "windows")]
+    paths.push(PathBuf::from(
+        r"C:\Program Files\Tesseract-OCR\tesseract.exe",
+    ));
+    paths
+}
+
+/// First working executable and its version line
+fn find_engine(config: &OcrConfig) -> Option<(PathBuf, String)> {
+    candidates(config).into_iter().find_map(|path| {
+        let output = Command::new(&path).arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // Older versions print the version on stderr
+        let text = if output.stdout.is_empty() {
+            output.stderr
+        } else {
+            output.stdout
+        };
+        let version = tesseract_version(&String::from_utf8_lossy(&text))?;
+        Some((path, version))
+    })
+}
+
+pub fn get_ocr_status() -> OcrStatus {
+    let engine = find_engine(&get_config()).map(|(_, version)| version);
+    OcrStatus {
+        available: engine.is_some(),
+        engine,
+    }
+}
+
+/// Words of `tesseract ... tsv` output (level 5 rows with text)
+fn parse_tsv(tsv: &str) -> Vec<Word> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 12 || fields[0] != "5" {
+                return None;
+            }
+            let int = |i: usize| fields[i].trim().parse::<i64>().ok();
+            let id = |i: usize| fields[i].trim().parse::<u32>().ok();
+            let text = fields[11].trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(Word {
+                line: (id(1)?, id(2)?, id(3)?, id(4)?),
+                left: int(6)?,
+                width: int(8)?,
+                height: int(9)?,
+                confidence: fields[10].trim().parse().ok()?,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Split one printed line into cells at the wide gaps
+fn line_cells(words: &mut [&Word]) -> Vec<Cell> {
+    words.sort_by_key(|w| w.left);
+    let mut heights: Vec<i64> = words.iter().map(|w| w.height).collect();
+    heights.sort_unstable();
+    let line_height = heights.get(heights.len() / 2).copied().unwrap_or(0) as f64;
+
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut end = None;
+    for word in words.iter() {
+        let near = end.is_some_and(|end| ((word.left - end) as f64) <= CELL_GAP * line_height);
+        match cells.last_mut() {
+            Some(cell) if near => {
+                cell.text.push(' ');
+                cell.text.push_str(&word.text);
+                cell.confidence = cell.confidence.min(word.confidence);
+            }
+            _ => cells.push(Cell {
+                left: word.left,
+                text: word.text.clone(),
+                confidence: word.confidence,
+            }),
+        }
+        end = Some(word.left + word.width);
+    }
+    cells
+}
+
+/// Lay the words out as rows of cells, with the uncertain cells
+fn to_table(words: &[Word]) -> (Vec<Vec<String>>, Vec<UncertainCell>) {
+    // Lines in reading order
+    let mut lines: Vec<(LineId, Vec<&Word>)> = Vec::new();
+    for word in words {
+        match lines.iter_mut().find(|(line, _)| *line == word.line) {
+            Some((_, line_words)) => line_words.push(word),
+            None => lines.push((word.line, vec![word])),
+        }
+    }
+    let lines: Vec<Vec<Cell>> = lines
+        .iter_mut()
+        .map(|(_, line_words)| line_cells(line_words))
+        .collect();
+
+    // Column positions from the fullest line (the first, if tied)
+    let anchors: Vec<i64> = lines
+        .iter()
+        .rev()
+        .max_by_key(|cells| cells.len())
+        .map(|cells| cells.iter().map(|c| c.left).collect())
+        .unwrap_or_default();
+
+    let mut rows = Vec::new();
+    let mut uncertain = Vec::new();
+    for cells in lines {
+        let mut row: Vec<String> = vec![String::new(); anchors.len()];
+        let mut confidence = vec![f64::MAX; anchors.len()];
+        for cell in cells {
+            let column = anchors
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, anchor)| (cell.left - **anchor).abs())
+                .map_or(0, |(i, _)| i);
+            if !row[column].is_empty() {
+                row[column].push(' ');
+            }
+            row[column].push_str(&cell.text);
+            confidence[column] = confidence[column].min(cell.confidence);
+        }
+        for (column, text) in row.iter().enumerate() {
+            if !text.is_empty() && confidence[column] < LOW_CONFIDENCE {
+                uncertain.push(UncertainCell {
+                    row: rows.len(),
+                    column,
+                    text: text.clone(),
+                    confidence: confidence[column],
+                });
+            }
+        }
+        rows.push(row);
+    }
+    (rows, uncertain)
+}
+
+/// Grayscale, upright PNG of the photo, for Tesseract
+fn prepare_image(path: &Path, target: &Path) -> Result<(), BackendError> {
+    if std::fs::metadata(path)?.len() > image_optimize::MAX_INPUT_BYTES {
+        return Err(
+            BackendError::new(errors::system::INVALID_INPUT, "Image file is too large")
+                .with_details(path.to_string_lossy().to_string()),
+        );
+    }
+    let image = image_optimize::decode(&std::fs::read(path)?, OCR_MAX_DIM)?;
+    image.to_luma8().save(target).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to prepare the image")
+            .with_details(e.to_string())
+    })
+}
+
+/// Read a photo or scan of a printed class list into a table and a roster
+pub fn ocr_image_to_table(path: &str) -> Result<OcrTable, BackendError> {
+    let source = Path::new(path);
+    let supported = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "File must be an image (.png, .jpg, .tif, .bmp)",
+        )
+        .with_details(path.to_string()));
+    }
+    let config = get_config();
+    let (engine, _) = find_engine(&config).ok_or_else(|| match &config.tesseract_path {
+        Some(path) if !is_tesseract_file(Path::new(path)) => {
+            engine_unavailable(format!("{} is not a Tesseract executable", path))
+        }
+        _ => engine_unavailable("tesseract not found"),
+    })?;
+
+    let prepared = std::env::temp_dir().join(format!(
+        "classroom-ocr-{}-{}.png",
+        std::process::id(),
+        crate::clock::now_millis()
+    ));
+    let output = prepare_image(source, &prepared).and_then(|()| {
+        Command::new(&engine)
+            .arg(&prepared)
+            .arg("stdout")
+            .args([
+                "-l",
+                config.languages.as_deref().unwrap_or(DEFAULT_LANGUAGES),
+            ])
+            // A single uniform block: keeps table rows as lines
+            .args(["--psm", "6"])
+            .arg("tsv")
+            .output()
+            .map_err(engine_unavailable)
+    });
+    let _ = std::fs::remove_file(&prepared);
+    let output = output?;
+    if !output.status.success() {
+        return Err(
+            BackendError::new(errors::ocr::FAILED, "Text recognition failed")
+                .with_details(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        );
+    }
+
+    let words = parse_tsv(&String::from_utf8_lossy(&output.stdout));
+    let (rows, uncertain) = to_table(&words);
+    let import = import_adapters::import_rows(&rows);
+    Ok(OcrTable {
+        rows,
+        uncertain,
+        import,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// TSV word rows: (line, left, width, confidence, text), height 20
+    fn tsv(words: &[(u32, i64, i64, f64, &str)]) -> String {
+        let mut out = String::from(
+            "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n",
+        );
+        out.push_str("1\t1\t0\t0\t0\t0\t0\t0\t1000\t1000\t-1\t\n");
+        for (i, (line, left, width, conf, text)) in words.iter().enumerate() {
+            out.push_str(&format!(
+                "5\t1\t1\t1\t{}\t{}\t{}\t{}\t{}\t20\t{}\t{}\n",
+                line,
+                i,
+                left,
+                line * 30,
+                width,
+                conf,
+                text
+            ));
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        let words = parse_tsv(&tsv(&[
+            (1, 10, 50, 96.5, "Cognome"),
+            (1, 200, 40, 91.0, "Nome"),
+        ]));
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].line, (1, 1, 1, 1));
+        assert_eq!((words[1].left, words[1].width), (200, 40));
+        assert_eq!(words[1].text, "Nome");
+        assert!(parse_tsv("level\n5\t1\tbroken").is_empty());
+    }
+
+    #[test]
+    fn test_table_lines_up_columns() {
+        let words = parse_tsv(&tsv(&[
+            (1, 10, 20, 95.0, "N."),
+            (1, 100, 80, 95.0, "Cognome"),
+            (1, 300, 50, 95.0, "Nome"),
+            (2, 10, 10, 95.0, "1"),
+            (2, 100, 60, 95.0, "ROSSI"),
+            (2, 300, 60, 95.0, "MARIO"),
+            // No number; a two-word given name stays one cell
+            (3, 102, 60, 42.0, "BIANCHI"),
+            (3, 301, 50, 90.0, "ANNA"),
+            (3, 360, 50, 90.0, "MARIA"),
+        ]));
+        let (rows, uncertain) = to_table(&words);
+        assert_eq!(
+            rows,
+            [
+                ["N.", "Cognome", "Nome"],
+                ["1", "ROSSI", "MARIO"],
+                ["", "BIANCHI", "ANNA MARIA"],
+            ]
+        );
+        assert_eq!(uncertain.len(), 1);
+        assert_eq!((uncertain[0].row, uncertain[0].column), (2, 1));
+        assert_eq!(uncertain[0].text, "BIANCHI");
+
+        let import = import_adapters::import_rows(&rows);
+        assert_eq!(
+            import.roster.students,
+            ["Rossi Mario", "Bianchi Anna Maria"]
+        );
+    }
+
+    #[test]
+    fn test_only_tesseract_is_run() {
+        let dir = TempDir::new().unwrap();
+        let engine = dir.path().join(if cfg!(windows) {
+            "tesseract.exe"
+        } else {
+            "tesseract"
+        });
+        let other = dir.path().join("notepad.exe");
+        std::fs::write(&engine, "").unwrap();
+        std::fs::write(&other, "").unwrap();
+        assert!(is_tesseract_file(&engine));
+        assert!(!is_tesseract_file(&other));
+        assert!(!is_tesseract_file(Path::new("tesseract")));
+        assert!(!is_tesseract_file(dir.path()));
+
+        assert_eq!(
+            tesseract_version("tesseract 5.3.0\n leptonica-1.82.0\n").as_deref(),
+            Some("tesseract 5.3.0")
+        );
+        assert_eq!(tesseract_version("Python 3.11.4"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Resource usage of the running app
+//!
+//! Handles:
+//! - Process memory (resident set) and CPU usage
+//! - Backlogs: throttled events waiting to be sent, unfinished jobs
+//! - Audio buffer underruns reported by native streams
+//! - On-disk size of the config file and data collections
+//!
+//! Meant for a diagnostics panel on low-memory school laptops and for
+//! attaching to bug reports. CPU usage is measured between two calls, so
+//! the first call reports 0.
+
+use crate::audio_supervisor;
+use crate::errors::BackendError;
+use crate::event_throttle;
+use crate::file_ops;
+use crate::jobs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Kept between calls: CPU usage is a delta since the previous refresh
+static SYSTEM: Mutex<Option<System>> = Mutex::new(None);
+
+/// Snapshot returned by `get_performance_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceStats {
+    /// Resident memory of the backend process, in bytes
+    pub rss_bytes: u64,
+    /// Physical memory of the machine, in bytes
+    pub total_memory_bytes: u64,
+    /// CPU usage since the previous call, 0-100 across all cores
+    pub cpu_percent: f32,
+    /// Events held back by throttling, per channel
+    pub event_queue_depths: BTreeMap<String, usize>,
+    /// Queued or running background jobs
+    pub active_jobs: usize,
+    /// Audio buffer underruns since startup, per stream
+    pub audio_underruns: BTreeMap<String, u64>,
+    /// Size of `app_config.json` plus all data collections, in bytes
+    pub data_size_bytes: u64,
+}
+
+/// Total size of the files directly in `dir` (0 if missing)
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn data_size() -> Result<u64, BackendError> {
+    let config_dir = file_ops::get_config_dir()?;
+    let config_size = fs::metadata(config_dir.join(file_ops::CONFIG_FILENAME))
+        .map(|m| m.len())
+        .unwrap_or(0);
+    Ok(config_size + dir_size(&config_dir.join(file_ops::DATA_DIR)))
+}
+
+/// Memory and CPU of this process: (rss, total memory, cpu %)
+fn process_usage() -> (u64, u64, f32) {
+    let mut guard = SYSTEM.lock().unwrap_or_else(|e| e.into_inner());
+    let system = guard.get_or_insert_with(System::new);
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return (0, 0, 0.0);
+    };
+    system.refresh_memory();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_memory().with_cpu(),
+    );
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1) as f32;
+    let total = system.total_memory();
+    match system.process(pid) {
+        Some(process) => (
+            process.memory(),
+            total,
+            (process.cpu_usage() / cores).clamp(0.0, 100.0),
+        ),
+        None => (0, total, 0.0),
+    }
+}
+
+/// Current resource usage
+pub fn get_performance_stats() -> Result<PerformanceStats, BackendError> {
+    let (rss_bytes, total_memory_bytes, cpu_percent) = process_usage();
+    Ok(PerformanceStats {
+        rss_bytes,
+        total_memory_bytes,
+        cpu_percent,
+        event_queue_depths: event_throttle::queue_depths(),
+        active_jobs: jobs::list_jobs()
+            .iter()
+            .filter(|j| !j.state.is_finished())
+            .count(),
+        audio_underruns: audio_supervisor::underrun_counts(),
+        data_size_bytes: data_size()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dir_size_counts_files_only() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.json"), [0u8; 10]).unwrap();
+        fs::write(dir.path().join("b.json"), [0u8; 5]).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        assert_eq!(dir_size(dir.path()), 15);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_process_usage_reports_memory() {
+        let (rss, total, cpu) = process_usage();
+        assert!(rss > 0);
+        assert!(total >= rss);
+        assert!((0.0..=100.0).contains(&cpu));
+    }
+}
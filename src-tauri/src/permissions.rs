@@ -2,7 +2,8 @@
 //!
 //! Handles platform-specific permission requests for:
 //! - Microphone access (primary use case for noise monitoring)
-//! - Future: Camera, storage access
+//! - Screen capture (window screenshots)
+//! - Camera access (ambient light sampling)
 //!
 //! References: CLAUDE.md § Edge Cases - EC-000 (First-time microphone permission)
 
@@ -125,55 +126,33 @@ fn check_windows_audio_devices() -> Result<(bool, bool), String> {
     // This replaces the PowerShell approach which is fragile and may not be available
     // in restricted environments.
 
+    use crate::platform::windows::ComGuard;
     use windows::Win32::Media::Audio::*;
     use windows::Win32::System::Com::*;
 
-    unsafe {
-        // Initialize COM
-        let hr = CoInitializeEx(None, COINIT_MULTITHREADED);
-        // Allow RPC_E_CHANGED_MODE which means COM was already initialized
-        if hr.is_err() {
-            // Try to continue even if COM initialization fails
-            // Some environments may have COM already initialized
-        }
+    // Declared first so the COM objects below are released before it drops
+    let _com = ComGuard::new().map_err(|e| e.to_string())?;
 
+    unsafe {
         // Create device enumerator
-        let enumerator: IMMDeviceEnumerator = match CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL
-        ) {
-            Ok(e) => e,
-            Err(e) => {
-                CoUninitialize();
-                return Err(format!("Failed to create device enumerator: {:?}", e));
-            }
-        };
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                .map_err(|e| format!("Failed to create device enumerator: {:?}", e))?;
 
         // Enumerate audio capture devices
         // Note: DEVICE_STATE values are u32, so we use bitwise OR
         let device_state_mask = DEVICE_STATE_ACTIVE.0 | DEVICE_STATE_UNPLUGGED.0;
-        let collection = match enumerator.EnumAudioEndpoints(
-            eCapture,  // Capture devices (microphones)
-            DEVICE_STATE(device_state_mask)  // Active or unplugged devices
-        ) {
-            Ok(c) => c,
-            Err(e) => {
-                CoUninitialize();
-                return Err(format!("Failed to enumerate audio endpoints: {:?}", e));
-            }
-        };
+        let collection = enumerator
+            .EnumAudioEndpoints(
+                eCapture,                        // Capture devices (microphones)
+                DEVICE_STATE(device_state_mask), // Active or unplugged devices
+            )
+            .map_err(|e| format!("Failed to enumerate audio endpoints: {:?}", e))?;
 
         // Get device count
-        let count = match collection.GetCount() {
-            Ok(c) => c,
-            Err(e) => {
-                CoUninitialize();
-                return Err(format!("Failed to get device count: {:?}", e));
-            }
-        };
-
-        CoUninitialize();
+        let count = collection
+            .GetCount()
+            .map_err(|e| format!("Failed to get device count: {:?}", e))?;
 
         // If we found any capture devices, microphone is available and granted
         let has_devices = count > 0;
@@ -329,6 +308,183 @@ fn check_linux_audio_devices() -> Result<bool, String> {
     Ok(pulse_output.unwrap_or(false))
 }
 
+// ============================================================================
+// Screen Capture
+// ============================================================================
+
+/// Request permission to capture the screen (window screenshots)
+///
+/// # Platform-Specific Behavior
+///
+/// **Windows**: No permission needed; windows are captured with `PrintWindow`.
+///
+/// **macOS**: Screen Recording permission (System Settings › Privacy).
+/// The first request shows the system prompt; macOS only applies a new
+/// grant after the app restarts.
+///
+/// **Linux**: No permission system; `available` reports whether a capture
+/// tool is installed (`grim` on Wayland, ImageMagick `import` on X11).
+pub fn request_screen_capture_permission() -> Result<PermissionStatus, BackendError> {
+    #[cfg(target_os = "macos")]
+    return Ok(screen_capture_status_macos());
+
+    #[cfg(target_os = "linux")]
+    return Ok(screen_capture_status_linux());
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    Ok(PermissionStatus {
+        granted: true,
+        available: true,
+        message: "Screen capture available".to_string(),
+        details: None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn screen_capture_status_macos() -> PermissionStatus {
+    let granted = macos_screen_capture::request();
+    PermissionStatus {
+        granted,
+        available: true,
+        message: if granted {
+            "Screen recording permission granted".to_string()
+        } else {
+            "Screen recording permission denied".to_string()
+        },
+        details: (!granted).then(|| {
+            "Allow it in System Settings > Privacy & Security > Screen Recording, then restart the app"
+                .to_string()
+        }),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn screen_capture_status_linux() -> PermissionStatus {
+    let tool = linux_capture_tool();
+    let available = std::process::Command::new(tool)
+        .arg(if tool == "grim" { "-h" } else { "-version" })
+        .output()
+        .is_ok();
+    PermissionStatus {
+        granted: available,
+        available,
+        message: if available {
+            "Screen capture available".to_string()
+        } else {
+            format!("Screen capture needs `{}` to be installed", tool)
+        },
+        details: None,
+    }
+}
+
+/// Capture tool for the current Linux session
+#[cfg(target_os = "linux")]
+pub fn linux_capture_tool() -> &'static str {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        "grim"
+    } else {
+        "import"
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_screen_capture {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    /// Whether capture is allowed, prompting the first time
+    pub fn request() -> bool {
+        // SAFETY: both functions take no arguments and only query/prompt TCC
+        unsafe { CGPreflightScreenCaptureAccess() || CGRequestScreenCaptureAccess() }
+    }
+}
+
+/// Check camera access (no prompt)
+///
+/// **Windows**: `available` when ffmpeg lists a DirectShow camera; access
+/// is governed by the Privacy settings, which ffmpeg reports on capture.
+///
+/// **macOS**: AVFoundation authorization for video. macOS asks the first
+/// time the app uses the camera (e.g. from the web view).
+///
+/// **Linux**: No permission system; a readable `/dev/video*` device.
+///
+/// Sampling also needs `ffmpeg` on the PATH (see `ambient_light`).
+pub fn camera_permission() -> PermissionStatus {
+    let ffmpeg = std::process::Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .is_ok();
+    if !ffmpeg {
+        return PermissionStatus {
+            granted: false,
+            available: false,
+            message: "Camera sampling needs `ffmpeg` to be installed".to_string(),
+            details: None,
+        };
+    }
+
+    #[cfg(target_os = "macos")]
+    return camera_status_macos();
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let available = crate::ambient_light::default_camera().is_some();
+        PermissionStatus {
+            granted: available,
+            available,
+            message: if available {
+                "Camera available".to_string()
+            } else {
+                "No camera detected".to_string()
+            },
+            details: None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn camera_status_macos() -> PermissionStatus {
+    let (granted, message) = match macos_camera::authorization_status() {
+        Some(macos_camera::AUTHORIZED) => (true, "Camera permission granted"),
+        Some(macos_camera::NOT_DETERMINED) => (false, "Camera permission not requested yet"),
+        Some(_) => (false, "Camera permission denied"),
+        None => (false, "Camera status unavailable"),
+    };
+    PermissionStatus {
+        granted,
+        available: true,
+        message: message.to_string(),
+        details: (!granted)
+            .then(|| "Allow it in System Settings > Privacy & Security > Camera".to_string()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_camera {
+    use objc2::msg_send;
+    use objc2::runtime::{AnyClass, AnyObject};
+
+    pub const NOT_DETERMINED: isize = 0;
+    pub const AUTHORIZED: isize = 3;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeVideo: *const AnyObject;
+    }
+
+    /// `AVCaptureDevice.authorizationStatus(for: .video)`
+    pub fn authorization_status() -> Option<isize> {
+        let class = AnyClass::get(c"AVCaptureDevice")?;
+        // SAFETY: AVMediaTypeVideo is a constant NSString exported by
+        // AVFoundation; the class method takes it and returns an NSInteger
+        Some(unsafe { msg_send![class, authorizationStatusForMediaType: AVMediaTypeVideo] })
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
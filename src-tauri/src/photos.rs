@@ -0,0 +1,298 @@
+//! Student photos
+//!
+//! Handles:
+//! - Bulk import from the folder of JPEGs delivered by the school
+//!   photographer, matching file names to students by id or by name
+//! - Turning photos upright and shrinking them to at most 400 px (aspect
+//!   ratio kept, see `image_optimize`), stored as
+//!   `photos/<class_id>/<student_id>.jpg` in the config directory
+//! - A match report listing unmatched files and students left without a photo
+//!
+//! Name matching ignores case, accents, word order and separators, and
+//! tolerates small misspellings, so "ROSSI_Mario.jpg", "mario-rossi.JPG" and
+//! "Rosi Mario.jpg" all match "Mario Rossi".
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::fuzzy;
+use crate::image_optimize::{self, ImageFormat};
+use crate::jobs::JobContext;
+use crate::roster::{ClassData, RosterStore, Student};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub(crate) const PHOTOS_DIR: &str = "photos";
+
+/// Longest side of a stored photo, in pixels
+pub const PHOTO_SIZE: u32 = 400;
+const JPEG_QUALITY: u8 = 85;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+/// How file names are matched to students
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchingStrategy {
+    /// File name (without extension) equals the student id
+    Id,
+    /// File name is the student's name, in any order or case
+    Name,
+}
+
+/// A file matched to a student
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoMatch {
+    pub file: String,
+    pub student_id: String,
+    pub student_name: String,
+}
+
+/// A matched file that could not be stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhotoFailure {
+    pub file: String,
+    pub error: String,
+}
+
+/// Outcome of a folder import
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoImportReport {
+    pub imported: Vec<PhotoMatch>,
+    /// Image files that matched no student
+    pub unmatched_files: Vec<String>,
+    /// Files matching a student that already got a photo from this folder
+    pub duplicate_files: Vec<String>,
+    /// Students with no matching file
+    pub students_without_photo: Vec<Student>,
+    pub failed: Vec<PhotoFailure>,
+}
+
+/// Find the student a file stem belongs to
+fn match_student<'a>(
+    stem: &str,
+    students: &'a [Student],
+    strategy: MatchingStrategy,
+) -> Option<&'a Student> {
+    match strategy {
+        MatchingStrategy::Id => students
+            .iter()
+            .find(|s| s.id.eq_ignore_ascii_case(stem.trim())),
+        MatchingStrategy::Name => {
+            let names: Vec<String> = students.iter().map(|s| s.name.clone()).collect();
+            fuzzy::match_names(&[stem.to_string()], &names, fuzzy::DEFAULT_THRESHOLD)
+                .into_iter()
+                .next()
+                .and_then(|m| m.target_index)
+                .map(|i| &students[i])
+        }
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Directory holding a class's photos
+pub fn class_photo_dir(class_id: &str) -> Result<PathBuf, BackendError> {
+    Ok(file_ops::get_config_dir()?.join(PHOTOS_DIR).join(class_id))
+}
+
+/// Delete the photos of a class that no longer exists
+pub fn remove_class_photos(class_id: &str) -> Result<(), BackendError> {
+    match fs::remove_dir_all(class_photo_dir(class_id)?) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Resize and save one photo as JPEG (see `image_optimize`)
+fn store_photo(source: &Path, target: &Path) -> Result<(), BackendError> {
+    if fs::metadata(source)?.len() > image_optimize::MAX_INPUT_BYTES {
+        return Err(
+            BackendError::new(errors::system::INVALID_INPUT, "Photo file is too large")
+                .with_details(source.to_string_lossy().to_string()),
+        );
+    }
+    let image = image_optimize::decode(&fs::read(source)?, PHOTO_SIZE)?;
+    let bytes = image_optimize::encode(&image, ImageFormat::Jpeg, JPEG_QUALITY)?;
+    let tmp = target.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, target)?;
+    Ok(())
+}
+
+/// A file and the student it will be stored for
+type PlannedPhoto<'a> = (PathBuf, &'a Student);
+
+/// Match the images in `folder` to the students of a class
+fn plan<'a>(
+    class: &'a ClassData,
+    folder: &Path,
+    strategy: MatchingStrategy,
+) -> Result<(Vec<PlannedPhoto<'a>>, PhotoImportReport), BackendError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(folder)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && is_image(p))
+        .collect();
+    files.sort();
+
+    let mut report = PhotoImportReport::default();
+    let mut taken: HashSet<&str> = HashSet::new();
+    let mut matches = Vec::new();
+    for path in files {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match match_student(&stem, &class.students, strategy) {
+            Some(student) if taken.insert(&student.id) => matches.push((path, student)),
+            Some(_) => report.duplicate_files.push(name),
+            None => report.unmatched_files.push(name),
+        }
+    }
+    report.students_without_photo = class
+        .students
+        .iter()
+        .filter(|s| !taken.contains(s.id.as_str()))
+        .cloned()
+        .collect();
+    Ok((matches, report))
+}
+
+/// Import student photos from a folder
+pub fn import_photos_from_folder(
+    job: &JobContext,
+    class_id: &str,
+    folder: &str,
+    strategy: MatchingStrategy,
+) -> Result<PhotoImportReport, BackendError> {
+    let class = RosterStore::load()?
+        .find(class_id)
+        .cloned()
+        .ok_or_else(|| {
+            BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                .with_details(class_id.to_string())
+        })?;
+    let folder = Path::new(folder);
+    if !folder.is_dir() {
+        return Err(
+            BackendError::new(errors::file::NOT_FOUND, "Photo folder not found")
+                .with_details(folder.to_string_lossy().to_string()),
+        );
+    }
+
+    let (matches, mut report) = plan(&class, folder, strategy)?;
+    let dir = class_photo_dir(&class.id)?;
+    fs::create_dir_all(&dir)?;
+    let total = matches.len();
+    for (done, (path, student)) in matches.into_iter().enumerate() {
+        job.check_cancelled()?;
+        let file = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        job.progress(done, total, &file);
+        match store_photo(&path, &dir.join(format!("{}.jpg", student.id))) {
+            Ok(()) => report.imported.push(PhotoMatch {
+                file,
+                student_id: student.id.clone(),
+                student_name: student.name.clone(),
+            }),
+            Err(e) => {
+                report.students_without_photo.push(student.clone());
+                report.failed.push(PhotoFailure {
+                    file,
+                    error: e.message,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(id: &str, name: &str) -> Student {
+        Student {
+            id: id.to_string(),
+            name: name.to_string(),
+            absent: false,
+            notes: None,
+            email: None,
+            accommodations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_name_matching_is_fuzzy() {
+        let students = vec![
+            student("s1", "Mario Rossi"),
+            student("s2", "Niccolò D'Amico"),
+        ];
+        let m = |stem| match_student(stem, &students, MatchingStrategy::Name).map(|s| &s.id[..]);
+        assert_eq!(m("ROSSI_Mario"), Some("s1"));
+        assert_eq!(m("mario-rossi"), Some("s1"));
+        assert_eq!(m("d'amico niccolo"), Some("s2"));
+        assert_eq!(m("Damico Nicolò"), Some("s2"));
+        assert_eq!(m("Rosi Mario"), Some("s1"));
+        assert_eq!(m("Rossi"), None);
+
+        let by_id = match_student("S2", &students, MatchingStrategy::Id);
+        assert_eq!(by_id.map(|s| &s.id[..]), Some("s2"));
+    }
+
+    #[test]
+    fn test_plan_reports_unmatched_and_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "Rossi Mario.jpg",
+            "rossi_mario.png",
+            "Bianchi.jpg",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        let class = ClassData {
+            id: "3A".to_string(),
+            name: "3A".to_string(),
+            students: vec![student("s1", "Mario Rossi"), student("s2", "Anna Verdi")],
+            created_at: 0,
+            updated_at: 0,
+        };
+        let (matches, report) = plan(&class, dir.path(), MatchingStrategy::Name).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1.id, "s1");
+        assert_eq!(report.unmatched_files, vec!["Bianchi.jpg"]);
+        assert_eq!(report.duplicate_files, vec!["rossi_mario.png"]);
+        assert_eq!(
+            report.students_without_photo,
+            vec![student("s2", "Anna Verdi")]
+        );
+    }
+
+    #[test]
+    fn test_store_photo_resizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("big.png");
+        image::RgbImage::from_pixel(1200, 800, image::Rgb([200, 30, 30]))
+            .save(&source)
+            .unwrap();
+        let target = dir.path().join("s1.jpg");
+        store_photo(&source, &target).unwrap();
+        let stored = image::open(&target).unwrap();
+        assert_eq!((stored.width(), stored.height()), (400, 267));
+    }
+}
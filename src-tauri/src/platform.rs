@@ -0,0 +1,7 @@
+//! Platform-specific helpers shared across modules
+//!
+//! Code that only one module needs stays in that module behind `cfg`; what
+//! several modules call into lives here, one submodule per platform.
+
+#[cfg(target_os = "windows")]
+pub mod windows;
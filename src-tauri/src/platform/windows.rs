@@ -0,0 +1,95 @@
+//! Windows helpers
+//!
+//! Handles:
+//! - COM initialization scoped to a guard (`ComGuard`), for code that calls
+//!   COM APIs (audio endpoint enumeration in `permissions`, audio device
+//!   code)
+//!
+//! `CoUninitialize` must be called once for every successful
+//! `CoInitializeEx` on the same thread, and never for a failed one: calling
+//! it after `RPC_E_CHANGED_MODE` (COM already set up in another apartment
+//! mode, e.g. by the webview) releases someone else's initialization. The
+//! guard only uninitializes what it initialized, and is not `Send` so it
+//! drops on the thread that created it.
+
+use crate::errors::{self, BackendError};
+use std::marker::PhantomData;
+use windows::Win32::Foundation::RPC_E_CHANGED_MODE;
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+
+/// COM initialized on the current thread for as long as the guard lives
+///
+/// Declare it before the COM objects it serves, so they are released
+/// before COM is uninitialized.
+pub struct ComGuard {
+    /// Whether this guard's `CoInitializeEx` succeeded and must be undone
+    initialized: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ComGuard {
+    /// Initialize COM (multithreaded apartment) on the current thread
+    ///
+    /// COM already initialized in another mode is fine to use, so that
+    /// case succeeds too, without taking a reference.
+    pub fn new() -> Result<Self, BackendError> {
+        // SAFETY: no reserved pointer; balanced by `Drop` only on success
+        let hr = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+        if hr == RPC_E_CHANGED_MODE {
+            return Ok(Self {
+                initialized: false,
+                _not_send: PhantomData,
+            });
+        }
+        hr.ok().map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to initialize COM")
+                .with_details(e.to_string())
+        })?;
+        // S_OK or S_FALSE (already initialized in this mode): both counted
+        Ok(Self {
+            initialized: true,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        if self.initialized {
+            // SAFETY: same thread (not `Send`), matching a successful init
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guards_nest() {
+        let outer = ComGuard::new().unwrap();
+        let inner = ComGuard::new().unwrap();
+        assert!(outer.initialized && inner.initialized);
+        drop(inner);
+        // The outer initialization is still held
+        let again = ComGuard::new().unwrap();
+        assert!(again.initialized);
+    }
+
+    #[test]
+    fn test_changed_mode_is_not_uninitialized() {
+        use windows::Win32::System::Com::COINIT_APARTMENTTHREADED;
+        std::thread::spawn(|| {
+            // SAFETY: balanced below on this thread
+            let hr = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+            assert!(hr.is_ok());
+            let guard = ComGuard::new().unwrap();
+            assert!(!guard.initialized);
+            drop(guard);
+            unsafe { CoUninitialize() };
+        })
+        .join()
+        .unwrap();
+    }
+}
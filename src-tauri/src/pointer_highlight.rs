@@ -0,0 +1,248 @@
+//! Laser-pointer highlight on the projector
+//!
+//! Handles:
+//! - The `pointer` window: a transparent, click-through fullscreen layer on
+//!   the projector (see `window::open_projector_layer`), kept above the
+//!   annotation overlay, where the frontend draws the highlight
+//! - Sampling the cursor natively (about 60 times a second) and sending its
+//!   position to that window as `pointer-highlight-moved`, so the dot
+//!   follows the mouse even over other apps and the annotation overlay
+//! - Color and size, announced to every window as
+//!   `pointer-highlight-changed`
+//!
+//! Positions are logical pixels within the layer; `null` when the cursor is
+//! on another screen.
+
+use crate::errors::{self, BackendError};
+use crate::window;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow, WindowEvent};
+
+/// Label of the highlight window
+pub const POINTER_LABEL: &str = "pointer";
+const POINTER_ROUTE: &str = "index.html#/pointer";
+/// Emitted to every window when the highlight is turned on/off or changed
+pub const POINTER_EVENT: &str = "pointer-highlight-changed";
+/// Sent to the highlight window only
+pub const POINTER_MOVED_EVENT: &str = "pointer-highlight-moved";
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(16);
+pub const MIN_SIZE: u32 = 8;
+pub const MAX_SIZE: u32 = 200;
+
+static STATE: Mutex<Option<PointerHighlight>> = Mutex::new(None);
+/// Bumped on every change so a stale sampling thread stops
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointerHighlight {
+    /// `#rrggbb`
+    pub color: String,
+    /// Diameter in logical pixels
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointerHighlightStatus {
+    pub enabled: bool,
+    pub highlight: Option<PointerHighlight>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointerPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+fn status(highlight: Option<PointerHighlight>) -> PointerHighlightStatus {
+    PointerHighlightStatus {
+        enabled: highlight.is_some(),
+        highlight,
+    }
+}
+
+fn set_state(app: &AppHandle, highlight: Option<PointerHighlight>) -> PointerHighlightStatus {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = highlight.clone();
+    let status = status(highlight);
+    let _ = app.emit(POINTER_EVENT, &status);
+    status
+}
+
+fn validate(color: &str, size: u32) -> Result<(), BackendError> {
+    let hex = color.strip_prefix('#').unwrap_or_default();
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(
+            BackendError::new(errors::system::INVALID_INPUT, "Color must be #rrggbb")
+                .with_details(color.to_string()),
+        );
+    }
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Size must be {} to {}", MIN_SIZE, MAX_SIZE),
+        ));
+    }
+    Ok(())
+}
+
+/// Cursor position (physical desktop pixels) within a layer, in logical
+/// pixels; `None` when outside
+fn relative_position(
+    cursor: (f64, f64),
+    origin: (i32, i32),
+    size: (u32, u32),
+    scale: f64,
+) -> Option<PointerPosition> {
+    let x = cursor.0 - origin.0 as f64;
+    let y = cursor.1 - origin.1 as f64;
+    if x < 0.0 || y < 0.0 || x >= size.0 as f64 || y >= size.1 as f64 {
+        return None;
+    }
+    Some(PointerPosition {
+        x: (x / scale).round(),
+        y: (y / scale).round(),
+    })
+}
+
+fn cursor_on(app: &AppHandle, layer: &WebviewWindow) -> Option<PointerPosition> {
+    let cursor = app.cursor_position().ok()?;
+    let origin = layer.outer_position().ok()?;
+    let size = layer.outer_size().ok()?;
+    let scale = layer.scale_factor().ok()?;
+    relative_position(
+        (cursor.x, cursor.y),
+        (origin.x, origin.y),
+        (size.width, size.height),
+        scale,
+    )
+}
+
+/// Follow the cursor until the highlight is turned off or changed
+fn start_sampling(app: AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    std::thread::spawn(move || {
+        let mut last: Option<Option<PointerPosition>> = None;
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            let Some(layer) = app.get_webview_window(POINTER_LABEL) else {
+                break;
+            };
+            let position = cursor_on(&app, &layer);
+            // Only send movement, not 60 identical events a second
+            if last != Some(position) {
+                let _ = app.emit_to(POINTER_LABEL, POINTER_MOVED_EVENT, position);
+                last = Some(position);
+            }
+            std::thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+}
+
+fn open_layer(app: &AppHandle) -> Result<(), BackendError> {
+    let layer = window::open_projector_layer(
+        app,
+        POINTER_LABEL,
+        POINTER_ROUTE,
+        "Classroom – Pointer",
+        None,
+    )?;
+    // Clicks go to whatever is under the highlight
+    let shown = layer
+        .set_ignore_cursor_events(true)
+        .and_then(|_| layer.show());
+    if let Err(e) = shown {
+        let _ = layer.destroy();
+        return Err(BackendError::new(
+            errors::window::CREATE_FAILED,
+            "Failed to show pointer highlight",
+        )
+        .with_details(e.to_string()));
+    }
+    let handle = app.clone();
+    layer.on_window_event(move |event| {
+        if matches!(event, WindowEvent::Destroyed) {
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+            set_state(&handle, None);
+        }
+    });
+    Ok(())
+}
+
+/// Put the highlight back above the other projector layers (called when
+/// the annotation overlay opens or takes focus)
+pub fn raise(app: &AppHandle) {
+    if let Some(layer) = app.get_webview_window(POINTER_LABEL) {
+        let _ = layer.set_always_on_top(false);
+        let _ = layer.set_always_on_top(true);
+    }
+}
+
+/// Current highlight
+pub fn get_pointer_highlight() -> PointerHighlightStatus {
+    status(STATE.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Remove the highlight layer
+pub fn remove_pointer_highlight(app: &AppHandle) -> Result<PointerHighlightStatus, BackendError> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(layer) = app.get_webview_window(POINTER_LABEL) {
+        // destroy() can't be vetoed by the page, unlike close()
+        layer.destroy().map_err(|e| {
+            BackendError::new(
+                errors::window::NOT_FOUND,
+                "Failed to remove pointer highlight",
+            )
+            .with_details(e.to_string())
+        })?;
+    }
+    Ok(set_state(app, None))
+}
+
+/// Turn the highlight on (or change it) or off
+pub fn set_pointer_highlight(
+    app: &AppHandle,
+    enabled: bool,
+    color: String,
+    size: u32,
+) -> Result<PointerHighlightStatus, BackendError> {
+    if !enabled {
+        return remove_pointer_highlight(app);
+    }
+
+    validate(&color, size)?;
+    if app.get_webview_window(POINTER_LABEL).is_none() {
+        open_layer(app)?;
+    }
+    let status = set_state(app, Some(PointerHighlight { color, size }));
+    start_sampling(app.clone());
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_position() {
+        // Projector right of a 1920 px laptop screen, at 1.5x
+        let origin = (1920, 0);
+        let size = (1920, 1080);
+        assert_eq!(
+            relative_position((2070.0, 300.0), origin, size, 1.5),
+            Some(PointerPosition { x: 100.0, y: 200.0 })
+        );
+        assert_eq!(relative_position((500.0, 300.0), origin, size, 1.5), None);
+        assert_eq!(relative_position((3840.0, 10.0), origin, size, 1.5), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("#ff0000", 32).is_ok());
+        assert!(validate("red", 32).is_err());
+        assert!(validate("#ff0000", 4).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Presentation-safe mode for screen sharing in video calls
+//!
+//! Handles:
+//! - One switch for when the teacher shares their screen in Meet or Teams:
+//!   every window except the projector layers (annotation, dim, pointer)
+//!   is protected from capture (see `content_protection`) and hidden, so
+//!   the control window with grades cannot leak into the call
+//! - Switching back: the hidden windows reappear and get the protection
+//!   they had before
+//! - A global shortcut that toggles the mode even while the browser has
+//!   focus (config `presentation_safe`, `Ctrl+Shift+F12` by default,
+//!   `null` to turn it off)
+//!
+//! Windows are protected before they are hidden, so a window that fails
+//! to hide is still kept out of the call. The mode lasts until switched
+//! off or the app restarts.
+
+use crate::annotation;
+use crate::content_protection;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::hid;
+use crate::pointer_highlight;
+use crate::projector_dim;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Emitted with the status whenever the mode changes
+pub const PRESENTATION_SAFE_EVENT: &str = "presentation-safe-changed";
+
+const CONFIG_KEY: &str = "presentation_safe";
+const DEFAULT_SHORTCUT: &str = "Ctrl+Shift+F12";
+
+/// Student-facing windows that stay visible
+const PROJECTOR_WINDOWS: &[&str] = &[
+    annotation::OVERLAY_LABEL,
+    projector_dim::DIM_LABEL,
+    pointer_highlight::POINTER_LABEL,
+];
+
+/// Labels of the windows hidden by the mode; `None` while it is off
+static HIDDEN: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static REGISTERED: Mutex<Option<Shortcut>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentationSafeConfig {
+    /// Accelerator as typed on the keyboard layout, `None` for no shortcut
+    pub shortcut: Option<String>,
+}
+
+impl Default for PresentationSafeConfig {
+    fn default() -> Self {
+        Self {
+            shortcut: Some(DEFAULT_SHORTCUT.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentationSafeStatus {
+    pub enabled: bool,
+    /// Windows hidden by the mode
+    pub hidden: Vec<String>,
+    pub shortcut: Option<String>,
+}
+
+pub fn get_config() -> PresentationSafeConfig {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn get_presentation_safe() -> PresentationSafeStatus {
+    let hidden = HIDDEN.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    PresentationSafeStatus {
+        enabled: hidden.is_some(),
+        hidden: hidden.unwrap_or_default(),
+        shortcut: get_config().shortcut,
+    }
+}
+
+/// Windows to hide: the visible ones that aren't projector layers
+fn windows_to_hide(open: &[(String, bool)]) -> Vec<String> {
+    open.iter()
+        .filter(|(label, visible)| *visible && !PROJECTOR_WINDOWS.contains(&label.as_str()))
+        .map(|(label, _)| label.clone())
+        .collect()
+}
+
+fn window_error(label: &str, e: tauri::Error) -> BackendError {
+    BackendError::new(
+        errors::window::PROTECTION_FAILED,
+        "Failed to hide or show window",
+    )
+    .with_details(format!("{}: {}", label, e))
+}
+
+/// Protect and hide windows, adding each to `hidden` as soon as it is
+/// touched so a failure midway can still be undone
+fn enable(app: &AppHandle, hidden: &mut Vec<String>) -> Result<(), BackendError> {
+    let windows = app.webview_windows();
+    let open: Vec<(String, bool)> = windows
+        .iter()
+        .map(|(label, w)| (label.clone(), w.is_visible().unwrap_or(true)))
+        .collect();
+    for label in windows_to_hide(&open) {
+        let window = &windows[&label];
+        hidden.push(label.clone());
+        content_protection::protect(window, true)?;
+        window.hide().map_err(|e| window_error(&label, e))?;
+    }
+    Ok(())
+}
+
+fn disable(app: &AppHandle, hidden: &[String]) -> Result<(), BackendError> {
+    let protected = content_protection::protected_windows();
+    for label in hidden {
+        // Closed while hidden
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+        window.show().map_err(|e| window_error(label, e))?;
+        content_protection::protect(&window, protected.contains(label))?;
+    }
+    Ok(())
+}
+
+/// Turn the mode on or off
+pub fn set_presentation_safe(
+    app: &AppHandle,
+    enabled: bool,
+) -> Result<PresentationSafeStatus, BackendError> {
+    {
+        let mut state = HIDDEN.lock().unwrap_or_else(|e| e.into_inner());
+        match (enabled, state.take()) {
+            (true, Some(hidden)) => *state = Some(hidden),
+            (true, None) => {
+                let mut hidden = Vec::new();
+                let result = enable(app, &mut hidden);
+                // Switching off brings back whatever was hidden so far
+                *state = Some(hidden);
+                result?;
+            }
+            (false, Some(hidden)) => disable(app, &hidden)?,
+            (false, None) => {}
+        }
+    }
+    let status = get_presentation_safe();
+    let _ = app.emit(PRESENTATION_SAFE_EVENT, &status);
+    Ok(status)
+}
+
+pub fn toggle_presentation_safe(app: &AppHandle) -> Result<PresentationSafeStatus, BackendError> {
+    let enabled = get_presentation_safe().enabled;
+    set_presentation_safe(app, !enabled)
+}
+
+/// Parse an accelerator for the keyboard layout, refusing keys the OS or
+/// a presenter binding already uses
+fn parse_shortcut(accelerator: &str) -> Result<Shortcut, BackendError> {
+    let check = hid::validate_accelerator(accelerator, None)?;
+    if !check.conflicts.is_empty() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "The shortcut is already in use",
+        )
+        .with_details(check.label));
+    }
+    Shortcut::from_str(&check.effective).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid shortcut")
+            .with_details(format!("{}: {}", accelerator, e))
+    })
+}
+
+/// Register the configured shortcut, replacing the previous one
+fn register(app: &AppHandle, config: &PresentationSafeConfig) -> Result<(), BackendError> {
+    let shortcut = config.shortcut.as_deref().map(parse_shortcut).transpose()?;
+    let manager = app.global_shortcut();
+    let mut registered = REGISTERED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(previous) = registered.take() {
+        let _ = manager.unregister(previous);
+    }
+    let Some(shortcut) = shortcut else {
+        return Ok(());
+    };
+    manager
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            if let Err(e) = toggle_presentation_safe(app) {
+                eprintln!("Presentation-safe mode: {}", e.message);
+            }
+        })
+        .map_err(|e| {
+            BackendError::new(
+                errors::system::INVALID_INPUT,
+                "Shortcut could not be registered",
+            )
+            .with_details(e.to_string())
+        })?;
+    *registered = Some(shortcut);
+    Ok(())
+}
+
+/// Save and register the toggle shortcut (`None` removes it)
+pub fn set_presentation_safe_shortcut(
+    app: &AppHandle,
+    shortcut: Option<String>,
+) -> Result<PresentationSafeStatus, BackendError> {
+    let config = PresentationSafeConfig {
+        shortcut: shortcut.map(|s| s.trim().to_string()),
+    };
+    register(app, &config)?;
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(get_presentation_safe())
+}
+
+/// Register the saved shortcut at startup
+pub fn start(app: &AppHandle) {
+    if let Err(e) = register(app, &get_config()) {
+        eprintln!("Presentation-safe shortcut not registered: {}", e.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_projector_layers_stay_visible() {
+        let open: Vec<(String, bool)> = [
+            ("main", true),
+            ("observer", true),
+            ("annotation", true),
+            ("dim", true),
+            ("pointer", false),
+            ("settings", false),
+        ]
+        .iter()
+        .map(|(label, visible)| (label.to_string(), *visible))
+        .collect();
+        assert_eq!(windows_to_hide(&open), ["main", "observer"]);
+    }
+
+    #[test]
+    fn test_default_shortcut() {
+        let config = PresentationSafeConfig::default();
+        assert_eq!(config.shortcut.as_deref(), Some(DEFAULT_SHORTCUT));
+        assert!(Shortcut::from_str(DEFAULT_SHORTCUT).is_ok());
+        let off: PresentationSafeConfig =
+            serde_json::from_value(serde_json::json!({ "shortcut": null })).unwrap();
+        assert_eq!(off.shortcut, None);
+    }
+}
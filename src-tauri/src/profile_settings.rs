@@ -0,0 +1,305 @@
+//! Per-profile settings: switching, diff and copy
+//!
+//! Handles:
+//! - Personal preferences (`PROFILE_KEYS`: language, window layout, light
+//!   thresholds, volume limit, exit ticket filter, presenter buttons,
+//!   weekly summary) kept per profile in the `profile_settings` data
+//!   collection
+//! - Swapping them in the config when the active profile changes: the
+//!   outgoing profile's values are saved, the incoming profile's applied
+//! - Showing which of these settings differ between two profiles, and
+//!   copying some or all of them from one profile to another
+//!
+//! Other settings (devices, network, cloud, schedule) belong to the PC and
+//! are shared. A profile that was never active has no snapshot yet and
+//! inherits the current values. `default` names the settings used with no
+//! active profile.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::roles::ProfileStore;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+const COLLECTION: &str = "profile_settings";
+
+/// Settings slot used while no profile is active
+pub const DEFAULT_SLOT: &str = "default";
+
+/// Config keys that follow the profile
+pub const PROFILE_KEYS: &[&str] = &[
+    "app_language",
+    "window_config",
+    "classroom_state_rules",
+    "volume_safety",
+    "exit_ticket_filter",
+    "presenter_bindings",
+    "weekly_summary",
+];
+
+/// Snapshots of profiles that are not active (key → value; a missing key
+/// means the default)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettingsStore {
+    pub profiles: BTreeMap<String, Map<String, Value>>,
+}
+
+impl ProfileSettingsStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+}
+
+/// One setting that differs; `null` means unset (the default applies)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingDifference {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSettingsDiff {
+    pub from: String,
+    pub to: String,
+    pub different: Vec<SettingDifference>,
+    /// Keys with the same value in both
+    pub same: Vec<String>,
+}
+
+fn slot(profile_id: Option<&str>) -> &str {
+    profile_id.unwrap_or(DEFAULT_SLOT)
+}
+
+/// Current values of the profile keys in the config
+fn live_settings() -> Result<Map<String, Value>, BackendError> {
+    let mut settings = Map::new();
+    for key in PROFILE_KEYS {
+        let value = file_ops::load_config(key)?;
+        if !value.is_null() {
+            settings.insert(key.to_string(), value);
+        }
+    }
+    Ok(settings)
+}
+
+/// Write a profile's values to the config (missing keys go back to default)
+fn apply_settings(settings: &Map<String, Value>) -> Result<(), BackendError> {
+    for key in PROFILE_KEYS {
+        match settings.get(*key) {
+            Some(value) => file_ops::save_config(key, value.clone())?,
+            None => {
+                file_ops::remove_config(key)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_keys(keys: &[String]) -> Result<(), BackendError> {
+    match keys.iter().find(|k| !PROFILE_KEYS.contains(&k.as_str())) {
+        Some(key) => Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Setting is not stored per profile",
+        )
+        .with_details(key.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Check that `profile_id` names a profile (or the default slot)
+fn check_profile(profiles: &ProfileStore, profile_id: &str) -> Result<(), BackendError> {
+    if profile_id == DEFAULT_SLOT || profiles.profiles.iter().any(|p| p.id == profile_id) {
+        Ok(())
+    } else {
+        Err(
+            BackendError::new(errors::role::PROFILE_NOT_FOUND, "Profile not found")
+                .with_details(profile_id.to_string()),
+        )
+    }
+}
+
+/// Settings of a slot: live for the active one, else its snapshot (or the
+/// live values it would inherit)
+fn settings_of(
+    slot_id: &str,
+    active: &str,
+    store: &ProfileSettingsStore,
+    live: &Map<String, Value>,
+) -> Map<String, Value> {
+    if slot_id == active {
+        return live.clone();
+    }
+    store
+        .profiles
+        .get(slot_id)
+        .cloned()
+        .unwrap_or_else(|| live.clone())
+}
+
+/// Compare two sets of profile settings
+pub fn diff_settings(
+    from: &Map<String, Value>,
+    to: &Map<String, Value>,
+) -> (Vec<SettingDifference>, Vec<String>) {
+    let mut different = Vec::new();
+    let mut same = Vec::new();
+    for key in PROFILE_KEYS {
+        let (a, b) = (
+            from.get(*key).cloned().unwrap_or(Value::Null),
+            to.get(*key).cloned().unwrap_or(Value::Null),
+        );
+        if a == b {
+            same.push(key.to_string());
+        } else {
+            different.push(SettingDifference {
+                key: key.to_string(),
+                from: a,
+                to: b,
+            });
+        }
+    }
+    (different, same)
+}
+
+/// Settings that differ between two profiles
+pub fn diff_profile_settings(from: &str, to: &str) -> Result<ProfileSettingsDiff, BackendError> {
+    let profiles = ProfileStore::load()?;
+    check_profile(&profiles, from)?;
+    check_profile(&profiles, to)?;
+    let active = slot(profiles.active_profile_id.as_deref());
+    let store = ProfileSettingsStore::load()?;
+    let live = live_settings()?;
+    let (different, same) = diff_settings(
+        &settings_of(from, active, &store, &live),
+        &settings_of(to, active, &store, &live),
+    );
+    Ok(ProfileSettingsDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        different,
+        same,
+    })
+}
+
+/// Copy settings from one profile to another (`keys`: all profile keys
+/// when `None`); returns the keys copied
+pub fn copy_settings_between_profiles(
+    from: &str,
+    to: &str,
+    keys: Option<Vec<String>>,
+) -> Result<Vec<String>, BackendError> {
+    let keys = keys.unwrap_or_else(|| PROFILE_KEYS.iter().map(|k| k.to_string()).collect());
+    check_keys(&keys)?;
+    let profiles = ProfileStore::load()?;
+    check_profile(&profiles, from)?;
+    check_profile(&profiles, to)?;
+    if from == to {
+        return Ok(Vec::new());
+    }
+    let active = slot(profiles.active_profile_id.as_deref());
+    let mut store = ProfileSettingsStore::load()?;
+    let live = live_settings()?;
+    let source = settings_of(from, active, &store, &live);
+    let mut target = settings_of(to, active, &store, &live);
+    for key in &keys {
+        match source.get(key) {
+            Some(value) => target.insert(key.clone(), value.clone()),
+            None => target.remove(key),
+        };
+    }
+
+    if to == active {
+        apply_settings(&target)?;
+    } else {
+        store.profiles.insert(to.to_string(), target);
+        store.save()?;
+    }
+    Ok(keys)
+}
+
+/// Swap settings when the active profile changes
+///
+/// Called by `roles::set_active_profile` after the switch.
+pub fn switch_profile(previous: Option<&str>, next: Option<&str>) -> Result<(), BackendError> {
+    let (previous, next) = (slot(previous), slot(next));
+    if previous == next {
+        return Ok(());
+    }
+    let mut store = ProfileSettingsStore::load()?;
+    store
+        .profiles
+        .insert(previous.to_string(), live_settings()?);
+    // The active profile's values live in the config only
+    if let Some(settings) = store.profiles.remove(next) {
+        apply_settings(&settings)?;
+    }
+    store.save()
+}
+
+/// Drop the snapshot of a deleted profile
+pub fn forget_profile(profile_id: &str) -> Result<(), BackendError> {
+    let mut store = ProfileSettingsStore::load()?;
+    if store.profiles.remove(profile_id).is_some() {
+        store.save()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_diff_settings() {
+        let a = settings(json!({
+            "app_language": "it",
+            "classroom_state_rules": { "yellowAbove": 55, "redAbove": 70 }
+        }));
+        let b = settings(json!({
+            "app_language": "it",
+            "volume_safety": { "maxDb": -12, "rampMs": 300 }
+        }));
+        let (different, same) = diff_settings(&a, &b);
+        let keys: Vec<_> = different.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(keys, ["classroom_state_rules", "volume_safety"]);
+        assert_eq!(different[1].from, Value::Null);
+        assert!(same.contains(&"app_language".to_string()));
+        assert_eq!(different.len() + same.len(), PROFILE_KEYS.len());
+    }
+
+    #[test]
+    fn test_settings_of_slots() {
+        let live = settings(json!({ "app_language": "en" }));
+        let mut store = ProfileSettingsStore::default();
+        store
+            .profiles
+            .insert("sub".into(), settings(json!({ "app_language": "it" })));
+        assert_eq!(
+            settings_of("sub", "default", &store, &live)["app_language"],
+            "it"
+        );
+        // The active slot is read from the config, never from a stale snapshot
+        assert_eq!(
+            settings_of("sub", "sub", &store, &live)["app_language"],
+            "en"
+        );
+        // Never active: inherits the current values
+        assert_eq!(settings_of("new", "default", &store, &live), live);
+        assert!(check_keys(&["lan_bind".to_string()]).is_err());
+        assert!(check_keys(&["app_language".to_string()]).is_ok());
+    }
+}
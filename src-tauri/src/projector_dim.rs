@@ -0,0 +1,156 @@
+//! Screen dimming on the projector ("Eyes on me")
+//!
+//! Handles:
+//! - The `dim` window: a transparent fullscreen layer on the projector that
+//!   the frontend fills with a translucent black at the requested level and
+//!   the message (see `window::open_projector_layer`)
+//! - Changing level/message while dimmed, and removing the layer
+//! - Keeping the state consistent when the layer goes away some other way
+//!   (closed by the OS, or the main window closing)
+//!
+//! The layer only covers the projector; the content underneath is never
+//! touched, so undimming is just destroying the window.
+
+use crate::errors::{self, BackendError};
+use crate::window;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WindowEvent};
+
+/// Label of the dimming window
+pub const DIM_LABEL: &str = "dim";
+const DIM_ROUTE: &str = "index.html#/dim";
+/// Emitted to every window when the projector dims, changes or undims
+pub const DIM_EVENT: &str = "projector-dim-changed";
+
+pub const MIN_LEVEL: f64 = 0.1;
+pub const MAX_LEVEL: f64 = 1.0;
+pub const MAX_MESSAGE_CHARS: usize = 120;
+
+static STATE: Mutex<Option<ProjectorDim>> = Mutex::new(None);
+
+/// Current dimming (`None` in the status when not dimmed)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectorDim {
+    /// Opacity of the dimming layer, 0.1 to 1
+    pub level: f64,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectorDimStatus {
+    pub dimmed: bool,
+    pub dim: Option<ProjectorDim>,
+}
+
+fn status(dim: Option<ProjectorDim>) -> ProjectorDimStatus {
+    ProjectorDimStatus {
+        dimmed: dim.is_some(),
+        dim,
+    }
+}
+
+fn set_state(app: &AppHandle, dim: Option<ProjectorDim>) -> ProjectorDimStatus {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = dim.clone();
+    let status = status(dim);
+    let _ = app.emit(DIM_EVENT, &status);
+    status
+}
+
+fn validate(level: f64, message: Option<String>) -> Result<ProjectorDim, BackendError> {
+    if !(MIN_LEVEL..=MAX_LEVEL).contains(&level) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Dim level must be {} to {}", MIN_LEVEL, MAX_LEVEL),
+        ));
+    }
+    let message = message
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    if message
+        .as_ref()
+        .is_some_and(|m| m.chars().count() > MAX_MESSAGE_CHARS)
+    {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Message must be at most {} characters", MAX_MESSAGE_CHARS),
+        ));
+    }
+    Ok(ProjectorDim { level, message })
+}
+
+/// Current dimming
+pub fn get_projector_dim() -> ProjectorDimStatus {
+    status(STATE.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Dim the projector, or change the level/message when already dimmed
+pub fn dim_projector(
+    app: &AppHandle,
+    level: f64,
+    message: Option<String>,
+) -> Result<ProjectorDimStatus, BackendError> {
+    let dim = validate(level, message)?;
+    if app.get_webview_window(DIM_LABEL).is_none() {
+        let layer =
+            window::open_projector_layer(app, DIM_LABEL, DIM_ROUTE, "Classroom – Dim", None)?;
+        // Pointer input on the projector still reaches what's underneath
+        let shown = layer
+            .set_ignore_cursor_events(true)
+            .and_then(|_| layer.show());
+        if let Err(e) = shown {
+            let _ = layer.destroy();
+            return Err(BackendError::new(
+                errors::window::CREATE_FAILED,
+                "Failed to show dim layer",
+            )
+            .with_details(e.to_string()));
+        }
+        let handle = app.clone();
+        layer.on_window_event(move |event| {
+            if matches!(event, WindowEvent::Destroyed) {
+                set_state(&handle, None);
+            }
+        });
+    }
+    Ok(set_state(app, Some(dim)))
+}
+
+/// Remove the dimming layer
+pub fn undim_projector(app: &AppHandle) -> Result<ProjectorDimStatus, BackendError> {
+    if let Some(layer) = app.get_webview_window(DIM_LABEL) {
+        // destroy() can't be vetoed by the page, unlike close()
+        layer.destroy().map_err(|e| {
+            BackendError::new(errors::window::NOT_FOUND, "Failed to remove dim layer")
+                .with_details(e.to_string())
+        })?;
+    }
+    Ok(set_state(app, None))
+}
+
+/// Dim with the default level and message, or undim
+pub fn toggle_projector_dim(app: &AppHandle) -> Result<ProjectorDimStatus, BackendError> {
+    if get_projector_dim().dimmed {
+        undim_projector(app)
+    } else {
+        dim_projector(app, 0.85, Some("Eyes on me".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let dim = validate(0.8, Some("  Eyes on me ".into())).unwrap();
+        assert_eq!(dim.message.as_deref(), Some("Eyes on me"));
+        assert_eq!(validate(0.5, Some("   ".into())).unwrap().message, None);
+        assert!(validate(0.0, None).is_err());
+        assert!(validate(1.5, None).is_err());
+        assert!(validate(f64::NAN, None).is_err());
+        assert!(validate(0.5, Some("x".repeat(121))).is_err());
+    }
+}
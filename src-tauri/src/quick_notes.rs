@@ -0,0 +1,223 @@
+//! Quick notes taken during a lesson
+//!
+//! Handles:
+//! - Jotting a short note ("Marco forgot homework"), optionally about a
+//!   student, without filling in a behavior entry
+//! - Expiry: at the end of the current lesson (see `schedule`), after a
+//!   given TTL, or after `DEFAULT_TTL_SECS` when no lesson is running
+//! - Promoting a note to the class journal (a `note` behavior entry, see
+//!   `class_records`), which keeps it for good
+//!
+//! Notes live in memory only: they are meant to be forgotten, so a restart
+//! drops them too. Expired notes are removed whenever the notes are used.
+
+use crate::class_records::{self, BehaviorEntry, BehaviorKind};
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::schedule::{self, PeriodKind};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Lifetime of a note taken outside a lesson
+pub const DEFAULT_TTL_SECS: u64 = 60 * 60;
+/// Longest TTL accepted
+pub const MAX_TTL_SECS: u64 = 12 * 60 * 60;
+
+const MAX_TEXT_CHARS: usize = 500;
+const MAX_NOTES: usize = 200;
+
+static BOARD: Mutex<NoteBoard> = Mutex::new(NoteBoard::new());
+
+/// A note waiting to expire or be promoted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickNote {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub class_id: Option<String>,
+    /// Student the note is about; needed to promote it
+    #[serde(default)]
+    pub student_id: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+/// The notes of this session
+struct NoteBoard {
+    notes: Vec<QuickNote>,
+    next_id: u64,
+}
+
+impl NoteBoard {
+    const fn new() -> Self {
+        Self {
+            notes: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    fn prune(&mut self, now: u64) {
+        self.notes.retain(|n| n.expires_at > now);
+    }
+
+    fn add(
+        &mut self,
+        text: &str,
+        class_id: Option<&str>,
+        student_id: Option<&str>,
+        now: u64,
+        expires_at: u64,
+    ) -> Result<QuickNote, BackendError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(invalid_input("The note is empty"));
+        }
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return Err(invalid_input("The note is too long"));
+        }
+        if student_id.is_some() && class_id.is_none() {
+            return Err(invalid_input("A student note needs its class"));
+        }
+        self.prune(now);
+        if self.notes.len() >= MAX_NOTES {
+            return Err(invalid_input(
+                "Too many quick notes; promote or wait for some to expire",
+            ));
+        }
+        let note = QuickNote {
+            id: format!("quick_note_{}", self.next_id),
+            text: text.to_string(),
+            class_id: class_id.map(String::from),
+            student_id: student_id.map(String::from),
+            created_at: now,
+            expires_at,
+        };
+        self.next_id += 1;
+        self.notes.push(note.clone());
+        Ok(note)
+    }
+
+    /// A note not yet expired
+    fn find(&mut self, id: &str, now: u64) -> Result<QuickNote, BackendError> {
+        self.prune(now);
+        self.notes
+            .iter()
+            .find(|n| n.id == id)
+            .cloned()
+            .ok_or_else(|| {
+                BackendError::new(errors::notes::NOT_FOUND, "Quick note not found or expired")
+                    .with_details(id.to_string())
+            })
+    }
+
+    fn remove(&mut self, id: &str) {
+        self.notes.retain(|n| n.id != id);
+    }
+}
+
+fn invalid_input(message: &str) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message)
+}
+
+fn board() -> std::sync::MutexGuard<'static, NoteBoard> {
+    BOARD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Drop the notes about a class that no longer exists
+pub fn forget_class(class_id: &str) {
+    board()
+        .notes
+        .retain(|n| n.class_id.as_deref() != Some(class_id));
+}
+
+/// When a note taken at `now` expires
+///
+/// An explicit TTL wins; otherwise the end of the running lesson, or
+/// `DEFAULT_TTL_SECS` outside lessons.
+fn expiry(now: u64, ttl_secs: Option<u64>, lesson_end: Option<u64>) -> Result<u64, BackendError> {
+    match ttl_secs {
+        Some(0) => Err(invalid_input("The TTL must be at least a second")),
+        Some(ttl) if ttl > MAX_TTL_SECS => Err(invalid_input("The TTL is at most 12 hours")),
+        Some(ttl) => Ok(now + ttl * 1000),
+        None => Ok(lesson_end
+            .filter(|end| *end > now)
+            .unwrap_or(now + DEFAULT_TTL_SECS * 1000)),
+    }
+}
+
+/// Add a note (see `expiry` for when it goes away)
+pub fn add_quick_note(
+    text: &str,
+    ttl_secs: Option<u64>,
+    class_id: Option<&str>,
+    student_id: Option<&str>,
+) -> Result<QuickNote, BackendError> {
+    let lesson_end = schedule::get_time_remaining_in_period()
+        .current
+        .filter(|p| p.kind == PeriodKind::Lesson)
+        .map(|p| p.ends_at);
+    let now = clock::now_millis();
+    let expires_at = expiry(now, ttl_secs, lesson_end)?;
+    board().add(text, class_id, student_id, now, expires_at)
+}
+
+/// Notes not yet expired, oldest first
+pub fn list_quick_notes() -> Vec<QuickNote> {
+    let mut board = board();
+    board.prune(clock::now_millis());
+    board.notes.clone()
+}
+
+/// Keep a note in the class journal, as a `note` behavior entry of its
+/// student; the quick note goes away
+pub fn promote_quick_note(id: &str) -> Result<BehaviorEntry, BackendError> {
+    let note = board().find(id, clock::now_millis())?;
+    let (Some(class_id), Some(student_id)) = (&note.class_id, &note.student_id) else {
+        return Err(invalid_input(
+            "Only notes about a student can go in the journal",
+        ));
+    };
+    let entry =
+        class_records::add_behavior_entry(class_id, student_id, BehaviorKind::Note, &note.text)?;
+    board().remove(id);
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry() {
+        assert_eq!(expiry(1_000, Some(60), Some(500_000)).unwrap(), 61_000);
+        assert_eq!(expiry(1_000, None, Some(500_000)).unwrap(), 500_000);
+        assert_eq!(
+            expiry(1_000, None, None).unwrap(),
+            1_000 + DEFAULT_TTL_SECS * 1000
+        );
+        assert!(expiry(1_000, Some(0), None).is_err());
+        assert!(expiry(1_000, Some(MAX_TTL_SECS + 1), None).is_err());
+    }
+
+    #[test]
+    fn test_notes_expire() {
+        let mut board = NoteBoard::new();
+        let short = board.add("Compiti", None, None, 0, 10).unwrap();
+        let long = board
+            .add(" Marco forgot homework ", Some("c1"), Some("s1"), 0, 100)
+            .unwrap();
+        assert_eq!(long.text, "Marco forgot homework");
+        assert!(board.add("  ", None, None, 0, 100).is_err());
+        assert!(board.add("Orphan", None, Some("s1"), 0, 100).is_err());
+
+        assert_eq!(
+            board.find(&short.id, 10).unwrap_err().code,
+            errors::notes::NOT_FOUND
+        );
+        assert_eq!(board.find(&long.id, 50).unwrap(), long);
+        board.remove(&long.id);
+        assert!(board.find(&long.id, 50).is_err());
+        assert!(board.notes.is_empty());
+    }
+}
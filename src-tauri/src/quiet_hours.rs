@@ -0,0 +1,340 @@
+//! Scheduled pauses of noise monitoring
+//!
+//! Handles:
+//! - Quiet-hour rules (`monitoring_quiet_hours` config key): the breaks of
+//!   the bell schedule (see `schedule`), bell periods with a given label
+//!   ("Verifica"), before and after school, while exam mode runs, and
+//!   fixed weekly windows
+//! - Working out whether monitoring is paused right now, and by which rule
+//!
+//! While paused, `classroom_state::report_noise_level` leaves reported
+//! levels out of the traffic light, noise history and gain compensation:
+//! the light can't turn red over a noisy break and the class's history
+//! isn't skewed by it. The meter itself keeps showing levels, flagged as
+//! paused.
+
+use crate::errors::{self, BackendError};
+use crate::exam_mode;
+use crate::file_ops;
+use crate::schedule::{self, BellSchedule, PeriodKind};
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+const CONFIG_KEY: &str = "monitoring_quiet_hours";
+
+pub const MAX_RULES: usize = 50;
+/// Longest margin around the school day
+pub const MAX_MARGIN_MINUTES: u32 = 240;
+
+/// When monitoring pauses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuietRule {
+    /// Every break of the bell schedule
+    Breaks,
+    /// Bell periods with this label, e.g. the weekly test slot
+    #[serde(rename_all = "camelCase")]
+    Period { label: String },
+    /// Before the first and after the last period of the day, and all day
+    /// on days without periods; never applies while the bell schedule is
+    /// empty
+    #[serde(rename_all = "camelCase")]
+    OutsideSchool {
+        /// Monitoring runs this long before the first and after the last
+        /// period
+        #[serde(default)]
+        margin_minutes: u32,
+    },
+    /// While exam mode runs
+    Exams,
+    /// A fixed window on some weekdays
+    #[serde(rename_all = "camelCase")]
+    Window {
+        /// "HH:MM"
+        start: String,
+        /// "HH:MM"
+        end: String,
+        /// ISO weekdays (1 = Monday … 7 = Sunday)
+        weekdays: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHours {
+    pub rules: Vec<QuietRule>,
+}
+
+/// The rules and whether they pause monitoring now
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHoursStatus {
+    pub rules: Vec<QuietRule>,
+    pub paused: bool,
+    /// The rule pausing monitoring
+    pub reason: Option<QuietRule>,
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+fn validate(rules: &[QuietRule]) -> Result<(), BackendError> {
+    if rules.len() > MAX_RULES {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("At most {} quiet-hour rules", MAX_RULES),
+        ));
+    }
+    for rule in rules {
+        let invalid = |msg: &str| {
+            Err(BackendError::new(errors::system::INVALID_INPUT, msg)
+                .with_details(format!("{:?}", rule)))
+        };
+        match rule {
+            QuietRule::Period { label } if label.trim().is_empty() => {
+                return invalid("A period rule needs a label");
+            }
+            QuietRule::OutsideSchool { margin_minutes } if *margin_minutes > MAX_MARGIN_MINUTES => {
+                return invalid("The margin around the school day is too long");
+            }
+            QuietRule::Window {
+                start,
+                end,
+                weekdays,
+            } => {
+                let (Some(start), Some(end)) = (parse_time(start), parse_time(end)) else {
+                    return invalid("Times must be HH:MM");
+                };
+                if start >= end {
+                    return invalid("A window must end after it starts");
+                }
+                if weekdays.is_empty() || weekdays.iter().any(|d| !(1..=7).contains(d)) {
+                    return invalid("Weekdays must be 1 (Monday) to 7 (Sunday)");
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn get_quiet_hours() -> QuietHours {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Whether `rule` pauses monitoring at local time `now`
+fn applies(
+    rule: &QuietRule,
+    schedule: &BellSchedule,
+    exam_running: bool,
+    now: NaiveDateTime,
+) -> bool {
+    let weekday = now.date().weekday().number_from_monday() as u8;
+    let time = now.time();
+    // Today's periods as (start, end, period)
+    let today = || {
+        schedule
+            .periods
+            .iter()
+            .filter(move |p| p.weekdays.contains(&weekday))
+            .filter_map(|p| Some((parse_time(&p.start)?, parse_time(&p.end)?, p)))
+    };
+    match rule {
+        QuietRule::Breaks => today()
+            .any(|(start, end, p)| p.kind == PeriodKind::Break && start <= time && time < end),
+        QuietRule::Period { label } => today().any(|(start, end, p)| {
+            p.label.trim().eq_ignore_ascii_case(label.trim()) && start <= time && time < end
+        }),
+        QuietRule::OutsideSchool { margin_minutes } => {
+            if schedule.periods.is_empty() {
+                return false;
+            }
+            let margin = Duration::minutes(i64::from(*margin_minutes));
+            let first = today().map(|(start, _, _)| start).min();
+            let last = today().map(|(_, end, _)| end).max();
+            match (first, last) {
+                (Some(first), Some(last)) => {
+                    let date = now.date();
+                    now < date.and_time(first) - margin || now >= date.and_time(last) + margin
+                }
+                _ => true,
+            }
+        }
+        QuietRule::Exams => exam_running,
+        QuietRule::Window {
+            start,
+            end,
+            weekdays,
+        } => {
+            weekdays.contains(&weekday)
+                && matches!(
+                    (parse_time(start), parse_time(end)),
+                    (Some(start), Some(end)) if start <= time && time < end
+                )
+        }
+    }
+}
+
+/// First rule pausing monitoring at local time `now`
+fn quiet_reason_at(
+    rules: &[QuietRule],
+    schedule: &BellSchedule,
+    exam_running: bool,
+    now: NaiveDateTime,
+) -> Option<QuietRule> {
+    rules
+        .iter()
+        .find(|rule| applies(rule, schedule, exam_running, now))
+        .cloned()
+}
+
+/// The rule pausing monitoring right now, if any
+pub fn current_reason() -> Option<QuietRule> {
+    let rules = get_quiet_hours().rules;
+    if rules.is_empty() {
+        return None;
+    }
+    quiet_reason_at(
+        &rules,
+        &schedule::get_bell_schedule(),
+        exam_mode::is_running(),
+        Local::now().naive_local(),
+    )
+}
+
+pub fn get_monitoring_quiet_hours() -> QuietHoursStatus {
+    let reason = current_reason();
+    QuietHoursStatus {
+        rules: get_quiet_hours().rules,
+        paused: reason.is_some(),
+        reason,
+    }
+}
+
+/// Validate and save the rules; an empty list never pauses monitoring
+pub fn set_monitoring_quiet_hours(rules: Vec<QuietRule>) -> Result<QuietHoursStatus, BackendError> {
+    validate(&rules)?;
+    let value = serde_json::to_value(QuietHours { rules }).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(get_monitoring_quiet_hours())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::BellPeriod;
+    use chrono::NaiveDate;
+
+    fn period(label: &str, kind: PeriodKind, start: &str, end: &str) -> BellPeriod {
+        BellPeriod {
+            label: label.to_string(),
+            kind,
+            start: start.to_string(),
+            end: end.to_string(),
+            weekdays: vec![1, 2, 3, 4, 5],
+        }
+    }
+
+    fn schedule() -> BellSchedule {
+        BellSchedule {
+            periods: vec![
+                period("1ª ora", PeriodKind::Lesson, "08:00", "08:55"),
+                period("Intervallo", PeriodKind::Break, "10:50", "11:00"),
+                period("Verifica", PeriodKind::Lesson, "11:00", "11:55"),
+                period("5ª ora", PeriodKind::Lesson, "12:00", "13:00"),
+            ],
+        }
+    }
+
+    // 2024-03-04 is a Monday
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rules_follow_the_schedule() {
+        let rules = vec![
+            QuietRule::Breaks,
+            QuietRule::Period {
+                label: "verifica".into(),
+            },
+            QuietRule::OutsideSchool { margin_minutes: 15 },
+        ];
+        let reason = |day, h, m| quiet_reason_at(&rules, &schedule(), false, at(day, h, m));
+        assert_eq!(reason(4, 8, 30), None);
+        assert_eq!(reason(4, 10, 55), Some(QuietRule::Breaks));
+        assert!(matches!(reason(4, 11, 30), Some(QuietRule::Period { .. })));
+        // Margin around the day
+        assert_eq!(reason(4, 7, 50), None);
+        assert_eq!(reason(4, 13, 10), None);
+        assert!(matches!(
+            reason(4, 7, 40),
+            Some(QuietRule::OutsideSchool { .. })
+        ));
+        assert!(matches!(
+            reason(4, 13, 20),
+            Some(QuietRule::OutsideSchool { .. })
+        ));
+        // Saturday has no periods
+        assert!(reason(9, 10, 0).is_some());
+        // Without a bell schedule only fixed windows and exams apply
+        let window = QuietRule::Window {
+            start: "09:00".into(),
+            end: "09:30".into(),
+            weekdays: vec![1],
+        };
+        let rules = vec![rules[2].clone(), window.clone(), QuietRule::Exams];
+        let empty = BellSchedule::default();
+        assert_eq!(quiet_reason_at(&rules, &empty, false, at(9, 6, 0)), None);
+        assert_eq!(
+            quiet_reason_at(&rules, &empty, false, at(4, 9, 10)),
+            Some(window)
+        );
+        assert_eq!(quiet_reason_at(&rules, &empty, false, at(5, 9, 10)), None);
+        assert_eq!(
+            quiet_reason_at(&rules, &empty, true, at(5, 9, 10)),
+            Some(QuietRule::Exams)
+        );
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let window = |start: &str, end: &str, weekdays: Vec<u8>| QuietRule::Window {
+            start: start.into(),
+            end: end.into(),
+            weekdays,
+        };
+        assert!(validate(&[QuietRule::Breaks, window("09:00", "09:30", vec![1, 5])]).is_ok());
+        assert!(validate(&[window("09:30", "09:00", vec![1])]).is_err());
+        assert!(validate(&[window("9", "10:00", vec![1])]).is_err());
+        assert!(validate(&[window("09:00", "10:00", vec![8])]).is_err());
+        assert!(validate(&[QuietRule::Period { label: " ".into() }]).is_err());
+        assert!(validate(&[QuietRule::OutsideSchool {
+            margin_minutes: MAX_MARGIN_MINUTES + 1
+        }])
+        .is_err());
+
+        let value = serde_json::to_value(QuietHours {
+            rules: vec![
+                QuietRule::OutsideSchool { margin_minutes: 10 },
+                window("09:00", "09:30", vec![1]),
+            ],
+        })
+        .unwrap();
+        assert_eq!(value["rules"][0]["kind"], "outsideSchool");
+        assert_eq!(value["rules"][0]["marginMinutes"], 10);
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &value).is_ok());
+        let unknown = serde_json::json!({ "rules": [{ "kind": "holidays" }] });
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &unknown).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Read-only mode when the data directory can't be written
+//!
+//! Handles:
+//! - Probing the data directory at startup (a protected install location,
+//!   a read-only network share) and after it is moved
+//! - The flag in `AppState`, announced as `read-only-mode` so the UI can
+//!   show a banner
+//! - Rejecting commands that save data with `READ_ONLY_MODE` up front,
+//!   instead of letting them fail halfway with an IO error; saves of config
+//!   and data collections are refused as well (`file_ops::block_writes`),
+//!   so anything missing from the list still can't half-write
+//!
+//! Everything else (showing rosters, timers, the projector, exports to
+//! other folders) keeps working.
+
+use crate::command_trace;
+use crate::errors::{self, BackendError};
+use crate::file_ops::{self, data_location};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// Emitted when read-only mode is entered or left
+pub const READ_ONLY_EVENT: &str = "read-only-mode";
+
+/// Commands that write to the data directory
+pub const WRITING_COMMANDS: &[&str] = &[
+    // Settings
+    "save_config",
+    "reset_setting",
+    "factory_reset",
+    "set_app_language",
+    "set_webview_zoom",
+    "set_content_protection",
+    "set_presentation_safe",
+    "set_presentation_safe_shortcut",
+    "save_window_layout",
+    "set_lan_tls_enabled",
+    "set_lan_bind_config",
+    "regenerate_tls_certificate",
+    "configure_webdav",
+    "configure_s3",
+    "configure_smtp",
+    "configure_weekly_summary",
+    "configure_end_of_day",
+    "set_audio_restart_policy",
+    "set_command_trace",
+    "set_event_rate",
+    "set_analytics_consent",
+    "record_feature_usage",
+    "set_controller_enabled",
+    "regenerate_controller_token",
+    "set_presenter_bindings",
+    "set_bell_schedule",
+    "set_insight_thresholds",
+    "set_timer_announcements",
+    "set_output_device",
+    "set_volume_safety",
+    "set_agc",
+    "set_sound_unit",
+    "set_monitoring_quiet_hours",
+    "set_badge_reader",
+    "set_app_lock",
+    "copy_settings_between_profiles",
+    "save_grade_template",
+    "delete_grade_template",
+    // Devices
+    "pair_device",
+    "refresh_device_token",
+    "rename_device",
+    "revoke_device",
+    // Class data
+    "start_exit_ticket",
+    "submit_exit_ticket",
+    "close_exit_ticket",
+    "start_mood_checkin",
+    "submit_mood_checkin",
+    "close_mood_checkin",
+    "import_roster_file",
+    "set_roster_watch_folder",
+    "scan_roster_folder",
+    "apply_roster_update",
+    "dismiss_roster_update",
+    "merge_roster",
+    "record_attendance",
+    "add_behavior_entry",
+    "promote_quick_note",
+    "save_seating_chart",
+    "set_student_accommodations",
+    "generate_substitute_pack",
+    "add_device",
+    "assign_device",
+    "return_device",
+    "report_device_issue",
+    "resolve_device_issue",
+    "import_class_archive",
+    "attach_file",
+    "remove_attachment",
+    "import_photos_from_folder",
+    "set_noise_context",
+    "import_audio_presets",
+    "add_score",
+    "delete_score",
+    "import_forms_results",
+    "start_exam_mode",
+    "stop_exam_mode",
+    "generate_weekly_summary_now",
+    // Lesson recovery
+    "save_lesson_state",
+    "clear_lesson_state",
+    "discard_recovery_state",
+    // Backups
+    "create_backup",
+    "backup_to_cloud",
+    "restore_from_cloud",
+    // Profiles
+    "create_profile",
+    "set_profile_pin",
+    "delete_profile",
+    "set_active_profile",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyStatus {
+    pub read_only: bool,
+    /// Data directory in use
+    pub path: Option<String>,
+    /// Why it can't be written
+    pub reason: Option<String>,
+}
+
+/// Whether `command` is refused in the given mode
+fn is_blocked(read_only: bool, command: &str) -> bool {
+    read_only && WRITING_COMMANDS.contains(&command)
+}
+
+/// Why the data directory can't be written, `None` if it can
+fn probe() -> Option<String> {
+    let dir = match file_ops::get_config_dir() {
+        Ok(dir) => dir,
+        Err(e) => return Some(e.message),
+    };
+    data_location::check_writable(&dir)
+        .err()
+        .map(|e| e.details.unwrap_or(e.message))
+}
+
+fn status(state: &AppState) -> ReadOnlyStatus {
+    let reason = state.read_only_reason();
+    ReadOnlyStatus {
+        read_only: reason.is_some(),
+        path: file_ops::get_config_dir()
+            .ok()
+            .map(|p| p.to_string_lossy().to_string()),
+        reason,
+    }
+}
+
+/// Probe the data directory and update the mode (at startup, and after the
+/// directory moves)
+pub fn detect(app: &AppHandle) -> ReadOnlyStatus {
+    let state = app.state::<AppState>();
+    let reason = probe();
+    if let Some(reason) = &reason {
+        eprintln!("Data directory is not writable, read-only mode: {}", reason);
+    }
+    file_ops::block_writes(reason.is_some());
+    let changed = state.set_read_only(reason);
+    let status = status(&state);
+    if changed {
+        let _ = app.emit(READ_ONLY_EVENT, &status);
+    }
+    status
+}
+
+pub fn get_read_only_status(state: &AppState) -> ReadOnlyStatus {
+    status(state)
+}
+
+fn refusal(command: &str) -> BackendError {
+    BackendError::new(
+        errors::system::READ_ONLY_MODE,
+        "The data folder is read-only; changes can't be saved",
+    )
+    .with_details(command.to_string())
+}
+
+/// Fail with `READ_ONLY_MODE` when `command` is refused in the current mode
+///
+/// For backend work reachable other than through the command itself
+/// (controller actions).
+pub fn check<R: Runtime>(app: &AppHandle<R>, command: &str) -> Result<(), BackendError> {
+    let read_only = app
+        .try_state::<AppState>()
+        .is_some_and(|state| state.is_read_only());
+    if is_blocked(read_only, command) {
+        return Err(refusal(command));
+    }
+    Ok(())
+}
+
+/// Wrap the app's command handler with the read-only check
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let read_only = invoke
+            .message
+            .webview_ref()
+            .try_state::<AppState>()
+            .is_some_and(|state| state.is_read_only());
+        let command = invoke.message.command().to_string();
+        if !is_blocked(read_only, &command) {
+            return handler(invoke);
+        }
+        command_trace::reject(invoke.resolver, refusal(&command));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Named like writes, but they keep working: they move the data
+    /// somewhere writable, keep state in memory or use the keychain
+    const NOT_WRITING: &[&str] = &[
+        "set_data_directory",
+        "set_window_position",
+        "set_backup_passphrase",
+        "set_observer_pin",
+        "set_classroom_state",
+        "set_annotation_click_through",
+        "set_pointer_highlight",
+        "add_appointment",
+        "remove_appointment",
+        "add_quick_note",
+        "create_timer_sequence",
+        "submit_feedback",
+    ];
+
+    #[test]
+    fn test_list_matches_registered_commands() {
+        let registered: Vec<&str> = include_str!("lib.rs")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("commands::")?.strip_suffix(','))
+            .collect();
+        for command in WRITING_COMMANDS {
+            assert!(
+                registered.contains(command),
+                "{} is not registered",
+                command
+            );
+        }
+        let writing_verbs = [
+            "set_",
+            "save_",
+            "import_",
+            "delete_",
+            "add_",
+            "remove_",
+            "configure_",
+            "apply_",
+            "create_",
+            "scan_",
+            "rename_",
+            "reset_",
+            "record_",
+            "submit_",
+            "merge_",
+            "regenerate_",
+            "pair_",
+            "revoke_",
+            "restore_",
+        ];
+        for command in registered {
+            if writing_verbs.iter().any(|verb| command.starts_with(verb)) {
+                assert!(
+                    WRITING_COMMANDS.contains(&command) || NOT_WRITING.contains(&command),
+                    "{} may write data: add it to WRITING_COMMANDS or NOT_WRITING",
+                    command
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_writing_commands_blocked() {
+        assert!(is_blocked(true, "record_attendance"));
+        assert!(!is_blocked(false, "record_attendance"));
+        assert!(!is_blocked(true, "get_classes"));
+        assert!(!is_blocked(true, "dim_projector"));
+        // Moving the data somewhere writable is the way out
+        assert!(!is_blocked(true, "set_data_directory"));
+        assert!(WRITING_COMMANDS
+            .iter()
+            .all(|c| !c.starts_with("get_") && !c.starts_with("list_")));
+    }
+
+    #[test]
+    fn test_state_flag() {
+        let state = AppState::new();
+        assert!(!state.is_read_only());
+        assert!(state.set_read_only(Some("EACCES".into())));
+        assert!(!state.set_read_only(Some("EROFS".into())));
+        assert_eq!(
+            get_read_only_status(&state).reason.as_deref(),
+            Some("EROFS")
+        );
+        assert!(state.set_read_only(None));
+    }
+}
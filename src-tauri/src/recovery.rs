@@ -0,0 +1,283 @@
+//! Crash recovery for in-progress lesson state
+//!
+//! Handles:
+//! - Keeping the latest transient lesson state (running timer, active noise
+//!   session, unsaved attendance marks) pushed by the frontend
+//! - Flushing it to `recovery.json` every few seconds when it changed
+//! - Offering the state left by an interrupted run on next launch
+//!
+//! The file is written to a temp file and renamed, so a power cut mid-write
+//! leaves the previous snapshot intact. It lives next to the config file,
+//! outside `data/`, so it is not part of backups.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const RECOVERY_FILENAME: &str = "recovery.json";
+
+/// How often pending state is written to disk
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// State of the current session, written by the flusher
+static CURRENT: Mutex<SessionState> = Mutex::new(SessionState::new());
+
+/// Snapshot left by the previous run, read once at startup
+static RECOVERED: Mutex<Option<RecoverySnapshot>> = Mutex::new(None);
+
+/// A running or paused countdown timer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerState {
+    pub label: Option<String>,
+    pub duration_ms: u64,
+    pub remaining_ms: u64,
+    pub running: bool,
+}
+
+/// An active noise monitoring session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseSessionState {
+    pub class_id: Option<String>,
+    pub started_at: u64,
+    pub threshold: Option<f32>,
+}
+
+/// An attendance mark not yet saved with `record_attendance`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingAttendanceMark {
+    pub class_id: String,
+    pub student_id: String,
+    pub absent: bool,
+}
+
+/// Transient lesson state, as sent by the frontend
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LessonState {
+    #[serde(default)]
+    pub class_id: Option<String>,
+    #[serde(default)]
+    pub timer: Option<TimerState>,
+    #[serde(default)]
+    pub noise_session: Option<NoiseSessionState>,
+    #[serde(default)]
+    pub attendance: Vec<PendingAttendanceMark>,
+}
+
+impl LessonState {
+    /// Nothing worth resuming
+    pub fn is_empty(&self) -> bool {
+        self.timer.is_none() && self.noise_session.is_none() && self.attendance.is_empty()
+    }
+}
+
+/// Lesson state saved to disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverySnapshot {
+    pub saved_at: u64,
+    pub state: LessonState,
+}
+
+/// Pending write for the flusher
+#[derive(Debug)]
+enum Pending {
+    Clean,
+    Write(RecoverySnapshot),
+    Remove,
+}
+
+#[derive(Debug)]
+struct SessionState {
+    pending: Pending,
+    /// State was saved during this run, so the file is no longer the old one
+    saved: bool,
+}
+
+impl SessionState {
+    const fn new() -> Self {
+        Self {
+            pending: Pending::Clean,
+            saved: false,
+        }
+    }
+
+    fn update(&mut self, state: LessonState, now: u64) {
+        self.saved = true;
+        self.pending = if state.is_empty() {
+            Pending::Remove
+        } else {
+            Pending::Write(RecoverySnapshot {
+                saved_at: now,
+                state,
+            })
+        };
+    }
+
+    fn take(&mut self) -> Pending {
+        std::mem::replace(&mut self.pending, Pending::Clean)
+    }
+}
+
+fn recovery_path() -> Result<PathBuf, BackendError> {
+    Ok(file_ops::get_config_dir()?.join(RECOVERY_FILENAME))
+}
+
+fn read_snapshot() -> Result<Option<RecoverySnapshot>, BackendError> {
+    let path = recovery_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    // A corrupt file is not worth an error dialog at startup
+    Ok(serde_json::from_str(&content)
+        .ok()
+        .filter(|s: &RecoverySnapshot| !s.state.is_empty()))
+}
+
+fn write_snapshot(snapshot: &RecoverySnapshot) -> Result<(), BackendError> {
+    let path = recovery_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(snapshot).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to serialize lesson state")
+            .with_details(e.to_string())
+    })?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+fn remove_snapshot() -> Result<(), BackendError> {
+    let path = recovery_path()?;
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// Write pending state, if any
+fn flush() -> Result<(), BackendError> {
+    let pending = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).take();
+    match pending {
+        Pending::Clean => Ok(()),
+        Pending::Write(snapshot) => write_snapshot(&snapshot).inspect_err(|_| {
+            // Retry next tick unless a newer state arrived meanwhile
+            let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+            if matches!(current.pending, Pending::Clean) {
+                current.pending = Pending::Write(snapshot.clone());
+            }
+        }),
+        Pending::Remove => remove_snapshot(),
+    }
+}
+
+/// Pick up the previous run's snapshot and start the periodic flusher
+pub fn start() {
+    if let Ok(snapshot) = read_snapshot() {
+        *RECOVERED.lock().unwrap_or_else(|e| e.into_inner()) = snapshot;
+    }
+    std::thread::spawn(|| loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        let _ = flush();
+    });
+}
+
+/// Write pending state now instead of at the next tick (at shutdown)
+pub fn flush_pending() -> Result<(), BackendError> {
+    flush()
+}
+
+/// Record the current lesson state; written to disk within a few seconds
+///
+/// An empty state (no timer, session or marks) removes the recovery file.
+pub fn save_lesson_state(state: LessonState) {
+    CURRENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .update(state, clock::now_millis());
+}
+
+/// The lesson interrupted by the previous run, if any
+pub fn get_recovery_state() -> Option<RecoverySnapshot> {
+    RECOVERED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Forget the interrupted lesson (resumed or declined by the user)
+///
+/// The file is removed unless this session already saved state of its own.
+pub fn discard_recovery_state() -> Result<(), BackendError> {
+    if RECOVERED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .is_none()
+    {
+        return Ok(());
+    }
+    let current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    if !current.saved {
+        remove_snapshot()?;
+    }
+    Ok(())
+}
+
+/// The lesson ended normally: drop the recovery file
+pub fn clear_lesson_state() -> Result<(), BackendError> {
+    let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    current.pending = Pending::Clean;
+    remove_snapshot()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer_state() -> LessonState {
+        LessonState {
+            class_id: Some("class_1".into()),
+            timer: Some(TimerState {
+                label: None,
+                duration_ms: 60_000,
+                remaining_ms: 30_000,
+                running: true,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_update_tracks_latest_state() {
+        let mut session = SessionState::new();
+        session.update(timer_state(), 10);
+        session.update(timer_state(), 20);
+        match session.take() {
+            Pending::Write(snapshot) => assert_eq!(snapshot.saved_at, 20),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(session.take(), Pending::Clean));
+
+        session.update(LessonState::default(), 30);
+        assert!(matches!(session.take(), Pending::Remove));
+    }
+
+    #[test]
+    fn test_lesson_state_accepts_partial_json() {
+        let state: LessonState = serde_json::from_str(
+            r#"{"attendance":[{"classId":"c","studentId":"s","absent":true}]}"#,
+        )
+        .unwrap();
+        assert!(state.timer.is_none());
+        assert!(!state.is_empty());
+    }
+}
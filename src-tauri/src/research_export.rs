@@ -0,0 +1,446 @@
+//! Pseudonymized dataset for classroom-climate research
+//!
+//! Handles:
+//! - Daily noise statistics (from `noise_history`) and attendance rates of
+//!   every class over a date range, written as one JSON file for
+//!   university partners
+//! - Pseudonyms instead of ids and no names: SHA-256 of the id with a salt
+//!   kept only in this PC's keychain, so the same class or student gets
+//!   the same pseudonym in every export but nobody can go back to the id
+//! - k-anonymity: classes with fewer than `k` students are left out; a
+//!   student's attendance rate is only given as a band wide enough that
+//!   at least `k` classmates share it (down to one band for the class),
+//!   and left out when fewer than `k` classmates have records
+//!
+//! Class sizes are given as 5-student bands. Noise statistics are in the
+//! chosen sound unit (see `sound_units`), named in the file.
+
+use crate::class_records::AttendanceStore;
+use crate::errors::{self, BackendError};
+use crate::noise_history::{self, NoiseHistoryStore};
+use crate::roster::{ClassData, RosterStore};
+use crate::secrets;
+use crate::sound_units::{LevelScale, SoundUnit};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub(crate) const SALT_SECRET: &str = "research_export_salt";
+const FORMAT_VERSION: u32 = 2;
+const MAX_RANGE_DAYS: i64 = 366;
+pub const DEFAULT_K: usize = 5;
+const MAX_K: usize = 50;
+/// Attendance band widths tried, narrowest first (percentage points)
+const BAND_WIDTHS: [u32; 4] = [10, 25, 50, 100];
+
+/// Dates exported, both included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchRange {
+    /// `YYYY-MM-DD`
+    pub from: String,
+    /// `YYYY-MM-DD`
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchDay {
+    pub date: String,
+    pub noise_mean: Option<f64>,
+    pub noise_median: Option<f64>,
+    pub noise_p95: Option<f64>,
+    /// Share of recorded students present (0.0-1.0)
+    pub attendance_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchClass {
+    pub class: String,
+    /// e.g. "21-25"
+    pub size_band: String,
+    /// Days with any data, oldest first
+    pub days: Vec<ResearchDay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchStudent {
+    pub student: String,
+    pub class: String,
+    /// e.g. "75-100%"
+    pub attendance_band: String,
+}
+
+/// The exported file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchDataset {
+    pub format: u32,
+    pub from: String,
+    pub to: String,
+    pub k: usize,
+    /// Unit of the noise statistics
+    pub noise_unit: SoundUnit,
+    pub classes: Vec<ResearchClass>,
+    pub students: Vec<ResearchStudent>,
+}
+
+/// Result of an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResearchExportInfo {
+    pub path: String,
+    pub classes: usize,
+    pub students: usize,
+    /// Classes with fewer than `k` students
+    pub suppressed_classes: usize,
+    /// Students left out: fewer than `k` classmates with records
+    pub suppressed_students: usize,
+}
+
+struct Sources {
+    classes: Vec<ClassData>,
+    attendance: AttendanceStore,
+    noise: NoiseHistoryStore,
+    scale: LevelScale,
+}
+
+fn invalid_input(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(details.to_string())
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, BackendError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| invalid_input("Date must be YYYY-MM-DD", date))
+}
+
+/// Salt of this PC, created on first use
+fn salt() -> Result<String, BackendError> {
+    if let Some(salt) = secrets::get_secret(SALT_SECRET)? {
+        return Ok(salt);
+    }
+    let mut buf = [0u8; 32];
+    getrandom::fill(&mut buf).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    let salt = hex::encode(buf);
+    secrets::set_secret(SALT_SECRET, &salt)?;
+    Ok(salt)
+}
+
+fn pseudonym(salt: &str, kind: &str, id: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}:{}", salt, kind, id));
+    hex::encode(&digest[..8])
+}
+
+fn size_band(students: usize) -> String {
+    let lower = students.saturating_sub(1) / 5 * 5 + 1;
+    format!("{}-{}", lower, lower + 4)
+}
+
+/// Lower bound of the `width`-point band `rate` (0.0-1.0) falls in
+fn band_start(rate: f64, width: u32) -> u32 {
+    let percent = (rate * 100.0).round().clamp(0.0, 100.0) as u32;
+    (percent / width * width).min(100 - width)
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+/// Bands of a class's students: the narrowest width where every band
+/// holds at least `k` of them; with fewer than `k` students, none
+fn student_bands(rates: &[(String, f64)], k: usize) -> (Vec<(String, String)>, usize) {
+    let width = BAND_WIDTHS
+        .into_iter()
+        .find(|&width| {
+            let mut sizes: BTreeMap<u32, usize> = BTreeMap::new();
+            for (_, rate) in rates {
+                *sizes.entry(band_start(*rate, width)).or_default() += 1;
+            }
+            sizes.values().all(|&n| n >= k)
+        })
+        .unwrap_or(100);
+    let mut bands: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+    for (id, rate) in rates {
+        bands
+            .entry(band_start(*rate, width))
+            .or_default()
+            .push(id.as_str());
+    }
+    let mut kept = Vec::new();
+    let mut suppressed = 0;
+    for (start, ids) in bands {
+        if ids.len() < k {
+            suppressed += ids.len();
+            continue;
+        }
+        let label = format!("{}-{}%", start, start + width);
+        kept.extend(ids.into_iter().map(|id| (id.to_string(), label.clone())));
+    }
+    (kept, suppressed)
+}
+
+fn build_dataset(
+    sources: &Sources,
+    from: NaiveDate,
+    to: NaiveDate,
+    k: usize,
+    salt: &str,
+) -> (ResearchDataset, usize, usize) {
+    let from_s = from.format("%Y-%m-%d").to_string();
+    let to_s = to.format("%Y-%m-%d").to_string();
+    let in_range = |date: &str| date >= from_s.as_str() && date <= to_s.as_str();
+
+    let mut classes = Vec::new();
+    let mut students = Vec::new();
+    let (mut suppressed_classes, mut suppressed_students) = (0, 0);
+    for class in &sources.classes {
+        if class.students.len() < k {
+            suppressed_classes += 1;
+            continue;
+        }
+        let class_pseudonym = pseudonym(salt, "class", &class.id);
+
+        // date → (present, recorded); student → (present, recorded)
+        let mut per_day: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+        let mut per_student: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+        for record in sources
+            .attendance
+            .records
+            .iter()
+            .filter(|r| r.class_id == class.id && in_range(&r.date))
+        {
+            for tally in [
+                per_day.entry(record.date.as_str()).or_default(),
+                per_student.entry(record.student_id.as_str()).or_default(),
+            ] {
+                tally.0 += u32::from(!record.absent);
+                tally.1 += 1;
+            }
+        }
+
+        let noise_days = sources.noise.classes.get(&class.id).map(|h| &h.days);
+        let mut dates: Vec<&str> = per_day.keys().copied().collect();
+        dates.extend(
+            noise_days
+                .into_iter()
+                .flat_map(|days| days.keys())
+                .map(String::as_str)
+                .filter(|d| in_range(d)),
+        );
+        dates.sort();
+        dates.dedup();
+        let days = dates
+            .into_iter()
+            .map(|date| {
+                let histogram = noise_days.and_then(|days| days.get(date));
+                let stat = |value: Option<f64>| sources.scale.convert_opt(value);
+                ResearchDay {
+                    date: date.to_string(),
+                    noise_mean: stat(histogram.and_then(|h| h.mean())),
+                    noise_median: stat(histogram.and_then(|h| h.percentile(0.5))),
+                    noise_p95: stat(histogram.and_then(|h| h.percentile(0.95))),
+                    attendance_rate: per_day
+                        .get(date)
+                        .map(|&(present, recorded)| round(present as f64 / recorded as f64, 3)),
+                }
+            })
+            .collect();
+
+        let rates: Vec<(String, f64)> = per_student
+            .into_iter()
+            .map(|(id, (present, recorded))| {
+                (
+                    pseudonym(salt, "student", id),
+                    present as f64 / recorded as f64,
+                )
+            })
+            .collect();
+        let (bands, suppressed) = student_bands(&rates, k);
+        suppressed_students += suppressed;
+        students.extend(bands.into_iter().map(|(student, band)| ResearchStudent {
+            student,
+            class: class_pseudonym.clone(),
+            attendance_band: band,
+        }));
+        classes.push(ResearchClass {
+            class: class_pseudonym,
+            size_band: size_band(class.students.len()),
+            days,
+        });
+    }
+    // Pseudonym order, so the file order says nothing about names
+    classes.sort_by(|a, b| a.class.cmp(&b.class));
+    students.sort_by(|a, b| a.student.cmp(&b.student));
+
+    let dataset = ResearchDataset {
+        format: FORMAT_VERSION,
+        from: from_s,
+        to: to_s,
+        k,
+        noise_unit: sources.scale.unit,
+        classes,
+        students,
+    };
+    (dataset, suppressed_classes, suppressed_students)
+}
+
+/// Write the research dataset for `range` to `path` (.json)
+pub fn export_research_dataset(
+    range: &ResearchRange,
+    path: &str,
+    k: Option<usize>,
+) -> Result<ResearchExportInfo, BackendError> {
+    let from = parse_date(&range.from)?;
+    let to = parse_date(&range.to)?;
+    if to < from || (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(invalid_input(
+            &format!(
+                "The range must go forward and span at most {} days",
+                MAX_RANGE_DAYS
+            ),
+            format!("{} - {}", range.from, range.to),
+        ));
+    }
+    let k = k.unwrap_or(DEFAULT_K);
+    if !(2..=MAX_K).contains(&k) {
+        return Err(invalid_input(&format!("k must be 2-{}", MAX_K), k));
+    }
+    let is_json = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    if !is_json {
+        return Err(BackendError::new(
+            errors::file::INVALID_FORMAT,
+            "Output file must have the .json extension",
+        )
+        .with_details(path.to_string()));
+    }
+
+    noise_history::flush_pending()?;
+    let sources = Sources {
+        classes: RosterStore::load()?.classes,
+        attendance: AttendanceStore::load()?,
+        noise: NoiseHistoryStore::load()?,
+        scale: LevelScale::current(),
+    };
+    let (dataset, suppressed_classes, suppressed_students) =
+        build_dataset(&sources, from, to, k, &salt()?);
+    let json = serde_json::to_vec_pretty(&dataset).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize dataset")
+            .with_details(e.to_string())
+    })?;
+    std::fs::write(path, json).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to write research dataset")
+            .with_details(format!("{}: {}", path, e))
+    })?;
+    Ok(ResearchExportInfo {
+        path: path.to_string(),
+        classes: dataset.classes.len(),
+        students: dataset.students.len(),
+        suppressed_classes,
+        suppressed_students,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_records::AttendanceRecord;
+    use crate::noise_history::Histogram;
+    use crate::roster::ClassBuilder;
+
+    fn class(id: &str, size: usize) -> ClassData {
+        (0..size)
+            .fold(
+                ClassBuilder::new(id, &format!("Classe {}", id)),
+                |class, i| class.student(&format!("{}_s{}", id, i), &format!("Studente {}", i)),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_bands_widen_until_k_share_them() {
+        let rates: Vec<(String, f64)> = [0.95, 0.92, 0.88, 0.81, 0.78, 0.5]
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (format!("s{}", i), *r))
+            .collect();
+        // 25-point bands leave 50% alone; 50-point bands hold everyone
+        let (kept, suppressed) = student_bands(&rates, 3);
+        assert_eq!((kept.len(), suppressed), (6, 0));
+        assert!(kept.iter().all(|(_, band)| band == "50-100%"));
+        let (kept, _) = student_bands(&rates[..4], 2);
+        assert!(kept
+            .iter()
+            .all(|(_, band)| band == "80-90%" || band == "90-100%"));
+        // Fewer than k students with records: nobody is given
+        let (kept, suppressed) = student_bands(&rates[..2], 3);
+        assert_eq!((kept.len(), suppressed), (0, 2));
+
+        assert_eq!(band_start(1.0, 10), 90);
+        assert_eq!(size_band(20), "16-20");
+        assert_eq!(size_band(21), "21-25");
+    }
+
+    #[test]
+    fn test_dataset_is_pseudonymized() {
+        let mut noise = NoiseHistoryStore::default();
+        let mut histogram = Histogram::default();
+        histogram.add(40.0);
+        histogram.add(60.0);
+        noise
+            .classes
+            .entry("big".into())
+            .or_default()
+            .days
+            .insert("2026-10-05".into(), histogram);
+        let big = class("big", 3);
+        let sources = Sources {
+            attendance: AttendanceStore {
+                records: big
+                    .students
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| AttendanceRecord {
+                        class_id: "big".into(),
+                        student_id: s.id.clone(),
+                        date: "2026-10-06".into(),
+                        absent: i == 0,
+                    })
+                    .collect(),
+            },
+            classes: vec![big, class("small", 2)],
+            noise,
+            scale: LevelScale::default(),
+        };
+        let from = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2026, 10, 31).unwrap();
+        let (dataset, suppressed_classes, _) = build_dataset(&sources, from, to, 3, "salt");
+        assert_eq!(suppressed_classes, 1);
+        assert_eq!(dataset.classes.len(), 1);
+        let days = &dataset.classes[0].days;
+        assert_eq!(days[0].noise_mean, Some(50.0));
+        assert_eq!(days[0].attendance_rate, None);
+        assert_eq!(days[1].attendance_rate, Some(0.667));
+
+        let json = serde_json::to_string(&dataset).unwrap();
+        for secret in ["big", "Classe", "Studente", "_s0", "salt"] {
+            assert!(!json.contains(secret), "{}", secret);
+        }
+        // Stable with the same salt, different with another
+        assert_eq!(dataset.classes[0].class, pseudonym("salt", "class", "big"));
+        assert_ne!(
+            pseudonym("salt", "class", "big"),
+            pseudonym("other", "class", "big")
+        );
+    }
+}
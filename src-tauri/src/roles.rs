@@ -0,0 +1,569 @@
+//! User profiles and role-based command gating
+//!
+//! Handles:
+//! - Profiles on a shared classroom PC (regular teacher, substitute,
+//!   assistant), each with a role: teacher, assistant, observer or
+//!   substitute
+//! - The active profile; switching to a higher role needs that profile's
+//!   PIN (stored hashed in the keychain) and switching up to a teacher
+//!   profile always does, switching down never does. Every role may list
+//!   and switch profiles, so no profile can shut the user in
+//! - The command guard: privileged commands (erasing data, changing
+//!   thresholds and settings, exporting) need the teacher role, observers
+//!   only reach read-only commands and substitutes only their pack and the
+//!   classroom tools (`substitute::SUBSTITUTE_COMMANDS`); anything else
+//!   fails with `FORBIDDEN`
+//!
+//! Profiles live in the `profiles` data collection. With no active profile
+//! the app behaves as before roles existed (teacher); when the profiles
+//! can't be read the guard falls back to the lowest role. Personal
+//! settings follow the active profile (see `profile_settings`).
+
+use crate::app_lock;
+use crate::clock;
+use crate::command_trace;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::observer;
+use crate::profile_settings;
+use crate::secrets;
+use crate::substitute;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::ipc::Invoke;
+use tauri::Runtime;
+
+const COLLECTION: &str = "profiles";
+const MAX_NAME_CHARS: usize = 40;
+
+/// Role of the active profile, cached for the command guard
+static ACTIVE_ROLE: Mutex<Option<Role>> = Mutex::new(None);
+
+/// Commands that need the teacher role
+pub const TEACHER_COMMANDS: &[&str] = &[
+    // Erasing data
+    "reset_setting",
+    "request_factory_reset",
+    "factory_reset",
+    "restore_from_cloud",
+    "import_class_archive",
+    "remove_attachment",
+    "revoke_device",
+    "discard_recovery_state",
+    "delete_score",
+    "delete_grade_template",
+    "apply_roster_update",
+    "merge_roster",
+    // Thresholds and settings
+    "save_config",
+    "set_content_protection",
+    "set_presentation_safe_shortcut",
+    "set_audio_restart_policy",
+    "set_output_device",
+    "set_volume_safety",
+    "set_agc",
+    "set_sound_unit",
+    "set_monitoring_quiet_hours",
+    "set_badge_reader",
+    "import_audio_presets",
+    "set_event_rate",
+    "set_bell_schedule",
+    "set_insight_thresholds",
+    "set_presenter_bindings",
+    "set_controller_enabled",
+    "regenerate_controller_token",
+    "set_lan_tls_enabled",
+    "regenerate_tls_certificate",
+    "set_lan_bind_config",
+    "start_kiosk_page",
+    "set_roster_watch_folder",
+    "set_backup_passphrase",
+    "set_analytics_consent",
+    "configure_webdav",
+    "configure_s3",
+    "configure_smtp",
+    "configure_weekly_summary",
+    "configure_end_of_day",
+    "set_observer_pin",
+    "set_app_lock",
+    "copy_settings_between_profiles",
+    "set_data_directory",
+    "set_command_trace",
+    "save_grade_template",
+    // Exporting
+    "create_backup",
+    "backup_to_cloud",
+    "export_class_archive",
+    "export_audio_presets",
+    "export_grades",
+    "export_research_dataset",
+    "export_exam_log",
+    "generate_class_documents",
+    "generate_substitute_pack",
+    "generate_docx_from_template",
+    "generate_weekly_summary_now",
+    "get_diagnostics_bundle",
+    "submit_feedback",
+    "verify_data_integrity",
+    // Ending an exam stops its integrity watchdog
+    "stop_exam_mode",
+    // Profiles
+    "create_profile",
+    "delete_profile",
+    "set_profile_pin",
+];
+
+/// Commands every role may run
+pub const PROFILE_SWITCH_COMMANDS: &[&str] = &["list_profiles", "set_active_profile"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Substitute,
+    Observer,
+    Assistant,
+    Teacher,
+}
+
+impl Role {
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Substitute => "substitute",
+            Role::Observer => "observer",
+            Role::Assistant => "assistant",
+            Role::Teacher => "teacher",
+        }
+    }
+}
+
+/// A user of the classroom PC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    /// Whether switching to this profile asks for a PIN
+    #[serde(default)]
+    pub has_pin: bool,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProfileStore {
+    pub profiles: Vec<Profile>,
+    pub active_profile_id: Option<String>,
+}
+
+impl ProfileStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)?;
+        *ACTIVE_ROLE.lock().unwrap_or_else(|e| e.into_inner()) = Some(self.active_role());
+        Ok(())
+    }
+
+    fn find(&self, id: &str) -> Result<&Profile, BackendError> {
+        self.profiles
+            .iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| not_found(id))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Result<&mut Profile, BackendError> {
+        self.profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| not_found(id))
+    }
+
+    fn active(&self) -> Option<&Profile> {
+        let id = self.active_profile_id.as_deref()?;
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// Role of the active profile (teacher when none is active)
+    pub fn active_role(&self) -> Role {
+        self.active().map_or(Role::Teacher, |p| p.role)
+    }
+}
+
+fn not_found(id: &str) -> BackendError {
+    BackendError::new(errors::role::PROFILE_NOT_FOUND, "Profile not found")
+        .with_details(id.to_string())
+}
+
+/// Role a command needs
+pub fn required_role(command: &str) -> Role {
+    if TEACHER_COMMANDS.contains(&command) {
+        Role::Teacher
+    } else if observer::READ_ONLY_COMMANDS.contains(&command) {
+        Role::Observer
+    } else {
+        Role::Assistant
+    }
+}
+
+/// Whether `role` may run `command`
+fn allowed(role: Role, command: &str) -> bool {
+    if PROFILE_SWITCH_COMMANDS.contains(&command) {
+        return true;
+    }
+    match role {
+        Role::Substitute => substitute::SUBSTITUTE_COMMANDS.contains(&command),
+        _ => role >= required_role(command),
+    }
+}
+
+fn forbidden(command: &str, role: Role) -> BackendError {
+    BackendError::new(
+        errors::role::FORBIDDEN,
+        format!("Not allowed for the {} role", role.as_str()),
+    )
+    .with_details(command.to_string())
+}
+
+/// Role of the active profile
+///
+/// Unreadable profiles give the lowest role (not cached, so it is read
+/// again once the file is fixed).
+pub fn active_role() -> Role {
+    let mut cached = ACTIVE_ROLE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(role) = *cached {
+        return role;
+    }
+    match ProfileStore::load() {
+        Ok(store) => *cached.insert(store.active_role()),
+        Err(e) => {
+            eprintln!(
+                "Failed to read profiles, allowing the lowest role: {}",
+                e.message
+            );
+            Role::Substitute
+        }
+    }
+}
+
+/// Fail with `FORBIDDEN` unless the active role may run `command`
+///
+/// For backend work reachable other than through the command itself
+/// (e.g. controller actions).
+pub fn check_command(command: &str) -> Result<(), BackendError> {
+    let role = active_role();
+    if allowed(role, command) {
+        Ok(())
+    } else {
+        Err(forbidden(command, role))
+    }
+}
+
+/// Fail unless `command` may run now for the window labelled `label`:
+/// the app lock, the observer window's read-only list and the active role
+/// all apply, as they do to the command itself
+///
+/// For backend work reachable other than through the command itself
+/// (controller actions, the asset protocol).
+pub fn check_access(label: &str, command: &str) -> Result<(), BackendError> {
+    let lock_screen = app_lock::UNLOCKED_COMMANDS.contains(&command);
+    if app_lock::is_locked() && !lock_screen {
+        return Err(BackendError::new(errors::lock::LOCKED, "The app is locked")
+            .with_details(command.to_string()));
+    }
+    if !observer::is_allowed(label, command) {
+        return Err(BackendError::new(
+            errors::observer::READ_ONLY,
+            "The observer window is read-only",
+        )
+        .with_details(command.to_string()));
+    }
+    if lock_screen {
+        return Ok(());
+    }
+    check_command(command)
+}
+
+/// Wrap the app's command handler with the role check
+pub fn command_guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let command = invoke.message.command();
+        // The lock screen must work whatever the role
+        let checked = if app_lock::UNLOCKED_COMMANDS.contains(&command) {
+            Ok(())
+        } else {
+            check_command(command)
+        };
+        match checked {
+            Ok(()) => handler(invoke),
+            Err(e) => {
+                command_trace::reject(invoke.resolver, e);
+                true
+            }
+        }
+    }
+}
+
+fn pin_secret(profile_id: &str) -> String {
+    format!("profile_pin:{}", profile_id)
+}
+
+/// Keychain entries of the profile PINs
+pub(crate) fn pin_secrets() -> Result<Vec<String>, BackendError> {
+    Ok(ProfileStore::load()?
+        .profiles
+        .iter()
+        .filter(|p| p.has_pin)
+        .map(|p| pin_secret(&p.id))
+        .collect())
+}
+
+/// Forget the cached role so it is read again from the profiles
+/// (factory reset)
+pub(crate) fn reset_active_role() {
+    *ACTIVE_ROLE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// All profiles and the active one
+pub fn list_profiles() -> Result<ProfileStore, BackendError> {
+    ProfileStore::load()
+}
+
+/// Add a profile
+pub fn create_profile(name: &str, role: Role, pin: Option<&str>) -> Result<Profile, BackendError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Profile name must be 1 to {} characters", MAX_NAME_CHARS),
+        ));
+    }
+    let mut store = ProfileStore::load()?;
+    let now = clock::now_millis();
+    let profile = Profile {
+        id: format!("profile_{}", now),
+        name: name.to_string(),
+        role,
+        has_pin: pin.is_some(),
+        created_at: now,
+    };
+    if let Some(pin) = pin {
+        secrets::validate_pin(pin)?;
+        secrets::set_secret(&pin_secret(&profile.id), &secrets::hash_pin(pin)?)?;
+    }
+    store.profiles.push(profile.clone());
+    store.save()?;
+    Ok(profile)
+}
+
+/// Set or clear a profile's PIN
+pub fn set_profile_pin(profile_id: &str, pin: Option<&str>) -> Result<Profile, BackendError> {
+    let mut store = ProfileStore::load()?;
+    let profile = store.find_mut(profile_id)?;
+    match pin {
+        Some(pin) => {
+            secrets::validate_pin(pin)?;
+            secrets::set_secret(&pin_secret(profile_id), &secrets::hash_pin(pin)?)?;
+        }
+        None => secrets::delete_secret(&pin_secret(profile_id))?,
+    }
+    profile.has_pin = pin.is_some();
+    let profile = profile.clone();
+    store.save()?;
+    Ok(profile)
+}
+
+/// Remove a profile (not the active one)
+pub fn delete_profile(profile_id: &str) -> Result<(), BackendError> {
+    let mut store = ProfileStore::load()?;
+    store.find(profile_id)?;
+    if store.active_profile_id.as_deref() == Some(profile_id) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Switch to another profile before deleting this one",
+        ));
+    }
+    store.profiles.retain(|p| p.id != profile_id);
+    store.save()?;
+    profile_settings::forget_profile(profile_id)?;
+    secrets::delete_secret(&pin_secret(profile_id))
+}
+
+/// Whether switching from `current` to `target` needs the target's PIN;
+/// going up to a teacher profile always does
+fn needs_pin(current: Role, target: &Profile) -> bool {
+    target.role > current && (target.has_pin || target.role == Role::Teacher)
+}
+
+/// Check a switch to `profile_id`; returns the profile whose PIN must be
+/// entered, if any
+fn check_switch<'a>(
+    store: &'a ProfileStore,
+    profile_id: Option<&str>,
+) -> Result<Option<&'a Profile>, BackendError> {
+    let current = store.active_role();
+    let Some(id) = profile_id else {
+        // Leaving profiles altogether means full access
+        if current < Role::Teacher {
+            return Err(forbidden("set_active_profile", current));
+        }
+        return Ok(None);
+    };
+    let target = store.find(id)?;
+    // Without a teacher PIN there would be no way back up
+    if target.role < Role::Teacher
+        && !store
+            .profiles
+            .iter()
+            .any(|p| p.role == Role::Teacher && p.has_pin)
+    {
+        return Err(BackendError::new(
+            errors::role::PIN_NOT_SET,
+            "Give a teacher profile a PIN before switching to a lower role",
+        ));
+    }
+    if !needs_pin(current, target) {
+        return Ok(None);
+    }
+    if !target.has_pin {
+        return Err(BackendError::new(
+            errors::role::PIN_NOT_SET,
+            "This teacher profile has no PIN; switch to one that has",
+        )
+        .with_details(id.to_string()));
+    }
+    Ok(Some(target))
+}
+
+/// Make a profile active; `None` returns to the default (teacher) when
+/// allowed
+pub fn set_active_profile(
+    profile_id: Option<&str>,
+    pin: Option<&str>,
+) -> Result<ProfileStore, BackendError> {
+    let mut store = ProfileStore::load()?;
+    if let Some(target) = check_switch(&store, profile_id)? {
+        let stored = secrets::get_secret(&pin_secret(&target.id))?.unwrap_or_default();
+        if !pin.is_some_and(|pin| secrets::verify_pin(&stored, pin)) {
+            return Err(BackendError::new(errors::role::INVALID_PIN, "Wrong PIN"));
+        }
+    }
+    let previous = store.active_profile_id.take();
+    store.active_profile_id = profile_id.map(String::from);
+    store.save()?;
+    profile_settings::switch_profile(previous.as_deref(), profile_id)?;
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(role: Role, has_pin: bool) -> Profile {
+        Profile {
+            id: "p".into(),
+            name: "Supplente".into(),
+            role,
+            has_pin,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_required_roles() {
+        assert_eq!(required_role("factory_reset"), Role::Teacher);
+        assert_eq!(required_role("export_class_archive"), Role::Teacher);
+        assert_eq!(required_role("save_config"), Role::Teacher);
+        assert_eq!(required_role("merge_roster"), Role::Teacher);
+        assert_eq!(required_role("stop_exam_mode"), Role::Teacher);
+        assert_eq!(required_role("record_attendance"), Role::Assistant);
+        assert_eq!(required_role("get_classroom_state"), Role::Observer);
+        assert!(Role::Teacher > Role::Assistant && Role::Assistant > Role::Observer);
+        assert!(Role::Observer > Role::Substitute);
+
+        assert!(allowed(Role::Substitute, "get_substitute_packs"));
+        assert!(allowed(Role::Substitute, "get_bell_schedule"));
+        assert!(!allowed(Role::Substitute, "get_classes"));
+        assert!(!allowed(Role::Substitute, "export_grades"));
+        assert!(allowed(Role::Observer, "get_classroom_state"));
+        assert!(!allowed(Role::Observer, "get_substitute_packs"));
+    }
+
+    #[test]
+    fn test_active_role_and_pin_rules() {
+        let mut store = ProfileStore::default();
+        assert_eq!(store.active_role(), Role::Teacher);
+        store.profiles.push(Profile {
+            id: "sub".into(),
+            ..profile(Role::Assistant, false)
+        });
+        store.active_profile_id = Some("sub".into());
+        assert_eq!(store.active_role(), Role::Assistant);
+        // A deleted profile falls back to the default
+        store.active_profile_id = Some("gone".into());
+        assert_eq!(store.active_role(), Role::Teacher);
+
+        assert!(needs_pin(Role::Assistant, &profile(Role::Teacher, true)));
+        assert!(needs_pin(Role::Assistant, &profile(Role::Teacher, false)));
+        assert!(needs_pin(Role::Observer, &profile(Role::Assistant, true)));
+        assert!(!needs_pin(Role::Observer, &profile(Role::Assistant, false)));
+        assert!(!needs_pin(Role::Teacher, &profile(Role::Assistant, true)));
+    }
+
+    #[test]
+    fn test_observer_switches_back_to_teacher() {
+        let mut store = ProfileStore {
+            profiles: vec![
+                Profile {
+                    id: "teacher".into(),
+                    ..profile(Role::Teacher, true)
+                },
+                Profile {
+                    id: "observer".into(),
+                    ..profile(Role::Observer, false)
+                },
+            ],
+            active_profile_id: None,
+        };
+        assert!(check_switch(&store, Some("observer")).unwrap().is_none());
+        store.active_profile_id = Some("observer".into());
+
+        for role in [Role::Observer, Role::Substitute] {
+            assert!(allowed(role, "list_profiles"));
+            assert!(allowed(role, "set_active_profile"));
+        }
+        let target = check_switch(&store, Some("teacher")).unwrap();
+        assert_eq!(target.map(|p| p.id.as_str()), Some("teacher"));
+        let err = check_switch(&store, None).unwrap_err();
+        assert_eq!(err.code, errors::role::FORBIDDEN);
+
+        // A teacher profile without a PIN can't be switched up to
+        store.profiles[0].has_pin = false;
+        let err = check_switch(&store, Some("teacher")).unwrap_err();
+        assert_eq!(err.code, errors::role::PIN_NOT_SET);
+    }
+
+    #[test]
+    fn test_switching_down_needs_a_teacher_pin() {
+        let store = ProfileStore {
+            profiles: vec![Profile {
+                id: "observer".into(),
+                ..profile(Role::Observer, false)
+            }],
+            active_profile_id: None,
+        };
+        let err = check_switch(&store, Some("observer")).unwrap_err();
+        assert_eq!(err.code, errors::role::PIN_NOT_SET);
+    }
+
+    #[test]
+    fn test_forbidden_error() {
+        let e = forbidden("factory_reset", Role::Assistant);
+        assert_eq!(e.code, errors::role::FORBIDDEN);
+        assert_eq!(e.message, "Not allowed for the assistant role");
+    }
+}
@@ -0,0 +1,362 @@
+//! Class rosters for Classroom Management App
+//!
+//! Handles:
+//! - Persisting classes and students (mirrors `ClassData` in classStore.ts)
+//! - Validation rules from EC-006 / EC-009 (max 30 students, required name)
+//!
+//! File parsing lives in `file_ops::import_adapters`.
+//!
+//! Classes are stored in the `rosters` data collection.
+
+use crate::accommodations::Accommodation;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::fuzzy;
+use serde::{Deserialize, Serialize};
+
+const STORE_COLLECTION: &str = "rosters";
+
+/// Maximum students per class (EC-009)
+pub const MAX_STUDENTS: usize = 30;
+
+/// A student in a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Student {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub absent: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// School email, used to match online quiz results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Enforced by the picker, groups and seating (see `accommodations`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accommodations: Vec<Accommodation>,
+}
+
+/// A class with its students
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassData {
+    pub id: String,
+    pub name: String,
+    pub students: Vec<Student>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// Result of parsing a roster file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParsedRoster {
+    /// Student names in file order
+    pub students: Vec<String>,
+    /// Validation problems; a roster with errors must not be applied
+    pub errors: Vec<String>,
+}
+
+impl ParsedRoster {
+    /// Add a student name read from data row `row` (1-based)
+    pub fn push_name(&mut self, row: usize, name: &str) {
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            self.errors
+                .push(format!("Row {}: Missing student name", row));
+        } else if self.students.iter().any(|s| same_name(s, &name)) {
+            self.errors
+                .push(format!("Row {}: Duplicate student \"{}\"", row, name));
+        } else {
+            self.students.push(name);
+        }
+    }
+
+    /// Apply whole-roster checks once all rows are read
+    pub fn finish(mut self) -> Self {
+        if self.students.is_empty() && self.errors.is_empty() {
+            self.errors.push("CSV file is empty".to_string());
+        }
+        if self.students.len() > MAX_STUDENTS {
+            self.errors.push(format!(
+                "Too many students ({}). Maximum is {}.",
+                self.students.len(),
+                MAX_STUDENTS
+            ));
+        }
+        self
+    }
+
+    /// Whether the roster may be saved
+    ///
+    /// Rows with a missing or duplicate name are left out, or with `strict`
+    /// (all or nothing) reject the roster with `IMPORT_STRICT_REJECTED`. A
+    /// roster with no valid student, or too many, is always rejected.
+    pub fn check(&self, strict: bool) -> Result<(), BackendError> {
+        if self.students.is_empty() || self.students.len() > MAX_STUDENTS {
+            return Err(BackendError::new(
+                errors::roster::INVALID_ROSTER,
+                "Roster file has validation errors",
+            )
+            .with_details(self.errors.join("; ")));
+        }
+        if strict && !self.errors.is_empty() {
+            return Err(BackendError::new(
+                errors::import::STRICT_REJECTED,
+                "Roster file has invalid rows; nothing was imported",
+            )
+            .with_details(self.errors.join("; ")));
+        }
+        Ok(())
+    }
+}
+
+/// Persisted classes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RosterStore {
+    #[serde(default)]
+    pub classes: Vec<ClassData>,
+}
+
+impl RosterStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STORE_COLLECTION)
+    }
+
+    pub fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STORE_COLLECTION, self)
+    }
+
+    pub fn find(&self, class_id: &str) -> Option<&ClassData> {
+        self.classes.iter().find(|c| c.id == class_id)
+    }
+
+    /// Find a class by name (ignoring case, accents and spacing)
+    pub fn find_by_name(&self, name: &str) -> Option<&ClassData> {
+        let name = fuzzy::fold(name);
+        self.classes.iter().find(|c| fuzzy::fold(&c.name) == name)
+    }
+
+    /// Replace a class's student list with `names`
+    ///
+    /// Students whose name is unchanged keep their id, absence and notes.
+    /// Creates the class if `class_id` is `None`. Returns the class id.
+    pub fn apply_names(
+        &mut self,
+        class_id: Option<&str>,
+        class_name: &str,
+        names: &[String],
+        now: u64,
+    ) -> Result<String, BackendError> {
+        let index = match class_id {
+            Some(id) => self
+                .classes
+                .iter()
+                .position(|c| c.id == id)
+                .ok_or_else(|| {
+                    BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                        .with_details(id.to_string())
+                })?,
+            None => {
+                self.classes.push(ClassData {
+                    id: format!("class_{}", now),
+                    name: class_name.trim().to_string(),
+                    students: Vec::new(),
+                    created_at: now,
+                    updated_at: now,
+                });
+                self.classes.len() - 1
+            }
+        };
+
+        let class = &mut self.classes[index];
+        let mut previous: Vec<Option<Student>> = std::mem::take(&mut class.students)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let previous_names: Vec<String> =
+            previous.iter().flatten().map(|s| s.name.clone()).collect();
+        let matches = fuzzy::match_names(names, &previous_names, fuzzy::DEFAULT_THRESHOLD);
+        for (i, (name, m)) in names.iter().zip(matches).enumerate() {
+            let student = match m.target_index.and_then(|pos| previous[pos].take()) {
+                Some(student) if m.exact => student,
+                // A corrected spelling keeps the student (and their history)
+                Some(student) => Student {
+                    name: name.clone(),
+                    ..student
+                },
+                None => Student {
+                    id: format!("student_{}_{}", now, i),
+                    name: name.clone(),
+                    absent: false,
+                    notes: None,
+                    email: None,
+                    accommodations: Vec::new(),
+                },
+            };
+            class.students.push(student);
+        }
+        class.updated_at = now;
+        Ok(class.id.clone())
+    }
+}
+
+/// Names compare ignoring case, accents, apostrophes and spacing
+pub fn same_name(a: &str, b: &str) -> bool {
+    fuzzy::fold(a) == fuzzy::fold(b)
+}
+
+/// All saved classes
+pub fn list_classes() -> Result<Vec<ClassData>, BackendError> {
+    Ok(RosterStore::load()?.classes)
+}
+
+/// Replace (or create) a class roster from a list of names
+pub fn save_class_roster(
+    class_id: Option<&str>,
+    class_name: &str,
+    names: &[String],
+) -> Result<String, BackendError> {
+    let mut store = RosterStore::load()?;
+    let id = store.apply_names(class_id, class_name, names, clock::now_millis())?;
+    store.save()?;
+    Ok(id)
+}
+
+/// Test fixture shared by the modules that need a class
+#[cfg(test)]
+pub(crate) struct ClassBuilder {
+    class: ClassData,
+}
+
+#[cfg(test)]
+impl ClassBuilder {
+    /// Class `id` named `name`, without students
+    pub(crate) fn new(id: &str, name: &str) -> Self {
+        Self {
+            class: ClassData {
+                id: id.into(),
+                name: name.into(),
+                students: Vec::new(),
+                created_at: 0,
+                updated_at: 0,
+            },
+        }
+    }
+
+    pub(crate) fn student(mut self, id: &str, name: &str) -> Self {
+        self.class.students.push(Student {
+            id: id.into(),
+            name: name.into(),
+            absent: false,
+            notes: None,
+            email: None,
+            accommodations: Vec::new(),
+        });
+        self
+    }
+
+    /// Add students named `names`, with ids `s0`, `s1`, ...
+    pub(crate) fn students(self, names: &[&str]) -> Self {
+        let first = self.class.students.len();
+        names.iter().enumerate().fold(self, |builder, (i, name)| {
+            builder.student(&format!("s{}", first + i), name)
+        })
+    }
+
+    /// Creation and last update time
+    pub(crate) fn created_at(mut self, at: u64) -> Self {
+        self.class.created_at = at;
+        self.class.updated_at = at;
+        self
+    }
+
+    pub(crate) fn build(self) -> ClassData {
+        self.class
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsed_roster_validation() {
+        let mut parsed = ParsedRoster::default();
+        parsed.push_name(1, " Mario  Rossi ");
+        parsed.push_name(2, "");
+        parsed.push_name(3, "mario rossi");
+        let parsed = parsed.finish();
+        assert_eq!(parsed.students, vec!["Mario Rossi"]);
+        assert_eq!(parsed.errors.len(), 2);
+
+        let mut many = ParsedRoster::default();
+        for i in 0..=MAX_STUDENTS {
+            many.push_name(i + 1, &format!("Studente {}", i));
+        }
+        let many = many.finish();
+        assert_eq!(many.errors.len(), 1);
+        let empty = ParsedRoster::default().finish();
+        assert_eq!(empty.errors.len(), 1);
+
+        // Invalid rows are left out unless strict; the others always fail
+        assert!(parsed.check(false).is_ok());
+        assert_eq!(
+            parsed.check(true).unwrap_err().code,
+            errors::import::STRICT_REJECTED
+        );
+        for roster in [many, empty] {
+            assert_eq!(
+                roster.check(false).unwrap_err().code,
+                errors::roster::INVALID_ROSTER
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_names_keeps_existing_students() {
+        let mut store = RosterStore::default();
+        let id = store
+            .apply_names(
+                None,
+                "3A",
+                &["Mario Rossi".into(), "Anna Bianchi".into()],
+                1,
+            )
+            .unwrap();
+        let anna_id = store.find(&id).unwrap().students[1].id.clone();
+
+        store
+            .apply_names(
+                Some(&id),
+                "3A",
+                &["anna  bianchi".into(), "Luca Verdi".into()],
+                2,
+            )
+            .unwrap();
+        let class = store.find_by_name(" 3a ").unwrap();
+        assert_eq!(class.students.len(), 2);
+        assert_eq!(class.students[0].id, anna_id);
+        assert_eq!(class.students[1].name, "Luca Verdi");
+        assert_eq!(class.updated_at, 2);
+
+        // Spelling fixes from the registry keep the student's id
+        store
+            .apply_names(
+                Some(&id),
+                "3A",
+                &[
+                    "Anna Bianchi".into(),
+                    "Luca Verdì".into(),
+                    "Luca Verde".into(),
+                ],
+                3,
+            )
+            .unwrap();
+        let class = store.find(&id).unwrap();
+        assert_eq!(class.students[0].id, anna_id);
+        assert_eq!(class.students[1].id, "student_2_1");
+        assert_eq!(class.students[2].id, "student_3_2");
+    }
+}
@@ -0,0 +1,227 @@
+//! Importing a roster file chosen by the teacher
+//!
+//! Handles:
+//! - Parsing the file (see `file_ops::import_adapters`) and saving the class
+//! - Recognising a file imported before, by its SHA-256 in the import
+//!   history (see `import_history`): the same content is not imported
+//!   again, so a second click does not create a duplicate class
+//! - Recognising a changed version of a file imported before (same file
+//!   name): the differences become a pending roster update (see
+//!   `roster_sync`) for the teacher to review and apply
+//!
+//! `force` skips both checks and imports as is. Rows with a missing or
+//! duplicate name are left out and reported, unless `strict` (all or
+//! nothing) rejects the whole file (see `ParsedRoster::check`). The
+//! import's stages go to `progress` (see `file_ops::import_progress`).
+
+use crate::errors::BackendError;
+use crate::file_ops::import_adapters;
+use crate::file_ops::import_progress::{ImportProgress, ImportStage};
+use crate::file_ops::import_transforms::Transform;
+use crate::import_history::{self, ImportHistoryStore, ImportKind, ImportReport, ImportSource};
+use crate::roster::{self, ClassData, RosterStore};
+use crate::roster_sync::{self, RosterUpdate};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Result of `import_roster_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum RosterImportOutcome {
+    /// The class was created or updated
+    #[serde(rename_all = "camelCase")]
+    Imported { class_id: String, students: usize },
+    /// The same content was imported before; nothing changed
+    #[serde(rename_all = "camelCase")]
+    AlreadyImported {
+        class_id: String,
+        /// When it was imported
+        imported_at: u64,
+        report_id: String,
+    },
+    /// A changed version of a file imported before; the differences wait
+    /// as a pending update (`apply_roster_update`)
+    Changed { update: RosterUpdate },
+    /// The file changed but the student list is the same
+    #[serde(rename_all = "camelCase")]
+    Unchanged { class_id: String },
+}
+
+/// What to do with a file
+#[derive(Debug)]
+enum Plan<'a> {
+    AlreadyImported(&'a ImportReport),
+    UpdateOf(&'a ClassData),
+    Import,
+}
+
+/// Check the history for earlier imports of the same content or file
+///
+/// Only imports into classes that still exist count, and only into the
+/// class asked for when there is one.
+fn plan<'a>(
+    history: &'a ImportHistoryStore,
+    store: &'a RosterStore,
+    source: &ImportSource,
+    class_id: Option<&str>,
+) -> Plan<'a> {
+    let target = |report: &ImportReport| {
+        let id = report.class_id.as_deref()?;
+        if class_id.is_some_and(|wanted| wanted != id) {
+            return None;
+        }
+        store.find(id)
+    };
+    if let Some(hash) = source.hash.as_deref() {
+        let same = history
+            .last_roster_import(|r| r.source.hash.as_deref() == Some(hash) && target(r).is_some());
+        if let Some(report) = same {
+            return Plan::AlreadyImported(report);
+        }
+    }
+    let earlier = history
+        .last_roster_import(|r| r.source.file_name == source.file_name && target(r).is_some());
+    match earlier.and_then(target) {
+        Some(class) => Plan::UpdateOf(class),
+        None => Plan::Import,
+    }
+}
+
+/// Import a roster file into a class (created if `class_id` is `None`),
+/// running `transforms` on its columns first
+pub fn import_roster_file(
+    path: &Path,
+    class_id: Option<&str>,
+    class_name: &str,
+    transforms: &[Transform],
+    force: bool,
+    strict: bool,
+    progress: &dyn ImportProgress,
+) -> Result<RosterImportOutcome, BackendError> {
+    let source = ImportSource::from_path(path);
+    if !force {
+        let history = ImportHistoryStore::load()?;
+        let store = RosterStore::load()?;
+        match plan(&history, &store, &source, class_id) {
+            Plan::AlreadyImported(report) => {
+                return Ok(RosterImportOutcome::AlreadyImported {
+                    class_id: report.class_id.clone().unwrap_or_default(),
+                    imported_at: report.started_at,
+                    report_id: report.id.clone(),
+                });
+            }
+            Plan::UpdateOf(class) => {
+                // A file that can't be saved goes through the import below,
+                // which reports (and records) why
+                if let Ok(import) = import_adapters::import_roster_file(path, transforms, progress)
+                {
+                    if import.roster.check(strict).is_ok() {
+                        let diff =
+                            roster_sync::diff_roster(&class.students, &import.roster.students);
+                        if diff.is_empty() {
+                            return Ok(RosterImportOutcome::Unchanged {
+                                class_id: class.id.clone(),
+                            });
+                        }
+                        let update = roster_sync::queue_update(
+                            &source.file_name,
+                            class,
+                            import.roster,
+                            source.hash.clone(),
+                        )?;
+                        return Ok(RosterImportOutcome::Changed { update });
+                    }
+                }
+            }
+            Plan::Import => {}
+        }
+    }
+
+    import_history::track(ImportKind::Roster, source, |history| {
+        history.class_id = class_id.map(String::from);
+        let import = import_adapters::import_roster_file(path, transforms, progress)?;
+        history.describe_roster(&import);
+        import.roster.check(strict)?;
+        let students = import.roster.students.len();
+        progress.report(ImportStage::Persisting, 0, Some(students));
+        let id = roster::save_class_roster(class_id, class_name, &import.roster.students)?;
+        progress.report(ImportStage::Persisting, students, Some(students));
+        history.imported = students;
+        history.class_id = Some(id.clone());
+        Ok(RosterImportOutcome::Imported {
+            class_id: id,
+            students,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn report(id: &str, file_name: &str, content: &[u8], class_id: &str) -> ImportReport {
+        ImportReport {
+            id: id.to_string(),
+            kind: ImportKind::Roster,
+            source: ImportSource::from_bytes(file_name, content),
+            format: None,
+            columns: BTreeMap::new(),
+            class_id: Some(class_id.to_string()),
+            rows: 1,
+            imported: 1,
+            skipped: 0,
+            errors: Vec::new(),
+            success: true,
+            failure: None,
+            started_at: 1,
+            duration_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_plan_detects_reimports() {
+        let mut store = RosterStore::default();
+        let class_id = store
+            .apply_names(None, "3A", &["Mario Rossi".into()], 1)
+            .unwrap();
+        let history = ImportHistoryStore {
+            reports: vec![
+                report("r1", "3A.csv", b"v1", &class_id),
+                report("r2", "old.csv", b"v0", "deleted_class"),
+            ],
+        };
+
+        let same = ImportSource::from_bytes("copy of 3A.csv", b"v1");
+        assert!(matches!(
+            plan(&history, &store, &same, None),
+            Plan::AlreadyImported(r) if r.id == "r1"
+        ));
+        // Into another class on purpose
+        assert!(matches!(
+            plan(&history, &store, &same, Some("class_9")),
+            Plan::Import
+        ));
+
+        let changed = ImportSource::from_bytes("3A.csv", b"v2");
+        assert!(matches!(
+            plan(&history, &store, &changed, None),
+            Plan::UpdateOf(c) if c.id == class_id
+        ));
+
+        // The class it went into was deleted
+        let orphan = ImportSource::from_bytes("old.csv", b"v0");
+        assert!(matches!(
+            plan(&history, &store, &orphan, None),
+            Plan::Import
+        ));
+
+        let failed = ImportHistoryStore {
+            reports: vec![ImportReport {
+                success: false,
+                ..report("r3", "3A.csv", b"v1", &class_id)
+            }],
+        };
+        assert!(matches!(plan(&failed, &store, &same, None), Plan::Import));
+    }
+}
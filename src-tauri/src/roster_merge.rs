@@ -0,0 +1,378 @@
+//! Merging a list of student records into a class
+//!
+//! Handles:
+//! - Matching the records to the class's students by name (see `fuzzy`):
+//!   a close spelling is a rename that keeps the student's id, absences
+//!   and notes
+//! - Two strategies: `replace` makes the class exactly the list (students
+//!   missing from it are removed), `append` only adds and renames
+//! - A dry run returning the exact changes, so the confirmation dialog can
+//!   say "3 added, 1 removed, 2 renamed" before anything is saved
+//!
+//! The dry run and the merge compute the same plan; the merge then saves
+//! it. Records come from the frontend (a pasted list, a colleague's
+//! export); roster files go through `roster_import`.
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::fuzzy;
+use crate::roster::{ClassData, ParsedRoster, RosterStore, Student, MAX_STUDENTS};
+use serde::{Deserialize, Serialize};
+
+/// What happens to students missing from the records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// They are removed: the class becomes the list
+    Replace,
+    /// They stay: the list only adds and renames
+    Append,
+}
+
+/// An incoming student
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeRecord {
+    pub name: String,
+    /// School email; replaces the student's when given
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// A student the merge removes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedStudent {
+    pub student_id: String,
+    pub name: String,
+}
+
+/// A student whose name the merge corrects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedStudent {
+    pub student_id: String,
+    pub from: String,
+    pub to: String,
+    /// Name similarity (0.0-1.0)
+    pub score: f64,
+}
+
+/// A student whose email the merge sets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailChange {
+    pub student_id: String,
+    pub name: String,
+    pub email: String,
+}
+
+/// Changes a merge makes to a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePreview {
+    pub class_id: String,
+    pub strategy: MergeStrategy,
+    pub added: Vec<String>,
+    pub removed: Vec<RemovedStudent>,
+    pub renamed: Vec<RenamedStudent>,
+    pub email_changes: Vec<EmailChange>,
+    /// Students left exactly as they are
+    pub unchanged: usize,
+    /// Validation problems; a merge with errors is refused
+    pub errors: Vec<String>,
+}
+
+impl MergePreview {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.email_changes.is_empty()
+    }
+}
+
+fn email_of(record: &MergeRecord) -> Option<&str> {
+    record
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+}
+
+/// Work out the changes and the student list after the merge
+///
+/// New students get ids from `now`, as in `RosterStore::apply_names`.
+fn plan(
+    class: &ClassData,
+    records: &[MergeRecord],
+    strategy: MergeStrategy,
+    now: u64,
+) -> (MergePreview, Vec<Student>) {
+    // Same name checks as a roster file; rejected rows are left out
+    let mut parsed = ParsedRoster::default();
+    let mut accepted: Vec<&MergeRecord> = Vec::new();
+    for (row, record) in records.iter().enumerate() {
+        let before = parsed.students.len();
+        parsed.push_name(row + 1, &record.name);
+        if parsed.students.len() > before {
+            accepted.push(record);
+        }
+    }
+
+    let existing: Vec<String> = class.students.iter().map(|s| s.name.clone()).collect();
+    let matches = fuzzy::match_names(&parsed.students, &existing, fuzzy::DEFAULT_THRESHOLD);
+
+    let mut preview = MergePreview {
+        class_id: class.id.clone(),
+        strategy,
+        added: Vec::new(),
+        removed: Vec::new(),
+        renamed: Vec::new(),
+        email_changes: Vec::new(),
+        unchanged: 0,
+        errors: parsed.errors,
+    };
+    // Merged students in record order, with the existing one they update
+    let mut results: Vec<(Option<usize>, Student)> = Vec::new();
+    for (i, ((name, record), m)) in parsed
+        .students
+        .iter()
+        .zip(accepted)
+        .zip(matches)
+        .enumerate()
+    {
+        let Some(pos) = m.target_index else {
+            preview.added.push(name.clone());
+            results.push((
+                None,
+                Student {
+                    id: format!("student_{}_{}", now, i),
+                    name: name.clone(),
+                    absent: false,
+                    notes: None,
+                    email: email_of(record).map(String::from),
+                    accommodations: Vec::new(),
+                },
+            ));
+            continue;
+        };
+        let mut student = class.students[pos].clone();
+        let mut changed = false;
+        // Equal after folding: the saved spelling stays
+        if !m.exact {
+            preview.renamed.push(RenamedStudent {
+                student_id: student.id.clone(),
+                from: student.name.clone(),
+                to: name.clone(),
+                score: m.score,
+            });
+            student.name = name.clone();
+            changed = true;
+        }
+        if let Some(email) = email_of(record) {
+            if student.email.as_deref() != Some(email) {
+                preview.email_changes.push(EmailChange {
+                    student_id: student.id.clone(),
+                    name: student.name.clone(),
+                    email: email.to_string(),
+                });
+                student.email = Some(email.to_string());
+                changed = true;
+            }
+        }
+        if !changed {
+            preview.unchanged += 1;
+        }
+        results.push((Some(pos), student));
+    }
+
+    let students: Vec<Student> = match strategy {
+        MergeStrategy::Replace => {
+            for (pos, student) in class.students.iter().enumerate() {
+                if !results.iter().any(|(p, _)| *p == Some(pos)) {
+                    preview.removed.push(RemovedStudent {
+                        student_id: student.id.clone(),
+                        name: student.name.clone(),
+                    });
+                }
+            }
+            // In list order, like an imported roster
+            results.into_iter().map(|(_, student)| student).collect()
+        }
+        MergeStrategy::Append => {
+            // Students missing from the list stay as they are
+            let matched = results.iter().filter(|(pos, _)| pos.is_some()).count();
+            preview.unchanged += class.students.len() - matched;
+            let mut students = class.students.clone();
+            let mut added = Vec::new();
+            for (pos, student) in results {
+                match pos {
+                    Some(pos) => students[pos] = student,
+                    None => added.push(student),
+                }
+            }
+            students.extend(added);
+            students
+        }
+    };
+
+    if strategy == MergeStrategy::Replace && students.is_empty() && preview.errors.is_empty() {
+        preview.errors.push("The list has no students".to_string());
+    }
+    if students.len() > MAX_STUDENTS {
+        preview.errors.push(format!(
+            "Too many students ({}). Maximum is {}.",
+            students.len(),
+            MAX_STUDENTS
+        ));
+    }
+    (preview, students)
+}
+
+fn class_not_found(class_id: &str) -> BackendError {
+    BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+        .with_details(class_id.to_string())
+}
+
+/// The changes merging `records` into a class would make, without saving
+pub fn preview_roster_merge(
+    class_id: &str,
+    records: &[MergeRecord],
+    strategy: MergeStrategy,
+) -> Result<MergePreview, BackendError> {
+    let store = RosterStore::load()?;
+    let class = store
+        .find(class_id)
+        .ok_or_else(|| class_not_found(class_id))?;
+    Ok(plan(class, records, strategy, clock::now_millis()).0)
+}
+
+/// Merge `records` into a class; returns the changes made
+///
+/// Refused when the records have validation errors.
+pub fn merge_roster(
+    class_id: &str,
+    records: &[MergeRecord],
+    strategy: MergeStrategy,
+) -> Result<MergePreview, BackendError> {
+    let mut store = RosterStore::load()?;
+    let now = clock::now_millis();
+    let class = store
+        .classes
+        .iter_mut()
+        .find(|c| c.id == class_id)
+        .ok_or_else(|| class_not_found(class_id))?;
+    let (preview, students) = plan(class, records, strategy, now);
+    if !preview.errors.is_empty() {
+        return Err(BackendError::new(
+            errors::roster::INVALID_ROSTER,
+            "Student list has validation errors",
+        )
+        .with_details(preview.errors.join("; ")));
+    }
+    if !preview.is_empty() {
+        class.students = students;
+        class.updated_at = now;
+        store.save()?;
+    }
+    Ok(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roster::ClassBuilder;
+
+    fn class(names: &[&str]) -> ClassData {
+        ClassBuilder::new("class_1", "3A")
+            .created_at(1)
+            .students(names)
+            .build()
+    }
+
+    fn records(names: &[&str]) -> Vec<MergeRecord> {
+        names
+            .iter()
+            .map(|name| MergeRecord {
+                name: name.to_string(),
+                email: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replace_plan() {
+        let class = class(&["Mario Rossi", "Anna Bianchi", "Luca Verdi", "Sara Neri"]);
+        let mut list = records(&["Giulia Russo", "mario rossi", "Anna Bianchini", "Sara Neri"]);
+        list[3].email = Some(" sara.neri@scuola.it ".into());
+        let (preview, students) = plan(&class, &list, MergeStrategy::Replace, 9);
+
+        assert_eq!(preview.added, ["Giulia Russo"]);
+        assert_eq!(
+            preview.removed,
+            [RemovedStudent {
+                student_id: "s2".into(),
+                name: "Luca Verdi".into()
+            }]
+        );
+        assert_eq!(preview.renamed.len(), 1);
+        assert_eq!(preview.renamed[0].student_id, "s1");
+        assert_eq!(preview.renamed[0].to, "Anna Bianchini");
+        assert_eq!(preview.email_changes[0].email, "sara.neri@scuola.it");
+        assert_eq!(preview.unchanged, 1);
+        assert!(preview.errors.is_empty());
+
+        // List order, existing ids and spelling kept on a folded match
+        let ids: Vec<&str> = students.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["student_9_0", "s0", "s1", "s3"]);
+        assert_eq!(students[1].name, "Mario Rossi");
+    }
+
+    #[test]
+    fn test_append_keeps_missing_students() {
+        let class = class(&["Mario Rossi", "Anna Bianchi"]);
+        let (preview, students) = plan(
+            &class,
+            &records(&["Luca Verdi", "Mario Rossi"]),
+            MergeStrategy::Append,
+            9,
+        );
+        assert_eq!(preview.added, ["Luca Verdi"]);
+        assert!(preview.removed.is_empty());
+        assert_eq!(preview.unchanged, 2);
+        let names: Vec<&str> = students.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Mario Rossi", "Anna Bianchi", "Luca Verdi"]);
+
+        let (same, _) = plan(
+            &class,
+            &records(&["Anna Bianchi"]),
+            MergeStrategy::Append,
+            9,
+        );
+        assert!(same.is_empty());
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let class = class(&["Mario Rossi"]);
+        let (preview, _) = plan(
+            &class,
+            &records(&["Anna Bianchi", "", "anna bianchi"]),
+            MergeStrategy::Replace,
+            9,
+        );
+        assert_eq!(preview.errors.len(), 2);
+        assert_eq!(preview.added, ["Anna Bianchi"]);
+
+        let (empty, _) = plan(&class, &[], MergeStrategy::Replace, 9);
+        assert_eq!(empty.errors, ["The list has no students"]);
+
+        let many: Vec<String> = (0..MAX_STUDENTS)
+            .map(|i| format!("Studente {}", i))
+            .collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        let (full, _) = plan(&class, &records(&many), MergeStrategy::Append, 9);
+        assert_eq!(full.errors.len(), 1);
+    }
+}
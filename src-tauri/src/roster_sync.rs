@@ -0,0 +1,447 @@
+//! Roster sync from a watched import folder
+//!
+//! Handles:
+//! - Configuring a folder (e.g. a shared drive where the secretary drops
+//!   updated class CSVs)
+//! - Polling it for new or changed roster files (CSV or registry
+//!   spreadsheets, see `file_ops::import_adapters`)
+//! - Validating them and computing a diff against the saved class
+//! - Emitting `roster-update-available` so the teacher can apply with one click
+//! - Queueing changed versions of roster files imported by hand (see
+//!   `roster_import`), so they go through the same review
+//!
+//! Polling is used instead of filesystem notifications because SMB/NFS
+//! shares do not deliver change events reliably. The class is matched by
+//! file name (`3A.csv` -> class "3A").
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::file_ops::import_adapters;
+use crate::file_ops::import_progress::NoProgress;
+use crate::fuzzy;
+use crate::import_history::{self, ImportKind, ImportSource};
+use crate::roster::{self, RosterStore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const FOLDER_CONFIG_KEY: &str = "roster_watch_folder";
+const STATE_COLLECTION: &str = "roster_sync";
+
+/// Event emitted for every new or changed roster file
+pub const UPDATE_EVENT: &str = "roster-update-available";
+
+/// How often the watched folder is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Larger files are ignored (a class roster is a few KB)
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Serializes scans from the watcher thread and manual commands
+static SCAN_LOCK: Mutex<()> = Mutex::new(());
+
+/// Changes a roster file would make to the saved class
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RosterDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl RosterDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A roster file waiting for the teacher to apply or dismiss it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterUpdate {
+    pub id: String,
+    pub file_name: String,
+    pub class_name: String,
+    /// Existing class with the same name; `None` creates a new class
+    pub class_id: Option<String>,
+    pub detected_at: u64,
+    pub students: Vec<String>,
+    /// Validation errors; their rows are left out when applied (see
+    /// `apply_update`)
+    pub errors: Vec<String>,
+    pub diff: RosterDiff,
+    /// SHA-256 of the file, recorded in the import history when applied
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SeenFile {
+    file_name: String,
+    hash: String,
+}
+
+/// Persisted watcher state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RosterSyncState {
+    #[serde(default)]
+    seen: Vec<SeenFile>,
+    #[serde(default)]
+    pub pending: Vec<RosterUpdate>,
+}
+
+impl RosterSyncState {
+    fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STATE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STATE_COLLECTION, self)
+    }
+
+    /// Record a file's content hash; returns false if it was already seen
+    fn mark_seen(&mut self, file_name: &str, hash: &str) -> bool {
+        match self.seen.iter_mut().find(|s| s.file_name == file_name) {
+            Some(seen) if seen.hash == hash => false,
+            Some(seen) => {
+                seen.hash = hash.to_string();
+                true
+            }
+            None => {
+                self.seen.push(SeenFile {
+                    file_name: file_name.to_string(),
+                    hash: hash.to_string(),
+                });
+                true
+            }
+        }
+    }
+
+    /// Check a folder and queue updates for new or changed files
+    ///
+    /// Returns only the updates produced by this scan.
+    pub fn scan(
+        &mut self,
+        folder: &Path,
+        store: &RosterStore,
+        now: u64,
+    ) -> Result<Vec<RosterUpdate>, BackendError> {
+        let mut files: Vec<_> = fs::read_dir(folder)?
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.metadata()
+                    .is_ok_and(|m| m.is_file() && m.len() <= MAX_FILE_SIZE)
+            })
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                    import_adapters::SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str())
+                })
+            })
+            .collect();
+        files.sort();
+
+        let mut updates = Vec::new();
+        for path in files {
+            let (Some(file_name), Some(class_name)) = (
+                path.file_name().and_then(|n| n.to_str()),
+                path.file_stem().and_then(|n| n.to_str()),
+            ) else {
+                continue;
+            };
+            // Files still being copied may fail to read; retry next poll
+            let Ok(bytes) = fs::read(&path) else { continue };
+            let hash = hex::encode(Sha256::digest(&bytes));
+            if !self.mark_seen(file_name, &hash) {
+                continue;
+            }
+
+            let mut update = build_update(file_name, class_name, &bytes, store, now);
+            update.hash = Some(hash);
+            self.pending.retain(|p| p.file_name != update.file_name);
+            // An identical roster needs no action
+            if update.class_id.is_some() && update.errors.is_empty() && update.diff.is_empty() {
+                continue;
+            }
+            self.pending.push(update.clone());
+            updates.push(update);
+        }
+        Ok(updates)
+    }
+
+    fn take_pending(&mut self, update_id: &str) -> Result<RosterUpdate, BackendError> {
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.id == update_id)
+            .ok_or_else(|| {
+                BackendError::new(errors::roster::UPDATE_NOT_FOUND, "Roster update not found")
+                    .with_details(update_id.to_string())
+            })?;
+        Ok(self.pending.remove(index))
+    }
+}
+
+/// Compare new student names with the saved class
+///
+/// Uses the same fuzzy matching as `RosterStore::apply_names`, so a
+/// corrected spelling is not reported as one student removed and one added.
+pub fn diff_roster(existing: &[roster::Student], names: &[String]) -> RosterDiff {
+    let existing_names: Vec<String> = existing.iter().map(|s| s.name.clone()).collect();
+    let matches = fuzzy::match_names(names, &existing_names, fuzzy::DEFAULT_THRESHOLD);
+    let added = matches
+        .iter()
+        .filter(|m| m.target.is_none())
+        .map(|m| m.candidate.clone())
+        .collect();
+    let removed: Vec<String> = existing_names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !matches.iter().any(|m| m.target_index == Some(*i)))
+        .map(|(_, name)| name.clone())
+        .collect();
+    RosterDiff {
+        added,
+        unchanged: existing.len() - removed.len(),
+        removed,
+    }
+}
+
+fn build_update(
+    file_name: &str,
+    class_name: &str,
+    bytes: &[u8],
+    store: &RosterStore,
+    now: u64,
+) -> RosterUpdate {
+    let parsed = match import_adapters::import_roster_bytes(bytes, &[], &NoProgress) {
+        Ok(import) => import.roster,
+        Err(e) => roster::ParsedRoster {
+            students: Vec::new(),
+            errors: vec![e.message],
+        },
+    };
+    let existing = store.find_by_name(class_name);
+    RosterUpdate {
+        id: format!("roster_update_{}_{}", now, file_name),
+        file_name: file_name.to_string(),
+        class_name: class_name.trim().to_string(),
+        class_id: existing.map(|c| c.id.clone()),
+        detected_at: now,
+        diff: diff_roster(existing.map_or(&[], |c| &c.students[..]), &parsed.students),
+        students: parsed.students,
+        errors: parsed.errors,
+        hash: None,
+    }
+}
+
+/// Queue a changed version of an imported roster file as a pending update
+/// of `class`, so the teacher reviews the differences before applying
+///
+/// Replaces a pending update for the same class.
+pub fn queue_update(
+    file_name: &str,
+    class: &roster::ClassData,
+    roster: roster::ParsedRoster,
+    hash: Option<String>,
+) -> Result<RosterUpdate, BackendError> {
+    let _guard = SCAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let now = clock::now_millis();
+    let update = RosterUpdate {
+        id: format!("roster_update_{}_{}", now, file_name),
+        file_name: file_name.to_string(),
+        class_name: class.name.clone(),
+        class_id: Some(class.id.clone()),
+        detected_at: now,
+        diff: diff_roster(&class.students, &roster.students),
+        students: roster.students,
+        errors: roster.errors,
+        hash,
+    };
+    let mut state = RosterSyncState::load()?;
+    state
+        .pending
+        .retain(|p| p.class_id.as_deref() != Some(class.id.as_str()));
+    state.pending.push(update.clone());
+    state.save()?;
+    Ok(update)
+}
+
+/// Configured watch folder, if any
+pub fn get_watch_folder() -> Option<String> {
+    file_ops::load_config(FOLDER_CONFIG_KEY)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+}
+
+/// Set (or clear with `None`) the watched folder
+///
+/// Changing the folder forgets previously seen files and pending updates.
+pub fn set_watch_folder(folder: Option<&str>) -> Result<(), BackendError> {
+    let _guard = SCAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let value = match folder.map(str::trim).filter(|f| !f.is_empty()) {
+        Some(folder) => {
+            if !Path::new(folder).is_dir() {
+                return Err(BackendError::new(
+                    errors::roster::FOLDER_NOT_FOUND,
+                    "Watched folder does not exist or is not reachable",
+                )
+                .with_details(folder.to_string()));
+            }
+            serde_json::Value::from(folder)
+        }
+        None => serde_json::Value::Null,
+    };
+    file_ops::save_config(FOLDER_CONFIG_KEY, value)?;
+    RosterSyncState::default().save()
+}
+
+/// Scan the watched folder now and emit an event per new update
+pub fn scan_now(app: &AppHandle) -> Result<Vec<RosterUpdate>, BackendError> {
+    let Some(folder) = get_watch_folder() else {
+        return Ok(Vec::new());
+    };
+    let folder = Path::new(&folder);
+    if !folder.is_dir() {
+        // Network share offline; try again on the next poll
+        return Err(BackendError::new(
+            errors::roster::FOLDER_NOT_FOUND,
+            "Watched folder is not reachable",
+        )
+        .with_details(folder.display().to_string()));
+    }
+
+    let _guard = SCAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let store = RosterStore::load()?;
+    let mut state = RosterSyncState::load()?;
+    let updates = state.scan(folder, &store, clock::now_millis())?;
+    state.save()?;
+
+    for update in &updates {
+        let _ = app.emit(UPDATE_EVENT, update);
+    }
+    Ok(updates)
+}
+
+/// Start the background thread polling the watched folder
+pub fn start_watcher(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        // Errors (share offline, unreadable file) are retried next poll
+        let _ = scan_now(&app);
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Updates waiting to be applied or dismissed
+pub fn get_pending_updates() -> Result<Vec<RosterUpdate>, BackendError> {
+    Ok(RosterSyncState::load()?.pending)
+}
+
+/// Apply a pending update to the saved class; returns the class id
+///
+/// Rows with errors are left out, or with `strict` reject the update (see
+/// `ParsedRoster::check`), which then stays pending.
+pub fn apply_update(update_id: &str, strict: bool) -> Result<String, BackendError> {
+    let _guard = SCAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = RosterSyncState::load()?;
+    let update = state.take_pending(update_id)?;
+    let roster = roster::ParsedRoster {
+        students: update.students.clone(),
+        errors: update.errors.clone(),
+    };
+    roster.check(strict)?;
+
+    let source = ImportSource {
+        file_name: update.file_name.clone(),
+        hash: update.hash.clone(),
+        size: None,
+    };
+    import_history::track(ImportKind::RosterUpdate, source, |history| {
+        history.describe_names(&roster);
+        let mut store = RosterStore::load()?;
+        // The class may have been created since the update was detected
+        let class_id = update
+            .class_id
+            .clone()
+            .or_else(|| store.find_by_name(&update.class_name).map(|c| c.id.clone()));
+        let id = store.apply_names(
+            class_id.as_deref(),
+            &update.class_name,
+            &update.students,
+            clock::now_millis(),
+        )?;
+        store.save()?;
+        state.save()?;
+        history.imported = update.students.len();
+        history.class_id = Some(id.clone());
+        Ok(id)
+    })
+}
+
+/// Discard a pending update (the file is not offered again until it changes)
+pub fn dismiss_update(update_id: &str) -> Result<(), BackendError> {
+    let _guard = SCAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = RosterSyncState::load()?;
+    state.take_pending(update_id)?;
+    state.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_detects_new_and_changed_files() {
+        let dir = TempDir::new().unwrap();
+        let mut store = RosterStore::default();
+        store
+            .apply_names(
+                None,
+                "3A",
+                &["Mario Rossi".into(), "Anna Bianchi".into()],
+                1,
+            )
+            .unwrap();
+        let mut state = RosterSyncState::default();
+
+        fs::write(dir.path().join("3A.csv"), "nome\nMario Rossi\nLuca Verdi\n").unwrap();
+        fs::write(dir.path().join("note.docx"), "ignored").unwrap();
+        let updates = state.scan(dir.path(), &store, 10).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].class_id.as_deref(), Some("class_1"));
+        assert_eq!(updates[0].diff.added, vec!["Luca Verdi"]);
+        assert_eq!(updates[0].diff.removed, vec!["Anna Bianchi"]);
+        assert_eq!(updates[0].diff.unchanged, 1);
+
+        // Unchanged file is not reported twice
+        assert!(state.scan(dir.path(), &store, 11).unwrap().is_empty());
+
+        // A changed file replaces the pending update
+        fs::write(dir.path().join("3A.csv"), "nome\nMario Rossi\n").unwrap();
+        assert_eq!(state.scan(dir.path(), &store, 12).unwrap().len(), 1);
+        assert_eq!(state.pending.len(), 1);
+        assert_eq!(state.pending[0].detected_at, 12);
+    }
+
+    #[test]
+    fn test_scan_reports_invalid_and_skips_identical() {
+        let dir = TempDir::new().unwrap();
+        let mut store = RosterStore::default();
+        store
+            .apply_names(None, "3A", &["Mario Rossi".into()], 1)
+            .unwrap();
+        let mut state = RosterSyncState::default();
+
+        fs::write(dir.path().join("3A.csv"), "nome\nmario rossi\n").unwrap();
+        fs::write(dir.path().join("4B.csv"), "nome\n\n,\n").unwrap();
+        let updates = state.scan(dir.path(), &store, 5).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].class_name, "4B");
+        assert!(updates[0].class_id.is_none());
+        assert!(!updates[0].errors.is_empty());
+    }
+}
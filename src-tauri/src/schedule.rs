@@ -0,0 +1,262 @@
+//! School bell schedule and period countdown
+//!
+//! Handles:
+//! - The weekly bell schedule (`bell_schedule` config key): lessons and
+//!   breaks with start/end times and the weekdays they apply to
+//! - Working out the current period and the time left until the bell
+//! - A low-frequency `period-countdown` event, so the main window, overlay
+//!   and projector all show the same "12 min to break"
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted every `COUNTDOWN_INTERVAL`
+pub const COUNTDOWN_EVENT: &str = "period-countdown";
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(30);
+const SCHEDULE_KEY: &str = "bell_schedule";
+
+pub const MAX_PERIODS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodKind {
+    Lesson,
+    Break,
+}
+
+/// One slot of the school day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BellPeriod {
+    pub label: String,
+    pub kind: PeriodKind,
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+    /// ISO weekdays (1 = Monday … 7 = Sunday)
+    pub weekdays: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BellSchedule {
+    pub periods: Vec<BellPeriod>,
+}
+
+/// A period resolved to today's date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodInfo {
+    pub label: String,
+    pub kind: PeriodKind,
+    /// Epoch millis
+    pub starts_at: u64,
+    pub ends_at: u64,
+}
+
+/// Time until the next bell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodCountdown {
+    pub current: Option<PeriodInfo>,
+    /// Next period today
+    pub next: Option<PeriodInfo>,
+    /// Until the end of `current`, or the start of `next` between periods
+    pub remaining_secs: Option<u64>,
+    /// `remaining_secs` rounded up, as displayed ("12 min")
+    pub remaining_minutes: Option<u64>,
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+fn validate(schedule: &BellSchedule) -> Result<(), BackendError> {
+    if schedule.periods.len() > MAX_PERIODS {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Too many periods in the schedule",
+        ));
+    }
+    for period in &schedule.periods {
+        let invalid = |msg: &str| {
+            Err(BackendError::new(errors::system::INVALID_INPUT, msg)
+                .with_details(period.label.clone()))
+        };
+        let (Some(start), Some(end)) = (parse_time(&period.start), parse_time(&period.end)) else {
+            return invalid("Times must be HH:MM");
+        };
+        if start >= end {
+            return invalid("A period must end after it starts");
+        }
+        if period.weekdays.is_empty() || period.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+            return invalid("Weekdays must be 1 (Monday) to 7 (Sunday)");
+        }
+    }
+    Ok(())
+}
+
+/// Load the bell schedule
+pub fn get_bell_schedule() -> BellSchedule {
+    file_ops::load_config(SCHEDULE_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Validate and save the bell schedule
+pub fn set_bell_schedule(schedule: BellSchedule) -> Result<BellSchedule, BackendError> {
+    validate(&schedule)?;
+    let value = serde_json::to_value(&schedule).map_err(|e| {
+        BackendError::new(
+            errors::system::UNKNOWN_ERROR,
+            "Failed to serialize schedule",
+        )
+        .with_details(e.to_string())
+    })?;
+    file_ops::save_config(SCHEDULE_KEY, value)?;
+    Ok(schedule)
+}
+
+fn to_millis(at: NaiveDateTime) -> u64 {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|dt| dt.timestamp_millis().max(0) as u64)
+        .unwrap_or(0)
+}
+
+/// Countdown at local time `now`
+pub fn countdown_at(schedule: &BellSchedule, now: NaiveDateTime) -> PeriodCountdown {
+    let today = now.date();
+    let weekday = today.weekday().number_from_monday() as u8;
+    let mut periods: Vec<(NaiveDateTime, NaiveDateTime, &BellPeriod)> = schedule
+        .periods
+        .iter()
+        .filter(|p| p.weekdays.contains(&weekday))
+        .filter_map(|p| {
+            Some((
+                today.and_time(parse_time(&p.start)?),
+                today.and_time(parse_time(&p.end)?),
+                p,
+            ))
+        })
+        .collect();
+    periods.sort_by_key(|(start, _, _)| *start);
+
+    let info = |(start, end, p): &(NaiveDateTime, NaiveDateTime, &BellPeriod)| PeriodInfo {
+        label: p.label.clone(),
+        kind: p.kind,
+        starts_at: to_millis(*start),
+        ends_at: to_millis(*end),
+    };
+    let current = periods
+        .iter()
+        .find(|(start, end, _)| *start <= now && now < *end);
+    let next = periods.iter().find(|(start, _, _)| *start > now);
+    let until = current
+        .map(|(_, end, _)| *end)
+        .or_else(|| next.map(|(start, _, _)| *start));
+    let remaining_secs = until.map(|at| (at - now).num_seconds().max(0) as u64);
+
+    PeriodCountdown {
+        current: current.map(info),
+        next: next.map(info),
+        remaining_secs,
+        remaining_minutes: remaining_secs.map(|s| s.div_ceil(60)),
+    }
+}
+
+/// Countdown right now
+pub fn get_time_remaining_in_period() -> PeriodCountdown {
+    countdown_at(&get_bell_schedule(), Local::now().naive_local())
+}
+
+/// Start emitting `period-countdown`
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let _ = app.emit(COUNTDOWN_EVENT, get_time_remaining_in_period());
+        std::thread::sleep(COUNTDOWN_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn period(label: &str, kind: PeriodKind, start: &str, end: &str) -> BellPeriod {
+        BellPeriod {
+            label: label.to_string(),
+            kind,
+            start: start.to_string(),
+            end: end.to_string(),
+            weekdays: vec![1, 2, 3, 4, 5],
+        }
+    }
+
+    fn schedule() -> BellSchedule {
+        BellSchedule {
+            periods: vec![
+                period("Intervallo", PeriodKind::Break, "10:50", "11:00"),
+                period("1ª ora", PeriodKind::Lesson, "08:00", "08:55"),
+                period("2ª ora", PeriodKind::Lesson, "08:55", "09:50"),
+            ],
+        }
+    }
+
+    // 2024-03-04 is a Monday
+    fn at(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_countdown_during_period() {
+        let countdown = countdown_at(&schedule(), at(8, 42, 30));
+        assert_eq!(countdown.current.unwrap().label, "1ª ora");
+        assert_eq!(countdown.next.unwrap().label, "2ª ora");
+        assert_eq!(countdown.remaining_secs, Some(12 * 60 + 30));
+        assert_eq!(countdown.remaining_minutes, Some(13));
+    }
+
+    #[test]
+    fn test_countdown_between_periods_and_after_school() {
+        let countdown = countdown_at(&schedule(), at(10, 0, 0));
+        assert!(countdown.current.is_none());
+        assert_eq!(countdown.next.unwrap().kind, PeriodKind::Break);
+        assert_eq!(countdown.remaining_minutes, Some(50));
+
+        let countdown = countdown_at(&schedule(), at(14, 0, 0));
+        assert!(countdown.current.is_none() && countdown.next.is_none());
+        assert_eq!(countdown.remaining_secs, None);
+
+        // Saturday
+        let saturday = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(8, 30, 0)
+            .unwrap();
+        assert!(countdown_at(&schedule(), saturday).current.is_none());
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        assert!(validate(&schedule()).is_ok());
+        let mut bad = schedule();
+        bad.periods[0].end = "10:40".to_string();
+        assert!(validate(&bad).is_err());
+        let mut bad = schedule();
+        bad.periods[0].weekdays = vec![0];
+        assert!(validate(&bad).is_err());
+        let mut bad = schedule();
+        bad.periods[0].start = "8".to_string();
+        assert!(validate(&bad).is_err());
+    }
+}
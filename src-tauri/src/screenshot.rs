@@ -0,0 +1,310 @@
+//! Window screenshots for lesson records
+//!
+//! Handles:
+//! - Capturing one app window (typically the projector view with the
+//!   leaderboard or seating chart) as a PNG
+//! - The screen-capture permission check (see `permissions`)
+//! - Default file names under `screenshots/` in the config directory
+//!
+//! Platform capture:
+//! - Windows: `PrintWindow` on the window handle, which also works when
+//!   the window is covered
+//! - macOS: `screencapture` on the window's screen area
+//! - Linux: `grim` (Wayland) or ImageMagick `import` (X11) on the window's
+//!   screen area
+//!
+//! On macOS and Linux the window must be visible and uncovered.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::permissions;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// A saved screenshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotInfo {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Window area on screen, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CaptureRect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+fn capture_error(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::window::CAPTURE_FAILED, message).with_details(details.to_string())
+}
+
+/// `screenshots/<label>-<timestamp>.png` in the config directory
+fn default_path(label: &str) -> Result<PathBuf, BackendError> {
+    let safe_label: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    Ok(file_ops::get_config_dir()?
+        .join(SCREENSHOTS_DIR)
+        .join(format!(
+            "{}-{}.png",
+            safe_label,
+            Local::now().format("%Y%m%d-%H%M%S")
+        )))
+}
+
+fn resolve_path(label: &str, path: Option<&str>) -> Result<PathBuf, BackendError> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => default_path(label)?,
+    };
+    let is_png = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    if !is_png {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Screenshot path must end in .png",
+        )
+        .with_details(path.to_string_lossy().to_string()));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+fn window_rect(window: &WebviewWindow) -> Result<CaptureRect, BackendError> {
+    let position = window
+        .outer_position()
+        .map_err(|e| capture_error("Failed to read window position", e))?;
+    let size = window
+        .outer_size()
+        .map_err(|e| capture_error("Failed to read window size", e))?;
+    if size.width == 0 || size.height == 0 {
+        return Err(capture_error("Window is not visible", window.label()));
+    }
+    Ok(CaptureRect {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+/// Capture a window and save it as PNG
+///
+/// `path` defaults to `screenshots/<label>-<timestamp>.png` in the config
+/// directory.
+pub fn capture_window_screenshot(
+    app: &AppHandle,
+    window_label: &str,
+    path: Option<&str>,
+) -> Result<ScreenshotInfo, BackendError> {
+    let window = app.get_webview_window(window_label).ok_or_else(|| {
+        BackendError::new(errors::window::NOT_FOUND, "Window not found")
+            .with_details(window_label.to_string())
+    })?;
+
+    let permission = permissions::request_screen_capture_permission()?;
+    if !permission.available {
+        return Err(BackendError::new(
+            errors::permission::SCREEN_CAPTURE_UNAVAILABLE,
+            permission.message,
+        ));
+    }
+    if !permission.granted {
+        let error = BackendError::new(
+            errors::permission::SCREEN_CAPTURE_DENIED,
+            permission.message,
+        );
+        return Err(match permission.details {
+            Some(details) => error.with_details(details),
+            None => error,
+        });
+    }
+
+    let path = resolve_path(window_label, path)?;
+    capture(&window, &path)?;
+    let (width, height) =
+        image::image_dimensions(&path).map_err(|e| capture_error("Screenshot was not saved", e))?;
+    Ok(ScreenshotInfo {
+        path: path.to_string_lossy().to_string(),
+        width,
+        height,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn capture(window: &WebviewWindow, path: &Path) -> Result<(), BackendError> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS};
+
+    /// Include DirectComposition content (the WebView2 surface)
+    const PW_RENDERFULLCONTENT: u32 = 2;
+
+    let rect = window_rect(window)?;
+    let hwnd = HWND(
+        window
+            .hwnd()
+            .map_err(|e| capture_error("Failed to get window handle", e))?
+            .0,
+    );
+    let (width, height) = (rect.width as i32, rect.height as i32);
+    let mut pixels = vec![0u8; rect.width as usize * rect.height as usize * 4];
+
+    // SAFETY: every GDI object created here is selected out and released
+    // before returning; `pixels` holds width * height 32-bit pixels as
+    // described by `info`
+    let (printed, lines) = unsafe {
+        let window_dc = GetDC(hwnd);
+        let memory_dc = CreateCompatibleDC(window_dc);
+        let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+        let printed = PrintWindow(hwnd, memory_dc, PRINT_WINDOW_FLAGS(PW_RENDERFULLCONTENT));
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height: rows top to bottom
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let lines = GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            rect.height,
+            Some(pixels.as_mut_ptr().cast()),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+        SelectObject(memory_dc, previous);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(hwnd, window_dc);
+        (printed.as_bool(), lines)
+    };
+    if !printed || lines == 0 {
+        return Err(capture_error("Window capture failed", "PrintWindow"));
+    }
+
+    // GDI gives BGRA with an undefined alpha channel
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        pixel[3] = 255;
+    }
+    image::RgbaImage::from_raw(rect.width, rect.height, pixels)
+        .ok_or_else(|| capture_error("Window capture failed", "buffer size"))?
+        .save(path)
+        .map_err(|e| capture_error("Failed to save screenshot", e))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn capture(window: &WebviewWindow, path: &Path) -> Result<(), BackendError> {
+    let rect = window_rect(window)?;
+    let scale = window
+        .scale_factor()
+        .map_err(|e| capture_error("Failed to read window scale", e))?;
+    let (program, args) = capture_command(rect, scale, path);
+    let output = std::process::Command::new(program)
+        .args(&args)
+        .output()
+        .map_err(|e| capture_error("Screen capture tool failed to start", e))?;
+    if !output.status.success() {
+        return Err(capture_error(
+            "Screen capture failed",
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    Ok(())
+}
+
+/// Capture tool invocation for a screen area
+#[cfg(target_os = "macos")]
+fn capture_command(rect: CaptureRect, scale: f64, path: &Path) -> (&'static str, Vec<String>) {
+    // screencapture works in points
+    let points = |px: f64| (px / scale).round() as i64;
+    let region = format!(
+        "{},{},{},{}",
+        points(f64::from(rect.x)),
+        points(f64::from(rect.y)),
+        points(f64::from(rect.width)),
+        points(f64::from(rect.height))
+    );
+    let path = path.to_string_lossy().to_string();
+    (
+        "screencapture",
+        vec!["-x".into(), "-R".into(), region, path],
+    )
+}
+
+/// Capture tool invocation for a screen area
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn capture_command(rect: CaptureRect, _scale: f64, path: &Path) -> (&'static str, Vec<String>) {
+    let path = path.to_string_lossy().to_string();
+    match permissions::linux_capture_tool() {
+        "grim" => {
+            let region = format!("{},{} {}x{}", rect.x, rect.y, rect.width, rect.height);
+            ("grim", vec!["-g".into(), region, path])
+        }
+        _ => {
+            let crop = format!("{}x{}+{}+{}", rect.width, rect.height, rect.x, rect.y);
+            (
+                "import",
+                vec!["-window".into(), "root".into(), "-crop".into(), crop, path],
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("records").join("3A.PNG");
+        let resolved = resolve_path("projector", Some(target.to_str().unwrap())).unwrap();
+        assert_eq!(resolved, target);
+        assert!(target.parent().unwrap().is_dir());
+        assert!(resolve_path("projector", Some("lesson.jpg")).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_x11_capture_command() {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return;
+        }
+        let rect = CaptureRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 720,
+        };
+        let (program, args) = capture_command(rect, 1.0, Path::new("/tmp/s.png"));
+        assert_eq!(program, "import");
+        assert_eq!(args[3], "1280x720+1920+0");
+    }
+}
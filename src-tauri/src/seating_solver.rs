@@ -0,0 +1,477 @@
+//! Seating chart suggestions
+//!
+//! Handles:
+//! - Arranging a class on a grid under constraints: pairs kept apart (not
+//!   in neighbouring seats, diagonals included), students needing the
+//!   front row, and optionally rows balanced by level (the gradebook's
+//!   weighted average, see `gradebook`)
+//! - Ranking distinct candidate layouts, best first
+//!
+//! Accommodations (see `accommodations`) are always part of the
+//! constraints. The search restarts from random layouts and improves each
+//! by moving students one at a time; trying every arrangement of 30 seats
+//! is out of the question. A candidate that still breaks a rule says which
+//! in `violations`: it is shown, but `save_seating_chart` refuses it.
+//!
+//! Empty seats are left at the back. Row 0 is the front row.
+
+use crate::accommodations::{self, AccommodationKind};
+use crate::class_records::{Seat, SeatingChart};
+use crate::errors::{self, BackendError};
+use crate::gradebook::{self, AverageStrategy, GradeStore};
+use crate::roster::{ClassData, RosterStore, Student};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const MAX_GRID_SIDE: u32 = 12;
+pub const MAX_CANDIDATES: usize = 10;
+const DEFAULT_CANDIDATES: usize = 3;
+
+const RESTARTS: usize = 24;
+const MOVES_PER_RESTART: usize = 1500;
+/// Cost of a broken rule; outweighs any balance difference
+const VIOLATION_COST: f64 = 1000.0;
+/// Cost per row of distance from the front, per student
+const BACK_ROW_COST: f64 = 0.001;
+
+/// What the layout must respect, on top of the accommodations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeatingConstraints {
+    pub rows: u32,
+    pub columns: u32,
+    /// Student id pairs not seated next to each other
+    pub keep_apart: Vec<(String, String)>,
+    /// Students seated in the front row
+    pub front_row: Vec<String>,
+    /// Make the average level of every row as even as possible
+    pub balance_by_level: bool,
+    /// Layouts returned (default 3)
+    pub candidates: Option<usize>,
+}
+
+/// A suggested layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatingCandidate {
+    pub chart: SeatingChart,
+    /// Rules the layout still breaks; empty for a usable layout
+    pub violations: Vec<String>,
+    /// Difference between the best and worst row average, when balancing
+    pub level_spread: Option<f64>,
+}
+
+/// Small deterministic generator, seeded from the OS for real searches
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn os_seed() -> Result<u64, BackendError> {
+    let mut buf = [0u8; 8];
+    getrandom::fill(&mut buf).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Random source unavailable")
+            .with_details(e.to_string())
+    })?;
+    // xorshift never leaves zero
+    Ok(u64::from_le_bytes(buf) | 1)
+}
+
+/// The class reduced to indexes
+struct Problem<'a> {
+    rows: u32,
+    columns: u32,
+    students: &'a [Student],
+    levels: Vec<Option<f64>>,
+    front: Vec<bool>,
+    apart: Vec<(usize, usize)>,
+    balance: bool,
+}
+
+/// A layout: the seat (`row * columns + column`) of every student
+type Layout = Vec<usize>;
+
+impl Problem<'_> {
+    fn seats(&self) -> usize {
+        (self.rows * self.columns) as usize
+    }
+
+    fn row(&self, seat: usize) -> u32 {
+        seat as u32 / self.columns
+    }
+
+    fn adjacent(&self, a: usize, b: usize) -> bool {
+        let (ra, ca) = (a as u32 / self.columns, a as u32 % self.columns);
+        let (rb, cb) = (b as u32 / self.columns, b as u32 % self.columns);
+        ra.abs_diff(rb) <= 1 && ca.abs_diff(cb) <= 1
+    }
+
+    fn violations(&self, layout: &[usize]) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (i, &seat) in layout.iter().enumerate() {
+            if self.front[i] && self.row(seat) != 0 {
+                violations.push(format!("{} is not in the front row", self.students[i].name));
+            }
+        }
+        for &(a, b) in &self.apart {
+            if self.adjacent(layout[a], layout[b]) {
+                violations.push(format!(
+                    "{} and {} are seated together",
+                    self.students[a].name, self.students[b].name
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Difference between the highest and lowest row average
+    fn level_spread(&self, layout: &[usize]) -> Option<f64> {
+        let mut rows: HashMap<u32, (f64, usize)> = HashMap::new();
+        for (i, &seat) in layout.iter().enumerate() {
+            if let Some(level) = self.levels[i] {
+                let row = rows.entry(self.row(seat)).or_default();
+                row.0 += level;
+                row.1 += 1;
+            }
+        }
+        let means = rows.values().map(|(sum, n)| sum / *n as f64);
+        let (min, max) = means.fold((f64::MAX, f64::MIN), |(lo, hi), m| (lo.min(m), hi.max(m)));
+        (min <= max).then_some(max - min)
+    }
+
+    fn cost(&self, layout: &[usize]) -> f64 {
+        let broken = self
+            .front
+            .iter()
+            .zip(layout)
+            .filter(|(&front, &seat)| front && self.row(seat) != 0)
+            .count()
+            + self
+                .apart
+                .iter()
+                .filter(|&&(a, b)| self.adjacent(layout[a], layout[b]))
+                .count();
+        let spread = if self.balance {
+            self.level_spread(layout).unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        let depth: u32 = layout.iter().map(|&seat| self.row(seat)).sum();
+        broken as f64 * VIOLATION_COST + spread + depth as f64 * BACK_ROW_COST
+    }
+
+    /// Random layout with front-row students already in front when possible
+    fn random_layout(&self, rng: &mut Rng) -> Layout {
+        let mut free: Vec<usize> = (0..self.seats()).collect();
+        let mut layout = vec![0; self.students.len()];
+        let (front, others): (Vec<usize>, Vec<usize>) =
+            (0..self.students.len()).partition(|&i| self.front[i]);
+        for i in front.into_iter().chain(others) {
+            let in_front: Vec<usize> = (0..free.len())
+                .filter(|&k| self.row(free[k]) == 0)
+                .collect();
+            let k = if self.front[i] && !in_front.is_empty() {
+                in_front[rng.below(in_front.len())]
+            } else {
+                rng.below(free.len())
+            };
+            layout[i] = free.swap_remove(k);
+        }
+        layout
+    }
+
+    /// Move students (swapping with whoever sits there) while it doesn't
+    /// make the layout worse
+    fn improve(&self, layout: &mut Layout, rng: &mut Rng) -> f64 {
+        let mut cost = self.cost(layout);
+        for _ in 0..MOVES_PER_RESTART {
+            let student = rng.below(layout.len());
+            let seat = rng.below(self.seats());
+            let from = layout[student];
+            let other = layout.iter().position(|&s| s == seat);
+            layout[student] = seat;
+            if let Some(other) = other {
+                layout[other] = from;
+            }
+            let moved = self.cost(layout);
+            if moved <= cost {
+                cost = moved;
+            } else {
+                layout[student] = from;
+                if let Some(other) = other {
+                    layout[other] = seat;
+                }
+            }
+        }
+        cost
+    }
+
+    /// Distinct layouts found, cheapest first
+    fn solve(&self, rng: &mut Rng, count: usize) -> Vec<Layout> {
+        let mut found: Vec<(f64, Layout)> = Vec::new();
+        for _ in 0..RESTARTS {
+            let mut layout = self.random_layout(rng);
+            let cost = self.improve(&mut layout, rng);
+            if !found.iter().any(|(_, l)| *l == layout) {
+                found.push((cost, layout));
+            }
+        }
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found.into_iter().take(count).map(|(_, l)| l).collect()
+    }
+
+    fn candidate(&self, class_id: &str, layout: &[usize]) -> SeatingCandidate {
+        let mut seats: Vec<Seat> = layout
+            .iter()
+            .zip(self.students)
+            .map(|(&seat, student)| Seat {
+                student_id: student.id.clone(),
+                row: self.row(seat),
+                column: seat as u32 % self.columns,
+            })
+            .collect();
+        seats.sort_by_key(|s| (s.row, s.column));
+        SeatingCandidate {
+            chart: SeatingChart {
+                class_id: class_id.to_string(),
+                rows: self.rows,
+                columns: self.columns,
+                seats,
+                updated_at: 0,
+            },
+            violations: self.violations(layout),
+            level_spread: if self.balance {
+                self.level_spread(layout)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+fn invalid_input(message: &str, details: impl ToString) -> BackendError {
+    BackendError::new(errors::system::INVALID_INPUT, message).with_details(details.to_string())
+}
+
+/// Turn the class and constraints into a problem
+fn problem<'a>(
+    class: &'a ClassData,
+    constraints: &SeatingConstraints,
+    levels: &HashMap<String, f64>,
+) -> Result<Problem<'a>, BackendError> {
+    let (rows, columns) = (constraints.rows, constraints.columns);
+    if !(1..=MAX_GRID_SIDE).contains(&rows) || !(1..=MAX_GRID_SIDE).contains(&columns) {
+        return Err(invalid_input(
+            "Rows and columns must be between 1 and 12",
+            format!("{}x{}", rows, columns),
+        ));
+    }
+    let students = &class.students;
+    if students.is_empty() || students.len() > (rows * columns) as usize {
+        return Err(invalid_input(
+            "The grid must have a seat for every student",
+            format!("{} students, {} seats", students.len(), rows * columns),
+        ));
+    }
+    let index = |id: &str| {
+        students
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| invalid_input("Student is not in this class", id))
+    };
+
+    let mut front: Vec<bool> = students
+        .iter()
+        .map(|s| {
+            s.accommodations
+                .iter()
+                .any(|a| a.kind == AccommodationKind::FrontSeat)
+        })
+        .collect();
+    for id in &constraints.front_row {
+        front[index(id)?] = true;
+    }
+    if front.iter().filter(|&&f| f).count() > columns as usize {
+        return Err(invalid_input(
+            "More students need the front row than it has seats",
+            columns,
+        ));
+    }
+
+    let mut apart: Vec<(usize, usize)> = Vec::new();
+    for (i, a) in students.iter().enumerate() {
+        for (j, b) in students.iter().enumerate().skip(i + 1) {
+            if accommodations::kept_apart(a, b) {
+                apart.push((i, j));
+            }
+        }
+    }
+    for (a, b) in &constraints.keep_apart {
+        let (a, b) = (index(a)?, index(b)?);
+        if a == b {
+            return Err(invalid_input(
+                "A student can't be kept apart from themselves",
+                &students[a].id,
+            ));
+        }
+        let pair = (a.min(b), a.max(b));
+        if !apart.contains(&pair) {
+            apart.push(pair);
+        }
+    }
+
+    Ok(Problem {
+        rows,
+        columns,
+        students,
+        levels: students
+            .iter()
+            .map(|s| levels.get(&s.id).copied())
+            .collect(),
+        front,
+        apart,
+        balance: constraints.balance_by_level,
+    })
+}
+
+/// Weighted average of every student of the class with scores
+fn class_levels(class: &ClassData) -> Result<HashMap<String, f64>, BackendError> {
+    let scores = GradeStore::load()?.for_class(&class.id);
+    Ok(class
+        .students
+        .iter()
+        .filter_map(|student| {
+            let own: Vec<_> = scores
+                .iter()
+                .filter(|s| s.student_id == student.id)
+                .cloned()
+                .collect();
+            gradebook::average(&student.id, &own, AverageStrategy::Weighted)
+                .value
+                .map(|value| (student.id.clone(), value))
+        })
+        .collect())
+}
+
+/// Suggest seating charts for a class, best first
+pub fn suggest_seating(
+    class_id: &str,
+    constraints: &SeatingConstraints,
+) -> Result<Vec<SeatingCandidate>, BackendError> {
+    let class = RosterStore::load()?
+        .find(class_id)
+        .cloned()
+        .ok_or_else(|| {
+            BackendError::new(errors::roster::CLASS_NOT_FOUND, "Class not found")
+                .with_details(class_id.to_string())
+        })?;
+    let count = constraints.candidates.unwrap_or(DEFAULT_CANDIDATES);
+    if !(1..=MAX_CANDIDATES).contains(&count) {
+        return Err(invalid_input("Candidates must be between 1 and 10", count));
+    }
+    let levels = if constraints.balance_by_level {
+        class_levels(&class)?
+    } else {
+        HashMap::new()
+    };
+    let problem = problem(&class, constraints, &levels)?;
+    let mut rng = Rng(os_seed()?);
+    Ok(problem
+        .solve(&mut rng, count)
+        .iter()
+        .map(|layout| problem.candidate(class_id, layout))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accommodations::Accommodation;
+    use crate::roster::ClassBuilder;
+
+    fn class(count: usize) -> ClassData {
+        (0..count)
+            .fold(
+                ClassBuilder::new("class_1", "3A").created_at(1),
+                |class, i| class.student(&format!("s{}", i), &format!("Studente {}", i)),
+            )
+            .build()
+    }
+
+    fn grid(rows: u32, columns: u32) -> SeatingConstraints {
+        SeatingConstraints {
+            rows,
+            columns,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_layouts_respect_constraints() {
+        let mut class = class(6);
+        class.students[0].accommodations = vec![Accommodation {
+            kind: AccommodationKind::FrontSeat,
+            details: None,
+        }];
+        class.students[3].accommodations = vec![Accommodation {
+            kind: AccommodationKind::KeepApart {
+                student_id: "s4".into(),
+            },
+            details: None,
+        }];
+        let constraints = SeatingConstraints {
+            keep_apart: vec![("s1".into(), "s2".into())],
+            ..grid(2, 4)
+        };
+        let problem = problem(&class, &constraints, &HashMap::new()).unwrap();
+        assert_eq!(problem.apart, [(3, 4), (1, 2)]);
+
+        let layouts = problem.solve(&mut Rng(42), 3);
+        assert_eq!(layouts.len(), 3);
+        let best = problem.candidate("class_1", &layouts[0]);
+        assert!(best.violations.is_empty(), "{:?}", best.violations);
+        assert!(accommodations::check_seating(&best.chart, &class).is_ok());
+        // Empty seats are at the back
+        assert_eq!(best.chart.seats.iter().filter(|s| s.row == 0).count(), 4);
+    }
+
+    #[test]
+    fn test_balance_and_validation() {
+        let class = class(4);
+        let levels: HashMap<String, f64> = [("s0", 9.0), ("s1", 9.0), ("s2", 4.0), ("s3", 4.0)]
+            .iter()
+            .map(|(id, level)| (id.to_string(), *level))
+            .collect();
+        let constraints = SeatingConstraints {
+            balance_by_level: true,
+            ..grid(2, 2)
+        };
+        let problem = problem(&class, &constraints, &levels).unwrap();
+        let best = problem.candidate("class_1", &problem.solve(&mut Rng(7), 1)[0]);
+        assert_eq!(best.level_spread, Some(0.0));
+
+        assert!(super::problem(&class, &grid(1, 3), &levels).is_err());
+        assert!(super::problem(&class, &grid(0, 4), &levels).is_err());
+        let crowded = SeatingConstraints {
+            front_row: vec!["s0".into(), "s1".into(), "s2".into()],
+            ..grid(2, 2)
+        };
+        assert!(super::problem(&class, &crowded, &levels).is_err());
+        let unknown = SeatingConstraints {
+            keep_apart: vec![("s0".into(), "s9".into())],
+            ..grid(2, 2)
+        };
+        assert!(super::problem(&class, &unknown, &levels).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! OS keychain access for credentials
+//!
+//! Passwords and API keys (WebDAV, S3, backup passphrase) never go into
+//! `app_config.json`; they are stored in the platform credential store:
+//! - Windows: Credential Manager
+//! - macOS: Keychain
+//! - Linux: Secret Service (GNOME Keyring / KWallet)
+
+use crate::errors::{self, BackendError};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+const KEYCHAIN_SERVICE: &str = "com.classroom.management";
+
+const MIN_PIN_DIGITS: usize = 4;
+const MAX_PIN_DIGITS: usize = 12;
+
+fn entry(name: &str) -> Result<keyring::Entry, BackendError> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name).map_err(|e| {
+        BackendError::new(
+            errors::backup::SECRET_STORE_ERROR,
+            "Failed to access the system keychain",
+        )
+        .with_details(e.to_string())
+    })
+}
+
+/// Store a secret under a name
+pub fn set_secret(name: &str, value: &str) -> Result<(), BackendError> {
+    entry(name)?.set_password(value).map_err(|e| {
+        BackendError::new(errors::backup::SECRET_STORE_ERROR, "Failed to store secret")
+            .with_details(e.to_string())
+    })
+}
+
+/// Read a secret; `Ok(None)` if it was never stored
+pub fn get_secret(name: &str) -> Result<Option<String>, BackendError> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(BackendError::new(
+            errors::backup::SECRET_STORE_ERROR,
+            "Failed to read secret",
+        )
+        .with_details(e.to_string())),
+    }
+}
+
+/// Remove a secret (missing secrets are not an error)
+pub fn delete_secret(name: &str) -> Result<(), BackendError> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(BackendError::new(
+            errors::backup::SECRET_STORE_ERROR,
+            "Failed to delete secret",
+        )
+        .with_details(e.to_string())),
+    }
+}
+
+/// PINs are 4 to 12 digits
+pub fn validate_pin(pin: &str) -> Result<(), BackendError> {
+    if (MIN_PIN_DIGITS..=MAX_PIN_DIGITS).contains(&pin.len())
+        && pin.chars().all(|c| c.is_ascii_digit())
+    {
+        Ok(())
+    } else {
+        Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!(
+                "The PIN must be {} to {} digits",
+                MIN_PIN_DIGITS, MAX_PIN_DIGITS
+            ),
+        ))
+    }
+}
+
+/// Argon2 hash of a PIN (PHC string), for storing with `set_secret`
+///
+/// Short numeric PINs are cheap to brute-force from a leaked hash; Argon2
+/// makes every guess cost real memory and time.
+pub fn hash_pin(pin: &str) -> Result<String, BackendError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to hash PIN")
+                .with_details(e.to_string())
+        })
+}
+
+/// Check a PIN against a `hash_pin` value
+pub fn verify_pin(stored: &str, pin: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(pin.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_hashing() {
+        let stored = hash_pin("2468").unwrap();
+        assert!(verify_pin(&stored, "2468"));
+        assert!(!verify_pin(&stored, "2469"));
+        assert!(!verify_pin("garbage", "2468"));
+        assert_ne!(hash_pin("2468").unwrap(), stored);
+
+        assert!(validate_pin("1234").is_ok());
+        assert!(validate_pin("123").is_err());
+        assert!(validate_pin("12a4").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Settings reset and factory reset
+//!
+//! Handles:
+//! - Resetting a single setting to its default
+//! - Factory reset of all settings, optionally keeping rosters and other data
+//! - A safety backup (`pre-reset`) before anything is removed
+//!
+//! A factory reset needs a short-lived confirmation token from
+//! `request_factory_reset`, so a stray call cannot wipe the app. It also
+//! deletes the PINs and account passwords in the OS keychain, except the
+//! backup passphrase, which is needed to restore the safety backup.
+
+use crate::app_lock;
+use crate::attachments;
+use crate::backup::{self, BackupInfo};
+use crate::clock;
+use crate::cloud;
+use crate::cloud_s3;
+use crate::controller;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::mailer;
+use crate::observer;
+use crate::photos;
+use crate::research_export;
+use crate::roles;
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Mutex;
+
+/// How long a confirmation token stays valid
+const TOKEN_TTL_MS: u64 = 2 * 60 * 1000;
+
+/// Outstanding confirmation token
+static PENDING_TOKEN: Mutex<Option<ResetToken>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+struct ResetToken {
+    token: String,
+    expires_at: u64,
+}
+
+impl ResetToken {
+    fn matches(&self, token: &str, now: u64) -> bool {
+        now <= self.expires_at && self.token == token
+    }
+}
+
+/// Result of a reset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetResult {
+    /// Backup of the state before the reset
+    pub backup: BackupInfo,
+    /// Config keys, data collections, folders and keychain entries that
+    /// were removed
+    pub removed: Vec<String>,
+    /// Keychain entries left in place
+    #[serde(default)]
+    pub kept: Vec<String>,
+}
+
+/// Reset one setting to its default
+///
+/// Returns `None` when the key was not set (nothing to back up or remove).
+pub fn reset_setting(key: &str) -> Result<Option<ResetResult>, BackendError> {
+    if file_ops::load_config(key)?.is_null() {
+        return Ok(None);
+    }
+    let backup = backup::create_local_backup("pre-reset")?;
+    file_ops::remove_config(key)?;
+    Ok(Some(ResetResult {
+        backup,
+        removed: vec![key.to_string()],
+        kept: Vec::new(),
+    }))
+}
+
+/// Keychain entries a factory reset deletes, and those it keeps
+///
+/// The backup passphrase is always kept: it opens the safety backup. With
+/// `keep_data`, the profile PINs and the research export salt stay with
+/// the profiles and pseudonyms they belong to.
+fn keychain_entries(keep_data: bool) -> Result<(Vec<String>, Vec<String>), BackendError> {
+    let mut delete: Vec<String> = [
+        app_lock::PIN_SECRET,
+        observer::PIN_SECRET,
+        controller::TOKEN_SECRET,
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect();
+    delete.extend(
+        [
+            cloud::configured_secret(),
+            cloud_s3::configured_secret(),
+            mailer::configured_secret(),
+        ]
+        .into_iter()
+        .flatten(),
+    );
+    let mut data = roles::pin_secrets()?;
+    data.push(research_export::SALT_SECRET.to_string());
+
+    let mut kept = vec![backup::PASSPHRASE_SECRET.to_string()];
+    if keep_data {
+        kept.extend(data);
+    } else {
+        delete.extend(data);
+    }
+    Ok((delete, kept))
+}
+
+/// Issue a confirmation token for `factory_reset`
+pub fn request_factory_reset() -> Result<String, BackendError> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to generate token")
+            .with_details(e.to_string())
+    })?;
+    let token = hex::encode(bytes);
+    *PENDING_TOKEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(ResetToken {
+        token: token.clone(),
+        expires_at: clock::now_millis() + TOKEN_TTL_MS,
+    });
+    Ok(token)
+}
+
+/// Restore all settings to defaults
+///
+/// With `keep_data`, rosters, exit tickets and other data collections,
+/// photos and attachments are kept and only the config file and the
+/// settings' keychain entries are removed (see `keychain_entries`).
+/// Backups are never deleted.
+pub fn factory_reset(confirm_token: &str, keep_data: bool) -> Result<ResetResult, BackendError> {
+    {
+        // The token is single-use, even when the reset fails afterwards
+        let pending = PENDING_TOKEN
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if !pending.is_some_and(|t| t.matches(confirm_token, clock::now_millis())) {
+            return Err(BackendError::new(
+                errors::config::INVALID_CONFIRMATION,
+                "Reset confirmation is missing or expired",
+            ));
+        }
+    }
+
+    let backup = backup::create_local_backup("pre-reset")?;
+    // Account entries are named in the config, so read them first
+    let (secrets_to_delete, kept) = keychain_entries(keep_data)?;
+    let config_dir = file_ops::get_config_dir()?;
+    let mut removed = Vec::new();
+
+    let config_path = config_dir.join(file_ops::CONFIG_FILENAME);
+    if config_path.exists() {
+        fs::remove_file(&config_path)?;
+        removed.push(file_ops::CONFIG_FILENAME.to_string());
+    }
+
+    let data_dir = config_dir.join(file_ops::DATA_DIR);
+    if !keep_data && data_dir.exists() {
+        for entry in fs::read_dir(&data_dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                fs::remove_file(&path)?;
+                removed.push(format!(
+                    "{}/{}",
+                    file_ops::DATA_DIR,
+                    entry.file_name().to_string_lossy()
+                ));
+            }
+        }
+    }
+
+    if !keep_data {
+        for dir in [photos::PHOTOS_DIR, attachments::STORAGE_DIR] {
+            let path = config_dir.join(dir);
+            if path.exists() {
+                fs::remove_dir_all(&path)?;
+                removed.push(format!("{}/", dir));
+            }
+        }
+    }
+
+    for name in secrets_to_delete {
+        if secrets::get_secret(&name)?.is_some() {
+            secrets::delete_secret(&name)?;
+            removed.push(format!("keychain:{}", name));
+        }
+    }
+
+    // Cached from what was just removed
+    roles::reset_active_role();
+    app_lock::reset_state();
+
+    removed.sort();
+    Ok(ResetResult {
+        backup,
+        removed,
+        kept: kept
+            .into_iter()
+            .map(|name| format!("keychain:{}", name))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches_only_before_expiry() {
+        let token = ResetToken {
+            token: "abc".into(),
+            expires_at: 1_000,
+        };
+        assert!(token.matches("abc", 1_000));
+        assert!(!token.matches("abc", 1_001));
+        assert!(!token.matches("abd", 500));
+    }
+
+    #[test]
+    fn test_factory_reset_requires_token() {
+        let err = factory_reset("not-a-token", true).unwrap_err();
+        assert_eq!(err.code, errors::config::INVALID_CONFIRMATION);
+    }
+}
@@ -0,0 +1,200 @@
+//! Orderly shutdown and detection of killed sessions
+//!
+//! Handles:
+//! - A session marker (`session.json`, next to the config file) written
+//!   at startup with `endedAt: null`
+//! - On exit requested (last window closed, quit from the menu):
+//!   - stopping native audio streams and background music
+//!   - ending the noise session, writing its pending samples
+//!   - writing pending lesson state (see `recovery`)
+//!   - closing the controller listener and the kiosk page
+//!   - stopping the ID card scanner, which releases the camera, and the
+//!     badge reader listener
+//!   - marking the session as ended
+//! - Telling the next run whether the previous one got there
+//!   (`get_previous_session`); when it didn't (force-killed, power cut,
+//!   crash), the lesson state in `recovery` is from up to a few seconds
+//!   before and the noise samples of the last minute are lost
+//!
+//! The config cache writes through on every save and data collections are
+//! plain JSON files without a write-ahead log, so neither has anything to
+//! flush.
+
+use crate::background_audio;
+use crate::badge_reader;
+use crate::badge_scan;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::noise_history;
+use crate::recovery;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const MARKER_FILENAME: &str = "session.json";
+
+/// Start of this session, `None` until the marker is written
+static STARTED_AT: Mutex<Option<u64>> = Mutex::new(None);
+/// Set by the first exit request
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+/// Marker left by the previous run, read once at startup
+static PREVIOUS: Mutex<Option<SessionMarker>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMarker {
+    pub started_at: u64,
+    /// Set by an orderly shutdown
+    pub ended_at: Option<u64>,
+}
+
+/// How the previous run ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousSession {
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    /// Whether it shut down in order; `false` after a kill or crash
+    pub clean: bool,
+}
+
+fn marker_path() -> Result<PathBuf, BackendError> {
+    Ok(file_ops::get_config_dir()?.join(MARKER_FILENAME))
+}
+
+/// A missing or unreadable marker counts as no previous session
+fn read_marker(path: &Path) -> Option<SessionMarker> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_marker(path: &Path, marker: &SessionMarker) -> Result<(), BackendError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(marker).map_err(|e| {
+        BackendError::new(errors::file::IO_ERROR, "Failed to serialize session marker")
+            .with_details(e.to_string())
+    })?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Pick up the previous run's marker and mark this session as running
+pub fn start() {
+    let path = match marker_path() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Session marker: {}", e.message);
+            return;
+        }
+    };
+    *PREVIOUS.lock().unwrap_or_else(|e| e.into_inner()) = read_marker(&path);
+    let now = clock::now_millis();
+    let marker = SessionMarker {
+        started_at: now,
+        ended_at: None,
+    };
+    match write_marker(&path, &marker) {
+        Ok(()) => *STARTED_AT.lock().unwrap_or_else(|e| e.into_inner()) = Some(now),
+        Err(e) => eprintln!("Session marker: {}", e.message),
+    }
+}
+
+/// How the previous run ended, `None` on first launch
+pub fn get_previous_session() -> Option<PreviousSession> {
+    PREVIOUS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|marker| PreviousSession {
+            started_at: marker.started_at,
+            ended_at: marker.ended_at,
+            clean: marker.ended_at.is_some(),
+        })
+}
+
+fn log_step(step: &str, result: Result<(), BackendError>) {
+    if let Err(e) = result {
+        eprintln!("Shutdown, {}: {}", step, e);
+    }
+}
+
+/// Stop and flush every subsystem, then mark the session as ended
+///
+/// Runs once; later exit requests find nothing left to do. A step that
+/// fails is logged and the others still run.
+pub fn shutdown(app: &AppHandle) {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let state = app.state::<AppState>();
+    state.stop_all_audio_streams();
+    log_step(
+        "background audio",
+        background_audio::stop_background_audio(app, None),
+    );
+    log_step("noise session", noise_history::set_noise_context(None));
+    log_step("lesson state", recovery::flush_pending());
+    state.controller().stop();
+    state.kiosk().stop();
+    badge_scan::stop_badge_scan();
+    badge_reader::stop();
+    // No marker when it couldn't be written at startup, or the checks
+    // failed before `start`
+    let Some(started_at) = *STARTED_AT.lock().unwrap_or_else(|e| e.into_inner()) else {
+        return;
+    };
+    let marker = SessionMarker {
+        started_at,
+        ended_at: Some(clock::now_millis()),
+    };
+    log_step(
+        "session marker",
+        marker_path().and_then(|path| write_marker(&path, &marker)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MARKER_FILENAME);
+        assert_eq!(read_marker(&path), None);
+        let running = SessionMarker {
+            started_at: 10,
+            ended_at: None,
+        };
+        write_marker(&path, &running).unwrap();
+        assert_eq!(read_marker(&path), Some(running));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        fs::write(&path, "{\"startedAt\": 1").unwrap();
+        assert_eq!(read_marker(&path), None);
+    }
+
+    #[test]
+    fn test_previous_session_is_clean_when_ended() {
+        *PREVIOUS.lock().unwrap() = Some(SessionMarker {
+            started_at: 10,
+            ended_at: None,
+        });
+        assert!(!get_previous_session().unwrap().clean);
+        *PREVIOUS.lock().unwrap() = Some(SessionMarker {
+            started_at: 10,
+            ended_at: Some(20),
+        });
+        assert!(get_previous_session().unwrap().clean);
+        *PREVIOUS.lock().unwrap() = None;
+    }
+}
@@ -0,0 +1,170 @@
+//! Units sound levels are shown in
+//!
+//! Handles:
+//! - The setting (`sound_unit` config key): percent of the meter (default),
+//!   dBFS, or an approximate dB SPL
+//! - Converting meter levels (0-100) for what teachers read: `noise-level`
+//!   events, the day overview, class comparisons and the research export
+//!
+//! Levels stay on the meter scale everywhere else: noise history, light
+//! thresholds, insights and threshold suggestions are all in meter points,
+//! so changing the unit never moves a threshold.
+//!
+//! The meter maps -100..-10 dBFS linearly onto 0-100 (as the frontend
+//! does), so every conversion is linear and converting a mean or a
+//! percentile gives the mean or percentile of the converted levels.
+//! dB SPL is dBFS plus a typical laptop-mic reference, corrected by the
+//! `noise_calibration` offset once the room has been calibrated; without a
+//! calibration it can be off by 10 dB or more.
+
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use serde::{Deserialize, Serialize};
+
+const CONFIG_KEY: &str = "sound_unit";
+const CALIBRATION_KEY: &str = "noise_calibration";
+
+/// dBFS at meter level 0 and 100
+pub const MIN_DBFS: f64 = -100.0;
+pub const MAX_DBFS: f64 = -10.0;
+/// dB SPL of a 0 dBFS signal on a typical built-in mic
+const SPL_REFERENCE: f64 = 120.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundUnit {
+    /// 0-100% of the meter
+    #[default]
+    Percent,
+    Dbfs,
+    /// Approximate sound pressure level
+    Dbspl,
+}
+
+impl SoundUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            SoundUnit::Percent => "%",
+            SoundUnit::Dbfs => "dBFS",
+            SoundUnit::Dbspl => "dB SPL",
+        }
+    }
+}
+
+/// The unit setting with what the frontend needs to label values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoundUnitInfo {
+    pub unit: SoundUnit,
+    pub label: String,
+    /// Whether dB SPL values use a room calibration
+    pub calibrated: bool,
+}
+
+/// Unit and calibration at one point, for converting many levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelScale {
+    pub unit: SoundUnit,
+    /// `noise_calibration.offsetDb`, `None` when not calibrated
+    pub spl_offset: Option<f64>,
+}
+
+impl Default for LevelScale {
+    fn default() -> Self {
+        Self {
+            unit: SoundUnit::Percent,
+            spl_offset: None,
+        }
+    }
+}
+
+impl LevelScale {
+    /// Current setting and calibration
+    pub fn current() -> Self {
+        let spl_offset = file_ops::load_config(CALIBRATION_KEY)
+            .ok()
+            .and_then(|v| v.get("offsetDb").and_then(|o| o.as_f64()));
+        Self {
+            unit: get_sound_unit(),
+            spl_offset,
+        }
+    }
+
+    /// A meter level in this unit, to one decimal
+    pub fn convert(&self, level: f64) -> f64 {
+        let dbfs = MIN_DBFS + level / 100.0 * (MAX_DBFS - MIN_DBFS);
+        let value = match self.unit {
+            SoundUnit::Percent => level,
+            SoundUnit::Dbfs => dbfs,
+            SoundUnit::Dbspl => dbfs + SPL_REFERENCE + self.spl_offset.unwrap_or(0.0),
+        };
+        (value * 10.0).round() / 10.0
+    }
+
+    pub fn convert_opt(&self, level: Option<f64>) -> Option<f64> {
+        level.map(|l| self.convert(l))
+    }
+}
+
+pub fn get_sound_unit() -> SoundUnit {
+    file_ops::load_config(CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn get_sound_unit_info() -> SoundUnitInfo {
+    let scale = LevelScale::current();
+    SoundUnitInfo {
+        unit: scale.unit,
+        label: scale.unit.label().to_string(),
+        calibrated: scale.spl_offset.is_some(),
+    }
+}
+
+pub fn set_sound_unit(unit: SoundUnit) -> Result<SoundUnitInfo, BackendError> {
+    let value = serde_json::to_value(unit).map_err(|e| {
+        BackendError::new(errors::system::UNKNOWN_ERROR, "Failed to serialize config")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(CONFIG_KEY, value)?;
+    Ok(get_sound_unit_info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let percent = LevelScale::default();
+        assert_eq!(percent.convert(42.0), 42.0);
+        let dbfs = LevelScale {
+            unit: SoundUnit::Dbfs,
+            spl_offset: None,
+        };
+        assert_eq!(dbfs.convert(0.0), MIN_DBFS);
+        assert_eq!(dbfs.convert(100.0), MAX_DBFS);
+        assert_eq!(dbfs.convert(50.0), -55.0);
+        let spl = LevelScale {
+            unit: SoundUnit::Dbspl,
+            spl_offset: None,
+        };
+        assert_eq!(spl.convert(50.0), 65.0);
+        let calibrated = LevelScale {
+            unit: SoundUnit::Dbspl,
+            spl_offset: Some(-7.5),
+        };
+        assert_eq!(calibrated.convert(50.0), 57.5);
+        assert_eq!(calibrated.convert_opt(None), None);
+    }
+
+    #[test]
+    fn test_unit_setting_format() {
+        let value = serde_json::to_value(SoundUnit::Dbspl).unwrap();
+        assert_eq!(value, serde_json::json!("dbspl"));
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &value).is_ok());
+        let unknown = serde_json::json!("phon");
+        assert!(file_ops::config_schema::validate(CONFIG_KEY, &unknown).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! Startup self-test
+//!
+//! Handles:
+//! - Quick checks run in `setup` while the main window is still hidden
+//!   (it starts with `visible: false`, see `tauri.conf.json`):
+//!   - config: the settings file can be read (a damaged one is repaired by
+//!     `config_repair` on this first read)
+//!   - data: the data directory exists (or can be created) and is writable
+//!   - collections: every data collection parses
+//!   - audio: an output device is available
+//!   - page, sounds: the frontend and its sound packs are in the build
+//! - Showing the main window when nothing failed, or else a minimal
+//!   diagnostic window with the structured errors. That window is plain
+//!   HTML with no frontend code, so it shows even when the frontend is
+//!   what's broken.
+//! - Keeping the report for `get_startup_report`, so the UI can show the
+//!   warnings
+//!
+//! Only config, data and page can fail the start, and data only when the
+//! directory can't be created. A directory that exists but can't be
+//! written is a warning: the app opens in read-only mode (see
+//! `read_only_mode`). Damaged collections are a warning too: restoring a
+//! backup is done from the app, which has to open for it.
+
+use crate::audio_output;
+use crate::clock;
+use crate::data_integrity;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::locale::{self, Language};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const DIAGNOSTIC_LABEL: &str = "startup-diagnostic";
+const MAIN_LABEL: &str = "main";
+const PROBE_FILE: &str = ".startup-probe";
+
+/// Bundled page the app can't start without
+const PAGE_ASSETS: &[&str] = &["index.html"];
+/// Sound packs played by the frontend (`SOUND_PACKS` in `audioStore.ts`)
+const SOUND_ASSETS: &[&str] = &[
+    "sounds/classic/timer-end.wav",
+    "sounds/classic/attention.wav",
+    "sounds/classic/transition.wav",
+    "sounds/modern/timer-end.wav",
+    "sounds/modern/attention.wav",
+    "sounds/modern/transition.wav",
+    "sounds/gentle/timer-end.wav",
+    "sounds/gentle/attention.wav",
+    "sounds/gentle/transition.wav",
+];
+
+static REPORT: Mutex<Option<StartupReport>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    /// The app starts, with something not working
+    Warning,
+    /// The app can't start
+    Failed,
+}
+
+/// Result of one check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupCheck {
+    /// "config", "data", "collections", "audio", "page" or "sounds"
+    pub name: String,
+    pub status: CheckStatus,
+    pub error: Option<BackendError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupReport {
+    pub checked_at: u64,
+    /// No check failed (warnings allowed)
+    pub ok: bool,
+    pub checks: Vec<StartupCheck>,
+}
+
+impl StartupReport {
+    fn new(checks: Vec<StartupCheck>) -> Self {
+        Self {
+            checked_at: clock::now_millis(),
+            ok: checks.iter().all(|c| c.status != CheckStatus::Failed),
+            checks,
+        }
+    }
+}
+
+fn check(name: &str, result: Result<(), BackendError>, on_error: CheckStatus) -> StartupCheck {
+    let (status, error) = match result {
+        Ok(()) => (CheckStatus::Passed, None),
+        Err(e) => (on_error, Some(e)),
+    };
+    StartupCheck {
+        name: name.to_string(),
+        status,
+        error,
+    }
+}
+
+/// Any key reads (and if needed repairs) the whole file
+fn check_config() -> Result<(), BackendError> {
+    file_ops::load_config("window_config").map(|_| ())
+}
+
+/// Create the data directory
+fn create_data_dir(dir: &Path) -> Result<(), BackendError> {
+    let data_dir = dir.join(file_ops::DATA_DIR);
+    fs::create_dir_all(&data_dir).map_err(|e| {
+        BackendError::new(
+            errors::file::IO_ERROR,
+            "The data directory could not be created",
+        )
+        .with_details(format!("{}: {}", data_dir.display(), e))
+    })
+}
+
+/// Write a file in the data directory
+fn check_writable(dir: &Path) -> Result<(), BackendError> {
+    let data_dir = dir.join(file_ops::DATA_DIR);
+    let probe = data_dir.join(PROBE_FILE);
+    fs::write(&probe, b"ok")
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| {
+            BackendError::new(errors::file::IO_ERROR, "The data directory is not writable")
+                .with_details(format!("{}: {}", data_dir.display(), e))
+        })
+}
+
+/// A directory that can't be created stops the app; one that exists but
+/// can't be written only warns, and read-only mode (`read_only_mode`)
+/// takes over
+fn check_data(dir: &Path) -> StartupCheck {
+    match create_data_dir(dir) {
+        Ok(()) => check("data", check_writable(dir), CheckStatus::Warning),
+        Err(e) => check("data", Err(e), CheckStatus::Failed),
+    }
+}
+
+fn check_collections(dir: &Path) -> Result<(), BackendError> {
+    let mut issues = Vec::new();
+    data_integrity::check_data(dir, &mut issues);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let files: Vec<String> = issues.into_iter().map(|i| i.target).collect();
+    Err(BackendError::new(
+        errors::file::INVALID_FORMAT,
+        "Some data files are damaged; run Troubleshoot to restore a backup",
+    )
+    .with_details(files.join(", ")))
+}
+
+fn check_audio() -> Result<(), BackendError> {
+    if audio_output::list_audio_output_devices()?.is_empty() {
+        return Err(BackendError::new(
+            errors::audio::OUTPUT_UNAVAILABLE,
+            "No audio output device",
+        ));
+    }
+    Ok(())
+}
+
+/// `required` paths missing from the bundled assets
+fn check_assets(bundled: &HashSet<String>, required: &[&str]) -> Result<(), BackendError> {
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|path| !bundled.contains(*path))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(
+        BackendError::new(errors::file::NOT_FOUND, "Files are missing from the build")
+            .with_details(missing.join(", ")),
+    )
+}
+
+/// Run the checks and keep the report for `get_startup_report`
+pub fn run_checks(app: &AppHandle) -> StartupReport {
+    let mut checks = vec![check("config", check_config(), CheckStatus::Failed)];
+    match file_ops::get_config_dir() {
+        Ok(dir) => {
+            checks.push(check_data(&dir));
+            checks.push(check(
+                "collections",
+                check_collections(&dir),
+                CheckStatus::Warning,
+            ));
+        }
+        Err(e) => checks.push(check("data", Err(e), CheckStatus::Failed)),
+    }
+    checks.push(check("audio", check_audio(), CheckStatus::Warning));
+    // In development the page comes from the dev server, not the build
+    if !tauri::is_dev() {
+        let bundled: HashSet<String> = app
+            .asset_resolver()
+            .iter()
+            .map(|(path, _)| path.trim_start_matches('/').to_string())
+            .collect();
+        checks.push(check(
+            "page",
+            check_assets(&bundled, PAGE_ASSETS),
+            CheckStatus::Failed,
+        ));
+        checks.push(check(
+            "sounds",
+            check_assets(&bundled, SOUND_ASSETS),
+            CheckStatus::Warning,
+        ));
+    }
+
+    let report = StartupReport::new(checks);
+    for check in &report.checks {
+        if let Some(e) = &check.error {
+            eprintln!("Startup check {}: {}", check.name, e);
+        }
+    }
+    *REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(report.clone());
+    report
+}
+
+/// Report of this run's checks
+pub fn get_startup_report() -> Option<StartupReport> {
+    REPORT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Page listing the checks that didn't pass
+fn diagnostic_page(report: &StartupReport, data_dir: &str, lang: Language) -> String {
+    let title = lang.pick("L'app non può avviarsi", "The app could not start");
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\"><head><meta charset=\"utf-8\">\
+         <title>{}</title>\
+         <style>body{{font-family:sans-serif;margin:24px}}code{{color:#555}}\
+         .failed{{color:#b71c1c}}.warning{{color:#e65100}}</style>\
+         </head><body>\n<h1>{}</h1>\n<p>{}</p>\n<ul>\n",
+        lang.code(),
+        title,
+        title,
+        lang.pick(
+            "Questi controlli non sono riusciti. Inviali al supporto tecnico.",
+            "These checks did not pass. Send them to technical support.",
+        )
+    );
+    for check in &report.checks {
+        let Some(error) = &check.error else {
+            continue;
+        };
+        let status = match check.status {
+            CheckStatus::Failed => "failed",
+            _ => "warning",
+        };
+        html.push_str(&format!(
+            "<li class=\"{}\"><b>{}</b>: {} <code>[{}]</code>",
+            status,
+            escape_html(&check.name),
+            escape_html(&error.message),
+            escape_html(&error.code)
+        ));
+        if let Some(details) = &error.details {
+            html.push_str(&format!("<br><code>{}</code>", escape_html(details)));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str(&format!(
+        "</ul>\n<p>{} <code>{}</code></p>\n</body></html>\n",
+        lang.pick("Cartella dei dati:", "Data folder:"),
+        escape_html(data_dir)
+    ));
+    html
+}
+
+/// Show the main window once the checks passed
+pub fn show_main(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            eprintln!("Main window not shown: {}", e);
+        }
+    }
+}
+
+/// Replace the hidden main window with the diagnostic window
+pub fn show_diagnostics(app: &AppHandle, report: &StartupReport) -> Result<(), BackendError> {
+    let data_dir = file_ops::get_config_dir()
+        .map(|d| d.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let lang = locale::app_language();
+    let page = diagnostic_page(report, &data_dir, lang);
+    let url = format!(
+        "data:text/html;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(page)
+    );
+    let window_error = |e: String| {
+        BackendError::new(
+            errors::window::CREATE_FAILED,
+            "Failed to open the diagnostic window",
+        )
+        .with_details(e)
+    };
+    let url = tauri::Url::parse(&url).map_err(|e| window_error(e.to_string()))?;
+    WebviewWindowBuilder::new(app, DIAGNOSTIC_LABEL, WebviewUrl::External(url))
+        .title(lang.pick("Classroom – Diagnostica", "Classroom – Diagnostics"))
+        .inner_size(640.0, 480.0)
+        .build()
+        .map_err(|e| window_error(e.to_string()))?;
+    // A hidden main window would keep the app running after this one closes
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.destroy();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join(file_ops::DATA_DIR);
+        assert_eq!(check_data(dir.path()).status, CheckStatus::Passed);
+        assert!(!data_dir.join(PROBE_FILE).exists());
+        assert!(check_collections(dir.path()).is_ok());
+
+        fs::write(data_dir.join("roster.json"), "{ \"classes\": [").unwrap();
+        let error = check_collections(dir.path()).unwrap_err();
+        assert_eq!(error.details.as_deref(), Some("roster.json"));
+
+        // A file where the data directory should be: it can't be created
+        let blocked = tempfile::tempdir().unwrap();
+        fs::write(blocked.path().join(file_ops::DATA_DIR), "").unwrap();
+        let failed = check_data(blocked.path());
+        assert_eq!(failed.status, CheckStatus::Failed);
+        assert_eq!(failed.error.unwrap().code, errors::file::IO_ERROR);
+
+        // Exists but can't be written: only a warning, read-only mode follows
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let locked = tempfile::tempdir().unwrap();
+            let data_dir = locked.path().join(file_ops::DATA_DIR);
+            fs::create_dir(&data_dir).unwrap();
+            fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o555)).unwrap();
+            // Root can write anyway
+            if fs::write(data_dir.join("probe"), "").is_err() {
+                assert_eq!(check_data(locked.path()).status, CheckStatus::Warning);
+            }
+            fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_report_and_page() {
+        let bundled: HashSet<String> = ["index.html", "sounds/classic/timer-end.wav"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(check_assets(&bundled, PAGE_ASSETS).is_ok());
+        let missing = check_assets(&bundled, SOUND_ASSETS).unwrap_err().details;
+        assert!(missing.unwrap().starts_with("sounds/classic/attention.wav"));
+
+        let warned = StartupReport::new(vec![
+            check("config", Ok(()), CheckStatus::Failed),
+            check(
+                "sounds",
+                check_assets(&bundled, SOUND_ASSETS),
+                CheckStatus::Warning,
+            ),
+        ]);
+        assert!(warned.ok);
+        let failed = StartupReport::new(vec![check(
+            "data",
+            Err(BackendError::new(errors::file::IO_ERROR, "<denied>")),
+            CheckStatus::Failed,
+        )]);
+        assert!(!failed.ok);
+        let page = diagnostic_page(&failed, "/data", Language::En);
+        assert!(page.contains("&lt;denied&gt;"));
+        assert!(page.contains("class=\"failed\""));
+        assert!(!page.contains("config"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Shared application state
+//!
+//! Registered with `.manage()` in `lib.rs` and injected into commands as
+//! `tauri::State<AppState>`. It holds state that belongs to the running
+//! app and is only reached through it:
+//! - Handles of running native audio streams, so they can be stopped
+//! - The LAN kiosk page and the local controller listener, so commands
+//!   and shutdown can stop them
+//! - Read-only mode, entered when the data directory isn't writable (see
+//!   `read_only_mode`)
+//!
+//! Deliberately not here: the config file and data collections. Commands,
+//! background threads and modules that never see an `AppHandle` read them
+//! the same way, through `file_ops`, which keeps their parsed copies in
+//! memory (`config_cache`, `data_cache`). There is no database pool to
+//! share.
+
+use crate::audio_supervisor::StreamHandle;
+use crate::controller::ControllerListener;
+use crate::kiosk::KioskServer;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub struct AppState {
+    kiosk: KioskServer,
+    controller: ControllerListener,
+    audio_streams: Mutex<HashMap<String, StreamHandle>>,
+    /// Why the app is read-only, `None` when writable
+    read_only: Mutex<Option<String>>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self {
+            kiosk: KioskServer::new(),
+            controller: ControllerListener::new(),
+            audio_streams: Mutex::new(HashMap::new()),
+            read_only: Mutex::new(None),
+        }
+    }
+
+    pub fn kiosk(&self) -> &KioskServer {
+        &self.kiosk
+    }
+
+    pub fn controller(&self) -> &ControllerListener {
+        &self.controller
+    }
+
+    /// Keep a running audio stream, stopping any previous one of that name
+    pub fn register_audio_stream(&self, name: &str, handle: StreamHandle) {
+        let previous = self
+            .audio_streams
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.to_string(), handle);
+        if let Some(previous) = previous {
+            previous.stop();
+        }
+    }
+
+    /// Stop a running audio stream; returns whether it was running
+    pub fn stop_audio_stream(&self, name: &str) -> bool {
+        let handle = self
+            .audio_streams
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name);
+        handle.map(StreamHandle::stop).is_some()
+    }
+
+    /// Stop every running audio stream (at shutdown)
+    pub fn stop_all_audio_streams(&self) {
+        let streams: Vec<StreamHandle> = self
+            .audio_streams
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+            .map(|(_, handle)| handle)
+            .collect();
+        for handle in streams {
+            handle.stop();
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only_reason().is_some()
+    }
+
+    /// Why data can't be written, `None` when it can
+    pub fn read_only_reason(&self) -> Option<String> {
+        self.read_only
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Enter read-only mode (`Some(reason)`) or leave it; returns whether
+    /// the mode changed
+    pub fn set_read_only(&self, reason: Option<String>) -> bool {
+        let mut current = self.read_only.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = current.is_some() != reason.is_some();
+        *current = reason;
+        changed
+    }
+
+    /// Names of audio streams whose supervisor is still running
+    pub fn active_audio_streams(&self) -> Vec<String> {
+        let mut streams = self.audio_streams.lock().unwrap_or_else(|e| e.into_inner());
+        streams.retain(|_, handle| !handle.is_finished());
+        let mut names: Vec<String> = streams.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,520 @@
+//! Substitute teacher packs
+//!
+//! Handles:
+//! - A redacted pack for a day the regular teacher is away: the class's
+//!   seating chart with first names only, the bell schedule of that
+//!   weekday and the key accommodations without their details
+//! - Keeping the latest pack per class and date (`substitute_packs` data
+//!   collection), optionally printed to a PDF
+//! - What a `substitute` profile may reach (see `roles`): the packs and
+//!   the classroom tools that show no student data
+//!
+//! Nothing else about the students goes into a pack: no surnames (two
+//! students sharing a first name get their surname's initial), notes,
+//! emails, grades or behavior.
+
+use crate::accommodations::AccommodationKind;
+use crate::class_records::{self, SeatingChart};
+use crate::clock;
+use crate::documents::pdf::TextPdf;
+use crate::errors::{self, BackendError};
+use crate::file_ops;
+use crate::locale::{self, DateStyle, Language};
+use crate::roster::{ClassData, Student};
+use crate::schedule::{self, BellSchedule, PeriodKind};
+use chrono::{Datelike, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const COLLECTION: &str = "substitute_packs";
+/// Packs kept, newest first
+const MAX_PACKS: usize = 20;
+
+/// The only commands a `substitute` profile may call
+pub const SUBSTITUTE_COMMANDS: &[&str] = &[
+    "get_substitute_packs",
+    // Getting back to the regular teacher's profile
+    "list_profiles",
+    "set_active_profile",
+    // Classroom tools without student data
+    "get_classroom_state",
+    "set_classroom_state",
+    "report_noise_level",
+    "get_input_mute_state",
+    "get_bell_schedule",
+    "get_time_remaining_in_period",
+    "create_timer_sequence",
+    "skip_phase",
+    "get_sequence_state",
+    "stop_timer_sequence",
+    "dim_projector",
+    "undim_projector",
+    "get_projector_dim",
+    "broadcast_freeze",
+    "release_freeze",
+    "get_freeze_status",
+    "set_presentation_safe",
+    "get_presentation_safe",
+    // App lock and locale
+    "lock_app",
+    "unlock_app",
+    "get_app_lock_status",
+    "get_app_language",
+    "get_system_locale",
+    "format_date",
+    "format_number",
+    "localize_error",
+];
+
+/// How student names are written in the roster
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameOrder {
+    /// "Mario Rossi"
+    #[default]
+    GivenFirst,
+    /// "Rossi Mario", as in school registers
+    SurnameFirst,
+}
+
+/// A student as the substitute sees them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackStudent {
+    pub first_name: String,
+    /// Accommodation labels in the app language, without details
+    pub accommodations: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackSeat {
+    pub first_name: String,
+    pub row: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackSeating {
+    pub rows: u32,
+    pub columns: u32,
+    pub seats: Vec<PackSeat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackPeriod {
+    pub label: String,
+    pub kind: PeriodKind,
+    /// "HH:MM"
+    pub start: String,
+    pub end: String,
+}
+
+/// Everything a substitute gets about a class for a day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubstitutePack {
+    pub class_id: String,
+    pub class_name: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub generated_at: u64,
+    pub students: Vec<PackStudent>,
+    /// `None` when the class has no seating chart
+    pub seating: Option<PackSeating>,
+    /// Periods of the date's weekday, in order
+    pub schedule: Vec<PackPeriod>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubstitutePackStore {
+    pub packs: Vec<SubstitutePack>,
+}
+
+impl SubstitutePackStore {
+    pub fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(COLLECTION, self)
+    }
+
+    /// Keep `pack`, replacing an older one of the same class and date
+    fn put(&mut self, pack: SubstitutePack) {
+        self.packs
+            .retain(|p| !(p.class_id == pack.class_id && p.date == pack.date));
+        self.packs.insert(0, pack);
+        self.packs.truncate(MAX_PACKS);
+    }
+}
+
+/// First name of every student, with the surname's initial where two
+/// would read the same
+fn first_names(students: &[Student], order: NameOrder) -> Vec<String> {
+    let split: Vec<(String, String)> = students
+        .iter()
+        .map(|s| {
+            let words: Vec<&str> = s.name.split_whitespace().collect();
+            match (order, words.len()) {
+                (_, 0 | 1) => (s.name.trim().to_string(), String::new()),
+                (NameOrder::GivenFirst, _) => (words[0].to_string(), words[1..].join(" ")),
+                (NameOrder::SurnameFirst, _) => (words[1..].join(" "), words[0].to_string()),
+            }
+        })
+        .collect();
+    split
+        .iter()
+        .map(|(given, surname)| {
+            let shared = split.iter().filter(|(g, _)| g == given).count() > 1;
+            match surname.chars().next() {
+                Some(initial) if shared => format!("{} {}.", given, initial),
+                _ => given.clone(),
+            }
+        })
+        .collect()
+}
+
+fn accommodation_label(
+    kind: &AccommodationKind,
+    class: &ClassData,
+    names: &[String],
+    lang: Language,
+) -> String {
+    match kind {
+        AccommodationKind::NoColdCall => lang
+            .pick("Non interrogare a sorpresa", "Don't cold-call")
+            .to_string(),
+        AccommodationKind::FrontSeat => lang.pick("Primo banco", "Front row").to_string(),
+        AccommodationKind::KeepApart { student_id } => {
+            let other = class
+                .students
+                .iter()
+                .position(|s| &s.id == student_id)
+                .map_or("?", |i| names[i].as_str());
+            format!(
+                "{} {}",
+                lang.pick("Tenere lontano da", "Keep apart from"),
+                other
+            )
+        }
+    }
+}
+
+/// Build the pack; `date` picks the weekday of the schedule
+fn build_pack(
+    class: &ClassData,
+    chart: Option<&SeatingChart>,
+    bells: &BellSchedule,
+    date: NaiveDate,
+    order: NameOrder,
+    lang: Language,
+    now: u64,
+) -> SubstitutePack {
+    let names = first_names(&class.students, order);
+    let students = class
+        .students
+        .iter()
+        .zip(&names)
+        .map(|(student, name)| PackStudent {
+            first_name: name.clone(),
+            accommodations: student
+                .accommodations
+                .iter()
+                .map(|a| accommodation_label(&a.kind, class, &names, lang))
+                .collect(),
+        })
+        .collect();
+    let seating = chart.map(|chart| PackSeating {
+        rows: chart.rows,
+        columns: chart.columns,
+        seats: chart
+            .seats
+            .iter()
+            .filter_map(|seat| {
+                let i = class
+                    .students
+                    .iter()
+                    .position(|s| s.id == seat.student_id)?;
+                Some(PackSeat {
+                    first_name: names[i].clone(),
+                    row: seat.row,
+                    column: seat.column,
+                })
+            })
+            .collect(),
+    });
+    let weekday = date.weekday().number_from_monday() as u8;
+    let mut periods: Vec<PackPeriod> = bells
+        .periods
+        .iter()
+        .filter(|p| p.weekdays.contains(&weekday))
+        .map(|p| PackPeriod {
+            label: p.label.clone(),
+            kind: p.kind,
+            start: p.start.clone(),
+            end: p.end.clone(),
+        })
+        .collect();
+    periods.sort_by_key(|p| NaiveTime::parse_from_str(&p.start, "%H:%M").ok());
+
+    SubstitutePack {
+        class_id: class.id.clone(),
+        class_name: class.name.clone(),
+        date: date.format("%Y-%m-%d").to_string(),
+        generated_at: now,
+        students,
+        seating,
+        schedule: periods,
+    }
+}
+
+/// Printable version of a pack
+fn pack_pdf(pack: &SubstitutePack, date: NaiveDate, lang: Language) -> Vec<u8> {
+    let mut pdf = TextPdf::new();
+    pdf.heading(&format!(
+        "{} - {}",
+        lang.pick("Supplenza", "Substitute pack"),
+        pack.class_name
+    ));
+    let noon = date.and_hms_opt(12, 0, 0).unwrap_or_default();
+    let millis = chrono::Local
+        .from_local_datetime(&noon)
+        .single()
+        .map_or(0, |d| d.timestamp_millis() as u64);
+    pdf.paragraph(&locale::format_date(millis, DateStyle::Long, lang));
+
+    pdf.subheading(lang.pick("Orario", "Schedule"));
+    if pack.schedule.is_empty() {
+        pdf.paragraph(lang.pick(
+            "Nessun orario per questo giorno",
+            "No schedule for this day",
+        ));
+    }
+    for period in &pack.schedule {
+        pdf.paragraph(&format!(
+            "{}-{}  {}",
+            period.start, period.end, period.label
+        ));
+    }
+
+    if let Some(seating) = &pack.seating {
+        pdf.subheading(lang.pick("Posti (fila 1 = prima fila)", "Seating (row 1 = front)"));
+        for row in 0..seating.rows {
+            let cells: Vec<&str> = (0..seating.columns)
+                .map(|column| {
+                    seating
+                        .seats
+                        .iter()
+                        .find(|s| s.row == row && s.column == column)
+                        .map_or("-", |s| s.first_name.as_str())
+                })
+                .collect();
+            pdf.paragraph(&format!(
+                "{} {}: {}",
+                lang.pick("Fila", "Row"),
+                row + 1,
+                cells.join(" | ")
+            ));
+        }
+    }
+
+    let with_needs: Vec<&PackStudent> = pack
+        .students
+        .iter()
+        .filter(|s| !s.accommodations.is_empty())
+        .collect();
+    if !with_needs.is_empty() {
+        pdf.subheading(lang.pick("Attenzioni", "Accommodations"));
+        for student in with_needs {
+            pdf.paragraph(&format!(
+                "{}: {}",
+                student.first_name,
+                student.accommodations.join(", ")
+            ));
+        }
+    }
+    pdf.finish()
+}
+
+/// Build and keep the pack of a class for `date` (`YYYY-MM-DD`), and print
+/// it to `pdf_path` when given
+pub fn generate_substitute_pack(
+    class_id: &str,
+    date: &str,
+    name_order: NameOrder,
+    pdf_path: Option<&str>,
+) -> Result<SubstitutePack, BackendError> {
+    let day = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| {
+        BackendError::new(errors::system::INVALID_INPUT, "Date must be YYYY-MM-DD")
+            .with_details(date.to_string())
+    })?;
+    if let Some(path) = pdf_path {
+        if Path::new(path).extension().and_then(|e| e.to_str()) != Some("pdf") {
+            return Err(BackendError::new(
+                errors::file::INVALID_FORMAT,
+                "Substitute pack must be a .pdf file",
+            )
+            .with_details(path.to_string()));
+        }
+    }
+    let class = class_records::load_class(class_id)?;
+    let chart = class_records::get_seating_chart(class_id)?;
+    let lang = locale::app_language();
+    let pack = build_pack(
+        &class,
+        chart.as_ref(),
+        &schedule::get_bell_schedule(),
+        day,
+        name_order,
+        lang,
+        clock::now_millis(),
+    );
+
+    if let Some(path) = pdf_path {
+        std::fs::write(path, pack_pdf(&pack, day, lang)).map_err(|e| {
+            BackendError::new(errors::file::IO_ERROR, "Failed to write substitute pack")
+                .with_details(format!("{}: {}", path, e))
+        })?;
+    }
+    let mut store = SubstitutePackStore::load()?;
+    store.put(pack.clone());
+    store.save()?;
+    Ok(pack)
+}
+
+/// Kept packs, newest first
+pub fn get_substitute_packs() -> Result<Vec<SubstitutePack>, BackendError> {
+    Ok(SubstitutePackStore::load()?.packs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accommodations::Accommodation;
+    use crate::class_records::Seat;
+    use crate::roster::ClassBuilder;
+    use crate::schedule::BellPeriod;
+
+    fn class() -> ClassData {
+        let mut class = ClassBuilder::new("class_1", "3A")
+            .students(&["Rossi Marco", "Bianchi Marco", "Verdi Anna Maria"])
+            .build();
+        for student in &mut class.students {
+            student.notes = Some("Diagnosi DSA".into());
+            student.email = Some(format!("{}@scuola.it", student.id));
+        }
+        class.students[2].accommodations = vec![
+            Accommodation {
+                kind: AccommodationKind::FrontSeat,
+                details: Some("Ipovedente".into()),
+            },
+            Accommodation {
+                kind: AccommodationKind::KeepApart {
+                    student_id: "s0".into(),
+                },
+                details: None,
+            },
+        ];
+        class
+    }
+
+    #[test]
+    fn test_pack_is_redacted() {
+        let class = class();
+        let chart = SeatingChart {
+            class_id: class.id.clone(),
+            rows: 2,
+            columns: 2,
+            seats: vec![Seat {
+                student_id: "s2".into(),
+                row: 0,
+                column: 1,
+            }],
+            updated_at: 0,
+        };
+        let day = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let pack = build_pack(
+            &class,
+            Some(&chart),
+            &BellSchedule::default(),
+            day,
+            NameOrder::SurnameFirst,
+            Language::En,
+            1,
+        );
+        let names: Vec<&str> = pack
+            .students
+            .iter()
+            .map(|s| s.first_name.as_str())
+            .collect();
+        assert_eq!(names, ["Marco R.", "Marco B.", "Anna Maria"]);
+        assert_eq!(
+            pack.students[2].accommodations,
+            ["Front row", "Keep apart from Marco R."]
+        );
+        assert_eq!(pack.seating.unwrap().seats[0].first_name, "Anna Maria");
+
+        let json = serde_json::to_string(&build_pack(
+            &class,
+            Some(&chart),
+            &BellSchedule::default(),
+            day,
+            NameOrder::SurnameFirst,
+            Language::It,
+            1,
+        ))
+        .unwrap();
+        for secret in ["Rossi", "Verdi", "DSA", "Ipovedente", "scuola.it", "s2"] {
+            assert!(!json.contains(secret), "{}", secret);
+        }
+        assert_eq!(
+            first_names(&class.students, NameOrder::GivenFirst),
+            ["Rossi", "Bianchi", "Verdi"]
+        );
+    }
+
+    #[test]
+    fn test_schedule_of_the_weekday() {
+        let period = |label: &str, start: &str, weekdays: Vec<u8>| BellPeriod {
+            label: label.into(),
+            kind: PeriodKind::Lesson,
+            start: start.into(),
+            end: "13:00".into(),
+            weekdays,
+        };
+        let bells = BellSchedule {
+            periods: vec![
+                period("Storia", "10:00", vec![1, 2]),
+                period("Matematica", "08:00", vec![1]),
+                period("Inglese", "09:00", vec![3]),
+            ],
+        };
+        // A Monday
+        let day = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let pack = build_pack(
+            &class(),
+            None,
+            &bells,
+            day,
+            NameOrder::GivenFirst,
+            Language::It,
+            1,
+        );
+        let labels: Vec<&str> = pack.schedule.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(labels, ["Matematica", "Storia"]);
+        assert!(pack_pdf(&pack, day, Language::It).starts_with(b"%PDF"));
+
+        let mut store = SubstitutePackStore::default();
+        store.put(pack.clone());
+        store.put(SubstitutePack {
+            generated_at: 2,
+            ..pack
+        });
+        assert_eq!(store.packs.len(), 1);
+        assert_eq!(store.packs[0].generated_at, 2);
+    }
+}
@@ -0,0 +1,601 @@
+//! Timer sequences (interval timers)
+//!
+//! Handles:
+//! - Ordered phases with their own duration, color and end sound, repeated
+//!   for a number of rounds (e.g. 20 min work / 5 min break ×3)
+//! - Advancing phases in the backend, so a rotation keeps running while the
+//!   window is hidden or the webview is busy
+//! - `timer-sequence-phase` on every phase change and a throttled
+//!   `timer-tick` for the countdown display
+//! - Voice announcements at configured milestones of every phase ("5
+//!   minutes left", "time's up"), kept per sequence name in the
+//!   `timer_announcements` config key
+//!
+//! Only one sequence runs at a time; creating a new one replaces it.
+//!
+//! Announcements are emitted as `timer-announcement` with the phrase in
+//! the app language; the frontend speaks it with the system voice.
+//! Background music is ducked meanwhile. When several milestones pass at
+//! once (e.g. after the PC slept) only the nearest to the end is spoken.
+
+use crate::background_audio;
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::event_throttle;
+use crate::file_ops;
+use crate::locale::{self, Language};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted when a phase starts or the sequence ends
+pub const PHASE_EVENT: &str = "timer-sequence-phase";
+const TICK_CHANNEL: &str = "timer-tick";
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+/// Emitted when a milestone is reached; the frontend speaks `text`
+pub const ANNOUNCEMENT_EVENT: &str = "timer-announcement";
+const ANNOUNCEMENTS_KEY: &str = "timer_announcements";
+/// Background music is ducked for this long while a phrase is spoken
+const ANNOUNCEMENT_DUCK_MS: u64 = 3_000;
+
+pub const MAX_PHASES: usize = 20;
+pub const MAX_ROUNDS: u32 = 20;
+const MAX_PHASE_SECS: u32 = 4 * 60 * 60;
+pub const MAX_MILESTONES: usize = 10;
+const MAX_PHRASE_CHARS: usize = 120;
+
+static RUN: Mutex<Option<SequenceRun>> = Mutex::new(None);
+static TICKER: OnceLock<()> = OnceLock::new();
+
+/// One phase of a sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerPhase {
+    pub label: String,
+    pub duration_secs: u32,
+    /// Background color, `#RRGGBB`
+    pub color: String,
+    /// Sound played when the phase ends (frontend sound id)
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+/// Sequence definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerSequence {
+    pub name: String,
+    pub phases: Vec<TimerPhase>,
+    /// How many times the phases are repeated
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+}
+
+fn default_rounds() -> u32 {
+    1
+}
+
+/// Snapshot of the running sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceState {
+    pub name: String,
+    /// Index into `phases`
+    pub phase_index: usize,
+    pub phase_count: usize,
+    /// 1-based
+    pub round: u32,
+    pub rounds: u32,
+    pub phase: TimerPhase,
+    pub remaining_ms: u64,
+    pub finished: bool,
+    /// Sound of the phase that just ended, set only in `timer-sequence-phase`
+    pub sound: Option<String>,
+}
+
+/// A moment announced in every phase of a sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Milestone {
+    /// Time left in the phase; 0 announces its end
+    pub remaining_secs: u32,
+    /// Phrase per language code (`it`, `en`) replacing the built-in one;
+    /// `{label}`, `{minutes}` and `{seconds}` are filled in
+    #[serde(default)]
+    pub phrases: BTreeMap<String, String>,
+}
+
+/// Payload of `timer-announcement`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerAnnouncement {
+    pub timer_id: String,
+    pub phase_label: String,
+    pub remaining_secs: u32,
+    pub text: String,
+    /// Language of `text`, for picking the voice
+    pub language: Language,
+}
+
+#[derive(Debug, Clone)]
+struct SequenceRun {
+    sequence: TimerSequence,
+    /// Position across all rounds; `total_steps()` once finished
+    step: usize,
+    step_started_at: u64,
+    milestones: Vec<Milestone>,
+    /// Milestones of the current phase already announced
+    announced: Vec<u32>,
+}
+
+impl SequenceRun {
+    fn new(sequence: TimerSequence, now: u64) -> Self {
+        Self {
+            sequence,
+            step: 0,
+            step_started_at: now,
+            milestones: Vec::new(),
+            announced: Vec::new(),
+        }
+    }
+
+    fn total_steps(&self) -> usize {
+        self.sequence.phases.len() * self.sequence.rounds as usize
+    }
+
+    fn is_finished(&self) -> bool {
+        self.step >= self.total_steps()
+    }
+
+    fn phase_index(&self) -> usize {
+        self.step.min(self.total_steps() - 1) % self.sequence.phases.len()
+    }
+
+    fn phase(&self) -> &TimerPhase {
+        &self.sequence.phases[self.phase_index()]
+    }
+
+    fn phase_ms(&self) -> u64 {
+        self.phase().duration_secs as u64 * 1000
+    }
+
+    fn next_step(&mut self, at: u64) -> Option<String> {
+        let sound = self.phase().sound.clone();
+        self.step += 1;
+        self.step_started_at = at;
+        self.announced.clear();
+        sound
+    }
+
+    /// The milestone of the current phase reached by `now` and not yet
+    /// announced; the end of the phase is announced by `tick` instead
+    fn due_milestone(&mut self, now: u64) -> Option<Milestone> {
+        if self.is_finished() {
+            return None;
+        }
+        let phase_ms = self.phase_ms();
+        let remaining = (self.step_started_at + phase_ms).saturating_sub(now);
+        let passed: Vec<&Milestone> = self
+            .milestones
+            .iter()
+            .filter(|m| {
+                let at = m.remaining_secs as u64 * 1000;
+                // Longer than the phase: never counted down from
+                at > 0 && at < phase_ms && remaining <= at
+            })
+            .filter(|m| !self.announced.contains(&m.remaining_secs))
+            .collect();
+        let due = (*passed.iter().min_by_key(|m| m.remaining_secs)?).clone();
+        self.announced
+            .extend(passed.iter().map(|m| m.remaining_secs));
+        Some(due)
+    }
+
+    fn ends_with_announcement(&self) -> Option<Milestone> {
+        self.milestones
+            .iter()
+            .find(|m| m.remaining_secs == 0)
+            .cloned()
+    }
+
+    /// Move past every phase that has ended by `now`; returns the sound of
+    /// the last phase that ended, or `None` if nothing changed
+    fn advance(&mut self, now: u64) -> Option<Option<String>> {
+        let mut ended = None;
+        while !self.is_finished() && now >= self.step_started_at + self.phase_ms() {
+            let end = self.step_started_at + self.phase_ms();
+            ended = Some(self.next_step(end));
+        }
+        ended
+    }
+
+    /// End the current phase immediately
+    fn skip(&mut self, now: u64) {
+        if !self.is_finished() {
+            self.next_step(now);
+        }
+    }
+
+    fn state(&self, now: u64, sound: Option<String>) -> SequenceState {
+        let finished = self.is_finished();
+        SequenceState {
+            name: self.sequence.name.clone(),
+            phase_index: self.phase_index(),
+            phase_count: self.sequence.phases.len(),
+            round: (self.step.min(self.total_steps() - 1) / self.sequence.phases.len()) as u32 + 1,
+            rounds: self.sequence.rounds,
+            phase: self.phase().clone(),
+            remaining_ms: if finished {
+                0
+            } else {
+                (self.step_started_at + self.phase_ms()).saturating_sub(now)
+            },
+            finished,
+            sound,
+        }
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn validate(sequence: &TimerSequence) -> Result<(), BackendError> {
+    let invalid = |msg: &str| Err(BackendError::new(errors::system::INVALID_INPUT, msg));
+    if sequence.phases.is_empty() || sequence.phases.len() > MAX_PHASES {
+        return invalid("A sequence needs 1-20 phases");
+    }
+    if sequence.rounds == 0 || sequence.rounds > MAX_ROUNDS {
+        return invalid("Rounds must be between 1 and 20");
+    }
+    for phase in &sequence.phases {
+        if phase.duration_secs == 0 || phase.duration_secs > MAX_PHASE_SECS {
+            return invalid("Phase duration must be between 1 second and 4 hours");
+        }
+        if !is_hex_color(&phase.color) {
+            return Err(
+                BackendError::new(errors::system::INVALID_INPUT, "Invalid phase color")
+                    .with_details(phase.color.clone()),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<SequenceRun>> {
+    RUN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn not_running() -> BackendError {
+    BackendError::new(errors::timer::NOT_RUNNING, "No timer sequence is running")
+}
+
+fn emit_phase(app: &AppHandle, state: &SequenceState) {
+    let _ = app.emit(PHASE_EVENT, state);
+}
+
+/// Built-in phrase for `remaining_secs` left
+fn default_phrase(remaining_secs: u32, lang: Language) -> &'static str {
+    match remaining_secs {
+        0 => lang.pick("Tempo scaduto", "Time's up"),
+        1..=59 => lang.pick("Mancano {seconds} secondi", "{seconds} seconds left"),
+        60 => lang.pick("Manca un minuto", "One minute left"),
+        _ => lang.pick("Mancano {minutes} minuti", "{minutes} minutes left"),
+    }
+}
+
+/// Text spoken for a milestone of the phase `label`
+fn phrase(milestone: &Milestone, label: &str, lang: Language) -> String {
+    let template = milestone
+        .phrases
+        .get(lang.code())
+        .map(String::as_str)
+        .unwrap_or_else(|| default_phrase(milestone.remaining_secs, lang));
+    let secs = milestone.remaining_secs;
+    template
+        .replace("{label}", label)
+        .replace("{minutes}", &secs.div_ceil(60).to_string())
+        .replace("{seconds}", &secs.to_string())
+}
+
+fn announce(app: &AppHandle, timer_id: &str, label: &str, milestone: &Milestone) {
+    let language = locale::app_language();
+    let announcement = TimerAnnouncement {
+        timer_id: timer_id.to_string(),
+        phase_label: label.to_string(),
+        remaining_secs: milestone.remaining_secs,
+        text: phrase(milestone, label, language),
+        language,
+    };
+    let _ = background_audio::duck_background_audio(app, ANNOUNCEMENT_DUCK_MS);
+    let _ = app.emit(ANNOUNCEMENT_EVENT, &announcement);
+}
+
+/// Advance the running sequence and emit events
+fn tick(app: &AppHandle) {
+    let now = clock::now_millis();
+    let mut run = lock();
+    let Some(current) = run.as_mut() else {
+        return;
+    };
+    let label = current.phase().label.clone();
+    if let Some(sound) = current.advance(now) {
+        emit_phase(app, &current.state(now, sound));
+        if let Some(end) = current.ends_with_announcement() {
+            announce(app, &current.sequence.name, &label, &end);
+        }
+    }
+    if let Some(milestone) = current.due_milestone(now) {
+        let label = current.phase().label.clone();
+        announce(app, &current.sequence.name, &label, &milestone);
+    }
+    if current.is_finished() {
+        *run = None;
+        return;
+    }
+    event_throttle::emit(app, TICK_CHANNEL, "sequence", &current.state(now, None));
+}
+
+fn ensure_ticker(app: &AppHandle) {
+    TICKER.get_or_init(|| {
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(TICK_INTERVAL);
+            tick(&app);
+        });
+    });
+}
+
+/// Start a sequence, replacing any running one
+pub fn create_timer_sequence(
+    app: &AppHandle,
+    sequence: TimerSequence,
+) -> Result<SequenceState, BackendError> {
+    validate(&sequence)?;
+    let now = clock::now_millis();
+    let mut run = SequenceRun::new(sequence, now);
+    run.milestones = get_timer_announcements(&run.sequence.name);
+    let state = run.state(now, None);
+    *lock() = Some(run);
+    ensure_ticker(app);
+    emit_phase(app, &state);
+    Ok(state)
+}
+
+/// End the current phase and start the next one
+pub fn skip_phase(app: &AppHandle) -> Result<SequenceState, BackendError> {
+    let now = clock::now_millis();
+    let mut run = lock();
+    let current = run.as_mut().ok_or_else(not_running)?;
+    let sound = current.phase().sound.clone();
+    current.skip(now);
+    let state = current.state(now, sound);
+    if current.is_finished() {
+        *run = None;
+    }
+    emit_phase(app, &state);
+    Ok(state)
+}
+
+/// State of the running sequence, `None` if idle
+pub fn get_sequence_state() -> Option<SequenceState> {
+    lock()
+        .as_ref()
+        .map(|run| run.state(clock::now_millis(), None))
+}
+
+/// Stop the running sequence
+pub fn stop_timer_sequence() -> Result<(), BackendError> {
+    lock().take().map(|_| ()).ok_or_else(not_running)
+}
+
+fn announcement_config() -> BTreeMap<String, Vec<Milestone>> {
+    file_ops::load_config(ANNOUNCEMENTS_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Check milestones; returns them sorted, furthest from the end first
+fn validate_milestones(mut milestones: Vec<Milestone>) -> Result<Vec<Milestone>, BackendError> {
+    let invalid = |msg: &str| BackendError::new(errors::system::INVALID_INPUT, msg);
+    if milestones.len() > MAX_MILESTONES {
+        return Err(invalid("At most 10 milestones per timer"));
+    }
+    milestones.sort_by_key(|m| std::cmp::Reverse(m.remaining_secs));
+    if milestones
+        .windows(2)
+        .any(|w| w[0].remaining_secs == w[1].remaining_secs)
+    {
+        return Err(invalid("Milestones must be different times"));
+    }
+    for milestone in &mut milestones {
+        if milestone.remaining_secs > MAX_PHASE_SECS {
+            return Err(invalid("Milestones must be within 4 hours"));
+        }
+        for (lang, text) in milestone.phrases.iter_mut() {
+            if Language::from_tag(lang).map(|l| l.code()) != Some(lang.as_str()) {
+                return Err(invalid("Unsupported phrase language").with_details(lang.clone()));
+            }
+            *text = text.trim().to_string();
+            if text.is_empty() || text.chars().count() > MAX_PHRASE_CHARS {
+                return Err(invalid("Phrases must be 1-120 characters"));
+            }
+        }
+    }
+    Ok(milestones)
+}
+
+/// Milestones announced for the sequence named `timer_id`
+pub fn get_timer_announcements(timer_id: &str) -> Vec<Milestone> {
+    announcement_config().remove(timer_id).unwrap_or_default()
+}
+
+/// Set the milestones announced for the sequence named `timer_id` (none
+/// turns announcements off); applies to the running sequence right away
+pub fn set_timer_announcements(
+    timer_id: &str,
+    milestones: Vec<Milestone>,
+) -> Result<Vec<Milestone>, BackendError> {
+    let milestones = validate_milestones(milestones)?;
+    let mut config = announcement_config();
+    if milestones.is_empty() {
+        config.remove(timer_id);
+    } else {
+        config.insert(timer_id.to_string(), milestones.clone());
+    }
+    let value = serde_json::to_value(&config).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid milestones")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(ANNOUNCEMENTS_KEY, value)?;
+    if let Some(run) = lock().as_mut().filter(|r| r.sequence.name == timer_id) {
+        run.milestones = milestones.clone();
+    }
+    Ok(milestones)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phase(label: &str, secs: u32, sound: Option<&str>) -> TimerPhase {
+        TimerPhase {
+            label: label.to_string(),
+            duration_secs: secs,
+            color: "#22AA44".to_string(),
+            sound: sound.map(str::to_string),
+        }
+    }
+
+    fn work_break(rounds: u32) -> TimerSequence {
+        TimerSequence {
+            name: "Rotazione".to_string(),
+            phases: vec![
+                phase("Lavoro", 20 * 60, Some("bell")),
+                phase("Pausa", 5 * 60, Some("chime")),
+            ],
+            rounds,
+        }
+    }
+
+    #[test]
+    fn test_advance_through_rounds() {
+        let mut run = SequenceRun::new(work_break(3), 0);
+        assert_eq!(run.advance(60_000), None);
+        assert_eq!(run.state(60_000, None).remaining_ms, 19 * 60_000);
+
+        // Work ends at 20 min
+        assert_eq!(run.advance(20 * 60_000), Some(Some("bell".to_string())));
+        let state = run.state(20 * 60_000, None);
+        assert_eq!((state.phase.label.as_str(), state.round), ("Pausa", 1));
+
+        // A long stall skips several phases but keeps phase boundaries
+        run.advance(55 * 60_000);
+        let state = run.state(55 * 60_000, None);
+        assert_eq!((state.phase.label.as_str(), state.round), ("Lavoro", 3));
+        assert_eq!(state.remaining_ms, 15 * 60_000);
+
+        run.advance(75 * 60_000);
+        assert!(run.is_finished());
+        let state = run.state(75 * 60_000, None);
+        assert!(state.finished);
+        assert_eq!(
+            (state.phase_index, state.round, state.remaining_ms),
+            (1, 3, 0)
+        );
+    }
+
+    #[test]
+    fn test_skip_restarts_clock() {
+        let mut run = SequenceRun::new(work_break(1), 0);
+        run.skip(30_000);
+        let state = run.state(30_000, None);
+        assert_eq!(state.phase.label, "Pausa");
+        assert_eq!(state.remaining_ms, 5 * 60_000);
+        run.skip(40_000);
+        assert!(run.is_finished());
+        run.skip(50_000);
+        assert_eq!(run.step, 2);
+    }
+
+    fn milestone(remaining_secs: u32) -> Milestone {
+        Milestone {
+            remaining_secs,
+            phrases: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_milestones_announced_once_per_phase() {
+        let mut run = SequenceRun::new(work_break(1), 0);
+        run.milestones = vec![
+            milestone(0),
+            milestone(60),
+            milestone(5 * 60),
+            milestone(10),
+        ];
+        assert_eq!(run.due_milestone(60_000), None);
+
+        // 5 min left in work
+        let due = run.due_milestone(15 * 60_000).unwrap();
+        assert_eq!(due.remaining_secs, 5 * 60);
+        assert_eq!(run.due_milestone(15 * 60_000 + 250), None);
+
+        // Stalled past both 1 min and 10 s: only the nearest is spoken
+        assert_eq!(
+            run.due_milestone(19 * 60_000 + 55_000)
+                .unwrap()
+                .remaining_secs,
+            10
+        );
+        assert_eq!(run.due_milestone(19 * 60_000 + 56_000), None);
+
+        // The break is as long as the 5 min milestone: never announced
+        run.advance(20 * 60_000);
+        assert_eq!(run.ends_with_announcement(), Some(milestone(0)));
+        assert_eq!(run.due_milestone(20 * 60_000 + 1), None);
+        assert_eq!(run.due_milestone(24 * 60_000).unwrap().remaining_secs, 60);
+    }
+
+    #[test]
+    fn test_phrases() {
+        assert_eq!(
+            phrase(&milestone(300), "Lavoro", Language::It),
+            "Mancano 5 minuti"
+        );
+        assert_eq!(
+            phrase(&milestone(60), "Lavoro", Language::En),
+            "One minute left"
+        );
+        assert_eq!(phrase(&milestone(0), "Lavoro", Language::En), "Time's up");
+        let mut custom = milestone(120);
+        custom
+            .phrases
+            .insert("it".into(), "{label}: ancora {minutes} minuti".into());
+        assert_eq!(
+            phrase(&custom, "Lavoro", Language::It),
+            "Lavoro: ancora 2 minuti"
+        );
+        assert_eq!(phrase(&custom, "Work", Language::En), "2 minutes left");
+
+        let sorted = validate_milestones(vec![milestone(0), custom.clone()]).unwrap();
+        assert_eq!(sorted[0].remaining_secs, 120);
+        assert!(validate_milestones(vec![milestone(60), milestone(60)]).is_err());
+        custom.phrases.insert("de".into(), "Noch {minutes}".into());
+        assert!(validate_milestones(vec![custom]).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&work_break(3)).is_ok());
+        assert!(validate(&work_break(0)).is_err());
+        let mut bad = work_break(1);
+        bad.phases[0].color = "green".to_string();
+        assert!(validate(&bad).is_err());
+        bad.phases.clear();
+        assert!(validate(&bad).is_err());
+    }
+}
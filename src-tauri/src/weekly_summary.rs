@@ -0,0 +1,476 @@
+//! Weekly class summary scheduler
+//!
+//! Handles:
+//! - Generating an HTML report of the week (classes, exit tickets)
+//! - Running it every Friday at a configured local time
+//! - Saving the report to a folder or emailing it through `mailer`
+//! - Keeping a log of generated summaries
+//!
+//! If the app is closed at the scheduled time, the summary for that week is
+//! generated the next time the app runs (before the following Friday).
+
+use crate::clock;
+use crate::errors::{self, BackendError};
+use crate::exit_tickets::{ExitTicketSession, ExitTicketStore};
+use crate::file_ops;
+use crate::locale::{self, DateStyle, Language};
+use crate::mailer;
+use crate::roster::{ClassData, RosterStore};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const OPTIONS_CONFIG_KEY: &str = "weekly_summary";
+const STATE_COLLECTION: &str = "weekly_summary";
+
+/// Summaries are kept here when emailed (and as the default folder)
+pub(crate) const SUMMARIES_DIR: &str = "summaries";
+
+/// How often the scheduler checks whether a summary is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Log entries kept (one school year of weeks)
+const MAX_LOG_ENTRIES: usize = 52;
+
+const WEEK_MILLIS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Serializes scheduled and manual runs
+static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+/// Where a generated summary goes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SummaryDelivery {
+    /// Save the report in a folder (empty path: app data folder)
+    Folder { path: String },
+    /// Email the report to these addresses
+    Email { to: Vec<String> },
+}
+
+/// Weekly summary settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeeklySummaryOptions {
+    pub enabled: bool,
+    /// Local time on Friday, `HH:MM`
+    pub time: String,
+    pub delivery: SummaryDelivery,
+}
+
+impl Default for WeeklySummaryOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: "16:00".to_string(),
+            delivery: SummaryDelivery::Folder {
+                path: String::new(),
+            },
+        }
+    }
+}
+
+/// A generated (or failed) summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryLogEntry {
+    pub generated_at: u64,
+    pub period_start: u64,
+    pub period_end: u64,
+    /// Saved report file
+    pub path: Option<String>,
+    /// Recipients when emailed
+    #[serde(default)]
+    pub emailed_to: Vec<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Persisted scheduler state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SummaryState {
+    /// Scheduled slot (epoch ms) of the last run
+    #[serde(default)]
+    last_slot: Option<u64>,
+    #[serde(default)]
+    log: Vec<SummaryLogEntry>,
+}
+
+impl SummaryState {
+    fn load() -> Result<Self, BackendError> {
+        file_ops::load_data(STATE_COLLECTION)
+    }
+
+    fn save(&self) -> Result<(), BackendError> {
+        file_ops::save_data(STATE_COLLECTION, self)
+    }
+
+    fn record(&mut self, entry: SummaryLogEntry) {
+        self.log.insert(0, entry);
+        self.log.truncate(MAX_LOG_ENTRIES);
+    }
+}
+
+pub(crate) fn parse_time(time: &str) -> Result<NaiveTime, BackendError> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| {
+        BackendError::new(errors::system::INVALID_INPUT, "Time must be HH:MM")
+            .with_details(time.to_string())
+    })
+}
+
+/// Most recent Friday at `time` that is not after `now`
+pub fn latest_slot(now: NaiveDateTime, time: NaiveTime) -> NaiveDateTime {
+    let days_since_friday =
+        (now.weekday().num_days_from_monday() + 7 - Weekday::Fri.num_days_from_monday()) % 7;
+    let slot = (now.date() - chrono::Duration::days(days_since_friday.into())).and_time(time);
+    if slot > now {
+        slot - chrono::Duration::days(7)
+    } else {
+        slot
+    }
+}
+
+/// Local date-time to epoch millis (first occurrence across DST changes)
+pub(crate) fn local_millis(datetime: NaiveDateTime) -> u64 {
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map_or(0, |dt| dt.timestamp_millis().max(0) as u64)
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn title(lang: Language) -> &'static str {
+    lang.pick("Riepilogo settimanale", "Weekly summary")
+}
+
+/// Build the HTML report for a period, in the app language
+pub fn build_report(
+    period_start: u64,
+    period_end: u64,
+    classes: &[ClassData],
+    sessions: &[ExitTicketSession],
+    lang: Language,
+) -> String {
+    let date = |millis| locale::format_date(millis, DateStyle::Short, lang);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\"><head><meta charset=\"utf-8\">\
+         <title>{}</title>\
+         <style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px;text-align:left}}</style>\
+         </head><body>\n",
+        lang.code(),
+        title(lang)
+    );
+    html.push_str(&format!(
+        "<h1>{}</h1>\n<p>{}</p>\n",
+        title(lang),
+        match lang {
+            Language::It => format!("Dal {} al {}", date(period_start), date(period_end)),
+            Language::En => format!("From {} to {}", date(period_start), date(period_end)),
+        }
+    ));
+
+    html.push_str(&format!("<h2>{}</h2>\n", lang.pick("Classi", "Classes")));
+    if classes.is_empty() {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            lang.pick("Nessuna classe registrata.", "No classes yet.")
+        ));
+    } else {
+        html.push_str(&format!(
+            "<table><tr><th>{}</th><th>{}</th></tr>\n",
+            lang.pick("Classe", "Class"),
+            lang.pick("Studenti", "Students")
+        ));
+        for class in classes {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&class.name),
+                class.students.len()
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Exit ticket</h2>\n");
+    let week: Vec<&ExitTicketSession> = sessions
+        .iter()
+        .filter(|s| s.opened_at >= period_start && s.opened_at < period_end)
+        .collect();
+    if week.is_empty() {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            lang.pick(
+                "Nessun exit ticket questa settimana.",
+                "No exit tickets this week."
+            )
+        ));
+    } else {
+        html.push_str(&format!(
+            "<table><tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>\n",
+            lang.pick("Data", "Date"),
+            lang.pick("Lezione", "Lesson"),
+            lang.pick("Domanda", "Question"),
+            lang.pick("Risposte", "Responses")
+        ));
+        for session in week {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                date(session.opened_at),
+                escape_html(&session.lesson_id),
+                escape_html(&session.prompt),
+                session.responses.len()
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// Current summary settings
+pub fn get_options() -> WeeklySummaryOptions {
+    file_ops::load_config(OPTIONS_CONFIG_KEY)
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Validate and save summary settings
+///
+/// The slot that has already passed this week is marked done, so enabling
+/// the summary on a Saturday does not immediately send last week's report.
+pub fn configure(options: WeeklySummaryOptions) -> Result<(), BackendError> {
+    let time = parse_time(&options.time)?;
+    match &options.delivery {
+        SummaryDelivery::Folder { path } => {
+            if !path.trim().is_empty() && !PathBuf::from(path.trim()).is_dir() {
+                return Err(BackendError::new(
+                    errors::file::NOT_FOUND,
+                    "Summary folder does not exist",
+                )
+                .with_details(path.clone()));
+            }
+        }
+        SummaryDelivery::Email { to } => {
+            if to.is_empty() {
+                return Err(BackendError::new(
+                    errors::mail::INVALID_ADDRESS,
+                    "No recipients",
+                ));
+            }
+            if !mailer::is_configured() {
+                return Err(BackendError::new(
+                    errors::mail::NOT_CONFIGURED,
+                    "Configure email before choosing email delivery",
+                ));
+            }
+        }
+    }
+
+    let value = serde_json::to_value(&options).map_err(|e| {
+        BackendError::new(errors::system::INVALID_INPUT, "Invalid summary options")
+            .with_details(e.to_string())
+    })?;
+    file_ops::save_config(OPTIONS_CONFIG_KEY, value)?;
+
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = SummaryState::load()?;
+    state.last_slot = Some(local_millis(latest_slot(Local::now().naive_local(), time)));
+    state.save()
+}
+
+/// Generate and deliver the summary for the week ending at `period_end`
+fn generate(options: &WeeklySummaryOptions, period_end: u64) -> SummaryLogEntry {
+    let period_start = period_end.saturating_sub(WEEK_MILLIS);
+    let mut entry = SummaryLogEntry {
+        generated_at: clock::now_millis(),
+        period_start,
+        period_end,
+        path: None,
+        emailed_to: Vec::new(),
+        success: false,
+        error: None,
+    };
+
+    let result = (|| -> Result<(), BackendError> {
+        let classes = RosterStore::load()?.classes;
+        let sessions = ExitTicketStore::load()?.sessions;
+        let lang = locale::app_language();
+        let html = build_report(period_start, period_end, &classes, &sessions, lang);
+
+        let dir = match &options.delivery {
+            SummaryDelivery::Folder { path } if !path.trim().is_empty() => {
+                PathBuf::from(path.trim())
+            }
+            _ => file_ops::get_config_dir()?.join(SUMMARIES_DIR),
+        };
+        fs::create_dir_all(&dir)?;
+        let date = Local
+            .timestamp_millis_opt(period_end as i64)
+            .single()
+            .map_or_else(
+                || period_end.to_string(),
+                |dt| dt.format("%Y-%m-%d").to_string(),
+            );
+        let file_name = format!(
+            "{}-{}.html",
+            lang.pick("riepilogo-settimanale", "weekly-summary"),
+            date
+        );
+        let path = dir.join(&file_name);
+        fs::write(&path, &html)?;
+        entry.path = Some(path.display().to_string());
+
+        if let SummaryDelivery::Email { to } = &options.delivery {
+            let attachment = mailer::MailAttachment {
+                file_name,
+                content_type: "text/html; charset=utf-8".to_string(),
+                content: html.clone().into_bytes(),
+            };
+            mailer::send_email(
+                to,
+                &format!(
+                    "{} {}",
+                    title(lang),
+                    locale::format_date(period_end, DateStyle::Short, lang)
+                ),
+                &html,
+                &[attachment],
+            )?;
+            entry.emailed_to = to.clone();
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => entry.success = true,
+        Err(e) => entry.error = Some(e.message),
+    }
+    entry
+}
+
+/// Run the summary if a Friday slot has passed since the last run
+pub fn run_if_due() -> Result<Option<SummaryLogEntry>, BackendError> {
+    let options = get_options();
+    if !options.enabled {
+        return Ok(None);
+    }
+    let slot = local_millis(latest_slot(
+        Local::now().naive_local(),
+        parse_time(&options.time)?,
+    ));
+
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = SummaryState::load()?;
+    if state.last_slot.is_some_and(|last| last >= slot) {
+        return Ok(None);
+    }
+
+    let entry = generate(&options, slot);
+    state.last_slot = Some(slot);
+    state.record(entry.clone());
+    state.save()?;
+    Ok(Some(entry))
+}
+
+/// Generate the summary for the last 7 days right now
+pub fn generate_now() -> Result<SummaryLogEntry, BackendError> {
+    let options = get_options();
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = generate(&options, clock::now_millis());
+    let mut state = SummaryState::load()?;
+    state.record(entry.clone());
+    state.save()?;
+    Ok(entry)
+}
+
+/// Generated summaries, newest first
+pub fn get_log() -> Result<Vec<SummaryLogEntry>, BackendError> {
+    Ok(SummaryState::load()?.log)
+}
+
+/// Start the background thread that runs the summary when due
+pub fn start_scheduler() {
+    std::thread::spawn(|| loop {
+        // Failures are recorded in the log; config errors retry next check
+        let _ = run_if_due();
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_latest_slot() {
+        let time = parse_time("16:00").unwrap();
+        // Friday 2024-03-15 after the slot
+        assert_eq!(
+            latest_slot(at(2024, 3, 15, 17, 0), time),
+            at(2024, 3, 15, 16, 0)
+        );
+        // Friday before the slot: previous Friday
+        assert_eq!(
+            latest_slot(at(2024, 3, 15, 15, 59), time),
+            at(2024, 3, 8, 16, 0)
+        );
+        // Wednesday
+        assert_eq!(
+            latest_slot(at(2024, 3, 13, 9, 0), time),
+            at(2024, 3, 8, 16, 0)
+        );
+        assert!(parse_time("25:00").is_err());
+    }
+
+    #[test]
+    fn test_build_report() {
+        let classes = vec![ClassData {
+            id: "class_1".into(),
+            name: "3A <scienze>".into(),
+            students: Vec::new(),
+            created_at: 0,
+            updated_at: 0,
+        }];
+        let session = ExitTicketSession {
+            id: "t1".into(),
+            lesson_id: "lesson_1".into(),
+            prompt: "Cosa hai imparato?".into(),
+            opened_at: 5_000,
+            closed_at: Some(6_000),
+            responses: Vec::new(),
+        };
+        let html = build_report(
+            1_000,
+            10_000,
+            &classes,
+            std::slice::from_ref(&session),
+            Language::It,
+        );
+        assert!(html.contains("3A &lt;scienze&gt;"));
+        assert!(html.contains("Cosa hai imparato?"));
+
+        let html = build_report(1_000, 10_000, &classes, &[], Language::It);
+        assert!(html.contains("Nessun exit ticket"));
+
+        let html = build_report(1_000, 10_000, &classes, &[session], Language::En);
+        assert!(html.contains("<html lang=\"en\">"));
+        assert!(html.contains("<h1>Weekly summary</h1>"));
+    }
+}
@@ -4,11 +4,16 @@
 //! - Window positioning and sizing
 //! - Overlay and fullscreen modes
 //! - Multi-monitor support
+//! - Safe-area insets (camera notch on recent MacBooks)
+//! - Display server detection on Linux and what windows can do there
+//! - Per-window zoom (e.g. a bigger projector view), kept in `webview_zoom`
 //! - Window persistence
 
 use crate::errors::{BackendError, self};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowPosition {
@@ -183,6 +188,442 @@ pub fn constrain_to_screen(mut position: WindowPosition) -> WindowPosition {
     position
 }
 
+/// Index of the projector among `positions` (monitor origins): the first
+/// one that isn't the primary, else the first
+fn projector_index(positions: &[(i32, i32)], primary: Option<(i32, i32)>) -> usize {
+    positions
+        .iter()
+        .position(|p| Some(*p) != primary)
+        .filter(|_| primary.is_some())
+        .unwrap_or(0)
+}
+
+/// Monitor for student-facing windows: `index` if given, else the projector
+pub fn projector_monitor(app: &AppHandle, index: Option<usize>) -> Result<Monitor, BackendError> {
+    let monitors = app.available_monitors().map_err(|e| {
+        BackendError::new(errors::window::MONITOR_NOT_FOUND, "Failed to list monitors")
+            .with_details(e.to_string())
+    })?;
+    let index = index.unwrap_or_else(|| {
+        let positions: Vec<(i32, i32)> = monitors
+            .iter()
+            .map(|m| (m.position().x, m.position().y))
+            .collect();
+        let primary = app
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .map(|m| (m.position().x, m.position().y));
+        projector_index(&positions, primary)
+    });
+    monitors.into_iter().nth(index).ok_or_else(|| {
+        BackendError::new(errors::window::MONITOR_NOT_FOUND, "Monitor not found")
+            .with_details(index.to_string())
+    })
+}
+
+/// Create a transparent, borderless, always-on-top fullscreen window over
+/// a monitor (default: the projector)
+///
+/// The window is created hidden so callers can set up input routing before
+/// calling `show()`.
+pub fn open_projector_layer(
+    app: &AppHandle,
+    label: &str,
+    route: &str,
+    title: &str,
+    monitor_index: Option<usize>,
+) -> Result<WebviewWindow, BackendError> {
+    let monitor = projector_monitor(app, monitor_index)?;
+    let window_error = |message: &str, e: tauri::Error| {
+        BackendError::new(errors::window::CREATE_FAILED, message).with_details(e.to_string())
+    };
+    let window = WebviewWindowBuilder::new(app, label, WebviewUrl::App(route.into()))
+        .title(title)
+        .transparent(true)
+        .decorations(false)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .visible(false)
+        .build()
+        .map_err(|e| window_error("Failed to open window", e))?;
+    // Position before going fullscreen so it lands on the chosen monitor
+    window
+        .set_position(PhysicalPosition::new(
+            monitor.position().x,
+            monitor.position().y,
+        ))
+        .map_err(|e| window_error("Failed to move window", e))?;
+    window
+        .set_fullscreen(true)
+        .map_err(|e| window_error("Failed to enter fullscreen", e))?;
+    Ok(window)
+}
+
+/// Config key: window label → zoom factor
+const ZOOM_KEY: &str = "webview_zoom";
+pub const MIN_ZOOM: f64 = 0.5;
+pub const MAX_ZOOM: f64 = 3.0;
+
+fn zoom_levels() -> serde_json::Map<String, serde_json::Value> {
+    crate::file_ops::load_config(ZOOM_KEY)
+        .ok()
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+/// Saved zoom factor of a window (1.0 if none)
+pub fn get_webview_zoom(label: &str) -> f64 {
+    zoom_levels()
+        .get(label)
+        .and_then(|v| v.as_f64())
+        .unwrap_or(1.0)
+}
+
+/// Zoom a window's content and remember the factor for that window
+///
+/// The window doesn't have to be open: the factor applies when it next
+/// loads. 1.0 forgets the saved factor.
+pub fn set_webview_zoom(app: &AppHandle, label: &str, factor: f64) -> Result<f64, BackendError> {
+    if label.trim().is_empty() {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            "Window label is required",
+        ));
+    }
+    if !factor.is_finite() || !(MIN_ZOOM..=MAX_ZOOM).contains(&factor) {
+        return Err(BackendError::new(
+            errors::system::INVALID_INPUT,
+            format!("Zoom must be between {} and {}", MIN_ZOOM, MAX_ZOOM),
+        )
+        .with_details(factor.to_string()));
+    }
+    let factor = (factor * 100.0).round() / 100.0;
+    if let Some(window) = app.get_webview_window(label) {
+        window.set_zoom(factor).map_err(|e| {
+            BackendError::new(errors::window::INVALID_POSITION, "Failed to zoom window")
+                .with_details(e.to_string())
+        })?;
+    }
+
+    let mut levels = zoom_levels();
+    if factor == 1.0 {
+        levels.remove(label);
+    } else {
+        levels.insert(label.to_string(), factor.into());
+    }
+    if levels.is_empty() {
+        crate::file_ops::remove_config(ZOOM_KEY)?;
+    } else {
+        crate::file_ops::save_config(ZOOM_KEY, levels.into())?;
+    }
+    Ok(factor)
+}
+
+/// Re-apply a window's saved zoom (page load hook in `lib.rs`)
+pub fn apply_saved_zoom(webview: &tauri::Webview) {
+    let factor = get_webview_zoom(webview.label());
+    if factor != 1.0 {
+        let _ = webview.set_zoom(factor);
+    }
+}
+
+/// Edges of a monitor that fullscreen content should keep clear of (camera
+/// notch, rounded corners), in logical pixels; all zero where the platform
+/// reports none
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeArea {
+    pub top: f64,
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+    /// Physical pixels per logical pixel on that monitor
+    pub scale_factor: f64,
+}
+
+/// Whether a screen frame (points, x and width) is the monitor at `x` with
+/// `width` (physical pixels)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn frame_matches(frame_x: f64, frame_width: f64, scale: f64, x: i32, width: u32) -> bool {
+    (frame_x * scale - x as f64).abs() < 1.0 && (frame_width * scale - width as f64).abs() < 1.0
+}
+
+#[cfg(target_os = "macos")]
+fn platform_insets(
+    app: &AppHandle,
+    monitor: &Monitor,
+) -> Result<(f64, f64, f64, f64), BackendError> {
+    use objc2::MainThreadMarker;
+    use objc2_app_kit::NSScreen;
+
+    let (x, width, scale) = (
+        monitor.position().x,
+        monitor.size().width,
+        monitor.scale_factor(),
+    );
+    let read = move |mtm: MainThreadMarker| {
+        NSScreen::screens(mtm)
+            .iter()
+            .find(|screen| {
+                let frame = screen.frame();
+                frame_matches(frame.origin.x, frame.size.width, scale, x, width)
+            })
+            .map(|screen| {
+                let insets = screen.safeAreaInsets();
+                (insets.top, insets.left, insets.bottom, insets.right)
+            })
+            .unwrap_or_default()
+    };
+    // AppKit screens may only be read on the main thread
+    if let Some(mtm) = MainThreadMarker::new() {
+        return Ok(read(mtm));
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    app.run_on_main_thread(move || {
+        if let Some(mtm) = MainThreadMarker::new() {
+            let _ = tx.send(read(mtm));
+        }
+    })
+    .map_err(|e| {
+        BackendError::new(
+            errors::window::MONITOR_NOT_FOUND,
+            "Failed to read safe area",
+        )
+        .with_details(e.to_string())
+    })?;
+    rx.recv().map_err(|e| {
+        BackendError::new(
+            errors::window::MONITOR_NOT_FOUND,
+            "Failed to read safe area",
+        )
+        .with_details(e.to_string())
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn platform_insets(
+    _app: &AppHandle,
+    _monitor: &Monitor,
+) -> Result<(f64, f64, f64, f64), BackendError> {
+    Ok((0.0, 0.0, 0.0, 0.0))
+}
+
+/// Safe area of a monitor: `monitor_index` if given, else the one `window`
+/// is on
+///
+/// Call from a background thread or async command on macOS: the insets are
+/// read on the main thread.
+pub fn get_safe_area(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    monitor_index: Option<usize>,
+) -> Result<SafeArea, BackendError> {
+    let not_found = |details: String| {
+        BackendError::new(errors::window::MONITOR_NOT_FOUND, "Monitor not found")
+            .with_details(details)
+    };
+    let monitor = match monitor_index {
+        Some(index) => app
+            .available_monitors()
+            .map_err(|e| not_found(e.to_string()))?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| not_found(index.to_string()))?,
+        None => window
+            .current_monitor()
+            .map_err(|e| not_found(e.to_string()))?
+            .ok_or_else(|| not_found(window.label().to_string()))?,
+    };
+    let (top, left, bottom, right) = platform_insets(app, &monitor)?;
+    Ok(SafeArea {
+        top,
+        left,
+        bottom,
+        right,
+        scale_factor: monitor.scale_factor(),
+    })
+}
+
+/// Windowing system the app is running on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayServer {
+    Windows,
+    Macos,
+    X11,
+    Wayland,
+    Unknown,
+}
+
+/// How overlay and projector windows are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowStrategy {
+    /// The platform honors always-on-top and absolute positions
+    Native,
+    /// Wayland session, app running through XWayland so the X11 behavior
+    /// applies
+    Xwayland,
+    /// Native Wayland: the compositor places windows; overlays go
+    /// fullscreen on whatever screen it picks
+    WaylandFallback,
+}
+
+/// What window management can be relied on in this session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowCapabilities {
+    pub display_server: DisplayServer,
+    /// Session type seen by the desktop (`wayland` under XWayland)
+    pub session: DisplayServer,
+    /// `XDG_CURRENT_DESKTOP` on Linux (e.g. `GNOME`, `KDE`)
+    pub desktop: Option<String>,
+    pub strategy: WindowStrategy,
+    pub always_on_top: bool,
+    pub absolute_positioning: bool,
+    /// Projector layers can be sent to a chosen monitor
+    pub monitor_targeting: bool,
+    pub click_through: bool,
+    /// Shown to the teacher when something won't work as expected
+    pub hints: Vec<String>,
+}
+
+/// Capabilities for a Linux session described by environment variables
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn linux_capabilities(env: impl Fn(&str) -> Option<String>) -> WindowCapabilities {
+    let has = |key: &str| env(key).is_some_and(|v| !v.is_empty());
+    let wayland_session = has("WAYLAND_DISPLAY")
+        || env("XDG_SESSION_TYPE").is_some_and(|v| v.eq_ignore_ascii_case("wayland"));
+    let forced_x11 = env("GDK_BACKEND").is_some_and(|v| v.starts_with("x11"));
+    let desktop = env("XDG_CURRENT_DESKTOP").filter(|v| !v.is_empty());
+
+    let (display_server, session, strategy) = if wayland_session && !forced_x11 {
+        (
+            DisplayServer::Wayland,
+            DisplayServer::Wayland,
+            WindowStrategy::WaylandFallback,
+        )
+    } else if wayland_session {
+        (
+            DisplayServer::X11,
+            DisplayServer::Wayland,
+            WindowStrategy::Xwayland,
+        )
+    } else if has("DISPLAY") {
+        (
+            DisplayServer::X11,
+            DisplayServer::X11,
+            WindowStrategy::Native,
+        )
+    } else {
+        (
+            DisplayServer::Unknown,
+            DisplayServer::Unknown,
+            WindowStrategy::Native,
+        )
+    };
+
+    let native = strategy != WindowStrategy::WaylandFallback;
+    let mut hints = Vec::new();
+    if !native {
+        hints.push(
+            "Wayland doesn't let apps keep windows on top or place them: the compact overlay may be covered by other windows".to_string(),
+        );
+        hints.push(
+            "Projector windows open on the screen the desktop chooses; move the teacher window to the other screen first if they appear on the wrong one".to_string(),
+        );
+        if !has("DISPLAY") {
+            hints.push(
+                "Install XWayland (or start the app with GDK_BACKEND=x11) for full overlay support"
+                    .to_string(),
+            );
+        }
+    }
+    WindowCapabilities {
+        display_server,
+        session,
+        desktop,
+        strategy,
+        always_on_top: native,
+        absolute_positioning: native,
+        monitor_targeting: native,
+        click_through: true,
+        hints,
+    }
+}
+
+/// Capabilities of the current session
+pub fn get_window_capabilities() -> WindowCapabilities {
+    #[cfg(target_os = "linux")]
+    {
+        linux_capabilities(|key| std::env::var(key).ok())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let display_server = if cfg!(target_os = "windows") {
+            DisplayServer::Windows
+        } else if cfg!(target_os = "macos") {
+            DisplayServer::Macos
+        } else {
+            DisplayServer::Unknown
+        };
+        WindowCapabilities {
+            display_server,
+            session: display_server,
+            desktop: None,
+            strategy: WindowStrategy::Native,
+            always_on_top: true,
+            absolute_positioning: true,
+            monitor_targeting: true,
+            click_through: true,
+            hints: Vec::new(),
+        }
+    }
+}
+
+/// Pick the display backend before the window system starts (call first
+/// thing in `run`)
+///
+/// In a Wayland session with XWayland available, GTK is pointed at X11 so
+/// always-on-top and positioning keep working (GNOME ignores both for
+/// Wayland windows). A `GDK_BACKEND` set by the user is left alone, so
+/// `GDK_BACKEND=wayland` runs natively with the fallback strategy.
+pub fn prepare_display_backend() {
+    #[cfg(target_os = "linux")]
+    {
+        let set = |key: &str| std::env::var_os(key).is_some_and(|v| !v.is_empty());
+        if set("WAYLAND_DISPLAY") && set("DISPLAY") && !set("GDK_BACKEND") {
+            std::env::set_var("GDK_BACKEND", "x11");
+        }
+    }
+}
+
+/// Keep webview storage with the portable data (call before the first
+/// window is created)
+///
+/// WebView2 takes its user-data folder from `WEBVIEW2_USER_DATA_FOLDER` and
+/// WebKitGTK follows the XDG data and cache dirs, so every window, the main
+/// one from `tauri.conf.json` included, uses it. WKWebView on macOS keeps
+/// its store in the user's Library whatever is set.
+pub fn prepare_webview_data_dir() {
+    let Some(dir) = crate::file_ops::portable_webview_dir() else {
+        return;
+    };
+    #[cfg(target_os = "windows")]
+    std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", &dir);
+    #[cfg(target_os = "linux")]
+    {
+        std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+        std::env::set_var("XDG_CACHE_HOME", dir.join("cache"));
+    }
+    #[cfg(target_os = "macos")]
+    let _ = dir;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,4 +640,59 @@ mod tests {
         assert!(constrained.x >= 0);
         assert!(constrained.y >= 0);
     }
+
+    #[test]
+    fn test_projector_is_first_secondary_monitor() {
+        let monitors = [(0, 0), (1920, 0), (-1280, 0)];
+        assert_eq!(projector_index(&monitors, Some((0, 0))), 1);
+        assert_eq!(projector_index(&monitors, Some((1920, 0))), 0);
+        assert_eq!(projector_index(&[(0, 0)], Some((0, 0))), 0);
+        assert_eq!(projector_index(&monitors, None), 0);
+    }
+
+    #[test]
+    fn test_safe_area_screen_matching() {
+        // A Retina laptop panel: 1512 points wide at 2x
+        assert!(frame_matches(0.0, 1512.0, 2.0, 0, 3024));
+        assert!(!frame_matches(0.0, 1512.0, 2.0, 0, 1920));
+        // Projector to the right, at 1x
+        assert!(frame_matches(1512.0, 1920.0, 1.0, 1512, 1920));
+        assert!(!frame_matches(1512.0, 1920.0, 1.0, 3024, 1920));
+    }
+
+    fn env_of<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_linux_capabilities() {
+        let x11 = linux_capabilities(env_of(&[("DISPLAY", ":0"), ("XDG_SESSION_TYPE", "x11")]));
+        assert_eq!(x11.display_server, DisplayServer::X11);
+        assert_eq!(x11.strategy, WindowStrategy::Native);
+        assert!(x11.always_on_top && x11.hints.is_empty());
+
+        let gnome = linux_capabilities(env_of(&[
+            ("WAYLAND_DISPLAY", "wayland-0"),
+            ("XDG_CURRENT_DESKTOP", "GNOME"),
+        ]));
+        assert_eq!(gnome.strategy, WindowStrategy::WaylandFallback);
+        assert!(!gnome.always_on_top && !gnome.absolute_positioning);
+        assert_eq!(gnome.desktop.as_deref(), Some("GNOME"));
+        // No XWayland: suggest installing it
+        assert_eq!(gnome.hints.len(), 3);
+
+        let xwayland = linux_capabilities(env_of(&[
+            ("WAYLAND_DISPLAY", "wayland-0"),
+            ("DISPLAY", ":0"),
+            ("GDK_BACKEND", "x11"),
+        ]));
+        assert_eq!(xwayland.display_server, DisplayServer::X11);
+        assert_eq!(xwayland.session, DisplayServer::Wayland);
+        assert_eq!(xwayland.strategy, WindowStrategy::Xwayland);
+        assert!(xwayland.always_on_top);
+    }
 }